@@ -2,9 +2,10 @@
 // Copyright 2019-2022 ChainSafe Systems
 // SPDX-License-Identifier: Apache-2.0, MIT
 
-use std::cell::RefCell;
-use std::collections::HashMap;
+use std::cell::{Cell, RefCell};
+use std::collections::{HashMap, HashSet};
 use std::io::Read;
+use std::sync::Arc;
 
 use anyhow::{anyhow, Result};
 use cid::Cid;
@@ -18,6 +19,76 @@ use fvm_shared::commcid::{FIL_COMMITMENT_SEALED, FIL_COMMITMENT_UNSEALED};
 pub struct BufferedBlockstore<BS> {
     base: BS,
     write: RefCell<HashMap<Cid, Vec<u8>>>,
+    shared: RefCell<SharedBuffers>,
+    limits: BufferLimits,
+    stats: Cell<BufferStats>,
+}
+
+/// Write buffers frozen by [`BufferedBlockstore::fork`], shared (read-only) between the forks.
+#[derive(Debug, Default)]
+struct SharedBuffers {
+    /// The frozen buffers, oldest first.
+    layers: Vec<Arc<HashMap<Cid, Vec<u8>>>>,
+    /// Blocks from layers shared with other forks that have already been flushed (these can't be
+    /// removed from the layers).
+    flushed: HashSet<Cid>,
+}
+
+impl SharedBuffers {
+    fn get(&self, k: &Cid) -> Option<&Vec<u8>> {
+        self.layers.iter().rev().find_map(|layer| layer.get(k))
+    }
+
+    /// Takes a block that hasn't been flushed yet, removing it from the layer if this is the only
+    /// fork referencing it.
+    fn take(&mut self, k: &Cid) -> Option<Vec<u8>> {
+        if self.flushed.contains(k) {
+            return None;
+        }
+        for layer in self.layers.iter_mut().rev() {
+            if !layer.contains_key(k) {
+                continue;
+            }
+            return match Arc::get_mut(layer) {
+                Some(layer) => layer.remove(k),
+                None => {
+                    self.flushed.insert(*k);
+                    layer.get(k).cloned()
+                }
+            };
+        }
+        None
+    }
+}
+
+/// Limits on the blocks buffered by a [`BufferedBlockstore`]. When either limit is exceeded, the
+/// entire buffer is spilled (written) to the backing store, whether or not the blocks end up being
+/// reachable from the flushed root. This bounds memory usage at the cost of writing some garbage.
+///
+/// By default, blocks are buffered until flushed.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct BufferLimits {
+    /// The maximum total size of the buffered blocks, in bytes.
+    pub max_bytes: Option<usize>,
+    /// The maximum number of buffered blocks.
+    pub max_blocks: Option<usize>,
+}
+
+/// Statistics about a [`BufferedBlockstore`]'s buffer.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct BufferStats {
+    /// The number of blocks currently buffered.
+    pub buffered_blocks: usize,
+    /// The total size of the blocks currently buffered, in bytes.
+    pub buffered_bytes: usize,
+    /// The largest total size of the buffered blocks so far, in bytes.
+    pub peak_bytes: usize,
+    /// The number of times the buffer was spilled for exceeding the [`BufferLimits`].
+    pub spills: u64,
+    /// The number of blocks written to the backing store by spills.
+    pub spilled_blocks: u64,
+    /// The number of blocks written to the backing store by flushes.
+    pub flushed_blocks: u64,
 }
 
 impl<BS> BufferedBlockstore<BS>
@@ -25,15 +96,85 @@ where
     BS: Blockstore,
 {
     pub fn new(base: BS) -> Self {
+        Self::with_limits(base, BufferLimits::default())
+    }
+
+    /// Creates a buffered blockstore that spills its buffer to the backing store whenever it
+    /// exceeds the given limits.
+    pub fn with_limits(base: BS, limits: BufferLimits) -> Self {
         Self {
             base,
             write: Default::default(),
+            shared: Default::default(),
+            limits,
+            stats: Default::default(),
         }
     }
 
     pub fn into_inner(self) -> BS {
         self.base
     }
+
+    /// Returns statistics about the write buffer. Blocks shared with forks (see
+    /// [`BufferedBlockstore::fork`]) aren't counted as buffered.
+    pub fn stats(&self) -> BufferStats {
+        self.stats.get()
+    }
+
+    /// Creates an independent copy of this blockstore, backed by a clone of the backing store.
+    ///
+    /// The blocks buffered so far aren't copied: the write buffer is frozen and shared
+    /// (copy-on-write) by both copies, each of which starts a new, empty write buffer. Blocks
+    /// subsequently written to either copy aren't visible to the other until flushed (or spilled)
+    /// to a shared backing store.
+    pub fn fork(&self) -> Self
+    where
+        BS: Clone,
+    {
+        let mut write = self.write.borrow_mut();
+        let mut shared = self.shared.borrow_mut();
+        if !write.is_empty() {
+            shared.layers.push(Arc::new(std::mem::take(&mut *write)));
+            let mut stats = self.stats.get();
+            stats.buffered_blocks = 0;
+            stats.buffered_bytes = 0;
+            self.stats.set(stats);
+        }
+        Self {
+            base: self.base.clone(),
+            write: Default::default(),
+            shared: RefCell::new(SharedBuffers {
+                layers: shared.layers.clone(),
+                flushed: shared.flushed.clone(),
+            }),
+            limits: self.limits,
+            stats: Default::default(),
+        }
+    }
+
+    /// Records the buffer's new size and spills it if it exceeds the limits.
+    fn buffered(&self, write: &mut HashMap<Cid, Vec<u8>>, bytes: usize) -> Result<()> {
+        let mut stats = self.stats.get();
+        stats.buffered_blocks = write.len();
+        stats.buffered_bytes = bytes;
+        stats.peak_bytes = stats.peak_bytes.max(bytes);
+
+        let over_limit = self.limits.max_bytes.map_or(false, |max| bytes > max)
+            || self
+                .limits
+                .max_blocks
+                .map_or(false, |max| write.len() > max);
+        if over_limit {
+            log::debug!("spilling {} buffered blocks ({} bytes)", write.len(), bytes);
+            stats.spills += 1;
+            stats.spilled_blocks += write.len() as u64;
+            stats.buffered_blocks = 0;
+            stats.buffered_bytes = 0;
+            self.base.put_many_keyed(write.drain())?;
+        }
+        self.stats.set(stats);
+        Ok(())
+    }
 }
 
 impl<BS> Buffered for BufferedBlockstore<BS>
@@ -44,8 +185,18 @@ where
     /// This will recursively traverse the cache and write all data connected by links to this
     /// root Cid, moving the reachable blocks from the write buffer to the backing store.
     fn flush(&self, root: &Cid) -> Result<()> {
+        let mut write = self.write.borrow_mut();
+        let mut shared = self.shared.borrow_mut();
+        let (blocks, shared_blocks) = take_reachable(&mut write, &mut shared, root)?;
+
+        let mut stats = self.stats.get();
+        stats.flushed_blocks += (blocks.len() + shared_blocks.len()) as u64;
+        stats.buffered_blocks = write.len();
+        stats.buffered_bytes -= blocks.iter().map(|(_, b)| b.len()).sum::<usize>();
+        self.stats.set(stats);
+
         self.base
-            .put_many_keyed(take_reachable(&mut self.write.borrow_mut(), root)?)
+            .put_many_keyed(blocks.into_iter().chain(shared_blocks))
     }
 }
 
@@ -81,7 +232,7 @@ fn cbor_read_header_buf<B: Read>(br: &mut B) -> anyhow::Result<(u8, u64)> {
 /// Given a CBOR serialized IPLD buffer, read through all of it and return all the Links.
 /// This function is useful because it is quite a bit more fast than doing this recursively on a
 /// deserialized IPLD object.
-fn scan_for_links(mut buf: &[u8], out: &mut Vec<Cid>) -> Result<()> {
+pub(crate) fn scan_for_links(mut buf: &[u8], out: &mut Vec<Cid>) -> Result<()> {
     let mut remaining = 1;
     while remaining > 0 {
         let (maj, extra) = cbor_read_header_buf(&mut buf)?;
@@ -135,8 +286,16 @@ fn scan_for_links(mut buf: &[u8], out: &mut Vec<Cid>) -> Result<()> {
     Ok(())
 }
 
-/// Moves the IPLD DAG under `root` from the cache to the base store.
-fn take_reachable(cache: &mut HashMap<Cid, Vec<u8>>, root: &Cid) -> Result<Vec<(Cid, Vec<u8>)>> {
+/// Blocks taken from a buffer by [`take_reachable`].
+type Blocks = Vec<(Cid, Vec<u8>)>;
+
+/// Moves the IPLD DAG under `root` from the cache (and the shared buffers) to the base store,
+/// returning the blocks taken from the cache and from the shared buffers respectively.
+fn take_reachable(
+    cache: &mut HashMap<Cid, Vec<u8>>,
+    shared: &mut SharedBuffers,
+    root: &Cid,
+) -> Result<(Blocks, Blocks)> {
     const BLAKE2B_256: u64 = 0xb220;
     const BLAKE2B_LEN: u8 = 32;
     const IDENTITY: u64 = 0x0;
@@ -149,6 +308,7 @@ fn take_reachable(cache: &mut HashMap<Cid, Vec<u8>>, root: &Cid) -> Result<Vec<(
 
     let mut stack = vec![*root];
     let mut result = Vec::new();
+    let mut shared_result = Vec::new();
 
     while let Some(k) = stack.pop() {
         // Check the codec.
@@ -181,7 +341,13 @@ fn take_reachable(cache: &mut HashMap<Cid, Vec<u8>>, root: &Cid) -> Result<Vec<(
             //
             // The alternative would be to check if it's in the datastore, but that's likely even more
             // expensive. And there wouldn't be much we could do at that point but abort the block.
-            let Some(block) = cache.remove(&k) else { continue };
+            let (block, result) = match cache.remove(&k) {
+                Some(block) => (block, &mut result),
+                None => match shared.take(&k) {
+                    Some(block) => (block, &mut shared_result),
+                    None => continue,
+                },
+            };
 
             // At the moment, only DAG_CBOR can link to other blocks.
             if k.codec() == DAG_CBOR {
@@ -193,7 +359,7 @@ fn take_reachable(cache: &mut HashMap<Cid, Vec<u8>>, root: &Cid) -> Result<Vec<(
         };
     }
 
-    Ok(result)
+    Ok((result, shared_result))
 }
 
 impl<BS> Blockstore for BufferedBlockstore<BS>
@@ -203,18 +369,24 @@ where
     fn get(&self, cid: &Cid) -> Result<Option<Vec<u8>>> {
         Ok(if let Some(data) = self.write.borrow().get(cid) {
             Some(data.clone())
+        } else if let Some(data) = self.shared.borrow().get(cid) {
+            Some(data.clone())
         } else {
             self.base.get(cid)?
         })
     }
 
     fn put_keyed(&self, cid: &Cid, buf: &[u8]) -> Result<()> {
-        self.write.borrow_mut().insert(*cid, Vec::from(buf));
-        Ok(())
+        let mut write = self.write.borrow_mut();
+        let mut bytes = self.stats.get().buffered_bytes + buf.len();
+        if let Some(old) = write.insert(*cid, Vec::from(buf)) {
+            bytes -= old.len();
+        }
+        self.buffered(&mut write, bytes)
     }
 
     fn has(&self, k: &Cid) -> Result<bool> {
-        if self.write.borrow().contains_key(k) {
+        if self.write.borrow().contains_key(k) || self.shared.borrow().get(k).is_some() {
             Ok(true)
         } else {
             Ok(self.base.has(k)?)
@@ -227,10 +399,16 @@ where
         D: AsRef<[u8]>,
         I: IntoIterator<Item = (Cid, D)>,
     {
-        self.write
-            .borrow_mut()
-            .extend(blocks.into_iter().map(|(k, v)| (k, v.as_ref().into())));
-        Ok(())
+        let mut write = self.write.borrow_mut();
+        let mut bytes = self.stats.get().buffered_bytes;
+        for (k, v) in blocks {
+            let v = v.as_ref();
+            bytes += v.len();
+            if let Some(old) = write.insert(k, v.into()) {
+                bytes -= old.len();
+            }
+        }
+        self.buffered(&mut write, bytes)
     }
 }
 
@@ -329,4 +507,73 @@ mod tests {
         assert_eq!(buf_store.get(&sealed_comm_cid).unwrap(), None);
         assert_eq!(mem.get_cbor::<u8>(&unconnected).unwrap(), None);
     }
+
+    #[test]
+    fn spill_over_limits() {
+        let mem = MemoryBlockstore::default();
+        let buf_store = BufferedBlockstore::with_limits(
+            &mem,
+            BufferLimits {
+                max_blocks: Some(2),
+                ..Default::default()
+            },
+        );
+
+        let a = buf_store.put_cbor(&1u8, Code::Blake2b256).unwrap();
+        let b = buf_store.put_cbor(&2u8, Code::Blake2b256).unwrap();
+        assert_eq!(buf_store.stats().buffered_blocks, 2);
+        assert_eq!(buf_store.stats().buffered_bytes, 2);
+        assert!(!mem.has(&a).unwrap());
+
+        // The third block spills the whole buffer.
+        let c = buf_store.put_cbor(&3u8, Code::Blake2b256).unwrap();
+        assert!(mem.has(&a).unwrap() && mem.has(&b).unwrap() && mem.has(&c).unwrap());
+        let stats = buf_store.stats();
+        assert_eq!(stats.spills, 1);
+        assert_eq!(stats.spilled_blocks, 3);
+        assert_eq!(stats.buffered_blocks, 0);
+        assert_eq!(stats.peak_bytes, 3);
+
+        // Spilled blocks are still reachable when flushing.
+        let root = buf_store.put_cbor(&(a, b, c), Code::Blake2b256).unwrap();
+        buf_store.flush(&root).unwrap();
+        assert!(mem.has(&root).unwrap());
+        let stats = buf_store.stats();
+        assert_eq!(stats.flushed_blocks, 1);
+        assert_eq!(stats.buffered_blocks, 0);
+        assert_eq!(stats.buffered_bytes, 0);
+    }
+
+    #[test]
+    fn fork_shares_buffer() {
+        let mem = MemoryBlockstore::default();
+        let buf_store = BufferedBlockstore::new(&mem);
+
+        let a = buf_store.put_cbor(&1u8, Code::Blake2b256).unwrap();
+        let forked = buf_store.fork();
+
+        // Both copies see the shared block, which is no longer counted as buffered.
+        assert_eq!(forked.get_cbor::<u8>(&a).unwrap(), Some(1));
+        assert_eq!(buf_store.get_cbor::<u8>(&a).unwrap(), Some(1));
+        assert_eq!(buf_store.stats().buffered_blocks, 0);
+        assert_eq!(forked.stats().buffered_blocks, 0);
+
+        // New blocks are private to each copy.
+        let b = forked.put_cbor(&(a, 2u8), Code::Blake2b256).unwrap();
+        let c = buf_store.put_cbor(&(a, 3u8), Code::Blake2b256).unwrap();
+        assert!(!buf_store.has(&b).unwrap());
+        assert!(!forked.has(&c).unwrap());
+
+        // Flushing writes the reachable shared blocks to the backing store, once.
+        forked.flush(&b).unwrap();
+        assert!(mem.has(&a).unwrap() && mem.has(&b).unwrap());
+        assert_eq!(forked.stats().flushed_blocks, 2);
+        forked.flush(&b).unwrap();
+        assert_eq!(forked.stats().flushed_blocks, 2);
+
+        // The other copy still flushes its own view, including the shared block.
+        buf_store.flush(&c).unwrap();
+        assert!(mem.has(&c).unwrap());
+        assert_eq!(buf_store.stats().flushed_blocks, 2);
+    }
 }
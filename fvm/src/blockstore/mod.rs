@@ -5,5 +5,6 @@
 mod buffered;
 mod discard;
 
-pub use buffered::BufferedBlockstore;
+pub(crate) use buffered::scan_for_links;
+pub use buffered::{BufferLimits, BufferStats, BufferedBlockstore};
 pub(crate) use discard::DiscardBlockstore;
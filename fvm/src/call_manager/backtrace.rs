@@ -3,10 +3,11 @@
 use std::fmt::Display;
 
 use fvm_shared::address::Address;
-use fvm_shared::error::{ErrorNumber, ExitCode};
+use fvm_shared::error::{CustomErrorNumber, ErrorNumber, ExitCode};
 use fvm_shared::{ActorID, MethodNum};
 
-use crate::kernel::SyscallError;
+use crate::gas::GasCharge;
+use crate::kernel::{CustomSyscallError, SyscallError};
 
 /// A call backtrace records the actors an error was propagated through, from
 /// the moment it was emitted. The original error is the _cause_. Backtraces are
@@ -76,8 +77,12 @@ impl Backtrace {
 pub struct Frame {
     /// The actor that exited with this code.
     pub source: ActorID,
+    /// The name of the actor's code (e.g., "account"), if known.
+    pub actor_name: Option<String>,
     /// The method that was invoked.
     pub method: MethodNum,
+    /// The name of the method that was invoked, if known.
+    pub method_name: Option<String>,
     /// The exit code.
     pub code: ExitCode,
     /// The abort message.
@@ -86,14 +91,15 @@ pub struct Frame {
 
 impl Display for Frame {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(
-            f,
-            "{} (method {}) -- {} ({})",
-            Address::new_id(self.source),
-            self.method,
-            &self.message,
-            self.code,
-        )
+        write!(f, "{}", Address::new_id(self.source))?;
+        if let Some(name) = &self.actor_name {
+            write!(f, " [{}]", name)?;
+        }
+        write!(f, " (method {}", self.method)?;
+        if let Some(name) = &self.method_name {
+            write!(f, ": {}", name)?;
+        }
+        write!(f, ") -- {} ({})", &self.message, self.code)
     }
 }
 
@@ -111,6 +117,17 @@ pub enum Cause {
         /// The informational syscall message.
         message: String,
     },
+    /// The original cause was a custom syscall error, with an embedder-specific error number.
+    CustomSyscall {
+        /// The syscall "module".
+        module: &'static str,
+        /// The syscall function name.
+        function: &'static str,
+        /// The exact syscall error.
+        error: CustomErrorNumber,
+        /// The informational syscall message.
+        message: String,
+    },
     /// The original cause was a fatal error.
     Fatal {
         /// The alternate-formatted message from the anyhow error.
@@ -132,6 +149,20 @@ impl Cause {
         }
     }
 
+    /// Records a failing custom syscall as the cause of a backtrace.
+    pub fn from_custom_syscall(
+        module: &'static str,
+        function: &'static str,
+        err: CustomSyscallError,
+    ) -> Self {
+        Self::CustomSyscall {
+            module,
+            function,
+            error: err.1,
+            message: err.0,
+        }
+    }
+
     /// Records a fatal error as the cause of a backtrace.
     pub fn from_fatal(err: anyhow::Error) -> Self {
         Self::Fatal {
@@ -156,6 +187,22 @@ impl Display for Cause {
                     module, function, &message, *error as u32, error,
                 )
             }
+            Cause::CustomSyscall {
+                module,
+                function,
+                error,
+                message,
+            } => {
+                write!(
+                    f,
+                    "{}::{} -- {} ({}: {})",
+                    module,
+                    function,
+                    &message,
+                    error.value(),
+                    error,
+                )
+            }
             Cause::Fatal {
                 error_msg,
                 backtrace,
@@ -165,3 +212,42 @@ impl Display for Cause {
         }
     }
 }
+
+/// A snapshot of the call frame that exhausted its gas budget, captured when a message runs out of
+/// gas.
+#[derive(Clone, Debug)]
+pub struct OutOfGasCheckpoint {
+    /// The actor that ran out of gas.
+    pub actor: ActorID,
+    /// The method that was invoked.
+    pub method: MethodNum,
+    /// The last gas charges (at most [`RECENT_GAS_CHARGES`](crate::gas::RECENT_GAS_CHARGES))
+    /// applied before running out of gas, oldest first. The last charge is the one that exceeded
+    /// the budget. Only recorded when tracing gas or when actor debugging is enabled.
+    pub recent_charges: Vec<GasCharge>,
+    /// The wasm backtrace at the point the actor ran out of gas, only captured when actor
+    /// debugging is enabled.
+    pub wasm_backtrace: Option<String>,
+}
+
+impl Display for OutOfGasCheckpoint {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(
+            f,
+            "{} (method {}) ran out of gas",
+            Address::new_id(self.actor),
+            self.method
+        )?;
+        for charge in &self.recent_charges {
+            writeln!(
+                f,
+                "  {} -- compute: {}, other: {}",
+                charge.name, charge.compute_gas, charge.other_gas
+            )?;
+        }
+        if let Some(bt) = &self.wasm_backtrace {
+            writeln!(f, "--> wasm backtrace:\n{}", bt)?;
+        }
+        Ok(())
+    }
+}
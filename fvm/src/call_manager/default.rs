@@ -1,6 +1,8 @@
 // Copyright 2021-2023 Protocol Labs
 // SPDX-License-Identifier: Apache-2.0, MIT
+use std::cell::Cell;
 use std::rc::Rc;
+use std::time::Instant;
 
 use anyhow::{anyhow, Context};
 use cid::Cid;
@@ -14,12 +16,15 @@ use fvm_shared::event::StampedEvent;
 use fvm_shared::sys::BlockId;
 use fvm_shared::{ActorID, MethodNum, METHOD_SEND};
 use num_traits::Zero;
+use wasmtime::WasmBacktrace;
 
 use super::state_access_tracker::{ActorAccessState, StateAccessTracker};
 use super::{Backtrace, CallManager, InvocationResult, NO_DATA_BLOCK_ID};
 use crate::blockstore::DiscardBlockstore;
 use crate::call_manager::backtrace::Frame;
-use crate::call_manager::FinishRet;
+use crate::call_manager::{
+    BlockstoreStats, CallFrame, DebugArtifact, FinishRet, OutOfGasCheckpoint,
+};
 use crate::eam_actor::EAM_ACTOR_ID;
 use crate::engine::Engine;
 use crate::gas::{Gas, GasTracker};
@@ -27,11 +32,11 @@ use crate::kernel::{
     Block, BlockRegistry, ClassifyResult, ExecutionError, Kernel, Result, SyscallError,
 };
 use crate::machine::limiter::MemoryLimiter;
-use crate::machine::Machine;
+use crate::machine::{resolve_names, Machine};
 use crate::state_tree::ActorState;
 use crate::syscalls::error::Abort;
 use crate::syscalls::{charge_for_exec, update_gas_available};
-use crate::trace::{ExecutionEvent, ExecutionTrace};
+use crate::trace::{ExecutionEvent, TraceCategory, TraceRecorder};
 use crate::{syscall_error, system_actor};
 
 /// The default [`CallManager`] implementation.
@@ -65,16 +70,30 @@ pub struct InnerDefaultCallManager<M: Machine> {
     num_actors_created: u64,
     /// Current call-stack depth.
     call_stack_depth: u32,
+    /// Maximum call-stack depth reached.
+    max_call_stack_depth: u32,
+    /// The actors currently being invoked.
+    call_stack: Vec<CallFrame>,
     /// The current chain of errors, if any.
     backtrace: Backtrace,
+    /// The call frame that last ran out of gas, if any.
+    out_of_gas: Option<OutOfGasCheckpoint>,
     /// The current execution trace.
-    exec_trace: ExecutionTrace,
+    tracer: TraceRecorder,
     /// Number of actors that have been invoked in this message execution.
     invocation_count: u64,
     /// Limits on memory throughout the execution.
     limits: M::Limiter,
     /// Accumulator for events emitted in this call stack.
     events: EventsAccumulator,
+    /// Debug artifacts stored in this call stack.
+    artifacts: Vec<DebugArtifact>,
+    /// Blocks read and written by actors in this call stack.
+    blockstore_stats: BlockstoreStats,
+    /// Number of consensus faults verified in this call stack.
+    consensus_fault_verifications: Cell<u32>,
+    /// The time by which this call stack must finish executing, if any.
+    deadline: Option<Instant>,
 }
 
 #[doc(hidden)]
@@ -111,8 +130,20 @@ where
         gas_premium: TokenAmount,
     ) -> Self {
         let limits = machine.new_limiter();
-        let gas_tracker =
-            GasTracker::new(Gas::new(gas_limit), Gas::zero(), machine.context().tracing);
+        let deadline = machine
+            .context()
+            .execution_budget
+            .map(|budget| Instant::now() + budget);
+        let trace_gas = machine
+            .context()
+            .trace_config()
+            .is_traced(TraceCategory::Gas);
+        let mut gas_tracker = GasTracker::new(Gas::new(gas_limit), Gas::zero(), trace_gas);
+        // Recent charges are only reported in out of gas checkpoints, which are diagnostics.
+        if trace_gas || machine.context().actor_debugging {
+            gas_tracker = gas_tracker.with_recent_charges();
+        }
+        let tracer = TraceRecorder::new(machine.context().trace_config());
 
         let state_access_tracker =
             StateAccessTracker::new(&machine.context().price_list.preloaded_actors);
@@ -153,11 +184,18 @@ where
             nonce,
             num_actors_created: 0,
             call_stack_depth: 0,
+            max_call_stack_depth: 0,
+            call_stack: Vec::new(),
             backtrace: Backtrace::default(),
-            exec_trace: vec![],
+            out_of_gas: None,
+            tracer,
             invocation_count: 0,
             limits,
             events: Default::default(),
+            artifacts: Vec::new(),
+            blockstore_stats: BlockstoreStats::default(),
+            consensus_fault_verifications: Cell::new(0),
+            deadline,
             state_access_tracker,
         })))
     }
@@ -179,7 +217,7 @@ where
     where
         K: Kernel<CallManager = Self>,
     {
-        if self.machine.context().tracing {
+        if self.tracer.is_traced(TraceCategory::Calls) {
             self.trace(ExecutionEvent::Call {
                 from,
                 to,
@@ -201,16 +239,18 @@ where
 
         if self.call_stack_depth >= self.machine.context().max_call_depth {
             let sys_err = syscall_error!(LimitExceeded, "message execution exceeds call depth");
-            if self.machine.context().tracing {
+            if self.tracer.is_traced(TraceCategory::Calls) {
                 self.trace(ExecutionEvent::CallError(sys_err.clone()));
             }
             return Err(sys_err.into());
         }
 
-        self.state_tree_mut().begin_transaction();
+        let savepoint = self.state_tree_mut().savepoint();
         self.events.begin_transaction();
         self.state_access_tracker.begin_transaction();
+        self.gas_tracker.enter_call(to, method);
         self.call_stack_depth += 1;
+        self.max_call_stack_depth = self.max_call_stack_depth.max(self.call_stack_depth);
 
         let (revert, mut result) = match <<Self::Machine as Machine>::Limiter>::with_stack_frame(
             self,
@@ -222,13 +262,18 @@ where
         };
 
         self.call_stack_depth -= 1;
+        self.gas_tracker.exit_call();
         // Return the _first_ error (if any). We don't expect any errors here anyways as all error
         // cases are fatal.
         if let Some(err) = [
             // End all transactions
             self.state_access_tracker.end_transaction(revert).err(),
             self.events.end_transaction(revert).err(),
-            self.state_tree_mut().end_transaction(revert).err(),
+            if revert {
+                self.state_tree_mut().rollback(savepoint).err()
+            } else {
+                self.state_tree_mut().commit(savepoint).err()
+            },
             // If we pushed a gas limit, pop it.
             gas_limit.and_then(|_| self.gas_tracker.pop_limit().err()),
         ]
@@ -245,18 +290,18 @@ where
             && matches!(result, Err(ExecutionError::OutOfGas))
         {
             result = Ok(InvocationResult {
-                exit_code: ExitCode::SYS_OUT_OF_GAS,
+                exit_code: self.context().exit_code_policy.out_of_gas(),
                 value: None,
             })
         }
 
-        if self.machine.context().tracing {
+        if self.tracer.is_traced(TraceCategory::Calls) {
             self.trace(match &result {
                 Ok(InvocationResult { exit_code, value }) => {
                     ExecutionEvent::CallReturn(*exit_code, value.as_ref().map(Into::into))
                 }
                 Err(ExecutionError::OutOfGas) => {
-                    ExecutionEvent::CallReturn(ExitCode::SYS_OUT_OF_GAS, None)
+                    ExecutionEvent::CallReturn(self.context().exit_code_policy.out_of_gas(), None)
                 }
                 Err(ExecutionError::Fatal(_)) => {
                     ExecutionEvent::CallError(SyscallError::new(ErrorNumber::Forbidden, "fatal"))
@@ -272,18 +317,24 @@ where
         let InnerDefaultCallManager {
             machine,
             backtrace,
-            gas_tracker,
-            mut exec_trace,
+            out_of_gas,
+            mut gas_tracker,
+            tracer,
             events,
+            artifacts,
+            max_call_stack_depth,
+            blockstore_stats,
             ..
         } = *self.0.take().expect("call manager is poisoned");
 
         let gas_used = gas_tracker.gas_used().round_up();
+        let call_gas = gas_tracker.take_call_gas();
 
         // Finalize any trace events, if we're tracing.
-        if machine.context().tracing {
-            exec_trace.extend(gas_tracker.drain_trace().map(ExecutionEvent::GasCharge));
+        for charge in gas_tracker.drain_trace() {
+            tracer.record(ExecutionEvent::GasCharge(charge));
         }
+        let exec_trace = tracer.finish();
 
         let res = events.finish();
         let Events {
@@ -297,10 +348,15 @@ where
         (
             Ok(FinishRet {
                 gas_used,
+                call_gas,
                 backtrace,
+                out_of_gas,
                 exec_trace,
                 events,
                 events_root,
+                artifacts,
+                max_call_depth: max_call_stack_depth,
+                blockstore_stats,
             }),
             machine,
         )
@@ -338,6 +394,14 @@ where
         self.nonce
     }
 
+    fn call_depth(&self) -> u32 {
+        self.call_stack_depth
+    }
+
+    fn call_stack(&self) -> &[CallFrame] {
+        &self.call_stack
+    }
+
     fn next_actor_address(&self) -> Address {
         // Base the next address on the address specified as the message origin. This lets us use,
         // e.g., an f2 address even if we can't look it up anywhere.
@@ -413,14 +477,45 @@ where
     }
 
     fn append_event(&mut self, evt: StampedEvent) {
+        if self.tracer.is_traced(TraceCategory::Events) {
+            self.trace(ExecutionEvent::Event(evt.clone()));
+        }
         self.events.append_event(evt)
     }
 
+    fn trace(&self, event: ExecutionEvent) {
+        // Flush pending gas charges first so the trace stays in order.
+        for charge in self.gas_tracker.drain_trace() {
+            self.tracer.record(ExecutionEvent::GasCharge(charge));
+        }
+        self.tracer.record(event);
+    }
+
+    fn record_block_read(&mut self, size: usize) {
+        self.blockstore_stats.reads += 1;
+        self.blockstore_stats.read_bytes += size as u64;
+    }
+
+    fn record_block_write(&mut self, size: usize) {
+        self.blockstore_stats.writes += 1;
+        self.blockstore_stats.write_bytes += size as u64;
+    }
+
+    fn append_artifact(&mut self, artifact: DebugArtifact) {
+        self.artifacts.push(artifact)
+    }
+
     // Helper for creating actors. This really doesn't belong on this trait.
     fn invocation_count(&self) -> u64 {
         self.invocation_count
     }
 
+    fn record_consensus_fault_verification(&self) -> u32 {
+        let count = self.consensus_fault_verifications.get().saturating_add(1);
+        self.consensus_fault_verifications.set(count);
+        count
+    }
+
     /// Resolve an address and charge for it.
     fn resolve_address(&self, address: &Address) -> Result<Option<ActorID>> {
         if let Ok(id) = address.id() {
@@ -445,6 +540,7 @@ where
         }
         let actor = self.state_tree().get_actor(id)?;
         self.state_access_tracker.record_actor_read(id);
+        self.trace_state_access(id, false);
         Ok(actor)
     }
 
@@ -460,6 +556,7 @@ where
         }
         self.state_tree_mut().set_actor(id, state);
         self.state_access_tracker.record_actor_update(id);
+        self.trace_state_access(id, true);
         Ok(())
     }
 
@@ -475,6 +572,7 @@ where
         }
         self.state_tree_mut().delete_actor(id);
         self.state_access_tracker.record_actor_update(id);
+        self.trace_state_access(id, true);
         Ok(())
     }
 
@@ -520,15 +618,10 @@ impl<M> DefaultCallManager<M>
 where
     M: Machine,
 {
-    fn trace(&mut self, trace: ExecutionEvent) {
-        // The price of deref magic is that you sometimes need to tell the compiler: no, this is
-        // fine.
-        let s = &mut **self;
-
-        s.exec_trace
-            .extend(s.gas_tracker.drain_trace().map(ExecutionEvent::GasCharge));
-
-        s.exec_trace.push(trace);
+    fn trace_state_access(&self, actor: ActorID, update: bool) {
+        if self.tracer.is_traced(TraceCategory::StateAccess) {
+            self.trace(ExecutionEvent::StateAccess { actor, update });
+        }
     }
 
     /// Helper method to create an uninitialized actor due to a send.
@@ -659,7 +752,7 @@ where
             .get_actor(to)?
             .ok_or_else(|| syscall_error!(NotFound; "actor does not exist: {}", to))?;
 
-        if self.machine.context().tracing {
+        if self.tracer.is_traced(TraceCategory::Calls) {
             self.trace(ExecutionEvent::InvokeActor(state.code));
         }
 
@@ -701,8 +794,14 @@ where
             )?;
 
         log::trace!("calling {} -> {}::{}", from, to, method);
-        self.map_mut(|cm| {
+        self.call_stack.push(CallFrame {
+            caller: from,
+            actor: to,
+            method,
+        });
+        let ret = self.map_mut(|cm| {
             let engine = cm.engine.clone(); // reference the RC.
+            let deadline = cm.deadline;
 
             // Make the kernel.
             let kernel = K::new(
@@ -717,6 +816,12 @@ where
 
             // Make a store.
             let mut store = engine.new_store(kernel);
+            if let Some(deadline) = deadline {
+                engine.set_execution_deadline(&mut store, deadline);
+            }
+
+            // The wasm backtrace of a failed invocation (only captured with actor debugging).
+            let mut wasm_backtrace = None;
 
             // From this point on, there are no more syscall errors, only aborts.
             let result: std::result::Result<BlockId, Abort> = (|| {
@@ -749,20 +854,25 @@ where
                 }))
                 .map_err(|panic| Abort::Fatal(anyhow!("panic within actor: {:?}", panic)))?;
 
+                if let Err(err) = &res {
+                    wasm_backtrace = err.downcast_ref::<WasmBacktrace>().map(ToString::to_string);
+                }
+
                 // Charge for any remaining uncharged execution gas, returning an error if we run
                 // out.
                 charge_for_exec(&mut store)?;
 
                 // If the invocation failed due to running out of exec_units, we have already
                 // detected it and returned OutOfGas above. Any other invocation failure is returned
-                // here as an Abort
-
-                Ok(res?)
+                // here as an Abort, with the exit code chosen by the exit code policy.
+                let policy = &store.data().kernel.machine().context().exit_code_policy;
+                res.map_err(|e| Abort::from_trap(e, &**policy))
             })();
 
             let invocation_data = store.into_data();
             let last_error = invocation_data.last_error;
             let (mut cm, block_registry) = invocation_data.kernel.into_inner();
+            let policy = cm.context().exit_code_policy.clone();
 
             // Resolve the return block's ID into an actual block, converting to an abort if it
             // doesn't exist.
@@ -772,7 +882,7 @@ where
                 } else {
                     Some(block_registry.get(ret_id).map_err(|_| {
                         Abort::Exit(
-                            ExitCode::SYS_MISSING_RETURN,
+                            policy.missing_return(),
                             String::from("returned block does not exist"),
                             NO_DATA_BLOCK_ID,
                         )
@@ -780,6 +890,19 @@ where
                 })
             });
 
+            // If this call ran out of gas (rather than propagating an out of gas error from a
+            // sub-call), record where.
+            if matches!(result, Err(Abort::OutOfGas)) {
+                if let Some(recent_charges) = cm.gas_tracker.take_exhausted() {
+                    cm.out_of_gas = Some(OutOfGasCheckpoint {
+                        actor: to,
+                        method,
+                        recent_charges,
+                        wasm_backtrace,
+                    });
+                }
+            }
+
             // Process the result, updating the backtrace if necessary.
             let mut ret = match result {
                 Ok(ret) => Ok(InvocationResult {
@@ -798,7 +921,7 @@ where
                         ),
                         Abort::Exit(code, message, blk_id) => match block_registry.get(blk_id) {
                             Err(e) => (
-                                ExitCode::SYS_MISSING_RETURN,
+                                policy.missing_return(),
                                 "error getting exit data block".to_owned(),
                                 Err(ExecutionError::Fatal(anyhow!(e))),
                             ),
@@ -812,12 +935,12 @@ where
                             ),
                         },
                         Abort::OutOfGas => (
-                            ExitCode::SYS_OUT_OF_GAS,
+                            policy.out_of_gas(),
                             "out of gas".to_owned(),
                             Err(ExecutionError::OutOfGas),
                         ),
                         Abort::Fatal(err) => (
-                            ExitCode::SYS_ASSERTION_FAILED,
+                            policy.fatal(),
                             "fatal error".to_owned(),
                             Err(ExecutionError::Fatal(err)),
                         ),
@@ -828,9 +951,17 @@ where
                             cm.backtrace.begin(err);
                         }
 
+                        let (actor_name, method_name) = resolve_names(
+                            cm.context().name_resolver.as_deref(),
+                            cm.builtin_actors(),
+                            &state.code,
+                            method,
+                        );
                         cm.backtrace.push_frame(Frame {
                             source: to,
+                            actor_name,
                             method,
+                            method_name,
                             message,
                             code,
                         });
@@ -876,7 +1007,9 @@ where
 
             t.stop();
             (ret, cm)
-        })
+        });
+        self.call_stack.pop();
+        ret
     }
 
     /// Temporarily replace `self` with a version that contains `None` for the inner part,
@@ -1,5 +1,7 @@
 // Copyright 2021-2023 Protocol Labs
 // SPDX-License-Identifier: Apache-2.0, MIT
+use std::path::{Path, PathBuf};
+
 use cid::Cid;
 use fvm_shared::address::Address;
 use fvm_shared::econ::TokenAmount;
@@ -7,7 +9,7 @@ use fvm_shared::error::ExitCode;
 use fvm_shared::{ActorID, MethodNum};
 
 use crate::engine::Engine;
-use crate::gas::{Gas, GasCharge, GasTimer, GasTracker, PriceList};
+use crate::gas::{CallGasUsage, Gas, GasCharge, GasTimer, GasTracker, PriceList};
 use crate::kernel::{self, Result};
 use crate::machine::{Machine, MachineContext};
 use crate::state_tree::ActorState;
@@ -15,14 +17,14 @@ use crate::Kernel;
 
 pub mod backtrace;
 mod state_access_tracker;
-pub use backtrace::Backtrace;
+pub use backtrace::{Backtrace, OutOfGasCheckpoint};
 
 mod default;
 
 pub use default::DefaultCallManager;
 use fvm_shared::event::StampedEvent;
 
-use crate::trace::ExecutionTrace;
+use crate::trace::{ExecutionEvent, ExecutionTrace};
 
 /// BlockID representing nil parameters or return data.
 pub const NO_DATA_BLOCK_ID: u32 = 0;
@@ -130,12 +132,24 @@ pub trait CallManager: 'static {
     /// Getter for message nonce.
     fn nonce(&self) -> u64;
 
+    /// Returns the current call depth, as limited by
+    /// [`NetworkConfig::max_call_depth`](crate::machine::NetworkConfig::max_call_depth). This
+    /// counts all pending sends, including those that haven't (yet) invoked an actor.
+    fn call_depth(&self) -> u32;
+
+    /// Returns the actors currently being invoked, outermost first.
+    fn call_stack(&self) -> &[CallFrame];
+
     /// Gets the total invocations done on this call stack.
     fn invocation_count(&self) -> u64;
 
+    /// Records a consensus fault verification on this call stack, returning the total number of
+    /// verifications (including this one).
+    fn record_consensus_fault_verification(&self) -> u32;
+
     /// Returns the current price list.
     fn price_list(&self) -> &PriceList {
-        self.machine().context().price_list
+        &self.machine().context().price_list
     }
 
     /// Returns the machine context.
@@ -163,6 +177,38 @@ pub trait CallManager: 'static {
 
     /// Appends an event to the event accumulator.
     fn append_event(&mut self, evt: StampedEvent);
+
+    /// Records an event in the execution trace, subject to the machine's
+    /// [`TraceConfig`](crate::trace::TraceConfig). The default implementation discards it.
+    fn trace(&self, event: ExecutionEvent) {
+        let _ = event;
+    }
+
+    /// Records that an actor read a block of the given size from the blockstore. The default
+    /// implementation discards it.
+    fn record_block_read(&mut self, size: usize) {
+        let _ = size;
+    }
+
+    /// Records that an actor wrote a block of the given size to the blockstore. The default
+    /// implementation discards it.
+    fn record_block_write(&mut self, size: usize) {
+        let _ = size;
+    }
+
+    /// Records a debug artifact stored by an actor.
+    fn append_artifact(&mut self, artifact: DebugArtifact);
+}
+
+/// An actor invocation on the call stack.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct CallFrame {
+    /// The invoking actor.
+    pub caller: ActorID,
+    /// The invoked actor.
+    pub actor: ActorID,
+    /// The invoked method.
+    pub method: MethodNum,
 }
 
 /// The result of a method invocation.
@@ -186,8 +232,55 @@ impl Default for InvocationResult {
 /// The returned values upon finishing a call manager.
 pub struct FinishRet {
     pub gas_used: u64,
+    /// The gas used by each top-level call (only when tracing gas).
+    pub call_gas: Vec<CallGasUsage>,
     pub backtrace: Backtrace,
+    /// The call frame that last ran out of gas, if any.
+    pub out_of_gas: Option<OutOfGasCheckpoint>,
     pub exec_trace: ExecutionTrace,
     pub events: Vec<StampedEvent>,
     pub events_root: Option<Cid>,
+    pub artifacts: Vec<DebugArtifact>,
+    /// The maximum call depth reached.
+    pub max_call_depth: u32,
+    /// The blocks read from and written to the blockstore by actors.
+    pub blockstore_stats: BlockstoreStats,
+}
+
+/// Counters of the blocks read from and written to the blockstore by actors (through the `ipld`
+/// syscalls) while executing a message.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct BlockstoreStats {
+    /// The number of blocks read.
+    pub reads: u64,
+    /// The total size of the blocks read, in bytes.
+    pub read_bytes: u64,
+    /// The number of blocks written.
+    pub writes: u64,
+    /// The total size of the blocks written, in bytes.
+    pub write_bytes: u64,
+}
+
+/// A debug artifact stored by an actor through the `debug::store_artifact` syscall.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DebugArtifact {
+    /// The actor that stored the artifact.
+    pub actor: ActorID,
+    /// The name of the artifact.
+    pub name: String,
+    /// The contents of the artifact.
+    pub data: Vec<u8>,
+}
+
+impl DebugArtifact {
+    /// Writes the artifact to `<dir>/<actor>/<name>`, creating any missing directories, and
+    /// returns the path of the written file. Artifacts with the same name stored by the same actor
+    /// overwrite each other.
+    pub fn write_to_dir(&self, dir: impl AsRef<Path>) -> std::io::Result<PathBuf> {
+        let dir = dir.as_ref().join(self.actor.to_string());
+        std::fs::create_dir_all(&dir)?;
+        let path = dir.join(&self.name);
+        std::fs::write(&path, &self.data)?;
+        Ok(path)
+    }
 }
@@ -1,6 +1,9 @@
 // Copyright 2021-2023 Protocol Labs
 // SPDX-License-Identifier: Apache-2.0, MIT
 use std::sync::{Condvar, Mutex};
+use std::time::{Duration, Instant};
+
+use super::EnginePoolStats;
 
 /// An engine concurrency manages the concurrency available for a single engine. It's basically a
 /// semaphore that also assigns IDs to new engines.
@@ -12,6 +15,7 @@ pub(super) struct EngineConcurrency {
 struct EngineConcurrencyInner {
     next_id: u64,
     limit: u32,
+    stats: EnginePoolStats,
 }
 
 impl EngineConcurrency {
@@ -20,6 +24,7 @@ impl EngineConcurrency {
             inner: Mutex::new(EngineConcurrencyInner {
                 next_id: 0,
                 limit: concurrency,
+                stats: Default::default(),
             }),
             condv: Condvar::new(),
         }
@@ -28,16 +33,52 @@ impl EngineConcurrency {
     /// Acquire a new engine (well, an engine ID). This function blocks until we're below the
     /// maximum engine concurrency limit.
     pub fn acquire(&self) -> u64 {
-        let mut guard = self
-            .condv
-            .wait_while(self.inner.lock().unwrap(), |inner| inner.limit == 0)
-            .unwrap();
+        self.acquire_timeout(None)
+            .expect("engine acquisition can't time out without a timeout")
+    }
+
+    /// Like [`acquire`](Self::acquire), but gives up (returning `None`) if no engine becomes
+    /// available within the given timeout.
+    pub fn acquire_timeout(&self, timeout: Option<Duration>) -> Option<u64> {
+        let start = Instant::now();
+        let guard = self.inner.lock().unwrap();
+        let contended = guard.limit == 0;
+        let mut guard = match timeout {
+            Some(timeout) => {
+                let (mut guard, res) = self
+                    .condv
+                    .wait_timeout_while(guard, timeout, |inner| inner.limit == 0)
+                    .unwrap();
+                if res.timed_out() {
+                    guard.stats.timed_out += 1;
+                    return None;
+                }
+                guard
+            }
+            None => self
+                .condv
+                .wait_while(guard, |inner| inner.limit == 0)
+                .unwrap(),
+        };
         let id = guard.next_id;
 
         guard.limit -= 1;
         guard.next_id += 1;
 
-        id
+        guard.stats.acquired += 1;
+        if contended {
+            let waited = start.elapsed();
+            guard.stats.contended += 1;
+            guard.stats.wait_time += waited;
+            guard.stats.max_wait_time = guard.stats.max_wait_time.max(waited);
+        }
+
+        Some(id)
+    }
+
+    /// Returns the engine acquisition statistics.
+    pub fn stats(&self) -> EnginePoolStats {
+        self.inner.lock().unwrap().stats
     }
 
     /// Release the engine. After this is called, the caller should not allocate any more instances
@@ -73,3 +114,20 @@ fn test_engine_concurrency() {
         assert_eq!(concurrency.inner.lock().unwrap().limit, 1);
     });
 }
+
+#[test]
+fn test_engine_concurrency_timeout() {
+    let concurrency = EngineConcurrency::new(1);
+    assert_eq!(concurrency.acquire_timeout(Some(Duration::ZERO)), Some(0));
+    assert_eq!(
+        concurrency.acquire_timeout(Some(Duration::from_millis(10))),
+        None
+    );
+    concurrency.release();
+    assert_eq!(concurrency.acquire_timeout(None), Some(1));
+
+    let stats = concurrency.stats();
+    assert_eq!(stats.acquired, 2);
+    assert_eq!(stats.contended, 0);
+    assert_eq!(stats.timed_out, 1);
+}
@@ -0,0 +1,214 @@
+// Copyright 2021-2023 Protocol Labs
+// SPDX-License-Identifier: Apache-2.0, MIT
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use cid::Cid;
+use wasmtime::Module;
+
+use super::EngineConfig;
+use crate::gas::WasmGasPrices;
+
+/// Length of the header preceding each serialized module: the byte size of the module's
+/// instrumented wasm, as a little-endian u64.
+const HEADER_LEN: usize = 8;
+
+/// A directory of wasmtime-compiled modules, persisted across processes to avoid re-compiling
+/// actor code on every launch.
+///
+/// Modules are keyed by code CID and a fingerprint of the engine configuration options that affect
+/// code generation (including the FVM version, as instrumentation may change between releases),
+/// and of the wasmtime version and compilation settings. Wasmtime additionally refuses to load
+/// modules compiled by incompatible engines.
+pub(super) struct DiskCache {
+    dir: PathBuf,
+    fingerprint: String,
+}
+
+impl DiskCache {
+    pub fn new(dir: PathBuf, ec: &EngineConfig, engine: &wasmtime::Engine) -> anyhow::Result<Self> {
+        Ok(DiskCache {
+            dir,
+            fingerprint: fingerprint(ec, engine)?,
+        })
+    }
+
+    fn path(&self, k: &Cid) -> PathBuf {
+        self.dir.join(format!("{}-{}.module", k, self.fingerprint))
+    }
+
+    /// Load a previously stored module, returning it along with its recorded size. Missing,
+    /// corrupt, or incompatible entries are treated as cache misses.
+    ///
+    /// # Safety
+    ///
+    /// The cache directory must only be writable by trusted parties. See
+    /// [`wasmtime::Module::deserialize`] for details.
+    pub unsafe fn load(&self, engine: &wasmtime::Engine, k: &Cid) -> Option<(Module, usize)> {
+        let path = self.path(k);
+        let data = match fs::read(&path) {
+            Ok(data) => data,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return None,
+            Err(e) => {
+                log::warn!("failed to read cached module {}: {}", path.display(), e);
+                return None;
+            }
+        };
+        if data.len() < HEADER_LEN {
+            log::warn!("cached module {} is truncated", path.display());
+            return None;
+        }
+        let (header, compiled) = data.split_at(HEADER_LEN);
+        let size = u64::from_le_bytes(header.try_into().expect("header is 8 bytes")) as usize;
+        match Module::deserialize(engine, compiled) {
+            Ok(module) => Some((module, size)),
+            Err(e) => {
+                log::warn!("failed to load cached module {}: {}", path.display(), e);
+                None
+            }
+        }
+    }
+
+    /// Persist a compiled module. Failures are logged and otherwise ignored, as the cache is
+    /// purely an optimization.
+    pub fn store(&self, k: &Cid, module: &Module, size: usize) {
+        let path = self.path(k);
+        if let Err(e) = self.try_store(&path, module, size) {
+            log::warn!("failed to cache module {}: {}", path.display(), e);
+        }
+    }
+
+    fn try_store(&self, path: &Path, module: &Module, size: usize) -> anyhow::Result<()> {
+        let mut data = (size as u64).to_le_bytes().to_vec();
+        data.extend_from_slice(&module.serialize()?);
+
+        // Write to a temporary file first and rename it into place so concurrent processes never
+        // observe a partially written module.
+        fs::create_dir_all(&self.dir)?;
+        let tmp = path.with_extension(format!("tmp.{}", std::process::id()));
+        fs::write(&tmp, &data)?;
+        if let Err(e) = fs::rename(&tmp, path) {
+            let _ = fs::remove_file(&tmp);
+            return Err(e.into());
+        }
+        Ok(())
+    }
+}
+
+/// Computes a blake2b fingerprint over an explicit encoding of the engine configuration, so it
+/// stays the same across Rust releases (unlike `std`'s `DefaultHasher`).
+fn fingerprint(ec: &EngineConfig, engine: &wasmtime::Engine) -> anyhow::Result<String> {
+    let mut state = blake2b_simd::Params::new().hash_length(16).to_state();
+
+    let version = env!("CARGO_PKG_VERSION");
+    state.update(&(version.len() as u64).to_le_bytes());
+    state.update(version.as_bytes());
+
+    state.update(&ec.max_wasm_stack.to_le_bytes());
+    state.update(&ec.max_inst_memory_bytes.to_le_bytes());
+    state.update(&[
+        ec.wasm_bulk_memory as u8,
+        ec.wasm_simd as u8,
+        ec.wasm_backtrace as u8,
+        ec.epoch_interruption as u8,
+    ]);
+
+    // Destructure so that new prices can't be silently left out of the fingerprint.
+    let WasmGasPrices {
+        instruction_default,
+        math_default,
+        jump_unconditional,
+        jump_conditional,
+        jump_indirect,
+        call,
+        memory_fill_base_cost,
+        memory_fill_per_byte_cost,
+        memory_access_cost,
+        memory_copy_per_byte_cost,
+        memory_grow_base_cost,
+        memory_grow_per_page_cost,
+    } = &ec.wasm_prices;
+    for price in [
+        instruction_default,
+        math_default,
+        jump_unconditional,
+        jump_conditional,
+        jump_indirect,
+        call,
+        memory_fill_base_cost,
+        memory_fill_per_byte_cost,
+        memory_access_cost,
+        memory_copy_per_byte_cost,
+        memory_grow_base_cost,
+        memory_grow_per_page_cost,
+    ] {
+        state.update(&price.as_milligas().to_le_bytes());
+    }
+
+    // Covers the wasmtime version and the compilation settings of the engine, which may differ
+    // from those derived from the engine config: wasmtime records both in every module it
+    // compiles, so we hash an empty module compiled by the engine.
+    let empty = engine.precompile_module(b"\0asm\x01\0\0\0")?;
+    state.update(&(empty.len() as u64).to_le_bytes());
+    state.update(&empty);
+
+    Ok(state.finalize().to_hex().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use multihash::Code::Blake2b256;
+    use multihash::MultihashDigest;
+
+    use super::*;
+    use crate::machine::NetworkConfig;
+    use fvm_shared::version::NetworkVersion;
+
+    #[test]
+    fn round_trip() {
+        let dir = std::env::temp_dir().join(format!("fvm-module-cache-{}", std::process::id()));
+        let ec: EngineConfig = (&NetworkConfig::new(NetworkVersion::V18)).into();
+        let engine = wasmtime::Engine::default();
+        let cache = DiskCache::new(dir.clone(), &ec, &engine).unwrap();
+
+        let wasm = b"\0asm\x01\0\0\0";
+        let k = Cid::new_v1(fvm_ipld_encoding::IPLD_RAW, Blake2b256.digest(wasm));
+        assert!(unsafe { cache.load(&engine, &k) }.is_none());
+
+        let module = Module::new(&engine, wasm).unwrap();
+        cache.store(&k, &module, 42);
+        let (_, size) = unsafe { cache.load(&engine, &k) }.expect("module should be cached");
+        assert_eq!(size, 42);
+
+        // A different configuration doesn't see the cached module.
+        let other = DiskCache::new(
+            dir.clone(),
+            &EngineConfig {
+                wasm_simd: !ec.wasm_simd,
+                ..ec.clone()
+            },
+            &engine,
+        )
+        .unwrap();
+        assert!(unsafe { other.load(&engine, &k) }.is_none());
+
+        // Neither does an engine with different compilation settings.
+        let mut config = wasmtime::Config::new();
+        config.wasm_simd(false);
+        let other_engine = wasmtime::Engine::new(&config).unwrap();
+        let other = DiskCache::new(dir.clone(), &ec, &other_engine).unwrap();
+        assert_ne!(other.fingerprint, cache.fingerprint);
+        assert!(unsafe { other.load(&other_engine, &k) }.is_none());
+
+        // The fingerprint is deterministic.
+        assert_eq!(
+            DiskCache::new(dir.clone(), &ec, &engine)
+                .unwrap()
+                .fingerprint,
+            cache.fingerprint
+        );
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -1,6 +1,7 @@
 // Copyright 2021-2023 Protocol Labs
 // SPDX-License-Identifier: Apache-2.0, MIT
 use std::sync::{Condvar, Mutex};
+use std::time::{Duration, Instant};
 
 /// An instance pool manages the available pool of engine instances.
 ///
@@ -24,6 +25,8 @@ struct InstancePoolInner {
     per_engine_limit: u32,
     /// The ID of the engine currently "locking" the instance pool.
     locked: Option<u64>,
+    /// The total time engines spent waiting for the pool to be unlocked.
+    wait_time: Duration,
 }
 
 impl InstancePool {
@@ -34,6 +37,7 @@ impl InstancePool {
                 available,
                 per_engine_limit,
                 locked: None,
+                wait_time: Duration::ZERO,
             }),
             condv: Condvar::new(),
         }
@@ -62,10 +66,14 @@ impl InstancePool {
         // Wait until we have an instance available. Either:
         // 1. We own the executor lock.
         // 2. We _could_ own the executor lock.
-        guard = self
-            .condv
-            .wait_while(guard, |p| p.locked.unwrap_or(id) != id)
-            .unwrap();
+        if guard.locked.unwrap_or(id) != id {
+            let start = Instant::now();
+            guard = self
+                .condv
+                .wait_while(guard, |p| p.locked.unwrap_or(id) != id)
+                .unwrap();
+            guard.wait_time += start.elapsed();
+        }
 
         // We either have, or could, lock the executor. So there should be instances available.
         assert!(
@@ -79,6 +87,11 @@ impl InstancePool {
             guard.locked = Some(id);
         }
     }
+
+    /// Returns the total time engines spent waiting for instances held by other engines.
+    pub fn wait_time(&self) -> Duration {
+        self.inner.lock().unwrap().wait_time
+    }
 }
 
 #[test]
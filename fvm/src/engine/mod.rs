@@ -2,47 +2,147 @@
 // SPDX-License-Identifier: Apache-2.0, MIT
 
 mod concurrency;
+mod disk_cache;
 mod instance_pool;
 
 use std::any::{Any, TypeId};
 use std::collections::hash_map::Entry::{Occupied, Vacant};
 use std::collections::HashMap;
 use std::ops::Deref;
-use std::sync::{Arc, Mutex};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, Arc, Mutex, Once};
+use std::thread;
+use std::time::{Duration, Instant};
 
 use anyhow::{anyhow, Context};
 use cid::Cid;
 use fvm_ipld_blockstore::Blockstore;
-use fvm_shared::error::ExitCode;
+use fvm_shared::error::ErrorNumber;
 use fvm_wasm_instrument::gas_metering::GAS_COUNTER_NAME;
 use num_traits::Zero;
 use wasmtime::OptLevel::Speed;
 use wasmtime::{
-    Global, GlobalType, InstanceAllocationStrategy, Linker, Memory, MemoryType, Module, Mutability,
-    Val, ValType,
+    AsContextMut, Extern, Global, GlobalType, InstanceAllocationStrategy, Linker, Memory,
+    MemoryType, Module, Mutability, Val, ValType,
 };
 
 use crate::gas::{Gas, GasTimer, WasmGasPrices};
 use crate::machine::limiter::MemoryLimiter;
-use crate::machine::{Machine, NetworkConfig};
+use crate::machine::{Machine, MachineContext, NetworkConfig};
 use crate::syscalls::error::Abort;
 use crate::syscalls::{
-    bind_syscalls, charge_for_exec, charge_for_init, record_init_time, update_gas_available,
-    InvocationData,
+    charge_for_exec, charge_for_init, record_init_time, update_gas_available, InvocationData,
 };
 use crate::Kernel;
 
 use self::concurrency::EngineConcurrency;
+use self::disk_cache::DiskCache;
 use self::instance_pool::InstancePool;
 
 /// The expected max stack depth used to determine the number of instances needed for a given
 /// concurrency level.
 const EXPECTED_MAX_STACK_DEPTH: u32 = 20;
 
+/// The interval at which the engine's epoch is incremented, i.e., the granularity at which
+/// execution deadlines are enforced.
+const EPOCH_TICK: Duration = Duration::from_millis(10);
+
+/// The fatal error raised when a message exceeds the execution time budget configured in
+/// [`MachineContext::execution_budget`](crate::machine::MachineContext::execution_budget).
+///
+/// Unlike other fatal errors, this error is not turned into a receipt: it is returned from
+/// [`Executor::execute_message`](crate::executor::Executor::execute_message) as-is, and the
+/// machine must be discarded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
+#[error("execution time budget exceeded")]
+pub struct ExecutionBudgetExceeded;
+
+/// The error returned when no engine became available within
+/// [`ConcurrencyConfig::acquire_timeout`]. The message wasn't applied and the state is unchanged,
+/// so it may be retried.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
+#[error("timed out waiting for an engine")]
+pub struct EngineAcquireTimeout;
+
 /// Container managing engines with different consensus-affecting configurations.
 pub struct MultiEngine {
     engines: Mutex<HashMap<EngineConfig, EnginePool>>,
     concurrency: u32,
+    module_cache_dir: Option<PathBuf>,
+    instance_pool: InstancePoolConfig,
+    concurrency_config: ConcurrencyConfig,
+}
+
+/// Sizing of the pool of pre-allocated wasm instance slots backing each engine.
+///
+/// Instances are allocated from a fixed pool of slots. When an instance is dropped, its slot is kept
+/// "warm" and preferentially reused to instantiate the same module again, which avoids most of the
+/// instantiation overhead for frequently invoked actor code.
+///
+/// None of these options affect consensus.
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+pub struct InstancePoolConfig {
+    /// The total number of instance slots. Must be at least the maximum call depth.
+    ///
+    /// DEFAULT: None (enough slots for one full call stack, plus some per concurrent engine)
+    pub instance_count: Option<u32>,
+    /// The maximum number of unused slots to keep warm for reuse by the module they last hosted.
+    ///
+    /// DEFAULT: 100
+    pub max_unused_warm_slots: u32,
+    /// The number of bytes of each slot's linear memory to keep resident (instead of releasing it
+    /// back to the OS) when the slot is reused.
+    ///
+    /// DEFAULT: 0
+    pub memory_keep_resident: usize,
+}
+
+impl Default for InstancePoolConfig {
+    fn default() -> Self {
+        InstancePoolConfig {
+            instance_count: None,
+            max_unused_warm_slots: 100,
+            memory_keep_resident: 0,
+        }
+    }
+}
+
+/// Concurrency controls of each engine pool, trading throughput for memory usage.
+///
+/// None of these options affect consensus.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Hash)]
+pub struct ConcurrencyConfig {
+    /// The number of instance slots reserved for a single engine: once fewer slots are available,
+    /// the engine that took the pool below this number gets exclusive use of the pool until it
+    /// returns enough slots, while other engines wait. Must be at least the maximum call depth (so
+    /// the engine can complete its call stack), and at most the instance pool's size.
+    ///
+    /// DEFAULT: None (the maximum call depth)
+    pub instance_reservation: Option<u32>,
+    /// How long to wait for an engine to become available before giving up on applying a message
+    /// with [`EngineAcquireTimeout`].
+    ///
+    /// DEFAULT: None (wait indefinitely)
+    pub acquire_timeout: Option<Duration>,
+}
+
+/// Statistics about an [`EnginePool`]'s contention, for tuning its concurrency.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct EnginePoolStats {
+    /// The number of engines acquired.
+    pub acquired: u64,
+    /// The number of engine acquisitions that had to wait for another engine to be released.
+    pub contended: u64,
+    /// The total time spent waiting for engines.
+    pub wait_time: Duration,
+    /// The longest time spent waiting for an engine.
+    pub max_wait_time: Duration,
+    /// The number of engine acquisitions that gave up after the
+    /// [`ConcurrencyConfig::acquire_timeout`].
+    pub timed_out: u64,
+    /// The total time engines spent waiting for instance slots reserved by other engines.
+    pub instance_wait_time: Duration,
 }
 
 /// The proper way of getting this struct is to convert from `NetworkConfig`
@@ -52,12 +152,35 @@ pub struct EngineConfig {
     pub max_wasm_stack: u32,
     pub max_inst_memory_bytes: u64,
     pub concurrency: u32,
-    pub wasm_prices: &'static WasmGasPrices,
+    pub wasm_prices: WasmGasPrices,
     pub actor_redirect: Vec<(Cid, Cid)>,
+    pub wasm_bulk_memory: bool,
+    pub wasm_simd: bool,
+    /// Capture wasm backtraces when actors fail (enabled along with actor debugging).
+    pub wasm_backtrace: bool,
+    /// Compile actors with epoch interruption checks at function entries and loop back-edges,
+    /// required to enforce a [`MachineContext::execution_budget`]. These checks slow down all
+    /// actor code, so they're only enabled for machines with an execution budget.
+    pub epoch_interruption: bool,
+    /// Syscalls, as `(module, name)` pairs, that are unavailable to actors.
+    pub disabled_syscalls: Vec<(&'static str, &'static str)>,
+    /// Directory in which to persist compiled modules across processes. When unset, modules are
+    /// compiled on first use in every process.
+    ///
+    /// This directory must only be writable by trusted parties: compiled modules are loaded
+    /// without validation.
+    pub module_cache_dir: Option<PathBuf>,
+    /// Sizing of the instance pool.
+    pub instance_pool: InstancePoolConfig,
+    /// Concurrency controls of the engine pool.
+    pub concurrency_config: ConcurrencyConfig,
 }
 
 impl EngineConfig {
     fn instance_pool_size(&self) -> u32 {
+        if let Some(count) = self.instance_pool.instance_count {
+            return count;
+        }
         std::cmp::min(
             // Allocate at least one full call depth worth of stack, plus some per concurrent call
             // we allow.
@@ -67,6 +190,12 @@ impl EngineConfig {
             48 * 1024,
         )
     }
+
+    fn instance_reservation(&self) -> u32 {
+        self.concurrency_config
+            .instance_reservation
+            .unwrap_or(self.max_call_depth)
+    }
 }
 
 impl From<&NetworkConfig> for EngineConfig {
@@ -75,9 +204,26 @@ impl From<&NetworkConfig> for EngineConfig {
             max_call_depth: nc.max_call_depth,
             max_wasm_stack: nc.max_wasm_stack,
             max_inst_memory_bytes: nc.max_inst_memory_bytes,
-            wasm_prices: &nc.price_list.wasm_rules,
+            wasm_prices: nc.price_list.wasm_rules.clone(),
             actor_redirect: nc.actor_redirect.clone(),
+            wasm_bulk_memory: nc.wasm_bulk_memory,
+            wasm_simd: nc.wasm_simd,
+            wasm_backtrace: nc.actor_debugging,
+            epoch_interruption: false,
+            disabled_syscalls: nc.disabled_syscalls.clone(),
             concurrency: 1,
+            module_cache_dir: None,
+            instance_pool: Default::default(),
+            concurrency_config: Default::default(),
+        }
+    }
+}
+
+impl From<&MachineContext> for EngineConfig {
+    fn from(mc: &MachineContext) -> Self {
+        EngineConfig {
+            epoch_interruption: mc.execution_budget.is_some(),
+            ..(&mc.network).into()
         }
     }
 }
@@ -90,17 +236,52 @@ impl MultiEngine {
         MultiEngine {
             engines: Mutex::new(HashMap::new()),
             concurrency,
+            module_cache_dir: None,
+            instance_pool: Default::default(),
+            concurrency_config: Default::default(),
         }
     }
 
+    /// Persist compiled modules to (and load them from) the given directory. See
+    /// [`EngineConfig::module_cache_dir`].
+    pub fn with_module_cache_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.module_cache_dir = Some(dir.into());
+        self
+    }
+
+    /// Size the instance pools of all engines according to the given configuration. See
+    /// [`InstancePoolConfig`].
+    pub fn with_instance_pool(mut self, config: InstancePoolConfig) -> Self {
+        self.instance_pool = config;
+        self
+    }
+
+    /// Apply the given concurrency controls to all engines. See [`ConcurrencyConfig`].
+    pub fn with_concurrency_config(mut self, config: ConcurrencyConfig) -> Self {
+        self.concurrency_config = config;
+        self
+    }
+
     pub fn get(&self, nc: &NetworkConfig) -> anyhow::Result<EnginePool> {
+        self.get_with_config(nc.into())
+    }
+
+    /// Get an engine for the given machine context. Unlike [`MultiEngine::get`], this returns an
+    /// engine able to enforce the context's [`MachineContext::execution_budget`], if any.
+    pub fn get_for_context(&self, mc: &MachineContext) -> anyhow::Result<EnginePool> {
+        self.get_with_config(mc.into())
+    }
+
+    fn get_with_config(&self, mut ec: EngineConfig) -> anyhow::Result<EnginePool> {
         let mut engines = self
             .engines
             .lock()
             .map_err(|_| anyhow::Error::msg("multiengine lock is poisoned"))?;
 
-        let mut ec: EngineConfig = nc.into();
         ec.concurrency = self.concurrency;
+        ec.module_cache_dir = self.module_cache_dir.clone();
+        ec.instance_pool = self.instance_pool.clone();
+        ec.concurrency_config = self.concurrency_config.clone();
 
         let pool = match engines.entry(ec.clone()) {
             Occupied(entry) => entry.into_mut(),
@@ -122,46 +303,39 @@ fn wasmtime_config(ec: &EngineConfig) -> anyhow::Result<wasmtime::Config> {
         return Err(anyhow!("concurrency limit must not be 0"));
     }
 
-    let instance_count = ec.instance_pool_size();
-    let instance_memory_maximum_size = ec.max_inst_memory_bytes;
-    if instance_memory_maximum_size % wasmtime_environ::WASM_PAGE_SIZE as u64 != 0 {
-        return Err(anyhow!(
-            "requested memory limit {} not a multiple of the WASM_PAGE_SIZE {}",
-            instance_memory_maximum_size,
-            wasmtime_environ::WASM_PAGE_SIZE
-        ));
-    }
-
     let mut c = wasmtime::Config::default();
 
     // wasmtime default: OnDemand
     // We want to pre-allocate all permissible memory to support the maximum allowed recursion limit.
-
-    let mut alloc_strat_cfg = wasmtime::PoolingAllocationConfig::default();
-    alloc_strat_cfg.instance_count(instance_count);
-
-    // Adjust the maximum amount of host memory that can be committed to an instance to
-    // match the static linear memory size we reserve for each slot.
-    alloc_strat_cfg.instance_memory_pages(
-        instance_memory_maximum_size / (wasmtime_environ::WASM_PAGE_SIZE as u64),
-    );
-    c.allocation_strategy(InstanceAllocationStrategy::Pooling(alloc_strat_cfg));
+    c.allocation_strategy(InstanceAllocationStrategy::Pooling(
+        InstancePoolSettings::new(ec)?.to_wasmtime(),
+    ));
 
     // wasmtime default: true
     // We disable this as we always charge for memory regardless and `memory_init_cow` can baloon compiled wasm modules.
     c.memory_init_cow(false);
 
     // wasmtime default: 4GB
-    c.static_memory_maximum_size(instance_memory_maximum_size);
+    c.static_memory_maximum_size(ec.max_inst_memory_bytes);
 
     // wasmtime default: false
     // We don't want threads, there is no way to ensure determisism
     c.wasm_threads(false);
 
     // wasmtime default: true
-    // simd isn't supported in wasm-instrument, but if we add support there, we can probably enable this.
-    // Note: stack limits may need adjusting after this is enabled
-    c.wasm_simd(false);
+    // Opt-in per network. Only the deterministic (integer, bitwise, and memory) subset of SIMD is
+    // accepted: floating point SIMD instructions are rejected when injecting gas (see
+    // `WasmGasPrices`).
+    // Note: stack limits may need adjusting when this is enabled
+    c.wasm_simd(ec.wasm_simd);
+
+    // wasmtime default: true
+    // Only used to diagnose failures (e.g., running out of gas), so only enabled when debugging.
+    c.wasm_backtrace(ec.wasm_backtrace);
+
+    // wasmtime default: false
+    // Relaxed SIMD is non-deterministic by design.
+    c.wasm_relaxed_simd(false);
 
     // wasmtime default: false
     c.wasm_multi_memory(false);
@@ -170,9 +344,8 @@ fn wasmtime_config(ec: &EngineConfig) -> anyhow::Result<wasmtime::Config> {
     c.wasm_memory64(false);
 
     // wasmtime default: true
-    // Note: wasm-instrument only supports this at a basic level, for M2 we will
-    // need to add more advanced support
-    c.wasm_bulk_memory(true);
+    // Enabled by default on all networks, but may be disabled per network.
+    c.wasm_bulk_memory(ec.wasm_bulk_memory);
 
     // wasmtime default: true
     // we should be able to enable this for M2, just need to make sure that it's
@@ -196,7 +369,10 @@ fn wasmtime_config(ec: &EngineConfig) -> anyhow::Result<wasmtime::Config> {
 
     // Execution cost accouting is done through wasm instrumentation,
     c.consume_fuel(false);
-    c.epoch_interruption(false);
+
+    // wasmtime default: false
+    // Used to enforce (non-consensus) wall-clock execution budgets, see `ExecutionBudgetExceeded`.
+    c.epoch_interruption(ec.epoch_interruption);
 
     // Disable debug-related things, wasm-instrument doesn't fix debug info
     // yet, so those aren't useful, just add overhead
@@ -204,7 +380,6 @@ fn wasmtime_config(ec: &EngineConfig) -> anyhow::Result<wasmtime::Config> {
     c.generate_address_map(false);
     c.cranelift_debug_verifier(false);
     c.native_unwind_info(false);
-    c.wasm_backtrace(false);
     c.wasm_reference_types(false);
 
     // Reiterate some defaults
@@ -221,6 +396,81 @@ fn wasmtime_config(ec: &EngineConfig) -> anyhow::Result<wasmtime::Config> {
     Ok(c)
 }
 
+/// The error returned when the [`InstancePoolConfig`] or [`ConcurrencyConfig`] of an engine is
+/// inconsistent with its limits.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
+pub enum InstancePoolError {
+    #[error("instance pool size {count} is smaller than the max call depth {max_call_depth}")]
+    TooFewInstances { count: u32, max_call_depth: u32 },
+    #[error("instance reservation {reservation} must be between the max call depth {max_call_depth} and the instance pool size {count}")]
+    InvalidReservation {
+        reservation: u32,
+        max_call_depth: u32,
+        count: u32,
+    },
+    #[error("requested memory limit {0} not a multiple of the WASM_PAGE_SIZE")]
+    UnalignedMemoryLimit(u64),
+}
+
+/// The settings of the instance pool backing an engine.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct InstancePoolSettings {
+    instance_count: u32,
+    max_unused_warm_slots: u32,
+    memory_keep_resident: usize,
+    memory_pages: u64,
+}
+
+impl InstancePoolSettings {
+    /// Computes the instance pool settings, validating the [`InstancePoolConfig`] and
+    /// [`ConcurrencyConfig`] against the engine's limits.
+    fn new(ec: &EngineConfig) -> Result<Self, InstancePoolError> {
+        let instance_count = ec.instance_pool_size();
+        if instance_count < ec.max_call_depth {
+            return Err(InstancePoolError::TooFewInstances {
+                count: instance_count,
+                max_call_depth: ec.max_call_depth,
+            });
+        }
+        let reservation = ec.instance_reservation();
+        if reservation < ec.max_call_depth || reservation > instance_count {
+            return Err(InstancePoolError::InvalidReservation {
+                reservation,
+                max_call_depth: ec.max_call_depth,
+                count: instance_count,
+            });
+        }
+        if ec.max_inst_memory_bytes % wasmtime_environ::WASM_PAGE_SIZE as u64 != 0 {
+            return Err(InstancePoolError::UnalignedMemoryLimit(
+                ec.max_inst_memory_bytes,
+            ));
+        }
+        Ok(InstancePoolSettings {
+            instance_count,
+            max_unused_warm_slots: ec.instance_pool.max_unused_warm_slots,
+            memory_keep_resident: ec.instance_pool.memory_keep_resident,
+            // Adjust the maximum amount of host memory that can be committed to an instance to
+            // match the static linear memory size we reserve for each slot.
+            memory_pages: ec.max_inst_memory_bytes / (wasmtime_environ::WASM_PAGE_SIZE as u64),
+        })
+    }
+
+    fn to_wasmtime(self) -> wasmtime::PoolingAllocationConfig {
+        let mut alloc_strat_cfg = wasmtime::PoolingAllocationConfig::default();
+        alloc_strat_cfg.instance_count(self.instance_count);
+
+        // wasmtime default: 100
+        // Idle slots are reused for the module they last hosted, keeping hot actor code warm.
+        alloc_strat_cfg.max_unused_warm_slots(self.max_unused_warm_slots);
+
+        // wasmtime default: 0
+        alloc_strat_cfg.linear_memory_keep_resident(self.memory_keep_resident);
+
+        alloc_strat_cfg.instance_memory_pages(self.memory_pages);
+        alloc_strat_cfg
+    }
+}
+
 #[derive(Clone)]
 struct ModuleRecord {
     module: Module,
@@ -243,10 +493,22 @@ struct EngineInner {
     dummy_memory: Memory,
 
     module_cache: Mutex<HashMap<Cid, ModuleRecord>>,
+    disk_cache: Option<DiskCache>,
     instance_cache: Mutex<HashMap<TypeId, Box<dyn Any + Send>>>,
     config: EngineConfig,
 
     actor_redirect: HashMap<Cid, Cid>,
+
+    /// Started on first use of an execution deadline, increments the engine's epoch every
+    /// [`EPOCH_TICK`] until the engine is dropped.
+    epoch_ticker: Once,
+    epoch_ticker_stop: Arc<AtomicBool>,
+}
+
+impl Drop for EngineInner {
+    fn drop(&mut self) {
+        self.epoch_ticker_stop.store(true, Ordering::Relaxed);
+    }
 }
 
 /// EnginePool represents a limited pool of engines.
@@ -263,6 +525,35 @@ impl EnginePool {
         }
     }
 
+    /// Like [`acquire`](Self::acquire), but gives up with [`EngineAcquireTimeout`] if no
+    /// [`Engine`] becomes available within the configured
+    /// [`ConcurrencyConfig::acquire_timeout`].
+    pub fn try_acquire(&self) -> Result<Engine, EngineAcquireTimeout> {
+        let timeout = self.0.config.concurrency_config.acquire_timeout;
+        let id = self
+            .0
+            .concurrency_limit
+            .acquire_timeout(timeout)
+            .ok_or(EngineAcquireTimeout)?;
+        Ok(Engine {
+            id,
+            inner: self.0.clone(),
+        })
+    }
+
+    /// Returns statistics about contention for this pool's engines and instances.
+    pub fn stats(&self) -> EnginePoolStats {
+        EnginePoolStats {
+            instance_wait_time: self.0.instance_limit.wait_time(),
+            ..self.0.concurrency_limit.stats()
+        }
+    }
+
+    /// Returns the configuration of this pool's engines.
+    pub fn config(&self) -> &EngineConfig {
+        &self.0.config
+    }
+
     pub fn new_default(ec: EngineConfig) -> anyhow::Result<Self> {
         EnginePool::new(&wasmtime_config(&ec)?, ec)
     }
@@ -280,19 +571,160 @@ impl EnginePool {
             .expect("failed to create dummy memory");
 
         let actor_redirect = ec.actor_redirect.iter().cloned().collect();
+        let disk_cache = ec
+            .module_cache_dir
+            .clone()
+            .map(|dir| DiskCache::new(dir, &ec, &engine))
+            .transpose()?;
 
         Ok(EnginePool(Arc::new(EngineInner {
             concurrency_limit: EngineConcurrency::new(ec.concurrency),
-            instance_limit: InstancePool::new(ec.instance_pool_size(), ec.max_call_depth),
+            instance_limit: InstancePool::new(ec.instance_pool_size(), ec.instance_reservation()),
             engine,
             dummy_memory,
             dummy_gas_global: dummy_gg,
             module_cache: Default::default(),
+            disk_cache,
             instance_cache: Mutex::new(HashMap::new()),
             config: ec,
             actor_redirect,
+            epoch_ticker: Once::new(),
+            epoch_ticker_stop: Default::default(),
         })))
     }
+
+    /// Compiles the actor code addressed by the supplied CIDs in the background (on rayon's global
+    /// thread pool), so it's ready by the time it's executed. Unlike [`Engine::preload`], this
+    /// doesn't acquire an engine and doesn't block on compilation, only on reading the code from
+    /// the blockstore. Code that's already loaded, or missing from the blockstore, is skipped.
+    ///
+    /// Compilation failures are logged and otherwise ignored: they're reported again if the code
+    /// is executed.
+    pub fn prefetch<'a, BS, I>(&self, blockstore: BS, cids: I) -> anyhow::Result<Prefetch>
+    where
+        BS: Blockstore,
+        I: IntoIterator<Item = &'a Cid>,
+    {
+        let (done_tx, done) = mpsc::channel();
+        let mut pending = 0;
+        for cid in cids {
+            let cid = *self.0.actor_redirect.get(cid).unwrap_or(cid);
+            if self
+                .0
+                .module_cache
+                .lock()
+                .expect("module_cache poisoned")
+                .contains_key(&cid)
+            {
+                continue;
+            }
+            let Some(wasm) = blockstore.get(&cid)? else {
+                continue;
+            };
+
+            log::trace!("prefetching code CID {cid}");
+            let inner = self.0.clone();
+            let done_tx = done_tx.clone();
+            pending += 1;
+            rayon::spawn(move || {
+                // Compile without holding the lock so we don't block execution.
+                match inner.load_raw(&cid, &wasm) {
+                    Ok(module) => {
+                        inner
+                            .module_cache
+                            .lock()
+                            .expect("module_cache poisoned")
+                            .entry(cid)
+                            .or_insert(module);
+                    }
+                    Err(e) => log::warn!("failed to prefetch code CID {cid}: {e:#}"),
+                }
+                let _ = done_tx.send(());
+            });
+        }
+        Ok(Prefetch { pending, done })
+    }
+}
+
+/// Code being compiled in the background by [`EnginePool::prefetch`]. Dropping this doesn't cancel
+/// compilation.
+pub struct Prefetch {
+    pending: usize,
+    done: mpsc::Receiver<()>,
+}
+
+impl Prefetch {
+    /// The number of modules being compiled.
+    pub fn len(&self) -> usize {
+        self.pending
+    }
+
+    /// Returns true if no modules needed compiling.
+    pub fn is_empty(&self) -> bool {
+        self.pending == 0
+    }
+
+    /// Blocks until all modules have been compiled (or failed to compile).
+    pub fn wait(self) {
+        for _ in 0..self.pending {
+            if self.done.recv().is_err() {
+                break;
+            }
+        }
+    }
+}
+
+impl EngineInner {
+    fn load_raw(&self, k: &Cid, raw_wasm: &[u8]) -> anyhow::Result<ModuleRecord> {
+        if let Some(disk_cache) = &self.disk_cache {
+            // SAFETY: the cache directory is trusted, see `EngineConfig::module_cache_dir`.
+            if let Some((module, size)) = unsafe { disk_cache.load(&self.engine, k) } {
+                return Ok(ModuleRecord { module, size });
+            }
+        }
+
+        // First make sure that non-instrumented wasm is valid
+        Module::validate(&self.engine, raw_wasm)
+            .map_err(anyhow::Error::msg)
+            .with_context(|| "failed to validate actor wasm")?;
+
+        // Note: when adding debug mode support (with recorded syscall replay) don't instrument to
+        // avoid breaking debug info
+
+        use fvm_wasm_instrument::{gas_metering, stack_limiter};
+
+        // stack limiter adds post/pre-ambles to call instructions; We want to do that
+        // before injecting gas accounting calls to avoid this overhead in every single
+        // block of code.
+        let raw_wasm = stack_limiter::inject(raw_wasm, self.config.max_wasm_stack)
+            .map_err(anyhow::Error::msg)?;
+
+        // inject gas metering based on a price list. This function will
+        // * add a new mutable i64 global import, gas.gas_counter
+        // * push a gas counter function which deduces gas from the global, and
+        //   traps when gas.gas_counter is less than zero
+        // * optionally push a function which wraps memory.grow instruction
+        //   making it charge gas based on memory requested
+        // * divide code into metered blocks, and add a call to the gas counter
+        //   function before entering each metered block
+        // * NOTE: Currently cannot instrument and charge for `table.grow` because the instruction
+        //   (code `0xFC 15`) uses what parity-wasm calls the `BULK_PREFIX` but it was added later in
+        //   https://github.com/WebAssembly/reference-types/issues/29 and is not recognised by the
+        //   parity-wasm module parser, so the contract cannot grow the tables.
+        let raw_wasm = gas_metering::inject(&raw_wasm, &self.config.wasm_prices, "gas")
+            .map_err(|_| anyhow::Error::msg("injecting gas counter failed"))?;
+
+        let module = Module::from_binary(&self.engine, &raw_wasm)?;
+
+        if let Some(disk_cache) = &self.disk_cache {
+            disk_cache.store(k, &module, raw_wasm.len());
+        }
+
+        Ok(ModuleRecord {
+            module,
+            size: raw_wasm.len(),
+        })
+    }
 }
 
 struct Cache<K> {
@@ -393,7 +825,7 @@ impl Engine {
         let size = match cache.get(k) {
             Some(item) => item.size,
             None => {
-                let m = self.load_raw(wasm)?;
+                let m = self.inner.load_raw(k, wasm)?;
                 let s = m.size;
                 cache.insert(*k, m);
                 s
@@ -402,46 +834,6 @@ impl Engine {
         Ok(size)
     }
 
-    fn load_raw(&self, raw_wasm: &[u8]) -> anyhow::Result<ModuleRecord> {
-        // First make sure that non-instrumented wasm is valid
-        Module::validate(&self.inner.engine, raw_wasm)
-            .map_err(anyhow::Error::msg)
-            .with_context(|| "failed to validate actor wasm")?;
-
-        // Note: when adding debug mode support (with recorded syscall replay) don't instrument to
-        // avoid breaking debug info
-
-        use fvm_wasm_instrument::{gas_metering, stack_limiter};
-
-        // stack limiter adds post/pre-ambles to call instructions; We want to do that
-        // before injecting gas accounting calls to avoid this overhead in every single
-        // block of code.
-        let raw_wasm = stack_limiter::inject(raw_wasm, self.inner.config.max_wasm_stack)
-            .map_err(anyhow::Error::msg)?;
-
-        // inject gas metering based on a price list. This function will
-        // * add a new mutable i64 global import, gas.gas_counter
-        // * push a gas counter function which deduces gas from the global, and
-        //   traps when gas.gas_counter is less than zero
-        // * optionally push a function which wraps memory.grow instruction
-        //   making it charge gas based on memory requested
-        // * divide code into metered blocks, and add a call to the gas counter
-        //   function before entering each metered block
-        // * NOTE: Currently cannot instrument and charge for `table.grow` because the instruction
-        //   (code `0xFC 15`) uses what parity-wasm calls the `BULK_PREFIX` but it was added later in
-        //   https://github.com/WebAssembly/reference-types/issues/29 and is not recognised by the
-        //   parity-wasm module parser, so the contract cannot grow the tables.
-        let raw_wasm = gas_metering::inject(&raw_wasm, self.inner.config.wasm_prices, "gas")
-            .map_err(|_| anyhow::Error::msg("injecting gas counter failed"))?;
-
-        let module = Module::from_binary(&self.inner.engine, &raw_wasm)?;
-
-        Ok(ModuleRecord {
-            module,
-            size: raw_wasm.len(),
-        })
-    }
-
     /// Load compiled wasm code into the engine.
     ///
     /// # Safety
@@ -489,7 +881,7 @@ impl Engine {
             Vacant(v) => blockstore
                 .get(k)
                 .context("failed to lookup wasm module in blockstore")?
-                .map(|raw_wasm| Ok(v.insert(self.load_raw(&raw_wasm)?).module.clone()))
+                .map(|raw_wasm| Ok(v.insert(self.inner.load_raw(k, &raw_wasm)?).module.clone()))
                 .transpose(),
         }
     }
@@ -517,7 +909,11 @@ impl Engine {
                     let mut linker: Linker<InvocationData<K>> = Linker::new(&self.inner.engine);
                     linker.allow_shadowing(true);
 
-                    bind_syscalls(&mut linker).map_err(Abort::Fatal)?;
+                    K::bind_syscalls(&mut linker).map_err(Abort::Fatal)?;
+                    for &(module, name) in &self.inner.config.disabled_syscalls {
+                        disable_syscall(&mut linker, &mut *store, module, name)
+                            .map_err(Abort::Fatal)?;
+                    }
                     Box::new(Cache { linker })
                 })
                 .downcast_mut()
@@ -559,8 +955,9 @@ impl Engine {
                 // We can't really tell what type of error happened, so we have to assume that we
                 // either ran out of memory or trapped. Given that we've already type-checked the
                 // module, this is the most likely case anyways. That or there'a a bug in the FVM.
+                let policy = &store.data().kernel.machine().context().exit_code_policy;
                 Abort::Exit(
-                    ExitCode::SYS_ILLEGAL_INSTRUCTION,
+                    policy.illegal_instruction(),
                     format!("failed to instantiate module: {e}"),
                     0,
                 )
@@ -588,7 +985,7 @@ impl Engine {
             {
                 Some(raw_wasm) => instantiate(
                     store,
-                    &v.insert(self.load_raw(&raw_wasm).map_err(Abort::Fatal)?)
+                    &v.insert(self.inner.load_raw(k, &raw_wasm).map_err(Abort::Fatal)?)
                         .module,
                 ),
                 None => Ok(None),
@@ -630,6 +1027,11 @@ impl Engine {
             .expect("failed to create available_gas global");
         store.data_mut().avail_gas_global = gg;
 
+        // No deadline by default. The epoch is only ever incremented every few milliseconds, so
+        // this will never be reached.
+        store.set_epoch_deadline(u64::MAX / 2);
+        store.epoch_deadline_trap();
+
         store.limiter(move |data| {
             // Keep the reservation alive as long as the limiter is alive. The limiter limits the
             // store to one instance and one memory, which is covered by the reservation.
@@ -653,6 +1055,52 @@ impl Engine {
 
         store
     }
+
+    /// Abort execution within the given store with [`ExecutionBudgetExceeded`] once the given
+    /// deadline has passed. The deadline is only checked while executing wasm code, at function
+    /// entries and loop back-edges, and only by engines configured with
+    /// [`EngineConfig::epoch_interruption`].
+    pub fn set_execution_deadline<K: Kernel>(
+        &self,
+        store: &mut wasmtime::Store<InvocationData<K>>,
+        deadline: Instant,
+    ) {
+        self.inner.epoch_ticker.call_once(|| {
+            let engine = self.inner.engine.clone();
+            let stop = self.inner.epoch_ticker_stop.clone();
+            thread::spawn(move || {
+                while !stop.load(Ordering::Relaxed) {
+                    thread::sleep(EPOCH_TICK);
+                    engine.increment_epoch();
+                }
+            });
+        });
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        let ticks = (remaining.as_nanos() / EPOCH_TICK.as_nanos()) as u64;
+        store.set_epoch_deadline(ticks.max(1));
+    }
+}
+
+/// Shadows a bound syscall with one that always fails with `IllegalOperation`, preserving its
+/// signature so actors importing it can still be instantiated.
+fn disable_syscall<T>(
+    linker: &mut Linker<T>,
+    mut store: impl AsContextMut<Data = T>,
+    module: &'static str,
+    name: &'static str,
+) -> anyhow::Result<()> {
+    let ty = match linker.get(&mut store, module, name) {
+        Some(Extern::Func(func)) => func.ty(&store),
+        // Nothing to disable.
+        _ => return Ok(()),
+    };
+    linker.func_new(module, name, ty, |_, _, results: &mut [Val]| {
+        if let Some(ret) = results.first_mut() {
+            *ret = Val::I32(ErrorNumber::IllegalOperation as i32);
+        }
+        Ok(())
+    })?;
+    Ok(())
 }
 
 #[repr(transparent)]
@@ -697,10 +1145,15 @@ impl<L: MemoryLimiter> wasmtime::ResourceLimiter for WasmtimeLimiter<L> {
 
 #[cfg(test)]
 mod tests {
+    use fvm_shared::version::NetworkVersion;
     use wasmtime::ResourceLimiter;
 
-    use crate::engine::WasmtimeLimiter;
+    use crate::engine::{
+        EngineConfig, EnginePool, InstancePoolConfig, InstancePoolError, InstancePoolSettings,
+        WasmtimeLimiter,
+    };
     use crate::machine::limiter::MemoryLimiter;
+    use crate::machine::NetworkConfig;
 
     #[derive(Default)]
     struct Limiter {
@@ -754,4 +1207,53 @@ mod tests {
         assert!(limits.table_growing(2, 4, None).unwrap());
         assert_eq!(limits.0.memory, 5 * 8);
     }
+
+    #[test]
+    fn instance_pool() {
+        let ec = EngineConfig {
+            instance_pool: InstancePoolConfig {
+                instance_count: Some(2000),
+                max_unused_warm_slots: 7,
+                memory_keep_resident: 1 << 16,
+            },
+            ..(&NetworkConfig::new(NetworkVersion::V18)).into()
+        };
+
+        // The options reach the wasmtime pooling config.
+        let settings = InstancePoolSettings::new(&ec).unwrap();
+        assert_eq!(
+            (
+                settings.instance_count,
+                settings.max_unused_warm_slots,
+                settings.memory_keep_resident
+            ),
+            (2000, 7, 1 << 16)
+        );
+        EnginePool::new_default(ec.clone()).unwrap();
+
+        // Fewer slots than a full call stack are rejected.
+        let mut invalid = ec.clone();
+        invalid.instance_pool.instance_count = Some(ec.max_call_depth - 1);
+        let expected = InstancePoolError::TooFewInstances {
+            count: ec.max_call_depth - 1,
+            max_call_depth: ec.max_call_depth,
+        };
+        assert_eq!(InstancePoolSettings::new(&invalid), Err(expected));
+        let err = EnginePool::new_default(invalid).err().unwrap();
+        assert_eq!(err.downcast_ref::<InstancePoolError>(), Some(&expected));
+
+        // As are reservations outside of [max call depth, instance count].
+        for reservation in [ec.max_call_depth - 1, 2001] {
+            let mut invalid = ec.clone();
+            invalid.concurrency_config.instance_reservation = Some(reservation);
+            assert_eq!(
+                InstancePoolSettings::new(&invalid),
+                Err(InstancePoolError::InvalidReservation {
+                    reservation,
+                    max_call_depth: ec.max_call_depth,
+                    count: 2000,
+                })
+            );
+        }
+    }
 }
@@ -1,5 +1,7 @@
 // Copyright 2021-2023 Protocol Labs
 // SPDX-License-Identifier: Apache-2.0, MIT
+#[cfg(feature = "bundles")]
+use std::io::Read;
 use std::ops::{Deref, DerefMut};
 use std::result::Result as StdResult;
 
@@ -7,21 +9,36 @@ use anyhow::{anyhow, Result};
 use cid::Cid;
 use fvm_ipld_encoding::{RawBytes, CBOR};
 use fvm_shared::address::Payload;
+use fvm_shared::clock::ChainEpoch;
+use fvm_shared::crypto::signature::Signature;
 use fvm_shared::econ::TokenAmount;
-use fvm_shared::error::{ErrorNumber, ExitCode};
+use fvm_shared::error::ExitCode;
 use fvm_shared::event::StampedEvent;
-use fvm_shared::message::Message;
+use fvm_shared::message::{Message, MessagePolicy};
 use fvm_shared::receipt::Receipt;
-use fvm_shared::{ActorID, IPLD_RAW, METHOD_SEND};
+use fvm_shared::{ActorID, BLOCK_GAS_LIMIT, IPLD_RAW, METHOD_SEND};
 use num_traits::Zero;
 
-use super::{ApplyFailure, ApplyKind, ApplyRet, Executor};
-use crate::call_manager::{backtrace, Backtrace, CallManager, InvocationResult};
+use super::{
+    ApplyFailure, ApplyKind, ApplyRet, EpochJobs, EstimateOptions, Executor, ExecutorEvents,
+    FeeBreakdown, GasEstimate, MessageStats, NoncePolicy, SenderRejection, SenderValidator,
+    StrictNonces,
+};
+use crate::call_manager::{
+    backtrace, Backtrace, BlockstoreStats, CallManager, DebugArtifact, InvocationResult,
+    OutOfGasCheckpoint,
+};
 use crate::eam_actor::EAM_ACTOR_ID;
-use crate::engine::EnginePool;
-use crate::gas::{Gas, GasCharge, GasOutputs};
+use crate::engine::{EnginePool, ExecutionBudgetExceeded, MultiEngine};
+use crate::gas::{
+    CallGasUsage, FeeDistribution, FilecoinFeeDistribution, FilecoinOverestimationBurn, Gas,
+    GasCharge, GasChargeName, GasOutputs, OverestimationBurnPolicy,
+};
 use crate::kernel::{Block, ClassifyResult, Context as _, ExecutionError, Kernel};
-use crate::machine::{Machine, BURNT_FUNDS_ACTOR_ID, REWARD_ACTOR_ID};
+use crate::machine::Machine;
+#[cfg(feature = "bundles")]
+use crate::machine::Manifest;
+use crate::state_tree::{ActorState, Savepoint};
 use crate::trace::ExecutionTrace;
 
 /// The default [`Executor`].
@@ -32,9 +49,21 @@ use crate::trace::ExecutionTrace;
 /// least 64MiB of stack space. If you can't guarantee 64MiB of stack space, wrap this executor in
 /// a [`ThreadedExecutor`][super::ThreadedExecutor].
 pub struct DefaultExecutor<K: Kernel> {
-    engine_pool: EnginePool,
+    pub(super) engine_pool: EnginePool,
     // If the inner value is `None` it means the machine got poisoned and is unusable.
     machine: Option<<K::CallManager as CallManager>::Machine>,
+    /// Decides how much unused gas is burned when finishing a message.
+    overestimation_burn: Box<dyn OverestimationBurnPolicy>,
+    /// Decides which actors receive the miner tip and burnt funds.
+    fee_distribution: Box<dyn FeeDistribution>,
+    /// Callbacks invoked as messages are executed, if any.
+    events: Option<Box<dyn ExecutorEvents>>,
+    /// Validators for custom sender actor types, consulted in order.
+    sender_validators: Vec<Box<dyn SenderValidator>>,
+    /// Decides which message sequences (nonces) are acceptable.
+    nonce_policy: Box<dyn NoncePolicy>,
+    /// Custom implicit calls applied at the end of every epoch, if any.
+    pub(super) epoch_jobs: Option<Box<dyn EpochJobs>>,
 }
 
 impl<K: Kernel> Deref for DefaultExecutor<K> {
@@ -64,20 +93,280 @@ where
         apply_kind: ApplyKind,
         raw_length: usize,
     ) -> anyhow::Result<ApplyRet> {
+        self.execute(msg, None, apply_kind, raw_length)
+    }
+
+    /// Flush the state-tree to the underlying blockstore.
+    fn flush(&mut self) -> anyhow::Result<Cid> {
+        let k = (**self).flush()?;
+        Ok(k)
+    }
+}
+
+impl<K> DefaultExecutor<K>
+where
+    K: Kernel,
+{
+    /// Create a new [`DefaultExecutor`] for executing messages on the [`Machine`].
+    pub fn new(
+        engine_pool: EnginePool,
+        machine: <K::CallManager as CallManager>::Machine,
+    ) -> anyhow::Result<Self> {
+        if machine.context().execution_budget.is_some() && !engine_pool.config().epoch_interruption
+        {
+            return Err(anyhow!(
+                "the machine has an execution budget, but the engine wasn't configured with epoch interruption"
+            ));
+        }
+
+        // Skip preloading all builtin actors when testing.
+        #[cfg(not(any(test, feature = "testing")))]
+        {
+            // Preload any uncached modules.
+            // This interface works for now because we know all actor CIDs
+            // ahead of time, but with user-supplied code, we won't have that
+            // guarantee.
+            engine_pool.acquire().preload(
+                machine.blockstore(),
+                machine.builtin_actors().builtin_actor_codes(),
+            )?;
+        }
+        Ok(Self {
+            engine_pool,
+            machine: Some(machine),
+            overestimation_burn: Box::new(FilecoinOverestimationBurn),
+            fee_distribution: Box::new(FilecoinFeeDistribution),
+            events: None,
+            sender_validators: Vec::new(),
+            nonce_policy: Box::new(StrictNonces),
+            epoch_jobs: None,
+        })
+    }
+
+    /// Replaces the policy deciding how much unused gas is burned when a message over-estimates its
+    /// gas limit (defaults to [`FilecoinOverestimationBurn`]). This is consensus-critical.
+    pub fn with_overestimation_burn(
+        mut self,
+        policy: impl OverestimationBurnPolicy + 'static,
+    ) -> Self {
+        self.overestimation_burn = Box::new(policy);
+        self
+    }
+
+    /// Replaces the policy deciding which actors receive the miner tip and burnt funds (defaults to
+    /// [`FilecoinFeeDistribution`]). This is consensus-critical.
+    pub fn with_fee_distribution(mut self, distribution: impl FeeDistribution + 'static) -> Self {
+        self.fee_distribution = Box::new(distribution);
+        self
+    }
+
+    /// Invokes the given callbacks as messages are executed. See [`ExecutorEvents`].
+    pub fn with_events(mut self, events: impl ExecutorEvents + 'static) -> Self {
+        self.events = Some(Box::new(events));
+        self
+    }
+
+    /// Checks message sequences (nonces) with the given policy instead of requiring them to be
+    /// sequential. See [`NoncePolicy`].
+    pub fn with_nonce_policy(mut self, policy: impl NoncePolicy + 'static) -> Self {
+        self.nonce_policy = Box::new(policy);
+        self
+    }
+
+    /// Applies the given implicit calls at the end of every epoch, after cron. See [`EpochJobs`].
+    pub fn with_epoch_jobs(mut self, jobs: impl EpochJobs + 'static) -> Self {
+        self.epoch_jobs = Some(Box::new(jobs));
+        self
+    }
+
+    /// Validates messages from senders the given validator is responsible for with that validator
+    /// instead of the builtin account checks. If multiple validators are responsible for the same
+    /// sender, the first one registered wins. See [`SenderValidator`].
+    pub fn with_sender_validator(mut self, validator: impl SenderValidator + 'static) -> Self {
+        self.sender_validators.push(Box::new(validator));
+        self
+    }
+
+    /// Loads a builtin-actors bundle (a CAR file) into the machine's blockstore, compiles its
+    /// actors, and switches the machine over to the bundle's actors, returning the CID of the
+    /// bundle's manifest. See [`Manifest::load_bundle`](crate::machine::Manifest::load_bundle) for
+    /// how the bundle is validated.
+    ///
+    /// This is consensus-critical: it changes which actor code CIDs are considered builtin (e.g.,
+    /// the code of newly created accounts). Actors already deployed are unaffected.
+    #[cfg(feature = "bundles")]
+    pub fn load_bundle(&mut self, reader: impl Read + Send) -> anyhow::Result<Cid> {
+        let (manifest_cid, manifest) =
+            Manifest::load_bundle(self.blockstore(), reader, self.context().network_version)?;
+        self.engine_pool
+            .acquire()
+            .preload(self.blockstore(), manifest.builtin_actor_codes())?;
+        self.set_builtin_actors(manifest)?;
+        Ok(manifest_cid)
+    }
+
+    /// Advances the machine to a later epoch, switching network versions when crossing an upgrade
+    /// in the [`NetworkConfig::fork_schedule`](crate::machine::NetworkConfig::fork_schedule). This
+    /// lets a single executor replay a range of tipsets spanning network upgrades. See
+    /// [`Machine::advance_epoch`].
+    ///
+    /// When the network version changes, the executor switches to an engine from the given
+    /// [`MultiEngine`] matching the new network config, and compiles the new builtin actors.
+    pub fn advance_epoch(
+        &mut self,
+        engines: &MultiEngine,
+        epoch: ChainEpoch,
+        timestamp: u64,
+    ) -> anyhow::Result<()> {
+        let network_version = self.context().network_version;
+        Machine::advance_epoch(&mut **self, epoch, timestamp)?;
+        if self.context().network_version != network_version {
+            self.engine_pool = engines.get_for_context(self.context())?;
+            self.engine_pool.acquire().preload(
+                self.blockstore(),
+                self.builtin_actors().builtin_actor_codes(),
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Creates a state savepoint, e.g., to apply multiple messages speculatively and later revert
+    /// them all with [`DefaultExecutor::rollback`]. See
+    /// [`StateTree::savepoint`](crate::state_tree::StateTree::savepoint).
+    ///
+    /// The state can't be flushed until the savepoint has been released.
+    pub fn savepoint(&mut self) -> Savepoint {
+        self.state_tree_mut().savepoint()
+    }
+
+    /// Keeps all state changes made since the savepoint. See
+    /// [`StateTree::commit`](crate::state_tree::StateTree::commit).
+    pub fn commit(&mut self, savepoint: Savepoint) -> anyhow::Result<()> {
+        self.state_tree_mut().commit(savepoint)?;
+        Ok(())
+    }
+
+    /// Reverts all state changes made since the savepoint. See
+    /// [`StateTree::rollback`](crate::state_tree::StateTree::rollback).
+    pub fn rollback(&mut self, savepoint: Savepoint) -> anyhow::Result<()> {
+        self.state_tree_mut().rollback(savepoint)?;
+        Ok(())
+    }
+
+    /// Estimates the gas required to apply the message, without committing any state changes.
+    ///
+    /// The message is applied as an [`ApplyKind::Estimate`] message with its own gas limit (use the
+    /// block gas limit if unknown) and the sender's current nonce, ignoring the sender's balance
+    /// for gas fees. If the message fails with its own gas limit, no search is performed and the
+    /// failed [`ApplyRet`] is returned in the estimate for inspection.
+    pub fn estimate_gas(
+        &mut self,
+        msg: Message,
+        raw_length: usize,
+        options: &EstimateOptions,
+    ) -> anyhow::Result<GasEstimate> {
+        let apply_ret = self.apply_and_revert(msg.clone(), raw_length)?;
+        let gas_used = apply_ret.msg_receipt.gas_used;
+
+        let mut min_gas_limit = gas_used;
+        if options.search && apply_ret.msg_receipt.exit_code.is_success() {
+            // Invariant: the message succeeds with a gas limit of `hi`.
+            let (mut lo, mut hi) = (gas_used, msg.gas_limit);
+            while lo < hi {
+                let mid = lo + (hi - lo) / 2;
+                let ret = self.apply_and_revert(
+                    Message {
+                        gas_limit: mid,
+                        ..msg.clone()
+                    },
+                    raw_length,
+                )?;
+                if ret.msg_receipt.exit_code.is_success() {
+                    hi = mid;
+                } else {
+                    lo = mid + 1;
+                }
+            }
+            min_gas_limit = hi;
+        }
+
+        let overestimation = min_gas_limit.saturating_mul(options.overestimation_percent) / 100;
+        let suggested_gas_limit = min_gas_limit
+            .saturating_add(overestimation)
+            .min(BLOCK_GAS_LIMIT);
+
+        Ok(GasEstimate {
+            gas_used,
+            min_gas_limit,
+            suggested_gas_limit,
+            apply_ret,
+        })
+    }
+
+    /// Applies the message as an [`ApplyKind::Estimate`] message, reverting all state changes.
+    fn apply_and_revert(&mut self, msg: Message, raw_length: usize) -> anyhow::Result<ApplyRet> {
+        let savepoint = self.savepoint();
+        let ret = self.execute_message(msg, ApplyKind::Estimate, raw_length);
+        self.rollback(savepoint)?;
+        ret
+    }
+
+    /// Executes a message like [`Executor::execute_message`], also verifying the message's
+    /// signature against its sender (see [`DefaultExecutor::verify_message_signature`]) as part of
+    /// sender validation. Messages with invalid signatures fail pre-validation, and the miner is
+    /// penalized as usual.
+    pub fn execute_signed_message(
+        &mut self,
+        msg: Message,
+        signature: &Signature,
+        apply_kind: ApplyKind,
+        raw_length: usize,
+    ) -> anyhow::Result<ApplyRet> {
+        self.execute(msg, Some(signature), apply_kind, raw_length)
+    }
+
+    /// Executes a message, verifying its signature (if given) during pre-validation.
+    fn execute(
+        &mut self,
+        msg: Message,
+        signature: Option<&Signature>,
+        apply_kind: ApplyKind,
+        raw_length: usize,
+    ) -> anyhow::Result<ApplyRet> {
+        #[cfg(feature = "metrics")]
+        let _timer = crate::metrics::message_timer(apply_kind);
+
+        // Acquire an engine from the pool before touching the state, so we can give up if none
+        // becomes available in time. This may block if there are concurrently executing messages
+        // inside other executors sharing the same pool.
+        let engine = self.engine_pool.try_acquire()?;
+
+        if let Some(events) = &self.events {
+            events.message_started(&msg, apply_kind);
+        }
+
         // Validate if the message was correct, charge for it, and extract some preliminary data.
-        let (sender_id, gas_cost, inclusion_cost) =
-            match self.preflight_message(&msg, apply_kind, raw_length)? {
+        let (sender_id, payer_id, gas_cost, inclusion_cost) =
+            match self.preflight_message(&msg, signature, apply_kind, raw_length)? {
                 Ok(res) => res,
-                Err(apply_ret) => return Ok(apply_ret),
+                Err(apply_ret) => {
+                    self.message_finished(&apply_ret, apply_kind, 0, BlockstoreStats::default());
+                    return Ok(apply_ret);
+                }
             };
 
         struct MachineExecRet {
             result: crate::kernel::error::Result<InvocationResult>,
             gas_used: u64,
+            call_gas: Option<CallGasUsage>,
             backtrace: Backtrace,
+            out_of_gas: Option<OutOfGasCheckpoint>,
             exec_trace: ExecutionTrace,
             events_root: Option<Cid>,
             events: Vec<StampedEvent>, // TODO consider removing if nothing in the client ends up using it.
+            artifacts: Vec<DebugArtifact>,
+            max_call_depth: u32,
+            blockstore_stats: BlockstoreStats,
         }
 
         // Pre-resolve the message receiver's address, if known.
@@ -94,9 +383,11 @@ where
             .min(&msg.gas_fee_cap - &self.context().base_fee)
             .max(TokenAmount::zero());
 
-        // Acquire an engine from the pool. This may block if there are concurrently executing
-        // messages inside other executors sharing the same pool.
-        let engine = self.engine_pool.acquire();
+        // Queries are unmetered, so they ignore the message's gas limit.
+        let gas_limit = match apply_kind {
+            ApplyKind::Query => u64::MAX,
+            _ => msg.gas_limit,
+        };
 
         // Apply the message.
         let ret = self.map_machine(|machine| {
@@ -104,7 +395,7 @@ where
             let mut cm = K::CallManager::new(
                 machine,
                 engine,
-                msg.gas_limit,
+                gas_limit,
                 sender_id,
                 msg.from,
                 receiver_id,
@@ -148,7 +439,7 @@ where
                 params,
                 &msg.value,
                 None,
-                false,
+                apply_kind == ApplyKind::Query,
             );
 
             let (res, machine) = match cm.finish() {
@@ -160,10 +451,15 @@ where
                 Ok(MachineExecRet {
                     result,
                     gas_used: res.gas_used,
+                    call_gas: res.call_gas.into_iter().next(),
                     backtrace: res.backtrace,
+                    out_of_gas: res.out_of_gas,
                     exec_trace: res.exec_trace,
                     events_root: res.events_root,
                     events: res.events,
+                    artifacts: res.artifacts,
+                    max_call_depth: res.max_call_depth,
+                    blockstore_stats: res.blockstore_stats,
                 }),
                 machine,
             )
@@ -172,13 +468,19 @@ where
         let MachineExecRet {
             result: res,
             gas_used,
+            call_gas,
             mut backtrace,
+            out_of_gas,
             exec_trace,
             events_root,
             events,
+            artifacts,
+            max_call_depth,
+            blockstore_stats,
         } = ret;
 
         // Extract the exit code and build the result of the message application.
+        let policy = self.context().exit_code_policy.clone();
         let receipt = match res {
             Ok(InvocationResult { exit_code, value }) => {
                 // Convert back into a top-level return "value". We throw away the codec here,
@@ -198,7 +500,7 @@ where
                 }
             }
             Err(ExecutionError::OutOfGas) => Receipt {
-                exit_code: ExitCode::SYS_OUT_OF_GAS,
+                exit_code: policy.out_of_gas(),
                 return_data: Default::default(),
                 gas_used,
                 events_root,
@@ -207,11 +509,7 @@ where
                 // Errors indicate the message couldn't be dispatched at all
                 // (as opposed to failing during execution of the receiving actor).
                 // These errors are mapped to exit codes that persist on chain.
-                let exit_code = match err.1 {
-                    ErrorNumber::InsufficientFunds => ExitCode::SYS_INSUFFICIENT_FUNDS,
-                    ErrorNumber::NotFound => ExitCode::SYS_INVALID_RECEIVER,
-                    _ => ExitCode::SYS_ASSERTION_FAILED,
-                };
+                let exit_code = policy.send_error(err.1);
 
                 backtrace.begin(backtrace::Cause::from_syscall("send", "send", err));
                 Receipt {
@@ -221,6 +519,13 @@ where
                     events_root,
                 }
             }
+            Err(ExecutionError::Fatal(err))
+                if err.downcast_ref::<ExecutionBudgetExceeded>().is_some() =>
+            {
+                // Running out of time isn't deterministic, so we can't produce a receipt. Instead,
+                // we return the error and let the caller discard the machine.
+                return Err(err);
+            }
             Err(ExecutionError::Fatal(err)) => {
                 // We produce a receipt with SYS_ASSERTION_FAILED exit code, and
                 // we consume the full gas amount so that, in case of a network-
@@ -240,7 +545,7 @@ where
                 ));
                 backtrace.set_cause(backtrace::Cause::from_fatal(err));
                 Receipt {
-                    exit_code: ExitCode::SYS_ASSERTION_FAILED,
+                    exit_code: policy.fatal(),
                     return_data: Default::default(),
                     gas_used: msg.gas_limit,
                     events_root,
@@ -254,18 +559,23 @@ where
             Some(ApplyFailure::MessageBacktrace(backtrace))
         };
 
-        match apply_kind {
+        // Only report where we ran out of gas if that's why the message failed.
+        let out_of_gas = out_of_gas.filter(|_| receipt.exit_code == policy.out_of_gas());
+
+        let ret = match apply_kind {
             ApplyKind::Explicit => self.finish_message(
-                sender_id,
+                payer_id,
                 msg,
                 receipt,
                 failure_info,
+                out_of_gas,
                 gas_cost,
                 exec_trace,
+                call_gas,
                 events,
-            ),
-            ApplyKind::Implicit => Ok(ApplyRet {
-                msg_receipt: receipt,
+                artifacts,
+            )?,
+            ApplyKind::Implicit | ApplyKind::Estimate | ApplyKind::Query => ApplyRet {
                 penalty: TokenAmount::zero(),
                 miner_tip: TokenAmount::zero(),
                 base_fee_burn: TokenAmount::zero(),
@@ -273,45 +583,18 @@ where
                 refund: TokenAmount::zero(),
                 gas_refund: 0,
                 gas_burned: 0,
+                fees: FeeBreakdown::unpaid(msg.gas_limit, receipt.gas_used),
+                msg_receipt: receipt,
                 failure_info,
+                out_of_gas,
                 exec_trace,
+                call_gas,
                 events,
-            }),
-        }
-    }
-
-    /// Flush the state-tree to the underlying blockstore.
-    fn flush(&mut self) -> anyhow::Result<Cid> {
-        let k = (**self).flush()?;
-        Ok(k)
-    }
-}
-
-impl<K> DefaultExecutor<K>
-where
-    K: Kernel,
-{
-    /// Create a new [`DefaultExecutor`] for executing messages on the [`Machine`].
-    pub fn new(
-        engine_pool: EnginePool,
-        machine: <K::CallManager as CallManager>::Machine,
-    ) -> anyhow::Result<Self> {
-        // Skip preloading all builtin actors when testing.
-        #[cfg(not(any(test, feature = "testing")))]
-        {
-            // Preload any uncached modules.
-            // This interface works for now because we know all actor CIDs
-            // ahead of time, but with user-supplied code, we won't have that
-            // guarantee.
-            engine_pool.acquire().preload(
-                machine.blockstore(),
-                machine.builtin_actors().builtin_actor_codes(),
-            )?;
-        }
-        Ok(Self {
-            engine_pool,
-            machine: Some(machine),
-        })
+                artifacts,
+            },
+        };
+        self.message_finished(&ret, apply_kind, max_call_depth, blockstore_stats);
+        Ok(ret)
     }
 
     /// Consume consumes the executor and returns the Machine. If the Machine had
@@ -328,29 +611,41 @@ where
     fn preflight_message(
         &mut self,
         msg: &Message,
+        signature: Option<&Signature>,
         apply_kind: ApplyKind,
         raw_length: usize,
-    ) -> Result<StdResult<(ActorID, TokenAmount, GasCharge), ApplyRet>> {
-        msg.check().or_fatal()?;
+    ) -> Result<StdResult<(ActorID, ActorID, TokenAmount, GasCharge), ApplyRet>> {
+        msg.check(self.context().network_version, &MessagePolicy::default())
+            .or_fatal()?;
 
         // TODO We don't like having price lists _inside_ the FVM, but passing
         //  these across the boundary is also a no-go.
         let pl = &self.context().price_list;
 
+        if apply_kind == ApplyKind::Query && !msg.value.is_zero() {
+            return Ok(Err(ApplyRet::prevalidation_fail(
+                ExitCode::USR_READ_ONLY,
+                "cannot transfer value in a read-only query",
+                msg.gas_limit,
+                TokenAmount::zero(),
+            )));
+        }
+
         let (inclusion_cost, miner_penalty_amount) = match apply_kind {
-            ApplyKind::Implicit => (
-                GasCharge::new("none", Gas::zero(), Gas::zero()),
+            ApplyKind::Implicit | ApplyKind::Query => (
+                GasCharge::new(GasChargeName::Empty, Gas::zero(), Gas::zero()),
                 Default::default(),
             ),
-            ApplyKind::Explicit => {
+            ApplyKind::Explicit | ApplyKind::Estimate => {
                 let inclusion_cost = pl.on_chain_message(raw_length);
                 let inclusion_total = inclusion_cost.total().round_up();
 
                 // Verify the cost of the message is not over the message gas limit.
                 if inclusion_total > msg.gas_limit {
                     return Ok(Err(ApplyRet::prevalidation_fail(
-                        ExitCode::SYS_OUT_OF_GAS,
+                        self.context().exit_code_policy.out_of_gas(),
                         format!("Out of gas ({} > {})", inclusion_total, msg.gas_limit),
+                        msg.gas_limit,
                         &self.context().base_fee * inclusion_total,
                     )));
                 }
@@ -369,15 +664,21 @@ where
             Some(id) => id,
             None => {
                 return Ok(Err(ApplyRet::prevalidation_fail(
-                    ExitCode::SYS_SENDER_INVALID,
+                    self.context().exit_code_policy.sender_invalid(),
                     "Sender invalid",
+                    msg.gas_limit,
                     miner_penalty_amount,
                 )));
             }
         };
 
-        if apply_kind == ApplyKind::Implicit {
-            return Ok(Ok((sender_id, TokenAmount::zero(), inclusion_cost)));
+        if matches!(apply_kind, ApplyKind::Implicit | ApplyKind::Query) {
+            return Ok(Ok((
+                sender_id,
+                sender_id,
+                TokenAmount::zero(),
+                inclusion_cost,
+            )));
         }
 
         let mut sender_state = match self
@@ -388,13 +689,112 @@ where
             Some(act) => act,
             None => {
                 return Ok(Err(ApplyRet::prevalidation_fail(
-                    ExitCode::SYS_SENDER_INVALID,
+                    self.context().exit_code_policy.sender_invalid(),
                     "Sender invalid",
+                    msg.gas_limit,
+                    miner_penalty_amount,
+                )));
+            }
+        };
+
+        if let Some(signature) = signature {
+            if let Err(rejection) = self.verify_message_signature(msg, signature)? {
+                return Ok(Err(ApplyRet::prevalidation_fail(
+                    rejection.exit_code,
+                    rejection.message,
+                    msg.gas_limit,
+                    miner_penalty_amount,
+                )));
+            }
+        }
+
+        let validator = self
+            .sender_validators
+            .iter()
+            .find(|v| v.handles(&sender_state.code));
+        let validated = match validator {
+            Some(validator) => validator.validate(msg, apply_kind, sender_id, &mut sender_state),
+            None => self
+                .validate_sender(msg, apply_kind, &mut sender_state)
+                .map(|_| sender_id),
+        };
+        let payer_id = match validated {
+            Ok(payer_id) => payer_id,
+            Err(rejection) => {
+                return Ok(Err(ApplyRet::prevalidation_fail(
+                    rejection.exit_code,
+                    rejection.message,
+                    msg.gas_limit,
                     miner_penalty_amount,
                 )));
             }
         };
 
+        // When estimating, we don't charge for gas so the payer's balance doesn't matter.
+        if apply_kind == ApplyKind::Estimate {
+            self.state_tree_mut().set_actor(sender_id, sender_state);
+            return Ok(Ok((
+                sender_id,
+                payer_id,
+                TokenAmount::zero(),
+                inclusion_cost,
+            )));
+        }
+
+        let mut payer_state = if payer_id == sender_id {
+            None
+        } else {
+            match self
+                .state_tree()
+                .get_actor(payer_id)
+                .with_context(|| format!("failed to lookup payer {}", payer_id))?
+            {
+                Some(act) => Some(act),
+                None => {
+                    return Ok(Err(ApplyRet::prevalidation_fail(
+                        self.context().exit_code_policy.sender_invalid(),
+                        "Payer invalid",
+                        msg.gas_limit,
+                        miner_penalty_amount,
+                    )));
+                }
+            }
+        };
+
+        // Ensure the payer has enough balance to cover the gas cost of the message.
+        let gas_cost: TokenAmount = msg.gas_fee_cap.clone() * msg.gas_limit;
+        let payer = payer_state.as_mut().unwrap_or(&mut sender_state);
+        if payer.balance < gas_cost {
+            return Ok(Err(ApplyRet::prevalidation_fail(
+                self.context().exit_code_policy.sender_state_invalid(),
+                format!(
+                    "Actor balance less than needed: {} < {}",
+                    payer.balance, gas_cost
+                ),
+                msg.gas_limit,
+                miner_penalty_amount,
+            )));
+        }
+
+        payer.deduct_funds(&gas_cost)?;
+
+        // Update the actors in the state tree
+        self.state_tree_mut().set_actor(sender_id, sender_state);
+        if let Some(payer_state) = payer_state {
+            self.state_tree_mut().set_actor(payer_id, payer_state);
+        }
+
+        Ok(Ok((sender_id, payer_id, gas_cost, inclusion_cost)))
+    }
+
+    /// Validates a sender no [`SenderValidator`] is responsible for, advancing its sequence
+    /// according to the [`NoncePolicy`].
+    fn validate_sender(
+        &self,
+        msg: &Message,
+        apply_kind: ApplyKind,
+        sender_state: &mut ActorState,
+    ) -> StdResult<(), SenderRejection> {
         // Sender is valid if it is:
         // - an account actor
         // - an Ethereum Externally Owned Address
@@ -416,58 +816,50 @@ where
         }
 
         if !sender_is_valid {
-            return Ok(Err(ApplyRet::prevalidation_fail(
-                ExitCode::SYS_SENDER_INVALID,
+            return Err(SenderRejection::new(
+                self.context().exit_code_policy.sender_invalid(),
                 "Send not from valid sender",
-                miner_penalty_amount,
-            )));
-        };
-
-        // Check sequence is correct
-        if msg.sequence != sender_state.sequence {
-            return Ok(Err(ApplyRet::prevalidation_fail(
-                ExitCode::SYS_SENDER_STATE_INVALID,
-                format!(
-                    "Actor sequence invalid: {} != {}",
-                    msg.sequence, sender_state.sequence
-                ),
-                miner_penalty_amount,
-            )));
+            ));
         };
 
-        sender_state.sequence += 1;
+        sender_state.sequence =
+            self.nonce_policy
+                .advance(apply_kind, msg.sequence, sender_state.sequence)?;
+        Ok(())
+    }
 
-        // Ensure from actor has enough balance to cover the gas cost of the message.
-        let gas_cost: TokenAmount = msg.gas_fee_cap.clone() * msg.gas_limit;
-        if sender_state.balance < gas_cost {
-            return Ok(Err(ApplyRet::prevalidation_fail(
-                ExitCode::SYS_SENDER_STATE_INVALID,
-                format!(
-                    "Actor balance less than needed: {} < {}",
-                    sender_state.balance, gas_cost
-                ),
-                miner_penalty_amount,
-            )));
+    /// Reports a finished message to the [`ExecutorEvents`], if any.
+    fn message_finished(
+        &self,
+        ret: &ApplyRet,
+        apply_kind: ApplyKind,
+        max_call_depth: u32,
+        blockstore: BlockstoreStats,
+    ) {
+        if let Some(events) = &self.events {
+            events.message_finished(&MessageStats {
+                apply_kind,
+                gas_used: ret.msg_receipt.gas_used,
+                exit_code: ret.msg_receipt.exit_code,
+                max_call_depth,
+                blockstore,
+            });
         }
-
-        sender_state.deduct_funds(&gas_cost)?;
-
-        // Update the actor in the state tree
-        self.state_tree_mut().set_actor(sender_id, sender_state);
-
-        Ok(Ok((sender_id, gas_cost, inclusion_cost)))
     }
 
     #[allow(clippy::too_many_arguments)]
     fn finish_message(
         &mut self,
-        sender_id: ActorID,
+        payer_id: ActorID,
         msg: Message,
         receipt: Receipt,
         failure_info: Option<ApplyFailure>,
+        out_of_gas: Option<OutOfGasCheckpoint>,
         gas_cost: TokenAmount,
         exec_trace: ExecutionTrace,
+        call_gas: Option<CallGasUsage>,
         events: Vec<StampedEvent>,
+        artifacts: Vec<DebugArtifact>,
     ) -> anyhow::Result<ApplyRet> {
         // NOTE: we don't support old network versions in the FVM, so we always burn.
         let GasOutputs {
@@ -484,8 +876,14 @@ where
             &self.context().base_fee,
             &msg.gas_fee_cap,
             &msg.gas_premium,
+            &*self.overestimation_burn,
         );
 
+        let epoch = self.context().epoch;
+        let base_fee_burn_to = self.fee_distribution.burn(epoch, &base_fee_burn);
+        let miner_tip_to = self.fee_distribution.miner_tip(epoch, &miner_tip);
+        let over_estimation_burn_to = self.fee_distribution.burn(epoch, &over_estimation_burn);
+
         let mut transfer_to_actor = |addr: ActorID, amt: &TokenAmount| -> anyhow::Result<()> {
             if amt.is_negative() {
                 return Err(anyhow!("attempted to transfer negative value into actor"));
@@ -503,16 +901,44 @@ where
             Ok(())
         };
 
-        transfer_to_actor(BURNT_FUNDS_ACTOR_ID, &base_fee_burn)?;
-
-        transfer_to_actor(REWARD_ACTOR_ID, &miner_tip)?;
+        let distributions = [
+            (&base_fee_burn, base_fee_burn_to),
+            (&miner_tip, miner_tip_to),
+            (&over_estimation_burn, over_estimation_burn_to),
+        ];
+
+        // Check every distribution before transferring anything, so a bad distribution can't
+        // leave partially transferred fees behind.
+        for (amt, to) in &distributions {
+            if to.iter().map(|(_, a)| a).sum::<TokenAmount>() != **amt {
+                return Err(anyhow!("fee distribution doesn't add up to {}", amt));
+            }
+            if to.iter().any(|(_, a)| a.is_negative()) {
+                return Err(anyhow!("fee distribution of {} has negative shares", amt));
+            }
+        }
 
-        transfer_to_actor(BURNT_FUNDS_ACTOR_ID, &over_estimation_burn)?;
+        for (_, to) in &distributions {
+            to.iter()
+                .try_for_each(|(addr, amt)| transfer_to_actor(*addr, amt))?;
+        }
 
         // refund unused gas
-        transfer_to_actor(sender_id, &refund)?;
+        transfer_to_actor(payer_id, &refund)?;
+
+        let fees = FeeBreakdown {
+            gas_limit: msg.gas_limit,
+            gas_used: receipt.gas_used,
+            gas_refund,
+            gas_burned,
+            base_fee_burn: base_fee_burn.clone(),
+            over_estimation_burn: over_estimation_burn.clone(),
+            miner_penalty: miner_penalty.clone(),
+            miner_tip: miner_tip.clone(),
+            refund: refund.clone(),
+        };
 
-        if (&base_fee_burn + &over_estimation_burn + &refund + &miner_tip) != gas_cost {
+        if fees.total_paid() + &fees.refund != gas_cost {
             // Sanity check. This could be a fatal error.
             return Err(anyhow!("Gas handling math is wrong"));
         }
@@ -525,9 +951,13 @@ where
             refund,
             gas_refund,
             gas_burned,
+            fees,
             failure_info,
+            out_of_gas,
             exec_trace,
+            call_gas,
             events,
+            artifacts,
         })
     }
 
@@ -0,0 +1,95 @@
+// Copyright 2021-2023 Protocol Labs
+// SPDX-License-Identifier: Apache-2.0, MIT
+use std::collections::BTreeMap;
+
+use anyhow::anyhow;
+use cid::Cid;
+use fvm_shared::address::Address;
+use fvm_shared::econ::TokenAmount;
+use fvm_shared::message::Message;
+
+use super::{ApplyKind, ApplyRet, DefaultExecutor, Executor};
+use crate::kernel::Context as _;
+use crate::machine::Machine;
+use crate::Kernel;
+
+/// Overrides applied to an existing actor for the duration of a dry run. Unset fields are left
+/// unchanged.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ActorOverride {
+    /// The actor's balance.
+    pub balance: Option<TokenAmount>,
+    /// The actor's sequence (nonce).
+    pub sequence: Option<u64>,
+    /// The actor's code CID. The code must be in the machine's blockstore.
+    pub code: Option<Cid>,
+    /// The actor's state root. To override specific state fields, write the modified state to the
+    /// machine's blockstore and set its root here.
+    pub state: Option<Cid>,
+}
+
+/// Actor overrides for a dry run, by actor address.
+pub type StateOverrides = BTreeMap<Address, ActorOverride>;
+
+impl<K> DefaultExecutor<K>
+where
+    K: Kernel,
+{
+    /// Applies the message on top of the current state with the given actor overrides, then
+    /// reverts all state changes (including the overrides), returning the full [`ApplyRet`]. This
+    /// is intended for `eth_call`-style queries.
+    ///
+    /// Use [`ApplyKind::Estimate`] to skip the sender's balance checks (and gas payment), or
+    /// [`ApplyKind::Query`] to additionally forbid state mutations.
+    ///
+    /// Fails if any of the overridden actors doesn't exist.
+    pub fn dry_run(
+        &mut self,
+        msg: Message,
+        apply_kind: ApplyKind,
+        raw_length: usize,
+        overrides: &StateOverrides,
+    ) -> anyhow::Result<ApplyRet> {
+        // Make sure overridden code is loaded before we try to execute it.
+        self.engine_pool.acquire().preload(
+            self.blockstore(),
+            overrides.values().filter_map(|o| o.code.as_ref()),
+        )?;
+
+        let savepoint = self.savepoint();
+        let ret = self
+            .apply_overrides(overrides)
+            .and_then(|_| self.execute_message(msg, apply_kind, raw_length));
+        self.rollback(savepoint)?;
+        ret
+    }
+
+    fn apply_overrides(&mut self, overrides: &StateOverrides) -> anyhow::Result<()> {
+        for (addr, o) in overrides {
+            let id = self
+                .state_tree()
+                .lookup_id(addr)
+                .context("failed to look up overridden actor")?
+                .ok_or_else(|| anyhow!("cannot override non-existent actor {}", addr))?;
+            let mut actor = self
+                .state_tree()
+                .get_actor(id)
+                .context("failed to load overridden actor")?
+                .ok_or_else(|| anyhow!("cannot override non-existent actor {}", addr))?;
+            if let Some(balance) = &o.balance {
+                actor.balance = balance.clone();
+            }
+            if let Some(sequence) = o.sequence {
+                actor.sequence = sequence;
+            }
+            if let Some(code) = o.code {
+                actor.code = code;
+            }
+            if let Some(state) = o.state {
+                actor.state = state;
+            }
+            self.state_tree_mut().set_actor(id, actor);
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,212 @@
+// Copyright 2021-2023 Protocol Labs
+// SPDX-License-Identifier: Apache-2.0, MIT
+//! The Ethereum transactions behind delegated-signed messages.
+//!
+//! Like Lotus, we treat a delegated-signed message as the EIP-1559 transaction it was translated
+//! from: the signature is over the keccak256 hash of the transaction's unsigned, RLP-encoded form.
+use fvm_ipld_encoding::{from_slice, BytesDe};
+use fvm_shared::address::{Address, EthAddress};
+use fvm_shared::chainid::ChainID;
+use fvm_shared::econ::TokenAmount;
+use fvm_shared::message::Message;
+use fvm_shared::{method_hash, MethodNum};
+
+use crate::eam_actor::EAM_ACTOR_ID;
+
+/// The EAM method creating a contract from an Ethereum transaction without a recipient.
+const EAM_CREATE_EXTERNAL: MethodNum = 4;
+
+/// The EVM method invoked by Ethereum transactions with a recipient.
+const EVM_INVOKE_CONTRACT: MethodNum = method_hash!("InvokeEVM");
+
+/// The EIP-2718 type of EIP-1559 transactions, prefixing their encoding.
+const EIP1559_TX_TYPE: u8 = 0x02;
+
+/// Returns the bytes a delegated signature of the message signs: the type-prefixed, RLP-encoded
+/// unsigned EIP-1559 transaction the message was translated from. Fails if the message can't be
+/// expressed as an Ethereum transaction.
+pub(super) fn signing_bytes(msg: &Message, chain_id: ChainID) -> Result<Vec<u8>, String> {
+    if msg.version != 0 {
+        return Err(format!("unsupported message version {}", msg.version));
+    }
+
+    // The transaction's input is passed to the EAM or EVM as a CBOR byte string.
+    let input = if msg.params.is_empty() {
+        Vec::new()
+    } else {
+        from_slice::<BytesDe>(&msg.params)
+            .map_err(|e| format!("parameters aren't an Ethereum transaction's input: {}", e))?
+            .into_vec()
+    };
+
+    let to = if msg.to == Address::new_id(EAM_ACTOR_ID) {
+        if msg.method_num != EAM_CREATE_EXTERNAL {
+            return Err(format!(
+                "method {} of the EAM can't be called by an Ethereum transaction",
+                msg.method_num
+            ));
+        }
+        None
+    } else if msg.method_num == EVM_INVOKE_CONTRACT {
+        let to = EthAddress::from_address(&msg.to)
+            .map_err(|e| format!("recipient {} isn't an Ethereum address: {}", msg.to, e))?;
+        Some(to)
+    } else {
+        return Err(format!(
+            "method {} can't be called by an Ethereum transaction",
+            msg.method_num
+        ));
+    };
+
+    let fields = [
+        rlp_uint(chain_id.into()),
+        rlp_uint(msg.sequence),
+        rlp_amount(&msg.gas_premium)?,
+        rlp_amount(&msg.gas_fee_cap)?,
+        rlp_uint(msg.gas_limit),
+        rlp_bytes(to.as_ref().map(|to| &to.0[..]).unwrap_or_default()),
+        rlp_amount(&msg.value)?,
+        rlp_bytes(&input),
+        // The access list is always empty.
+        rlp_list(&[]),
+    ];
+    let mut out = vec![EIP1559_TX_TYPE];
+    out.extend(rlp_list(&fields));
+    Ok(out)
+}
+
+/// RLP-encodes a byte string.
+fn rlp_bytes(data: &[u8]) -> Vec<u8> {
+    match data {
+        [b] if *b < 0x80 => vec![*b],
+        _ => rlp_with_header(0x80, data),
+    }
+}
+
+/// RLP-encodes a list of already encoded items.
+fn rlp_list(items: &[Vec<u8>]) -> Vec<u8> {
+    rlp_with_header(0xc0, &items.concat())
+}
+
+/// RLP-encodes an integer, as its minimal big-endian bytes.
+fn rlp_uint(value: u64) -> Vec<u8> {
+    rlp_bytes(trim_leading_zeros(&value.to_be_bytes()))
+}
+
+/// RLP-encodes a (non-negative) token amount in attoFIL, as its minimal big-endian bytes.
+fn rlp_amount(amount: &TokenAmount) -> Result<Vec<u8>, String> {
+    if amount.is_negative() {
+        return Err(format!("negative amount {}", amount));
+    }
+    let (_, bytes) = amount.atto().to_bytes_be();
+    Ok(rlp_bytes(trim_leading_zeros(&bytes)))
+}
+
+/// Prefixes the payload with its RLP header, for byte strings (offset `0x80`) or lists (`0xc0`).
+fn rlp_with_header(offset: u8, payload: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(payload.len() + 9);
+    if payload.len() <= 55 {
+        out.push(offset + payload.len() as u8);
+    } else {
+        let len = (payload.len() as u64).to_be_bytes();
+        let len = trim_leading_zeros(&len);
+        out.push(offset + 55 + len.len() as u8);
+        out.extend_from_slice(len);
+    }
+    out.extend_from_slice(payload);
+    out
+}
+
+fn trim_leading_zeros(bytes: &[u8]) -> &[u8] {
+    let start = bytes.iter().position(|&b| b != 0).unwrap_or(bytes.len());
+    &bytes[start..]
+}
+
+#[cfg(test)]
+mod tests {
+    use fvm_ipld_encoding::{BytesSer, RawBytes};
+
+    use super::*;
+
+    #[test]
+    fn rlp() {
+        // Examples from the Ethereum wiki.
+        assert_eq!(rlp_bytes(b"dog"), [0x83, b'd', b'o', b'g']);
+        assert_eq!(rlp_bytes(&[]), [0x80]);
+        assert_eq!(rlp_uint(0), [0x80]);
+        assert_eq!(rlp_uint(15), [0x0f]);
+        assert_eq!(rlp_uint(1024), [0x82, 0x04, 0x00]);
+        assert_eq!(rlp_list(&[]), [0xc0]);
+        assert_eq!(
+            rlp_list(&[rlp_bytes(b"cat"), rlp_bytes(b"dog")]),
+            [0xc8, 0x83, b'c', b'a', b't', 0x83, b'd', b'o', b'g']
+        );
+        let lorem = b"Lorem ipsum dolor sit amet, consectetur adipisicing elit";
+        let encoded = rlp_bytes(lorem);
+        assert_eq!(encoded[..2], [0xb8, 0x38]);
+        assert_eq!(encoded[2..], lorem[..]);
+    }
+
+    #[test]
+    fn eth_transactions() {
+        let msg = Message {
+            version: 0,
+            from: Address::new_delegated(EAM_ACTOR_ID, &[1; 20]).unwrap(),
+            to: Address::new_id(1234),
+            sequence: 1,
+            value: TokenAmount::from_atto(1000),
+            method_num: EVM_INVOKE_CONTRACT,
+            params: RawBytes::serialize(BytesSer(&[0xaa, 0xbb])).unwrap(),
+            gas_limit: 0x10000,
+            gas_fee_cap: TokenAmount::from_atto(0x300),
+            gas_premium: TokenAmount::from_atto(0x200),
+        };
+        let chain_id = ChainID::from(314);
+        let mut expected = vec![EIP1559_TX_TYPE, 0xea, 0x82, 0x01, 0x3a, 0x01];
+        expected.extend([0x82, 0x02, 0x00, 0x82, 0x03, 0x00, 0x83, 0x01, 0x00, 0x00]);
+        // The masked ID address of the recipient.
+        expected.extend([0x94, 0xff]);
+        expected.extend([0; 17]);
+        expected.extend([0x04, 0xd2]);
+        expected.extend([0x82, 0x03, 0xe8, 0x82, 0xaa, 0xbb, 0xc0]);
+        assert_eq!(signing_bytes(&msg, chain_id).unwrap(), expected);
+
+        // Contract creations are sent to the EAM, without a recipient.
+        let create = Message {
+            to: Address::new_id(EAM_ACTOR_ID),
+            method_num: EAM_CREATE_EXTERNAL,
+            ..msg.clone()
+        };
+        let mut expected_create = vec![EIP1559_TX_TYPE, 0xd6];
+        expected_create.extend(&expected[2..16]);
+        expected_create.push(0x80);
+        expected_create.extend(&expected[37..]);
+        assert_eq!(signing_bytes(&create, chain_id).unwrap(), expected_create);
+
+        // Other messages aren't Ethereum transactions.
+        for msg in [
+            Message {
+                method_num: 2,
+                ..msg.clone()
+            },
+            Message {
+                to: Address::new_id(EAM_ACTOR_ID),
+                ..msg.clone()
+            },
+            Message {
+                to: Address::new_secp256k1(&[2; 65]).unwrap(),
+                ..msg.clone()
+            },
+            Message {
+                params: RawBytes::new(vec![0xaa]),
+                ..msg.clone()
+            },
+            Message {
+                version: 1,
+                ..msg.clone()
+            },
+        ] {
+            assert!(signing_bytes(&msg, chain_id).is_err());
+        }
+    }
+}
@@ -0,0 +1,41 @@
+// Copyright 2021-2023 Protocol Labs
+// SPDX-License-Identifier: Apache-2.0, MIT
+use fvm_shared::error::ExitCode;
+use fvm_shared::message::Message;
+
+use super::ApplyKind;
+use crate::call_manager::BlockstoreStats;
+
+/// Statistics about a message's execution, reported to [`ExecutorEvents::message_finished`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MessageStats {
+    /// How the message was applied.
+    pub apply_kind: ApplyKind,
+    /// The gas used by the message.
+    pub gas_used: u64,
+    /// The message's exit code.
+    pub exit_code: ExitCode,
+    /// The maximum call depth reached while executing the message (0 if the message failed
+    /// validation and wasn't executed).
+    pub max_call_depth: u32,
+    /// The blocks read from and written to the blockstore by actors while executing the message.
+    pub blockstore: BlockstoreStats,
+}
+
+/// Callbacks invoked by the [`DefaultExecutor`](super::DefaultExecutor) as it executes messages,
+/// e.g., to record metrics or logs. Set them with
+/// [`DefaultExecutor::with_events`](super::DefaultExecutor::with_events).
+///
+/// All methods do nothing by default.
+pub trait ExecutorEvents: Send + Sync {
+    /// Called before a message is validated and executed.
+    fn message_started(&self, msg: &Message, apply_kind: ApplyKind) {
+        let _ = (msg, apply_kind);
+    }
+
+    /// Called once a message has been applied. This isn't called if applying the message fails
+    /// with an error (in which case the executor should be discarded).
+    fn message_finished(&self, stats: &MessageStats) {
+        let _ = stats;
+    }
+}
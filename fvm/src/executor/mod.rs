@@ -1,22 +1,40 @@
 // Copyright 2021-2023 Protocol Labs
 // SPDX-License-Identifier: Apache-2.0, MIT
 mod default;
+mod dry_run;
+mod eth_tx;
+mod events;
+mod nonce;
+mod prefetch;
+mod sender;
+mod speculative;
 mod threaded;
+mod tipset;
 
 use std::fmt::Display;
+use std::path::Path;
 
 use cid::Cid;
 pub use default::DefaultExecutor;
+pub use dry_run::{ActorOverride, StateOverrides};
+pub use events::{ExecutorEvents, MessageStats};
 use fvm_ipld_encoding::RawBytes;
 use fvm_shared::econ::TokenAmount;
 use fvm_shared::error::ExitCode;
 use fvm_shared::event::StampedEvent;
 use fvm_shared::message::Message;
-use fvm_shared::receipt::Receipt;
+use fvm_shared::receipt::{ExtendedReceipt, GasBreakdown, Receipt};
+pub use nonce::{DisabledNonces, GapTolerantNonces, NoncePolicy, StrictNonces};
 use num_traits::Zero;
+pub use sender::{SenderRejection, SenderValidator};
+pub use speculative::SpeculativeCache;
 pub use threaded::ThreadedExecutor;
+pub use tipset::{
+    BlockResult, ChainMessage, EpochJobs, ImplicitCall, TipsetBlock, TipsetResult, CRON_ACTOR_ID,
+};
 
-use crate::call_manager::Backtrace;
+use crate::call_manager::{Backtrace, DebugArtifact, OutOfGasCheckpoint};
+use crate::gas::CallGasUsage;
 use crate::trace::ExecutionTrace;
 use crate::Kernel;
 
@@ -86,13 +104,24 @@ pub struct ApplyRet {
     pub refund: TokenAmount,
     pub gas_refund: u64,
     pub gas_burned: u64,
+    /// The gas fees charged for the message, as computed by the executor. Duplicates the fee
+    /// fields above, along with the gas limit and gas used.
+    pub fees: FeeBreakdown,
 
     /// Additional failure information for debugging, if any.
     pub failure_info: Option<ApplyFailure>,
+    /// The call frame that exhausted the gas budget, if the message ran out of gas.
+    pub out_of_gas: Option<OutOfGasCheckpoint>,
     /// Execution trace information, for debugging.
     pub exec_trace: ExecutionTrace,
+    /// The gas used by the message's top-level call and, recursively, by its sub-calls (only when
+    /// tracing gas).
+    pub call_gas: Option<CallGasUsage>,
     /// Events generated while applying the message.
     pub events: Vec<StampedEvent>,
+    /// Debug artifacts stored by actors while applying the message (only when actor debugging is
+    /// enabled), in the order they were stored.
+    pub artifacts: Vec<DebugArtifact>,
 }
 
 impl ApplyRet {
@@ -100,6 +129,7 @@ impl ApplyRet {
     pub fn prevalidation_fail(
         code: ExitCode,
         message: impl Into<String>,
+        gas_limit: u64,
         miner_penalty: TokenAmount,
     ) -> ApplyRet {
         ApplyRet {
@@ -109,18 +139,99 @@ impl ApplyRet {
                 gas_used: 0,
                 events_root: None,
             },
-            penalty: miner_penalty,
+            penalty: miner_penalty.clone(),
             miner_tip: TokenAmount::zero(),
             base_fee_burn: TokenAmount::zero(),
             over_estimation_burn: TokenAmount::zero(),
             refund: TokenAmount::zero(),
             gas_refund: 0,
             gas_burned: 0,
+            fees: FeeBreakdown {
+                miner_penalty,
+                ..FeeBreakdown::unpaid(gas_limit, 0)
+            },
             failure_info: Some(ApplyFailure::PreValidation(message.into())),
+            out_of_gas: None,
             exec_trace: vec![],
+            call_gas: None,
             events: vec![],
+            artifacts: vec![],
+        }
+    }
+
+    /// Returns the message's receipt extended with its [`GasBreakdown`]. Unlike
+    /// [`ApplyRet::msg_receipt`], the extended receipt must not be committed to on-chain.
+    pub fn extended_receipt(&self) -> ExtendedReceipt {
+        ExtendedReceipt {
+            receipt: self.msg_receipt.clone(),
+            gas_breakdown: GasBreakdown {
+                gas_limit: self.fees.gas_limit,
+                gas_refund: self.fees.gas_refund,
+                gas_burned: self.fees.gas_burned,
+            },
         }
     }
+
+    /// Writes all debug artifacts stored while applying the message to `dir`. See
+    /// [`DebugArtifact::write_to_dir`].
+    pub fn write_artifacts(&self, dir: impl AsRef<Path>) -> std::io::Result<()> {
+        for artifact in &self.artifacts {
+            artifact.write_to_dir(dir.as_ref())?;
+        }
+        Ok(())
+    }
+}
+
+/// The breakdown of the gas fees charged for a message.
+///
+/// The sender pays for the entire gas limit up-front (`fee_cap * gas_limit`). After execution, this
+/// is split into the base fee burn, the over-estimation burn, the miner tip, and the refund to the
+/// sender. The miner penalty is charged separately to the miner that included the message.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct FeeBreakdown {
+    /// The message's gas limit.
+    pub gas_limit: u64,
+    /// The gas used by the message.
+    pub gas_used: u64,
+    /// The unused gas refunded to the sender.
+    pub gas_refund: u64,
+    /// The unused gas burned as a penalty for over-estimating the gas limit.
+    pub gas_burned: u64,
+
+    /// The base fee burned for the gas used.
+    pub base_fee_burn: TokenAmount,
+    /// The base fee burned for the over-estimated gas ([`FeeBreakdown::gas_burned`]).
+    pub over_estimation_burn: TokenAmount,
+    /// The penalty charged to the miner that included the message, if any (e.g., because the
+    /// message was invalid or its fee cap was below the base fee).
+    pub miner_penalty: TokenAmount,
+    /// The tip paid to the miner that included the message.
+    pub miner_tip: TokenAmount,
+    /// The funds refunded to the sender.
+    pub refund: TokenAmount,
+}
+
+impl FeeBreakdown {
+    /// A breakdown for a message that was not charged any fees (e.g., an implicit message, or a
+    /// message that failed pre-validation).
+    pub fn unpaid(gas_limit: u64, gas_used: u64) -> Self {
+        FeeBreakdown {
+            gas_limit,
+            gas_used,
+            ..Default::default()
+        }
+    }
+
+    /// The total funds burned: the base fee burn plus the over-estimation burn.
+    pub fn total_burned(&self) -> TokenAmount {
+        &self.base_fee_burn + &self.over_estimation_burn
+    }
+
+    /// The total fees paid by the sender (excluding the refund): the burned funds plus the miner
+    /// tip.
+    pub fn total_paid(&self) -> TokenAmount {
+        self.total_burned() + &self.miner_tip
+    }
 }
 
 /// The kind of message being applied:
@@ -129,8 +240,60 @@ impl ApplyRet {
 /// consumed.
 /// 2. Implicit messages may come from any actor, ignore the nonce, and charge no gas (but still
 /// account for it).
-#[derive(Eq, PartialEq, Copy, Clone, Debug)]
+/// 3. Estimate messages are validated like explicit messages (sender, nonce, inclusion gas), but
+/// ignore the sender's balance and charge no gas (but still account for it). Use these to estimate
+/// the gas a message will consume, e.g., with [`DefaultExecutor::estimate_gas`].
+/// 4. Query messages are read-only state queries (e.g., to serve `StateCall` RPCs). Like implicit
+/// messages, they may come from any actor, ignore the nonce, and charge no gas. Additionally, they
+/// charge no inclusion gas, may not transfer value, and are executed read-only so they can't
+/// modify the state. Queries are unmetered: they ignore the message's gas limit (the gas used is
+/// still reported). Use [`MachineContext::execution_budget`] to bound their execution time.
+///
+/// [`MachineContext::execution_budget`]: crate::machine::MachineContext::execution_budget
+#[derive(Eq, PartialEq, Hash, Copy, Clone, Debug)]
 pub enum ApplyKind {
     Explicit,
     Implicit,
+    Estimate,
+    Query,
+}
+
+/// Options for [`DefaultExecutor::estimate_gas`].
+#[derive(Copy, Clone, Debug)]
+pub struct EstimateOptions {
+    /// The percentage by which to increase the estimated gas limit to account for state changes
+    /// between estimation and inclusion.
+    ///
+    /// DEFAULT: 25
+    pub overestimation_percent: u64,
+    /// Binary search for the smallest gas limit (between the gas used and the message's gas limit)
+    /// with which the message succeeds. The gas used by a message doesn't always suffice as its
+    /// gas limit (e.g., when an actor reserves gas for sub-calls).
+    ///
+    /// DEFAULT: false
+    pub search: bool,
+}
+
+impl Default for EstimateOptions {
+    fn default() -> Self {
+        EstimateOptions {
+            overestimation_percent: 25,
+            search: false,
+        }
+    }
+}
+
+/// The result of estimating a message's gas with [`DefaultExecutor::estimate_gas`].
+#[derive(Clone, Debug)]
+pub struct GasEstimate {
+    /// The gas used when applying the message with its original gas limit.
+    pub gas_used: u64,
+    /// The smallest gas limit found to be sufficient: the gas used, or the result of the binary
+    /// search if requested.
+    pub min_gas_limit: u64,
+    /// The suggested gas limit: the minimum gas limit plus the requested overestimation, capped at
+    /// the block gas limit.
+    pub suggested_gas_limit: u64,
+    /// The result of applying the message with its original gas limit.
+    pub apply_ret: ApplyRet,
 }
@@ -0,0 +1,129 @@
+// Copyright 2021-2023 Protocol Labs
+// SPDX-License-Identifier: Apache-2.0, MIT
+use fvm_shared::error::ExitCode;
+
+use super::{ApplyKind, SenderRejection};
+
+/// Decides whether a message's sequence (nonce) is acceptable given its sender's current sequence,
+/// and what the sender's sequence becomes once the message is applied. Set it with
+/// [`DefaultExecutor::with_nonce_policy`](super::DefaultExecutor::with_nonce_policy).
+///
+/// The policy is consulted for explicit messages and gas estimates from senders validated by the
+/// executor itself. Implicit messages and read-only queries never check nonces, and custom
+/// [`SenderValidator`](super::SenderValidator)s are responsible for their own senders' nonces.
+///
+/// Anything other than [`StrictNonces`] is unsafe for applying messages on-chain, where nonces
+/// prevent replays. Policies can use the [`ApplyKind`] to only relax checks when estimating.
+pub trait NoncePolicy: Send + Sync {
+    /// Checks the message's sequence against the sender's, returning the sender's new sequence.
+    fn advance(
+        &self,
+        apply_kind: ApplyKind,
+        msg_sequence: u64,
+        sender_sequence: u64,
+    ) -> Result<u64, SenderRejection>;
+}
+
+/// Requires messages to carry their sender's current sequence, which is then incremented. This is
+/// the Filecoin behavior.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct StrictNonces;
+
+impl NoncePolicy for StrictNonces {
+    fn advance(
+        &self,
+        _apply_kind: ApplyKind,
+        msg_sequence: u64,
+        sender_sequence: u64,
+    ) -> Result<u64, SenderRejection> {
+        if msg_sequence != sender_sequence {
+            return Err(SenderRejection::new(
+                ExitCode::SYS_SENDER_STATE_INVALID,
+                format!(
+                    "Actor sequence invalid: {} != {}",
+                    msg_sequence, sender_sequence
+                ),
+            ));
+        }
+        next_sequence(sender_sequence)
+    }
+}
+
+/// Like [`StrictNonces`], except that gas estimates may skip ahead of their sender's current
+/// sequence (e.g., to estimate a message queued behind pending messages). The sender's sequence
+/// then advances past the estimated message's.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct GapTolerantNonces;
+
+impl NoncePolicy for GapTolerantNonces {
+    fn advance(
+        &self,
+        apply_kind: ApplyKind,
+        msg_sequence: u64,
+        sender_sequence: u64,
+    ) -> Result<u64, SenderRejection> {
+        if apply_kind != ApplyKind::Estimate || msg_sequence < sender_sequence {
+            return StrictNonces.advance(apply_kind, msg_sequence, sender_sequence);
+        }
+        next_sequence(msg_sequence)
+    }
+}
+
+/// Like [`StrictNonces`], except that gas estimates ignore nonces entirely, leaving the sender's
+/// sequence unchanged.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct DisabledNonces;
+
+impl NoncePolicy for DisabledNonces {
+    fn advance(
+        &self,
+        apply_kind: ApplyKind,
+        msg_sequence: u64,
+        sender_sequence: u64,
+    ) -> Result<u64, SenderRejection> {
+        if apply_kind != ApplyKind::Estimate {
+            return StrictNonces.advance(apply_kind, msg_sequence, sender_sequence);
+        }
+        Ok(sender_sequence)
+    }
+}
+
+/// Returns the sequence following the given one, rejecting the message if the sequence would
+/// overflow.
+fn next_sequence(sequence: u64) -> Result<u64, SenderRejection> {
+    sequence.checked_add(1).ok_or_else(|| {
+        SenderRejection::new(
+            ExitCode::SYS_SENDER_STATE_INVALID,
+            format!("Actor sequence overflow: {}", sequence),
+        )
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn nonce_policies() {
+        assert_eq!(StrictNonces.advance(ApplyKind::Explicit, 3, 3), Ok(4));
+        assert!(StrictNonces.advance(ApplyKind::Estimate, 4, 3).is_err());
+
+        assert_eq!(GapTolerantNonces.advance(ApplyKind::Estimate, 5, 3), Ok(6));
+        assert!(GapTolerantNonces
+            .advance(ApplyKind::Estimate, 2, 3)
+            .is_err());
+        assert!(GapTolerantNonces
+            .advance(ApplyKind::Explicit, 5, 3)
+            .is_err());
+
+        assert!(StrictNonces
+            .advance(ApplyKind::Explicit, u64::MAX, u64::MAX)
+            .is_err());
+        assert!(GapTolerantNonces
+            .advance(ApplyKind::Estimate, u64::MAX, 3)
+            .is_err());
+
+        assert_eq!(DisabledNonces.advance(ApplyKind::Estimate, 0, 3), Ok(3));
+        assert!(DisabledNonces.advance(ApplyKind::Explicit, 0, 3).is_err());
+    }
+}
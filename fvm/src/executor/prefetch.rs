@@ -0,0 +1,46 @@
+// Copyright 2021-2023 Protocol Labs
+// SPDX-License-Identifier: Apache-2.0, MIT
+use std::collections::BTreeSet;
+
+use fvm_shared::message::Message;
+
+use super::DefaultExecutor;
+use crate::engine::Prefetch;
+use crate::kernel::Context as _;
+use crate::machine::Machine;
+use crate::Kernel;
+
+impl<K> DefaultExecutor<K>
+where
+    K: Kernel,
+{
+    /// Starts compiling the code of the given messages' receivers in the background (see
+    /// [`EnginePool::prefetch`](crate::engine::EnginePool::prefetch)), so that it's ready by the
+    /// time the messages are applied. Receivers that don't exist yet are skipped.
+    ///
+    /// Builtin actors are already loaded when the executor is created, so this mostly helps with
+    /// user-deployed actors, which would otherwise be compiled the first time they're called.
+    pub fn prefetch_code<'a>(
+        &self,
+        msgs: impl IntoIterator<Item = &'a Message>,
+    ) -> anyhow::Result<Prefetch> {
+        let mut code = BTreeSet::new();
+        for msg in msgs {
+            let Some(id) = self
+                .state_tree()
+                .lookup_id(&msg.to)
+                .context("failed to look up message receiver")?
+            else {
+                continue;
+            };
+            if let Some(actor) = self
+                .state_tree()
+                .get_actor(id)
+                .context("failed to load message receiver")?
+            {
+                code.insert(actor.code);
+            }
+        }
+        self.engine_pool.prefetch(self.blockstore(), &code)
+    }
+}
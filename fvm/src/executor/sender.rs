@@ -0,0 +1,155 @@
+// Copyright 2021-2023 Protocol Labs
+// SPDX-License-Identifier: Apache-2.0, MIT
+use anyhow::Context as _;
+use cid::multihash::{Code, MultihashDigest};
+use cid::Cid;
+use fvm_ipld_encoding::{to_vec, DAG_CBOR};
+use fvm_shared::address::{Address, Payload};
+use fvm_shared::crypto::signature::{Signature, SignatureType};
+use fvm_shared::error::ExitCode;
+use fvm_shared::message::Message;
+use fvm_shared::ActorID;
+
+use super::{eth_tx, ApplyKind, DefaultExecutor};
+use crate::eam_actor::EAM_ACTOR_ID;
+use crate::machine::Machine;
+use crate::state_tree::ActorState;
+use crate::Kernel;
+
+/// Why a [`SenderValidator`] rejected a message. The message fails pre-validation with the given
+/// exit code, and the miner is penalized as usual.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SenderRejection {
+    /// The exit code recorded in the message's receipt.
+    pub exit_code: ExitCode,
+    /// A human-readable description of the failure.
+    pub message: String,
+}
+
+impl SenderRejection {
+    /// Creates a new rejection with the given exit code and message.
+    pub fn new(exit_code: ExitCode, message: impl Into<String>) -> Self {
+        Self {
+            exit_code,
+            message: message.into(),
+        }
+    }
+}
+
+/// Validates the senders of messages in place of the builtin account checks, e.g., to experiment
+/// with account abstraction (sponsored transactions, session keys, etc.). Register validators with
+/// [`DefaultExecutor::with_sender_validator`](super::DefaultExecutor::with_sender_validator). This
+/// is consensus-critical.
+///
+/// Validators are only consulted for explicit messages (and gas estimates), never for implicit
+/// messages or read-only queries.
+pub trait SenderValidator: Send + Sync {
+    /// Returns true if this validator is responsible for senders with the given code CID. Senders
+    /// no registered validator is responsible for are validated as usual (they must be accounts,
+    /// Ethereum accounts, or placeholders with an f4 address in the EAM's namespace).
+    fn handles(&self, code: &Cid) -> bool;
+
+    /// Validates a message from a sender this validator is responsible for, in place of the usual
+    /// sender type and sequence checks. On success, returns the ID of the actor paying for the
+    /// message's gas (normally the sender itself), which must cover the message's gas limit at the
+    /// message's fee cap (unless estimating gas). Unused gas is refunded to the payer.
+    ///
+    /// Validators may update the sender's state, which is written back if the message passes
+    /// pre-validation. They are responsible for preventing message replays, normally by checking
+    /// and incrementing the sender's sequence.
+    fn validate(
+        &self,
+        msg: &Message,
+        apply_kind: ApplyKind,
+        sender_id: ActorID,
+        sender: &mut ActorState,
+    ) -> Result<ActorID, SenderRejection>;
+}
+
+impl<K> DefaultExecutor<K>
+where
+    K: Kernel,
+{
+    /// Verifies a message's signature against its sender.
+    ///
+    /// - Secp256k1 and BLS signatures must be from the key behind the sender's f1/f3 address, over
+    ///   the bytes of the message's CID.
+    /// - Delegated signatures must be from the key behind the sender's f410 address: either the
+    ///   message's `from` address, or the f410 address of the actor it resolves to (e.g., an
+    ///   Ethereum account or placeholder). As in Lotus, they're over the RLP-encoded EIP-1559
+    ///   transaction the message was translated from, so only messages expressible as Ethereum
+    ///   transactions can have delegated signatures.
+    ///
+    /// [`DefaultExecutor::execute_message`] doesn't verify signatures (block validation does), but
+    /// [`DefaultExecutor::execute_signed_message`] verifies them as part of sender validation.
+    pub fn verify_message_signature(
+        &self,
+        msg: &Message,
+        signature: &Signature,
+    ) -> anyhow::Result<Result<(), SenderRejection>> {
+        let signer = match (signature.sig_type, msg.from.payload()) {
+            (SignatureType::Secp256k1, Payload::Secp256k1(_))
+            | (SignatureType::BLS, Payload::BLS(_)) => msg.from,
+            (SignatureType::Delegated, Payload::Delegated(da))
+                if da.namespace() == EAM_ACTOR_ID =>
+            {
+                msg.from
+            }
+            (SignatureType::Delegated, _) => match self.eth_signer(&msg.from)? {
+                Some(addr) => addr,
+                None => {
+                    return Ok(Err(SenderRejection::new(
+                        self.context().exit_code_policy.sender_invalid(),
+                        format!("sender {} has no f410 address", msg.from),
+                    )))
+                }
+            },
+            (sig_type, _) => {
+                return Ok(Err(SenderRejection::new(
+                    self.context().exit_code_policy.sender_invalid(),
+                    format!(
+                        "{:?} signature cannot be verified against sender {}",
+                        sig_type, msg.from
+                    ),
+                )))
+            }
+        };
+
+        let signing_bytes = match signature.sig_type {
+            SignatureType::Delegated => {
+                match eth_tx::signing_bytes(msg, self.context().network.chain_id) {
+                    Ok(bytes) => bytes,
+                    Err(e) => {
+                        return Ok(Err(SenderRejection::new(
+                            self.context().exit_code_policy.sender_invalid(),
+                            format!("message can't have a delegated signature: {}", e),
+                        )))
+                    }
+                }
+            }
+            _ => Cid::new_v1(
+                DAG_CBOR,
+                Code::Blake2b256.digest(&to_vec(msg).context("failed to encode message")?),
+            )
+            .to_bytes(),
+        };
+        Ok(signature
+            .verify(&signing_bytes, &signer)
+            .map_err(|e| SenderRejection::new(self.context().exit_code_policy.sender_invalid(), e)))
+    }
+
+    /// Returns the f410 address of the actor the given address resolves to, if any.
+    fn eth_signer(&self, addr: &Address) -> anyhow::Result<Option<Address>> {
+        let Some(id) = self.state_tree().lookup_id(addr)? else {
+            return Ok(None);
+        };
+        let delegated = self
+            .state_tree()
+            .get_actor(id)?
+            .and_then(|act| act.delegated_address)
+            .filter(
+                |a| matches!(a.payload(), Payload::Delegated(da) if da.namespace() == EAM_ACTOR_ID),
+            );
+        Ok(delegated)
+    }
+}
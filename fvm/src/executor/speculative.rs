@@ -0,0 +1,248 @@
+// Copyright 2021-2023 Protocol Labs
+// SPDX-License-Identifier: Apache-2.0, MIT
+use std::collections::{HashMap, VecDeque};
+use std::io::Write;
+use std::sync::Mutex;
+
+use cid::Cid;
+use fvm_ipld_blockstore::Blockstore;
+use fvm_shared::message::Message;
+
+use super::{ApplyKind, ApplyRet, DefaultExecutor, Executor};
+use crate::machine::{Machine, MachineContext};
+use crate::Kernel;
+
+/// Identifies a message application: the state root it was applied on top of, the message's CID,
+/// how it was applied, and the fingerprint of the machine context it was applied in (see
+/// [`context_fingerprint`]).
+type CacheKey = (Cid, Cid, ApplyKind, [u8; 32]);
+
+/// Hashes everything in the machine context that can affect the result of applying a message
+/// (other than the state itself): the network configuration (version, chain ID, prices, policies,
+/// etc.), the epoch and timestamp, the base fee, the circulating supply, and the trace
+/// configuration.
+fn context_fingerprint(context: &MachineContext) -> [u8; 32] {
+    let mut state = blake2b_simd::Params::new().hash_length(32).to_state();
+    // Writing to the hasher can't fail.
+    let _ = write!(
+        state,
+        "{:?}|{}|{}|{}|{}|{:?}",
+        context.network,
+        context.epoch,
+        context.timestamp,
+        context.base_fee.atto(),
+        context.circ_supply.atto(),
+        context.trace_config(),
+    );
+    let mut fingerprint = [0u8; 32];
+    fingerprint.copy_from_slice(state.finalize().as_bytes());
+    fingerprint
+}
+
+/// A memoized message application.
+#[derive(Clone)]
+struct CacheEntry {
+    ret: ApplyRet,
+    /// The state root after applying the message.
+    state_root: Cid,
+}
+
+/// A cache of message applications (their [`ApplyRet`] and resulting state root), keyed by the
+/// state root they were applied on top of, the message's CID, and a fingerprint of the machine
+/// context (network configuration, epoch, base fee, circulating supply, etc.). This lets work done
+/// when speculatively applying messages (e.g., when validating messages for the mempool) be reused
+/// when applying the same messages on top of the same state (e.g., when validating a block).
+///
+/// Use it with [`DefaultExecutor::execute_message_cached`]. The cache may be shared between
+/// executors (e.g., behind an `Arc`), as long as their externs are for the same chain (randomness
+/// and tipset CIDs aren't part of the fingerprint). Cached states are only reused if their root is
+/// readable through the executor's blockstore: the resulting states stay in the write buffer of the
+/// executor that applied the message until it's flushed.
+///
+/// When full, the oldest entries are evicted first.
+pub struct SpeculativeCache {
+    capacity: usize,
+    inner: Mutex<CacheInner>,
+}
+
+#[derive(Default)]
+struct CacheInner {
+    entries: HashMap<CacheKey, CacheEntry>,
+    /// Keys in insertion order, for eviction.
+    order: VecDeque<CacheKey>,
+    hits: u64,
+    misses: u64,
+}
+
+impl SpeculativeCache {
+    /// Creates a cache holding up to `capacity` message applications.
+    pub fn new(capacity: usize) -> Self {
+        SpeculativeCache {
+            capacity,
+            inner: Default::default(),
+        }
+    }
+
+    /// The number of cached message applications.
+    pub fn len(&self) -> usize {
+        self.inner.lock().unwrap().entries.len()
+    }
+
+    /// Returns true if the cache is empty.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the number of cache hits and misses so far.
+    pub fn hits_and_misses(&self) -> (u64, u64) {
+        let inner = self.inner.lock().unwrap();
+        (inner.hits, inner.misses)
+    }
+
+    /// Evicts all cached message applications.
+    pub fn clear(&self) {
+        let mut inner = self.inner.lock().unwrap();
+        inner.entries.clear();
+        inner.order.clear();
+    }
+
+    fn get(&self, key: &CacheKey) -> Option<CacheEntry> {
+        let mut inner = self.inner.lock().unwrap();
+        let entry = inner.entries.get(key).cloned();
+        match entry {
+            Some(_) => inner.hits += 1,
+            None => inner.misses += 1,
+        }
+        entry
+    }
+
+    fn insert(&self, key: CacheKey, entry: CacheEntry) {
+        if self.capacity == 0 {
+            return;
+        }
+        let mut inner = self.inner.lock().unwrap();
+        if inner.entries.insert(key, entry).is_none() {
+            inner.order.push_back(key);
+        }
+        while inner.entries.len() > self.capacity {
+            let Some(oldest) = inner.order.pop_front() else {
+                break;
+            };
+            inner.entries.remove(&oldest);
+        }
+    }
+}
+
+impl<K> DefaultExecutor<K>
+where
+    K: Kernel,
+{
+    /// Like [`execute_message`](Executor::execute_message), but memoizes the result in the given
+    /// [`SpeculativeCache`], keyed by the current state root, the message's CID, and the machine
+    /// context. If the same message was already applied in the same way on top of the same state
+    /// in the same context, the cached [`ApplyRet`] is returned and the executor switches over to
+    /// the resulting state without re-executing the message.
+    ///
+    /// The state roots are computed with [`Machine::flush_partial`], so nothing is written to the
+    /// underlying blockstore. Cache hits don't invoke the
+    /// [`ExecutorEvents`](super::ExecutorEvents).
+    pub fn execute_message_cached(
+        &mut self,
+        cache: &SpeculativeCache,
+        msg_cid: Cid,
+        msg: Message,
+        apply_kind: ApplyKind,
+        raw_length: usize,
+    ) -> anyhow::Result<ApplyRet> {
+        let key = (
+            self.flush_partial()?,
+            msg_cid,
+            apply_kind,
+            context_fingerprint(self.context()),
+        );
+        if let Some(entry) = cache.get(&key) {
+            if self.blockstore().has(&entry.state_root)? {
+                self.state_tree_mut().set_root(&entry.state_root)?;
+                return Ok(entry.ret);
+            }
+        }
+
+        let ret = self.execute_message(msg, apply_kind, raw_length)?;
+        let state_root = self.flush_partial()?;
+        cache.insert(
+            key,
+            CacheEntry {
+                ret: ret.clone(),
+                state_root,
+            },
+        );
+        Ok(ret)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use fvm_shared::error::ExitCode;
+
+    use cid::multihash::{Code, MultihashDigest};
+    use fvm_ipld_encoding::IPLD_RAW;
+    use fvm_shared::econ::TokenAmount;
+    use fvm_shared::version::NetworkVersion;
+
+    use super::*;
+    use crate::machine::NetworkConfig;
+    use crate::EMPTY_ARR_CID;
+
+    #[test]
+    fn evicts_oldest() {
+        let cache = SpeculativeCache::new(2);
+        let keys = [ApplyKind::Explicit, ApplyKind::Implicit, ApplyKind::Query]
+            .map(|kind| (*EMPTY_ARR_CID, *EMPTY_ARR_CID, kind, [0; 32]));
+        for (i, key) in keys.iter().enumerate() {
+            let entry = CacheEntry {
+                ret: ApplyRet::prevalidation_fail(ExitCode::OK, "", i as u64, Default::default()),
+                state_root: *EMPTY_ARR_CID,
+            };
+            cache.insert(*key, entry);
+        }
+        assert_eq!(cache.len(), 2);
+        assert!(cache.get(&keys[0]).is_none());
+        assert_eq!(cache.get(&keys[2]).unwrap().ret.fees.gas_limit, 2);
+        assert_eq!(cache.hits_and_misses(), (1, 1));
+
+        cache.clear();
+        assert!(cache.is_empty());
+    }
+
+    #[test]
+    fn fingerprints_context() {
+        let nc = NetworkConfig::new(NetworkVersion::V18);
+        let context = nc.for_epoch(10, 0, *EMPTY_ARR_CID).unwrap();
+        let fingerprint = context_fingerprint(&context);
+
+        // The initial state root isn't part of the fingerprint (it's part of the key).
+        let mut other = context.clone();
+        other.initial_state_root = Cid::new_v1(IPLD_RAW, Code::Blake2b256.digest(b"other"));
+        assert_eq!(context_fingerprint(&other), fingerprint);
+
+        let mut other = context.clone();
+        other.epoch = 11;
+        assert_ne!(context_fingerprint(&other), fingerprint);
+
+        let mut other = context.clone();
+        other.base_fee = TokenAmount::from_atto(1234);
+        assert_ne!(context_fingerprint(&other), fingerprint);
+
+        let mut other = context.clone();
+        other.circ_supply = TokenAmount::from_atto(1234);
+        assert_ne!(context_fingerprint(&other), fingerprint);
+
+        let mut other = context.clone();
+        other.enable_tracing();
+        assert_ne!(context_fingerprint(&other), fingerprint);
+
+        let mut other = context;
+        other.network.chain_id = 1234.into();
+        assert_ne!(context_fingerprint(&other), fingerprint);
+    }
+}
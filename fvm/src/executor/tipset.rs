@@ -0,0 +1,321 @@
+// Copyright 2021-2023 Protocol Labs
+// SPDX-License-Identifier: Apache-2.0, MIT
+use std::collections::HashSet;
+
+use anyhow::{anyhow, Context};
+use cid::Cid;
+use fvm_ipld_encoding::tuple::*;
+use fvm_ipld_encoding::{to_vec, RawBytes};
+use fvm_shared::address::Address;
+use fvm_shared::clock::ChainEpoch;
+use fvm_shared::econ::TokenAmount;
+use fvm_shared::message::Message;
+use fvm_shared::receipt::Receipt;
+use fvm_shared::{ActorID, MethodNum, BLOCK_GAS_LIMIT};
+use num_traits::Zero;
+
+use super::{ApplyKind, ApplyRet, DefaultExecutor, Executor};
+use crate::machine::{Machine, REWARD_ACTOR_ID};
+use crate::system_actor::SYSTEM_ACTOR_ID;
+use crate::Kernel;
+
+/// The cron actor, invoked at the end of every tipset.
+pub const CRON_ACTOR_ID: ActorID = 3;
+
+/// The reward actor's `AwardBlockReward` method.
+const METHOD_AWARD_BLOCK_REWARD: MethodNum = 2;
+
+/// The cron actor's `EpochTick` method.
+const METHOD_EPOCH_TICK: MethodNum = 2;
+
+/// The gas limit of the implicit reward messages.
+const REWARD_GAS_LIMIT: u64 = 1 << 30;
+
+/// The gas limit of the implicit cron message.
+const CRON_GAS_LIMIT: u64 = BLOCK_GAS_LIMIT * 10000;
+
+/// The parameters of the reward actor's `AwardBlockReward` method.
+#[derive(Serialize_tuple, Deserialize_tuple)]
+struct AwardBlockRewardParams {
+    miner: Address,
+    penalty: TokenAmount,
+    gas_reward: TokenAmount,
+    win_count: i64,
+}
+
+/// An implicit message from the system actor, applied with [`ApplyKind::Implicit`]. Implicit
+/// messages don't pay for gas, and must succeed.
+#[derive(Clone, Debug)]
+pub struct ImplicitCall {
+    /// The actor to invoke.
+    pub to: ActorID,
+    /// The method to invoke.
+    pub method_num: MethodNum,
+    /// The method's parameters.
+    pub params: RawBytes,
+    /// The message's gas limit.
+    pub gas_limit: u64,
+}
+
+impl ImplicitCall {
+    /// The cron actor's `EpochTick`, run at the end of every epoch (including null rounds).
+    pub fn cron_tick() -> Self {
+        ImplicitCall {
+            to: CRON_ACTOR_ID,
+            method_num: METHOD_EPOCH_TICK,
+            params: RawBytes::default(),
+            gas_limit: CRON_GAS_LIMIT,
+        }
+    }
+
+    /// The reward actor's `AwardBlockReward`, rewarding a block's miner with the block reward plus
+    /// the tips (and minus the penalties) of the block's messages.
+    pub fn award_block_reward(
+        miner: Address,
+        penalty: TokenAmount,
+        gas_reward: TokenAmount,
+        win_count: i64,
+    ) -> anyhow::Result<Self> {
+        let params = AwardBlockRewardParams {
+            miner,
+            penalty,
+            gas_reward,
+            win_count,
+        };
+        Ok(ImplicitCall {
+            to: REWARD_ACTOR_ID,
+            method_num: METHOD_AWARD_BLOCK_REWARD,
+            params: RawBytes::serialize(params)?,
+            gas_limit: REWARD_GAS_LIMIT,
+        })
+    }
+}
+
+/// Supplies custom implicit calls to apply at the end of every epoch, after cron (e.g., network
+/// specific per-epoch jobs). Set it with
+/// [`DefaultExecutor::with_epoch_jobs`](super::DefaultExecutor::with_epoch_jobs). This is
+/// consensus-critical.
+pub trait EpochJobs: Send + Sync {
+    /// Returns the implicit calls to apply at the end of the given epoch, in order.
+    fn implicit_calls(&self, epoch: ChainEpoch) -> Vec<ImplicitCall>;
+}
+
+/// A message included in a block.
+#[derive(Clone, Debug)]
+pub struct ChainMessage {
+    /// The CID of the message as included on-chain (the CID of the _signed_ message for
+    /// secp256k1-signed messages). Messages included in multiple blocks of a tipset are only
+    /// applied once.
+    pub cid: Cid,
+    /// The message.
+    pub message: Message,
+    /// The length of the message as it appears on-chain, used to charge message inclusion gas.
+    pub raw_length: usize,
+}
+
+/// A block in a tipset, as applied by [`DefaultExecutor::apply_tipset`].
+#[derive(Clone, Debug)]
+pub struct TipsetBlock {
+    /// The miner that mined the block, rewarded for it.
+    pub miner: Address,
+    /// The number of winning tickets in the block's election proof.
+    pub win_count: i64,
+    /// The block's messages, in order.
+    pub messages: Vec<ChainMessage>,
+}
+
+/// The result of applying a single block's messages.
+#[derive(Clone, Debug)]
+pub struct BlockResult {
+    /// The results of the messages applied from this block, in order, along with their CIDs.
+    /// Messages already applied from a previous block are skipped.
+    pub messages: Vec<(Cid, ApplyRet)>,
+    /// The number of messages skipped because they were already applied from a previous block.
+    pub duplicates: usize,
+    /// The sum of the gas limits of the messages applied from this block. Blocks are expected to
+    /// have been validated against the block gas limit before being applied.
+    pub gas_limit: u64,
+    /// The gas used by the messages applied from this block.
+    pub gas_used: u64,
+    /// The result of the implicit message rewarding the block's miner.
+    pub reward: ApplyRet,
+}
+
+/// The result of applying a tipset.
+#[derive(Clone, Debug)]
+pub struct TipsetResult {
+    /// The results of each block, in order.
+    pub blocks: Vec<BlockResult>,
+    /// The result of the implicit cron message.
+    pub cron: ApplyRet,
+    /// The results of the [`EpochJobs`]' implicit calls, in order.
+    pub jobs: Vec<ApplyRet>,
+    /// The state root after applying the tipset.
+    pub state_root: Cid,
+}
+
+impl TipsetResult {
+    /// The receipts of the tipset's messages, in the order they were applied (skipping
+    /// duplicates). This is the list of receipts committed to by the next tipset.
+    pub fn receipts(&self) -> impl Iterator<Item = &Receipt> {
+        self.blocks
+            .iter()
+            .flat_map(|b| &b.messages)
+            .map(|(_, ret)| &ret.msg_receipt)
+    }
+
+    /// The receipts of the implicit messages, in the order they were applied: each block's reward,
+    /// cron, then the epoch jobs.
+    pub fn implicit_receipts(&self) -> impl Iterator<Item = &Receipt> {
+        self.blocks
+            .iter()
+            .map(|b| &b.reward)
+            .chain(Some(&self.cron))
+            .chain(&self.jobs)
+            .map(|ret| &ret.msg_receipt)
+    }
+}
+
+impl<K> DefaultExecutor<K>
+where
+    K: Kernel,
+{
+    /// Applies all messages in a tipset, along with the implicit messages that go with them:
+    ///
+    /// 1. The messages of each block, in order, skipping messages already applied from a previous
+    ///    block.
+    /// 2. After each block's messages, an implicit message rewarding the block's miner with the
+    ///    block reward plus the tips (and minus the penalties) of the block's messages.
+    /// 3. Finally, an implicit cron message followed by the [`EpochJobs`]' implicit calls, if any
+    ///    (see [`end_of_epoch_calls`](Self::end_of_epoch_calls)).
+    ///
+    /// The state is flushed at the end, and the resulting root returned. The machine must be at the
+    /// tipset's epoch (see [`advance_epoch`](Self::advance_epoch)): null rounds must be handled by
+    /// the caller (by advancing to each null epoch and applying its
+    /// [`end_of_epoch_calls`](Self::end_of_epoch_calls)).
+    ///
+    /// Fails if any of the implicit messages fails.
+    pub fn apply_tipset(
+        &mut self,
+        blocks: &[TipsetBlock],
+        epoch: ChainEpoch,
+    ) -> anyhow::Result<TipsetResult> {
+        if epoch != self.context().epoch {
+            return Err(anyhow!(
+                "cannot apply a tipset at epoch {} on a machine for epoch {}",
+                epoch,
+                self.context().epoch
+            ));
+        }
+
+        let mut applied = HashSet::new();
+        let mut results = Vec::with_capacity(blocks.len());
+        for block in blocks {
+            let mut messages = Vec::with_capacity(block.messages.len());
+            let mut duplicates = 0;
+            let mut gas_limit = 0u64;
+            let mut gas_used = 0u64;
+            let mut penalty = TokenAmount::zero();
+            let mut gas_reward = TokenAmount::zero();
+            for msg in &block.messages {
+                if !applied.insert(msg.cid) {
+                    duplicates += 1;
+                    continue;
+                }
+                let ret =
+                    self.execute_message(msg.message.clone(), ApplyKind::Explicit, msg.raw_length)?;
+                gas_limit = gas_limit.saturating_add(msg.message.gas_limit);
+                gas_used = gas_used.saturating_add(ret.msg_receipt.gas_used);
+                penalty += &ret.penalty;
+                gas_reward += &ret.miner_tip;
+                messages.push((msg.cid, ret));
+            }
+
+            let call = ImplicitCall::award_block_reward(
+                block.miner,
+                penalty,
+                gas_reward,
+                block.win_count,
+            )?;
+            let reward = self
+                .apply_implicit(&call, epoch)
+                .with_context(|| format!("failed to reward miner {}", block.miner))?;
+
+            results.push(BlockResult {
+                messages,
+                duplicates,
+                gas_limit,
+                gas_used,
+                reward,
+            });
+        }
+
+        let calls = self.end_of_epoch_calls(epoch);
+        let mut jobs = self
+            .apply_implicit_calls(&calls, epoch)
+            .context("failed to run end of epoch calls")?;
+        let cron = jobs.remove(0);
+
+        let state_root = self.flush()?;
+        Ok(TipsetResult {
+            blocks: results,
+            cron,
+            jobs,
+            state_root,
+        })
+    }
+
+    /// Returns the implicit calls to apply at the end of the given epoch: the cron tick, followed
+    /// by the [`EpochJobs`]' calls, if any.
+    pub fn end_of_epoch_calls(&self, epoch: ChainEpoch) -> Vec<ImplicitCall> {
+        let mut calls = vec![ImplicitCall::cron_tick()];
+        if let Some(jobs) = &self.epoch_jobs {
+            calls.extend(jobs.implicit_calls(epoch));
+        }
+        calls
+    }
+
+    /// Applies the given implicit calls in order, returning their results. Fails if any of them
+    /// fails (leaving the state changes of the previous calls in place).
+    pub fn apply_implicit_calls(
+        &mut self,
+        calls: &[ImplicitCall],
+        epoch: ChainEpoch,
+    ) -> anyhow::Result<Vec<ApplyRet>> {
+        calls
+            .iter()
+            .map(|call| self.apply_implicit(call, epoch))
+            .collect()
+    }
+
+    /// Applies an implicit message from the system actor, failing if the message fails.
+    fn apply_implicit(
+        &mut self,
+        call: &ImplicitCall,
+        epoch: ChainEpoch,
+    ) -> anyhow::Result<ApplyRet> {
+        let msg = Message {
+            version: 0,
+            from: Address::new_id(SYSTEM_ACTOR_ID),
+            to: Address::new_id(call.to),
+            sequence: epoch as u64,
+            value: TokenAmount::zero(),
+            method_num: call.method_num,
+            params: call.params.clone(),
+            gas_limit: call.gas_limit,
+            gas_fee_cap: TokenAmount::zero(),
+            gas_premium: TokenAmount::zero(),
+        };
+        let raw_length = to_vec(&msg)?.len();
+        let ret = self.execute_message(msg, ApplyKind::Implicit, raw_length)?;
+        if !ret.msg_receipt.exit_code.is_success() {
+            return Err(anyhow!(
+                "implicit message to {} (method {}) failed with exit code {}",
+                Address::new_id(call.to),
+                call.method_num,
+                ret.msg_receipt.exit_code
+            ));
+        }
+        Ok(ret)
+    }
+}
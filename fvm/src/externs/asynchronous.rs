@@ -0,0 +1,166 @@
+// Copyright 2021-2023 Protocol Labs
+// SPDX-License-Identifier: Apache-2.0, MIT
+//! Async externs, for nodes backed by async chain stores.
+//!
+//! The FVM executes messages synchronously, so [`AsyncExterns`] must be bridged into the
+//! synchronous [`Externs`] traits with [`BlockingExterns`], which blocks the executor thread on
+//! each call until the returned future completes.
+
+use async_trait::async_trait;
+use cid::Cid;
+use futures_executor::block_on;
+use fvm_shared::clock::ChainEpoch;
+use fvm_shared::consensus::ConsensusFault;
+
+use super::{Chain, Consensus, Externs, Rand};
+
+/// The async equivalent of [`Externs`]. See [`Rand`], [`Consensus`], and [`Chain`] for the
+/// semantics of each method.
+#[async_trait]
+pub trait AsyncExterns: Sync {
+    /// Async variant of [`Rand::get_chain_randomness`].
+    async fn get_chain_randomness(&self, round: ChainEpoch) -> anyhow::Result<[u8; 32]>;
+
+    /// Async variant of [`Rand::get_beacon_randomness`].
+    async fn get_beacon_randomness(&self, round: ChainEpoch) -> anyhow::Result<[u8; 32]>;
+
+    /// Async variant of [`Rand::verify_beacon_entry`].
+    async fn verify_beacon_entry(
+        &self,
+        round: u64,
+        prev_signature: &[u8],
+        signature: &[u8],
+    ) -> anyhow::Result<bool>;
+
+    /// Async variant of [`Consensus::verify_consensus_fault`].
+    async fn verify_consensus_fault(
+        &self,
+        h1: &[u8],
+        h2: &[u8],
+        extra: &[u8],
+    ) -> anyhow::Result<(Option<ConsensusFault>, i64)>;
+
+    /// Async variant of [`Chain::get_tipset_cid`].
+    async fn get_tipset_cid(&self, epoch: ChainEpoch) -> anyhow::Result<Cid>;
+}
+
+/// Adapts [`AsyncExterns`] into [`Externs`] by blocking on each call.
+///
+/// The futures are driven on the calling (executor) thread, so they must not depend on being
+/// polled from within a specific async runtime. Futures that do (e.g., tokio IO) should be spawned
+/// onto their runtime by the [`AsyncExterns`] implementation, awaiting the resulting handle.
+#[derive(Debug, Clone, Default)]
+pub struct BlockingExterns<E>(pub E);
+
+impl<E> BlockingExterns<E> {
+    /// Wraps the given async externs.
+    pub fn new(externs: E) -> Self {
+        BlockingExterns(externs)
+    }
+
+    /// Returns the wrapped async externs.
+    pub fn into_inner(self) -> E {
+        self.0
+    }
+}
+
+impl<E: AsyncExterns> Externs for BlockingExterns<E> {}
+
+impl<E: AsyncExterns> Rand for BlockingExterns<E> {
+    fn get_chain_randomness(&self, round: ChainEpoch) -> anyhow::Result<[u8; 32]> {
+        block_on(self.0.get_chain_randomness(round))
+    }
+
+    fn get_beacon_randomness(&self, round: ChainEpoch) -> anyhow::Result<[u8; 32]> {
+        block_on(self.0.get_beacon_randomness(round))
+    }
+
+    fn verify_beacon_entry(
+        &self,
+        round: u64,
+        prev_signature: &[u8],
+        signature: &[u8],
+    ) -> anyhow::Result<bool> {
+        block_on(self.0.verify_beacon_entry(round, prev_signature, signature))
+    }
+}
+
+impl<E: AsyncExterns> Consensus for BlockingExterns<E> {
+    fn verify_consensus_fault(
+        &self,
+        h1: &[u8],
+        h2: &[u8],
+        extra: &[u8],
+    ) -> anyhow::Result<(Option<ConsensusFault>, i64)> {
+        block_on(self.0.verify_consensus_fault(h1, h2, extra))
+    }
+}
+
+impl<E: AsyncExterns> Chain for BlockingExterns<E> {
+    fn get_tipset_cid(&self, epoch: ChainEpoch) -> anyhow::Result<Cid> {
+        block_on(self.0.get_tipset_cid(epoch))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::anyhow;
+    use fvm_shared::IDENTITY_HASH;
+    use multihash::Multihash;
+
+    use super::*;
+
+    struct TestAsyncExterns;
+
+    #[async_trait]
+    impl AsyncExterns for TestAsyncExterns {
+        async fn get_chain_randomness(&self, round: ChainEpoch) -> anyhow::Result<[u8; 32]> {
+            Ok([round as u8; 32])
+        }
+
+        async fn get_beacon_randomness(&self, _round: ChainEpoch) -> anyhow::Result<[u8; 32]> {
+            Err(anyhow!("no beacon"))
+        }
+
+        async fn verify_beacon_entry(
+            &self,
+            round: u64,
+            _prev_signature: &[u8],
+            _signature: &[u8],
+        ) -> anyhow::Result<bool> {
+            Ok(round % 2 == 0)
+        }
+
+        async fn verify_consensus_fault(
+            &self,
+            _h1: &[u8],
+            _h2: &[u8],
+            _extra: &[u8],
+        ) -> anyhow::Result<(Option<ConsensusFault>, i64)> {
+            Ok((None, 42))
+        }
+
+        async fn get_tipset_cid(&self, _epoch: ChainEpoch) -> anyhow::Result<Cid> {
+            Ok(Cid::new_v1(
+                fvm_ipld_encoding::DAG_CBOR,
+                Multihash::wrap(IDENTITY_HASH, b"tipset").unwrap(),
+            ))
+        }
+    }
+
+    #[test]
+    fn blocking_externs() {
+        let externs = BlockingExterns::new(TestAsyncExterns);
+        assert_eq!(externs.get_chain_randomness(7).unwrap(), [7; 32]);
+        assert!(externs.get_beacon_randomness(7).is_err());
+        assert!(externs.verify_beacon_entry(2, &[], &[]).unwrap());
+        assert!(!externs.verify_beacon_entry(3, &[], &[]).unwrap());
+        let (fault, gas) = externs.verify_consensus_fault(&[], &[], &[]).unwrap();
+        assert!(fault.is_none());
+        assert_eq!(gas, 42);
+        assert_eq!(
+            externs.get_tipset_cid(1).unwrap().hash().digest(),
+            b"tipset"
+        );
+    }
+}
@@ -6,6 +6,11 @@ use cid::Cid;
 use fvm_shared::clock::ChainEpoch;
 use fvm_shared::consensus::ConsensusFault;
 
+#[cfg(feature = "async-externs")]
+mod asynchronous;
+#[cfg(feature = "async-externs")]
+pub use asynchronous::{AsyncExterns, BlockingExterns};
+
 pub trait Externs: Rand + Consensus + Chain {}
 
 /// Consensus related methods.
@@ -28,6 +33,18 @@ pub trait Rand {
     /// Gets 32 bytes of randomness for ChainRand paramaterized by the DomainSeparationTag,
     /// ChainEpoch, Entropy from the latest beacon entry.
     fn get_beacon_randomness(&self, round: ChainEpoch) -> anyhow::Result<[u8; 32]>;
+
+    /// Verifies a beacon (currently Drand) entry for the given beacon round against the
+    /// network's beacon chain info. Returns `Ok(false)` if the signature is invalid for the round.
+    ///
+    /// `prev_signature` is the signature of the previous round for chained beacons, and empty for
+    /// unchained beacons.
+    fn verify_beacon_entry(
+        &self,
+        round: u64,
+        prev_signature: &[u8],
+        signature: &[u8],
+    ) -> anyhow::Result<bool>;
 }
 
 /// Chain information provider.
@@ -0,0 +1,76 @@
+// Copyright 2021-2023 Protocol Labs
+// SPDX-License-Identifier: Apache-2.0, MIT
+use fvm_shared::address::Address;
+use fvm_shared::MethodNum;
+use num_traits::Zero;
+
+use super::Gas;
+
+/// The gas used by a single call and, recursively, by the calls it made.
+///
+/// Only recorded when tracing is enabled. Gas charged outside of any call (e.g., message inclusion
+/// gas) isn't attributed to any call.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CallGasUsage {
+    /// The address of the called actor, as specified by the caller.
+    pub to: Address,
+    /// The called method.
+    pub method: MethodNum,
+    /// The gas charged while executing this call, excluding the gas charged by its sub-calls. This
+    /// includes the gas charged to resolve the receiver, transfer funds, and invoke the actor.
+    pub local: Gas,
+    /// The sub-calls made by this call, in order.
+    pub children: Vec<CallGasUsage>,
+}
+
+impl CallGasUsage {
+    pub(super) fn new(to: Address, method: MethodNum) -> Self {
+        CallGasUsage {
+            to,
+            method,
+            local: Gas::zero(),
+            children: Vec::new(),
+        }
+    }
+
+    /// The total gas charged by this call, including its sub-calls.
+    pub fn total(&self) -> Gas {
+        self.children
+            .iter()
+            .fold(self.local, |total, child| total + child.total())
+    }
+}
+
+/// Records a [`CallGasUsage`] tree as calls are entered and exited.
+#[derive(Default)]
+pub(super) struct CallGasRecorder {
+    /// The calls currently on the call stack, innermost last.
+    stack: Vec<CallGasUsage>,
+    /// The completed top-level calls.
+    roots: Vec<CallGasUsage>,
+}
+
+impl CallGasRecorder {
+    pub fn enter(&mut self, to: Address, method: MethodNum) {
+        self.stack.push(CallGasUsage::new(to, method));
+    }
+
+    pub fn exit(&mut self) {
+        if let Some(call) = self.stack.pop() {
+            match self.stack.last_mut() {
+                Some(parent) => parent.children.push(call),
+                None => self.roots.push(call),
+            }
+        }
+    }
+
+    pub fn record(&mut self, gas: Gas) {
+        if let Some(call) = self.stack.last_mut() {
+            call.local += gas;
+        }
+    }
+
+    pub fn take(&mut self) -> Vec<CallGasUsage> {
+        std::mem::take(&mut self.roots)
+    }
+}
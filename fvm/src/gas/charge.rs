@@ -3,6 +3,8 @@
 // SPDX-License-Identifier: Apache-2.0, MIT
 
 use std::borrow::Cow;
+use std::fmt;
+use std::ops::Deref;
 
 use super::timer::GasDuration;
 use super::Gas;
@@ -11,7 +13,7 @@ use super::Gas;
 /// as the amount of gas needed for computation and storage respectively.
 #[derive(Clone, Debug)]
 pub struct GasCharge {
-    pub name: Cow<'static, str>,
+    pub name: GasChargeName,
     /// Gas charged for immediate computation.
     pub compute_gas: Gas,
 
@@ -43,7 +45,7 @@ impl PartialEq for GasCharge {
 impl Eq for GasCharge {}
 
 impl GasCharge {
-    pub fn new(name: impl Into<Cow<'static, str>>, compute_gas: Gas, other_gas: Gas) -> Self {
+    pub fn new(name: impl Into<GasChargeName>, compute_gas: Gas, other_gas: Gas) -> Self {
         let name = name.into();
         Self {
             name,
@@ -59,3 +61,164 @@ impl GasCharge {
         self.compute_gas + self.other_gas
     }
 }
+
+macro_rules! charge_names {
+    ($($variant:ident => $name:literal,)*) => {
+        /// The name of a [`GasCharge`].
+        ///
+        /// All charges made by the FVM itself have a dedicated variant, so naming them doesn't
+        /// allocate and analysis tools can match on them exhaustively. Other charges (e.g., charges
+        /// made by actors through the `gas::charge` syscall) are named with
+        /// [`GasChargeName::Custom`].
+        ///
+        /// Names dereference to (and compare equal with) their string forms, as they appear in
+        /// execution traces.
+        #[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+        pub enum GasChargeName {
+            $($variant,)*
+            /// Any other charge.
+            Custom(Cow<'static, str>),
+        }
+
+        impl GasChargeName {
+            /// Returns the name as a string.
+            pub fn as_str(&self) -> &str {
+                match self {
+                    $(GasChargeName::$variant => $name,)*
+                    GasChargeName::Custom(name) => name,
+                }
+            }
+
+            /// Returns the dedicated variant with the given name, if any.
+            fn known(name: &str) -> Option<GasChargeName> {
+                match name {
+                    $($name => Some(GasChargeName::$variant),)*
+                    _ => None,
+                }
+            }
+        }
+    };
+}
+
+charge_names! {
+    OnActorCharge => "OnActorCharge",
+    OnActorCreate => "OnActorCreate",
+    OnActorEventAccept => "OnActorEventAccept",
+    OnActorEventValidate => "OnActorEventValidate",
+    OnActorLookup => "OnActorLookup",
+    OnActorUpdate => "OnActorUpdate",
+    OnBalanceOf => "OnBalanceOf",
+    OnBlockCreate => "OnBlockCreate",
+    OnBlockLink => "OnBlockLink",
+    OnBlockLinks => "OnBlockLinks",
+    OnBlockOpenBase => "OnBlockOpenBase",
+    OnBlockOpenPerByte => "OnBlockOpenPerByte",
+    OnBlockRead => "OnBlockRead",
+    OnBlockStat => "OnBlockStat",
+    OnChainMessage => "OnChainMessage",
+    OnChainReturnValue => "OnChainReturnValue",
+    OnComputeUnsealedSectorCid => "OnComputeUnsealedSectorCid",
+    OnCopy => "OnCopy",
+    OnCreateActor => "OnCreateActor",
+    OnDeleteActor => "OnDeleteActor",
+    OnGetActorCodeCid => "OnGetActorCodeCid",
+    OnGetBuiltinActorType => "OnGetBuiltinActorType",
+    OnGetCodeCidForType => "OnGetCodeCidForType",
+    OnGetRandomness => "OnGetRandomness",
+    OnHashing => "OnHashing",
+    OnInstallActor => "OnInstallActor",
+    OnLookupAddress => "OnLookupAddress",
+    OnMessageContext => "OnMessageContext",
+    OnMethodInvocation => "OnMethodInvocation",
+    OnNetworkContext => "OnNetworkContext",
+    OnRecoverSecpPublicKey => "OnRecoverSecpPublicKey",
+    OnResolveAddress => "OnResolveAddress",
+    OnSelfBalance => "OnSelfBalance",
+    OnSyscall => "OnSyscall",
+    OnTipsetCid => "OnTipsetCid",
+    OnValueTransfer => "OnValueTransfer",
+    OnVerifyAggregateSeals => "OnVerifyAggregateSeals",
+    OnVerifyBeaconEntry => "OnVerifyBeaconEntry",
+    OnVerifyConsensusFault => "OnVerifyConsensusFault",
+    OnVerifyPost => "OnVerifyPost",
+    OnVerifyReplicaUpdate => "OnVerifyReplicaUpdate",
+    OnVerifySeal => "OnVerifySeal",
+    OnVerifySignature => "OnVerifySignature",
+    WasmExec => "wasm_exec",
+    WasmMemoryGrow => "wasm_memory_grow",
+    WasmMemoryInit => "wasm_memory_init",
+    WasmTableInit => "wasm_table_init",
+    Empty => "none",
+}
+
+impl From<&'static str> for GasChargeName {
+    fn from(name: &'static str) -> Self {
+        GasChargeName::known(name).unwrap_or(GasChargeName::Custom(Cow::Borrowed(name)))
+    }
+}
+
+impl From<String> for GasChargeName {
+    fn from(name: String) -> Self {
+        GasChargeName::known(&name).unwrap_or(GasChargeName::Custom(Cow::Owned(name)))
+    }
+}
+
+impl From<GasChargeName> for String {
+    fn from(name: GasChargeName) -> Self {
+        match name {
+            GasChargeName::Custom(name) => name.into_owned(),
+            name => name.as_str().to_owned(),
+        }
+    }
+}
+
+impl Deref for GasChargeName {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl AsRef<str> for GasChargeName {
+    fn as_ref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl fmt::Display for GasChargeName {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl PartialEq<str> for GasChargeName {
+    fn eq(&self, other: &str) -> bool {
+        self.as_str() == other
+    }
+}
+
+impl PartialEq<&str> for GasChargeName {
+    fn eq(&self, other: &&str) -> bool {
+        self.as_str() == *other
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn known_names() {
+        assert_eq!(GasChargeName::from("OnSyscall"), GasChargeName::OnSyscall);
+        assert_eq!(
+            GasChargeName::from("wasm_exec".to_owned()),
+            GasChargeName::WasmExec
+        );
+        assert_eq!(GasChargeName::OnSyscall, "OnSyscall");
+
+        let custom = GasChargeName::from("my_charge");
+        assert_eq!(custom, GasChargeName::Custom("my_charge".into()));
+        assert_eq!(String::from(custom), "my_charge");
+    }
+}
@@ -3,25 +3,41 @@
 // SPDX-License-Identifier: Apache-2.0, MIT
 
 use std::cell::{Cell, RefCell};
+use std::collections::VecDeque;
 use std::fmt::{Debug, Display};
 use std::ops::{Add, AddAssign, Mul, Sub, SubAssign};
 
 use anyhow::Context;
+use fvm_shared::address::Address;
+use fvm_shared::MethodNum;
 use num_traits::Zero;
 
-pub use self::charge::GasCharge;
+use self::call_gas::CallGasRecorder;
+pub use self::call_gas::CallGasUsage;
+pub use self::charge::{GasCharge, GasChargeName};
 pub(crate) use self::outputs::GasOutputs;
+pub use self::outputs::{
+    FeeDistribution, FilecoinFeeDistribution, FilecoinOverestimationBurn, OverestimationBurnPolicy,
+};
+pub(crate) use self::price_list::builtin_price_list;
 pub use self::price_list::{price_list_by_network_version, PriceList, WasmGasPrices};
+pub use self::price_overrides::PriceOverrides;
 pub use self::timer::{GasInstant, GasTimer};
 use crate::kernel::{ClassifyResult, ExecutionError, Result};
 
+mod call_gas;
 mod charge;
 mod outputs;
 mod price_list;
+mod price_overrides;
 mod timer;
 
 pub const MILLIGAS_PRECISION: u64 = 1000;
 
+/// The number of recent gas charges recorded when running out of gas. See
+/// [`GasTracker::take_exhausted`].
+pub const RECENT_GAS_CHARGES: usize = 8;
+
 /// A typesafe representation of gas (internally stored as milligas).
 ///
 /// - All math operations are _saturating_ and never overflow.
@@ -169,6 +185,14 @@ pub struct GasTracker {
     gas_used: Cell<Gas>,
     gas_snapshots: Vec<GasSnapshot>,
     trace: Option<RefCell<Vec<GasCharge>>>,
+    /// The total gas charged for allocating wasm memory (initial and grown).
+    memory_gas_used: Cell<Gas>,
+    /// The gas charged per call (only when tracing).
+    call_gas: Option<RefCell<CallGasRecorder>>,
+    /// The last [`RECENT_GAS_CHARGES`] charges (only when recording recent charges).
+    recent_charges: Option<RefCell<VecDeque<GasCharge>>>,
+    /// A snapshot of `recent_charges` taken when we last ran out of gas.
+    exhausted: RefCell<Option<Vec<GasCharge>>>,
 }
 
 impl GasTracker {
@@ -186,29 +210,81 @@ impl GasTracker {
             gas_used: Cell::new(gas_used),
             gas_snapshots: Vec::new(),
             trace: enable_tracing.then_some(Default::default()),
+            memory_gas_used: Default::default(),
+            call_gas: enable_tracing.then_some(Default::default()),
+            recent_charges: None,
+            exhausted: Default::default(),
         }
     }
 
-    fn charge_gas_inner(&self, to_use: Gas) -> Result<()> {
+    /// Records the last [`RECENT_GAS_CHARGES`] charges, to be returned by
+    /// [`GasTracker::take_exhausted`] when we run out of gas. Recording clones every charge, so
+    /// this is disabled by default.
+    pub fn with_recent_charges(mut self) -> Self {
+        self.recent_charges = Some(RefCell::new(VecDeque::with_capacity(RECENT_GAS_CHARGES)));
+        self
+    }
+
+    fn charge_gas_inner(&self, charge: &GasCharge) -> Result<()> {
+        let res = self.consume(charge.total());
+        self.record_recent(std::slice::from_ref(charge), res.is_err());
+        res
+    }
+
+    /// Consumes the given amount of gas, failing (and using all remaining gas) if that exceeds the
+    /// gas limit.
+    fn consume(&self, to_use: Gas) -> Result<()> {
         // The gas type uses saturating math.
-        let gas_used = self.gas_used.get() + to_use;
-        if gas_used > self.gas_limit {
+        let prev_gas_used = self.gas_used.get();
+        let gas_used = prev_gas_used + to_use;
+        let res = if gas_used > self.gas_limit {
             log::trace!("gas limit reached");
             self.gas_used.set(self.gas_limit);
             Err(ExecutionError::OutOfGas)
         } else {
             self.gas_used.set(gas_used);
             Ok(())
+        };
+        if let Some(call_gas) = &self.call_gas {
+            call_gas
+                .borrow_mut()
+                .record(self.gas_used.get() - prev_gas_used);
+        }
+        res
+    }
+
+    /// Records the given charges as the most recent ones (if recording recent charges), taking a
+    /// snapshot if they exhausted the gas budget.
+    fn record_recent(&self, charges: &[GasCharge], exhausted: bool) {
+        if let Some(recent) = &self.recent_charges {
+            let mut recent = recent.borrow_mut();
+            for charge in charges {
+                if recent.len() == RECENT_GAS_CHARGES {
+                    recent.pop_front();
+                }
+                recent.push_back(charge.clone());
+            }
+        }
+        if exhausted {
+            let recent = self
+                .recent_charges
+                .as_ref()
+                .map(|recent| recent.borrow().iter().cloned().collect())
+                .unwrap_or_default();
+            self.exhausted.replace(Some(recent));
         }
     }
 
     /// Safely consumes gas and returns an out of gas error if there is not sufficient
     /// enough gas remaining for charge.
-    pub fn charge_gas(&self, name: &str, to_use: Gas) -> Result<GasTimer> {
+    pub fn charge_gas(&self, name: impl Into<GasChargeName>, to_use: Gas) -> Result<GasTimer> {
+        let name = name.into();
         log::trace!("charging gas: {} {}", name, to_use);
-        let res = self.charge_gas_inner(to_use);
+        #[cfg(feature = "metrics")]
+        crate::metrics::record_gas(&crate::metrics::GAS_COMPUTE, to_use);
+        let mut charge = GasCharge::new(name, to_use, Gas::zero());
+        let res = self.charge_gas_inner(&charge);
         if let Some(trace) = &self.trace {
-            let mut charge = GasCharge::new(name.to_owned(), to_use, Gas::zero());
             let timer = GasTimer::new(&mut charge.elapsed);
             trace.borrow_mut().push(charge);
             res.map(|_| timer)
@@ -221,7 +297,83 @@ impl GasTracker {
     pub fn apply_charge(&self, mut charge: GasCharge) -> Result<GasTimer> {
         let to_use = charge.total();
         log::trace!("charging gas: {} {}", &charge.name, to_use);
-        let res = self.charge_gas_inner(to_use);
+        #[cfg(feature = "metrics")]
+        {
+            crate::metrics::record_gas(&crate::metrics::GAS_COMPUTE, charge.compute_gas);
+            crate::metrics::record_gas(&crate::metrics::GAS_OTHER, charge.other_gas);
+        }
+        let res = self.charge_gas_inner(&charge);
+        if let Some(trace) = &self.trace {
+            let timer = GasTimer::new(&mut charge.elapsed);
+            trace.borrow_mut().push(charge);
+            res.map(|_| timer)
+        } else {
+            res.map(|_| GasTimer::empty())
+        }
+    }
+
+    /// Applies several related charges at once, checking their total against the gas limit a single
+    /// time. Each charge is still traced on its own, and gets its own timer (returned in order).
+    ///
+    /// This is equivalent to applying the charges one by one, back to back: either way, running out
+    /// of gas uses all the remaining gas.
+    pub fn apply_charges(&self, charges: &[GasCharge]) -> Result<Vec<GasTimer>> {
+        let to_use = charges
+            .iter()
+            .fold(Gas::zero(), |total, charge| total + charge.total());
+        log::trace!("charging gas: {} charges {}", charges.len(), to_use);
+        #[cfg(feature = "metrics")]
+        for charge in charges {
+            crate::metrics::record_gas(&crate::metrics::GAS_COMPUTE, charge.compute_gas);
+            crate::metrics::record_gas(&crate::metrics::GAS_OTHER, charge.other_gas);
+        }
+        let res = self.consume(to_use);
+        self.record_recent(charges, res.is_err());
+        if let Some(trace) = &self.trace {
+            let mut trace = trace.borrow_mut();
+            let timers = charges
+                .iter()
+                .cloned()
+                .map(|mut charge| {
+                    let timer = GasTimer::new(&mut charge.elapsed);
+                    trace.push(charge);
+                    timer
+                })
+                .collect();
+            res.map(|_| timers)
+        } else {
+            res.map(|_| charges.iter().map(|_| GasTimer::empty()).collect())
+        }
+    }
+
+    /// Applies a charge for copying data between an actor's memory and the host. Copy charges are
+    /// traced like any other charge, but are also recorded separately in the gas metrics.
+    pub fn charge_copy(&self, charge: GasCharge) -> Result<()> {
+        let to_use = charge.total();
+        log::trace!("charging gas: {} {}", &charge.name, to_use);
+        #[cfg(feature = "metrics")]
+        crate::metrics::record_gas(&crate::metrics::GAS_COPY, to_use);
+        let res = self.charge_gas_inner(&charge);
+        if let Some(trace) = &self.trace {
+            trace.borrow_mut().push(charge);
+        }
+        res
+    }
+
+    /// Applies a charge for allocating wasm memory. Memory charges are accounted separately from
+    /// other charges (see [`GasTracker::memory_gas_used`]), and are traced as "other" gas because
+    /// they don't correspond to any measured computation.
+    pub fn charge_memory(&self, name: impl Into<GasChargeName>, to_use: Gas) -> Result<GasTimer> {
+        let name = name.into();
+        log::trace!("charging memory gas: {} {}", name, to_use);
+        #[cfg(feature = "metrics")]
+        crate::metrics::record_gas(&crate::metrics::GAS_MEMORY, to_use);
+        let mut charge = GasCharge::new(name, Gas::zero(), to_use);
+        let res = self.charge_gas_inner(&charge);
+        if res.is_ok() {
+            self.memory_gas_used
+                .set(self.memory_gas_used.get() + to_use);
+        }
         if let Some(trace) = &self.trace {
             let timer = GasTimer::new(&mut charge.elapsed);
             trace.borrow_mut().push(charge);
@@ -231,6 +383,42 @@ impl GasTracker {
         }
     }
 
+    /// Record that a call to the given actor and method has started. Subsequent charges are
+    /// attributed to this call until it exits (only when tracing).
+    pub fn enter_call(&mut self, to: Address, method: MethodNum) {
+        if let Some(call_gas) = &mut self.call_gas {
+            call_gas.get_mut().enter(to, method);
+        }
+    }
+
+    /// Record that the current call has exited.
+    pub fn exit_call(&mut self) {
+        if let Some(call_gas) = &mut self.call_gas {
+            call_gas.get_mut().exit();
+        }
+    }
+
+    /// Takes the gas usage trees of all completed top-level calls (empty unless tracing).
+    pub fn take_call_gas(&mut self) -> Vec<CallGasUsage> {
+        self.call_gas
+            .as_mut()
+            .map(|call_gas| call_gas.get_mut().take())
+            .unwrap_or_default()
+    }
+
+    /// Returns true if charges are recorded, either traced or kept as recent charges. Charge names
+    /// are discarded otherwise, so callers may skip building them.
+    pub fn records_charges(&self) -> bool {
+        self.trace.is_some() || self.recent_charges.is_some()
+    }
+
+    /// Takes the last [`RECENT_GAS_CHARGES`] charges (oldest first) recorded when we last ran out of
+    /// gas, if we've run out of gas since this was last called. The charges are empty unless
+    /// recording recent charges (see [`GasTracker::with_recent_charges`]).
+    pub fn take_exhausted(&self) -> Option<Vec<GasCharge>> {
+        self.exhausted.take()
+    }
+
     /// Push a new gas limit.
     pub fn push_limit(&mut self, new_limit: Gas) {
         self.gas_snapshots.push(GasSnapshot {
@@ -264,6 +452,12 @@ impl GasTracker {
         self.gas_used.get()
     }
 
+    /// Getter for the gas charged for allocating wasm memory, over all gas limits. This is included
+    /// in [`GasTracker::gas_used`].
+    pub fn memory_gas_used(&self) -> Gas {
+        self.memory_gas_used.get()
+    }
+
     /// Getter for gas available.
     pub fn gas_available(&self) -> Gas {
         self.gas_limit - self.gas_used.get()
@@ -308,6 +502,138 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn copy_charges_are_traced_in_order() -> Result<()> {
+        let t = GasTracker::new(Gas::new(20), Gas::zero(), true);
+        t.charge_copy(GasCharge::new("OnCopy", Gas::new(2), Gas::zero()))?;
+        t.apply_charge(GasCharge::new("OnOther", Gas::new(1), Gas::zero()))?;
+        t.charge_copy(GasCharge::new("OnCopy", Gas::new(3), Gas::zero()))?;
+        assert_eq!(t.gas_used(), Gas::new(6));
+
+        let trace: Vec<_> = t.drain_trace().collect();
+        let trace: Vec<_> = trace.iter().map(|c| (&*c.name, c.total())).collect();
+        assert_eq!(
+            trace,
+            [
+                ("OnCopy", Gas::new(2)),
+                ("OnOther", Gas::new(1)),
+                ("OnCopy", Gas::new(3)),
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn memory_charges_are_separate() -> Result<()> {
+        let t = GasTracker::new(Gas::new(20), Gas::zero(), true);
+        t.charge_gas("OnOther", Gas::new(1))?;
+        t.charge_memory("OnMemory", Gas::new(2))?;
+        assert_eq!(t.gas_used(), Gas::new(3));
+        assert_eq!(t.memory_gas_used(), Gas::new(2));
+
+        let trace: Vec<_> = t.drain_trace().collect();
+        assert_eq!(trace[1].name, "OnMemory");
+        assert_eq!(trace[1].compute_gas, Gas::zero());
+        assert_eq!(trace[1].other_gas, Gas::new(2));
+
+        // Failed charges aren't counted.
+        assert!(t.charge_memory("OnMemory", Gas::new(100)).is_err());
+        assert_eq!(t.memory_gas_used(), Gas::new(2));
+        Ok(())
+    }
+
+    #[test]
+    fn charges_applied_together() -> Result<()> {
+        let t = GasTracker::new(Gas::new(20), Gas::zero(), true);
+        t.apply_charges(&[
+            GasCharge::new("OnFirst", Gas::new(1), Gas::new(2)),
+            GasCharge::new("OnSecond", Gas::new(3), Gas::new(4)),
+        ])?;
+        assert_eq!(t.gas_used(), Gas::new(10));
+
+        // Nothing to charge.
+        t.apply_charges(&[])?;
+        assert_eq!(t.gas_used(), Gas::new(10));
+
+        let trace: Vec<_> = t.drain_trace().collect();
+        assert_eq!(trace.len(), 2);
+        assert_eq!(trace[0].name, "OnFirst");
+        assert_eq!(trace[0].compute_gas, Gas::new(1));
+        assert_eq!(trace[0].other_gas, Gas::new(2));
+        assert_eq!(trace[1].name, "OnSecond");
+        assert_eq!(trace[1].compute_gas, Gas::new(3));
+        assert_eq!(trace[1].other_gas, Gas::new(4));
+
+        // Running out of gas uses all the remaining gas, as if the charges were applied one by one.
+        assert!(t
+            .apply_charges(&[
+                GasCharge::new("OnFirst", Gas::new(5), Gas::zero()),
+                GasCharge::new("OnSecond", Gas::new(6), Gas::zero()),
+            ])
+            .is_err());
+        assert_eq!(t.gas_used(), Gas::new(20));
+        Ok(())
+    }
+
+    #[test]
+    fn recent_charges_on_exhaustion() -> Result<()> {
+        let t = GasTracker::new(Gas::new(100), Gas::zero(), false).with_recent_charges();
+        for _ in 0..RECENT_GAS_CHARGES {
+            t.charge_gas("OnEarly", Gas::new(1))?;
+        }
+        t.charge_gas("OnLate", Gas::new(2))?;
+        assert!(t.take_exhausted().is_none());
+
+        assert!(t.charge_gas("OnExhaust", Gas::new(1000)).is_err());
+        let recent = t.take_exhausted().expect("should have run out of gas");
+        assert_eq!(recent.len(), RECENT_GAS_CHARGES);
+        assert_eq!(recent[RECENT_GAS_CHARGES - 2].name, "OnLate");
+        assert_eq!(recent[RECENT_GAS_CHARGES - 1].name, "OnExhaust");
+        assert_eq!(recent[RECENT_GAS_CHARGES - 1].compute_gas, Gas::new(1000));
+
+        // The snapshot is only taken once.
+        assert!(t.take_exhausted().is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn recent_charges_disabled() {
+        let t = GasTracker::new(Gas::new(100), Gas::zero(), false);
+        t.charge_gas("OnEarly", Gas::new(1)).unwrap();
+        assert!(t.charge_gas("OnExhaust", Gas::new(1000)).is_err());
+        let recent = t.take_exhausted().expect("should have run out of gas");
+        assert!(recent.is_empty());
+    }
+
+    #[test]
+    fn call_gas_tree() -> Result<()> {
+        let mut t = GasTracker::new(Gas::new(20), Gas::zero(), true);
+        t.charge_gas("OnOutside", Gas::new(1))?;
+        t.enter_call(Address::new_id(1), 2);
+        t.charge_gas("OnParent", Gas::new(2))?;
+        t.enter_call(Address::new_id(3), 4);
+        t.charge_gas("OnChild", Gas::new(3))?;
+        t.exit_call();
+        t.charge_gas("OnParent", Gas::new(4))?;
+        t.exit_call();
+
+        let roots = t.take_call_gas();
+        assert_eq!(roots.len(), 1);
+        let root = &roots[0];
+        assert_eq!((root.to, root.method), (Address::new_id(1), 2));
+        assert_eq!(root.local, Gas::new(6));
+        assert_eq!(root.total(), Gas::new(9));
+        assert_eq!(root.children.len(), 1);
+        assert_eq!(root.children[0].local, Gas::new(3));
+
+        // Only the gas actually charged is attributed when running out of gas.
+        t.enter_call(Address::new_id(1), 2);
+        assert!(t.charge_gas("OnParent", Gas::new(100)).is_err());
+        t.exit_call();
+        assert_eq!(t.take_call_gas()[0].local, Gas::new(10));
+        Ok(())
+    }
+
     #[test]
     fn milligas_to_gas_round() {
         assert_eq!(milligas_to_gas(100, false), 0);
@@ -1,7 +1,69 @@
 // Copyright 2021-2023 Protocol Labs
 // SPDX-License-Identifier: Apache-2.0, MIT
 
+use fvm_shared::clock::ChainEpoch;
 use fvm_shared::econ::TokenAmount;
+use fvm_shared::ActorID;
+
+use crate::machine::{BURNT_FUNDS_ACTOR_ID, REWARD_ACTOR_ID};
+
+/// Decides how much of the gas a message didn't use is burned (as a penalty for over-estimating
+/// the gas limit), and how much is refunded to the sender.
+///
+/// The default policy is [`FilecoinOverestimationBurn`]. Networks with different fee market rules
+/// can supply their own with
+/// [`DefaultExecutor::with_overestimation_burn`](crate::executor::DefaultExecutor::with_overestimation_burn).
+/// This affects the fees charged to every message, so it's consensus-critical.
+pub trait OverestimationBurnPolicy: Send + Sync {
+    /// Returns the gas to refund and the gas to burn, in whole gas units, given the gas used and the
+    /// gas limit. The two must never add up to more than the unused gas (`gas_limit - gas_used`).
+    fn compute(&self, gas_used: u64, gas_limit: u64) -> (u64, u64);
+}
+
+/// The Filecoin over-estimation burn: refunds the unused gas up to 10% over the gas used, and burns
+/// an increasing fraction of the unused gas beyond that.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct FilecoinOverestimationBurn;
+
+impl OverestimationBurnPolicy for FilecoinOverestimationBurn {
+    fn compute(&self, gas_used: u64, gas_limit: u64) -> (u64, u64) {
+        compute_gas_overestimation_burn(gas_used, gas_limit)
+    }
+}
+
+/// Decides which actors receive the fees paid by messages: the miner tip and the burnt funds (the
+/// base fee burn and the over-estimation burn).
+///
+/// The default is [`FilecoinFeeDistribution`]. Networks with a custom reward actor or fee split
+/// schedules can supply their own with
+/// [`DefaultExecutor::with_fee_distribution`](crate::executor::DefaultExecutor::with_fee_distribution).
+/// This is consensus-critical.
+///
+/// Note that [`DefaultExecutor::apply_tipset`](crate::executor::DefaultExecutor::apply_tipset)
+/// still has the reward actor pay out the full miner tip to block miners, so networks redirecting
+/// the tip should apply their tipsets themselves.
+pub trait FeeDistribution: Send + Sync {
+    /// Returns the actors receiving the miner tip paid by a message applied at the given epoch,
+    /// along with the amount each receives. The amounts must add up to the tip.
+    fn miner_tip(&self, epoch: ChainEpoch, tip: &TokenAmount) -> Vec<(ActorID, TokenAmount)> {
+        let _ = epoch;
+        vec![(REWARD_ACTOR_ID, tip.clone())]
+    }
+
+    /// Returns the actors receiving the funds burnt by a message applied at the given epoch, along
+    /// with the amount each receives. The amounts must add up to the burnt funds.
+    fn burn(&self, epoch: ChainEpoch, burnt: &TokenAmount) -> Vec<(ActorID, TokenAmount)> {
+        let _ = epoch;
+        vec![(BURNT_FUNDS_ACTOR_ID, burnt.clone())]
+    }
+}
+
+/// The Filecoin fee distribution: miner tips go to the reward actor, and burnt funds to the burnt
+/// funds actor.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct FilecoinFeeDistribution;
+
+impl FeeDistribution for FilecoinFeeDistribution {}
 
 #[derive(Clone, Default)]
 pub(crate) struct GasOutputs {
@@ -24,6 +86,7 @@ impl GasOutputs {
         base_fee: &TokenAmount,
         fee_cap: &TokenAmount,
         gas_premium: &TokenAmount,
+        burn_policy: &dyn OverestimationBurnPolicy,
     ) -> Self {
         let mut base_fee_to_pay = base_fee;
 
@@ -42,7 +105,7 @@ impl GasOutputs {
         }
         out.miner_tip = &miner_tip * gas_limit;
 
-        let (out_gas_refund, out_gas_burned) = compute_gas_overestimation_burn(gas_used, gas_limit);
+        let (out_gas_refund, out_gas_burned) = burn_policy.compute(gas_used, gas_limit);
         out.gas_refund = out_gas_refund;
         out.gas_burned = out_gas_burned;
 
@@ -134,6 +197,7 @@ fn gas_outputs_test() {
             &base_fee,
             &TokenAmount::from_atto(fee_cap),
             &TokenAmount::from_atto(premium),
+            &FilecoinOverestimationBurn,
         );
         assert_eq!(
             TokenAmount::from_atto(base_fee_burn),
@@ -162,3 +226,31 @@ fn gas_outputs_test() {
     do_test(100, 110, 10, 1, 1_000, 0, 0, 0, 100);
     do_test(100, 110, 6, 1, 600, 0, 400, 0, 60);
 }
+
+#[test]
+fn custom_burn_policy_test() {
+    /// Refunds all unused gas.
+    struct NoBurn;
+
+    impl OverestimationBurnPolicy for NoBurn {
+        fn compute(&self, gas_used: u64, gas_limit: u64) -> (u64, u64) {
+            (gas_limit.saturating_sub(gas_used), 0)
+        }
+    }
+
+    let output = GasOutputs::compute(
+        100,
+        200,
+        &TokenAmount::from_atto(10),
+        &TokenAmount::from_atto(11),
+        &TokenAmount::from_atto(1),
+        &NoBurn,
+    );
+    assert_eq!(output.gas_refund, 100);
+    assert_eq!(output.gas_burned, 0);
+    assert_eq!(output.over_estimation_burn, TokenAmount::from_atto(0));
+    assert_eq!(
+        output.refund,
+        TokenAmount::from_atto(11 * 200 - 10 * 100 - 200)
+    );
+}
@@ -19,13 +19,15 @@ use fvm_wasm_instrument::gas_metering::{InstructionCost, Operator, Rules};
 use lazy_static::lazy_static;
 use num_traits::Zero;
 
-use super::GasCharge;
+use super::{GasCharge, GasChargeName};
 use crate::gas::Gas;
 use crate::kernel::SupportedHashes;
+use crate::machine::NetworkSchedule;
 
 // Each element reserves a `usize` in the table, so we charge 8 bytes per pointer.
 // https://docs.rs/wasmtime/2.0.2/wasmtime/struct.InstanceLimits.html#structfield.table_elements
 const TABLE_ELEMENT_SIZE: u32 = 8;
+const WASM_PAGE_SIZE: u32 = wasmtime_environ::WASM_PAGE_SIZE;
 
 /// Create a mapping from enum items to values in a way that guarantees at compile
 /// time that we did not miss any member, in any of the prices, even if the enum
@@ -97,6 +99,11 @@ lazy_static! {
                     flat: Gas::new(16598605),
                     scale: Gas::new(26),
                 },
+                // Priced as a secp256k1 signature: the keccak hashing is negligible in comparison.
+                Delegated => ScalingCost {
+                    flat: Gas::new(1637292),
+                    scale: Gas::new(10),
+                },
             }
         },
         secp256k1_recover_cost: Gas::new(1637292),
@@ -230,6 +237,16 @@ lazy_static! {
         // TODO(#1384): Reprice
         get_randomness_seed: Gas::new(21000),
 
+        // Verifying a beacon entry is a single BLS signature verification over the 32 byte
+        // sha256 digest of the previous signature (if chained) and the round. We price the
+        // verification as a BLS signature over a 32 byte message, 16598605 (flat) + 26 * 32
+        // (scale), and charge the BLS per-byte price for both signatures, which covers the
+        // hashing and the signature decoding.
+        verify_beacon_entry: ScalingCost {
+            flat: Gas::new(16599437),
+            scale: Gas::new(26),
+        },
+
         block_allocate: ScalingCost {
             flat: Gas::zero(),
             scale: Gas::new(2),
@@ -266,6 +283,10 @@ lazy_static! {
 
         syscall_cost: Gas::new(14000),
 
+        // Copies between actor memory and the host are accounted for by the individual syscall
+        // charges (e.g., block reads and writes) until nv21, which prices them like memcpy.
+        syscall_copy: ScalingCost::zero(),
+
         // TODO(#1347)
         builtin_actor_manifest_lookup: Zero::zero(),
         // TODO(#1347)
@@ -291,6 +312,10 @@ lazy_static! {
             // Charge 0.4gas/byte for copying/fill.
             memory_copy_per_byte_cost: Gas::from_milligas(400),
             memory_fill_per_byte_cost: Gas::from_milligas(400),
+
+            // Charge for growing memory like filling it: 0.4gas/byte, or 26214.4gas/page.
+            memory_grow_base_cost: Gas::zero(),
+            memory_grow_per_page_cost: Gas::from_milligas(400 * WASM_PAGE_SIZE as u64),
         },
 
         // These parameters are specifically sized for EVM events. They will need
@@ -320,6 +345,12 @@ lazy_static! {
         // Preloaded actor IDs per FIP-0055.
         preloaded_actors: vec![0, 1, 2, 3, 4, 5, 6, 7, 10, 99],
     };
+
+    static ref WATERMELON_PRICES: PriceList = PriceList {
+        // Copies between actor memory and the host cost as much as copying memory in the host.
+        syscall_copy: HYGGE_PRICES.block_memcpy,
+        ..HYGGE_PRICES.clone()
+    };
 }
 
 #[derive(Clone, Debug, Copy, PartialEq, Eq)]
@@ -438,6 +469,10 @@ pub struct PriceList {
     /// randomness (hashing).
     pub(crate) get_randomness_seed: Gas,
 
+    /// Gas cost for verifying a beacon entry against the network's beacon chain, scaling with
+    /// the length of the signatures.
+    pub(crate) verify_beacon_entry: ScalingCost,
+
     /// Gas cost per byte copied.
     pub(crate) block_memcpy: ScalingCost,
 
@@ -463,6 +498,9 @@ pub struct PriceList {
     /// General gas cost for performing a syscall, accounting for the overhead thereof.
     pub(crate) syscall_cost: Gas,
 
+    /// Gas cost for copying data between an actor's memory and the host, per byte copied.
+    pub(crate) syscall_copy: ScalingCost,
+
     /// Rules for execution gas.
     pub(crate) wasm_rules: WasmGasPrices,
 
@@ -488,29 +526,39 @@ pub struct PriceList {
     pub(crate) preloaded_actors: Vec<ActorID>,
 }
 
+/// The costs of wasm instructions, injected into actor code by the gas instrumentation.
+///
+/// Instructions are priced per class (e.g., math, jumps, memory accesses). Changing any of these
+/// prices changes the instrumented code, so engines compile separate modules for each distinct
+/// set of prices.
 #[derive(Clone, Debug, Eq, PartialEq, Hash)]
 pub struct WasmGasPrices {
     /// The default gas cost for instructions.
-    pub(crate) instruction_default: Gas,
+    pub instruction_default: Gas,
     /// The default gas cost for math instructions.
-    pub(crate) math_default: Gas,
+    pub math_default: Gas,
     /// The gas cost for unconditional jumps.
-    pub(crate) jump_unconditional: Gas,
+    pub jump_unconditional: Gas,
     /// The gas cost for conditional jumps.
-    pub(crate) jump_conditional: Gas,
+    pub jump_conditional: Gas,
     /// The gas cost for indirect jumps.
-    pub(crate) jump_indirect: Gas,
+    pub jump_indirect: Gas,
     /// The gas cost for calls (not including the jump cost).
-    pub(crate) call: Gas,
+    pub call: Gas,
 
     /// Gas cost for any memory fill instruction (one time charge).
-    pub(crate) memory_fill_base_cost: Gas,
+    pub memory_fill_base_cost: Gas,
     /// Gas cost for every byte "filled" in Wasm memory.
-    pub(crate) memory_fill_per_byte_cost: Gas,
+    pub memory_fill_per_byte_cost: Gas,
     /// Gas cost for any memory copy instruction (one time charge).
-    pub(crate) memory_access_cost: Gas,
+    pub memory_access_cost: Gas,
     /// Gas cost for every byte copied in Wasm memory.
-    pub(crate) memory_copy_per_byte_cost: Gas,
+    pub memory_copy_per_byte_cost: Gas,
+
+    /// Gas cost for any memory grow instruction (one time charge).
+    pub memory_grow_base_cost: Gas,
+    /// Gas cost for every (64KiB) page by which Wasm memory is grown.
+    pub memory_grow_per_page_cost: Gas,
 }
 
 impl PriceList {
@@ -519,7 +567,7 @@ impl PriceList {
     #[inline]
     pub fn on_chain_message(&self, msg_size: usize) -> GasCharge {
         GasCharge::new(
-            "OnChainMessage",
+            GasChargeName::OnChainMessage,
             self.on_chain_message_compute.apply(msg_size),
             self.actor_update + self.on_chain_message_storage.apply(msg_size),
         )
@@ -528,13 +576,21 @@ impl PriceList {
     /// Returns the gas required when invoking a method.
     #[inline]
     pub fn on_value_transfer(&self) -> GasCharge {
-        GasCharge::new("OnValueTransfer", self.send_transfer_funds, Zero::zero())
+        GasCharge::new(
+            GasChargeName::OnValueTransfer,
+            self.send_transfer_funds,
+            Zero::zero(),
+        )
     }
 
     /// Returns the gas required when invoking a method.
     #[inline]
     pub fn on_method_invocation(&self) -> GasCharge {
-        GasCharge::new("OnMethodInvocation", self.send_invoke_method, Zero::zero())
+        GasCharge::new(
+            GasChargeName::OnMethodInvocation,
+            self.send_invoke_method,
+            Zero::zero(),
+        )
     }
 
     /// Returns the gas required for storing the response of a message in the chain.
@@ -542,7 +598,7 @@ impl PriceList {
     pub fn on_method_return(&self, call_depth: u32, data_size: u32) -> Option<GasCharge> {
         (call_depth == 1).then(|| {
             GasCharge::new(
-                "OnChainReturnValue",
+                GasChargeName::OnChainReturnValue,
                 self.on_chain_return_compute.apply(data_size),
                 self.on_chain_return_storage.apply(data_size),
             )
@@ -551,7 +607,17 @@ impl PriceList {
 
     /// Returns the gas cost to be applied on a syscall.
     pub fn on_syscall(&self) -> GasCharge {
-        GasCharge::new("OnSyscall", self.syscall_cost, Zero::zero())
+        GasCharge::new(GasChargeName::OnSyscall, self.syscall_cost, Zero::zero())
+    }
+
+    /// Returns the gas required for copying data between an actor's memory and the host.
+    #[inline]
+    pub fn on_copy(&self, bytes: usize) -> GasCharge {
+        GasCharge::new(
+            GasChargeName::OnCopy,
+            self.syscall_copy.apply(bytes),
+            Zero::zero(),
+        )
     }
 
     /// Returns the gas required for creating an actor. Pass `true` to when explicitly assigning a
@@ -562,13 +628,13 @@ impl PriceList {
         if new_address {
             gas += self.address_assignment + self.address_lookup;
         }
-        GasCharge::new("OnCreateActor", Zero::zero(), gas)
+        GasCharge::new(GasChargeName::OnCreateActor, Zero::zero(), gas)
     }
 
     /// Returns the gas required for deleting an actor.
     #[inline]
     pub fn on_delete_actor(&self) -> GasCharge {
-        GasCharge::new("OnDeleteActor", Zero::zero(), Zero::zero())
+        GasCharge::new(GasChargeName::OnDeleteActor, Zero::zero(), Zero::zero())
     }
 
     /// Returns gas required for signature verification.
@@ -576,14 +642,14 @@ impl PriceList {
     pub fn on_verify_signature(&self, sig_type: SignatureType, data_len: usize) -> GasCharge {
         let cost = self.sig_cost[&sig_type];
         let gas = cost.apply(data_len);
-        GasCharge::new("OnVerifySignature", gas, Zero::zero())
+        GasCharge::new(GasChargeName::OnVerifySignature, gas, Zero::zero())
     }
 
     /// Returns gas required for recovering signer pubkey from signature
     #[inline]
     pub fn on_recover_secp_public_key(&self) -> GasCharge {
         GasCharge::new(
-            "OnRecoverSecpPublicKey",
+            GasChargeName::OnRecoverSecpPublicKey,
             self.secp256k1_recover_cost,
             Zero::zero(),
         )
@@ -594,7 +660,7 @@ impl PriceList {
     pub fn on_hashing(&self, hasher: SupportedHashes, data_len: usize) -> GasCharge {
         let cost = self.hashing_cost[&hasher];
         let gas = cost.apply(data_len);
-        GasCharge::new("OnHashing", gas, Zero::zero())
+        GasCharge::new(GasChargeName::OnHashing, gas, Zero::zero())
     }
 
     /// Returns gas required for computing unsealed sector Cid.
@@ -605,7 +671,7 @@ impl PriceList {
         _pieces: &[PieceInfo],
     ) -> GasCharge {
         GasCharge::new(
-            "OnComputeUnsealedSectorCid",
+            GasChargeName::OnComputeUnsealedSectorCid,
             self.compute_unsealed_sector_cid_base,
             Zero::zero(),
         )
@@ -614,7 +680,11 @@ impl PriceList {
     /// Returns gas required for seal verification.
     #[inline]
     pub fn on_verify_seal(&self, _info: &SealVerifyInfo) -> GasCharge {
-        GasCharge::new("OnVerifySeal", self.verify_seal_base, Zero::zero())
+        GasCharge::new(
+            GasChargeName::OnVerifySeal,
+            self.verify_seal_base,
+            Zero::zero(),
+        )
     }
     #[inline]
     pub fn on_verify_aggregate_seals(
@@ -646,7 +716,7 @@ impl PriceList {
         // Should be safe because there is a limit to how much seals get aggregated
         let num = aggregate.infos.len() as u64;
         GasCharge::new(
-            "OnVerifyAggregateSeals",
+            GasChargeName::OnVerifyAggregateSeals,
             per_proof * num + step.lookup(num),
             Zero::zero(),
         )
@@ -656,7 +726,7 @@ impl PriceList {
     #[inline]
     pub fn on_verify_replica_update(&self, _replica: &ReplicaUpdateInfo) -> GasCharge {
         GasCharge::new(
-            "OnVerifyReplicaUpdate",
+            GasChargeName::OnVerifyReplicaUpdate,
             self.verify_replica_update,
             Zero::zero(),
         )
@@ -678,7 +748,7 @@ impl PriceList {
 
         let gas_used = cost.apply(info.challenged_sectors.len());
 
-        GasCharge::new("OnVerifyPost", gas_used, Zero::zero())
+        GasCharge::new(GasChargeName::OnVerifyPost, gas_used, Zero::zero())
     }
 
     /// Returns gas required for verifying consensus fault.
@@ -690,7 +760,7 @@ impl PriceList {
         _extra_len: usize,
     ) -> GasCharge {
         GasCharge::new(
-            "OnVerifyConsensusFault",
+            GasChargeName::OnVerifyConsensusFault,
             Zero::zero(),
             self.verify_consensus_fault,
         )
@@ -709,7 +779,7 @@ impl PriceList {
                 8;
 
         GasCharge::new(
-            "OnGetRandomness",
+            GasChargeName::OnGetRandomness,
             Zero::zero(),
             self.get_randomness_seed
                 + self.hashing_cost[&SupportedHashes::Blake2b256]
@@ -717,10 +787,26 @@ impl PriceList {
         )
     }
 
+    /// Returns the gas required for verifying a beacon entry, given the lengths of the previous
+    /// signature and the signature.
+    #[inline]
+    pub fn on_verify_beacon_entry(&self, prev_sig_len: usize, sig_len: usize) -> GasCharge {
+        GasCharge::new(
+            GasChargeName::OnVerifyBeaconEntry,
+            self.verify_beacon_entry
+                .apply(prev_sig_len.saturating_add(sig_len)),
+            Zero::zero(),
+        )
+    }
+
     /// Returns the base gas required for loading an object, independent of the object's size.
     #[inline]
     pub fn on_block_open_base(&self) -> GasCharge {
-        GasCharge::new("OnBlockOpenBase", Zero::zero(), self.block_open.flat)
+        GasCharge::new(
+            GasChargeName::OnBlockOpenBase,
+            Zero::zero(),
+            self.block_open.flat,
+        )
     }
 
     /// Returns the gas required for loading an object based on the size of the object.
@@ -734,7 +820,7 @@ impl PriceList {
         let retention_min = self.block_memory_retention_minimum.apply(data_size);
         let retention_surcharge = (retention_min - (compute + block_open)).max(Gas::zero());
         GasCharge::new(
-            "OnBlockOpenPerByte",
+            GasChargeName::OnBlockOpenPerByte,
             compute,
             // We charge the `block_open` fee as "extra" to make sure the FVM benchmarks still work.
             block_open + retention_surcharge,
@@ -745,7 +831,7 @@ impl PriceList {
     #[inline]
     pub fn on_block_read(&self, data_size: usize) -> GasCharge {
         GasCharge::new(
-            "OnBlockRead",
+            GasChargeName::OnBlockRead,
             self.block_memcpy.apply(data_size),
             Zero::zero(),
         )
@@ -761,7 +847,7 @@ impl PriceList {
         let retention_min = self.block_memory_retention_minimum.apply(data_size);
         let retention_surcharge = (retention_min - compute).max(Gas::zero());
 
-        GasCharge::new("OnBlockCreate", compute, retention_surcharge)
+        GasCharge::new(GasChargeName::OnBlockCreate, compute, retention_surcharge)
     }
 
     /// Returns the gas required for committing an object to the state blockstore.
@@ -783,45 +869,72 @@ impl PriceList {
         // per-byte charges combined, so we ignore them for simplicity.
         let deferred_compute = self.block_persist_compute;
 
-        GasCharge::new("OnBlockLink", initial_compute, deferred_compute + storage)
+        GasCharge::new(
+            GasChargeName::OnBlockLink,
+            initial_compute,
+            deferred_compute + storage,
+        )
     }
 
     /// Returns the gas required for storing an object.
     #[inline]
     pub fn on_block_stat(&self) -> GasCharge {
-        GasCharge::new("OnBlockStat", Zero::zero(), Zero::zero())
+        GasCharge::new(GasChargeName::OnBlockStat, Zero::zero(), Zero::zero())
+    }
+
+    /// Returns the gas required for enumerating the links of a block. Scanning a block for links
+    /// is a linear pass over the block, priced like a copy.
+    #[inline]
+    pub fn on_block_links(&self, data_size: usize) -> GasCharge {
+        GasCharge::new(
+            GasChargeName::OnBlockLinks,
+            self.block_memcpy.apply(data_size),
+            Zero::zero(),
+        )
     }
 
     /// Returns the gas required to lookup an actor in the state-tree.
     #[inline]
     pub fn on_actor_lookup(&self) -> GasCharge {
-        GasCharge::new("OnActorLookup", Zero::zero(), self.actor_lookup)
+        GasCharge::new(
+            GasChargeName::OnActorLookup,
+            Zero::zero(),
+            self.actor_lookup,
+        )
     }
 
     /// Returns the gas required to update an actor in the state-tree. Assumes that the actor lookup
     /// fee has already been charged.
     #[inline]
     pub fn on_actor_update(&self) -> GasCharge {
-        GasCharge::new("OnActorUpdate", Zero::zero(), self.actor_update)
+        GasCharge::new(
+            GasChargeName::OnActorUpdate,
+            Zero::zero(),
+            self.actor_update,
+        )
     }
 
     /// Returns the gas required to create a new actor in the state-tree. Assumes that the actor
     /// lookup and update fees have already been charged.
     #[inline]
     pub fn on_actor_create(&self) -> GasCharge {
-        GasCharge::new("OnActorCreate", Zero::zero(), self.actor_create_storage)
+        GasCharge::new(
+            GasChargeName::OnActorCreate,
+            Zero::zero(),
+            self.actor_create_storage,
+        )
     }
 
     /// Returns the gas required for accessing the balance of the current actor.
     #[inline]
     pub fn on_self_balance(&self) -> GasCharge {
-        GasCharge::new("OnSelfBalance", Zero::zero(), Zero::zero())
+        GasCharge::new(GasChargeName::OnSelfBalance, Zero::zero(), Zero::zero())
     }
 
     /// Returns the gas required for accessing the balance of an actor.
     #[inline]
     pub fn on_balance_of(&self) -> GasCharge {
-        GasCharge::new("OnBalanceOf", Zero::zero(), Zero::zero())
+        GasCharge::new(GasChargeName::OnBalanceOf, Zero::zero(), Zero::zero())
     }
 
     /// Returns the gas required for resolving an actor address.
@@ -829,13 +942,13 @@ impl PriceList {
     /// Might require lookup in the state tree as well as loading the state of the init actor.
     #[inline]
     pub fn on_resolve_address(&self) -> GasCharge {
-        GasCharge::new("OnResolveAddress", Zero::zero(), Zero::zero())
+        GasCharge::new(GasChargeName::OnResolveAddress, Zero::zero(), Zero::zero())
     }
 
     /// Returns the gas required for looking up an actor's delegated address.
     #[inline]
     pub fn on_lookup_delegated_address(&self) -> GasCharge {
-        GasCharge::new("OnLookupAddress", Zero::zero(), Zero::zero())
+        GasCharge::new(GasChargeName::OnLookupAddress, Zero::zero(), Zero::zero())
     }
 
     /// Returns the gas required for getting the CID of the code of an actor.
@@ -843,14 +956,14 @@ impl PriceList {
     /// Might require looking up the actor in the state tree.
     #[inline]
     pub fn on_get_actor_code_cid(&self) -> GasCharge {
-        GasCharge::new("OnGetActorCodeCid", Zero::zero(), Zero::zero())
+        GasCharge::new(GasChargeName::OnGetActorCodeCid, Zero::zero(), Zero::zero())
     }
 
     /// Returns the gas required for looking up the type of a builtin actor by CID.
     #[inline]
     pub fn on_get_builtin_actor_type(&self) -> GasCharge {
         GasCharge::new(
-            "OnGetBuiltinActorType",
+            GasChargeName::OnGetBuiltinActorType,
             self.builtin_actor_manifest_lookup,
             Zero::zero(),
         )
@@ -860,7 +973,7 @@ impl PriceList {
     #[inline]
     pub fn on_get_code_cid_for_type(&self) -> GasCharge {
         GasCharge::new(
-            "OnGetCodeCidForType",
+            GasChargeName::OnGetCodeCidForType,
             self.builtin_actor_manifest_lookup,
             Zero::zero(),
         )
@@ -870,7 +983,7 @@ impl PriceList {
     #[inline]
     pub fn on_tipset_cid(&self, lookback: bool) -> GasCharge {
         GasCharge::new(
-            "OnTipsetCid",
+            GasChargeName::OnTipsetCid,
             Zero::zero(),
             if lookback {
                 self.tipset_cid_historical
@@ -883,20 +996,28 @@ impl PriceList {
     /// Returns the gas required for accessing the network context.
     #[inline]
     pub fn on_network_context(&self) -> GasCharge {
-        GasCharge::new("OnNetworkContext", self.network_context, Zero::zero())
+        GasCharge::new(
+            GasChargeName::OnNetworkContext,
+            self.network_context,
+            Zero::zero(),
+        )
     }
 
     /// Returns the gas required for accessing the message context.
     #[inline]
     pub fn on_message_context(&self) -> GasCharge {
-        GasCharge::new("OnMessageContext", self.message_context, Zero::zero())
+        GasCharge::new(
+            GasChargeName::OnMessageContext,
+            self.message_context,
+            Zero::zero(),
+        )
     }
 
     /// Returns the gas required for installing an actor.
     #[cfg(feature = "m2-native")]
     pub fn on_install_actor(&self, wasm_size: usize) -> GasCharge {
         GasCharge::new(
-            "OnInstallActor",
+            GasChargeName::OnInstallActor,
             self.install_wasm_per_byte_cost * wasm_size,
             Zero::zero(),
         )
@@ -910,8 +1031,23 @@ impl PriceList {
 
     /// Returns the gas required for growing memory.
     pub fn grow_memory_gas(&self, grow_memory_bytes: usize) -> Gas {
-        self.wasm_rules.memory_fill_base_cost
-            + self.wasm_rules.memory_fill_per_byte_cost * grow_memory_bytes
+        let per_page = self.wasm_rules.memory_grow_per_page_cost.as_milligas();
+        let bytes = grow_memory_bytes.try_into().unwrap_or(u64::MAX);
+        self.wasm_rules.memory_grow_base_cost
+            + Gas::from_milligas(per_page.saturating_mul(bytes) / WASM_PAGE_SIZE as u64)
+    }
+
+    /// Returns the wasm instruction costs.
+    pub fn wasm_prices(&self) -> &WasmGasPrices {
+        &self.wasm_rules
+    }
+
+    /// Returns a copy of this price list with the given wasm instruction costs.
+    pub fn with_wasm_prices(&self, wasm_prices: WasmGasPrices) -> PriceList {
+        PriceList {
+            wasm_rules: wasm_prices,
+            ..self.clone()
+        }
     }
 
     /// Returns the gas required for initializing tables.
@@ -927,7 +1063,7 @@ impl PriceList {
         let validate = self.event_validation_cost.apply(data_size);
 
         GasCharge::new(
-            "OnActorEventValidate",
+            GasChargeName::OnActorEventValidate,
             memcpy + alloc + validate,
             Zero::zero(),
         )
@@ -967,7 +1103,7 @@ impl PriceList {
         let hash = self.hashing_cost[&SupportedHashes::Blake2b256].apply(stamped_event_size);
 
         GasCharge::new(
-            "OnActorEventAccept",
+            GasChargeName::OnActorEventAccept,
             memcpy + alloc,
             self.event_accept_per_index_element.flat * indexed_elements
                 + self.event_accept_per_index_element.scale * indexed_bytes
@@ -978,13 +1114,23 @@ impl PriceList {
     }
 }
 
-/// Returns gas price list by NetworkVersion for gas consumption.
-pub fn price_list_by_network_version(network_version: NetworkVersion) -> &'static PriceList {
+/// Returns the price list built into the FVM for the given network version, if any, including
+/// network versions still under development. This is used to populate the default
+/// [`NetworkSchedule`](crate::machine::NetworkSchedule) registry.
+pub(crate) fn builtin_price_list(network_version: NetworkVersion) -> Option<&'static PriceList> {
     match network_version {
-        NetworkVersion::V18 | NetworkVersion::V19 | NetworkVersion::V20 => &HYGGE_PRICES,
-        #[cfg(feature = "nv21-dev")]
-        _ if network_version == NetworkVersion::V21 => &HYGGE_PRICES,
-        _ => panic!("network version {nv} not supported", nv = network_version),
+        NetworkVersion::V18 | NetworkVersion::V19 | NetworkVersion::V20 => Some(&HYGGE_PRICES),
+        _ if network_version == NetworkVersion::V21 => Some(&WATERMELON_PRICES),
+        _ => None,
+    }
+}
+
+/// Returns gas price list by NetworkVersion for gas consumption, as registered in the
+/// [`NetworkSchedule`](crate::machine::NetworkSchedule) registry.
+pub fn price_list_by_network_version(network_version: NetworkVersion) -> &'static PriceList {
+    match NetworkSchedule::get(network_version) {
+        Some(schedule) => schedule.price_list,
+        None => panic!("network version {nv} not supported", nv = network_version),
     }
 }
 
@@ -1159,11 +1305,11 @@ impl Rules for WasmGasPrices {
                 self.memory_fill_per_byte_cost,
                 TABLE_ELEMENT_SIZE,
             ),
+            // This is the odd-one out because it operates on entire pages.
             MemoryGrow => linear(
-                self.instruction_default + self.memory_fill_base_cost,
-                self.memory_fill_per_byte_cost,
-                // This is the odd-one out because it operates on entire pages.
-                wasmtime_environ::WASM_PAGE_SIZE,
+                self.instruction_default + self.memory_grow_base_cost,
+                self.memory_grow_per_page_cost,
+                1,
             ),
             MemoryFill => linear(
                 self.instruction_default + self.memory_fill_base_cost,
@@ -1221,29 +1367,50 @@ impl Rules for WasmGasPrices {
             I32AtomicRmwCmpxchg, I32AtomicRmw8CmpxchgU, I32AtomicRmw16CmpxchgU,
             I64AtomicRmwCmpxchg, I64AtomicRmw8CmpxchgU, I64AtomicRmw16CmpxchgU, I64AtomicRmw32CmpxchgU,
 
-            // All SIMD operations.
+            => unsupported("unsupported operation"),
+
+            /*****************************/
+            /*  SIMD (deterministic)     */
+            /*****************************/
 
-            V128Load, V128Store, V128Const,
+            // These instructions are only accepted when the SIMD proposal is enabled in the
+            // engine (see `NetworkConfig::wasm_simd`). Otherwise, they fail validation.
+
+            // SIMD loads.
+            V128Load,
             V128Load8x8S, V128Load16x4S, V128Load32x2S,
             V128Load8x8U, V128Load16x4U, V128Load32x2U,
             V128Load8Splat, V128Load16Splat, V128Load32Splat, V128Load64Splat,
             V128Load32Zero, V128Load64Zero,
             V128Load8Lane, V128Load16Lane, V128Load32Lane, V128Load64Lane,
+            => fixed(self.instruction_default + self.memory_access_cost),
+
+            // SIMD stores.
+            V128Store,
             V128Store8Lane, V128Store16Lane, V128Store32Lane, V128Store64Lane,
-            I8x16Shuffle,
-            I8x16ReplaceLane, I8x16ExtractLaneS, I16x8ExtractLaneS,
-            I16x8ReplaceLane, I8x16ExtractLaneU, I16x8ExtractLaneU,
-            I32x4ExtractLane, I64x2ExtractLane, F32x4ExtractLane, F64x2ExtractLane,
-            I32x4ReplaceLane, I64x2ReplaceLane, F32x4ReplaceLane, F64x2ReplaceLane,
-            I8x16Swizzle, I8x16RelaxedSwizzle,
-            I8x16Splat, I16x8Splat, I32x4Splat, I64x2Splat, F32x4Splat, F64x2Splat,
+            => fixed(self.instruction_default + self.memory_fill_base_cost),
+
+            // Constants & lane manipulation.
+            V128Const,
+            I8x16Shuffle, I8x16Swizzle,
+            I8x16ExtractLaneS, I8x16ExtractLaneU, I16x8ExtractLaneS, I16x8ExtractLaneU,
+            I32x4ExtractLane, I64x2ExtractLane,
+            I8x16ReplaceLane, I16x8ReplaceLane, I32x4ReplaceLane, I64x2ReplaceLane,
+            I8x16Splat, I16x8Splat, I32x4Splat, I64x2Splat,
+            => fixed(self.instruction_default),
+
+            // Bitwise.
+            V128Not, V128And, V128AndNot, V128Or, V128Xor, V128Bitselect, V128AnyTrue,
+            => fixed(self.math_default),
+
+            // Integer comparison.
             I8x16Eq, I8x16Ne, I8x16LtS, I8x16LtU, I8x16GtS, I8x16GtU, I8x16LeS, I8x16LeU, I8x16GeS, I8x16GeU,
             I16x8Eq, I16x8Ne, I16x8LtS, I16x8LtU, I16x8GtS, I16x8GtU, I16x8LeS, I16x8LeU, I16x8GeS, I16x8GeU,
             I32x4Eq, I32x4Ne, I32x4LtS, I32x4LtU, I32x4GtS, I32x4GtU, I32x4LeS, I32x4LeU, I32x4GeS, I32x4GeU,
             I64x2Eq, I64x2Ne, I64x2LtS, I64x2GtS, I64x2LeS, I64x2GeS,
-            F32x4Eq, F32x4Ne, F32x4Lt, F32x4Gt,
-            F32x4Le, F32x4Ge, F64x2Eq, F64x2Ne, F64x2Lt, F64x2Gt, F64x2Le, F64x2Ge,
-            V128Not, V128And, V128AndNot, V128Or, V128Xor, V128Bitselect, V128AnyTrue,
+            => fixed(self.math_default),
+
+            // Integer math.
             I8x16Abs, I8x16Neg, I8x16Popcnt, I8x16AllTrue, I8x16Bitmask, I8x16NarrowI16x8S,
             I8x16NarrowI16x8U, I8x16Shl, I8x16ShrS, I8x16ShrU, I8x16Add, I8x16AddSatS, I8x16AddSatU,
             I8x16Sub, I8x16SubSatS, I8x16SubSatU, I8x16MinS, I8x16MinU, I8x16MaxS, I8x16MaxU, I8x16AvgrU,
@@ -1262,6 +1429,20 @@ impl Rules for WasmGasPrices {
             I64x2ExtendHighI32x4S, I64x2ExtendLowI32x4U, I64x2ExtendHighI32x4U, I64x2Shl,
             I64x2ShrS, I64x2ShrU, I64x2Add, I64x2Sub, I64x2Mul, I64x2ExtMulLowI32x4S,
             I64x2ExtMulHighI32x4S, I64x2ExtMulLowI32x4U, I64x2ExtMulHighI32x4U,
+            => fixed(self.math_default),
+
+            /*******************************/
+            /*  SIMD (non-deterministic)   */
+            /*******************************/
+
+            // We reject all floating point SIMD operations (including conversions to/from floats)
+            // as we haven't audited their NaN behavior across platforms, and all relaxed SIMD
+            // operations as their results are implementation-defined by design.
+
+            F32x4ExtractLane, F64x2ExtractLane, F32x4ReplaceLane, F64x2ReplaceLane,
+            F32x4Splat, F64x2Splat,
+            F32x4Eq, F32x4Ne, F32x4Lt, F32x4Gt,
+            F32x4Le, F32x4Ge, F64x2Eq, F64x2Ne, F64x2Lt, F64x2Gt, F64x2Le, F64x2Ge,
             F32x4Ceil, F32x4Floor, F32x4Trunc, F32x4Nearest, F32x4Abs, F32x4Neg, F32x4Sqrt,
             F32x4Add, F32x4Sub, F32x4Mul, F32x4Div, F32x4Min, F32x4Max, F32x4PMin, F32x4PMax,
             F64x2Ceil, F64x2Floor, F64x2Trunc, F64x2Nearest, F64x2Abs, F64x2Neg, F64x2Sqrt,
@@ -1271,6 +1452,9 @@ impl Rules for WasmGasPrices {
             I32x4TruncSatF64x2SZero, I32x4TruncSatF64x2UZero,
             F64x2ConvertLowI32x4S, F64x2ConvertLowI32x4U,
             F32x4DemoteF64x2Zero, F64x2PromoteLowF32x4,
+            => unsupported("non-deterministic simd operation"),
+
+            I8x16RelaxedSwizzle,
             I32x4RelaxedTruncSatF32x4S, I32x4RelaxedTruncSatF64x2SZero,
             I32x4RelaxedTruncSatF32x4U, I32x4RelaxedTruncSatF64x2UZero,
             F32x4RelaxedFma, F64x2RelaxedFma,
@@ -1280,7 +1464,7 @@ impl Rules for WasmGasPrices {
             I16x8RelaxedQ15mulrS,
             I16x8DotI8x16I7x16S, I32x4DotI8x16I7x16AddS,
             F32x4RelaxedDotBf16x8AddF32x4,
-            => unsupported("unsupported operation"),
+            => unsupported("non-deterministic simd operation"),
         }
     }
 
@@ -1304,6 +1488,73 @@ fn test_read_write() {
     assert_eq!(HYGGE_PRICES.on_block_create(10).total(), Gas::new(100));
 }
 
+#[test]
+fn test_copy() {
+    // Copies are only priced from nv21.
+    assert!(HYGGE_PRICES.on_copy(100).total().is_zero());
+    assert_eq!(WATERMELON_PRICES.on_copy(100).total(), Gas::new(40));
+}
+
+#[test]
+fn test_verify_beacon_entry() {
+    // An unchained beacon entry (a single 96 byte signature) costs a BLS signature verification
+    // over a 32 byte digest, plus the per-byte price of the signature.
+    let unchained = HYGGE_PRICES.on_verify_beacon_entry(0, 96);
+    assert_eq!(unchained.total(), Gas::new(16599437 + 26 * 96));
+
+    // Chained entries also pay for the previous signature.
+    let chained = HYGGE_PRICES.on_verify_beacon_entry(96, 96);
+    assert_eq!(chained.total() - unchained.total(), Gas::new(26 * 96));
+}
+
+#[test]
+fn test_memory_grow() {
+    // The default memory grow prices charge exactly as much as filling the new memory did before
+    // they were introduced.
+    let prices = &HYGGE_PRICES.wasm_rules;
+    for bytes in [
+        0,
+        1,
+        100,
+        WASM_PAGE_SIZE as usize,
+        10 * WASM_PAGE_SIZE as usize,
+    ] {
+        assert_eq!(
+            HYGGE_PRICES.grow_memory_gas(bytes),
+            prices.memory_fill_base_cost + prices.memory_fill_per_byte_cost * bytes
+        );
+    }
+
+    // The instrumentation charges the same for `memory.grow`.
+    let InstructionCost::Linear(base, per_page) = prices
+        .instruction_cost(&Operator::MemoryGrow {
+            mem: 0,
+            mem_byte: 0,
+        })
+        .unwrap()
+    else {
+        panic!("expected memory.grow to have a linear cost");
+    };
+    assert_eq!(
+        base,
+        (prices.instruction_default + prices.memory_fill_base_cost).as_milligas()
+    );
+    assert_eq!(
+        u32::from(per_page) as u64,
+        (prices.memory_fill_per_byte_cost * WASM_PAGE_SIZE).as_milligas()
+    );
+
+    // Overridden prices saturate instead of overflowing.
+    let overridden = HYGGE_PRICES.with_wasm_prices(WasmGasPrices {
+        memory_grow_per_page_cost: Gas::from_milligas(u64::MAX),
+        ..prices.clone()
+    });
+    assert_eq!(
+        overridden.grow_memory_gas(usize::MAX),
+        Gas::from_milligas(u64::MAX / WASM_PAGE_SIZE as u64)
+    );
+}
+
 #[test]
 fn test_step_cost() {
     let costs = StepCost(vec![
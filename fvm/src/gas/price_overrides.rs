@@ -0,0 +1,235 @@
+// Copyright 2021-2023 Protocol Labs
+// SPDX-License-Identifier: Apache-2.0, MIT
+use std::collections::BTreeMap;
+
+use anyhow::anyhow;
+use serde::{Deserialize, Serialize};
+
+use super::{Gas, PriceList};
+
+/// Gas price overrides, applied on top of an existing [`PriceList`] with
+/// [`PriceList::with_overrides`]. Intended for devnets and benchmarks experimenting with prices
+/// without recompiling the FVM.
+///
+/// Overrides map charge names to prices in _milligas_, and can be deserialized from any
+/// self-describing format (JSON, TOML, etc.). For example, in TOML:
+///
+/// ```toml
+/// send_invoke_method = 75000000
+/// "block_memcpy.scale" = 400
+/// "wasm.instruction_default" = 4000
+/// ```
+///
+/// - Fixed charges are named after the charge (e.g., `syscall_cost`).
+/// - Scaling charges are split into their `.flat` and `.scale` components.
+/// - Wasm instruction charges are prefixed with `wasm.`.
+///
+/// Per-proof, per-signature, and per-hash tables can't currently be overridden. See
+/// [`PriceOverrides::NAMES`] for the full list of supported names.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct PriceOverrides(pub BTreeMap<String, u64>);
+
+macro_rules! price_names {
+    (
+        fixed: [$($fixed:ident),* $(,)?],
+        scaling: [$($scaling:ident),* $(,)?],
+        wasm: [$($wasm:ident),* $(,)?] $(,)?
+    ) => {
+        impl PriceOverrides {
+            /// The names of all prices that can be overridden.
+            pub const NAMES: &'static [&'static str] = &[
+                $(stringify!($fixed),)*
+                $(concat!(stringify!($scaling), ".flat"), concat!(stringify!($scaling), ".scale"),)*
+                $(concat!("wasm.", stringify!($wasm)),)*
+            ];
+        }
+
+        /// Returns a mutable reference to the named price, if it exists.
+        fn price_mut<'a>(pl: &'a mut PriceList, name: &str) -> Option<&'a mut Gas> {
+            match name {
+                $(stringify!($fixed) => Some(&mut pl.$fixed),)*
+                $(
+                    concat!(stringify!($scaling), ".flat") => Some(&mut pl.$scaling.flat),
+                    concat!(stringify!($scaling), ".scale") => Some(&mut pl.$scaling.scale),
+                )*
+                $(concat!("wasm.", stringify!($wasm)) => Some(&mut pl.wasm_rules.$wasm),)*
+                _ => None,
+            }
+        }
+    };
+}
+
+price_names! {
+    fixed: [
+        send_transfer_funds,
+        send_invoke_method,
+        address_lookup,
+        address_assignment,
+        actor_lookup,
+        actor_update,
+        actor_create_storage,
+        secp256k1_recover_cost,
+        tipset_cid_latest,
+        tipset_cid_historical,
+        compute_unsealed_sector_cid_base,
+        verify_seal_base,
+        verify_consensus_fault,
+        verify_replica_update,
+        get_randomness_seed,
+        block_persist_compute,
+        syscall_cost,
+        builtin_actor_manifest_lookup,
+        network_context,
+        message_context,
+        install_wasm_per_byte_cost,
+    ],
+    scaling: [
+        on_chain_message_compute,
+        on_chain_message_storage,
+        on_chain_return_compute,
+        on_chain_return_storage,
+        verify_beacon_entry,
+        block_memcpy,
+        block_allocate,
+        block_memory_retention_minimum,
+        block_open,
+        block_persist_storage,
+        syscall_copy,
+        event_validation_cost,
+        event_accept_per_index_element,
+    ],
+    wasm: [
+        instruction_default,
+        math_default,
+        jump_unconditional,
+        jump_conditional,
+        jump_indirect,
+        call,
+        memory_fill_base_cost,
+        memory_fill_per_byte_cost,
+        memory_access_cost,
+        memory_copy_per_byte_cost,
+        memory_grow_base_cost,
+        memory_grow_per_page_cost,
+    ],
+}
+
+impl PriceList {
+    /// Returns a copy of this price list with the given overrides applied, failing if any of the
+    /// overrides doesn't name a known price.
+    pub fn with_overrides(&self, overrides: &PriceOverrides) -> anyhow::Result<PriceList> {
+        let mut pl = self.clone();
+        for (name, &milligas) in &overrides.0 {
+            *price_mut(&mut pl, name).ok_or_else(|| anyhow!("unknown gas price: {}", name))? =
+                Gas::from_milligas(milligas);
+        }
+        Ok(pl)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use fvm_shared::version::NetworkVersion;
+
+    use super::*;
+    use crate::gas::price_list_by_network_version;
+    use crate::machine::NetworkConfig;
+    use crate::EMPTY_ARR_CID;
+
+    #[test]
+    fn apply_overrides() {
+        let base = price_list_by_network_version(NetworkVersion::V18);
+        let overrides = PriceOverrides(
+            [
+                ("send_invoke_method".to_owned(), 1),
+                ("block_memcpy.scale".to_owned(), 2),
+                ("wasm.instruction_default".to_owned(), 3),
+            ]
+            .into_iter()
+            .collect(),
+        );
+        let pl = base.with_overrides(&overrides).unwrap();
+        assert_eq!(pl.send_invoke_method, Gas::from_milligas(1));
+        assert_eq!(pl.block_memcpy.scale, Gas::from_milligas(2));
+        assert_eq!(pl.block_memcpy.flat, base.block_memcpy.flat);
+        assert_eq!(pl.wasm_rules.instruction_default, Gas::from_milligas(3));
+    }
+
+    #[test]
+    fn overrides_survive_upgrades() {
+        let mut nc = NetworkConfig::new(NetworkVersion::V18);
+        nc.override_prices(&PriceOverrides(
+            [("send_invoke_method".to_owned(), 1)].into_iter().collect(),
+        ))
+        .unwrap()
+        .fork_schedule([(10, NetworkVersion::V19)])
+        .unwrap();
+
+        let mc = nc.for_epoch(10, 0, *EMPTY_ARR_CID).unwrap();
+        assert_eq!(mc.network_version, NetworkVersion::V19);
+        assert_eq!(mc.price_list.send_invoke_method, Gas::from_milligas(1));
+        assert_eq!(
+            mc.price_list.block_memcpy,
+            price_list_by_network_version(NetworkVersion::V19).block_memcpy
+        );
+    }
+
+    #[test]
+    fn overrides_change_charges() {
+        let base = price_list_by_network_version(NetworkVersion::V18);
+        let overrides = PriceOverrides(
+            [
+                ("syscall_cost".to_owned(), 11),
+                ("send_invoke_method".to_owned(), 13),
+                ("syscall_copy.flat".to_owned(), 17),
+                ("syscall_copy.scale".to_owned(), 19),
+            ]
+            .into_iter()
+            .collect(),
+        );
+        let pl = base.with_overrides(&overrides).unwrap();
+        assert_eq!(pl.on_syscall().total(), Gas::from_milligas(11));
+        assert_eq!(pl.on_method_invocation().total(), Gas::from_milligas(13));
+        assert_eq!(pl.on_copy(10).total(), Gas::from_milligas(17 + 19 * 10));
+        assert_eq!(
+            pl.on_block_open_base().total(),
+            base.on_block_open_base().total()
+        );
+    }
+
+    #[test]
+    fn names_are_distinct_prices() {
+        let base = price_list_by_network_version(NetworkVersion::V18);
+        // Overriding each name must change exactly one price of the price list: no name may be
+        // dangling or alias another.
+        let overridden: Vec<PriceList> = PriceOverrides::NAMES
+            .iter()
+            .map(|name| {
+                let overrides =
+                    PriceOverrides([(name.to_string(), 987_654_321)].into_iter().collect());
+                let pl = base.with_overrides(&overrides).unwrap();
+                assert_ne!(&pl, base, "{name} doesn't override a price");
+                pl
+            })
+            .collect();
+        for (i, a) in overridden.iter().enumerate() {
+            for (j, b) in overridden.iter().enumerate().skip(i + 1) {
+                assert_ne!(
+                    a,
+                    b,
+                    "{} and {} override the same price",
+                    PriceOverrides::NAMES[i],
+                    PriceOverrides::NAMES[j]
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn reject_unknown() {
+        let base = price_list_by_network_version(NetworkVersion::V18);
+        let overrides = PriceOverrides([("not_a_price".to_owned(), 1)].into_iter().collect());
+        assert!(base.with_overrides(&overrides).is_err());
+    }
+}
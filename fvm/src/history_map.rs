@@ -66,6 +66,16 @@ where
         self.map.get(k)
     }
 
+    /// Lookup a mutable value in the map given a key. Changes made through the returned reference
+    /// are _not_ recorded in the history.
+    pub fn get_mut<Q>(&mut self, k: &Q) -> Option<&mut V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq,
+    {
+        self.map.get_mut(k)
+    }
+
     /// Looks up a value in the map given a key, or initializes the entry with the provided
     /// function. Any modifications to the map are recorded in the history.
     pub fn get_or_try_insert_with<F, E>(&mut self, k: K, f: F) -> std::result::Result<&V, E>
@@ -105,6 +115,27 @@ where
         self.history.clear();
     }
 
+    /// Returns the number of entries in the map.
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    /// Iterate over the current map.
+    pub fn iter(&self) -> impl Iterator<Item = (&K, &V)> {
+        self.map.iter()
+    }
+
+    /// Remove an entry from the map _without_ recording it in the history. This must only be
+    /// called when there's no history to roll back to (see [`HistoryMap::discard_history`]).
+    pub fn evict<Q>(&mut self, k: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq,
+    {
+        debug_assert!(self.history.is_empty(), "evicting with a non-empty history");
+        self.map.remove(k)
+    }
+
     /// Iterate mutably over the current map.
     pub fn iter_mut(&mut self) -> impl Iterator<Item = (&K, &mut V)> {
         self.map.iter_mut()
@@ -3,9 +3,11 @@
 use std::convert::TryInto;
 use std::rc::Rc;
 
+use cid::Cid;
 use fvm_ipld_encoding::ipld_block::IpldBlock;
 use fvm_ipld_encoding::{CBOR, DAG_CBOR};
 use fvm_shared::IPLD_RAW;
+use once_cell::unsync::OnceCell;
 use thiserror::Error;
 
 use super::{ExecutionError, SyscallError};
@@ -31,6 +33,9 @@ const ALLOWED_CODECS: &[u64; 3] = &[CBOR, DAG_CBOR, IPLD_RAW];
 pub struct BlockStat {
     pub codec: u64,
     pub size: u32,
+    /// Whether the block is known to be in the reachable set, i.e., it was either opened by CID or
+    /// linked.
+    pub reachable: bool,
 }
 
 #[derive(Debug, Clone)]
@@ -40,6 +45,9 @@ pub struct Block {
     // copy the bytes. So we accept some indirection for reliable performance.
     #[allow(clippy::redundant_allocation)]
     data: Rc<Box<[u8]>>,
+    reachable: bool,
+    /// The links parsed from the block's data, cached after the first scan.
+    links: OnceCell<Rc<[Cid]>>,
 }
 
 impl Block {
@@ -49,9 +57,33 @@ impl Block {
         Self {
             codec,
             data: Rc::new(data.into()),
+            reachable: false,
+            links: OnceCell::new(),
         }
     }
 
+    /// Returns the links parsed from the block's data, if they've already been scanned.
+    #[inline(always)]
+    pub fn links(&self) -> Option<&Rc<[Cid]>> {
+        self.links.get()
+    }
+
+    /// Caches the links parsed from the block's data, returning the cached links.
+    pub fn set_links(&self, links: Rc<[Cid]>) -> &Rc<[Cid]> {
+        self.links.get_or_init(|| links)
+    }
+
+    /// Marks the block as being in the reachable set.
+    #[inline(always)]
+    pub fn mark_reachable(&mut self) {
+        self.reachable = true;
+    }
+
+    #[inline(always)]
+    pub fn is_reachable(&self) -> bool {
+        self.reachable
+    }
+
     #[inline(always)]
     pub fn codec(&self) -> u64 {
         self.codec
@@ -72,6 +104,7 @@ impl Block {
         BlockStat {
             codec: self.codec(),
             size: self.size(),
+            reachable: self.is_reachable(),
         }
     }
 }
@@ -178,10 +211,19 @@ impl BlockRegistry {
             .ok()
             .and_then(|idx: usize| self.blocks.get(idx - FIRST_ID as usize))
             .ok_or(InvalidHandleError(id))
-            .map(|b| BlockStat {
-                codec: b.codec(),
-                size: b.size(),
-            })
+            .map(|b| b.stat())
+    }
+
+    /// Marks the block associated with a block handle as being in the reachable set.
+    pub fn mark_reachable(&mut self, id: BlockId) -> Result<(), InvalidHandleError> {
+        if id < FIRST_ID {
+            return Err(InvalidHandleError(id));
+        }
+        id.try_into()
+            .ok()
+            .and_then(|idx: usize| self.blocks.get_mut(idx - FIRST_ID as usize))
+            .ok_or(InvalidHandleError(id))
+            .map(|b| b.mark_reachable())
     }
 
     pub fn is_full(&self) -> bool {
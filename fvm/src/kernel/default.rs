@@ -4,14 +4,13 @@ use std::collections::BTreeMap;
 use std::convert::{TryFrom, TryInto};
 use std::panic::{self, UnwindSafe};
 use std::path::PathBuf;
+use std::rc::Rc;
 
 use anyhow::{anyhow, Context as _};
-use blake2b_simd::Params;
-use byteorder::WriteBytesExt;
 use cid::Cid;
 use filecoin_proofs_api::{self as proofs, ProverId, PublicReplicaInfo, SectorId};
 use fvm_ipld_blockstore::Blockstore;
-use fvm_ipld_encoding::{bytes_32, IPLD_RAW};
+use fvm_ipld_encoding::{bytes_32, DAG_CBOR, IPLD_RAW};
 use fvm_shared::address::Payload;
 use fvm_shared::bigint::Zero;
 use fvm_shared::chainid::ChainID;
@@ -21,6 +20,7 @@ use fvm_shared::econ::TokenAmount;
 use fvm_shared::error::ErrorNumber;
 use fvm_shared::event::ActorEvent;
 use fvm_shared::piece::{zero_piece_commitment, PaddedPieceSize};
+use fvm_shared::randomness::draw_randomness;
 use fvm_shared::sector::RegisteredPoStProof::{StackedDRGWindow32GiBV1, StackedDRGWindow32GiBV1P1};
 use fvm_shared::sector::{RegisteredPoStProof, SectorInfo};
 use fvm_shared::sys::out::vm::ContextFlags;
@@ -29,19 +29,25 @@ use lazy_static::lazy_static;
 use multihash::MultihashDigest;
 use rayon::iter::{IndexedParallelIterator, IntoParallelRefIterator, ParallelIterator};
 use rayon::prelude::ParallelDrainRange;
-use std::io::Write;
 
 use super::blocks::{Block, BlockRegistry};
 use super::error::Result;
 use super::hash::SupportedHashes;
 use super::*;
-use crate::call_manager::{CallManager, InvocationResult, NO_DATA_BLOCK_ID};
-use crate::externs::{Chain, Consensus, Rand};
+use crate::blockstore::scan_for_links;
+use crate::call_manager::{
+    CallFrame, CallManager, DebugArtifact, InvocationResult, NO_DATA_BLOCK_ID,
+};
+use crate::externs::Rand;
 use crate::gas::GasTimer;
 use crate::init_actor::INIT_ACTOR_ID;
-use crate::machine::{MachineContext, NetworkConfig};
+use crate::machine::{
+    ActorEventLimits, MachineContext, NetworkConfig, SelfDestructPolicy, BURNT_FUNDS_ACTOR_ID,
+};
 use crate::state_tree::ActorState;
 use crate::syscall_error;
+use crate::syscalls::InvocationData;
+use crate::trace::ExecutionEvent;
 
 lazy_static! {
     static ref NUM_CPUS: usize = num_cpus::get();
@@ -115,6 +121,11 @@ where
         self.call_manager.machine()
     }
 
+    fn trace_syscall(&self, module: &'static str, name: &'static str) {
+        self.call_manager
+            .trace(ExecutionEvent::Syscall { module, name })
+    }
+
     fn send<K: Kernel<CallManager = C>>(
         &mut self,
         recipient: &Address,
@@ -171,13 +182,38 @@ where
                 value: None,
             } => SendResult {
                 block_id: NO_DATA_BLOCK_ID,
-                block_stat: BlockStat { codec: 0, size: 0 },
+                block_stat: BlockStat {
+                    codec: 0,
+                    size: 0,
+                    reachable: false,
+                },
                 exit_code,
             },
         })
     }
 }
 
+/// The default kernel binds the full syscall set for any kernel implementing all capabilities.
+/// Kernels wrapping the [`DefaultKernel`] can delegate to this implementation.
+impl<K> SyscallHandler<K> for DefaultKernel<K::CallManager>
+where
+    K: Kernel
+        + ActorOps
+        + CircSupplyOps
+        + CryptoOps
+        + DebugOps
+        + EventOps
+        + IpldBlockOps
+        + MessageOps
+        + NetworkOps
+        + RandomnessOps
+        + SelfOps,
+{
+    fn bind_syscalls(linker: &mut Linker<InvocationData<K>>) -> anyhow::Result<()> {
+        crate::syscalls::bind_syscalls(linker)
+    }
+}
+
 impl<C> DefaultKernel<C>
 where
     C: CallManager,
@@ -232,6 +268,14 @@ where
             return Err(syscall_error!(ReadOnly; "cannot self-destruct when read-only").into());
         }
 
+        let beneficiary = match self.call_manager.context().network.self_destruct_policy {
+            SelfDestructPolicy::TransferToBeneficiary => Some(beneficiary),
+            SelfDestructPolicy::Burn => None,
+            SelfDestructPolicy::Disallow => {
+                return Err(syscall_error!(IllegalOperation; "self-destruct is disallowed").into())
+            }
+        };
+
         // Idempotentcy: If the actor doesn't exist, this won't actually do anything. The current
         // balance will be zero, and `delete_actor_id` will be a no-op.
         let t = self
@@ -240,15 +284,24 @@ where
 
         let balance = self.current_balance()?;
         if balance != TokenAmount::zero() {
-            // Starting from network version v7, the runtime checks if the beneficiary
-            // exists; if missing, it fails the self destruct.
-            //
-            // In FVM we check unconditionally, since we only support nv13+.
-            let beneficiary_id = self.resolve_address(beneficiary)?;
-
-            if beneficiary_id == self.actor_id {
-                return Err(syscall_error!(Forbidden, "benefactor cannot be beneficiary").into());
-            }
+            let beneficiary_id = match beneficiary {
+                Some(beneficiary) => {
+                    // Starting from network version v7, the runtime checks if the beneficiary
+                    // exists; if missing, it fails the self destruct.
+                    //
+                    // In FVM we check unconditionally, since we only support nv13+.
+                    let beneficiary_id = self.resolve_address(beneficiary)?;
+
+                    if beneficiary_id == self.actor_id {
+                        return Err(
+                            syscall_error!(Forbidden, "benefactor cannot be beneficiary").into(),
+                        );
+                    }
+                    beneficiary_id
+                }
+                // Burn the funds.
+                None => BURNT_FUNDS_ACTOR_ID,
+            };
 
             // Transfer the entirety of funds to beneficiary.
             self.call_manager
@@ -284,8 +337,10 @@ where
             // reachability checking (for user actors) we won't get here unless the block is known
             // to be in the state-tree.
             .or_fatal()?;
+        self.call_manager.record_block_read(data.len());
 
-        let block = Block::new(cid.codec(), data);
+        let mut block = Block::new(cid.codec(), data);
+        block.mark_reachable();
 
         let t = self.call_manager.charge_gas(
             self.call_manager
@@ -331,13 +386,15 @@ where
             return Err(syscall_error!(IllegalCid; "invalid hash length: {}", hash_len).into());
         }
         let k = Cid::new_v1(block.codec(), hash.truncate(hash_len as u8));
-        // TODO(M2): Add the block to the reachable set.
         self.call_manager
             .blockstore()
             .put_keyed(&k, block.data())
             // TODO: This is really "super fatal". It means we failed to store state, and should
             // probably abort the entire block.
             .or_fatal()?;
+        let size = block.size() as usize;
+        self.call_manager.record_block_write(size);
+        self.blocks.mark_reachable(id)?;
         t.stop_with(start);
         Ok(k)
     }
@@ -384,6 +441,31 @@ where
 
         t.record(Ok(self.blocks.stat(id)?))
     }
+
+    fn block_links(&self, id: BlockId) -> Result<Rc<[Cid]>> {
+        let start = GasTimer::start();
+        let block = self.blocks.get(id)?;
+
+        // Only the first call scans (and charges for) the block, so actors can cheaply page
+        // through the links.
+        if let Some(links) = block.links() {
+            return Ok(links.clone());
+        }
+
+        let t = self.call_manager.charge_gas(
+            self.call_manager
+                .price_list()
+                .on_block_links(block.size() as usize),
+        )?;
+
+        let mut links = Vec::new();
+        if block.codec() == DAG_CBOR {
+            scan_for_links(block.data(), &mut links)
+                .map_err(|e| syscall_error!(Serialization; "failed to parse block links: {}", e))?;
+        }
+        t.stop_with(start);
+        Ok(block.set_links(links.into()).clone())
+    }
 }
 
 impl<C> MessageOps for DefaultKernel<C>
@@ -420,6 +502,14 @@ where
         t.stop();
         Ok(ctx)
     }
+
+    fn call_depth(&self) -> u32 {
+        self.call_manager.call_depth()
+    }
+
+    fn call_stack(&self) -> &[CallFrame] {
+        self.call_manager.call_stack()
+    }
 }
 
 impl<C> CircSupplyOps for DefaultKernel<C>
@@ -451,10 +541,12 @@ where
                 .on_verify_signature(sig_type, plaintext.len()),
         )?;
 
-        // We only support key addresses (f1/f3). This change does not require a FIP, because no
-        // actors invoke this method with non-key addresses.
+        // We only support key addresses (f1/f3), and f410 addresses for delegated signatures (only
+        // available from network version 21, see the syscall). This change does not require a
+        // FIP, because no actors invoke this method with non-key addresses.
         let signing_addr = match signer.payload() {
             Payload::BLS(_) | Payload::Secp256k1(_) => *signer,
+            Payload::Delegated(_) if sig_type == SignatureType::Delegated => *signer,
             // Not a key address.
             _ => {
                 return Err(syscall_error!(IllegalArgument; "address protocol {} not supported", signer.protocol()).into());
@@ -554,6 +646,15 @@ where
         h2: &[u8],
         extra: &[u8],
     ) -> Result<Option<ConsensusFault>> {
+        let limit = self
+            .call_manager
+            .context()
+            .network
+            .max_consensus_fault_verifications;
+        if self.call_manager.record_consensus_fault_verification() > limit {
+            return Err(syscall_error!(LimitExceeded; "too many consensus fault verifications (limit {})", limit).into());
+        }
+
         let t = self.call_manager.charge_gas(
             self.call_manager.price_list().on_verify_consensus_fault(
                 h1.len(),
@@ -563,10 +664,10 @@ where
         )?;
 
         // This syscall cannot be resolved inside the FVM, so we need to traverse
-        // the node boundary through an extern.
+        // the node boundary through an extern (cached by the machine).
         let (fault, _) = t.record(
             self.call_manager
-                .externs()
+                .machine()
                 .verify_consensus_fault(h1, h2, extra)
                 .or_illegal_argument(),
         )?;
@@ -577,13 +678,12 @@ where
     fn batch_verify_seals(&self, vis: &[SealVerifyInfo]) -> Result<Vec<bool>> {
         // NOTE: gas has already been charged by the power actor when the batch verify was enqueued.
         // Lotus charges "virtual" gas here for tracing only.
-        let mut items = Vec::new();
-        for vi in vis {
-            let t = self
-                .call_manager
-                .charge_gas(self.call_manager.price_list().on_verify_seal(vi))?;
-            items.push((vi, t));
-        }
+        let charges: Vec<_> = vis
+            .iter()
+            .map(|vi| self.call_manager.price_list().on_verify_seal(vi))
+            .collect();
+        let timers = self.charge_gas_many(&charges)?;
+        let mut items: Vec<_> = vis.iter().zip(timers).collect();
         log::debug!("batch verify seals start");
         let out = items.par_drain(..)
             .with_min_len(vis.len() / *NUM_CPUS)
@@ -660,10 +760,28 @@ where
         self.call_manager.gas_tracker().gas_available()
     }
 
-    fn charge_gas(&self, name: &str, compute: Gas) -> Result<GasTimer> {
+    fn charge_gas(&self, name: impl Into<GasChargeName>, compute: Gas) -> Result<GasTimer> {
         self.call_manager.gas_tracker().charge_gas(name, compute)
     }
 
+    fn charge_copy(&self, bytes: usize) -> Result<()> {
+        self.call_manager
+            .gas_tracker()
+            .charge_copy(self.call_manager.price_list().on_copy(bytes))
+    }
+
+    fn charge_memory(&self, name: impl Into<GasChargeName>, gas: Gas) -> Result<GasTimer> {
+        self.call_manager.gas_tracker().charge_memory(name, gas)
+    }
+
+    fn charge_gas_many(&self, charges: &[GasCharge]) -> Result<Vec<GasTimer>> {
+        self.call_manager.gas_tracker().apply_charges(charges)
+    }
+
+    fn records_gas_charges(&self) -> bool {
+        self.call_manager.gas_tracker().records_charges()
+    }
+
     fn price_list(&self) -> &PriceList {
         self.call_manager.price_list()
     }
@@ -673,7 +791,7 @@ impl<C> NetworkOps for DefaultKernel<C>
 where
     C: CallManager,
 {
-    fn network_context(&self) -> Result<NetworkContext> {
+    fn network_context(&self) -> Result<NetworkContextV2> {
         let t = self
             .call_manager
             .charge_gas(self.call_manager.price_list().on_network_context())?;
@@ -686,12 +804,14 @@ where
                 NetworkConfig {
                     network_version,
                     chain_id,
+                    genesis_timestamp,
+                    block_gas_limit,
                     ..
                 },
             ..
         } = self.call_manager.context();
 
-        let ctx = NetworkContext {
+        let ctx = NetworkContextV2 {
             chain_id: (*chain_id).into(),
             epoch: *epoch,
             network_version: *network_version,
@@ -700,6 +820,8 @@ where
                 .try_into()
                 .or_fatal()
                 .context("base-fee exceeds u128 limit")?,
+            genesis_timestamp: *genesis_timestamp,
+            block_gas_limit: *block_gas_limit,
         };
 
         t.stop();
@@ -731,28 +853,10 @@ where
         self.call_manager
             .charge_gas(self.call_manager.price_list().on_tipset_cid(offset > 1))?;
 
-        self.call_manager.externs().get_tipset_cid(epoch).or_fatal()
+        self.call_manager.machine().tipset_cid(epoch).or_fatal()
     }
 }
 
-fn draw_randomness(
-    rbase: &[u8; RANDOMNESS_LENGTH],
-    pers: i64,
-    round: ChainEpoch,
-    entropy: &[u8],
-) -> anyhow::Result<[u8; RANDOMNESS_LENGTH]> {
-    let mut state = Params::new().hash_length(32).to_state();
-    state.write_i64::<byteorder::BigEndian>(pers)?;
-    state.write_all(rbase)?;
-    state.write_i64::<byteorder::BigEndian>(round)?;
-    state.write_all(entropy)?;
-    state
-        .finalize()
-        .as_bytes()
-        .try_into()
-        .map_err(anyhow::Error::from)
-}
-
 impl<C> RandomnessOps for DefaultKernel<C>
 where
     C: CallManager,
@@ -779,9 +883,12 @@ where
                 .or_illegal_argument(),
         )?;
 
-        draw_randomness(&digest, personalization, rand_epoch, entropy).map_err(|e| {
-            syscall_error!(IllegalArgument; "failed to draw chain randmness {}", e).into()
-        })
+        Ok(draw_randomness(
+            &digest,
+            personalization,
+            rand_epoch,
+            entropy,
+        ))
     }
 
     fn get_randomness_from_beacon(
@@ -805,9 +912,32 @@ where
                 .or_illegal_argument(),
         )?;
 
-        draw_randomness(&digest, personalization, rand_epoch, entropy).map_err(|e| {
-            syscall_error!(IllegalArgument; "failed to draw beacon randmness {}", e).into()
-        })
+        Ok(draw_randomness(
+            &digest,
+            personalization,
+            rand_epoch,
+            entropy,
+        ))
+    }
+
+    fn verify_beacon_entry(
+        &self,
+        round: u64,
+        prev_signature: &[u8],
+        signature: &[u8],
+    ) -> Result<bool> {
+        let t = self.call_manager.charge_gas(
+            self.call_manager
+                .price_list()
+                .on_verify_beacon_entry(prev_signature.len(), signature.len()),
+        )?;
+
+        t.record(
+            self.call_manager
+                .externs()
+                .verify_beacon_entry(round, prev_signature, signature)
+                .or_illegal_argument(),
+        )
     }
 }
 
@@ -955,7 +1085,7 @@ where
         self.call_manager.context().actor_debugging
     }
 
-    fn store_artifact(&self, name: &str, data: &[u8]) -> Result<()> {
+    fn store_artifact(&mut self, name: &str, data: &[u8]) -> Result<()> {
         // Ensure well formed artifact name
         {
             if name.len() > MAX_ARTIFACT_NAME_LEN {
@@ -976,7 +1106,7 @@ where
         }
         .or_error(fvm_shared::error::ErrorNumber::IllegalArgument)?;
 
-        // Write to disk
+        // Write to disk, if requested.
         if let Ok(dir) = std::env::var(ENV_ARTIFACT_DIR).as_deref() {
             let dir: PathBuf = [
                 dir,
@@ -996,12 +1126,14 @@ where
             } else {
                 log::info!("wrote artifact: {} to {:?}", name, dir);
             }
-        } else {
-            log::error!(
-                "store_artifact was ignored, env var {} was not set",
-                ENV_ARTIFACT_DIR
-            )
         }
+
+        // Record the artifact so it's returned to the host with the message's results.
+        self.call_manager.append_artifact(DebugArtifact {
+            actor: self.actor_id,
+            name: name.to_owned(),
+            data: data.to_vec(),
+        });
         Ok(())
     }
 }
@@ -1030,27 +1162,39 @@ where
             .call_manager
             .charge_gas(self.call_manager.price_list().on_actor_event_validate(len))?;
 
-        // This is an over-estimation of the maximum event size, for safety. No valid event can even
-        // get close to this. We check this first so we don't try to decode a large event.
-        const MAX_ENCODED_SIZE: usize = 1 << 20;
-        if raw_evt.len() > MAX_ENCODED_SIZE {
-            return Err(syscall_error!(IllegalArgument; "event WAY too large").into());
+        let limits = self.call_manager.context().network.event_limits;
+
+        // We check this first so we don't try to decode a large event.
+        if raw_evt.len() > limits.max_encoded_size {
+            return Err(SyscallError::new(
+                limits.limit_exceeded_error(),
+                format_args!(
+                    "event too large: {} > {}",
+                    raw_evt.len(),
+                    limits.max_encoded_size
+                ),
+            )
+            .into());
         }
 
         let actor_evt = {
             let res = match panic::catch_unwind(|| {
-                fvm_ipld_encoding::from_slice(raw_evt).or_error(ErrorNumber::IllegalArgument)
+                fvm_ipld_encoding::from_slice(raw_evt).or_error(limits.serialization_error())
             }) {
                 Ok(v) => v,
                 Err(e) => {
                     log::error!("panic when decoding event cbor from actor: {:?}", e);
-                    Err(syscall_error!(IllegalArgument; "panic when decoding event cbor from actor").into())
+                    Err(SyscallError::new(
+                        limits.serialization_error(),
+                        "panic when decoding event cbor from actor",
+                    )
+                    .into())
                 }
             };
             t.stop();
             res
         }?;
-        validate_actor_event(&actor_evt)?;
+        validate_actor_event(&actor_evt, &limits)?;
 
         let t = self.call_manager.charge_gas(
             self.call_manager
@@ -1077,18 +1221,30 @@ fn catch_and_log_panic<F: FnOnce() -> Result<R> + UnwindSafe, R>(context: &str,
     }
 }
 
-fn validate_actor_event(evt: &ActorEvent) -> Result<()> {
-    const MAX_ENTRIES: usize = 256;
-    const MAX_DATA: usize = 8 << 10;
-    const MAX_KEY_LEN: usize = 32;
-
-    if evt.entries.len() > MAX_ENTRIES {
-        return Err(syscall_error!(IllegalArgument; "event exceeded max entries: {} > {MAX_ENTRIES}", evt.entries.len()).into());
+fn validate_actor_event(evt: &ActorEvent, limits: &ActorEventLimits) -> Result<()> {
+    if evt.entries.len() > limits.max_entries {
+        return Err(SyscallError::new(
+            limits.limit_exceeded_error(),
+            format_args!(
+                "event exceeded max entries: {} > {}",
+                evt.entries.len(),
+                limits.max_entries
+            ),
+        )
+        .into());
     }
     let mut total_value_size: usize = 0;
     for entry in &evt.entries {
-        if entry.key.len() > MAX_KEY_LEN {
-            return Err(syscall_error!(IllegalArgument; "event key exceeded max size: {} > {MAX_KEY_LEN}", entry.key.len()).into());
+        if entry.key.len() > limits.max_key_len {
+            return Err(SyscallError::new(
+                limits.limit_exceeded_error(),
+                format_args!(
+                    "event key exceeded max size: {} > {}",
+                    entry.key.len(),
+                    limits.max_key_len
+                ),
+            )
+            .into());
         }
         if entry.codec != IPLD_RAW {
             return Err(
@@ -1098,10 +1254,15 @@ fn validate_actor_event(evt: &ActorEvent) -> Result<()> {
         }
         total_value_size += entry.value.len();
     }
-    if total_value_size > MAX_DATA {
-        return Err(
-            syscall_error!(IllegalArgument; "event total values exceeded max size: {total_value_size} > {MAX_DATA}").into(),
-        );
+    if total_value_size > limits.max_total_values_len {
+        return Err(SyscallError::new(
+            limits.limit_exceeded_error(),
+            format_args!(
+                "event total values exceeded max size: {total_value_size} > {}",
+                limits.max_total_values_len
+            ),
+        )
+        .into());
     }
     Ok(())
 }
@@ -1144,7 +1305,7 @@ fn to_fil_public_replica_infos(
         .map::<core::result::Result<(SectorId, PublicReplicaInfo), String>, _>(
             |sector_info: &SectorInfo| {
                 let commr = commcid::cid_to_replica_commitment_v1(&sector_info.sealed_cid)?;
-                if !check_valid_proof_type(typ, sector_info.proof) {
+                if !typ.is_compatible_with(sector_info.proof) {
                     return Err("invalid proof type".to_string());
                 }
                 let replica = PublicReplicaInfo::new(typ.try_into()?, commr);
@@ -1156,34 +1317,6 @@ fn to_fil_public_replica_infos(
     Ok(replicas)
 }
 
-fn check_valid_proof_type(post_type: RegisteredPoStProof, seal_type: RegisteredSealProof) -> bool {
-    let proof_type_v1p1 = seal_type
-        .registered_window_post_proof()
-        .unwrap_or(RegisteredPoStProof::Invalid(-1));
-    let proof_type_v1 = match proof_type_v1p1 {
-        RegisteredPoStProof::StackedDRGWindow2KiBV1P1 => {
-            RegisteredPoStProof::StackedDRGWindow2KiBV1
-        }
-        RegisteredPoStProof::StackedDRGWindow8MiBV1P1 => {
-            RegisteredPoStProof::StackedDRGWindow8MiBV1
-        }
-        RegisteredPoStProof::StackedDRGWindow512MiBV1P1 => {
-            RegisteredPoStProof::StackedDRGWindow512MiBV1
-        }
-        RegisteredPoStProof::StackedDRGWindow32GiBV1P1 => {
-            RegisteredPoStProof::StackedDRGWindow32GiBV1
-        }
-        RegisteredPoStProof::StackedDRGWindow64GiBV1P1 => {
-            RegisteredPoStProof::StackedDRGWindow64GiBV1
-        }
-        _ => {
-            return false;
-        }
-    };
-
-    proof_type_v1 == post_type || proof_type_v1p1 == post_type
-}
-
 fn verify_seal(vi: &SealVerifyInfo) -> Result<bool> {
     let commr = commcid::cid_to_replica_commitment_v1(&vi.sealed_cid).or_illegal_argument()?;
     let commd = commcid::cid_to_data_commitment_v1(&vi.unsealed_cid).or_illegal_argument()?;
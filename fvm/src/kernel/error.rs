@@ -3,7 +3,7 @@
 use std::fmt::Display;
 
 use derive_more::Display;
-use fvm_shared::error::ErrorNumber;
+use fvm_shared::error::{CustomErrorNumber, ErrorNumber};
 
 /// Execution result.
 pub type Result<T> = std::result::Result<T, ExecutionError>;
@@ -187,6 +187,18 @@ impl SyscallError {
     }
 }
 
+/// An error from a custom syscall, with an embedder-specific error number. See
+/// [`bind_typed_syscall_with_custom_errors`](crate::syscalls::bind_typed_syscall_with_custom_errors).
+#[derive(thiserror::Error, Debug, Clone)]
+#[error("custom syscall error: {0} (error_number={1})")]
+pub struct CustomSyscallError(pub String, pub CustomErrorNumber);
+
+impl CustomSyscallError {
+    pub fn new<D: Display>(c: CustomErrorNumber, d: D) -> Self {
+        CustomSyscallError(d.to_string(), c)
+    }
+}
+
 #[test]
 fn test_syscall_error_formatting() {
     let test_value = 1;
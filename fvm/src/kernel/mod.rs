@@ -1,5 +1,7 @@
 // Copyright 2021-2023 Protocol Labs
 // SPDX-License-Identifier: Apache-2.0, MIT
+use std::rc::Rc;
+
 pub use blocks::{Block, BlockId, BlockRegistry, BlockStat};
 use cid::Cid;
 use fvm_shared::address::Address;
@@ -16,7 +18,7 @@ use fvm_shared::sector::{
     AggregateSealVerifyProofAndInfos, RegisteredSealProof, ReplicaUpdateInfo, SealVerifyInfo,
     WindowPoStVerifyInfo,
 };
-use fvm_shared::sys::out::network::NetworkContext;
+use fvm_shared::sys::out::network::NetworkContextV2;
 use fvm_shared::sys::out::vm::MessageContext;
 use fvm_shared::sys::SendFlags;
 use fvm_shared::{ActorID, MethodNum};
@@ -28,15 +30,19 @@ pub mod default;
 
 pub(crate) mod error;
 
-pub use error::{ClassifyResult, Context, ExecutionError, Result, SyscallError};
+pub use error::{
+    ClassifyResult, Context, CustomSyscallError, ExecutionError, Result, SyscallError,
+};
 use fvm_shared::event::StampedEvent;
 pub use hash::SupportedHashes;
 use multihash::MultihashGeneric;
+use wasmtime::Linker;
 
-use crate::call_manager::CallManager;
-use crate::gas::{Gas, GasTimer, PriceList};
+use crate::call_manager::{CallFrame, CallManager};
+use crate::gas::{Gas, GasCharge, GasChargeName, GasTimer, PriceList};
 use crate::machine::limiter::MemoryLimiter;
 use crate::machine::Machine;
+use crate::syscalls::InvocationData;
 
 pub struct SendResult {
     pub block_id: BlockId,
@@ -51,21 +57,12 @@ pub struct SendResult {
 ///
 /// Actors may call into the kernel via the syscalls defined in the [`syscalls`][crate::syscalls]
 /// module.
-pub trait Kernel:
-    ActorOps
-    + IpldBlockOps
-    + CircSupplyOps
-    + CryptoOps
-    + DebugOps
-    + EventOps
-    + GasOps
-    + MessageOps
-    + NetworkOps
-    + RandomnessOps
-    + SelfOps
-    + LimiterOps
-    + 'static
-{
+///
+/// The kernel's capabilities are split into a set of "ops" traits ([`ActorOps`],
+/// [`IpldBlockOps`], [`CryptoOps`], etc.). A kernel only has to implement the capabilities it
+/// intends to expose; which syscalls are actually made available to actors is decided by its
+/// [`SyscallHandler`] implementation.
+pub trait Kernel: GasOps + LimiterOps + SyscallHandler<Self> + 'static {
     /// The [`Kernel`]'s [`CallManager`] is
     type CallManager: CallManager;
 
@@ -97,6 +94,12 @@ pub trait Kernel:
     /// The kernel's underlying "machine".
     fn machine(&self) -> &<Self::CallManager as CallManager>::Machine;
 
+    /// Records a syscall invocation in the execution trace. The default implementation does
+    /// nothing.
+    fn trace_syscall(&self, module: &'static str, name: &'static str) {
+        let _ = (module, name);
+    }
+
     /// Sends a message to another actor.
     /// The method type parameter K is the type of the kernel to instantiate for
     /// the receiving actor. This is necessary to support wrapping a kernel, so the outer
@@ -115,10 +118,22 @@ pub trait Kernel:
     ) -> Result<SendResult>;
 }
 
+/// Binds the syscalls supported by a kernel into the wasm linker.
+///
+/// The [`DefaultKernel`][crate::DefaultKernel] implements this for any kernel `K` that implements
+/// _all_ the capability traits, binding the full default syscall set. Custom kernels that omit or
+/// extend capabilities should implement this trait themselves, composing the per-capability
+/// binding functions exported from the [`syscalls`][crate::syscalls] module (and binding any
+/// syscalls of their own).
+pub trait SyscallHandler<K>: Sized {
+    /// Bind all syscalls exposed by this kernel.
+    fn bind_syscalls(linker: &mut Linker<InvocationData<K>>) -> anyhow::Result<()>;
+}
+
 /// Network-related operations.
 pub trait NetworkOps {
-    /// Network information (epoch, version, etc.).
-    fn network_context(&self) -> Result<NetworkContext>;
+    /// Network information (epoch, version, chain ID, etc.).
+    fn network_context(&self) -> Result<NetworkContextV2>;
 
     /// The CID of the tipset at the specified epoch.
     fn tipset_cid(&self, epoch: ChainEpoch) -> Result<Cid>;
@@ -128,6 +143,12 @@ pub trait NetworkOps {
 pub trait MessageOps {
     /// Message information.
     fn msg_context(&self) -> Result<MessageContext>;
+
+    /// The current call depth (1 for the actor invoked by the message).
+    fn call_depth(&self) -> u32;
+
+    /// The actors currently being invoked, outermost first (ending with the current actor).
+    fn call_stack(&self) -> &[CallFrame];
 }
 
 /// The IPLD subset of the kernel.
@@ -156,10 +177,19 @@ pub trait IpldBlockOps {
     /// This method will fail if the block handle is invalid.
     fn block_read(&self, id: BlockId, offset: u32, buf: &mut [u8]) -> Result<i32>;
 
-    /// Returns the blocks codec & size.
+    /// Returns the blocks codec, size, and whether it's in the reachable set.
     ///
     /// This method will fail if the block handle is invalid.
     fn block_stat(&self, id: BlockId) -> Result<BlockStat>;
+
+    /// Returns the CIDs linked from a block, in the order in which they appear in the block. Only
+    /// DagCBOR blocks have links, links in CBOR and raw blocks are never traversed.
+    ///
+    /// The block is only scanned (and charged for) on the first call, subsequent calls return the
+    /// cached links.
+    ///
+    /// This method will fail if the block handle is invalid, or the block can't be parsed.
+    fn block_links(&self, id: BlockId) -> Result<Rc<[Cid]>>;
 }
 
 /// Actor state access and manipulation.
@@ -179,6 +209,9 @@ pub trait SelfOps: IpldBlockOps {
     /// Deletes the executing actor from the state tree, transferring any balance to beneficiary.
     /// Aborts if the beneficiary does not exist.
     /// May only be called by the actor itself.
+    ///
+    /// What happens to the balance (and whether self-destruct is allowed at all) is determined by
+    /// the network's [`SelfDestructPolicy`](crate::machine::SelfDestructPolicy).
     fn self_destruct(&mut self, beneficiary: &Address) -> Result<()>;
 }
 
@@ -248,7 +281,26 @@ pub trait GasOps {
 
     /// ChargeGas charges specified amount of `gas` for execution.
     /// `name` provides information about gas charging point.
-    fn charge_gas(&self, name: &str, compute: Gas) -> Result<GasTimer>;
+    fn charge_gas(&self, name: impl Into<GasChargeName>, compute: Gas) -> Result<GasTimer>;
+
+    /// Charges for copying `bytes` bytes between the actor's memory and the host. Syscalls must
+    /// call this for every buffer they read from or write to the actor's memory, unless the copy
+    /// is already priced by the operation itself (e.g., reading and creating blocks).
+    fn charge_copy(&self, bytes: usize) -> Result<()>;
+
+    /// Charges for allocating wasm memory. Memory gas is accounted separately from other gas, but
+    /// otherwise behaves like [`GasOps::charge_gas`].
+    fn charge_memory(&self, name: impl Into<GasChargeName>, gas: Gas) -> Result<GasTimer>;
+
+    /// Applies several related charges at once, checking their total against the gas limit a single
+    /// time, and returns one timer per charge. See [`GasTracker::apply_charges`].
+    ///
+    /// [`GasTracker::apply_charges`]: crate::gas::GasTracker::apply_charges
+    fn charge_gas_many(&self, charges: &[GasCharge]) -> Result<Vec<GasTimer>>;
+
+    /// Returns true if gas charges are recorded (traced, or kept for out of gas checkpoints), in
+    /// which case their names must be preserved.
+    fn records_gas_charges(&self) -> bool;
 
     /// Returns the currently active gas price list.
     fn price_list(&self) -> &PriceList;
@@ -341,6 +393,18 @@ pub trait RandomnessOps {
         rand_epoch: ChainEpoch,
         entropy: &[u8],
     ) -> Result<[u8; RANDOMNESS_LENGTH]>;
+
+    /// Verifies a beacon entry (round and signature) against the network's beacon chain,
+    /// allowing actors to accept externally supplied randomness for past beacon rounds.
+    ///
+    /// For chained beacons, `prev_signature` must be the signature of the previous round. It's
+    /// empty for unchained beacons.
+    fn verify_beacon_entry(
+        &self,
+        round: u64,
+        prev_signature: &[u8],
+        signature: &[u8],
+    ) -> Result<bool>;
 }
 
 /// Debugging APIs.
@@ -351,9 +415,10 @@ pub trait DebugOps {
     /// Returns whether debug mode is enabled.
     fn debug_enabled(&self) -> bool;
 
-    /// Store an artifact.
+    /// Store an artifact. Stored artifacts are returned to the host in
+    /// [`ApplyRet::artifacts`](crate::executor::ApplyRet::artifacts).
     /// Returns error on malformed name, returns Ok and logs the error on system/os errors.
-    fn store_artifact(&self, name: &str, data: &[u8]) -> Result<()>;
+    fn store_artifact(&mut self, name: &str, data: &[u8]) -> Result<()>;
 }
 
 /// Track and limit memory expansion.
@@ -40,6 +40,10 @@ pub mod system_actor;
 
 mod eam_actor;
 mod history_map;
+#[cfg(feature = "metrics")]
+pub mod metrics;
+#[cfg(feature = "replay")]
+pub mod replay;
 pub mod trace;
 
 use cid::multihash::{Code, MultihashDigest};
@@ -70,6 +74,7 @@ mod test {
     use crate::state_tree::StateTree;
     use crate::{executor, DefaultKernel};
 
+    #[derive(Clone)]
     struct DummyExterns;
 
     impl Externs for DummyExterns {}
@@ -91,6 +96,15 @@ mod test {
         ) -> anyhow::Result<[u8; 32]> {
             todo!()
         }
+
+        fn verify_beacon_entry(
+            &self,
+            _round: u64,
+            _prev_signature: &[u8],
+            _signature: &[u8],
+        ) -> anyhow::Result<bool> {
+            todo!()
+        }
     }
 
     impl Consensus for DummyExterns {
@@ -131,7 +145,8 @@ mod test {
 
         let mc = NetworkConfig::new(fvm_shared::version::NetworkVersion::V18)
             .override_actors(actors_cid)
-            .for_epoch(0, 0, root);
+            .for_epoch(0, 0, root)
+            .unwrap();
 
         let machine = DefaultMachine::new(&mc, bs, DummyExterns).unwrap();
         let engine = EnginePool::new_default((&mc.network).into()).unwrap();
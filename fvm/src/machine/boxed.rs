@@ -1,6 +1,8 @@
 // Copyright 2021-2023 Protocol Labs
 // SPDX-License-Identifier: Apache-2.0, MIT
 use cid::Cid;
+use fvm_shared::clock::ChainEpoch;
+use fvm_shared::consensus::ConsensusFault;
 
 use super::{Machine, MachineContext, Manifest};
 use crate::kernel::Result;
@@ -33,6 +35,16 @@ impl<M: Machine> Machine for Box<M> {
         (**self).builtin_actors()
     }
 
+    #[inline(always)]
+    fn set_builtin_actors(&mut self, manifest: Manifest) -> anyhow::Result<()> {
+        (**self).set_builtin_actors(manifest)
+    }
+
+    #[inline(always)]
+    fn advance_epoch(&mut self, epoch: ChainEpoch, timestamp: u64) -> anyhow::Result<()> {
+        (**self).advance_epoch(epoch, timestamp)
+    }
+
     #[inline(always)]
     fn state_tree(&self) -> &StateTree<Self::Blockstore> {
         (**self).state_tree()
@@ -48,6 +60,16 @@ impl<M: Machine> Machine for Box<M> {
         (**self).flush()
     }
 
+    #[inline(always)]
+    fn fork(&mut self) -> anyhow::Result<Self> {
+        Ok(Box::new((**self).fork()?))
+    }
+
+    #[inline(always)]
+    fn flush_partial(&mut self) -> Result<Cid> {
+        (**self).flush_partial()
+    }
+
     #[inline(always)]
     fn into_store(self) -> Self::Blockstore {
         (*self).into_store()
@@ -62,4 +84,19 @@ impl<M: Machine> Machine for Box<M> {
     fn new_limiter(&self) -> Self::Limiter {
         (**self).new_limiter()
     }
+
+    #[inline(always)]
+    fn tipset_cid(&self, epoch: ChainEpoch) -> anyhow::Result<Cid> {
+        (**self).tipset_cid(epoch)
+    }
+
+    #[inline(always)]
+    fn verify_consensus_fault(
+        &self,
+        h1: &[u8],
+        h2: &[u8],
+        extra: &[u8],
+    ) -> anyhow::Result<(Option<ConsensusFault>, i64)> {
+        (**self).verify_consensus_fault(h1, h2, extra)
+    }
 }
@@ -1,18 +1,21 @@
 // Copyright 2021-2023 Protocol Labs
 // SPDX-License-Identifier: Apache-2.0, MIT
-use std::ops::RangeInclusive;
+use std::cell::RefCell;
+use std::collections::HashMap;
 
 use anyhow::{anyhow, Context as _};
 use cid::Cid;
 use fvm_ipld_blockstore::{Block, Blockstore, Buffered};
 use fvm_ipld_encoding::{to_vec, CborStore, DAG_CBOR};
+use fvm_shared::clock::ChainEpoch;
+use fvm_shared::consensus::ConsensusFault;
 use fvm_shared::version::NetworkVersion;
 use log::debug;
 use multihash::Code::Blake2b256;
 
-use super::{Machine, MachineContext};
+use super::{Machine, MachineContext, NetworkSchedule};
 use crate::blockstore::BufferedBlockstore;
-use crate::externs::Externs;
+use crate::externs::{Chain, Consensus, Externs};
 use crate::kernel::{ClassifyResult, Result};
 use crate::machine::limiter::DefaultMemoryLimiter;
 use crate::machine::Manifest;
@@ -43,6 +46,11 @@ pub struct DefaultMachine<B, E> {
     /// Somewhat unique ID of the machine consisting of (epoch, randomness)
     /// randomness is generated with `initial_state_root`
     id: String,
+    /// Tipset CIDs previously looked up through the externs, by epoch.
+    tipset_cids: RefCell<HashMap<ChainEpoch, Cid>>,
+    /// Consensus fault verification results previously returned by the externs, keyed by the
+    /// hash of the verified headers.
+    consensus_faults: RefCell<HashMap<[u8; 32], (Option<ConsensusFault>, i64)>>,
 }
 
 impl<B, E> DefaultMachine<B, E>
@@ -59,25 +67,29 @@ where
     /// * `blockstore`: The underlying [blockstore][`Blockstore`] for reading/writing state.
     /// * `externs`: Client-provided ["external"][`Externs`] methods for accessing chain state.
     pub fn new(context: &MachineContext, blockstore: B, externs: E) -> anyhow::Result<Self> {
-        #[cfg(not(feature = "nv21-dev"))]
-        const SUPPORTED_VERSIONS: RangeInclusive<NetworkVersion> =
-            NetworkVersion::V18..=NetworkVersion::V20;
-
-        #[cfg(feature = "nv21-dev")]
-        const SUPPORTED_VERSIONS: RangeInclusive<NetworkVersion> =
-            NetworkVersion::V18..=NetworkVersion::V21;
+        // Resolve the base fee for this epoch, if we have a schedule.
+        let mut context = context.clone();
+        context.base_fee = context.resolve_base_fee();
 
         debug!(
             "initializing a new machine, epoch={}, base_fee={}, nv={:?}, root={}",
             context.epoch, &context.base_fee, context.network_version, context.initial_state_root
         );
 
-        if !SUPPORTED_VERSIONS.contains(&context.network_version) {
+        if NetworkSchedule::get(context.network_version).is_none() {
             return Err(anyhow!(
                 "unsupported network version: {}",
                 context.network_version
             ));
         }
+        if context.network_version != context.network_version_at(context.epoch) {
+            return Err(anyhow!(
+                "machine context for epoch {} has network version {}, expected {}",
+                context.epoch,
+                context.network_version,
+                context.network_version_at(context.epoch)
+            ));
+        }
 
         // Sanity check that the blockstore contains the supplied state root.
         if !blockstore
@@ -94,8 +106,10 @@ where
 
         // Create a new state tree from the supplied root.
         let state_tree = {
-            let bstore = BufferedBlockstore::new(blockstore);
-            StateTree::new_from_root(bstore, &context.initial_state_root)?
+            let bstore = BufferedBlockstore::with_limits(blockstore, context.buffer_limits);
+            let mut state_tree = StateTree::new_from_root(bstore, &context.initial_state_root)?;
+            state_tree.set_cache_limits(context.actor_cache_limits);
+            state_tree
         };
 
         // Load the built-in actors manifest.
@@ -109,33 +123,31 @@ where
             }
             None => {
                 let (state, _) = SystemActorState::load(&state_tree)?;
-                (state.builtin_actors, 1)
+                (
+                    state.builtin_actors,
+                    manifest_version(context.network_version)?,
+                )
             }
         };
         let builtin_actors =
             Manifest::load(state_tree.store(), &builtin_actors_cid, manifest_version)?;
 
-        // 16 bytes is random _enough_
-        let randomness: [u8; 16] = rand::random();
-
         Ok(DefaultMachine {
-            context: context.clone(),
+            id: machine_id(context.epoch),
+            context,
             externs,
             state_tree,
             builtin_actors,
-            id: format!(
-                "{}-{}",
-                context.epoch,
-                cid::multibase::encode(cid::multibase::Base::Base32Lower, randomness)
-            ),
+            tipset_cids: Default::default(),
+            consensus_faults: Default::default(),
         })
     }
 }
 
 impl<B, E> Machine for DefaultMachine<B, E>
 where
-    B: Blockstore + 'static,
-    E: Externs + 'static,
+    B: Blockstore + Clone + 'static,
+    E: Externs + Clone + 'static,
 {
     type Blockstore = BufferedBlockstore<B>;
     type Externs = E;
@@ -157,6 +169,76 @@ where
         &self.builtin_actors
     }
 
+    fn set_builtin_actors(&mut self, manifest: Manifest) -> anyhow::Result<()> {
+        self.builtin_actors = manifest;
+        Ok(())
+    }
+
+    /// Advances the machine to a later epoch. When crossing a network upgrade, the builtin actors
+    /// are reloaded from the system actor (unless overridden), picking up any new actors installed
+    /// by the upgrade's migration.
+    fn advance_epoch(&mut self, epoch: ChainEpoch, timestamp: u64) -> anyhow::Result<()> {
+        if epoch < self.context.epoch {
+            return Err(anyhow!(
+                "cannot move the machine back from epoch {} to epoch {}",
+                self.context.epoch,
+                epoch
+            ));
+        }
+
+        let mut context = self.context.clone();
+        context.epoch = epoch;
+        context.timestamp = timestamp;
+        context.base_fee = context.resolve_base_fee();
+
+        let nv = context.network_version_at(epoch);
+        if nv != context.network_version {
+            debug!(
+                "upgrading machine from nv={:?} to nv={:?} at epoch={}",
+                context.network_version, nv, epoch
+            );
+            context.set_network_version(nv)?;
+            if context.builtin_actors_override.is_none() {
+                let (state, _) = SystemActorState::load(&self.state_tree)?;
+                self.builtin_actors = Manifest::load(
+                    self.state_tree.store(),
+                    &state.builtin_actors,
+                    manifest_version(nv)?,
+                )?;
+            }
+        }
+
+        self.id = machine_id(epoch);
+        self.context = context;
+        Ok(())
+    }
+
+    /// Forks the machine. The fork gets a clone of the base blockstore (which should be cheap to
+    /// clone and share the underlying store, e.g., an `Arc`) and of the externs, and its own state
+    /// tree cache. The write buffer isn't copied: the blocks buffered so far are shared
+    /// (copy-on-write) by both machines, and each buffers its subsequent writes separately.
+    fn fork(&mut self) -> anyhow::Result<Self> {
+        let root = self
+            .flush_partial()
+            .map_err(anyhow::Error::from)
+            .context("failed to flush the state before forking")?;
+
+        let mut context = self.context.clone();
+        context.initial_state_root = root;
+        let mut state_tree = StateTree::new_from_root(self.state_tree.store().fork(), &root)?;
+        state_tree.set_cache_limits(context.actor_cache_limits);
+
+        Ok(DefaultMachine {
+            id: machine_id(context.epoch),
+            context,
+            externs: self.externs.clone(),
+            state_tree,
+            builtin_actors: self.builtin_actors.clone(),
+            tipset_cids: self.tipset_cids.clone(),
+            consensus_faults: self.consensus_faults.clone(),
+        })
+    }
+
     fn state_tree(&self) -> &StateTree<Self::Blockstore> {
         &self.state_tree
     }
@@ -169,7 +251,9 @@ where
     ///
     /// This method also flushes all new blocks (reachable from this new root CID) from the write
     /// buffer into the underlying blockstore (the blockstore with which the machine was
-    /// constructed).
+    /// constructed). Use [`Machine::flush_partial`] to leave the new blocks in the write buffer,
+    /// e.g., for intermediate checkpoints: blocks orphaned by subsequent changes will then never be
+    /// written to the underlying blockstore.
     fn flush(&mut self) -> Result<Cid> {
         let root = self.state_tree_mut().flush()?;
         self.blockstore().flush(&root).or_fatal()?;
@@ -187,6 +271,43 @@ where
     fn new_limiter(&self) -> Self::Limiter {
         DefaultMemoryLimiter::for_network(&self.context().network)
     }
+
+    /// Looks up the tipset CID through the externs, caching the result for the lifetime of the
+    /// machine.
+    fn tipset_cid(&self, epoch: ChainEpoch) -> anyhow::Result<Cid> {
+        if let Some(cid) = self.tipset_cids.borrow().get(&epoch) {
+            return Ok(*cid);
+        }
+        let cid = self.externs.get_tipset_cid(epoch)?;
+        self.tipset_cids.borrow_mut().insert(epoch, cid);
+        Ok(cid)
+    }
+
+    /// Verifies the consensus fault through the externs, caching the result (keyed by the headers)
+    /// for the lifetime of the machine.
+    fn verify_consensus_fault(
+        &self,
+        h1: &[u8],
+        h2: &[u8],
+        extra: &[u8],
+    ) -> anyhow::Result<(Option<ConsensusFault>, i64)> {
+        let key = {
+            let mut state = blake2b_simd::Params::new().hash_length(32).to_state();
+            for part in [h1, h2, extra] {
+                state.update(&(part.len() as u64).to_le_bytes());
+                state.update(part);
+            }
+            let mut key = [0u8; 32];
+            key.copy_from_slice(state.finalize().as_bytes());
+            key
+        };
+        if let Some(res) = self.consensus_faults.borrow().get(&key) {
+            return Ok(res.clone());
+        }
+        let res = self.externs.verify_consensus_fault(h1, h2, extra)?;
+        self.consensus_faults.borrow_mut().insert(key, res.clone());
+        Ok(res)
+    }
 }
 
 // Helper method that puts certain "empty" types in the blockstore.
@@ -201,3 +322,22 @@ fn put_empty_blocks<B: Blockstore>(blockstore: B) -> anyhow::Result<()> {
 
     Ok(())
 }
+
+// Returns the version of the builtin actors manifest installed in the system actor at the given
+// network version (the system actor only records the manifest's data, not its version).
+fn manifest_version(nv: NetworkVersion) -> anyhow::Result<u32> {
+    NetworkSchedule::get(nv)
+        .map(|schedule| schedule.manifest_version)
+        .ok_or_else(|| anyhow!("unsupported network version: {}", nv))
+}
+
+// Generates a somewhat unique machine ID from the epoch and some randomness.
+fn machine_id(epoch: ChainEpoch) -> String {
+    // 16 bytes is random _enough_
+    let randomness: [u8; 16] = rand::random();
+    format!(
+        "{}-{}",
+        epoch,
+        cid::multibase::encode(cid::multibase::Base::Base32Lower, randomness)
+    )
+}
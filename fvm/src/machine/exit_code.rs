@@ -0,0 +1,114 @@
+// Copyright 2021-2023 Protocol Labs
+// SPDX-License-Identifier: Apache-2.0, MIT
+use std::fmt::Debug;
+
+use fvm_shared::error::{ErrorNumber, ExitCode};
+
+/// Maps execution failures to the exit codes recorded in message receipts. Set it with
+/// [`NetworkConfig::exit_code_policy`](super::NetworkConfig::exit_code_policy) to introduce custom
+/// system exit codes. This is consensus-critical.
+///
+/// Every method defaults to the standard Filecoin mapping (see [`DefaultExitCodePolicy`]), so
+/// policies need only override the cases they change.
+pub trait ExitCodePolicy: Debug + Send + Sync {
+    /// The exit code of a message that couldn't be dispatched because the top-level send failed
+    /// with the given error.
+    fn send_error(&self, err: ErrorNumber) -> ExitCode {
+        match err {
+            ErrorNumber::InsufficientFunds => ExitCode::SYS_INSUFFICIENT_FUNDS,
+            ErrorNumber::NotFound => ExitCode::SYS_INVALID_RECEIVER,
+            _ => ExitCode::SYS_ASSERTION_FAILED,
+        }
+    }
+
+    /// The exit code of a call that ran out of gas.
+    fn out_of_gas(&self) -> ExitCode {
+        ExitCode::SYS_OUT_OF_GAS
+    }
+
+    /// The exit code of a call that failed with a fatal error.
+    fn fatal(&self) -> ExitCode {
+        ExitCode::SYS_ASSERTION_FAILED
+    }
+
+    /// The exit code of a call that trapped (e.g., reached an `unreachable` instruction or accessed
+    /// memory out of bounds), or whose actor module failed to instantiate.
+    fn illegal_instruction(&self) -> ExitCode {
+        ExitCode::SYS_ILLEGAL_INSTRUCTION
+    }
+
+    /// The exit code of a message whose sender (or payer) is invalid: it doesn't exist, can't send
+    /// messages, or didn't sign the message.
+    fn sender_invalid(&self) -> ExitCode {
+        ExitCode::SYS_SENDER_INVALID
+    }
+
+    /// The exit code of a message whose sender (or payer) can't afford the message's gas.
+    fn sender_state_invalid(&self) -> ExitCode {
+        ExitCode::SYS_SENDER_STATE_INVALID
+    }
+
+    /// The exit code of a call that returned a block that doesn't exist.
+    fn missing_return(&self) -> ExitCode {
+        ExitCode::SYS_MISSING_RETURN
+    }
+
+    /// The exit code of an actor that aborted with the given (non-zero) exit code. Actors may not
+    /// abort with system exit codes.
+    fn actor_exit(&self, code: ExitCode) -> ExitCode {
+        if code.is_system_error() {
+            ExitCode::SYS_ILLEGAL_EXIT_CODE
+        } else {
+            code
+        }
+    }
+}
+
+/// The standard Filecoin exit code mapping.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DefaultExitCodePolicy;
+
+impl ExitCodePolicy for DefaultExitCodePolicy {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug)]
+    struct CustomPolicy;
+
+    impl ExitCodePolicy for CustomPolicy {
+        fn send_error(&self, err: ErrorNumber) -> ExitCode {
+            match err {
+                ErrorNumber::LimitExceeded => ExitCode::new(14),
+                _ => DefaultExitCodePolicy.send_error(err),
+            }
+        }
+    }
+
+    #[test]
+    fn override_single_case() {
+        assert_eq!(
+            CustomPolicy.send_error(ErrorNumber::LimitExceeded),
+            ExitCode::new(14)
+        );
+        assert_eq!(
+            CustomPolicy.send_error(ErrorNumber::NotFound),
+            ExitCode::SYS_INVALID_RECEIVER
+        );
+        assert_eq!(CustomPolicy.out_of_gas(), ExitCode::SYS_OUT_OF_GAS);
+        assert_eq!(
+            CustomPolicy.illegal_instruction(),
+            ExitCode::SYS_ILLEGAL_INSTRUCTION
+        );
+        assert_eq!(CustomPolicy.sender_invalid(), ExitCode::SYS_SENDER_INVALID);
+        assert_eq!(
+            CustomPolicy.actor_exit(ExitCode::SYS_OUT_OF_GAS),
+            ExitCode::SYS_ILLEGAL_EXIT_CODE
+        );
+        assert_eq!(
+            CustomPolicy.actor_exit(ExitCode::USR_NOT_FOUND),
+            ExitCode::USR_NOT_FOUND
+        );
+    }
+}
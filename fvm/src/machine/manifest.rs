@@ -1,11 +1,22 @@
 // Copyright 2021-2023 Protocol Labs
 // SPDX-License-Identifier: Apache-2.0, MIT
 use std::collections::HashMap;
+#[cfg(feature = "bundles")]
+use std::io::Read;
 
 use anyhow::{anyhow, Context};
 use cid::Cid;
+#[cfg(feature = "bundles")]
+use futures::executor::block_on;
+#[cfg(feature = "bundles")]
+use futures::io::AllowStdIo;
 use fvm_ipld_blockstore::Blockstore;
 use fvm_ipld_encoding::CborStore;
+#[cfg(feature = "bundles")]
+use fvm_shared::version::NetworkVersion;
+
+#[cfg(feature = "bundles")]
+use super::NetworkSchedule;
 
 const ACCOUNT_ACTOR_NAME: &str = "account";
 const INIT_ACTOR_NAME: &str = "init";
@@ -15,6 +26,7 @@ const EAM_ACTOR_NAME: &str = "eam";
 const ETHACCOUNT_ACTOR_NAME: &str = "ethaccount";
 
 /// A mapping of builtin actor CIDs to their respective types.
+#[derive(Clone)]
 pub struct Manifest {
     account_code: Cid,
     placeholder_code: Cid,
@@ -25,6 +37,7 @@ pub struct Manifest {
 
     by_id: HashMap<u32, Cid>,
     by_code: HashMap<Cid, u32>,
+    names: HashMap<Cid, String>,
 }
 
 /// Create an "id CID" (for testing).
@@ -85,11 +98,63 @@ impl Manifest {
         Manifest::new(vec)
     }
 
+    /// Load a builtin-actors bundle (a CAR file rooted at a versioned manifest) into the
+    /// blockstore, returning the CID of the versioned manifest (suitable for
+    /// [`NetworkConfig::override_actors`](super::NetworkConfig::override_actors)) along with the
+    /// loaded manifest.
+    ///
+    /// Fails if the bundle's manifest version isn't the one expected by the network version's
+    /// [`NetworkSchedule`], or if the bundle is missing the code of any of its actors.
+    #[cfg(feature = "bundles")]
+    pub fn load_bundle<B: Blockstore>(
+        bs: &B,
+        reader: impl Read + Send,
+        network_version: NetworkVersion,
+    ) -> anyhow::Result<(Cid, Manifest)> {
+        let schedule = NetworkSchedule::get(network_version)
+            .ok_or_else(|| anyhow!("unsupported network version: {}", network_version))?;
+
+        let roots = block_on(fvm_ipld_car::load_car(bs, AllowStdIo::new(reader)))
+            .context("failed to load actor bundle")?;
+        let root = match &*roots {
+            [root] => *root,
+            _ => {
+                return Err(anyhow!(
+                    "expected exactly one root in actor bundle, found {}",
+                    roots.len()
+                ))
+            }
+        };
+
+        let (version, data): (u32, Cid) = bs
+            .get_cbor(&root)
+            .context("failed to decode actor bundle manifest")?
+            .ok_or_else(|| anyhow!("actor bundle is missing its manifest {}", root))?;
+        if version != schedule.manifest_version {
+            return Err(anyhow!(
+                "actor bundle has manifest version {}, but network version {} expects version {}",
+                version,
+                network_version,
+                schedule.manifest_version
+            ));
+        }
+
+        let manifest = Manifest::load(bs, &data, version)?;
+        for code in manifest.builtin_actor_codes() {
+            if !bs.has(code)? {
+                return Err(anyhow!("actor bundle is missing the code for {}", code));
+            }
+        }
+
+        Ok((root, manifest))
+    }
+
     /// Construct a new manifest from actor name/cid tuples.
     pub fn new(iter: impl IntoIterator<Item = (impl Into<String>, Cid)>) -> anyhow::Result<Self> {
         let mut by_name = HashMap::new();
         let mut by_id = HashMap::new();
         let mut by_code = HashMap::new();
+        let mut names = HashMap::new();
 
         // Actors are indexed sequentially, starting at 1, in the order in which they appear in the
         // manifest. 0 is reserved for "everything else" (i.e., not a builtin actor).
@@ -97,6 +162,7 @@ impl Manifest {
             let name = name.into();
             by_id.insert(id, code_cid);
             by_code.insert(code_cid, id);
+            names.insert(code_cid, name.clone());
             by_name.insert(name, code_cid);
         }
 
@@ -133,6 +199,7 @@ impl Manifest {
             ethaccount_code,
             by_id,
             by_code,
+            names,
         })
     }
 
@@ -146,6 +213,11 @@ impl Manifest {
         self.by_code.get(code).copied().unwrap_or(0)
     }
 
+    /// Returns the name of the builtin actor with the given code (e.g., "account"), if any.
+    pub fn name_by_code(&self, code: &Cid) -> Option<&str> {
+        self.names.get(code).map(String::as_str)
+    }
+
     /// Returns true id the passed code CID is the account actor.
     pub fn is_account_actor(&self, cid: &Cid) -> bool {
         &self.account_code == cid
@@ -195,3 +267,60 @@ impl Manifest {
         &self.ethaccount_code
     }
 }
+
+#[cfg(all(test, feature = "bundles"))]
+mod tests {
+    use cid::multihash::{Code, MultihashDigest};
+    use fvm_ipld_blockstore::MemoryBlockstore;
+    use fvm_ipld_car::CarHeader;
+    use fvm_ipld_encoding::{to_vec, DAG_CBOR};
+    use fvm_shared::IPLD_RAW;
+
+    use super::*;
+
+    fn bundle(version: u32) -> (Cid, Vec<u8>) {
+        let mut blocks = Vec::new();
+        let mut actors = Vec::new();
+        for name in [
+            "system",
+            "init",
+            "eam",
+            "ethaccount",
+            "account",
+            "placeholder",
+        ] {
+            let code = format!("{} actor code", name).into_bytes();
+            let cid = Cid::new_v1(IPLD_RAW, Code::Blake2b256.digest(&code));
+            blocks.push((cid, code));
+            actors.push((name.to_owned(), cid));
+        }
+        let data = to_vec(&actors).unwrap();
+        let data_cid = Cid::new_v1(DAG_CBOR, Code::Blake2b256.digest(&data));
+        blocks.push((data_cid, data));
+        let root = to_vec(&(version, data_cid)).unwrap();
+        let root_cid = Cid::new_v1(DAG_CBOR, Code::Blake2b256.digest(&root));
+        blocks.push((root_cid, root));
+
+        let mut car = Vec::new();
+        block_on(CarHeader::from(vec![root_cid]).write_stream_async(
+            &mut AllowStdIo::new(&mut car),
+            &mut futures::stream::iter(blocks),
+        ))
+        .unwrap();
+        (actors[4].1, car)
+    }
+
+    #[test]
+    fn load_bundle() {
+        let bs = MemoryBlockstore::new();
+        let (account_code, car) = bundle(1);
+        let (_, manifest) = Manifest::load_bundle(&bs, &*car, NetworkVersion::V18).unwrap();
+        assert_eq!(manifest.get_account_code(), &account_code);
+
+        let (_, car) = bundle(2);
+        let err = Manifest::load_bundle(&bs, &*car, NetworkVersion::V18)
+            .err()
+            .expect("expected a manifest version mismatch");
+        assert!(err.to_string().contains("manifest version 2"));
+    }
+}
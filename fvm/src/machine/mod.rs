@@ -1,18 +1,29 @@
 // Copyright 2021-2023 Protocol Labs
 // SPDX-License-Identifier: Apache-2.0, MIT
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::Context as _;
 use cid::Cid;
 use derive_more::{Deref, DerefMut};
 use fvm_ipld_blockstore::Blockstore;
 use fvm_shared::clock::ChainEpoch;
+use fvm_shared::consensus::ConsensusFault;
 use fvm_shared::econ::TokenAmount;
+use fvm_shared::error::ErrorNumber;
 use fvm_shared::version::NetworkVersion;
-use fvm_shared::ActorID;
+use fvm_shared::{ActorID, BLOCK_GAS_LIMIT};
 use num_traits::Zero;
 
-use crate::externs::Externs;
-use crate::gas::{price_list_by_network_version, PriceList};
+use crate::blockstore::BufferLimits;
+use crate::externs::{Chain, Consensus, Externs};
+use crate::gas::{PriceList, PriceOverrides};
 use crate::kernel::Result;
-use crate::state_tree::StateTree;
+use crate::state_tree::{ActorCacheLimits, StateTree};
+use crate::trace::TraceConfig;
 
 mod default;
 
@@ -27,6 +38,16 @@ pub use manifest::Manifest;
 use self::limiter::MemoryLimiter;
 
 mod boxed;
+mod exit_code;
+mod multi;
+mod names;
+mod schedule;
+
+pub use exit_code::{DefaultExitCodePolicy, ExitCodePolicy};
+pub use multi::{MultiMachine, MultiMachineExecutor};
+pub(crate) use names::resolve_names;
+pub use names::NameResolver;
+pub use schedule::{NetworkSchedule, NV21_SYSCALLS};
 
 pub const REWARD_ACTOR_ID: ActorID = 2;
 
@@ -62,6 +83,42 @@ pub trait Machine: 'static {
     /// Returns the builtin actor index.
     fn builtin_actors(&self) -> &Manifest;
 
+    /// Replaces the builtin actor index, e.g., after loading a new actor bundle with
+    /// [`Manifest::load_bundle`]. By default, machines don't support replacing their builtin
+    /// actors.
+    fn set_builtin_actors(&mut self, _manifest: Manifest) -> anyhow::Result<()> {
+        Err(anyhow::anyhow!(
+            "this machine doesn't support replacing its builtin actors"
+        ))
+    }
+
+    /// Advances the machine to a later epoch (e.g., the next tipset when replaying a range of
+    /// tipsets), continuing from the current state. The base fee is re-resolved through the
+    /// [`MachineContext::base_fee_schedule`], if set, and the machine switches to the network
+    /// version in effect at the new epoch according to the [`NetworkConfig::fork_schedule`].
+    ///
+    /// Any state migration due at a network upgrade must be applied by the caller. By default,
+    /// machines don't support advancing epochs.
+    fn advance_epoch(&mut self, _epoch: ChainEpoch, _timestamp: u64) -> anyhow::Result<()> {
+        Err(anyhow::anyhow!(
+            "this machine doesn't support advancing epochs"
+        ))
+    }
+
+    /// Forks the machine, returning an independent machine starting from the current state
+    /// (including any changes not yet flushed to the blockstore), e.g., to run concurrent what-if
+    /// executions from the same state. The current state is flushed with
+    /// [`Machine::flush_partial`] first.
+    ///
+    /// The fork shares the base blockstore, but buffers its own writes and has its own state tree
+    /// cache. By default, machines don't support forking.
+    fn fork(&mut self) -> anyhow::Result<Self>
+    where
+        Self: Sized,
+    {
+        Err(anyhow::anyhow!("this machine doesn't support forking"))
+    }
+
     /// Returns an immutable reference to the state tree.
     fn state_tree(&self) -> &StateTree<Self::Blockstore>;
 
@@ -73,6 +130,14 @@ pub trait Machine: 'static {
         self.state_tree_mut().flush()
     }
 
+    /// Flushes the state-tree and returns the new root CID, without necessarily persisting the new
+    /// state beyond the machine's own blockstore. This is cheaper than [`Machine::flush`] and
+    /// intended for intermediate checkpoints (e.g., between messages): the returned root can be
+    /// read through the machine, but must be persisted with a final [`Machine::flush`].
+    fn flush_partial(&mut self) -> Result<Cid> {
+        self.state_tree_mut().flush()
+    }
+
     /// Consumes the machine and returns the owned blockstore.
     fn into_store(self) -> Self::Blockstore;
 
@@ -81,6 +146,99 @@ pub trait Machine: 'static {
 
     /// Creates a new limiter to track the resources of a message execution.
     fn new_limiter(&self) -> Self::Limiter;
+
+    /// Returns the CID of the tipset at the given epoch. By default, this simply queries the
+    /// externs, but implementations may cache the result as it can't change over the lifetime of
+    /// the machine.
+    fn tipset_cid(&self, epoch: ChainEpoch) -> anyhow::Result<Cid> {
+        self.externs().get_tipset_cid(epoch)
+    }
+
+    /// Verifies a consensus fault. By default, this simply queries the externs, but
+    /// implementations may cache the result as it only depends on the supplied headers.
+    fn verify_consensus_fault(
+        &self,
+        h1: &[u8],
+        h2: &[u8],
+        extra: &[u8],
+    ) -> anyhow::Result<(Option<ConsensusFault>, i64)> {
+        self.externs().verify_consensus_fault(h1, h2, extra)
+    }
+}
+
+/// Determines what happens to an actor's remaining balance when it self-destructs.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum SelfDestructPolicy {
+    /// Transfer the remaining balance to the beneficiary. The beneficiary must exist and must not
+    /// be the actor itself.
+    TransferToBeneficiary,
+    /// Burn the remaining balance by transferring it to the burnt funds actor. The beneficiary is
+    /// ignored.
+    Burn,
+    /// Refuse to self-destruct with [`IllegalOperation`](fvm_shared::error::ErrorNumber::IllegalOperation).
+    Disallow,
+}
+
+impl SelfDestructPolicy {
+    /// Returns the self-destruct policy registered for the given network version (see
+    /// [`NetworkSchedule`]), defaulting to [`SelfDestructPolicy::TransferToBeneficiary`].
+    pub fn for_network_version(network_version: NetworkVersion) -> Self {
+        NetworkSchedule::get(network_version)
+            .map(|schedule| schedule.self_destruct_policy)
+            .unwrap_or(SelfDestructPolicy::TransferToBeneficiary)
+    }
+}
+
+/// Limits enforced when validating actor events.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct ActorEventLimits {
+    /// The maximum size of an encoded event, checked before decoding it.
+    pub max_encoded_size: usize,
+    /// The maximum number of entries in an event.
+    pub max_entries: usize,
+    /// The maximum length of an entry key, in bytes.
+    pub max_key_len: usize,
+    /// The maximum combined size of all entry values in an event, in bytes.
+    pub max_total_values_len: usize,
+    /// Whether event validation failures return precise error numbers
+    /// ([`ErrorNumber::LimitExceeded`] when a limit is exceeded, [`ErrorNumber::Serialization`]
+    /// when the event fails to decode) instead of [`ErrorNumber::IllegalArgument`]. Actors can
+    /// observe these error numbers, so this must only be enabled as part of a network upgrade.
+    pub precise_errors: bool,
+}
+
+impl ActorEventLimits {
+    /// The error number returned when an event exceeds one of these limits.
+    pub(crate) fn limit_exceeded_error(&self) -> ErrorNumber {
+        if self.precise_errors {
+            ErrorNumber::LimitExceeded
+        } else {
+            ErrorNumber::IllegalArgument
+        }
+    }
+
+    /// The error number returned when an event fails to decode.
+    pub(crate) fn serialization_error(&self) -> ErrorNumber {
+        if self.precise_errors {
+            ErrorNumber::Serialization
+        } else {
+            ErrorNumber::IllegalArgument
+        }
+    }
+}
+
+impl Default for ActorEventLimits {
+    fn default() -> Self {
+        ActorEventLimits {
+            // This is an over-estimation of the maximum event size, for safety. No valid event can
+            // even get close to this.
+            max_encoded_size: 1 << 20,
+            max_entries: 256,
+            max_key_len: 32,
+            max_total_values_len: 8 << 10,
+            precise_errors: false,
+        }
+    }
 }
 
 /// Network-level settings. Except when testing locally, changing any of these likely requires a
@@ -95,6 +253,24 @@ pub struct NetworkConfig {
     /// DEFAULT: 0 (Invalid)
     pub chain_id: ChainID,
 
+    /// The network version activated at each network upgrade epoch (see
+    /// [`NetworkConfig::fork_schedule`]). Contexts created with [`NetworkConfig::for_epoch`] run
+    /// at the network version in effect at their epoch, and machines switch network versions as
+    /// they're advanced across upgrades (see [`Machine::advance_epoch`]).
+    ///
+    /// DEFAULT: empty (always [`NetworkConfig::network_version`])
+    pub fork_schedule: BTreeMap<ChainEpoch, NetworkVersion>,
+
+    /// The time of the genesis block (seconds since the unix epoch).
+    ///
+    /// DEFAULT: 0
+    pub genesis_timestamp: u64,
+
+    /// The maximum amount of gas that may be used by all messages in a block.
+    ///
+    /// DEFAULT: [`BLOCK_GAS_LIMIT`]
+    pub block_gas_limit: u64,
+
     /// The maximum call depth.
     ///
     /// DEFAULT: 1024
@@ -132,30 +308,87 @@ pub struct NetworkConfig {
     /// DEFAULT: `false`
     pub actor_debugging: bool,
 
+    /// Enable the wasm bulk-memory proposal.
+    ///
+    /// DEFAULT: `true`
+    pub wasm_bulk_memory: bool,
+
+    /// Enable the deterministic subset of the wasm SIMD proposal (integer, bitwise, and memory
+    /// instructions). Floating point and relaxed SIMD instructions are always rejected.
+    ///
+    /// DEFAULT: `false`
+    pub wasm_simd: bool,
+
+    /// What happens to an actor's remaining balance when it self-destructs.
+    ///
+    /// DEFAULT: The self-destruct policy for the current network version.
+    pub self_destruct_policy: SelfDestructPolicy,
+
+    /// The limits enforced when validating actor events.
+    ///
+    /// DEFAULT: [`ActorEventLimits::default`]
+    pub event_limits: ActorEventLimits,
+
+    /// The maximum number of consensus fault verifications a single message may perform.
+    ///
+    /// DEFAULT: `u32::MAX` (unlimited)
+    pub max_consensus_fault_verifications: u32,
+
     /// The price list.
     ///
     /// DEFAULT: The price-list for the current network version.
-    pub price_list: &'static PriceList,
+    pub price_list: Cow<'static, PriceList>,
+
+    /// The gas price overrides applied on top of the price list of every network version (see
+    /// [`NetworkConfig::override_prices`]).
+    ///
+    /// DEFAULT: `None`
+    pub price_overrides: Option<PriceOverrides>,
+
+    /// Syscalls, as `(module, name)` pairs, that are unavailable to actors.
+    ///
+    /// DEFAULT: The syscalls disabled for the current network version.
+    pub disabled_syscalls: Vec<(&'static str, &'static str)>,
 
     /// Actor redirects for debug execution
     pub actor_redirect: Vec<(Cid, Cid)>,
+
+    /// Maps execution failures to message exit codes.
+    ///
+    /// DEFAULT: [`DefaultExitCodePolicy`]
+    pub exit_code_policy: Arc<dyn ExitCodePolicy>,
 }
 
 impl NetworkConfig {
     /// Create a new network config for the given network version.
+    ///
+    /// Panics if no [`NetworkSchedule`] is registered for the network version.
     pub fn new(network_version: NetworkVersion) -> Self {
+        let schedule = NetworkSchedule::get(network_version)
+            .unwrap_or_else(|| panic!("network version {nv} not supported", nv = network_version));
         NetworkConfig {
             chain_id: ChainID::from(0u64),
+            fork_schedule: BTreeMap::new(),
+            genesis_timestamp: 0,
+            block_gas_limit: BLOCK_GAS_LIMIT,
             network_version,
             max_call_depth: 1024,
             max_wasm_stack: 2048,
             max_inst_memory_bytes: 512 * (1 << 20),
             max_memory_bytes: 2 * (1 << 30),
             actor_debugging: false,
+            wasm_bulk_memory: true,
+            wasm_simd: false,
+            self_destruct_policy: schedule.self_destruct_policy,
+            event_limits: ActorEventLimits::default(),
+            max_consensus_fault_verifications: u32::MAX,
             builtin_actors_override: None,
-            price_list: price_list_by_network_version(network_version),
+            price_list: Cow::Borrowed(schedule.price_list),
+            price_overrides: None,
+            disabled_syscalls: schedule.disabled_syscalls,
             actor_redirect: vec![],
             max_block_size: 1 << 20,
+            exit_code_policy: Arc::new(DefaultExitCodePolicy),
         }
     }
 
@@ -166,6 +399,60 @@ impl NetworkConfig {
         self
     }
 
+    /// Enable or disable the wasm bulk-memory proposal. Actors using bulk-memory instructions fail
+    /// to load when disabled, so every node must use the same setting.
+    pub fn wasm_bulk_memory(&mut self, enabled: bool) -> &mut Self {
+        self.wasm_bulk_memory = enabled;
+        self
+    }
+
+    /// Enable or disable the deterministic subset of the wasm SIMD proposal. Like
+    /// [`NetworkConfig::wasm_bulk_memory`], this decides which actors can be loaded at all.
+    /// Floating point SIMD instructions are always rejected.
+    pub fn wasm_simd(&mut self, enabled: bool) -> &mut Self {
+        self.wasm_simd = enabled;
+        self
+    }
+
+    /// Set what happens to an actor's remaining balance when it self-destructs, overriding the
+    /// policy of the network version's [`NetworkSchedule`]. The policy decides where funds end up
+    /// (or whether self-destruct fails), so it changes the resulting state tree.
+    pub fn self_destruct_policy(&mut self, policy: SelfDestructPolicy) -> &mut Self {
+        self.self_destruct_policy = policy;
+        self
+    }
+
+    /// Set the limits enforced when validating actor events. Events exceeding them make the
+    /// `event::emit_event` syscall fail, which actors can observe.
+    pub fn event_limits(&mut self, limits: ActorEventLimits) -> &mut Self {
+        self.event_limits = limits;
+        self
+    }
+
+    /// Set the maximum number of consensus fault verifications a single message may perform.
+    /// Verifications past the limit fail with
+    /// [`LimitExceeded`](fvm_shared::error::ErrorNumber::LimitExceeded), bounding the work (and the
+    /// extern calls) a single message can trigger.
+    pub fn max_consensus_fault_verifications(&mut self, limit: u32) -> &mut Self {
+        self.max_consensus_fault_verifications = limit;
+        self
+    }
+
+    /// Set the maximum call depth. Sends that would exceed it fail, so every node must use the same
+    /// limit. The engine's instance pool is sized according to this limit, so engines must be
+    /// created from the updated config.
+    pub fn max_call_depth(&mut self, depth: u32) -> &mut Self {
+        self.max_call_depth = depth;
+        self
+    }
+
+    /// Set the maximum number of elements on the wasm stack. Actors exceeding it abort with a stack
+    /// overflow, so lowering it can make previously valid messages fail.
+    pub fn max_wasm_stack(&mut self, elements: u32) -> &mut Self {
+        self.max_wasm_stack = elements;
+        self
+    }
+
     /// Override actors with the specific manifest. This is primarily useful for testing, or
     /// networks prior to NV16 (where the actor's "manifest" isn't specified on-chain).
     pub fn override_actors(&mut self, manifest: Cid) -> &mut Self {
@@ -173,28 +460,66 @@ impl NetworkConfig {
         self
     }
 
+    /// Override some of the gas prices of the current price list. The overrides are kept (and
+    /// merged with any previous overrides) in [`NetworkConfig::price_overrides`], and re-applied
+    /// when switching network versions. This is a consensus-critical option, intended for devnets
+    /// and benchmarks.
+    ///
+    /// Fails if any of the overrides doesn't name a known price.
+    pub fn override_prices(&mut self, overrides: &PriceOverrides) -> anyhow::Result<&mut Self> {
+        self.price_list = Cow::Owned(self.price_list.with_overrides(overrides)?);
+        self.price_overrides
+            .get_or_insert_with(Default::default)
+            .0
+            .extend(
+                overrides
+                    .0
+                    .iter()
+                    .map(|(name, &price)| (name.clone(), price)),
+            );
+        Ok(self)
+    }
+
     /// Set actor redirects for debug execution
     pub fn redirect_actors(&mut self, actor_redirect: Vec<(Cid, Cid)>) -> &mut Self {
         self.actor_redirect = actor_redirect;
         self
     }
 
-    /// Create a ['MachineContext'] for a given epoch, timestamp, and initial state.
+    /// Create a ['MachineContext'] for a given epoch, timestamp, and initial state. The context
+    /// runs at the network version in effect at the epoch according to the
+    /// [`NetworkConfig::fork_schedule`], if any (see [`NetworkConfig::set_network_version`]).
+    ///
+    /// Fails if the fork schedule is invalid (see [`NetworkConfig::fork_schedule`]).
     pub fn for_epoch(
         &self,
         epoch: ChainEpoch,
         timestamp: u64,
         initial_state: Cid,
-    ) -> MachineContext {
-        MachineContext {
-            network: self.clone(),
+    ) -> anyhow::Result<MachineContext> {
+        schedule::check_upgrades(&self.fork_schedule).context("invalid fork schedule")?;
+        let mut network = self.clone();
+        let nv = self.network_version_at(epoch);
+        if nv != network.network_version {
+            network
+                .set_network_version(nv)
+                .context("invalid fork schedule")?;
+        }
+        Ok(MachineContext {
+            network,
             base_fee: TokenAmount::zero(),
+            base_fee_schedule: None,
             epoch,
             timestamp,
             initial_state_root: initial_state,
             circ_supply: fvm_shared::TOTAL_FILECOIN.clone(),
             tracing: false,
-        }
+            trace: TraceConfig::default(),
+            execution_budget: None,
+            name_resolver: None,
+            buffer_limits: BufferLimits::default(),
+            actor_cache_limits: ActorCacheLimits::default(),
+        })
     }
 
     /// Set Chain ID of the network.
@@ -202,6 +527,75 @@ impl NetworkConfig {
         self.chain_id = id;
         self
     }
+
+    /// Set the network upgrades, as `(epoch, network_version)` pairs: each network version is in
+    /// effect from its epoch up to the next upgrade, and epochs before the first upgrade run at
+    /// [`NetworkConfig::network_version`]. This is consensus-critical.
+    ///
+    /// Fails if network versions don't increase with epochs, or if any network version has no
+    /// registered [`NetworkSchedule`].
+    pub fn fork_schedule(
+        &mut self,
+        upgrades: impl IntoIterator<Item = (ChainEpoch, NetworkVersion)>,
+    ) -> anyhow::Result<&mut Self> {
+        let upgrades = upgrades.into_iter().collect();
+        schedule::check_upgrades(&upgrades)?;
+        self.fork_schedule = upgrades;
+        Ok(self)
+    }
+
+    /// Returns the network version in effect at the given epoch according to the
+    /// [`NetworkConfig::fork_schedule`], defaulting to [`NetworkConfig::network_version`].
+    pub fn network_version_at(&self, epoch: ChainEpoch) -> NetworkVersion {
+        self.fork_schedule
+            .range(..=epoch)
+            .next_back()
+            .map(|(_, &nv)| nv)
+            .unwrap_or(self.network_version)
+    }
+
+    /// Switch to the given network version, resetting the price list, self-destruct policy, and
+    /// disabled syscalls to those registered in its [`NetworkSchedule`]. The
+    /// [`NetworkConfig::price_overrides`] are re-applied to the new price list. All other settings
+    /// (e.g., the chain ID) are preserved.
+    ///
+    /// Fails if no [`NetworkSchedule`] is registered for the network version, or if the price
+    /// overrides don't apply to its price list.
+    pub fn set_network_version(
+        &mut self,
+        network_version: NetworkVersion,
+    ) -> anyhow::Result<&mut Self> {
+        let schedule = NetworkSchedule::get(network_version)
+            .ok_or_else(|| anyhow::anyhow!("unsupported network version: {}", network_version))?;
+        let price_list = match &self.price_overrides {
+            Some(overrides) => Cow::Owned(schedule.price_list.with_overrides(overrides)?),
+            None => Cow::Borrowed(schedule.price_list),
+        };
+        self.network_version = network_version;
+        self.price_list = price_list;
+        self.self_destruct_policy = schedule.self_destruct_policy;
+        self.disabled_syscalls = schedule.disabled_syscalls;
+        Ok(self)
+    }
+
+    /// Set the genesis timestamp of the network.
+    pub fn genesis_timestamp(&mut self, timestamp: u64) -> &mut Self {
+        self.genesis_timestamp = timestamp;
+        self
+    }
+
+    /// Set the block gas limit of the network.
+    pub fn block_gas_limit(&mut self, limit: u64) -> &mut Self {
+        self.block_gas_limit = limit;
+        self
+    }
+
+    /// Set the policy mapping execution failures to message exit codes. Exit codes are part of the
+    /// message receipts, so the policy must match the rest of the network.
+    pub fn exit_code_policy(&mut self, policy: impl ExitCodePolicy + 'static) -> &mut Self {
+        self.exit_code_policy = Arc::new(policy);
+        self
+    }
 }
 
 /// Per-epoch machine context.
@@ -227,6 +621,14 @@ pub struct MachineContext {
     /// Default: 0.
     pub base_fee: TokenAmount,
 
+    /// Resolves the base fee by epoch. When set, this takes precedence over
+    /// [`MachineContext::base_fee`]: the machine replaces the latter with the base fee resolved for
+    /// [`MachineContext::epoch`] when constructed. This lets a single context be reused (with
+    /// different epochs) across multi-epoch executions.
+    ///
+    /// Default: None.
+    pub base_fee_schedule: Option<BaseFeeSchedule>,
+
     /// The initial state root on which this block is based.
     pub initial_state_root: Cid,
 
@@ -237,9 +639,66 @@ pub struct MachineContext {
     /// DEFAULT: Total FIL supply (likely not what you want).
     pub circ_supply: TokenAmount,
 
-    /// Whether or not to produce execution traces in the returned result.
+    /// Whether or not to produce execution traces in the returned result. When set, all execution
+    /// events are traced without limits, regardless of [`MachineContext::trace`].
     /// Not consensus-critical, but has a performance impact.
     pub tracing: bool,
+
+    /// Which execution events to record in the returned execution trace, when
+    /// [`MachineContext::tracing`] isn't set.
+    /// Not consensus-critical, but has a performance impact.
+    pub trace: TraceConfig,
+
+    /// The maximum wall-clock time a single message may execute for before being aborted with
+    /// [`ExecutionBudgetExceeded`](crate::engine::ExecutionBudgetExceeded).
+    /// Not consensus-critical: intended for block builders needing a hard stop on pathological
+    /// messages.
+    ///
+    /// DEFAULT: None (unlimited)
+    pub execution_budget: Option<Duration>,
+
+    /// Resolves actor and method names in backtraces.
+    /// Not consensus-critical.
+    ///
+    /// DEFAULT: None (builtin actor names from the manifest only)
+    pub name_resolver: Option<Arc<dyn NameResolver>>,
+
+    /// Limits on the blocks buffered by the machine before being spilled to the blockstore (see
+    /// [`BufferLimits`]). Spilling bounds memory usage during large migrations and tipsets at the
+    /// cost of writing unreachable blocks.
+    /// Not consensus-critical.
+    ///
+    /// DEFAULT: unlimited (blocks are buffered until the machine is flushed)
+    pub buffer_limits: BufferLimits,
+
+    /// Limits on the actors cached by the machine's state tree (see [`ActorCacheLimits`]). This
+    /// bounds memory usage when applying long runs of messages without recreating the machine.
+    /// Not consensus-critical.
+    ///
+    /// DEFAULT: unlimited (actors are cached for the lifetime of the machine)
+    pub actor_cache_limits: ActorCacheLimits,
+}
+
+/// Resolves the base fee in effect at a given epoch. See [`MachineContext::base_fee_schedule`].
+#[derive(Clone)]
+pub struct BaseFeeSchedule(Arc<dyn Fn(ChainEpoch) -> TokenAmount + Send + Sync>);
+
+impl BaseFeeSchedule {
+    /// Create a schedule from a function mapping epochs to base fees.
+    pub fn new(schedule: impl Fn(ChainEpoch) -> TokenAmount + Send + Sync + 'static) -> Self {
+        BaseFeeSchedule(Arc::new(schedule))
+    }
+
+    /// Returns the base fee in effect at the given epoch.
+    pub fn base_fee_at(&self, epoch: ChainEpoch) -> TokenAmount {
+        (self.0)(epoch)
+    }
+}
+
+impl fmt::Debug for BaseFeeSchedule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("BaseFeeSchedule(..)")
+    }
 }
 
 impl MachineContext {
@@ -249,6 +708,24 @@ impl MachineContext {
         self
     }
 
+    /// Sets [`MachineContext::base_fee_schedule`].
+    pub fn set_base_fee_schedule(
+        &mut self,
+        schedule: impl Fn(ChainEpoch) -> TokenAmount + Send + Sync + 'static,
+    ) -> &mut Self {
+        self.base_fee_schedule = Some(BaseFeeSchedule::new(schedule));
+        self
+    }
+
+    /// Returns the base fee in effect at [`MachineContext::epoch`]: resolved through
+    /// [`MachineContext::base_fee_schedule`] if set, [`MachineContext::base_fee`] otherwise.
+    pub fn resolve_base_fee(&self) -> TokenAmount {
+        match &self.base_fee_schedule {
+            Some(schedule) => schedule.base_fee_at(self.epoch),
+            None => self.base_fee.clone(),
+        }
+    }
+
     /// Set [`MachineContext::circ_supply`].
     pub fn set_circulating_supply(&mut self, amt: TokenAmount) -> &mut Self {
         self.circ_supply = amt;
@@ -260,4 +737,44 @@ impl MachineContext {
         self.tracing = true;
         self
     }
+
+    /// Set [`MachineContext::trace`].
+    pub fn set_trace_config(&mut self, config: TraceConfig) -> &mut Self {
+        self.trace = config;
+        self
+    }
+
+    /// Returns the effective trace configuration: everything if [`MachineContext::tracing`] is set,
+    /// [`MachineContext::trace`] otherwise.
+    pub fn trace_config(&self) -> TraceConfig {
+        if self.tracing {
+            TraceConfig::all()
+        } else {
+            self.trace
+        }
+    }
+
+    /// Set [`MachineContext::execution_budget`].
+    pub fn set_execution_budget(&mut self, budget: Duration) -> &mut Self {
+        self.execution_budget = Some(budget);
+        self
+    }
+
+    /// Set [`MachineContext::buffer_limits`].
+    pub fn set_buffer_limits(&mut self, limits: BufferLimits) -> &mut Self {
+        self.buffer_limits = limits;
+        self
+    }
+
+    /// Set [`MachineContext::actor_cache_limits`].
+    pub fn set_actor_cache_limits(&mut self, limits: ActorCacheLimits) -> &mut Self {
+        self.actor_cache_limits = limits;
+        self
+    }
+
+    /// Set [`MachineContext::name_resolver`].
+    pub fn set_name_resolver(&mut self, resolver: impl NameResolver + 'static) -> &mut Self {
+        self.name_resolver = Some(Arc::new(resolver));
+        self
+    }
 }
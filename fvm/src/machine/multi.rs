@@ -0,0 +1,175 @@
+// Copyright 2021-2023 Protocol Labs
+// SPDX-License-Identifier: Apache-2.0, MIT
+use std::collections::BTreeMap;
+
+use anyhow::anyhow;
+use cid::Cid;
+use fvm_ipld_blockstore::Blockstore;
+use fvm_shared::clock::ChainEpoch;
+use fvm_shared::version::NetworkVersion;
+
+use super::schedule::check_upgrades;
+use super::{DefaultMachine, MachineContext, NetworkConfig};
+use crate::call_manager::DefaultCallManager;
+use crate::engine::MultiEngine;
+use crate::executor::DefaultExecutor;
+use crate::externs::Externs;
+use crate::DefaultKernel;
+
+/// The executor created by [`MultiMachine::new_executor`].
+pub type MultiMachineExecutor<B, E> =
+    DefaultExecutor<DefaultKernel<DefaultCallManager<DefaultMachine<B, E>>>>;
+
+/// Creates machines for a chain spanning multiple network versions, e.g., to apply historical
+/// messages across network upgrades from a single process.
+///
+/// Given the epochs at which each network version activates, it picks the [`NetworkConfig`] (and
+/// therefore the price list, available syscalls, etc., registered in the version's
+/// [`NetworkSchedule`](super::NetworkSchedule)) in effect at each epoch. Every network version
+/// must have a registered schedule; versions still under development can be registered at runtime
+/// (see [`NetworkSchedule::builtin`](super::NetworkSchedule::builtin)).
+///
+/// The upgrades are also set as the [`NetworkConfig::fork_schedule`] of every network config, so
+/// executors created from a [`MultiMachine`] can be advanced across upgrades with
+/// [`DefaultExecutor::advance_epoch`].
+#[derive(Clone, Debug)]
+pub struct MultiMachine {
+    /// The network version activated at each upgrade epoch.
+    upgrades: BTreeMap<ChainEpoch, NetworkVersion>,
+    /// The network config of each network version.
+    configs: BTreeMap<NetworkVersion, NetworkConfig>,
+}
+
+impl MultiMachine {
+    /// Create a new [`MultiMachine`] from `(epoch, network_version)` upgrades: each network version
+    /// is in effect from its epoch up to the next upgrade. Epochs before the first upgrade aren't
+    /// supported.
+    ///
+    /// Fails if network versions don't increase with epochs, or if any network version has no
+    /// registered [`NetworkSchedule`](super::NetworkSchedule).
+    pub fn new(
+        upgrades: impl IntoIterator<Item = (ChainEpoch, NetworkVersion)>,
+    ) -> anyhow::Result<Self> {
+        let upgrades: BTreeMap<_, _> = upgrades.into_iter().collect();
+        if upgrades.is_empty() {
+            return Err(anyhow!("no network upgrades specified"));
+        }
+        check_upgrades(&upgrades)?;
+
+        let mut configs = BTreeMap::new();
+        for &nv in upgrades.values() {
+            let mut config = NetworkConfig::new(nv);
+            config.fork_schedule = upgrades.clone();
+            configs.insert(nv, config);
+        }
+
+        Ok(MultiMachine { upgrades, configs })
+    }
+
+    /// Apply the given function to the network config of every network version, e.g., to set the
+    /// chain ID.
+    pub fn configure(&mut self, mut f: impl FnMut(&mut NetworkConfig)) -> &mut Self {
+        self.configs.values_mut().for_each(&mut f);
+        self
+    }
+
+    /// Returns a mutable reference to the network config of the given network version, e.g., to
+    /// override the builtin actors of a specific network version.
+    pub fn network_config_mut(&mut self, nv: NetworkVersion) -> Option<&mut NetworkConfig> {
+        self.configs.get_mut(&nv)
+    }
+
+    /// Returns the network version in effect at the given epoch, if any.
+    pub fn network_version(&self, epoch: ChainEpoch) -> Option<NetworkVersion> {
+        self.upgrades.range(..=epoch).next_back().map(|(_, &nv)| nv)
+    }
+
+    /// Returns the network config in effect at the given epoch.
+    pub fn network_config(&self, epoch: ChainEpoch) -> anyhow::Result<&NetworkConfig> {
+        let nv = self
+            .network_version(epoch)
+            .ok_or_else(|| anyhow!("epoch {} precedes the first network upgrade", epoch))?;
+        Ok(&self.configs[&nv])
+    }
+
+    /// Create the machine context for the given epoch. See [`NetworkConfig::for_epoch`].
+    pub fn for_epoch(
+        &self,
+        epoch: ChainEpoch,
+        timestamp: u64,
+        initial_state: Cid,
+    ) -> anyhow::Result<MachineContext> {
+        self.network_config(epoch)?
+            .for_epoch(epoch, timestamp, initial_state)
+    }
+
+    /// Create a machine for the given context, which must have been created by
+    /// [`MultiMachine::for_epoch`] (possibly modified, e.g., to set the base fee).
+    pub fn new_machine<B, E>(
+        &self,
+        context: &MachineContext,
+        blockstore: B,
+        externs: E,
+    ) -> anyhow::Result<DefaultMachine<B, E>>
+    where
+        B: Blockstore + 'static,
+        E: Externs + 'static,
+    {
+        let expected = self.network_version(context.epoch);
+        if expected != Some(context.network_version) {
+            return Err(anyhow!(
+                "machine context for epoch {} has network version {}, expected {:?}",
+                context.epoch,
+                context.network_version,
+                expected
+            ));
+        }
+        DefaultMachine::new(context, blockstore, externs)
+    }
+
+    /// Create an executor for the given context (see [`MultiMachine::new_machine`]), with an engine
+    /// from the given [`MultiEngine`] matching the context.
+    pub fn new_executor<B, E>(
+        &self,
+        engines: &MultiEngine,
+        context: &MachineContext,
+        blockstore: B,
+        externs: E,
+    ) -> anyhow::Result<MultiMachineExecutor<B, E>>
+    where
+        B: Blockstore + Clone + 'static,
+        E: Externs + Clone + 'static,
+    {
+        let machine = self.new_machine(context, blockstore, externs)?;
+        DefaultExecutor::new(engines.get_for_context(context)?, machine)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn selects_network_version() {
+        let mm =
+            MultiMachine::new([(100, NetworkVersion::V18), (200, NetworkVersion::V19)]).unwrap();
+        assert_eq!(mm.network_version(99), None);
+        assert_eq!(mm.network_version(100), Some(NetworkVersion::V18));
+        assert_eq!(mm.network_version(199), Some(NetworkVersion::V18));
+        assert_eq!(mm.network_version(1000), Some(NetworkVersion::V19));
+        assert_eq!(
+            mm.network_config(150).unwrap().network_version,
+            NetworkVersion::V18
+        );
+        assert!(mm.network_config(0).is_err());
+        assert_eq!(
+            mm.network_config(150).unwrap().network_version_at(250),
+            NetworkVersion::V19
+        );
+
+        assert!(
+            MultiMachine::new([(100, NetworkVersion::V19), (200, NetworkVersion::V18)]).is_err()
+        );
+        assert!(MultiMachine::new([(100, NetworkVersion::new(1_000_000))]).is_err());
+    }
+}
@@ -0,0 +1,77 @@
+// Copyright 2021-2023 Protocol Labs
+// SPDX-License-Identifier: Apache-2.0, MIT
+use std::fmt::Debug;
+
+use cid::Cid;
+use fvm_shared::{MethodNum, METHOD_CONSTRUCTOR, METHOD_SEND};
+
+use super::Manifest;
+
+/// Resolves human-readable actor and method names, used to annotate
+/// [`Backtrace`](crate::call_manager::Backtrace) frames. Set it with
+/// [`MachineContext::set_name_resolver`](super::MachineContext::set_name_resolver).
+///
+/// Names not resolved by the resolver fall back on the builtin actor names from the machine's
+/// actor manifest, and on the standard `Send` and `Constructor` method names.
+pub trait NameResolver: Debug + Send + Sync {
+    /// Returns the name of actors with the given code, if known.
+    fn actor_name(&self, code: &Cid) -> Option<String> {
+        let _ = code;
+        None
+    }
+
+    /// Returns the name of the given method of actors with the given code, if known.
+    fn method_name(&self, code: &Cid, method: MethodNum) -> Option<String> {
+        let _ = (code, method);
+        None
+    }
+}
+
+/// Resolves the actor and method names of a backtrace frame.
+pub(crate) fn resolve_names(
+    resolver: Option<&dyn NameResolver>,
+    manifest: &Manifest,
+    code: &Cid,
+    method: MethodNum,
+) -> (Option<String>, Option<String>) {
+    let actor_name = resolver
+        .and_then(|r| r.actor_name(code))
+        .or_else(|| manifest.name_by_code(code).map(String::from));
+    let method_name = resolver
+        .and_then(|r| r.method_name(code, method))
+        .or_else(|| match method {
+            METHOD_SEND => Some("Send".into()),
+            METHOD_CONSTRUCTOR => Some("Constructor".into()),
+            _ => None,
+        });
+    (actor_name, method_name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug)]
+    struct Resolver;
+
+    impl NameResolver for Resolver {
+        fn method_name(&self, _code: &Cid, method: MethodNum) -> Option<String> {
+            (method == 2).then(|| "Increment".into())
+        }
+    }
+
+    #[test]
+    fn fall_back_on_manifest() {
+        let manifest = Manifest::dummy();
+        let code = *manifest.get_account_code();
+        assert_eq!(
+            resolve_names(Some(&Resolver), &manifest, &code, 2),
+            (Some("account".into()), Some("Increment".into()))
+        );
+        assert_eq!(
+            resolve_names(None, &manifest, &code, METHOD_CONSTRUCTOR),
+            (Some("account".into()), Some("Constructor".into()))
+        );
+        assert_eq!(resolve_names(None, &manifest, &code, 2).1, None);
+    }
+}
@@ -0,0 +1,200 @@
+// Copyright 2021-2023 Protocol Labs
+// SPDX-License-Identifier: Apache-2.0, MIT
+use std::collections::BTreeMap;
+use std::sync::RwLock;
+
+use anyhow::anyhow;
+use fvm_shared::clock::ChainEpoch;
+use fvm_shared::version::NetworkVersion;
+use lazy_static::lazy_static;
+
+use super::SelfDestructPolicy;
+use crate::gas::{builtin_price_list, PriceList};
+
+/// The syscalls, as `(module, name)` pairs, introduced in network version 21. They're disabled in
+/// the builtin schedules of earlier network versions.
+pub const NV21_SYSCALLS: &[(&str, &str)] = &[
+    ("vm", "call_depth"),
+    ("vm", "message_context_ext"),
+    ("network", "context_ext"),
+    ("ipld", "block_stat_v2"),
+    ("ipld", "block_links"),
+];
+
+/// The network-version dependent parameters of the FVM: the gas schedule and the set of available
+/// syscalls.
+///
+/// Schedules for all network versions supported by the FVM are registered by default. Embedders
+/// (e.g., devnets) may register schedules for additional network versions, or replace existing
+/// ones, with [`NetworkSchedule::register`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NetworkSchedule {
+    /// The price list.
+    pub price_list: &'static PriceList,
+    /// What happens to an actor's remaining balance when it self-destructs.
+    pub self_destruct_policy: SelfDestructPolicy,
+    /// Syscalls, as `(module, name)` pairs, that are unavailable on this network version. Calling
+    /// an unavailable syscall fails with
+    /// [`IllegalOperation`](fvm_shared::error::ErrorNumber::IllegalOperation).
+    pub disabled_syscalls: Vec<(&'static str, &'static str)>,
+    /// The builtin-actors manifest version accepted by
+    /// [`Manifest::load_bundle`](super::Manifest::load_bundle) on this network version.
+    pub manifest_version: u32,
+}
+
+impl NetworkSchedule {
+    /// Create a schedule with the given price list, the default self-destruct policy, all syscalls
+    /// available, and version 1 actor manifests.
+    pub fn new(price_list: &'static PriceList) -> Self {
+        NetworkSchedule {
+            price_list,
+            self_destruct_policy: SelfDestructPolicy::TransferToBeneficiary,
+            disabled_syscalls: Vec::new(),
+            manifest_version: 1,
+        }
+    }
+
+    /// Returns the schedule built into the FVM for the given network version, if any, whether or
+    /// not it's registered. This includes network versions still under development (only
+    /// registered by default with the `nv21-dev` feature), which can be enabled at runtime with:
+    ///
+    /// ```
+    /// # use fvm::machine::NetworkSchedule;
+    /// # use fvm_shared::version::NetworkVersion;
+    /// let schedule = NetworkSchedule::builtin(NetworkVersion::V21).unwrap();
+    /// NetworkSchedule::register(NetworkVersion::V21, schedule);
+    /// ```
+    pub fn builtin(network_version: NetworkVersion) -> Option<NetworkSchedule> {
+        let mut schedule = NetworkSchedule::new(builtin_price_list(network_version)?);
+        if network_version < NetworkVersion::V21 {
+            schedule.disabled_syscalls = NV21_SYSCALLS.to_vec();
+        }
+        Some(schedule)
+    }
+
+    /// Returns the schedule registered for the given network version, if any.
+    pub fn get(network_version: NetworkVersion) -> Option<NetworkSchedule> {
+        SCHEDULES
+            .read()
+            .expect("network schedule registry poisoned")
+            .get(&network_version)
+            .cloned()
+    }
+
+    /// Register the schedule for the given network version, replacing any existing schedule.
+    ///
+    /// This affects all machines subsequently created for this network version, and is therefore
+    /// consensus-critical.
+    pub fn register(network_version: NetworkVersion, schedule: NetworkSchedule) {
+        SCHEDULES
+            .write()
+            .expect("network schedule registry poisoned")
+            .insert(network_version, schedule);
+    }
+
+    /// Returns the network versions with a registered schedule, in ascending order.
+    pub fn supported_versions() -> Vec<NetworkVersion> {
+        SCHEDULES
+            .read()
+            .expect("network schedule registry poisoned")
+            .keys()
+            .copied()
+            .collect()
+    }
+}
+
+/// Checks that network versions increase with the epochs of the given network upgrades, and that
+/// every network version has a registered [`NetworkSchedule`].
+pub(super) fn check_upgrades(
+    upgrades: &BTreeMap<ChainEpoch, NetworkVersion>,
+) -> anyhow::Result<()> {
+    for ((_, prev), (epoch, nv)) in upgrades.iter().zip(upgrades.iter().skip(1)) {
+        if nv <= prev {
+            return Err(anyhow!(
+                "network version {} at epoch {} doesn't follow network version {}",
+                nv,
+                epoch,
+                prev
+            ));
+        }
+    }
+    for &nv in upgrades.values() {
+        if NetworkSchedule::get(nv).is_none() {
+            return Err(anyhow!("unsupported network version: {}", nv));
+        }
+    }
+    Ok(())
+}
+
+lazy_static! {
+    static ref SCHEDULES: RwLock<BTreeMap<NetworkVersion, NetworkSchedule>> = {
+        #[cfg(not(feature = "nv21-dev"))]
+        const DEFAULT_VERSIONS: &[NetworkVersion] = &[
+            NetworkVersion::V18,
+            NetworkVersion::V19,
+            NetworkVersion::V20,
+        ];
+
+        #[cfg(feature = "nv21-dev")]
+        const DEFAULT_VERSIONS: &[NetworkVersion] = &[
+            NetworkVersion::V18,
+            NetworkVersion::V19,
+            NetworkVersion::V20,
+            NetworkVersion::V21,
+        ];
+
+        RwLock::new(
+            DEFAULT_VERSIONS
+                .iter()
+                .map(|&nv| {
+                    let schedule = NetworkSchedule::builtin(nv).expect("missing builtin schedule");
+                    (nv, schedule)
+                })
+                .collect(),
+        )
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::machine::NetworkConfig;
+    use crate::EMPTY_ARR_CID;
+
+    #[test]
+    fn register_custom_version() {
+        let nv = NetworkVersion::new(1000);
+        assert!(NetworkSchedule::get(nv).is_none());
+
+        let mut schedule = NetworkSchedule::get(NetworkVersion::V18).unwrap();
+        schedule.self_destruct_policy = SelfDestructPolicy::Disallow;
+        schedule.disabled_syscalls = vec![("self", "self_destruct")];
+        NetworkSchedule::register(nv, schedule.clone());
+
+        assert_eq!(NetworkSchedule::get(nv), Some(schedule));
+        assert!(NetworkSchedule::supported_versions().contains(&nv));
+    }
+
+    #[test]
+    fn invalid_fork_schedule() {
+        let mut nc = NetworkConfig::new(NetworkVersion::V18);
+
+        // Network versions must increase with epochs.
+        nc.fork_schedule = [(10, NetworkVersion::V19), (20, NetworkVersion::V18)]
+            .into_iter()
+            .collect();
+        assert!(nc.for_epoch(5, 0, *EMPTY_ARR_CID).is_err());
+
+        // And must have a registered schedule.
+        nc.fork_schedule = [(10, NetworkVersion::new(1001))].into_iter().collect();
+        assert!(nc.for_epoch(10, 0, *EMPTY_ARR_CID).is_err());
+    }
+
+    #[test]
+    fn new_syscalls_gated() {
+        let schedule = NetworkSchedule::builtin(NetworkVersion::V20).unwrap();
+        assert_eq!(schedule.disabled_syscalls, NV21_SYSCALLS);
+        let schedule = NetworkSchedule::builtin(NetworkVersion::V21).unwrap();
+        assert!(schedule.disabled_syscalls.is_empty());
+    }
+}
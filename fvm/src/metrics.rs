@@ -0,0 +1,89 @@
+// Copyright 2021-2023 Protocol Labs
+// SPDX-License-Identifier: Apache-2.0, MIT
+//! Prometheus metrics (enabled with the `metrics` feature).
+//!
+//! The FVM records:
+//!
+//! - `fvm_syscall_invocations_total{module, name}`: the number of syscall invocations.
+//! - `fvm_gas_charged_milligas_total{category}`: the gas charged, in milligas, by category:
+//!   - `compute`: compute gas, including wasm execution.
+//!   - `other`: storage and other non-compute gas.
+//!   - `copy`: gas charged for copying data between actors and the host.
+//!   - `memory`: gas charged for allocating wasm memory.
+//! - `fvm_message_execution_seconds{kind}`: the time spent executing messages, by
+//!   [`ApplyKind`](crate::executor::ApplyKind).
+//!
+//! Metrics are collected globally, but aren't exported anywhere by default. Call [`register`] to
+//! add them to a registry.
+use lazy_static::lazy_static;
+use prometheus::{
+    exponential_buckets, HistogramOpts, HistogramTimer, HistogramVec, IntCounter, IntCounterVec,
+    Opts, Registry,
+};
+
+use crate::executor::ApplyKind;
+use crate::gas::Gas;
+
+lazy_static! {
+    static ref SYSCALL_INVOCATIONS: IntCounterVec = IntCounterVec::new(
+        Opts::new(
+            "fvm_syscall_invocations_total",
+            "Number of syscall invocations"
+        ),
+        &["module", "name"],
+    )
+    .expect("invalid syscall metric");
+    static ref GAS_CHARGED: IntCounterVec = IntCounterVec::new(
+        Opts::new(
+            "fvm_gas_charged_milligas_total",
+            "Gas charged, in milligas, by category"
+        ),
+        &["category"],
+    )
+    .expect("invalid gas metric");
+    static ref MESSAGE_EXECUTION_SECONDS: HistogramVec = HistogramVec::new(
+        HistogramOpts::new(
+            "fvm_message_execution_seconds",
+            "Time spent executing messages"
+        )
+        .buckets(exponential_buckets(0.0001, 4.0, 10).expect("invalid buckets")),
+        &["kind"],
+    )
+    .expect("invalid execution metric");
+    pub(crate) static ref GAS_COMPUTE: IntCounter = GAS_CHARGED.with_label_values(&["compute"]);
+    pub(crate) static ref GAS_OTHER: IntCounter = GAS_CHARGED.with_label_values(&["other"]);
+    pub(crate) static ref GAS_COPY: IntCounter = GAS_CHARGED.with_label_values(&["copy"]);
+    pub(crate) static ref GAS_MEMORY: IntCounter = GAS_CHARGED.with_label_values(&["memory"]);
+}
+
+/// Register all FVM metrics with the given registry.
+pub fn register(registry: &Registry) -> prometheus::Result<()> {
+    registry.register(Box::new(SYSCALL_INVOCATIONS.clone()))?;
+    registry.register(Box::new(GAS_CHARGED.clone()))?;
+    registry.register(Box::new(MESSAGE_EXECUTION_SECONDS.clone()))?;
+    Ok(())
+}
+
+/// Returns the invocation counter for the given syscall. Resolve this once when binding the
+/// syscall, not on every invocation.
+pub(crate) fn syscall_invocations(module: &str, name: &str) -> IntCounter {
+    SYSCALL_INVOCATIONS.with_label_values(&[module, name])
+}
+
+/// Record gas charged to the given counter.
+pub(crate) fn record_gas(counter: &IntCounter, gas: Gas) {
+    counter.inc_by(gas.as_milligas())
+}
+
+/// Start timing the execution of a message. The time is recorded when the timer is dropped.
+pub(crate) fn message_timer(kind: ApplyKind) -> HistogramTimer {
+    let kind = match kind {
+        ApplyKind::Explicit => "explicit",
+        ApplyKind::Implicit => "implicit",
+        ApplyKind::Estimate => "estimate",
+        ApplyKind::Query => "query",
+    };
+    MESSAGE_EXECUTION_SECONDS
+        .with_label_values(&[kind])
+        .start_timer()
+}
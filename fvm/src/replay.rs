@@ -0,0 +1,497 @@
+// Copyright 2021-2023 Protocol Labs
+// SPDX-License-Identifier: Apache-2.0, MIT
+//! Record-and-replay of message execution, for reproducing issues (e.g., consensus divergence
+//! reports) offline.
+//!
+//! 1. To record, create a [`Recorder`] and construct the machine with the blockstore and externs it
+//!    wraps. After executing the message(s), write the recording to a CAR with
+//!    [`Recorder::write_car`]. The CAR contains every block read during execution, with the
+//!    [`ReplayLog`] of all nondeterministic inputs as its root.
+//! 2. To replay, load the CAR into a blockstore with [`load_car`], then construct a machine from
+//!    [`ReplayLog::machine_context`] and [`ReplayExterns`], and re-apply the same message(s).
+use std::collections::BTreeMap;
+use std::io::{Read, Write};
+use std::sync::{Arc, Mutex};
+
+use anyhow::{anyhow, Context};
+use cid::multihash::{Code, MultihashDigest};
+use cid::Cid;
+use futures::executor::block_on;
+use futures::io::AllowStdIo;
+use fvm_ipld_blockstore::Blockstore;
+use fvm_ipld_car::CarHeader;
+use fvm_ipld_encoding::tuple::*;
+use fvm_ipld_encoding::{strict_bytes, to_vec, CborStore, DAG_CBOR};
+use fvm_shared::address::Address;
+use fvm_shared::clock::ChainEpoch;
+use fvm_shared::consensus::{ConsensusFault, ConsensusFaultType};
+use fvm_shared::econ::TokenAmount;
+use fvm_shared::version::NetworkVersion;
+use num_traits::FromPrimitive;
+
+use crate::externs::{Chain, Consensus, Externs, Rand};
+use crate::machine::{MachineContext, NetworkConfig};
+
+/// The nondeterministic inputs of a message execution: the machine's context, and the results of
+/// all extern calls.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize_tuple, Deserialize_tuple)]
+pub struct ReplayLog {
+    pub network_version: NetworkVersion,
+    pub chain_id: u64,
+    pub builtin_actors: Option<Cid>,
+    pub epoch: ChainEpoch,
+    pub timestamp: u64,
+    pub state_root: Cid,
+    pub base_fee: TokenAmount,
+    pub circ_supply: TokenAmount,
+    pub chain_randomness: Vec<RecordedRandomness>,
+    pub beacon_randomness: Vec<RecordedRandomness>,
+    pub beacon_entries: Vec<RecordedBeaconEntry>,
+    pub tipset_cids: Vec<(ChainEpoch, Cid)>,
+    pub consensus_faults: Vec<RecordedConsensusFault>,
+}
+
+/// Randomness returned by the externs for an epoch.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize_tuple, Deserialize_tuple)]
+pub struct RecordedRandomness {
+    pub epoch: ChainEpoch,
+    #[serde(with = "strict_bytes")]
+    pub randomness: [u8; 32],
+}
+
+/// The result of verifying a beacon entry.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize_tuple, Deserialize_tuple)]
+pub struct RecordedBeaconEntry {
+    pub round: u64,
+    #[serde(with = "strict_bytes")]
+    pub prev_signature: Vec<u8>,
+    #[serde(with = "strict_bytes")]
+    pub signature: Vec<u8>,
+    pub valid: bool,
+}
+
+/// The result of verifying a consensus fault.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize_tuple, Deserialize_tuple)]
+pub struct RecordedConsensusFault {
+    #[serde(with = "strict_bytes")]
+    pub h1: Vec<u8>,
+    #[serde(with = "strict_bytes")]
+    pub h2: Vec<u8>,
+    #[serde(with = "strict_bytes")]
+    pub extra: Vec<u8>,
+    /// The fault's target, epoch, and type (if any).
+    pub fault: Option<(Address, ChainEpoch, u8)>,
+    pub gas: i64,
+}
+
+impl ReplayLog {
+    /// Create an empty log for a machine with the given context.
+    pub fn new(context: &MachineContext) -> Self {
+        ReplayLog {
+            network_version: context.network_version,
+            chain_id: context.chain_id.into(),
+            builtin_actors: context.builtin_actors_override,
+            epoch: context.epoch,
+            timestamp: context.timestamp,
+            state_root: context.initial_state_root,
+            base_fee: context.resolve_base_fee(),
+            circ_supply: context.circ_supply.clone(),
+            chain_randomness: Vec::new(),
+            beacon_randomness: Vec::new(),
+            beacon_entries: Vec::new(),
+            tipset_cids: Vec::new(),
+            consensus_faults: Vec::new(),
+        }
+    }
+
+    /// Returns the context of the recorded machine. Network settings that aren't recorded (e.g.,
+    /// price overrides or actor debugging) must be reapplied by the caller.
+    pub fn machine_context(&self) -> anyhow::Result<MachineContext> {
+        let mut nc = NetworkConfig::new(self.network_version);
+        nc.chain_id(self.chain_id.into());
+        if let Some(manifest) = self.builtin_actors {
+            nc.override_actors(manifest);
+        }
+        let mut mc = nc.for_epoch(self.epoch, self.timestamp, self.state_root)?;
+        mc.set_base_fee(self.base_fee.clone())
+            .set_circulating_supply(self.circ_supply.clone());
+        Ok(mc)
+    }
+}
+
+struct Recording {
+    log: ReplayLog,
+    blocks: BTreeMap<Cid, Vec<u8>>,
+}
+
+/// Records the nondeterministic inputs and the blocks read by a machine, through the blockstore
+/// and externs it wraps. Cloning a recorder returns a handle to the same recording.
+#[derive(Clone)]
+pub struct Recorder(Arc<Mutex<Recording>>);
+
+impl Recorder {
+    /// Create a recorder for a machine with the given context.
+    pub fn new(context: &MachineContext) -> Self {
+        Recorder(Arc::new(Mutex::new(Recording {
+            log: ReplayLog::new(context),
+            blocks: BTreeMap::new(),
+        })))
+    }
+
+    /// Wrap the machine's blockstore, recording all blocks read.
+    pub fn blockstore<B: Blockstore>(&self, inner: B) -> RecordingBlockstore<B> {
+        RecordingBlockstore {
+            inner,
+            recorder: self.clone(),
+        }
+    }
+
+    /// Wrap the machine's externs, recording all results.
+    pub fn externs<E: Externs>(&self, inner: E) -> RecordingExterns<E> {
+        RecordingExterns {
+            inner,
+            recorder: self.clone(),
+        }
+    }
+
+    /// Returns the log recorded so far.
+    pub fn log(&self) -> ReplayLog {
+        self.with(|r| r.log.clone())
+    }
+
+    /// Write the recorded blocks to a CAR, with the log as its root. Returns the CID of the log.
+    pub fn write_car(&self, writer: impl Write + Send) -> anyhow::Result<Cid> {
+        let (log, blocks) = self.with(|r| (r.log.clone(), r.blocks.clone()));
+        let log = to_vec(&log)?;
+        let root = Cid::new_v1(DAG_CBOR, Code::Blake2b256.digest(&log));
+
+        let mut stream = futures::stream::iter(std::iter::once((root, log)).chain(blocks));
+        block_on(
+            CarHeader::from(vec![root])
+                .write_stream_async(&mut AllowStdIo::new(writer), &mut stream),
+        )?;
+        Ok(root)
+    }
+
+    fn with<T>(&self, f: impl FnOnce(&mut Recording) -> T) -> T {
+        f(&mut self.0.lock().expect("replay recorder poisoned"))
+    }
+}
+
+/// Load a CAR written by [`Recorder::write_car`] into the blockstore, returning the log.
+pub fn load_car<B: Blockstore>(bs: &B, reader: impl Read + Send) -> anyhow::Result<ReplayLog> {
+    let roots = block_on(fvm_ipld_car::load_car(bs, AllowStdIo::new(reader)))?;
+    let root = match roots[..] {
+        [root] => root,
+        _ => return Err(anyhow!("expected a single root, found {}", roots.len())),
+    };
+    bs.get_cbor(&root)?
+        .context("replay log missing from the CAR")
+}
+
+/// A blockstore recording all blocks read, created by [`Recorder::blockstore`].
+#[derive(Clone)]
+pub struct RecordingBlockstore<B> {
+    inner: B,
+    recorder: Recorder,
+}
+
+impl<B: Blockstore> Blockstore for RecordingBlockstore<B> {
+    fn get(&self, k: &Cid) -> anyhow::Result<Option<Vec<u8>>> {
+        let block = self.inner.get(k)?;
+        if let Some(block) = &block {
+            self.recorder.with(|r| r.blocks.insert(*k, block.clone()));
+        }
+        Ok(block)
+    }
+
+    fn put_keyed(&self, k: &Cid, block: &[u8]) -> anyhow::Result<()> {
+        self.inner.put_keyed(k, block)
+    }
+
+    fn has(&self, k: &Cid) -> anyhow::Result<bool> {
+        // Record the block so it's also present on replay.
+        Ok(self.get(k)?.is_some())
+    }
+}
+
+/// Externs recording all results, created by [`Recorder::externs`].
+#[derive(Clone)]
+pub struct RecordingExterns<E> {
+    inner: E,
+    recorder: Recorder,
+}
+
+impl<E: Externs> Externs for RecordingExterns<E> {}
+
+impl<E: Externs> Rand for RecordingExterns<E> {
+    fn get_chain_randomness(&self, round: ChainEpoch) -> anyhow::Result<[u8; 32]> {
+        let randomness = self.inner.get_chain_randomness(round)?;
+        self.recorder.with(|r| {
+            r.log.chain_randomness.push(RecordedRandomness {
+                epoch: round,
+                randomness,
+            })
+        });
+        Ok(randomness)
+    }
+
+    fn get_beacon_randomness(&self, round: ChainEpoch) -> anyhow::Result<[u8; 32]> {
+        let randomness = self.inner.get_beacon_randomness(round)?;
+        self.recorder.with(|r| {
+            r.log.beacon_randomness.push(RecordedRandomness {
+                epoch: round,
+                randomness,
+            })
+        });
+        Ok(randomness)
+    }
+
+    fn verify_beacon_entry(
+        &self,
+        round: u64,
+        prev_signature: &[u8],
+        signature: &[u8],
+    ) -> anyhow::Result<bool> {
+        let valid = self
+            .inner
+            .verify_beacon_entry(round, prev_signature, signature)?;
+        self.recorder.with(|r| {
+            r.log.beacon_entries.push(RecordedBeaconEntry {
+                round,
+                prev_signature: prev_signature.to_vec(),
+                signature: signature.to_vec(),
+                valid,
+            })
+        });
+        Ok(valid)
+    }
+}
+
+impl<E: Externs> Consensus for RecordingExterns<E> {
+    fn verify_consensus_fault(
+        &self,
+        h1: &[u8],
+        h2: &[u8],
+        extra: &[u8],
+    ) -> anyhow::Result<(Option<ConsensusFault>, i64)> {
+        let (fault, gas) = self.inner.verify_consensus_fault(h1, h2, extra)?;
+        self.recorder.with(|r| {
+            r.log.consensus_faults.push(RecordedConsensusFault {
+                h1: h1.to_vec(),
+                h2: h2.to_vec(),
+                extra: extra.to_vec(),
+                fault: fault
+                    .as_ref()
+                    .map(|f| (f.target, f.epoch, f.fault_type as u8)),
+                gas,
+            })
+        });
+        Ok((fault, gas))
+    }
+}
+
+impl<E: Externs> Chain for RecordingExterns<E> {
+    fn get_tipset_cid(&self, epoch: ChainEpoch) -> anyhow::Result<Cid> {
+        let cid = self.inner.get_tipset_cid(epoch)?;
+        self.recorder.with(|r| r.log.tipset_cids.push((epoch, cid)));
+        Ok(cid)
+    }
+}
+
+/// Externs replaying the results recorded in a [`ReplayLog`]. Fails on any call that wasn't
+/// recorded.
+#[derive(Clone)]
+pub struct ReplayExterns {
+    log: ReplayLog,
+}
+
+impl ReplayExterns {
+    pub fn new(log: ReplayLog) -> Self {
+        ReplayExterns { log }
+    }
+}
+
+impl Externs for ReplayExterns {}
+
+impl Rand for ReplayExterns {
+    fn get_chain_randomness(&self, round: ChainEpoch) -> anyhow::Result<[u8; 32]> {
+        self.log
+            .chain_randomness
+            .iter()
+            .find(|r| r.epoch == round)
+            .map(|r| r.randomness)
+            .ok_or_else(|| anyhow!("no recorded chain randomness for epoch {}", round))
+    }
+
+    fn get_beacon_randomness(&self, round: ChainEpoch) -> anyhow::Result<[u8; 32]> {
+        self.log
+            .beacon_randomness
+            .iter()
+            .find(|r| r.epoch == round)
+            .map(|r| r.randomness)
+            .ok_or_else(|| anyhow!("no recorded beacon randomness for epoch {}", round))
+    }
+
+    fn verify_beacon_entry(
+        &self,
+        round: u64,
+        prev_signature: &[u8],
+        signature: &[u8],
+    ) -> anyhow::Result<bool> {
+        self.log
+            .beacon_entries
+            .iter()
+            .find(|e| {
+                e.round == round && e.prev_signature == prev_signature && e.signature == signature
+            })
+            .map(|e| e.valid)
+            .ok_or_else(|| anyhow!("no recorded beacon entry verification for round {}", round))
+    }
+}
+
+impl Consensus for ReplayExterns {
+    fn verify_consensus_fault(
+        &self,
+        h1: &[u8],
+        h2: &[u8],
+        extra: &[u8],
+    ) -> anyhow::Result<(Option<ConsensusFault>, i64)> {
+        let recorded = self
+            .log
+            .consensus_faults
+            .iter()
+            .find(|f| f.h1 == h1 && f.h2 == h2 && f.extra == extra)
+            .ok_or_else(|| anyhow!("no recorded consensus fault verification"))?;
+        let fault = recorded
+            .fault
+            .map(|(target, epoch, fault_type)| {
+                Ok::<_, anyhow::Error>(ConsensusFault {
+                    target,
+                    epoch,
+                    fault_type: ConsensusFaultType::from_u8(fault_type)
+                        .ok_or_else(|| anyhow!("invalid consensus fault type {}", fault_type))?,
+                })
+            })
+            .transpose()?;
+        Ok((fault, recorded.gas))
+    }
+}
+
+impl Chain for ReplayExterns {
+    fn get_tipset_cid(&self, epoch: ChainEpoch) -> anyhow::Result<Cid> {
+        self.log
+            .tipset_cids
+            .iter()
+            .find(|(e, _)| *e == epoch)
+            .map(|(_, cid)| *cid)
+            .ok_or_else(|| anyhow!("no recorded tipset CID for epoch {}", epoch))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use fvm_ipld_blockstore::MemoryBlockstore;
+    use fvm_ipld_encoding::IPLD_RAW;
+
+    use super::*;
+
+    struct TestExterns;
+
+    impl Externs for TestExterns {}
+
+    impl Rand for TestExterns {
+        fn get_chain_randomness(&self, round: ChainEpoch) -> anyhow::Result<[u8; 32]> {
+            Ok([round as u8; 32])
+        }
+
+        fn get_beacon_randomness(&self, round: ChainEpoch) -> anyhow::Result<[u8; 32]> {
+            Ok([round as u8 + 1; 32])
+        }
+
+        fn verify_beacon_entry(
+            &self,
+            _round: u64,
+            _prev_signature: &[u8],
+            signature: &[u8],
+        ) -> anyhow::Result<bool> {
+            Ok(!signature.is_empty())
+        }
+    }
+
+    impl Consensus for TestExterns {
+        fn verify_consensus_fault(
+            &self,
+            _h1: &[u8],
+            _h2: &[u8],
+            _extra: &[u8],
+        ) -> anyhow::Result<(Option<ConsensusFault>, i64)> {
+            Ok((
+                Some(ConsensusFault {
+                    target: Address::new_id(1000),
+                    epoch: 10,
+                    fault_type: ConsensusFaultType::ParentGrinding,
+                }),
+                42,
+            ))
+        }
+    }
+
+    impl Chain for TestExterns {
+        fn get_tipset_cid(&self, epoch: ChainEpoch) -> anyhow::Result<Cid> {
+            Ok(Cid::new_v1(
+                IPLD_RAW,
+                Code::Blake2b256.digest(&epoch.to_be_bytes()),
+            ))
+        }
+    }
+
+    #[test]
+    fn record_and_replay() {
+        let source = MemoryBlockstore::default();
+        let block = source.put_cbor(&"block", Code::Blake2b256).unwrap();
+        let mc = NetworkConfig::new(NetworkVersion::V18)
+            .for_epoch(20, 1234, block)
+            .unwrap();
+        let recorder = Recorder::new(&mc);
+
+        // Record some blocks and extern calls.
+        let bs = recorder.blockstore(&source);
+        assert!(bs.get(&block).unwrap().is_some());
+
+        let externs = recorder.externs(TestExterns);
+        let chain_rand = externs.get_chain_randomness(5).unwrap();
+        let beacon_rand = externs.get_beacon_randomness(6).unwrap();
+        assert!(externs.verify_beacon_entry(7, b"prev", b"sig").unwrap());
+        let tipset_cid = externs.get_tipset_cid(8).unwrap();
+        let (fault, gas) = externs.verify_consensus_fault(b"h1", b"h2", b"").unwrap();
+
+        let mut car = Vec::new();
+        recorder.write_car(&mut car).unwrap();
+
+        // Replay them.
+        let replay_bs = MemoryBlockstore::default();
+        let log = load_car(&replay_bs, &car[..]).unwrap();
+        assert_eq!(log, recorder.log());
+        assert!(replay_bs.has(&block).unwrap());
+
+        let replay_mc = log.machine_context().unwrap();
+        assert_eq!(replay_mc.epoch, 20);
+        assert_eq!(replay_mc.timestamp, 1234);
+        assert_eq!(replay_mc.network_version, NetworkVersion::V18);
+
+        let externs = ReplayExterns::new(log);
+        assert_eq!(externs.get_chain_randomness(5).unwrap(), chain_rand);
+        assert_eq!(externs.get_beacon_randomness(6).unwrap(), beacon_rand);
+        assert!(externs.verify_beacon_entry(7, b"prev", b"sig").unwrap());
+        assert_eq!(externs.get_tipset_cid(8).unwrap(), tipset_cid);
+        let (replayed_fault, replayed_gas) =
+            externs.verify_consensus_fault(b"h1", b"h2", b"").unwrap();
+        assert_eq!(replayed_gas, gas);
+        assert_eq!(replayed_fault.map(|f| f.target), fault.map(|f| f.target));
+
+        // Calls that weren't recorded fail.
+        assert!(externs.get_chain_randomness(9).is_err());
+        assert!(externs.get_tipset_cid(9).is_err());
+    }
+}
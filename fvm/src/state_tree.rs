@@ -2,24 +2,32 @@
 // Copyright 2019-2022 ChainSafe Systems
 // SPDX-License-Identifier: Apache-2.0, MIT
 
-use std::cell::RefCell;
+use std::cell::{Cell, RefCell};
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap, HashSet, VecDeque};
+use std::hash::Hash;
+use std::sync::{Arc, Mutex, RwLock};
 
 use anyhow::{anyhow, Context as _};
+use cid::multihash::MultihashDigest;
 use cid::{multihash, Cid};
-use fvm_ipld_blockstore::Blockstore;
+use fvm_ipld_blockstore::{Blockstore, MemoryBlockstore};
 use fvm_ipld_encoding::tuple::*;
-use fvm_ipld_encoding::CborStore;
-use fvm_ipld_hamt::Hamt;
-use fvm_shared::address::{Address, Payload};
+use fvm_ipld_encoding::{CborStore, DAG_CBOR};
+use fvm_ipld_hamt::{BytesKey, Change, Hamt, Hamtv0, Problem as HamtProblem};
+use fvm_shared::address::{Address, Payload, Protocol};
+use fvm_shared::commcid::{FIL_COMMITMENT_SEALED, FIL_COMMITMENT_UNSEALED};
 use fvm_shared::econ::TokenAmount;
 use fvm_shared::state::{StateInfo0, StateRoot, StateTreeVersion};
-use fvm_shared::{ActorID, HAMT_BIT_WIDTH};
+use fvm_shared::{ActorID, HAMT_BIT_WIDTH, IDENTITY_HASH};
 use num_traits::Zero;
 #[cfg(feature = "arb")]
 use quickcheck::Arbitrary;
 
+use crate::blockstore::scan_for_links;
+use crate::eam_actor::EAM_ACTOR_ID;
 use crate::history_map::HistoryMap;
-use crate::init_actor::State as InitActorState;
+use crate::init_actor::{State as InitActorState, INIT_ACTOR_ID};
 use crate::kernel::{ClassifyResult, ExecutionError, Result};
 use crate::{syscall_error, EMPTY_ARR_CID};
 
@@ -33,11 +41,60 @@ pub struct StateTree<S> {
 
     /// An actor-state cache that internally keeps an undo history.
     actor_cache: RefCell<HistoryMap<ActorID, ActorCacheEntry>>,
+    /// Actors modified since the last flush. This may include actors whose modifications have
+    /// since been reverted, so flushing only writes the ones still marked as dirty in the cache.
+    dirty_actors: HashSet<ActorID>,
     /// An actor-address cache that internally keeps an undo history.
     resolve_cache: RefCell<HistoryMap<Address, ActorID>>,
+    /// A delegated-address lookup cache, filled by lookups and by creating or deleting actors.
+    /// `None` means there's no live actor with the delegated address. Internally keeps an undo
+    /// history, and is cleared outside of transactions once it exceeds
+    /// [`DELEGATED_CACHE_MAX_LEN`] entries.
+    delegated_cache: RefCell<HistoryMap<Address, Option<ActorID>>>,
     /// Snapshot layers. Each layer contains points in the actor/resolve cache histories to which
     /// said caches will be reverted on revert.
     layers: Vec<StateSnapLayer>,
+    /// Limits on the actor cache.
+    cache_limits: ActorCacheLimits,
+    /// The last time (see `access_clock`) each cached actor was accessed, tracked only when the
+    /// actor cache is limited.
+    last_access: RefCell<HashMap<ActorID, u64>>,
+    /// Actor accesses in the order in which they happened, oldest first. Entries superseded by a
+    /// later access to the same actor (see `last_access`) are skipped on eviction.
+    access_queue: RefCell<VecDeque<(u64, ActorID)>>,
+    /// A logical clock, incremented on every actor cache access.
+    access_clock: Cell<u64>,
+}
+
+/// The maximum number of delegated addresses cached by a [`StateTree`] outside of transactions.
+const DELEGATED_CACHE_MAX_LEN: usize = 1024;
+
+/// Limits on the actors cached by a [`StateTree`]. When either limit is exceeded outside of a
+/// transaction, the least recently used unmodified actors are evicted from the cache until it's
+/// back under 90% of the limits. Modified actors stay cached until flushed.
+///
+/// By default, actors are cached for the lifetime of the state tree.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ActorCacheLimits {
+    /// The maximum number of cached actors.
+    pub max_entries: Option<usize>,
+    /// The maximum (estimated) memory used by cached actors, in bytes.
+    pub max_bytes: Option<usize>,
+}
+
+impl ActorCacheLimits {
+    /// The estimated memory used by each cached actor.
+    const ENTRY_SIZE: usize =
+        std::mem::size_of::<(ActorID, ActorCacheEntry)>() + std::mem::size_of::<(ActorID, u64)>();
+
+    /// Returns the maximum number of cached actors allowed by these limits, if any.
+    fn max_len(&self) -> Option<usize> {
+        let by_bytes = self.max_bytes.map(|b| b / Self::ENTRY_SIZE);
+        match (self.max_entries, by_bytes) {
+            (Some(a), Some(b)) => Some(a.min(b)),
+            (a, b) => a.or(b),
+        }
+    }
 }
 
 /// An entry in the actor cache.
@@ -49,12 +106,27 @@ struct ActorCacheEntry {
     actor: Option<ActorState>,
 }
 
+/// A savepoint in a [`StateTree`], created by [`StateTree::savepoint`]. All changes made after
+/// the savepoint can be rolled back with [`StateTree::rollback`] or kept with
+/// [`StateTree::commit`].
+///
+/// Savepoints nest: committing or rolling back a savepoint also commits or rolls back (resp.)
+/// every savepoint and transaction started after it.
+#[must_use]
+#[derive(Debug, PartialEq, Eq)]
+pub struct Savepoint {
+    /// The number of snapshot layers, including this savepoint's layer.
+    depth: usize,
+}
+
 /// State snap shot layer.
 struct StateSnapLayer {
     /// The actor-cache height at which this snapshot was taken.
     actor_cache_height: usize,
     /// The resolve-cache height at which this snapshot was taken.
     resolve_cache_height: usize,
+    /// The delegated-cache height at which this snapshot was taken.
+    delegated_cache_height: usize,
 }
 
 impl<S> StateTree<S>
@@ -89,8 +161,14 @@ where
             version,
             info,
             actor_cache: Default::default(),
+            dirty_actors: Default::default(),
             resolve_cache: Default::default(),
+            delegated_cache: Default::default(),
             layers: Vec::new(),
+            cache_limits: Default::default(),
+            last_access: Default::default(),
+            access_queue: Default::default(),
+            access_clock: Default::default(),
         })
     }
 
@@ -138,8 +216,14 @@ where
                     version,
                     info,
                     actor_cache: Default::default(),
+                    dirty_actors: Default::default(),
                     resolve_cache: Default::default(),
+                    delegated_cache: Default::default(),
                     layers: Vec::new(),
+                    cache_limits: Default::default(),
+                    last_access: Default::default(),
+                    access_queue: Default::default(),
+                    access_clock: Default::default(),
                 })
             }
         }
@@ -150,6 +234,24 @@ where
         self.hamt.store()
     }
 
+    /// Limits the number of actors cached by the state tree. See [`ActorCacheLimits`].
+    pub fn set_cache_limits(&mut self, limits: ActorCacheLimits) {
+        self.cache_limits = limits;
+        // Start tracking the actors cached so far, in no particular order.
+        if limits != ActorCacheLimits::default() && self.last_access.get_mut().is_empty() {
+            let ids: Vec<_> = self
+                .actor_cache
+                .get_mut()
+                .iter()
+                .map(|(&id, _)| id)
+                .collect();
+            for id in ids {
+                self.touch(id);
+            }
+        }
+        self.settle();
+    }
+
     /// Get actor state from an address. Will be resolved to ID address.
     #[cfg(feature = "testing")]
     pub fn get_actor_by_address(&self, addr: &Address) -> Result<Option<ActorState>> {
@@ -162,7 +264,9 @@ where
 
     /// Get actor state from an actor ID.
     pub fn get_actor(&self, id: ActorID) -> Result<Option<ActorState>> {
-        self.actor_cache
+        self.touch(id);
+        let actor = self
+            .actor_cache
             .borrow_mut()
             .get_or_try_insert_with(id, || {
                 // It's not cached/dirty, so we look it up and cache it.
@@ -177,18 +281,26 @@ where
                         .cloned(),
                 })
             })
-            .map(|ActorCacheEntry { actor, .. }| actor.clone())
+            .map(|ActorCacheEntry { actor, .. }| actor.clone())?;
+        self.settle();
+        Ok(actor)
     }
 
     /// Set actor state with an actor ID.
     pub fn set_actor(&mut self, id: ActorID, actor: ActorState) {
+        self.touch(id);
+        self.dirty_actors.insert(id);
+        if let Some(addr) = actor.delegated_address {
+            self.cache_delegated(addr, Some(id));
+        }
         self.actor_cache.borrow_mut().insert(
             id,
             ActorCacheEntry {
                 actor: Some(actor),
                 dirty: true,
             },
-        )
+        );
+        self.settle();
     }
 
     /// Get an ID address from any Address
@@ -209,13 +321,85 @@ where
         };
 
         self.resolve_cache.borrow_mut().insert(*addr, a);
+        self.settle();
 
         Ok(Some(a))
     }
 
+    /// Resolves a delegated (f4) address to the ID of the live actor it's assigned to, if any.
+    /// Unlike [`StateTree::lookup_id`], this doesn't resolve the addresses of deleted actors.
+    ///
+    /// Lookups are cached, falling back on the init actor's address map for uncached addresses.
+    pub fn lookup_delegated(&self, addr: &Address) -> Result<Option<ActorID>> {
+        if addr.protocol() != Protocol::Delegated {
+            return Err(
+                syscall_error!(IllegalArgument; "{} is not a delegated address", addr).into(),
+            );
+        }
+
+        let cached = self.delegated_cache.borrow().get(addr).copied();
+        let id = match cached {
+            Some(id) => id,
+            None => {
+                let (state, _) = InitActorState::load(self)?;
+                state.resolve_address(self.store(), addr)?
+            }
+        };
+        // Make sure the actor still exists and is assigned this address.
+        let id = match id {
+            Some(id) => self
+                .get_actor(id)?
+                .filter(|act| act.delegated_address.as_ref() == Some(addr))
+                .map(|_| id),
+            None => None,
+        };
+
+        if cached != Some(id) {
+            self.delegated_cache.borrow_mut().insert(*addr, id);
+            self.settle();
+        }
+        Ok(id)
+    }
+
+    /// Resolves a 20-byte Ethereum address to the ID of a live actor, if any. "Masked" ID
+    /// addresses (`0xff`, followed by 11 zero bytes and the big-endian actor ID) resolve to the
+    /// actor with that ID, and all other addresses to the actor with the corresponding `f410`
+    /// delegated address (see [`StateTree::lookup_delegated`]).
+    pub fn lookup_eth_address(&self, eth_addr: &[u8; 20]) -> Result<Option<ActorID>> {
+        if let [0xff, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, id @ ..] = eth_addr {
+            let id = ActorID::from_be_bytes(*id);
+            return Ok(self.get_actor(id)?.map(|_| id));
+        }
+        let addr = Address::new_delegated(EAM_ACTOR_ID, eth_addr).or_fatal()?;
+        self.lookup_delegated(&addr)
+    }
+
+    /// Records the actor assigned to the given delegated address (or `None` if it was deleted) in
+    /// the delegated address cache.
+    fn cache_delegated(&mut self, addr: Address, id: Option<ActorID>) {
+        let cache = self.delegated_cache.get_mut();
+        if cache.get(&addr) != Some(&id) {
+            cache.insert(addr, id);
+        }
+    }
+
     /// Delete actor identified by the supplied ID.
     pub fn delete_actor(&mut self, id: ActorID) {
+        // Remove the actor from the delegated address cache, if we know its address. Otherwise,
+        // lookups will notice that the actor no longer exists.
+        let delegated = match self.actor_cache.get_mut().get(&id) {
+            Some(ActorCacheEntry {
+                actor: Some(act), ..
+            }) => act.delegated_address,
+            _ => None,
+        };
+        if let Some(addr) = delegated {
+            self.cache_delegated(addr, None);
+        }
+
         // Record that we've deleted the actor.
+        self.touch(id);
+        self.dirty_actors.insert(id);
         self.actor_cache.borrow_mut().insert(
             id,
             ActorCacheEntry {
@@ -223,6 +407,7 @@ where
                 actor: None,
             },
         );
+        self.settle();
     }
 
     /// Mutate and set actor state identified by the supplied ID. Returns a fatal error if the actor
@@ -259,6 +444,77 @@ where
         Ok(true)
     }
 
+    /// Mutates many actors at once, e.g., for migrations or genesis construction. Unlike
+    /// [`StateTree::mutate_actor`], actors that aren't already cached are read from and written
+    /// back to the underlying HAMT directly, without going through (and growing) the actor cache.
+    /// Changes are persisted on the next [`StateTree::flush`]. Returns the number of mutated
+    /// actors.
+    ///
+    /// Mutations can't be reverted, so this fails when called inside of a transaction. Returns a
+    /// fatal error if any of the actors doesn't exist, leaving the preceding mutations in place.
+    pub fn mutate_many<I, F>(&mut self, mutations: I) -> Result<usize>
+    where
+        I: IntoIterator<Item = (ActorID, F)>,
+        F: FnOnce(&mut ActorState) -> Result<()>,
+    {
+        if self.in_transaction() {
+            return Err(ExecutionError::Fatal(anyhow!(
+                "cannot mutate actors in bulk inside of a transaction",
+            )));
+        }
+        let mut count = 0;
+        for (id, mutate) in mutations {
+            let key = Address::new_id(id).to_bytes();
+            let delegated = match self.actor_cache.get_mut().get_mut(&id) {
+                // Modified actors are flushed from the cache anyways.
+                // Cached actors are mutated through a copy, so they're left untouched on failure.
+                Some(ActorCacheEntry {
+                    dirty: true,
+                    actor: Some(actor),
+                }) => {
+                    let mut mutated = actor.clone();
+                    mutate(&mut mutated)?;
+                    *actor = mutated;
+                    actor.delegated_address
+                }
+                // Keep the cached copies of unmodified actors in sync with the HAMT.
+                Some(ActorCacheEntry {
+                    dirty: false,
+                    actor: Some(actor),
+                }) => {
+                    let mut mutated = actor.clone();
+                    mutate(&mut mutated)?;
+                    self.hamt.set(key.into(), mutated.clone()).or_fatal()?;
+                    *actor = mutated;
+                    actor.delegated_address
+                }
+                Some(ActorCacheEntry { actor: None, .. }) => {
+                    return Err(anyhow!("failed to lookup actor {}", id)).or_fatal()
+                }
+                None => {
+                    let mut actor = self
+                        .hamt
+                        .get(&key)
+                        .with_context(|| format!("failed to lookup actor {}", id))
+                        .or_fatal()?
+                        .cloned()
+                        .with_context(|| format!("failed to lookup actor {}", id))
+                        .or_fatal()?;
+                    mutate(&mut actor)?;
+                    let delegated = actor.delegated_address;
+                    self.hamt.set(key.into(), actor).or_fatal()?;
+                    delegated
+                }
+            };
+            if let Some(addr) = delegated {
+                self.cache_delegated(addr, Some(id));
+            }
+            count += 1;
+        }
+        self.settle();
+        Ok(count)
+    }
+
     /// Register a new address through the init actor.
     pub fn register_new_address(&mut self, addr: &Address) -> Result<ActorID> {
         let (mut state, mut actor) = InitActorState::load(self)?;
@@ -273,6 +529,7 @@ where
 
         self.set_actor(crate::init_actor::INIT_ACTOR_ID, actor);
         self.resolve_cache.borrow_mut().insert(*addr, new_id);
+        self.settle();
 
         Ok(new_id)
     }
@@ -282,6 +539,7 @@ where
         self.layers.push(StateSnapLayer {
             actor_cache_height: self.actor_cache.get_mut().history_len(),
             resolve_cache_height: self.resolve_cache.get_mut().history_len(),
+            delegated_cache_height: self.delegated_cache.get_mut().history_len(),
         })
     }
 
@@ -299,32 +557,151 @@ where
             self.resolve_cache
                 .get_mut()
                 .rollback(layer.resolve_cache_height);
+            self.delegated_cache
+                .get_mut()
+                .rollback(layer.delegated_cache_height);
         }
         // When we end the last transaction, discard the undo history.
-        if !self.in_transaction() {
-            self.actor_cache.get_mut().discard_history();
-            self.resolve_cache.get_mut().discard_history();
-        }
+        self.settle();
         Ok(())
     }
 
+    /// Outside of transactions, discards the caches' undo histories (there's nothing left to
+    /// revert to) and evicts actors from the actor cache if it's over its limits. Does nothing
+    /// inside of transactions.
+    fn settle(&self) {
+        if self.in_transaction() {
+            return;
+        }
+        self.actor_cache.borrow_mut().discard_history();
+        self.resolve_cache.borrow_mut().discard_history();
+        let mut delegated_cache = self.delegated_cache.borrow_mut();
+        delegated_cache.discard_history();
+        if delegated_cache.len() > DELEGATED_CACHE_MAX_LEN {
+            *delegated_cache = Default::default();
+        }
+        drop(delegated_cache);
+        self.evict();
+    }
+
+    /// Records an access to the given actor, for the actor cache's LRU eviction.
+    fn touch(&self, id: ActorID) {
+        if self.cache_limits == ActorCacheLimits::default() {
+            return;
+        }
+        let now = self.access_clock.get() + 1;
+        self.access_clock.set(now);
+        let mut last_access = self.last_access.borrow_mut();
+        let mut queue = self.access_queue.borrow_mut();
+        last_access.insert(id, now);
+        queue.push_back((now, id));
+        // Drop superseded accesses once they make up most of the queue.
+        if queue.len() > 2 * last_access.len() + 16 {
+            queue.retain(|(time, id)| last_access.get(id) == Some(time));
+        }
+    }
+
+    /// Evicts the least recently used unmodified actors from the actor cache if it exceeds the
+    /// [`ActorCacheLimits`]. Nothing is evicted inside of transactions, as evicted actors couldn't
+    /// be restored on revert.
+    ///
+    /// Modified actors are dropped from the access queue when reached, and re-queued (in order of
+    /// last access) once flushed.
+    fn evict(&self) {
+        let Some(max_len) = self.cache_limits.max_len() else {
+            return;
+        };
+        if self.in_transaction() || self.actor_cache.borrow().len() <= max_len {
+            return;
+        }
+
+        let mut cache = self.actor_cache.borrow_mut();
+        let mut last_access = self.last_access.borrow_mut();
+        let mut queue = self.access_queue.borrow_mut();
+        let target = max_len - max_len / 10;
+        while cache.len() > target {
+            let Some((time, id)) = queue.pop_front() else {
+                break;
+            };
+            if last_access.get(&id) != Some(&time) {
+                continue;
+            }
+            match cache.get(&id) {
+                Some(entry) if entry.dirty => continue,
+                Some(_) => {
+                    cache.evict(&id);
+                }
+                None => (),
+            }
+            last_access.remove(&id);
+        }
+    }
+
+    /// Create a savepoint, to which the state tree can later be rolled back. Savepoints are
+    /// transactions and stack with them: the state tree can't be flushed until all savepoints have
+    /// been committed or rolled back.
+    pub fn savepoint(&mut self) -> Savepoint {
+        self.begin_transaction();
+        Savepoint {
+            depth: self.layers.len(),
+        }
+    }
+
+    /// Keep all changes made since the savepoint, releasing it along with any savepoints (and
+    /// transactions) started after it.
+    pub fn commit(&mut self, savepoint: Savepoint) -> Result<()> {
+        self.release(savepoint, false)
+    }
+
+    /// Revert all changes made since the savepoint, releasing it along with any savepoints (and
+    /// transactions) started after it.
+    pub fn rollback(&mut self, savepoint: Savepoint) -> Result<()> {
+        self.release(savepoint, true)
+    }
+
+    fn release(&mut self, savepoint: Savepoint, revert: bool) -> Result<()> {
+        if savepoint.depth == 0 || savepoint.depth > self.layers.len() {
+            return Err(ExecutionError::Fatal(anyhow!(
+                "savepoint at depth {} has already been released",
+                savepoint.depth
+            )));
+        }
+        self.layers.truncate(savepoint.depth);
+        self.end_transaction(revert)
+    }
+
+    /// Returns the number of open transactions (including savepoints).
+    pub fn transaction_depth(&self) -> usize {
+        self.layers.len()
+    }
+
     /// Returns true if we're inside of a transaction.
     pub fn in_transaction(&self) -> bool {
         !self.layers.is_empty()
     }
 
     /// Flush state tree and return Cid root.
+    ///
+    /// Only actors modified since the last flush are written, and only the HAMT nodes on the paths
+    /// to those actors are re-encoded: unmodified subtrees are left untouched.
     pub fn flush(&mut self) -> Result<Cid> {
         if self.in_transaction() {
             return Err(ExecutionError::Fatal(anyhow!(
                 "cannot flush while inside of a transaction",
             )));
         }
-        for (&id, entry) in self.actor_cache.get_mut().iter_mut() {
-            if !entry.dirty {
-                continue;
-            }
+        let cache = self.actor_cache.get_mut();
+        let last_access = self.last_access.get_mut();
+        let mut flushed = Vec::new();
+        for id in self.dirty_actors.drain() {
+            let entry = match cache.get_mut(&id) {
+                Some(entry) if entry.dirty => entry,
+                _ => continue,
+            };
             entry.dirty = false;
+            if self.cache_limits != ActorCacheLimits::default() {
+                flushed.push((last_access.get(&id).copied().unwrap_or_default(), id));
+            }
             let addr = Address::new_id(id);
             match entry.actor {
                 None => {
@@ -338,7 +715,17 @@ where
             }
         }
 
+        // Flushed actors can now be evicted, so queue them back up.
+        flushed.sort_unstable();
+        for (_, id) in flushed {
+            let now = self.access_clock.get() + 1;
+            self.access_clock.set(now);
+            last_access.insert(id, now);
+            self.access_queue.get_mut().push_back((now, id));
+        }
+
         let root = self.hamt.flush().or_fatal()?;
+        self.settle();
 
         match self.version {
             StateTreeVersion::V0 => Ok(root),
@@ -360,6 +747,45 @@ where
         }
     }
 
+    /// Switches the state tree over to the given (flushed) state root, discarding all unflushed
+    /// changes. The root must be in the state tree's blockstore.
+    pub fn set_root(&mut self, root: &Cid) -> Result<()> {
+        if self.in_transaction() {
+            return Err(ExecutionError::Fatal(anyhow!(
+                "cannot set the state root while inside of a transaction",
+            )));
+        }
+        let StateRoot {
+            version,
+            info,
+            actors,
+        } = self
+            .store()
+            .get_cbor(root)
+            .or_fatal()?
+            .with_context(|| format!("failed to find state tree {}", root))
+            .or_fatal()?;
+        if version != self.version {
+            return Err(ExecutionError::Fatal(anyhow!(
+                "cannot switch from a {:?} state tree to a {:?} state tree",
+                self.version,
+                version
+            )));
+        }
+        self.hamt
+            .set_root(&actors)
+            .context("failed to load state tree")
+            .or_fatal()?;
+        self.info = Some(info);
+        *self.actor_cache.get_mut() = Default::default();
+        *self.resolve_cache.get_mut() = Default::default();
+        *self.delegated_cache.get_mut() = Default::default();
+        self.last_access.get_mut().clear();
+        self.access_queue.get_mut().clear();
+        self.dirty_actors.clear();
+        Ok(())
+    }
+
     /// Consumes this StateTree and returns the Blockstore it owns via the HAMT.
     pub fn into_store(self) -> S {
         self.hamt.into_store()
@@ -375,110 +801,845 @@ where
         })?;
         Ok(())
     }
-}
 
-/// State of all actor implementations.
-#[derive(PartialEq, Eq, Clone, Debug, Serialize_tuple, Deserialize_tuple)]
-pub struct ActorState {
-    /// Link to code for the actor.
-    pub code: Cid,
-    /// Link to the state of the actor.
-    pub state: Cid,
-    /// Sequence of the actor.
-    pub sequence: u64,
-    /// Tokens available to the actor.
-    pub balance: TokenAmount,
-    /// The actor's "delegated" address, if assigned.
+    /// Iterates over at most `limit` actors (all remaining actors if `None`), starting at the
+    /// actor `start` (the first actor if `None`), returning the number of actors visited and the
+    /// cursor from which to resume: the ID of the next actor, if any. This lets callers page
+    /// through large state trees.
     ///
-    /// This field is set on actor creation and never modified.
-    pub delegated_address: Option<Address>,
-}
+    /// Actors are visited in HAMT order (not by ID), and only flushed changes are visible.
+    pub fn for_each_ranged<F>(
+        &self,
+        start: Option<ActorID>,
+        limit: Option<usize>,
+        mut f: F,
+    ) -> anyhow::Result<(usize, Option<ActorID>)>
+    where
+        F: FnMut(ActorID, &ActorState) -> anyhow::Result<()>,
+    {
+        let start = start.map(|id| Address::new_id(id).to_bytes());
+        let (traversed, next) = self
+            .hamt
+            .for_each_ranged(start.as_ref(), limit, |k, v| f(actor_id_from_key(k)?, v))?;
+        Ok((traversed, next.as_ref().map(actor_id_from_key).transpose()?))
+    }
 
-impl ActorState {
-    /// Constructor for actor state
-    pub fn new(
-        code: Cid,
-        state: Cid,
-        balance: TokenAmount,
-        sequence: u64,
-        address: Option<Address>,
-    ) -> Self {
-        Self {
-            code,
-            state,
-            sequence,
-            balance,
-            delegated_address: address,
+    /// Returns an iterator over the actors in the state tree, starting at the actor `start` (the
+    /// first actor if `None`). The ID of any actor returned by the iterator can be used to resume
+    /// iteration from that actor later.
+    ///
+    /// Actors are visited in HAMT order (not by ID), and only flushed changes are visible.
+    pub fn actors(
+        &self,
+        start: Option<ActorID>,
+    ) -> anyhow::Result<impl Iterator<Item = anyhow::Result<(ActorID, ActorState)>> + '_> {
+        let iter = match start {
+            Some(id) => self.hamt.iter_from(&Address::new_id(id).to_bytes())?,
+            None => self.hamt.iter(),
+        };
+        Ok(iter.map(|res| {
+            let (k, v) = res?;
+            Ok((actor_id_from_key(k)?, v.clone()))
+        }))
+    }
+
+    /// Flushes the state tree and migrates the flushed state to the given state tree version,
+    /// returning the migrated root. The state tree itself is left unchanged. See
+    /// [`migrate_version`].
+    pub fn migrate_version(&mut self, to_version: StateTreeVersion) -> anyhow::Result<Cid> {
+        let root = self.flush()?;
+        migrate_version(self.store(), &root, to_version)
+    }
+
+    /// Returns the actors changed between the state trees rooted at `a` and `b` (both in the
+    /// given store), sorted by actor ID. Only the parts of the state trees that differ are loaded.
+    ///
+    /// Fails if the state trees have different versions.
+    pub fn diff(store: &S, a: &Cid, b: &Cid) -> anyhow::Result<Vec<(ActorID, ActorChange)>> {
+        let a = StateTree::new_from_root(store, a)?;
+        let b = StateTree::new_from_root(store, b)?;
+        if a.version != b.version {
+            return Err(anyhow!(
+                "cannot diff a {:?} state tree with a {:?} state tree",
+                a.version,
+                b.version
+            ));
         }
+        let mut changes = a
+            .hamt
+            .diff(&b.hamt)?
+            .into_iter()
+            .map(|change| {
+                Ok(match change {
+                    Change::Add(k, act) => (actor_id_from_key(&k)?, ActorChange::Created(act)),
+                    Change::Remove(k, act) => (actor_id_from_key(&k)?, ActorChange::Deleted(act)),
+                    Change::Modify { key, before, after } => (
+                        actor_id_from_key(&key)?,
+                        ActorChange::Modified { before, after },
+                    ),
+                })
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        changes.sort_by_key(|(id, _)| *id);
+        Ok(changes)
     }
 
-    /// Construct a new empty actor with the specified code.
-    pub fn new_empty(code: Cid, delegated_address: Option<Address>) -> Self {
-        ActorState {
-            code,
-            state: *EMPTY_ARR_CID,
-            sequence: 0,
-            balance: TokenAmount::zero(),
-            delegated_address,
+    /// Walks every block reachable from the given state root (in this state tree's store): the
+    /// state root itself, the actors HAMT, and everything reachable from the actors (heads, HAMT
+    /// and AMT internals, code, etc.). Each block is passed to the visitor exactly once, and the
+    /// visitor's [`progress`](ReachableVisitor::progress) is reported every
+    /// [`PROGRESS_INTERVAL`](ReachableVisitor::PROGRESS_INTERVAL) blocks and once at the end.
+    ///
+    /// Identity-hashed CIDs aren't visited (they aren't stored), but their links are followed.
+    /// Piece commitments aren't visited. Only DAG-CBOR blocks are scanned for links.
+    ///
+    /// This walks the given root as stored, so unflushed changes aren't visible. Fails if a
+    /// reachable block is missing from the store.
+    pub fn reachable_cids<V>(&self, root: &Cid, visitor: &mut V) -> anyhow::Result<WalkProgress>
+    where
+        V: ReachableVisitor + ?Sized,
+    {
+        let mut seen = HashSet::new();
+        let mut stack = vec![*root];
+        let mut progress = WalkProgress::default();
+        while let Some(cid) = stack.pop() {
+            if matches!(cid.codec(), FIL_COMMITMENT_UNSEALED | FIL_COMMITMENT_SEALED)
+                || !seen.insert(cid)
+            {
+                continue;
+            }
+            if cid.hash().code() == IDENTITY_HASH {
+                if cid.codec() == DAG_CBOR {
+                    scan_for_links(cid.hash().digest(), &mut stack)?;
+                }
+                continue;
+            }
+
+            let block = self
+                .store()
+                .get(&cid)?
+                .ok_or_else(|| anyhow!("missing block {} reachable from {}", cid, root))?;
+            progress.blocks += 1;
+            progress.bytes += block.len() as u64;
+            if visitor.visit(&cid, &block)? && cid.codec() == DAG_CBOR {
+                scan_for_links(&block, &mut stack)
+                    .with_context(|| format!("failed to scan block {} for links", cid))?;
+            }
+            if progress.blocks % V::PROGRESS_INTERVAL == 0 {
+                progress.pending = stack.len();
+                visitor.progress(&progress)?;
+            }
         }
+        progress.pending = 0;
+        visitor.progress(&progress)?;
+        Ok(progress)
     }
 
-    /// Safely deducts funds from an Actor
-    pub fn deduct_funds(&mut self, amt: &TokenAmount) -> Result<()> {
-        if &self.balance < amt {
-            return Err(
-                syscall_error!(InsufficientFunds; "when deducting funds ({}) from balance ({})", amt, self.balance).into(),
-            );
+    /// Computes the size of the state reachable from each actor's state head in the state tree
+    /// rooted at `root`, returning the actors sorted by size (largest first, then by ID). If `top`
+    /// is specified, only the `top` largest actors are returned.
+    ///
+    /// Each actor's state is walked separately (see [`StateTree::reachable_cids`]), so blocks
+    /// shared between actors count towards each of them. Actor code isn't counted.
+    pub fn actor_state_sizes(
+        &self,
+        root: &Cid,
+        top: Option<usize>,
+    ) -> anyhow::Result<Vec<ActorStateSize>> {
+        struct Walk;
+        impl ReachableVisitor for Walk {
+            fn visit(&mut self, _: &Cid, _: &[u8]) -> anyhow::Result<bool> {
+                Ok(true)
+            }
         }
-        self.balance -= amt;
 
-        Ok(())
+        if top == Some(0) {
+            return Ok(Vec::new());
+        }
+
+        // A min-heap of the largest actors seen so far (or of all actors, if `top` isn't set),
+        // ordered by size and then by reverse ID.
+        let tree = StateTree::new_from_root(self.store(), root)?;
+        let mut sizes = BinaryHeap::new();
+        tree.hamt.for_each(|key, actor| {
+            let actor_id = actor_id_from_key(key)?;
+            let WalkProgress { blocks, bytes, .. } =
+                self.reachable_cids(&actor.state, &mut Walk)
+                    .with_context(|| format!("failed to walk the state of actor {}", actor_id))?;
+            sizes.push(Reverse((bytes, Reverse(actor_id), blocks)));
+            if top.map_or(false, |top| sizes.len() > top) {
+                sizes.pop();
+            }
+            Ok(())
+        })?;
+        Ok(sizes
+            .into_sorted_vec()
+            .into_iter()
+            .map(|Reverse((bytes, Reverse(actor), blocks))| ActorStateSize {
+                actor,
+                blocks,
+                bytes,
+            })
+            .collect())
     }
-    /// Deposits funds to an Actor
-    pub fn deposit_funds(&mut self, amt: &TokenAmount) {
-        self.balance += amt;
+
+    /// Checks the integrity of the state tree rooted at `root` (in this state tree's store),
+    /// reporting every problem found instead of stopping at the first one: structural problems in
+    /// the actors HAMT (see [`Hamt::validate`]), missing blocks, and malformed actors. This should
+    /// be run on imported snapshots before trusting them.
+    ///
+    /// Actor states are checked for presence, but their contents aren't walked (see
+    /// [`StateTree::reachable_cids`]). Fails if the state root itself can't be loaded.
+    pub fn verify(&self, root: &Cid) -> anyhow::Result<Vec<IntegrityProblem>> {
+        let tree = StateTree::new_from_root(self.store(), root)?;
+        let store = self.store();
+        // Returns the given problem if the block is missing.
+        let check = |cid: &Cid, missing: IntegrityProblem| {
+            // Identity-hashed blocks are inlined into their CIDs.
+            if cid.hash().code() == IDENTITY_HASH {
+                return None;
+            }
+            match store.has(cid) {
+                Ok(true) => None,
+                Ok(false) => Some(missing),
+                Err(e) => Some(IntegrityProblem::Store {
+                    cid: *cid,
+                    error: e.to_string(),
+                }),
+            }
+        };
+
+        let mut problems = Vec::new();
+        if let Some(info) = &tree.info {
+            problems.extend(check(info, IntegrityProblem::MissingInfo(*info)));
+        }
+        let hamt_problems = tree.hamt.validate_with(|key, actor| {
+            let id = match actor_id_from_key(key) {
+                Ok(id) => id,
+                Err(_) => {
+                    problems.push(IntegrityProblem::InvalidKey(key.clone()));
+                    return;
+                }
+            };
+            problems.extend(check(
+                &actor.code,
+                IntegrityProblem::MissingCode {
+                    actor: id,
+                    code: actor.code,
+                },
+            ));
+            problems.extend(check(
+                &actor.state,
+                IntegrityProblem::MissingState {
+                    actor: id,
+                    state: actor.state,
+                },
+            ));
+            if let Some(address) = actor.delegated_address {
+                if address.protocol() != Protocol::Delegated {
+                    problems.push(IntegrityProblem::InvalidDelegatedAddress { actor: id, address });
+                }
+            }
+        });
+
+        problems.extend(hamt_problems.into_iter().map(IntegrityProblem::Hamt));
+        Ok(problems)
+    }
+
+    /// Flushes the state tree and proves the inclusion (or absence) of an actor in the flushed
+    /// state, returning the proof along with the flushed state root. The proof contains the state
+    /// root block and the HAMT nodes on the path to the actor, and can be checked against the state
+    /// root with [`verify_actor_proof`].
+    ///
+    /// Non-ID addresses are resolved through the init actor, but the resolution itself isn't
+    /// proven. Fails if the address can't be resolved.
+    pub fn prove_actor(&mut self, addr: &Address) -> anyhow::Result<(Cid, ActorProof)> {
+        let id = self
+            .lookup_id(addr)?
+            .with_context(|| format!("failed to resolve {} to an actor ID", addr))?;
+        let root = self.flush()?;
+
+        // Look the actor up in a fresh state tree, recording the blocks it reads.
+        let recorder = ProofRecorder {
+            inner: self.store(),
+            blocks: Default::default(),
+        };
+        StateTree::new_from_root(&recorder, &root)?.get_actor(id)?;
+        Ok((
+            root,
+            ActorProof {
+                actor: id,
+                blocks: recorder.blocks.into_inner(),
+            },
+        ))
     }
 }
 
-#[cfg(feature = "arb")]
-impl Arbitrary for ActorState {
-    fn arbitrary(g: &mut quickcheck::Gen) -> Self {
-        Self {
-            code: Cid::arbitrary(g),
-            state: Cid::arbitrary(g),
-            sequence: u64::arbitrary(g),
-            balance: TokenAmount::arbitrary(g),
-            delegated_address: Option::arbitrary(g),
+/// A problem found by [`StateTree::verify`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum IntegrityProblem {
+    /// The state info block is missing from the store.
+    MissingInfo(Cid),
+    /// A structural problem in the actors HAMT.
+    Hamt(HamtProblem),
+    /// An actor stored under a key that isn't an ID address.
+    InvalidKey(BytesKey),
+    /// An actor's code is missing from the store.
+    MissingCode { actor: ActorID, code: Cid },
+    /// An actor's state head is missing from the store.
+    MissingState { actor: ActorID, state: Cid },
+    /// An actor's delegated address isn't a delegated (f4) address.
+    InvalidDelegatedAddress { actor: ActorID, address: Address },
+    /// The store failed to check for a block.
+    Store { cid: Cid, error: String },
+}
+
+/// A proof of the inclusion (or absence) of an actor in a state tree, created by
+/// [`StateTree::prove_actor`] and checked by [`verify_actor_proof`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ActorProof {
+    /// The proven actor.
+    pub actor: ActorID,
+    /// The blocks on the path from the state root to the actor, in the order they're read.
+    pub blocks: Vec<(Cid, Vec<u8>)>,
+}
+
+/// Checks an [`ActorProof`] against a (trusted) state root, returning the proven actor's state,
+/// or `None` if the proof shows that the actor doesn't exist.
+///
+/// Fails if any of the proof's blocks doesn't match its CID, or if the proof is incomplete.
+pub fn verify_actor_proof(root: &Cid, proof: &ActorProof) -> anyhow::Result<Option<ActorState>> {
+    let store = MemoryBlockstore::new();
+    for (cid, block) in &proof.blocks {
+        let code = multihash::Code::try_from(cid.hash().code())
+            .with_context(|| format!("unsupported multihash in proof block {}", cid))?;
+        if code.digest(block) != *cid.hash() {
+            return Err(anyhow!("proof block {} doesn't match its CID", cid));
         }
+        store.put_keyed(cid, block)?;
     }
+    let tree = StateTree::new_from_root(&store, root)
+        .map_err(anyhow::Error::from)
+        .context("incomplete actor proof")?;
+    tree.get_actor(proof.actor)
+        .map_err(anyhow::Error::from)
+        .context("incomplete actor proof")
 }
 
-#[cfg(feature = "json")]
-pub mod json {
-    use std::str::FromStr;
+/// A blockstore recording the blocks read through it, used to build [`ActorProof`]s.
+struct ProofRecorder<BS> {
+    inner: BS,
+    blocks: RefCell<Vec<(Cid, Vec<u8>)>>,
+}
 
-    use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+impl<BS: Blockstore> Blockstore for ProofRecorder<BS> {
+    fn get(&self, k: &Cid) -> anyhow::Result<Option<Vec<u8>>> {
+        let block = self.inner.get(k)?;
+        if let Some(block) = &block {
+            self.blocks.borrow_mut().push((*k, block.clone()));
+        }
+        Ok(block)
+    }
 
-    use super::*;
-    use crate::TokenAmount;
+    fn put_keyed(&self, k: &Cid, block: &[u8]) -> anyhow::Result<()> {
+        self.inner.put_keyed(k, block)
+    }
+}
 
-    /// Wrapper for serializing and deserializing a SignedMessage from JSON.
-    #[derive(Deserialize, Serialize)]
-    #[serde(transparent)]
-    pub struct ActorStateJson(#[serde(with = "self")] pub ActorState);
+/// Visits the blocks reachable from a state root. See [`StateTree::reachable_cids`].
+pub trait ReachableVisitor {
+    /// The number of blocks visited between progress reports.
+    const PROGRESS_INTERVAL: u64 = 1024;
 
-    /// Wrapper for serializing a SignedMessage reference to JSON.
-    #[derive(Serialize)]
-    #[serde(transparent)]
-    pub struct ActorStateJsonRef<'a>(#[serde(with = "self")] pub &'a ActorState);
+    /// Visits a reachable block. Returns whether to walk the block's links: visitors that already
+    /// know about a block (e.g., from a previous walk) can return `false` to skip its subtree.
+    fn visit(&mut self, cid: &Cid, block: &[u8]) -> anyhow::Result<bool>;
 
-    impl From<ActorStateJson> for ActorState {
-        fn from(wrapper: ActorStateJson) -> Self {
-            wrapper.0
-        }
+    /// Reports the progress of the walk. Returning an error aborts the walk.
+    fn progress(&mut self, progress: &WalkProgress) -> anyhow::Result<()> {
+        let _ = progress;
+        Ok(())
     }
+}
 
-    pub fn serialize<S>(m: &ActorState, serializer: S) -> std::result::Result<S::Ok, S::Error>
+/// The progress of a [`StateTree::reachable_cids`] walk.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct WalkProgress {
+    /// The number of blocks visited so far.
+    pub blocks: u64,
+    /// The total size of the blocks visited so far.
+    pub bytes: u64,
+    /// The number of links discovered but not yet walked (possibly including duplicates).
+    pub pending: usize,
+}
+
+/// The size of an actor's state, returned by [`StateTree::actor_state_sizes`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ActorStateSize {
+    /// The actor's ID.
+    pub actor: ActorID,
+    /// The number of blocks reachable from the actor's state head.
+    pub blocks: u64,
+    /// The total size of those blocks, in bytes.
+    pub bytes: u64,
+}
+
+/// A read-only view of a flushed state tree, for serving state queries from many threads against a
+/// fixed state root. Unlike a [`StateTree`], a view has no write buffer or undo history, and is
+/// `Sync` when its blockstore is `Send` and `Sync`.
+///
+/// Actors and resolved addresses are cached, up to [`VIEW_CACHE_MAX_LEN`] entries each. The
+/// actors HAMT is loaded once and reused across queries (or once per concurrent query).
+pub struct StateTreeView<S> {
+    store: Arc<S>,
+    root: Cid,
+    version: StateTreeVersion,
+    /// The root of the actors HAMT.
+    actors: Cid,
+    /// Loaded actors HAMTs, not currently in use. A HAMT caches the nodes it loads but isn't
+    /// `Sync`, so each concurrent query takes one out of the pool (loading a new one only if the
+    /// pool is empty), and returns it once done.
+    hamts: Mutex<Vec<Hamt<Arc<S>, ActorState>>>,
+    actor_cache: RwLock<HashMap<ActorID, Option<ActorState>>>,
+    resolve_cache: RwLock<HashMap<Address, Option<ActorID>>>,
+}
+
+/// The maximum number of actors (and, separately, of resolved addresses) cached by a
+/// [`StateTreeView`]. A full cache is cleared before caching more entries.
+pub const VIEW_CACHE_MAX_LEN: usize = 1 << 16;
+
+impl<S> StateTreeView<S>
+where
+    S: Blockstore,
+{
+    /// Creates a view of the state tree rooted at `root`. Fails if the state root can't be
+    /// loaded, or has an unsupported version.
+    pub fn new(store: S, root: &Cid) -> anyhow::Result<Self> {
+        let StateRoot {
+            version, actors, ..
+        } = store
+            .get_cbor(root)?
+            .with_context(|| format!("failed to find state tree {}", root))?;
+        if version != StateTreeVersion::V5 {
+            return Err(anyhow!("unsupported state tree version: {:?}", version));
+        }
+        let store = Arc::new(store);
+        let hamt = Hamt::load_with_bit_width(&actors, store.clone(), HAMT_BIT_WIDTH)
+            .context("failed to load state tree")?;
+        Ok(StateTreeView {
+            store,
+            root: *root,
+            version,
+            actors,
+            hamts: Mutex::new(vec![hamt]),
+            actor_cache: Default::default(),
+            resolve_cache: Default::default(),
+        })
+    }
+
+    /// Returns the viewed state root.
+    pub fn root(&self) -> &Cid {
+        &self.root
+    }
+
+    /// Returns the version of the viewed state tree.
+    pub fn version(&self) -> StateTreeVersion {
+        self.version
+    }
+
+    /// Returns the view's blockstore.
+    pub fn store(&self) -> &S {
+        &self.store
+    }
+
+    /// Returns the state of the actor with the given ID, if it exists.
+    pub fn get_actor(&self, id: ActorID) -> anyhow::Result<Option<ActorState>> {
+        if let Some(actor) = self.actor_cache.read().expect(POISONED).get(&id) {
+            return Ok(actor.clone());
+        }
+        let actor = self.with_hamt(|hamt| {
+            Ok(hamt
+                .get(&Address::new_id(id).to_bytes())
+                .with_context(|| format!("failed to lookup actor {}", id))?
+                .cloned())
+        })?;
+        // The pooled HAMTs cache the nodes they've loaded, so drop them along with the actors.
+        if insert_bounded(&self.actor_cache, id, actor.clone()) {
+            self.hamts.lock().expect(POISONED).clear();
+        }
+        Ok(actor)
+    }
+
+    /// Resolves an address to an actor ID, through the init actor for non-ID addresses.
+    pub fn lookup_id(&self, addr: &Address) -> anyhow::Result<Option<ActorID>> {
+        if let &Payload::ID(id) = addr.payload() {
+            return Ok(Some(id));
+        }
+        if let Some(&id) = self.resolve_cache.read().expect(POISONED).get(addr) {
+            return Ok(id);
+        }
+        let init_act = self
+            .get_actor(INIT_ACTOR_ID)?
+            .context("init actor address could not be resolved")?;
+        let state: InitActorState = self
+            .store
+            .get_cbor(&init_act.state)?
+            .context("init actor state not found")?;
+        let id = state.resolve_address(self.store(), addr)?;
+        insert_bounded(&self.resolve_cache, *addr, id);
+        Ok(id)
+    }
+
+    /// Returns the state of the actor with the given address, if it exists.
+    pub fn get_actor_by_address(&self, addr: &Address) -> anyhow::Result<Option<ActorState>> {
+        match self.lookup_id(addr)? {
+            Some(id) => self.get_actor(id),
+            None => Ok(None),
+        }
+    }
+
+    /// Calls `f` on every actor in the state tree, in HAMT order (not by ID).
+    pub fn for_each<F>(&self, mut f: F) -> anyhow::Result<()>
+    where
+        F: FnMut(ActorID, &ActorState) -> anyhow::Result<()>,
+    {
+        self.with_hamt(|hamt| {
+            hamt.for_each(|k, v| f(actor_id_from_key(k)?, v))?;
+            Ok(())
+        })
+    }
+
+    /// Calls `f` with an actors HAMT from the pool, loading a new one if all are in use.
+    fn with_hamt<T>(
+        &self,
+        f: impl FnOnce(&Hamt<Arc<S>, ActorState>) -> anyhow::Result<T>,
+    ) -> anyhow::Result<T> {
+        let pooled = self.hamts.lock().expect(POISONED).pop();
+        let hamt = match pooled {
+            Some(hamt) => hamt,
+            None => Hamt::load_with_bit_width(&self.actors, self.store.clone(), HAMT_BIT_WIDTH)
+                .context("failed to load state tree")?,
+        };
+        let res = f(&hamt);
+        self.hamts.lock().expect(POISONED).push(hamt);
+        res
+    }
+}
+
+/// Inserts an entry into a [`StateTreeView`] cache, first clearing the cache if it's full.
+/// Returns true if the cache was cleared.
+fn insert_bounded<K: Hash + Eq, V>(cache: &RwLock<HashMap<K, V>>, k: K, v: V) -> bool {
+    let mut cache = cache.write().expect(POISONED);
+    let full = cache.len() >= VIEW_CACHE_MAX_LEN;
+    if full {
+        cache.clear();
+    }
+    cache.insert(k, v);
+    full
+}
+
+/// The panic message of a poisoned [`StateTreeView`] cache.
+const POISONED: &str = "state tree view cache poisoned";
+
+/// A change to an actor between two state trees, returned by [`StateTree::diff`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ActorChange {
+    /// The actor was created, with the given state.
+    Created(ActorState),
+    /// The actor was deleted. This is its last state.
+    Deleted(ActorState),
+    /// The actor's code, state, sequence, balance, and/or delegated address changed.
+    Modified {
+        before: ActorState,
+        after: ActorState,
+    },
+}
+
+/// Decodes the ID of the actor stored under the given state tree key.
+fn actor_id_from_key(key: &BytesKey) -> anyhow::Result<ActorID> {
+    let addr = Address::from_bytes(&key.0)?;
+    addr.id()
+        .map_err(|_| anyhow!("non-ID address {} in the state tree", addr))
+}
+
+/// State of all actor implementations.
+#[derive(PartialEq, Eq, Clone, Debug, Serialize_tuple, Deserialize_tuple)]
+pub struct ActorState {
+    /// Link to code for the actor.
+    pub code: Cid,
+    /// Link to the state of the actor.
+    pub state: Cid,
+    /// Sequence of the actor.
+    pub sequence: u64,
+    /// Tokens available to the actor.
+    pub balance: TokenAmount,
+    /// The actor's "delegated" address, if assigned.
+    ///
+    /// This field is set on actor creation and never modified.
+    pub delegated_address: Option<Address>,
+}
+
+impl ActorState {
+    /// Constructor for actor state
+    pub fn new(
+        code: Cid,
+        state: Cid,
+        balance: TokenAmount,
+        sequence: u64,
+        address: Option<Address>,
+    ) -> Self {
+        Self {
+            code,
+            state,
+            sequence,
+            balance,
+            delegated_address: address,
+        }
+    }
+
+    /// Construct a new empty actor with the specified code.
+    pub fn new_empty(code: Cid, delegated_address: Option<Address>) -> Self {
+        ActorState {
+            code,
+            state: *EMPTY_ARR_CID,
+            sequence: 0,
+            balance: TokenAmount::zero(),
+            delegated_address,
+        }
+    }
+
+    /// Safely deducts funds from an Actor
+    pub fn deduct_funds(&mut self, amt: &TokenAmount) -> Result<()> {
+        if &self.balance < amt {
+            return Err(
+                syscall_error!(InsufficientFunds; "when deducting funds ({}) from balance ({})", amt, self.balance).into(),
+            );
+        }
+        self.balance -= amt;
+
+        Ok(())
+    }
+    /// Deposits funds to an Actor
+    pub fn deposit_funds(&mut self, amt: &TokenAmount) {
+        self.balance += amt;
+    }
+}
+
+#[cfg(feature = "arb")]
+impl Arbitrary for ActorState {
+    fn arbitrary(g: &mut quickcheck::Gen) -> Self {
+        Self {
+            code: Cid::arbitrary(g),
+            state: Cid::arbitrary(g),
+            sequence: u64::arbitrary(g),
+            balance: TokenAmount::arbitrary(g),
+            delegated_address: Option::arbitrary(g),
+        }
+    }
+}
+
+/// The actor layout of state trees before [`StateTreeVersion::V5`], without delegated addresses.
+#[derive(Clone, Serialize_tuple, Deserialize_tuple)]
+struct ActorStateV4 {
+    code: Cid,
+    state: Cid,
+    sequence: u64,
+    balance: TokenAmount,
+}
+
+impl From<ActorStateV4> for ActorState {
+    fn from(actor: ActorStateV4) -> Self {
+        ActorState::new(actor.code, actor.state, actor.balance, actor.sequence, None)
+    }
+}
+
+/// Converts an actor to the layout of state trees before [`StateTreeVersion::V5`], failing if it
+/// has a delegated address.
+fn legacy_actor(key: &BytesKey, actor: ActorState) -> anyhow::Result<ActorStateV4> {
+    if let Some(addr) = actor.delegated_address {
+        return Err(anyhow!(
+            "actor {} has a delegated address ({}), unsupported before state tree v5",
+            Address::from_bytes(&key.0)?,
+            addr
+        ));
+    }
+    Ok(ActorStateV4 {
+        code: actor.code,
+        state: actor.state,
+        sequence: actor.sequence,
+        balance: actor.balance,
+    })
+}
+
+/// The actors HAMT of a state tree, in the layout of a specific state tree version:
+/// [`StateTreeVersion::V0`] state trees use the version 0 HAMT format, and state trees before
+/// [`StateTreeVersion::V5`] store actors without delegated addresses.
+enum ActorsHamt<BS> {
+    V0(Hamtv0<BS, ActorStateV4>),
+    V4(Hamt<BS, ActorStateV4>),
+    V5(Hamt<BS, ActorState>),
+}
+
+impl<BS: Blockstore> ActorsHamt<BS> {
+    fn new(store: BS, version: StateTreeVersion) -> Self {
+        match version {
+            StateTreeVersion::V0 => {
+                ActorsHamt::V0(Hamtv0::new_with_bit_width(store, HAMT_BIT_WIDTH))
+            }
+            StateTreeVersion::V5 => ActorsHamt::V5(Hamt::new_with_bit_width(store, HAMT_BIT_WIDTH)),
+            _ => ActorsHamt::V4(Hamt::new_with_bit_width(store, HAMT_BIT_WIDTH)),
+        }
+    }
+
+    fn load(store: BS, version: StateTreeVersion, root: &Cid) -> anyhow::Result<Self> {
+        Ok(match version {
+            StateTreeVersion::V0 => {
+                ActorsHamt::V0(Hamtv0::load_with_bit_width(root, store, HAMT_BIT_WIDTH)?)
+            }
+            StateTreeVersion::V5 => {
+                ActorsHamt::V5(Hamt::load_with_bit_width(root, store, HAMT_BIT_WIDTH)?)
+            }
+            _ => ActorsHamt::V4(Hamt::load_with_bit_width(root, store, HAMT_BIT_WIDTH)?),
+        })
+    }
+
+    fn for_each(
+        &self,
+        mut f: impl FnMut(&BytesKey, ActorState) -> anyhow::Result<()>,
+    ) -> anyhow::Result<()> {
+        match self {
+            ActorsHamt::V0(hamt) => hamt.for_each(|k, v| f(k, v.clone().into()))?,
+            ActorsHamt::V4(hamt) => hamt.for_each(|k, v| f(k, v.clone().into()))?,
+            ActorsHamt::V5(hamt) => hamt.for_each(|k, v| f(k, v.clone()))?,
+        }
+        Ok(())
+    }
+
+    fn set(&mut self, key: BytesKey, actor: ActorState) -> anyhow::Result<()> {
+        match self {
+            ActorsHamt::V0(hamt) => {
+                let actor = legacy_actor(&key, actor)?;
+                hamt.set(key, actor)?;
+            }
+            ActorsHamt::V4(hamt) => {
+                let actor = legacy_actor(&key, actor)?;
+                hamt.set(key, actor)?;
+            }
+            ActorsHamt::V5(hamt) => {
+                hamt.set(key, actor)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn flush(&mut self) -> anyhow::Result<Cid> {
+        Ok(match self {
+            ActorsHamt::V0(hamt) => hamt.flush()?,
+            ActorsHamt::V4(hamt) => hamt.flush()?,
+            ActorsHamt::V5(hamt) => hamt.flush()?,
+        })
+    }
+}
+
+/// Migrates the state tree rooted at `root` to the given state tree version, returning the root of
+/// the migrated state tree. Actors are streamed from the original state tree into a new actors
+/// HAMT in the target version's layout, and the number of migrated actors is verified against the
+/// original state tree.
+///
+/// State trees of any version can be migrated, although only [`StateTreeVersion::V5`] state trees
+/// can be loaded as a [`StateTree`]. Fails when migrating actors with delegated addresses to a
+/// version before [`StateTreeVersion::V5`].
+pub fn migrate_version<BS: Blockstore>(
+    store: &BS,
+    root: &Cid,
+    to_version: StateTreeVersion,
+) -> anyhow::Result<Cid> {
+    let block = store
+        .get(root)
+        .with_context(|| format!("failed to load state tree {}", root))?
+        .with_context(|| format!("failed to find state tree {}", root))?;
+    let (from_version, info, actors) = match fvm_ipld_encoding::from_slice(&block) {
+        Ok(StateRoot {
+            version,
+            info,
+            actors,
+        }) => (version, Some(info), actors),
+        // Version 0 state trees are bare actor HAMTs, which don't decode as state roots.
+        Err(_) => (StateTreeVersion::V0, None, *root),
+    };
+    if from_version == to_version {
+        return Ok(*root);
+    }
+
+    let source = ActorsHamt::load(store, from_version, &actors)
+        .with_context(|| format!("failed to load {:?} state tree {}", from_version, root))?;
+    let mut target = ActorsHamt::new(store, to_version);
+    let mut migrated = 0u64;
+    source.for_each(|k, actor| {
+        target.set(k.clone(), actor)?;
+        migrated += 1;
+        Ok(())
+    })?;
+    let actors = target.flush()?;
+
+    // Make sure we didn't lose any actors along the way.
+    let mut count = 0u64;
+    ActorsHamt::load(store, to_version, &actors)?.for_each(|_, _| {
+        count += 1;
+        Ok(())
+    })?;
+    if count != migrated {
+        return Err(anyhow!(
+            "migrated {} actors, but the {:?} state tree has {}",
+            migrated,
+            to_version,
+            count
+        ));
+    }
+
+    if to_version == StateTreeVersion::V0 {
+        return Ok(actors);
+    }
+    let info = match info {
+        Some(info) => info,
+        None => store.put_cbor(&StateInfo0::default(), multihash::Code::Blake2b256)?,
+    };
+    store.put_cbor(
+        &StateRoot {
+            version: to_version,
+            actors,
+            info,
+        },
+        multihash::Code::Blake2b256,
+    )
+}
+
+#[cfg(feature = "json")]
+pub mod json {
+    use std::str::FromStr;
+
+    use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+
+    use super::*;
+    use crate::TokenAmount;
+
+    /// Wrapper for serializing and deserializing a SignedMessage from JSON.
+    #[derive(Deserialize, Serialize)]
+    #[serde(transparent)]
+    pub struct ActorStateJson(#[serde(with = "self")] pub ActorState);
+
+    /// Wrapper for serializing a SignedMessage reference to JSON.
+    #[derive(Serialize)]
+    #[serde(transparent)]
+    pub struct ActorStateJsonRef<'a>(#[serde(with = "self")] pub &'a ActorState);
+
+    impl From<ActorStateJson> for ActorState {
+        fn from(wrapper: ActorStateJson) -> Self {
+            wrapper.0
+        }
+    }
+
+    pub fn serialize<S>(m: &ActorState, serializer: S) -> std::result::Result<S::Ok, S::Error>
     where
         S: Serializer,
     {
@@ -536,18 +1697,23 @@ pub mod json {
 mod tests {
     use cid::multihash::Code::Blake2b256;
     use cid::multihash::Multihash;
+    use cid::multihash::MultihashDigest;
     use cid::Cid;
-    use fvm_ipld_blockstore::MemoryBlockstore;
+    use fvm_ipld_blockstore::{Blockstore, MemoryBlockstore};
     use fvm_ipld_encoding::{CborStore, DAG_CBOR};
     use fvm_shared::address::{Address, SECP_PUB_LEN};
     use fvm_shared::econ::TokenAmount;
-    use fvm_shared::state::StateTreeVersion;
+    use fvm_shared::state::{StateRoot, StateTreeVersion};
     use fvm_shared::{ActorID, IDENTITY_HASH, IPLD_RAW};
     use lazy_static::lazy_static;
 
     use crate::init_actor;
     use crate::init_actor::INIT_ACTOR_ID;
-    use crate::state_tree::{ActorState, StateTree};
+    use crate::state_tree::{
+        migrate_version, verify_actor_proof, ActorCacheLimits, ActorChange, ActorState,
+        ActorStateSize, IntegrityProblem, ReachableVisitor, StateTree, StateTreeView, WalkProgress,
+        DELEGATED_CACHE_MAX_LEN,
+    };
 
     lazy_static! {
         pub static ref DUMMY_ACCOUNT_ACTOR_CODE_ID: Cid = Cid::new_v1(
@@ -645,6 +1811,54 @@ mod tests {
         assert_eq!(assigned_addr, 100);
     }
 
+    #[test]
+    fn nested_savepoints() {
+        let store = MemoryBlockstore::default();
+        let mut tree = StateTree::new(&store, StateTreeVersion::V5).unwrap();
+        let actor = |balance| {
+            ActorState::new(
+                *DUMMY_ACCOUNT_ACTOR_CODE_ID,
+                *DUMMY_ACCOUNT_ACTOR_CODE_ID,
+                TokenAmount::from_atto(balance),
+                1,
+                None,
+            )
+        };
+
+        let outer = tree.savepoint();
+        tree.set_actor(101, actor(1));
+
+        let middle = tree.savepoint();
+        tree.set_actor(102, actor(2));
+        let inner = tree.savepoint();
+        tree.set_actor(103, actor(3));
+        tree.commit(inner).unwrap();
+
+        // An unbalanced transaction is rolled back along with the savepoint.
+        tree.begin_transaction();
+        tree.set_actor(104, actor(4));
+        assert_eq!(tree.transaction_depth(), 3);
+        tree.rollback(middle).unwrap();
+        assert_eq!(tree.transaction_depth(), 1);
+
+        assert!(tree.get_actor(101).unwrap().is_some());
+        for id in 102..=104 {
+            assert!(tree.get_actor(id).unwrap().is_none());
+        }
+        // Can't flush until the outer savepoint is released.
+        assert!(tree.flush().is_err());
+
+        tree.commit(outer).unwrap();
+        assert!(!tree.in_transaction());
+        tree.flush().unwrap();
+        assert!(tree.get_actor(101).unwrap().is_some());
+
+        // Releasing a savepoint twice fails.
+        let sp = tree.savepoint();
+        tree.end_transaction(false).unwrap();
+        assert!(tree.commit(sp).is_err());
+    }
+
     #[test]
     fn test_transactions() {
         let store = MemoryBlockstore::default();
@@ -761,4 +1975,641 @@ mod tests {
             assert!(err.is_fatal());
         }
     }
+
+    #[test]
+    fn flush_dirty_actors() {
+        let actor = |seq| ActorState::new(empty_cid(), empty_cid(), Default::default(), seq, None);
+        let store = MemoryBlockstore::default();
+        let mut tree = StateTree::new(&store, StateTreeVersion::V5).unwrap();
+        for id in 100..200 {
+            tree.set_actor(id, actor(0));
+        }
+        let first = tree.flush().unwrap();
+        assert_eq!(tree.flush().unwrap(), first);
+
+        // Reverted changes aren't flushed.
+        tree.begin_transaction();
+        tree.set_actor(150, actor(1));
+        tree.end_transaction(true).unwrap();
+        assert_eq!(tree.flush().unwrap(), first);
+
+        tree.set_actor(150, actor(1));
+        tree.delete_actor(199);
+        let second = tree.flush().unwrap();
+        assert_ne!(second, first);
+
+        // The incremental flush matches a flush of the same state from scratch.
+        let mut expected = StateTree::new(&store, StateTreeVersion::V5).unwrap();
+        for id in 100..199 {
+            expected.set_actor(id, actor(if id == 150 { 1 } else { 0 }));
+        }
+        assert_eq!(expected.flush().unwrap(), second);
+    }
+
+    #[test]
+    fn set_root() {
+        let actor = |seq| ActorState::new(empty_cid(), empty_cid(), Default::default(), seq, None);
+        let store = MemoryBlockstore::default();
+        let mut tree = StateTree::new(&store, StateTreeVersion::V5).unwrap();
+        tree.set_actor(100, actor(0));
+        let first = tree.flush().unwrap();
+        tree.set_actor(100, actor(1));
+        let second = tree.flush().unwrap();
+
+        // Unflushed changes are discarded.
+        tree.set_actor(101, actor(0));
+        tree.set_root(&first).unwrap();
+        assert_eq!(tree.get_actor(100).unwrap(), Some(actor(0)));
+        assert_eq!(tree.get_actor(101).unwrap(), None);
+        assert_eq!(tree.flush().unwrap(), first);
+
+        tree.set_root(&second).unwrap();
+        assert_eq!(tree.get_actor(100).unwrap(), Some(actor(1)));
+
+        tree.begin_transaction();
+        assert!(tree.set_root(&first).is_err());
+    }
+
+    #[test]
+    fn bounded_cache() {
+        let actor = |seq| ActorState::new(empty_cid(), empty_cid(), Default::default(), seq, None);
+        let store = MemoryBlockstore::default();
+        let mut tree = StateTree::new(&store, StateTreeVersion::V5).unwrap();
+        tree.set_cache_limits(ActorCacheLimits {
+            max_entries: Some(10),
+            max_bytes: None,
+        });
+
+        // Modified actors stay cached until flushed.
+        for id in 100..130 {
+            tree.set_actor(id, actor(id));
+        }
+        assert_eq!(tree.actor_cache.borrow().len(), 30);
+        tree.flush().unwrap();
+        assert_eq!(tree.actor_cache.borrow().len(), 9);
+
+        // The most recently used actors are kept, and evicted actors are reloaded.
+        assert!(tree.actor_cache.borrow().get(&129).is_some());
+        assert!(tree.actor_cache.borrow().get(&100).is_none());
+        assert_eq!(tree.get_actor(100).unwrap(), Some(actor(100)));
+        assert!(tree.actor_cache.borrow().len() <= 10);
+
+        // No undo history is kept outside of transactions.
+        tree.set_actor(100, actor(1));
+        tree.get_actor(101).unwrap();
+        assert_eq!(tree.actor_cache.borrow().history_len(), 0);
+        tree.flush().unwrap();
+        assert_eq!(tree.get_actor(100).unwrap(), Some(actor(1)));
+
+        // Nothing is evicted inside of transactions.
+        tree.begin_transaction();
+        for id in 100..130 {
+            tree.get_actor(id).unwrap();
+        }
+        assert_eq!(tree.actor_cache.borrow().len(), 30);
+        tree.end_transaction(false).unwrap();
+        assert_eq!(tree.actor_cache.borrow().len(), 9);
+    }
+
+    #[test]
+    fn migrate_versions() {
+        let actor = |seq| ActorState::new(empty_cid(), empty_cid(), Default::default(), seq, None);
+        let store = MemoryBlockstore::default();
+        let mut tree = StateTree::new(&store, StateTreeVersion::V5).unwrap();
+        for id in 100..150 {
+            tree.set_actor(id, actor(id));
+        }
+        let root = tree.flush().unwrap();
+
+        // Migrating down and back up yields the same state tree.
+        for version in [StateTreeVersion::V0, StateTreeVersion::V4] {
+            let migrated = tree.migrate_version(version).unwrap();
+            assert_ne!(migrated, root);
+            assert_eq!(
+                migrate_version(&store, &migrated, StateTreeVersion::V5).unwrap(),
+                root
+            );
+        }
+        assert_eq!(tree.migrate_version(StateTreeVersion::V5).unwrap(), root);
+
+        // Delegated addresses can't be migrated to older versions.
+        let addr = Address::new_delegated(10, &[1; 20]).unwrap();
+        tree.set_actor(
+            150,
+            ActorState::new(empty_cid(), empty_cid(), Default::default(), 0, Some(addr)),
+        );
+        assert!(tree.migrate_version(StateTreeVersion::V4).is_err());
+
+        // Missing state roots aren't mistaken for version 0 state trees.
+        let missing = Cid::new_v1(DAG_CBOR, Blake2b256.digest(b"missing"));
+        assert!(migrate_version(&store, &missing, StateTreeVersion::V5).is_err());
+    }
+
+    #[test]
+    fn mutate_many() {
+        let actor = |seq| ActorState::new(empty_cid(), empty_cid(), Default::default(), seq, None);
+        let store = MemoryBlockstore::default();
+        let mut tree = StateTree::new(&store, StateTreeVersion::V5).unwrap();
+        for id in 100..200 {
+            tree.set_actor(id, actor(0));
+        }
+        tree.flush().unwrap();
+
+        // One cached and modified, one cached and unmodified, the rest uncached.
+        let mut tree = StateTree::new_from_root(&store, &tree.flush().unwrap()).unwrap();
+        tree.set_actor(100, actor(1));
+        tree.get_actor(101).unwrap();
+
+        let mutated = tree
+            .mutate_many((100..200).map(|id| {
+                (id, |act: &mut ActorState| {
+                    act.sequence += 1;
+                    Ok(())
+                })
+            }))
+            .unwrap();
+        assert_eq!(mutated, 100);
+        assert_eq!(tree.get_actor(100).unwrap(), Some(actor(2)));
+        assert_eq!(tree.get_actor(101).unwrap(), Some(actor(1)));
+        assert_eq!(tree.actor_cache.borrow().len(), 2);
+
+        let root = tree.flush().unwrap();
+        let tree = StateTree::new_from_root(&store, &root).unwrap();
+        assert_eq!(tree.get_actor(100).unwrap(), Some(actor(2)));
+        for id in 101..200 {
+            assert_eq!(tree.get_actor(id).unwrap(), Some(actor(1)));
+        }
+
+        // Missing actors and transactions are rejected.
+        let mut tree = StateTree::new_from_root(&store, &root).unwrap();
+        assert!(tree
+            .mutate_many([(200, |_: &mut ActorState| Ok(()))])
+            .is_err());
+
+        // Failed mutations leave cached actors untouched.
+        assert_eq!(tree.get_actor(101).unwrap(), Some(actor(1)));
+        assert!(tree
+            .mutate_many([(101, |act: &mut ActorState| {
+                act.sequence = 10;
+                Err(crate::kernel::ExecutionError::Fatal(anyhow::anyhow!(
+                    "failed"
+                )))
+            })])
+            .is_err());
+        assert_eq!(tree.get_actor(101).unwrap(), Some(actor(1)));
+        assert_eq!(tree.flush().unwrap(), root);
+        tree.begin_transaction();
+        assert!(tree
+            .mutate_many([(100, |_: &mut ActorState| Ok(()))])
+            .is_err());
+    }
+
+    #[test]
+    fn paginate_actors() {
+        let actor = |seq| ActorState::new(empty_cid(), empty_cid(), Default::default(), seq, None);
+        let store = MemoryBlockstore::default();
+        let mut tree = StateTree::new(&store, StateTreeVersion::V5).unwrap();
+        for id in 100..150 {
+            tree.set_actor(id, actor(id));
+        }
+        tree.flush().unwrap();
+
+        // Page through the actors, 7 at a time.
+        let mut seen = Vec::new();
+        let mut cursor = None;
+        loop {
+            let (traversed, next) = tree
+                .for_each_ranged(cursor, Some(7), |id, act| {
+                    assert_eq!(act.sequence, id);
+                    seen.push(id);
+                    Ok(())
+                })
+                .unwrap();
+            assert!(traversed <= 7);
+            match next {
+                Some(next) => cursor = Some(next),
+                None => break,
+            }
+        }
+        seen.sort();
+        assert_eq!(seen, (100..150).collect::<Vec<_>>());
+
+        // The iterator resumes from any actor it returned.
+        let all: Vec<_> = tree
+            .actors(None)
+            .unwrap()
+            .map(|res| res.unwrap().0)
+            .collect();
+        assert_eq!(all.len(), 50);
+        let rest: Vec<_> = tree
+            .actors(Some(all[20]))
+            .unwrap()
+            .map(|res| res.unwrap().0)
+            .collect();
+        assert_eq!(rest, all[20..]);
+    }
+
+    #[test]
+    fn diff() {
+        let actor = |seq| ActorState::new(empty_cid(), empty_cid(), Default::default(), seq, None);
+        let store = MemoryBlockstore::default();
+        let mut tree = StateTree::new(&store, StateTreeVersion::V5).unwrap();
+        for id in 100..120 {
+            tree.set_actor(id, actor(0));
+        }
+        let before = tree.flush().unwrap();
+
+        tree.delete_actor(105);
+        tree.set_actor(110, actor(1));
+        tree.set_actor(120, actor(0));
+        let after = tree.flush().unwrap();
+
+        assert_eq!(
+            StateTree::diff(&store, &before, &after).unwrap(),
+            vec![
+                (105, ActorChange::Deleted(actor(0))),
+                (
+                    110,
+                    ActorChange::Modified {
+                        before: actor(0),
+                        after: actor(1)
+                    }
+                ),
+                (120, ActorChange::Created(actor(0))),
+            ]
+        );
+        assert!(StateTree::diff(&store, &after, &after).unwrap().is_empty());
+    }
+
+    #[test]
+    fn reachable_cids() {
+        #[derive(Default)]
+        struct Collect {
+            cids: Vec<Cid>,
+            reports: Vec<WalkProgress>,
+        }
+
+        impl ReachableVisitor for Collect {
+            const PROGRESS_INTERVAL: u64 = 2;
+
+            fn visit(&mut self, cid: &Cid, _: &[u8]) -> anyhow::Result<bool> {
+                self.cids.push(*cid);
+                Ok(true)
+            }
+
+            fn progress(&mut self, progress: &WalkProgress) -> anyhow::Result<()> {
+                self.reports.push(*progress);
+                Ok(())
+            }
+        }
+
+        let store = MemoryBlockstore::default();
+        let leaf = store.put_cbor(&"leaf", Blake2b256).unwrap();
+        let head = store.put_cbor(&(leaf, leaf), Blake2b256).unwrap();
+        let mut tree = StateTree::new(&store, StateTreeVersion::V5).unwrap();
+        // Two actors sharing the same head, which is walked once.
+        for id in [100, 101] {
+            tree.set_actor(
+                id,
+                ActorState::new(
+                    *DUMMY_ACCOUNT_ACTOR_CODE_ID,
+                    head,
+                    Default::default(),
+                    0,
+                    None,
+                ),
+            );
+        }
+        let root = tree.flush().unwrap();
+
+        let mut visitor = Collect::default();
+        let progress = tree.reachable_cids(&root, &mut visitor).unwrap();
+        // The state root, the state info, the actors HAMT root, the head, and the leaf.
+        assert_eq!(progress.blocks, 5);
+        assert_eq!(visitor.cids.len(), 5);
+        assert_eq!(visitor.cids[0], root);
+        assert!(visitor.cids.contains(&head));
+        assert!(visitor.cids.contains(&leaf));
+        assert_eq!(visitor.reports.len(), 3);
+        assert_eq!(visitor.reports.last(), Some(&progress));
+
+        // A missing head fails the walk.
+        let missing = Cid::new_v1(DAG_CBOR, Blake2b256.digest(b"missing"));
+        tree.set_actor(
+            102,
+            ActorState::new(
+                *DUMMY_ACCOUNT_ACTOR_CODE_ID,
+                missing,
+                Default::default(),
+                0,
+                None,
+            ),
+        );
+        let root = tree.flush().unwrap();
+        assert!(tree.reachable_cids(&root, &mut Collect::default()).is_err());
+    }
+
+    #[test]
+    fn lookup_delegated() {
+        let store = MemoryBlockstore::default();
+        let mut tree = StateTree::new(&store, StateTreeVersion::V5).unwrap();
+        let init_state = store
+            .put_cbor(&init_actor::State::new_test(&store), Blake2b256)
+            .unwrap();
+        tree.set_actor(
+            INIT_ACTOR_ID,
+            ActorState::new(
+                *DUMMY_INIT_ACTOR_CODE_ID,
+                init_state,
+                Default::default(),
+                0,
+                None,
+            ),
+        );
+        let actor = |addr| {
+            ActorState::new(
+                *DUMMY_ACCOUNT_ACTOR_CODE_ID,
+                empty_cid(),
+                Default::default(),
+                0,
+                Some(addr),
+            )
+        };
+
+        let addr = Address::new_delegated(10, &[1; 20]).unwrap();
+        let id = tree.register_new_address(&addr).unwrap();
+        tree.set_actor(id, actor(addr));
+        let root = tree.flush().unwrap();
+
+        // Uncached addresses are resolved through the init actor.
+        let mut tree = StateTree::new_from_root(&store, &root).unwrap();
+        assert_eq!(tree.lookup_delegated(&addr).unwrap(), Some(id));
+        assert_eq!(tree.lookup_eth_address(&[1; 20]).unwrap(), Some(id));
+
+        // Masked ID addresses resolve to live actors.
+        let mut masked = [0; 20];
+        masked[0] = 0xff;
+        masked[12..].copy_from_slice(&id.to_be_bytes());
+        assert_eq!(tree.lookup_eth_address(&masked).unwrap(), Some(id));
+        masked[19] += 1;
+        assert_eq!(tree.lookup_eth_address(&masked).unwrap(), None);
+
+        // New actors are cached (and uncached on revert).
+        let other = Address::new_delegated(10, &[2; 20]).unwrap();
+        tree.begin_transaction();
+        tree.set_actor(500, actor(other));
+        assert_eq!(tree.lookup_delegated(&other).unwrap(), Some(500));
+        tree.end_transaction(true).unwrap();
+        assert_eq!(tree.lookup_delegated(&other).unwrap(), None);
+
+        // Deleted actors aren't resolved, whether cached or not.
+        tree.delete_actor(id);
+        assert_eq!(tree.lookup_delegated(&addr).unwrap(), None);
+        let root = tree.flush().unwrap();
+        let tree = StateTree::new_from_root(&store, &root).unwrap();
+        assert_eq!(tree.lookup_delegated(&addr).unwrap(), None);
+        assert_eq!(tree.lookup_id(&addr).unwrap(), Some(id));
+
+        assert!(tree.lookup_delegated(&Address::new_id(id)).is_err());
+
+        // The cache is bounded.
+        for i in 0..=DELEGATED_CACHE_MAX_LEN as u64 {
+            let addr = Address::new_delegated(10, &i.to_be_bytes()).unwrap();
+            assert_eq!(tree.lookup_delegated(&addr).unwrap(), None);
+        }
+        assert!(tree.delegated_cache.borrow().len() <= DELEGATED_CACHE_MAX_LEN);
+    }
+
+    #[test]
+    fn prove_actor() {
+        let actor = |seq| ActorState::new(empty_cid(), empty_cid(), Default::default(), seq, None);
+        let store = MemoryBlockstore::default();
+        let mut tree = StateTree::new(&store, StateTreeVersion::V5).unwrap();
+        for id in 100..1100 {
+            tree.set_actor(id, actor(id));
+        }
+
+        let (root, proof) = tree.prove_actor(&Address::new_id(500)).unwrap();
+        assert_eq!(root, tree.flush().unwrap());
+        // The state root and a single path through the HAMT.
+        assert!(proof.blocks.len() > 2 && proof.blocks.len() < 10);
+        assert_eq!(verify_actor_proof(&root, &proof).unwrap(), Some(actor(500)));
+
+        // Absence can be proven too.
+        let (_, absent) = tree.prove_actor(&Address::new_id(5000)).unwrap();
+        assert_eq!(verify_actor_proof(&root, &absent).unwrap(), None);
+
+        // Incomplete proofs, tampered proofs, and proofs against other roots are rejected.
+        let mut incomplete = proof.clone();
+        incomplete.blocks.pop();
+        assert!(verify_actor_proof(&root, &incomplete).is_err());
+
+        let mut tampered = proof.clone();
+        tampered.blocks.last_mut().unwrap().1.push(0);
+        assert!(verify_actor_proof(&root, &tampered).is_err());
+
+        tree.set_actor(500, actor(0));
+        let new_root = tree.flush().unwrap();
+        assert!(verify_actor_proof(&new_root, &proof).is_err());
+    }
+
+    #[test]
+    fn verify() {
+        let store = MemoryBlockstore::default();
+        let mut tree = StateTree::new(&store, StateTreeVersion::V5).unwrap();
+        let head = store.put_cbor(&"state", Blake2b256).unwrap();
+        for id in 100..1100 {
+            tree.set_actor(
+                id,
+                ActorState::new(
+                    *DUMMY_ACCOUNT_ACTOR_CODE_ID,
+                    head,
+                    Default::default(),
+                    0,
+                    None,
+                ),
+            );
+        }
+        let root = tree.flush().unwrap();
+        assert_eq!(tree.verify(&root).unwrap(), Vec::new());
+
+        // Missing actor states are all reported.
+        let missing = Cid::new_v1(DAG_CBOR, Blake2b256.digest(b"missing"));
+        for id in [200, 300] {
+            tree.set_actor(
+                id,
+                ActorState::new(
+                    *DUMMY_ACCOUNT_ACTOR_CODE_ID,
+                    missing,
+                    Default::default(),
+                    0,
+                    None,
+                ),
+            );
+        }
+        let root = tree.flush().unwrap();
+        let mut problems = tree.verify(&root).unwrap();
+        problems.sort_by_key(|p| format!("{:?}", p));
+        assert_eq!(
+            problems,
+            vec![
+                IntegrityProblem::MissingState {
+                    actor: 200,
+                    state: missing
+                },
+                IntegrityProblem::MissingState {
+                    actor: 300,
+                    state: missing
+                },
+            ]
+        );
+
+        // As are missing HAMT nodes.
+        let partial = MemoryBlockstore::default();
+        let partial_tree = StateTree::new(&partial, StateTreeVersion::V5).unwrap();
+        let state_root: StateRoot = store.get_cbor(&root).unwrap().unwrap();
+        for cid in [root, state_root.actors] {
+            partial
+                .put_keyed(&cid, &store.get(&cid).unwrap().unwrap())
+                .unwrap();
+        }
+        let problems = partial_tree.verify(&root).unwrap();
+        assert!(!problems.is_empty());
+        assert!(problems
+            .iter()
+            .all(|p| matches!(p, IntegrityProblem::Hamt(_))));
+    }
+
+    #[test]
+    fn view() {
+        /// A `Sync` blockstore.
+        #[derive(Default)]
+        struct SyncBlockstore(std::sync::Mutex<std::collections::HashMap<Cid, Vec<u8>>>);
+
+        impl Blockstore for SyncBlockstore {
+            fn get(&self, k: &Cid) -> anyhow::Result<Option<Vec<u8>>> {
+                Ok(self.0.lock().unwrap().get(k).cloned())
+            }
+
+            fn put_keyed(&self, k: &Cid, block: &[u8]) -> anyhow::Result<()> {
+                self.0.lock().unwrap().insert(*k, block.into());
+                Ok(())
+            }
+        }
+
+        let store = SyncBlockstore::default();
+        let mut tree = StateTree::new(&store, StateTreeVersion::V5).unwrap();
+        let init_state = store
+            .put_cbor(&init_actor::State::new_test(&store), Blake2b256)
+            .unwrap();
+        tree.set_actor(
+            INIT_ACTOR_ID,
+            ActorState::new(
+                *DUMMY_INIT_ACTOR_CODE_ID,
+                init_state,
+                Default::default(),
+                0,
+                None,
+            ),
+        );
+        let addr = Address::new_secp256k1(&[2; SECP_PUB_LEN]).unwrap();
+        let id = tree.register_new_address(&addr).unwrap();
+        let actor = |seq| {
+            ActorState::new(
+                *DUMMY_ACCOUNT_ACTOR_CODE_ID,
+                empty_cid(),
+                Default::default(),
+                seq,
+                None,
+            )
+        };
+        for id in 100..200 {
+            tree.set_actor(id, actor(id));
+        }
+        let root = tree.flush().unwrap();
+
+        let view = StateTreeView::new(&store, &root).unwrap();
+        std::thread::scope(|s| {
+            for _ in 0..4 {
+                s.spawn(|| {
+                    for id in 100..200 {
+                        assert_eq!(view.get_actor(id).unwrap(), Some(actor(id)));
+                    }
+                    assert_eq!(view.get_actor(200).unwrap(), None);
+                    assert_eq!(view.lookup_id(&addr).unwrap(), Some(id));
+                    assert_eq!(view.get_actor_by_address(&addr).unwrap(), Some(actor(id)));
+                });
+            }
+        });
+        // HAMTs are only loaded for concurrent queries.
+        assert!((1..=4).contains(&view.hamts.lock().unwrap().len()));
+
+        let mut count = 0;
+        view.for_each(|_, _| {
+            count += 1;
+            Ok(())
+        })
+        .unwrap();
+        assert_eq!(count, 101);
+
+        // The view doesn't see later changes.
+        tree.set_actor(200, actor(200));
+        tree.flush().unwrap();
+        assert_eq!(view.get_actor(200).unwrap(), None);
+    }
+
+    #[test]
+    fn actor_state_sizes() {
+        let store = MemoryBlockstore::default();
+        let mut tree = StateTree::new(&store, StateTreeVersion::V5).unwrap();
+        let small = store.put_cbor(&[0u8; 10], Blake2b256).unwrap();
+        let large = store
+            .put_cbor(&(small, vec![0u8; 100]), Blake2b256)
+            .unwrap();
+        let block_size = |cid| store.get(cid).unwrap().unwrap().len() as u64;
+        for (id, head) in [(100, small), (101, large), (102, small)] {
+            tree.set_actor(
+                id,
+                ActorState::new(
+                    *DUMMY_ACCOUNT_ACTOR_CODE_ID,
+                    head,
+                    Default::default(),
+                    0,
+                    None,
+                ),
+            );
+        }
+        let root = tree.flush().unwrap();
+
+        let small_size = ActorStateSize {
+            actor: 100,
+            blocks: 1,
+            bytes: block_size(&small),
+        };
+        let large_size = ActorStateSize {
+            actor: 101,
+            blocks: 2,
+            bytes: block_size(&large) + block_size(&small),
+        };
+        assert_eq!(
+            tree.actor_state_sizes(&root, None).unwrap(),
+            vec![
+                large_size,
+                small_size,
+                ActorStateSize {
+                    actor: 102,
+                    ..small_size
+                }
+            ]
+        );
+        assert_eq!(
+            tree.actor_state_sizes(&root, Some(2)).unwrap(),
+            vec![large_size, small_size]
+        );
+        assert_eq!(
+            tree.actor_state_sizes(&root, Some(1)).unwrap(),
+            vec![large_size]
+        );
+        assert!(tree.actor_state_sizes(&root, Some(0)).unwrap().is_empty());
+    }
 }
@@ -4,21 +4,22 @@ use anyhow::{anyhow, Context as _};
 use fvm_shared::{sys, ActorID};
 
 use super::Context;
-use crate::kernel::{ClassifyResult, Result};
-use crate::{syscall_error, Kernel};
+use crate::kernel::{ActorOps, ClassifyResult, GasOps, Result};
+use crate::syscall_error;
 
 pub fn resolve_address(
-    context: Context<'_, impl Kernel>,
+    context: Context<'_, impl ActorOps + GasOps>,
     addr_off: u32, // Address
     addr_len: u32,
 ) -> Result<u64> {
     let addr = context.memory.read_address(addr_off, addr_len)?;
+    context.kernel.charge_copy(addr_len as usize)?;
     let actor_id = context.kernel.resolve_address(&addr)?;
     Ok(actor_id)
 }
 
 pub fn lookup_delegated_address(
-    context: Context<'_, impl Kernel>,
+    context: Context<'_, impl ActorOps + GasOps>,
     actor_id: ActorID,
     obuf_off: u32,
     obuf_len: u32,
@@ -27,11 +28,11 @@ pub fn lookup_delegated_address(
     match context.kernel.lookup_delegated_address(actor_id)? {
         Some(address) => {
             let address = address.to_bytes();
-            obuf.get_mut(..address.len())
-                .ok_or_else(
-                    || syscall_error!(BufferTooSmall; "address output buffer is too small"),
-                )?
-                .copy_from_slice(&address);
+            let obuf = obuf.get_mut(..address.len()).ok_or_else(
+                || syscall_error!(BufferTooSmall; "address output buffer is too small"),
+            )?;
+            context.kernel.charge_copy(address.len())?;
+            obuf.copy_from_slice(&address);
             Ok(address.len() as u32)
         }
         None => Ok(0),
@@ -39,7 +40,7 @@ pub fn lookup_delegated_address(
 }
 
 pub fn get_actor_code_cid(
-    context: Context<'_, impl Kernel>,
+    context: Context<'_, impl ActorOps + GasOps>,
     actor_id: u64,
     obuf_off: u32, // Cid
     obuf_len: u32,
@@ -49,6 +50,7 @@ pub fn get_actor_code_cid(
 
     let typ = context.kernel.get_actor_code_cid(actor_id)?;
 
+    context.kernel.charge_copy(typ.encoded_len())?;
     context.memory.write_cid(&typ, obuf_off, obuf_len)
 }
 
@@ -57,7 +59,7 @@ pub fn get_actor_code_cid(
 /// The output buffer must be at least 21 bytes long, which is the length of a class 2 address
 /// (protocol-generated actor address).
 pub fn next_actor_address(
-    context: Context<'_, impl Kernel>,
+    context: Context<'_, impl ActorOps + GasOps>,
     obuf_off: u32, // Address (out)
     obuf_len: u32,
 ) -> Result<u32> {
@@ -86,12 +88,13 @@ pub fn next_actor_address(
         return Err(anyhow!("created {} byte actor address", len)).or_fatal();
     }
 
+    context.kernel.charge_copy(len)?;
     obuf[..len].copy_from_slice(bytes.as_slice());
     Ok(len as u32)
 }
 
 pub fn create_actor(
-    context: Context<'_, impl Kernel>,
+    context: Context<'_, impl ActorOps + GasOps>,
     actor_id: u64, // ID
     typ_off: u32,  // Cid
     delegated_addr_off: u32,
@@ -105,20 +108,24 @@ pub fn create_actor(
                 .read_address(delegated_addr_off, delegated_addr_len)
         })
         .transpose()?;
+    context
+        .kernel
+        .charge_copy(typ.encoded_len() + delegated_addr_len as usize)?;
 
     context.kernel.create_actor(typ, actor_id, addr)
 }
 
 pub fn get_builtin_actor_type(
-    context: Context<'_, impl Kernel>,
+    context: Context<'_, impl ActorOps + GasOps>,
     code_cid_off: u32, // Cid
 ) -> Result<i32> {
     let cid = context.memory.read_cid(code_cid_off)?;
+    context.kernel.charge_copy(cid.encoded_len())?;
     Ok(context.kernel.get_builtin_actor_type(&cid)? as i32)
 }
 
 pub fn get_code_cid_for_type(
-    context: Context<'_, impl Kernel>,
+    context: Context<'_, impl ActorOps + GasOps>,
     typ: i32,
     obuf_off: u32, // Cid
     obuf_len: u32,
@@ -126,19 +133,24 @@ pub fn get_code_cid_for_type(
     context.memory.check_bounds(obuf_off, obuf_len)?;
 
     let k = context.kernel.get_code_cid_for_type(typ as u32)?;
+    context.kernel.charge_copy(k.encoded_len())?;
     context.memory.write_cid(&k, obuf_off, obuf_len)
 }
 
 #[cfg(feature = "m2-native")]
 pub fn install_actor(
-    context: Context<'_, impl Kernel>,
+    context: Context<'_, impl ActorOps + GasOps>,
     typ_off: u32, // Cid
 ) -> Result<()> {
     let typ = context.memory.read_cid(typ_off)?;
+    context.kernel.charge_copy(typ.encoded_len())?;
     context.kernel.install_actor(typ)
 }
 
-pub fn balance_of(context: Context<'_, impl Kernel>, actor_id: u64) -> Result<sys::TokenAmount> {
+pub fn balance_of(
+    context: Context<'_, impl ActorOps + GasOps>,
+    actor_id: u64,
+) -> Result<sys::TokenAmount> {
     let balance = context.kernel.balance_of(actor_id)?;
     balance
         .try_into()
@@ -86,7 +86,7 @@ where
     }
 }
 
-fn memory_and_data<'a, K: Kernel>(
+pub(super) fn memory_and_data<'a, K: Kernel>(
     caller: &'a mut Caller<'_, InvocationData<K>>,
 ) -> (&'a mut Memory, &'a mut InvocationData<K>) {
     let memory_handle = caller.data().memory;
@@ -98,7 +98,7 @@ macro_rules! charge_syscall_gas {
     ($kernel:expr) => {
         let charge = $kernel.price_list().on_syscall();
         $kernel
-            .charge_gas(&charge.name, charge.compute_gas)
+            .charge_gas(charge.name, charge.compute_gas)
             .map_err(Abort::from_error_as_fatal)?;
     };
 }
@@ -120,13 +120,18 @@ macro_rules! impl_bind_syscalls {
                 name: &'static str,
                 syscall: Func,
             ) -> anyhow::Result<&mut Self> {
+                #[cfg(feature = "metrics")]
+                let invocations = crate::metrics::syscall_invocations(module, name);
                 if mem::size_of::<Ret::Value>() == 0 {
                     // If we're returning a zero-sized "value", we return no value therefore and expect no out pointer.
                     self.func_wrap(module, name, move |mut caller: Caller<'_, InvocationData<K>> $(, $t: $t)*| {
                         charge_for_exec(&mut caller)?;
+                        #[cfg(feature = "metrics")]
+                        invocations.inc();
 
                         let (mut memory, mut data) = memory_and_data(&mut caller);
                         charge_syscall_gas!(data.kernel);
+                        data.kernel.trace_syscall(module, name);
 
                         let ctx = Context{kernel: &mut data.kernel, memory: &mut memory};
                         let out = syscall(ctx $(, $t)*).into();
@@ -154,9 +159,12 @@ macro_rules! impl_bind_syscalls {
                     // If we're returning an actual value, we need to write it back into the wasm module's memory.
                     self.func_wrap(module, name, move |mut caller: Caller<'_, InvocationData<K>>, ret: u32 $(, $t: $t)*| {
                         charge_for_exec(&mut caller)?;
+                        #[cfg(feature = "metrics")]
+                        invocations.inc();
 
                         let (mut memory, mut data) = memory_and_data(&mut caller);
                         charge_syscall_gas!(data.kernel);
+                        data.kernel.trace_syscall(module, name);
 
                         // We need to check to make sure we can store the return value _before_ we do anything.
                         if (ret as u64) > (memory.len() as u64)
@@ -170,6 +178,9 @@ macro_rules! impl_bind_syscalls {
                         let result = match syscall(ctx $(, $t)*).into() {
                             Ok(Ok(value)) => {
                                 log::trace!("syscall {}::{}: ok", module, name);
+                                data.kernel
+                                    .charge_copy(mem::size_of::<Ret::Value>())
+                                    .map_err(Abort::from_error_as_fatal)?;
                                 unsafe {
                                     // We're writing into a user-specified pointer, so avoid
                                     // derefering it as it may not be aligned.
@@ -1,6 +1,7 @@
 // Copyright 2021-2023 Protocol Labs
 // SPDX-License-Identifier: Apache-2.0, MIT
 use std::io::Cursor;
+use std::mem;
 use std::ops::{Deref, DerefMut};
 use std::panic;
 
@@ -8,6 +9,7 @@ use cid::Cid;
 use fvm_ipld_encoding::from_slice;
 use fvm_shared::address::Address;
 use fvm_shared::error::ErrorNumber;
+use fvm_shared::sys::Extensible;
 use fvm_shared::MAX_CID_LEN;
 use serde::de::DeserializeOwned;
 
@@ -102,6 +104,28 @@ impl Memory {
         Ok(len as u32)
     }
 
+    /// Writes as much of an extensible value (see [`Extensible`]) as fits into the buffer,
+    /// returning the number of bytes written. Fails if the buffer can't hold the first version of
+    /// the value.
+    pub fn write_extensible<T: Extensible>(
+        &mut self,
+        value: &T,
+        offset: u32,
+        len: u32,
+    ) -> Result<u32> {
+        if (len as usize) < T::MIN_SIZE {
+            return Err(syscall_error!(BufferTooSmall; "output buffer is too small").into());
+        }
+        let size = (len as usize).min(mem::size_of::<T>());
+        let out = self.try_slice_mut(offset, size as u32)?;
+        // SAFETY: syscall structs are packed, without padding, so all their bytes are initialized.
+        let bytes = unsafe {
+            std::slice::from_raw_parts(value as *const T as *const u8, mem::size_of::<T>())
+        };
+        out.copy_from_slice(&bytes[..size]);
+        Ok(size as u32)
+    }
+
     pub fn read_address(&self, offset: u32, len: u32) -> Result<Address> {
         let bytes = self.try_slice(offset, len)?;
         Address::from_bytes(bytes).or_error(ErrorNumber::IllegalArgument)
@@ -4,7 +4,8 @@ use std::cmp;
 
 use anyhow::{anyhow, Context as _};
 use fvm_shared::crypto::signature::{
-    SignatureType, SECP_PUB_LEN, SECP_SIG_LEN, SECP_SIG_MESSAGE_HASH_SIZE,
+    secp_signature_from_rsv, SecpComponentError, SignatureType, SECP_PUB_LEN,
+    SECP_SIG_COMPONENT_LEN, SECP_SIG_LEN, SECP_SIG_MESSAGE_HASH_SIZE,
 };
 use fvm_shared::piece::PieceInfo;
 use fvm_shared::sector::{
@@ -12,11 +13,13 @@ use fvm_shared::sector::{
     WindowPoStVerifyInfo,
 };
 use fvm_shared::sys;
+use fvm_shared::version::NetworkVersion;
 use num_traits::FromPrimitive;
 
 use super::Context;
-use crate::kernel::{ClassifyResult, Result};
-use crate::{syscall_error, Kernel};
+use crate::kernel::{ClassifyResult, CryptoOps, GasOps, Kernel, Result};
+use crate::machine::Machine;
+use crate::syscall_error;
 
 /// Verifies that a signature is valid for an address and plaintext.
 ///
@@ -25,7 +28,7 @@ use crate::{syscall_error, Kernel};
 ///  - -1: verification failed.
 #[allow(clippy::too_many_arguments)]
 pub fn verify_signature(
-    context: Context<'_, impl Kernel>,
+    context: Context<'_, impl Kernel + CryptoOps>,
     sig_type: u32,
     sig_off: u32,
     sig_len: u32,
@@ -34,12 +37,18 @@ pub fn verify_signature(
     plaintext_off: u32,
     plaintext_len: u32,
 ) -> Result<i32> {
+    let network_version = context.kernel.machine().context().network_version;
     let sig_type = SignatureType::from_u32(sig_type)
+        // Delegated signatures are only supported from network version 21.
+        .filter(|t| *t != SignatureType::Delegated || network_version >= NetworkVersion::V21)
         .with_context(|| format!("unknown signature type {}", sig_type))
         .or_illegal_argument()?;
     let sig_bytes = context.memory.try_slice(sig_off, sig_len)?;
     let addr = context.memory.read_address(addr_off, addr_len)?;
     let plaintext = context.memory.try_slice(plaintext_off, plaintext_len)?;
+    context
+        .kernel
+        .charge_copy(sig_bytes.len() + addr_len as usize + plaintext.len())?;
 
     context
         .kernel
@@ -48,7 +57,7 @@ pub fn verify_signature(
 }
 
 pub fn recover_secp_public_key(
-    context: Context<'_, impl Kernel>,
+    context: Context<'_, impl CryptoOps + GasOps>,
     hash_off: u32,
     sig_off: u32,
 ) -> Result<[u8; SECP_PUB_LEN]> {
@@ -63,6 +72,62 @@ pub fn recover_secp_public_key(
         .try_slice(sig_off, SECP_SIG_LEN as u32)?
         .try_into()
         .or_illegal_argument()?;
+    context
+        .kernel
+        .charge_copy(SECP_SIG_MESSAGE_HASH_SIZE + SECP_SIG_LEN)?;
+
+    context
+        .kernel
+        .recover_secp_public_key(&hash_bytes, &sig_bytes)
+}
+
+/// Recovers the signer public key from a message hash and the `r`, `s`, and `v` components of a
+/// signature. `v` may be either the raw recovery id (0 or 1) or the Ethereum-style 27 or 28.
+///
+/// Errors with:
+///  - `IllegalArgument` if the recovery id is invalid, if `r` or `s` is out of range, or if the
+///    public key can't be recovered.
+///  - `Forbidden` if `s` is "high" (the signature is malleable) and malleable signatures were
+///    rejected via the passed flags.
+pub fn recover_secp_public_key_rsv(
+    context: Context<'_, impl CryptoOps + GasOps>,
+    hash_off: u32,
+    r_off: u32,
+    s_off: u32,
+    v: u32,
+    flags: u64,
+) -> Result<[u8; SECP_PUB_LEN]> {
+    let flags = sys::SecpRecoverFlags::from_bits(flags)
+        .ok_or_else(|| syscall_error!(IllegalArgument; "invalid secp recover flags {}", flags))?;
+
+    let hash_bytes = context
+        .memory
+        .try_slice(hash_off, SECP_SIG_MESSAGE_HASH_SIZE as u32)?
+        .try_into()
+        .or_illegal_argument()?;
+    let r = context
+        .memory
+        .try_slice(r_off, SECP_SIG_COMPONENT_LEN as u32)?
+        .try_into()
+        .or_illegal_argument()?;
+    let s = context
+        .memory
+        .try_slice(s_off, SECP_SIG_COMPONENT_LEN as u32)?
+        .try_into()
+        .or_illegal_argument()?;
+    let v = u8::try_from(v)
+        .map_err(|_| syscall_error!(IllegalArgument; "invalid recovery id {}", v))?;
+    context
+        .kernel
+        .charge_copy(SECP_SIG_MESSAGE_HASH_SIZE + 2 * SECP_SIG_COMPONENT_LEN)?;
+
+    let sig_bytes =
+        secp_signature_from_rsv(r, s, v, !flags.reject_malleable()).map_err(|e| match e {
+            SecpComponentError::Malleable => syscall_error!(Forbidden; "{}", e),
+            SecpComponentError::InvalidRecoveryId(_) | SecpComponentError::OutOfRange => {
+                syscall_error!(IllegalArgument; "{}", e)
+            }
+        })?;
 
     context
         .kernel
@@ -72,7 +137,7 @@ pub fn recover_secp_public_key(
 /// Hashes input data using the specified hash function, writing the digest into the provided
 /// buffer.
 pub fn hash(
-    context: Context<'_, impl Kernel>,
+    context: Context<'_, impl CryptoOps + GasOps>,
     hash_code: u64,
     data_off: u32, // input
     data_len: u32,
@@ -85,12 +150,14 @@ pub fn hash(
     // Then hash.
     let digest = {
         let data = context.memory.try_slice(data_off, data_len)?;
+        context.kernel.charge_copy(data.len())?;
         context.kernel.hash(hash_code, data)?
     };
 
     // Then copy the result.
     let digest_out = context.memory.try_slice_mut(digest_off, digest_len)?;
     let length = cmp::min(digest_out.len(), digest.digest().len());
+    context.kernel.charge_copy(length)?;
     digest_out[..length].copy_from_slice(&digest.digest()[..length]);
     Ok(length as u32)
 }
@@ -100,7 +167,7 @@ pub fn hash(
 ///
 /// Writes the CID in the provided output buffer.
 pub fn compute_unsealed_sector_cid(
-    context: Context<'_, impl Kernel>,
+    context: Context<'_, impl CryptoOps + GasOps>,
     proof_type: i64, // RegisteredSealProof,
     pieces_off: u32, // [PieceInfo]
     pieces_len: u32,
@@ -114,6 +181,7 @@ pub fn compute_unsealed_sector_cid(
     }
     let pieces: Vec<PieceInfo> = context.memory.read_cbor(pieces_off, pieces_len)?;
     context.memory.check_bounds(cid_off, cid_len)?;
+    context.kernel.charge_copy(pieces_len as usize)?;
 
     // Compute
     let cid = context
@@ -121,6 +189,7 @@ pub fn compute_unsealed_sector_cid(
         .compute_unsealed_sector_cid(typ, pieces.as_slice())?;
 
     // REturn
+    context.kernel.charge_copy(cid.encoded_len())?;
     context.memory.write_cid(&cid, cid_off, cid_len)
 }
 
@@ -130,13 +199,14 @@ pub fn compute_unsealed_sector_cid(
 ///  - 0: verification ok.
 ///  - -1: verification failed.
 pub fn verify_post(
-    context: Context<'_, impl Kernel>,
+    context: Context<'_, impl CryptoOps + GasOps>,
     info_off: u32, // WindowPoStVerifyInfo,
     info_len: u32,
 ) -> Result<i32> {
     let info = context
         .memory
         .read_cbor::<WindowPoStVerifyInfo>(info_off, info_len)?;
+    context.kernel.charge_copy(info_len as usize)?;
     context
         .kernel
         .verify_post(&info)
@@ -154,7 +224,7 @@ pub fn verify_post(
 /// blocks in the parent of h2 (i.e. h2's grandparent).
 ///
 pub fn verify_consensus_fault(
-    context: Context<'_, impl Kernel>,
+    context: Context<'_, impl CryptoOps + GasOps>,
     h1_off: u32,
     h1_len: u32,
     h2_off: u32,
@@ -165,6 +235,9 @@ pub fn verify_consensus_fault(
     let h1 = context.memory.try_slice(h1_off, h1_len)?;
     let h2 = context.memory.try_slice(h2_off, h2_len)?;
     let extra = context.memory.try_slice(extra_off, extra_len)?;
+    context
+        .kernel
+        .charge_copy(h1.len() + h2.len() + extra.len())?;
 
     let ret = context.kernel.verify_consensus_fault(h1, h2, extra)?;
 
@@ -192,13 +265,14 @@ pub fn verify_consensus_fault(
 ///  - 0: verification ok.
 ///  - -1: verification failed.
 pub fn verify_aggregate_seals(
-    context: Context<'_, impl Kernel>,
+    context: Context<'_, impl CryptoOps + GasOps>,
     agg_off: u32, // AggregateSealVerifyProofAndInfos
     agg_len: u32,
 ) -> Result<i32> {
     let info = context
         .memory
         .read_cbor::<AggregateSealVerifyProofAndInfos>(agg_off, agg_len)?;
+    context.kernel.charge_copy(agg_len as usize)?;
     context
         .kernel
         .verify_aggregate_seals(&info)
@@ -209,13 +283,14 @@ pub fn verify_aggregate_seals(
 ///  - 0: verification ok.
 ///  - -1: verification failed.
 pub fn verify_replica_update(
-    context: Context<'_, impl Kernel>,
+    context: Context<'_, impl CryptoOps + GasOps>,
     rep_off: u32, // ReplicaUpdateInfo
     rep_len: u32,
 ) -> Result<i32> {
     let info = context
         .memory
         .read_cbor::<ReplicaUpdateInfo>(rep_off, rep_len)?;
+    context.kernel.charge_copy(rep_len as usize)?;
     context
         .kernel
         .verify_replica_update(&info)
@@ -227,7 +302,7 @@ pub fn verify_replica_update(
 /// When successful, this method will write a single byte back into the array at `result_off` for
 /// each result: 0 for failed, 1 for success.
 pub fn batch_verify_seals(
-    context: Context<'_, impl Kernel>,
+    context: Context<'_, impl CryptoOps + GasOps>,
     batch_off: u32,
     batch_len: u32,
     result_off: u32,
@@ -239,6 +314,7 @@ pub fn batch_verify_seals(
     let output = context
         .memory
         .try_slice_mut(result_off, batch.len() as u32)?;
+    context.kernel.charge_copy(batch_len as usize)?;
 
     // Execute.
     let result = context.kernel.batch_verify_seals(&batch)?;
@@ -254,6 +330,7 @@ pub fn batch_verify_seals(
     }
 
     // Return.
+    context.kernel.charge_copy(output.len())?;
     unsafe {
         output.copy_from_slice(&*(&*result as *const [bool] as *const [u8]));
     }
@@ -1,22 +1,22 @@
 // Copyright 2021-2023 Protocol Labs
 // SPDX-License-Identifier: Apache-2.0, MIT
-use crate::kernel::{ClassifyResult, Result};
+use crate::kernel::{ClassifyResult, DebugOps, GasOps, Result};
 use crate::syscalls::context::Context;
-use crate::Kernel;
 
-pub fn log(context: Context<'_, impl Kernel>, msg_off: u32, msg_len: u32) -> Result<()> {
+pub fn log(context: Context<'_, impl DebugOps + GasOps>, msg_off: u32, msg_len: u32) -> Result<()> {
     // No-op if disabled.
     if !context.kernel.debug_enabled() {
         return Ok(());
     }
 
     let msg = context.memory.try_slice(msg_off, msg_len)?;
+    context.kernel.charge_copy(msg.len())?;
     let msg = String::from_utf8(msg.to_owned()).or_illegal_argument()?;
     context.kernel.log(msg);
     Ok(())
 }
 
-pub fn enabled(context: Context<'_, impl Kernel>) -> Result<i32> {
+pub fn enabled(context: Context<'_, impl DebugOps + GasOps>) -> Result<i32> {
     Ok(if context.kernel.debug_enabled() {
         0
     } else {
@@ -25,7 +25,7 @@ pub fn enabled(context: Context<'_, impl Kernel>) -> Result<i32> {
 }
 
 pub fn store_artifact(
-    context: Context<'_, impl Kernel>,
+    context: Context<'_, impl DebugOps + GasOps>,
     name_off: u32,
     name_len: u32,
     data_off: u32,
@@ -38,6 +38,7 @@ pub fn store_artifact(
 
     let data = context.memory.try_slice(data_off, data_len)?;
     let name = context.memory.try_slice(name_off, name_len)?;
+    context.kernel.charge_copy(data.len() + name.len())?;
     let name =
         std::str::from_utf8(name).or_error(fvm_shared::error::ErrorNumber::IllegalArgument)?;
 
@@ -6,7 +6,9 @@ use fvm_shared::error::ExitCode;
 use wasmtime::Trap;
 
 use crate::call_manager::NO_DATA_BLOCK_ID;
+use crate::engine::ExecutionBudgetExceeded;
 use crate::kernel::{BlockId, ExecutionError};
+use crate::machine::{DefaultExitCodePolicy, ExitCodePolicy};
 
 /// Represents an actor "abort".
 #[derive(Debug, thiserror::Error)]
@@ -48,11 +50,10 @@ impl Abort {
             ExecutionError::Syscall(e) => Abort::Fatal(anyhow!("unexpected syscall error: {}", e)),
         }
     }
-}
 
-/// Unwraps a trap error from an actor into an "abort".
-impl From<anyhow::Error> for Abort {
-    fn from(e: anyhow::Error) -> Self {
+    /// Unwraps a trap error from an actor into an "abort", exiting with the policy's
+    /// [`illegal_instruction`](ExitCodePolicy::illegal_instruction) exit code if the actor trapped.
+    pub fn from_trap(e: anyhow::Error, policy: &dyn ExitCodePolicy) -> Self {
         if let Some(trap) = e.downcast_ref::<Trap>() {
             return match trap {
                 | Trap::MemoryOutOfBounds
@@ -71,10 +72,11 @@ impl From<anyhow::Error> for Abort {
 
                 // I think this is fatal? But I'm not sure.
                 | Trap::StackOverflow => Abort::Exit(
-                    ExitCode::SYS_ILLEGAL_INSTRUCTION,
+                    policy.illegal_instruction(),
                     trap.to_string(),
                     NO_DATA_BLOCK_ID,
                 ),
+                Trap::Interrupt => Abort::Fatal(ExecutionBudgetExceeded.into()),
                 _ => Abort::Fatal(anyhow!("unexpected wasmtime trap: {}", trap)),
             };
         };
@@ -84,3 +86,11 @@ impl From<anyhow::Error> for Abort {
         }
     }
 }
+
+/// Unwraps a trap error from an actor into an "abort", with the [`DefaultExitCodePolicy`]. See
+/// [`Abort::from_trap`].
+impl From<anyhow::Error> for Abort {
+    fn from(e: anyhow::Error) -> Self {
+        Abort::from_trap(e, &DefaultExitCodePolicy)
+    }
+}
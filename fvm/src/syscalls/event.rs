@@ -2,8 +2,7 @@
 // SPDX-License-Identifier: Apache-2.0, MIT
 
 use super::Context;
-use crate::kernel::Result;
-use crate::Kernel;
+use crate::kernel::{EventOps, Result};
 
 /// Emits an actor event. It takes an DAG-CBOR encoded ActorEvent that has been
 /// written to Wasm memory, as an offset and length tuple.
@@ -14,10 +13,11 @@ use crate::Kernel;
 /// Calling this syscall may immediately halt execution with an out of gas error,
 /// if such condition arises.
 pub fn emit_event(
-    context: Context<'_, impl Kernel>,
+    context: Context<'_, impl EventOps>,
     event_off: u32, // ActorEvent
     event_len: u32,
 ) -> Result<()> {
+    // The copy is priced by the event validation charge.
     let raw = context.memory.try_slice(event_off, event_len)?;
     context.kernel.emit_event(raw)
 }
@@ -3,18 +3,24 @@
 use std::str;
 
 use super::Context;
-use crate::gas::Gas;
-use crate::kernel::{ClassifyResult, Result};
-use crate::Kernel;
+use crate::gas::{Gas, GasChargeName};
+use crate::kernel::{ClassifyResult, GasOps, Result};
 
 pub fn charge_gas(
-    context: Context<'_, impl Kernel>,
+    context: Context<'_, impl GasOps>,
     name_off: u32,
     name_len: u32,
     compute: u64,
 ) -> Result<()> {
     let name =
         str::from_utf8(context.memory.try_slice(name_off, name_len)?).or_illegal_argument()?;
+    context.kernel.charge_copy(name.len())?;
+    // Only copy the name out of the actor's memory if the charge is recorded.
+    let name = if context.kernel.records_gas_charges() {
+        GasChargeName::from(name.to_owned())
+    } else {
+        GasChargeName::OnActorCharge
+    };
     // Gas charges from actors are always in full gas units. We use milligas internally, so convert here.
     context
         .kernel
@@ -22,6 +28,6 @@ pub fn charge_gas(
         .map(|_| ())
 }
 
-pub fn available(context: Context<'_, impl Kernel>) -> Result<u64> {
+pub fn available(context: Context<'_, impl GasOps>) -> Result<u64> {
     Ok(context.kernel.gas_available().round_down())
 }
@@ -3,11 +3,14 @@
 use fvm_shared::sys;
 
 use super::Context;
-use crate::kernel::Result;
-use crate::Kernel;
+use crate::kernel::{GasOps, IpldBlockOps, Result};
 
-pub fn block_open(context: Context<'_, impl Kernel>, cid: u32) -> Result<sys::out::ipld::IpldOpen> {
+pub fn block_open(
+    context: Context<'_, impl IpldBlockOps + GasOps>,
+    cid: u32,
+) -> Result<sys::out::ipld::IpldOpen> {
     let cid = context.memory.read_cid(cid)?;
+    context.kernel.charge_copy(cid.encoded_len())?;
     let (id, stat) = context.kernel.block_open(&cid)?;
     Ok(sys::out::ipld::IpldOpen {
         id,
@@ -17,17 +20,18 @@ pub fn block_open(context: Context<'_, impl Kernel>, cid: u32) -> Result<sys::ou
 }
 
 pub fn block_create(
-    context: Context<'_, impl Kernel>,
+    context: Context<'_, impl IpldBlockOps + GasOps>,
     codec: u64,
     data_off: u32,
     data_len: u32,
 ) -> Result<u32> {
+    // The copy is priced by the block creation charge.
     let data = context.memory.try_slice(data_off, data_len)?;
     context.kernel.block_create(codec, data)
 }
 
 pub fn block_link(
-    context: Context<'_, impl Kernel>,
+    context: Context<'_, impl IpldBlockOps + GasOps>,
     id: u32,
     hash_fun: u64,
     hash_len: u32,
@@ -41,21 +45,80 @@ pub fn block_link(
     let cid = context.kernel.block_link(id, hash_fun, hash_len)?;
 
     // Return
+    context.kernel.charge_copy(cid.encoded_len())?;
     context.memory.write_cid(&cid, cid_off, cid_len)
 }
 
 pub fn block_read(
-    context: Context<'_, impl Kernel>,
+    context: Context<'_, impl IpldBlockOps + GasOps>,
     id: u32,
     offset: u32,
     obuf_off: u32,
     obuf_len: u32,
 ) -> Result<i32> {
+    // The copy is priced by the block read charge.
     let data = context.memory.try_slice_mut(obuf_off, obuf_len)?;
     context.kernel.block_read(id, offset, data)
 }
 
-pub fn block_stat(context: Context<'_, impl Kernel>, id: u32) -> Result<sys::out::ipld::IpldStat> {
+pub fn block_stat_v2(
+    context: Context<'_, impl IpldBlockOps + GasOps>,
+    id: u32,
+) -> Result<sys::out::ipld::IpldStatV2> {
+    let stat = context.kernel.block_stat(id)?;
+    let links = context.kernel.block_links(id)?;
+    Ok(sys::out::ipld::IpldStatV2 {
+        codec: stat.codec,
+        size: stat.size,
+        links: links.len() as u32,
+        reachable: stat.reachable as u32,
+    })
+}
+
+pub fn block_links(
+    context: Context<'_, impl IpldBlockOps + GasOps>,
+    id: u32,
+    start: u32,
+    obuf_off: u32,
+    obuf_len: u32,
+) -> Result<sys::out::ipld::IpldLinks> {
+    // Check arguments first.
+    context.memory.check_bounds(obuf_off, obuf_len)?;
+
+    let links = context.kernel.block_links(id)?;
+
+    // Find how many links fit, starting at `start`.
+    let mut count = 0;
+    let mut length = 0;
+    for cid in links.iter().skip(start as usize) {
+        let len = cid.encoded_len() as u32;
+        if length + len > obuf_len {
+            break;
+        }
+        length += len;
+        count += 1;
+    }
+
+    // Then write them.
+    context.kernel.charge_copy(length as usize)?;
+    let mut offset = obuf_off;
+    for cid in links.iter().skip(start as usize).take(count as usize) {
+        offset += context
+            .memory
+            .write_cid(cid, offset, obuf_off + length - offset)?;
+    }
+
+    Ok(sys::out::ipld::IpldLinks {
+        total: links.len() as u32,
+        count,
+        length,
+    })
+}
+
+pub fn block_stat(
+    context: Context<'_, impl IpldBlockOps + GasOps>,
+    id: u32,
+) -> Result<sys::out::ipld::IpldStat> {
     context
         .kernel
         .block_stat(id)
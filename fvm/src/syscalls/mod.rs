@@ -5,8 +5,12 @@ use num_traits::Zero;
 use wasmtime::{AsContextMut, ExternType, Global, Linker, Memory, Module, Val};
 
 use crate::call_manager::backtrace;
-use crate::gas::{Gas, GasInstant, GasTimer};
+use crate::gas::{Gas, GasChargeName, GasInstant, GasTimer};
 use crate::kernel::ExecutionError;
+use crate::kernel::{
+    ActorOps, CircSupplyOps, CryptoOps, DebugOps, EventOps, IpldBlockOps, MessageOps, NetworkOps,
+    RandomnessOps, SelfOps,
+};
 use crate::machine::limiter::MemoryLimiter;
 use crate::Kernel;
 
@@ -24,9 +28,14 @@ mod network;
 mod rand;
 mod send;
 mod sself;
+mod typed;
 mod vm;
 
 pub(self) use context::Context;
+pub use context::Memory;
+pub use typed::{
+    bind_typed_syscall, bind_typed_syscall_with_custom_errors, Cbor, SyscallParam, SyscallParams,
+};
 
 /// Invocation data attached to a wasm "store" and available to the syscall binding.
 pub struct InvocationData<K> {
@@ -138,7 +147,7 @@ pub fn charge_for_exec<K: Kernel>(
 
     let t = data
         .kernel
-        .charge_gas("wasm_exec", exec_gas_charge)
+        .charge_gas(GasChargeName::WasmExec, exec_gas_charge)
         .map_err(Abort::from_error_as_fatal)?;
 
     // It should be okay to record time associated with Wasm execution because `charge_for_exec` is
@@ -152,7 +161,7 @@ pub fn charge_for_exec<K: Kernel>(
         // could perform stomething like a multi-variate linear regression to see if the amount of
         // memory explains any of the exectuion time.
         data.kernel
-            .charge_gas("wasm_memory_grow", memory_gas_charge)
+            .charge_memory(GasChargeName::WasmMemoryGrow, memory_gas_charge)
             .map_err(Abort::from_error_as_fatal)?;
     }
 
@@ -178,10 +187,12 @@ pub fn charge_for_init<K: Kernel>(
 
     if let Some(min_table_elements) = min_table_elements(module) {
         let table_gas = data.kernel.price_list().init_table_gas(min_table_elements);
-        data.kernel.charge_gas("wasm_table_init", table_gas)?;
+        data.kernel
+            .charge_gas(GasChargeName::WasmTableInit, table_gas)?;
     }
 
-    data.kernel.charge_gas("wasm_memory_init", memory_gas)
+    data.kernel
+        .charge_memory(GasChargeName::WasmMemoryInit, memory_gas)
 }
 
 /// Record the time it took to initialize a module.
@@ -233,33 +244,95 @@ fn min_table_elements(module: &Module) -> Option<u32> {
 use self::bind::BindSyscall;
 use self::error::Abort;
 
-// Binds the syscall handlers so they can handle invocations
-// from the actor code.
-pub fn bind_syscalls(
-    linker: &mut Linker<InvocationData<impl Kernel + 'static>>,
+/// Binds the full default syscall set so it can handle invocations from the actor code.
+///
+/// This requires a kernel implementing _all_ capability traits. Kernels that only support a
+/// subset of the capabilities should call the relevant `bind_*_syscalls` functions instead.
+pub fn bind_syscalls<K>(linker: &mut Linker<InvocationData<K>>) -> anyhow::Result<()>
+where
+    K: Kernel
+        + ActorOps
+        + CircSupplyOps
+        + CryptoOps
+        + DebugOps
+        + EventOps
+        + IpldBlockOps
+        + MessageOps
+        + NetworkOps
+        + RandomnessOps
+        + SelfOps,
+{
+    bind_vm_syscalls(linker)?;
+    bind_network_syscalls(linker)?;
+    bind_ipld_syscalls(linker)?;
+    bind_self_syscalls(linker)?;
+    bind_actor_syscalls(linker)?;
+    bind_crypto_syscalls(linker)?;
+    bind_event_syscalls(linker)?;
+    bind_rand_syscalls(linker)?;
+    bind_gas_syscalls(linker)?;
+    bind_send_syscalls(linker)?;
+    bind_debug_syscalls(linker)?;
+
+    Ok(())
+}
+
+/// Binds the "vm" syscalls (exit, message context, call depth).
+pub fn bind_vm_syscalls(
+    linker: &mut Linker<InvocationData<impl Kernel + MessageOps>>,
 ) -> anyhow::Result<()> {
     linker.bind("vm", "exit", vm::exit)?;
     linker.bind("vm", "message_context", vm::message_context)?;
+    linker.bind("vm", "message_context_ext", vm::message_context_ext)?;
+    linker.bind("vm", "call_depth", vm::call_depth)?;
+    Ok(())
+}
 
+/// Binds the "network" syscalls.
+pub fn bind_network_syscalls(
+    linker: &mut Linker<InvocationData<impl Kernel + NetworkOps + CircSupplyOps>>,
+) -> anyhow::Result<()> {
     linker.bind(
         "network",
         "total_fil_circ_supply",
         network::total_fil_circ_supply,
     )?;
     linker.bind("network", "context", network::context)?;
+    linker.bind("network", "context_v2", network::context_v2)?;
+    linker.bind("network", "context_ext", network::context_ext)?;
     linker.bind("network", "tipset_cid", network::tipset_cid)?;
+    Ok(())
+}
 
+/// Binds the "ipld" (block) syscalls.
+pub fn bind_ipld_syscalls(
+    linker: &mut Linker<InvocationData<impl Kernel + IpldBlockOps>>,
+) -> anyhow::Result<()> {
     linker.bind("ipld", "block_open", ipld::block_open)?;
     linker.bind("ipld", "block_create", ipld::block_create)?;
     linker.bind("ipld", "block_read", ipld::block_read)?;
     linker.bind("ipld", "block_stat", ipld::block_stat)?;
+    linker.bind("ipld", "block_stat_v2", ipld::block_stat_v2)?;
     linker.bind("ipld", "block_link", ipld::block_link)?;
+    linker.bind("ipld", "block_links", ipld::block_links)?;
+    Ok(())
+}
 
+/// Binds the "self" syscalls (state root, balance, self-destruct).
+pub fn bind_self_syscalls(
+    linker: &mut Linker<InvocationData<impl Kernel + SelfOps>>,
+) -> anyhow::Result<()> {
     linker.bind("self", "root", sself::root)?;
     linker.bind("self", "set_root", sself::set_root)?;
     linker.bind("self", "current_balance", sself::current_balance)?;
     linker.bind("self", "self_destruct", sself::self_destruct)?;
+    Ok(())
+}
 
+/// Binds the "actor" syscalls.
+pub fn bind_actor_syscalls(
+    linker: &mut Linker<InvocationData<impl Kernel + ActorOps>>,
+) -> anyhow::Result<()> {
     linker.bind("actor", "resolve_address", actor::resolve_address)?;
     linker.bind(
         "actor",
@@ -285,12 +358,24 @@ pub fn bind_syscalls(
     #[cfg(feature = "m2-native")]
     linker.bind("actor", "install_actor", actor::install_actor)?;
 
+    Ok(())
+}
+
+/// Binds the "crypto" syscalls.
+pub fn bind_crypto_syscalls(
+    linker: &mut Linker<InvocationData<impl Kernel + CryptoOps>>,
+) -> anyhow::Result<()> {
     linker.bind("crypto", "verify_signature", crypto::verify_signature)?;
     linker.bind(
         "crypto",
         "recover_secp_public_key",
         crypto::recover_secp_public_key,
     )?;
+    linker.bind(
+        "crypto",
+        "recover_secp_public_key_rsv",
+        crypto::recover_secp_public_key_rsv,
+    )?;
     linker.bind("crypto", "hash", crypto::hash)?;
     linker.bind("crypto", "verify_post", crypto::verify_post)?;
     linker.bind(
@@ -314,21 +399,47 @@ pub fn bind_syscalls(
         crypto::verify_replica_update,
     )?;
     linker.bind("crypto", "batch_verify_seals", crypto::batch_verify_seals)?;
+    Ok(())
+}
 
+/// Binds the "event" syscalls.
+pub fn bind_event_syscalls(
+    linker: &mut Linker<InvocationData<impl Kernel + EventOps>>,
+) -> anyhow::Result<()> {
     linker.bind("event", "emit_event", event::emit_event)?;
+    Ok(())
+}
 
+/// Binds the "rand" syscalls.
+pub fn bind_rand_syscalls(
+    linker: &mut Linker<InvocationData<impl Kernel + RandomnessOps>>,
+) -> anyhow::Result<()> {
     linker.bind("rand", "get_chain_randomness", rand::get_chain_randomness)?;
     linker.bind("rand", "get_beacon_randomness", rand::get_beacon_randomness)?;
+    linker.bind("rand", "verify_beacon_entry", rand::verify_beacon_entry)?;
+    Ok(())
+}
 
+/// Binds the "gas" syscalls. These only depend on [`GasOps`], which every kernel implements.
+pub fn bind_gas_syscalls(linker: &mut Linker<InvocationData<impl Kernel>>) -> anyhow::Result<()> {
     linker.bind("gas", "charge", gas::charge_gas)?;
     linker.bind("gas", "available", gas::available)?;
+    Ok(())
+}
 
+/// Binds the "send" syscall.
+pub fn bind_send_syscalls(linker: &mut Linker<InvocationData<impl Kernel>>) -> anyhow::Result<()> {
     // Ok, this singled-out syscall should probably be in another category.
     linker.bind("send", "send", send::send)?;
+    Ok(())
+}
 
+/// Binds the "debug" syscalls.
+pub fn bind_debug_syscalls(
+    linker: &mut Linker<InvocationData<impl Kernel + DebugOps>>,
+) -> anyhow::Result<()> {
     linker.bind("debug", "log", debug::log)?;
     linker.bind("debug", "enabled", debug::enabled)?;
     linker.bind("debug", "store_artifact", debug::store_artifact)?;
-
     Ok(())
 }
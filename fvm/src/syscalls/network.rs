@@ -2,13 +2,13 @@
 // SPDX-License-Identifier: Apache-2.0, MIT
 use anyhow::Context as _;
 use fvm_shared::sys;
-use fvm_shared::sys::out::network::NetworkContext;
+use fvm_shared::sys::out::network::{NetworkContext, NetworkContextV2};
 
 use super::Context;
-use crate::kernel::{ClassifyResult, Kernel, Result};
+use crate::kernel::{CircSupplyOps, ClassifyResult, GasOps, NetworkOps, Result};
 
 /// Returns the network circ supply split as two u64 ordered in little endian.
-pub fn total_fil_circ_supply(context: Context<'_, impl Kernel>) -> Result<sys::TokenAmount> {
+pub fn total_fil_circ_supply(context: Context<'_, impl CircSupplyOps>) -> Result<sys::TokenAmount> {
     context
         .kernel
         .total_fil_circ_supply()?
@@ -17,12 +17,34 @@ pub fn total_fil_circ_supply(context: Context<'_, impl Kernel>) -> Result<sys::T
         .or_fatal()
 }
 
-pub fn context(context: Context<'_, impl Kernel>) -> crate::kernel::Result<NetworkContext> {
+pub fn context(context: Context<'_, impl NetworkOps>) -> crate::kernel::Result<NetworkContext> {
+    context.kernel.network_context().map(Into::into)
+}
+
+/// Returns the extended network context, including the genesis timestamp and block gas limit.
+pub fn context_v2(
+    context: Context<'_, impl NetworkOps>,
+) -> crate::kernel::Result<NetworkContextV2> {
     context.kernel.network_context()
 }
 
+/// Writes as much of the extended network context as fits into the output buffer, returning the
+/// number of bytes written (see [`Extensible`](fvm_shared::sys::Extensible)).
+pub fn context_ext(
+    context: Context<'_, impl NetworkOps + GasOps>,
+    obuf_off: u32,
+    obuf_len: u32,
+) -> Result<u32> {
+    context.memory.check_bounds(obuf_off, obuf_len)?;
+
+    let ctx = context.kernel.network_context()?;
+    let written = context.memory.write_extensible(&ctx, obuf_off, obuf_len)?;
+    context.kernel.charge_copy(written as usize)?;
+    Ok(written)
+}
+
 pub fn tipset_cid(
-    context: Context<'_, impl Kernel>,
+    context: Context<'_, impl NetworkOps + GasOps>,
     epoch: i64,
     obuf_off: u32,
     obuf_len: u32,
@@ -31,5 +53,6 @@ pub fn tipset_cid(
     context.memory.check_bounds(obuf_off, obuf_len)?;
 
     let cid = context.kernel.tipset_cid(epoch)?;
+    context.kernel.charge_copy(cid.encoded_len())?;
     context.memory.write_cid(&cid, obuf_off, obuf_len)
 }
@@ -3,21 +3,21 @@
 use fvm_shared::randomness::RANDOMNESS_LENGTH;
 
 use super::Context;
-use crate::kernel::Result;
-use crate::Kernel;
+use crate::kernel::{GasOps, RandomnessOps, Result};
 
 /// Gets 32 bytes of randomness from the ticket chain.
 /// The supplied output buffer must have at least 32 bytes of capacity.
 /// If this syscall succeeds, exactly 32 bytes will be written starting at the
 /// supplied offset.
 pub fn get_chain_randomness(
-    context: Context<'_, impl Kernel>,
+    context: Context<'_, impl RandomnessOps + GasOps>,
     pers: i64,  // DomainSeparationTag
     round: i64, // ChainEpoch
     entropy_off: u32,
     entropy_len: u32,
 ) -> Result<[u8; RANDOMNESS_LENGTH]> {
     let entropy = context.memory.try_slice(entropy_off, entropy_len)?;
+    context.kernel.charge_copy(entropy.len())?;
     context
         .kernel
         .get_randomness_from_tickets(pers, round, entropy)
@@ -28,14 +28,40 @@ pub fn get_chain_randomness(
 /// If this syscall succeeds, exactly 32 bytes will be written starting at the
 /// supplied offset.
 pub fn get_beacon_randomness(
-    context: Context<'_, impl Kernel>,
+    context: Context<'_, impl RandomnessOps + GasOps>,
     pers: i64,  // DomainSeparationTag
     round: i64, // ChainEpoch
     entropy_off: u32,
     entropy_len: u32,
 ) -> Result<[u8; RANDOMNESS_LENGTH]> {
     let entropy = context.memory.try_slice(entropy_off, entropy_len)?;
+    context.kernel.charge_copy(entropy.len())?;
     context
         .kernel
         .get_randomness_from_beacon(pers, round, entropy)
 }
+
+/// Verifies a beacon entry (round, previous signature, and signature) against the network's beacon
+/// chain.
+///
+/// The return i32 indicates the status code of the verification:
+///  - 0: verification ok.
+///  - -1: verification failed.
+pub fn verify_beacon_entry(
+    context: Context<'_, impl RandomnessOps + GasOps>,
+    round: u64,
+    prev_sig_off: u32,
+    prev_sig_len: u32,
+    sig_off: u32,
+    sig_len: u32,
+) -> Result<i32> {
+    let prev_signature = context.memory.try_slice(prev_sig_off, prev_sig_len)?;
+    let signature = context.memory.try_slice(sig_off, sig_len)?;
+    context
+        .kernel
+        .charge_copy(prev_signature.len() + signature.len())?;
+    context
+        .kernel
+        .verify_beacon_entry(round, prev_signature, signature)
+        .map(|v| if v { 0 } else { -1 })
+}
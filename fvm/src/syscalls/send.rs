@@ -25,6 +25,7 @@ pub fn send<K: Kernel>(
     flags: u64,
 ) -> Result<sys::out::send::Send> {
     let recipient: Address = context.memory.read_address(recipient_off, recipient_len)?;
+    context.kernel.charge_copy(recipient_len as usize)?;
     let value = TokenAmount::from_atto((value_hi as u128) << 64 | value_lo as u128);
 
     // If that gas is u64::MAX, treat it as "all gas". Although really, this doesn't matter. Any gas
@@ -0,0 +1,367 @@
+// Copyright 2021-2023 Protocol Labs
+// SPDX-License-Identifier: Apache-2.0, MIT
+//! Typed custom syscalls.
+//!
+//! Embedders building domain-specific kernels can use [`bind_typed_syscall`] to expose additional
+//! syscalls to actors without hand-writing the offset/length plumbing used by the built-in
+//! syscalls. Each parameter type implements [`SyscallParam`], which describes the wasm values the
+//! parameter is passed as and how to read it out of the actor's memory.
+//!
+//! The calling convention matches the built-in syscalls:
+//!
+//! - The syscall returns a `u32` error number (0 on success). Syscalls bound with
+//!   [`bind_typed_syscall_with_custom_errors`] may also return embedder-specific error numbers
+//!   ([`CustomErrorNumber`](fvm_shared::error::CustomErrorNumber)).
+//! - Copying parameters out of, and the return value into, the actor's memory is charged via
+//!   [`GasOps::charge_copy`](crate::kernel::GasOps::charge_copy).
+//! - If the return type is non-empty, an out-pointer for the return value is prepended to the
+//!   parameters.
+//! - Byte slices, addresses, and CBOR parameters are passed as an `(offset, length)` pair, CIDs
+//!   are passed as an offset, and integers are passed as-is.
+//!
+//! # Example
+//!
+//! ```ignore
+//! bind_typed_syscall(
+//!     &mut linker,
+//!     "my_module",
+//!     "sum_len",
+//!     |kernel: &mut K, (data, extra): (Vec<u8>, u64)| Ok(data.len() as u64 + extra),
+//! )?;
+//! ```
+use std::mem;
+
+use cid::Cid;
+use fvm_shared::address::Address;
+use fvm_shared::error::ErrorNumber;
+use fvm_shared::sys::SyscallSafe;
+use serde::de::DeserializeOwned;
+use wasmtime::{Caller, FuncType, Linker, Val, ValType};
+
+use super::bind::memory_and_data;
+use super::context::Memory;
+use super::error::Abort;
+use super::{charge_for_exec, update_gas_available, InvocationData};
+use crate::call_manager::backtrace;
+use crate::kernel::{CustomSyscallError, ExecutionError, Kernel, Result, SyscallError};
+
+/// A single syscall parameter, read from the wasm arguments and (possibly) the actor's memory.
+pub trait SyscallParam: Sized {
+    /// The wasm value types this parameter is passed as, in order.
+    const WASM_TYPES: &'static [ValType];
+
+    /// Read the parameter given exactly `WASM_TYPES.len()` wasm values.
+    fn read(memory: &Memory, args: &[Val]) -> Result<Self>;
+
+    /// The number of bytes copied out of the actor's memory to read this parameter, used to charge
+    /// for the copy. Parameters passed by value don't copy anything.
+    fn copied_len(&self, _args: &[Val]) -> usize {
+        0
+    }
+}
+
+/// A tuple of [`SyscallParam`]s, implemented for tuples of up to 8 parameters.
+pub trait SyscallParams: Sized {
+    /// The flattened wasm value types of all parameters.
+    fn wasm_types() -> Vec<ValType>;
+
+    /// Read all parameters from the wasm arguments, returning them along with the total number of
+    /// bytes copied out of the actor's memory.
+    fn read(memory: &Memory, args: &[Val]) -> Result<(Self, usize)>;
+}
+
+/// A CBOR-encoded parameter, passed as an `(offset, length)` pair.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cbor<T>(pub T);
+
+macro_rules! impl_scalar_param {
+    ($t:ty, $vt:ident, $get:ident) => {
+        #[allow(clippy::unnecessary_cast)]
+        impl SyscallParam for $t {
+            const WASM_TYPES: &'static [ValType] = &[ValType::$vt];
+
+            fn read(_: &Memory, args: &[Val]) -> Result<Self> {
+                Ok(args[0].$get() as $t)
+            }
+        }
+    };
+}
+
+impl_scalar_param!(u32, I32, unwrap_i32);
+impl_scalar_param!(i32, I32, unwrap_i32);
+impl_scalar_param!(u64, I64, unwrap_i64);
+impl_scalar_param!(i64, I64, unwrap_i64);
+
+impl SyscallParam for Vec<u8> {
+    const WASM_TYPES: &'static [ValType] = &[ValType::I32, ValType::I32];
+
+    fn read(memory: &Memory, args: &[Val]) -> Result<Self> {
+        let (off, len) = (args[0].unwrap_i32() as u32, args[1].unwrap_i32() as u32);
+        Ok(memory.try_slice(off, len)?.to_vec())
+    }
+
+    fn copied_len(&self, _args: &[Val]) -> usize {
+        self.len()
+    }
+}
+
+impl SyscallParam for Cid {
+    const WASM_TYPES: &'static [ValType] = &[ValType::I32];
+
+    fn read(memory: &Memory, args: &[Val]) -> Result<Self> {
+        memory.read_cid(args[0].unwrap_i32() as u32)
+    }
+
+    fn copied_len(&self, _args: &[Val]) -> usize {
+        self.encoded_len()
+    }
+}
+
+impl SyscallParam for Address {
+    const WASM_TYPES: &'static [ValType] = &[ValType::I32, ValType::I32];
+
+    fn read(memory: &Memory, args: &[Val]) -> Result<Self> {
+        let (off, len) = (args[0].unwrap_i32() as u32, args[1].unwrap_i32() as u32);
+        memory.read_address(off, len)
+    }
+
+    fn copied_len(&self, args: &[Val]) -> usize {
+        args[1].unwrap_i32() as u32 as usize
+    }
+}
+
+impl<T: DeserializeOwned> SyscallParam for Cbor<T> {
+    const WASM_TYPES: &'static [ValType] = &[ValType::I32, ValType::I32];
+
+    fn read(memory: &Memory, args: &[Val]) -> Result<Self> {
+        let (off, len) = (args[0].unwrap_i32() as u32, args[1].unwrap_i32() as u32);
+        memory.read_cbor(off, len).map(Cbor)
+    }
+
+    fn copied_len(&self, args: &[Val]) -> usize {
+        args[1].unwrap_i32() as u32 as usize
+    }
+}
+
+macro_rules! impl_syscall_params {
+    ($($t:ident)*) => {
+        #[allow(non_snake_case, unused_variables, unused_mut, unused_assignments)]
+        impl<$($t: SyscallParam,)*> SyscallParams for ($($t,)*) {
+            fn wasm_types() -> Vec<ValType> {
+                let mut types = Vec::new();
+                $(types.extend_from_slice($t::WASM_TYPES);)*
+                types
+            }
+
+            fn read(memory: &Memory, args: &[Val]) -> Result<(Self, usize)> {
+                let mut offset = 0;
+                let mut copied = 0;
+                $(
+                    let $t = {
+                        let args = &args[offset..offset + $t::WASM_TYPES.len()];
+                        let param = $t::read(memory, args)?;
+                        copied += param.copied_len(args);
+                        param
+                    };
+                    offset += $t::WASM_TYPES.len();
+                )*
+                Ok((($($t,)*), copied))
+            }
+        }
+    }
+}
+
+impl_syscall_params!();
+impl_syscall_params!(A);
+impl_syscall_params!(A B);
+impl_syscall_params!(A B C);
+impl_syscall_params!(A B C D);
+impl_syscall_params!(A B C D E);
+impl_syscall_params!(A B C D E F);
+impl_syscall_params!(A B C D E F G);
+impl_syscall_params!(A B C D E F G H);
+
+/// Binds a custom syscall with a typed signature into the linker.
+///
+/// The syscall is charged the standard syscall gas, and errors are handled exactly like the
+/// built-in syscalls: syscall errors are returned to the actor as error numbers (and recorded as
+/// the last error for backtraces), while fatal errors and out-of-gas abort execution.
+pub fn bind_typed_syscall<K, P, R, F>(
+    linker: &mut Linker<InvocationData<K>>,
+    module: &'static str,
+    name: &'static str,
+    syscall: F,
+) -> anyhow::Result<()>
+where
+    K: Kernel,
+    P: SyscallParams,
+    R: SyscallSafe,
+    F: Fn(&mut K, P) -> Result<R> + Send + Sync + 'static,
+{
+    bind_typed_syscall_with_custom_errors(linker, module, name, move |kernel: &mut K, params| {
+        syscall(kernel, params).map(Ok)
+    })
+}
+
+/// Binds a custom syscall with a typed signature into the linker, like [`bind_typed_syscall`],
+/// but the syscall may also fail with a [`CustomSyscallError`]. Its embedder-specific error number
+/// is returned to the actor as is.
+pub fn bind_typed_syscall_with_custom_errors<K, P, R, F>(
+    linker: &mut Linker<InvocationData<K>>,
+    module: &'static str,
+    name: &'static str,
+    syscall: F,
+) -> anyhow::Result<()>
+where
+    K: Kernel,
+    P: SyscallParams,
+    R: SyscallSafe,
+    F: Fn(&mut K, P) -> Result<std::result::Result<R, CustomSyscallError>> + Send + Sync + 'static,
+{
+    // If we're returning a zero-sized "value", we expect no out pointer.
+    let has_ret = mem::size_of::<R>() != 0;
+
+    let mut params = Vec::new();
+    if has_ret {
+        params.push(ValType::I32);
+    }
+    params.extend(P::wasm_types());
+    let ty = FuncType::new(params, [ValType::I32]);
+
+    #[cfg(feature = "metrics")]
+    let invocations = crate::metrics::syscall_invocations(module, name);
+
+    linker.func_new(
+        module,
+        name,
+        ty,
+        move |mut caller: Caller<'_, InvocationData<K>>, args: &[Val], results: &mut [Val]| {
+            charge_for_exec(&mut caller)?;
+            #[cfg(feature = "metrics")]
+            invocations.inc();
+
+            let (memory, data) = memory_and_data(&mut caller);
+            let charge = data.kernel.price_list().on_syscall();
+            data.kernel
+                .charge_gas(charge.name, charge.compute_gas)
+                .map_err(Abort::from_error_as_fatal)?;
+            data.kernel.trace_syscall(module, name);
+
+            let (ret, args) = if has_ret {
+                (Some(args[0].unwrap_i32() as u32), &args[1..])
+            } else {
+                (None, args)
+            };
+
+            // We need to check to make sure we can store the return value _before_ we do anything.
+            if let Some(ret) = ret {
+                if (ret as u64) > (memory.len() as u64)
+                    || memory.len() - (ret as usize) < mem::size_of::<R>()
+                {
+                    let code = ErrorNumber::IllegalArgument;
+                    data.last_error = Some(backtrace::Cause::from_syscall(
+                        module,
+                        name,
+                        SyscallError("no space for return value".into(), code),
+                    ));
+                    results[0] = Val::I32(code as i32);
+                    return Ok(());
+                }
+            }
+
+            let result = P::read(memory, args).and_then(|(params, copied)| {
+                data.kernel.charge_copy(copied)?;
+                syscall(&mut data.kernel, params)
+            });
+            let code = match result {
+                Ok(Ok(value)) => {
+                    log::trace!("syscall {}::{}: ok", module, name);
+                    if let Some(ret) = ret {
+                        data.kernel
+                            .charge_copy(mem::size_of::<R>())
+                            .map_err(Abort::from_error_as_fatal)?;
+                        unsafe {
+                            // We're writing into a user-specified pointer, so avoid
+                            // derefering it as it may not be aligned.
+                            (memory.as_mut_ptr().offset(ret as isize) as *mut R)
+                                .write_unaligned(value);
+                        }
+                    }
+                    data.last_error = None;
+                    0
+                }
+                Ok(Err(err)) => {
+                    let code = err.1.value();
+                    log::trace!("syscall {}::{}: fail ({})", module, name, code);
+                    data.last_error =
+                        Some(backtrace::Cause::from_custom_syscall(module, name, err));
+                    code
+                }
+                Err(ExecutionError::Syscall(err)) => {
+                    let code = err.1;
+                    log::trace!("syscall {}::{}: fail ({})", module, name, code as u32);
+                    data.last_error = Some(backtrace::Cause::from_syscall(module, name, err));
+                    code as u32
+                }
+                Err(ExecutionError::OutOfGas) => return Err(Abort::OutOfGas.into()),
+                Err(ExecutionError::Fatal(err)) => return Err(Abort::Fatal(err).into()),
+            };
+
+            update_gas_available(&mut caller)?;
+
+            results[0] = Val::I32(code as i32);
+            Ok(())
+        },
+    )?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use fvm_ipld_encoding::to_vec;
+
+    use super::*;
+
+    #[test]
+    fn test_wasm_types() {
+        assert_eq!(
+            <(Vec<u8>, u64, Cid)>::wasm_types(),
+            vec![ValType::I32, ValType::I32, ValType::I64, ValType::I32]
+        );
+        assert!(<()>::wasm_types().is_empty());
+    }
+
+    #[test]
+    fn test_read_params() {
+        let mut buf = b"hello".to_vec();
+        let encoded = to_vec(&(1u64, "foo".to_string())).unwrap();
+        buf.extend_from_slice(&encoded);
+        let mem = Memory::new(&mut buf);
+
+        let args = [
+            Val::I32(0),
+            Val::I32(5),
+            Val::I64(42),
+            Val::I32(5),
+            Val::I32(encoded.len() as i32),
+        ];
+        let ((bytes, n, Cbor(params)), copied) =
+            <(Vec<u8>, u64, Cbor<(u64, String)>)>::read(mem, &args).unwrap();
+        assert_eq!(copied, 5 + encoded.len());
+        assert_eq!(bytes, b"hello");
+        assert_eq!(n, 42);
+        assert_eq!(params, (1, "foo".to_string()));
+    }
+
+    #[test]
+    fn test_read_params_out_of_bounds() {
+        let mem = Memory::new(&mut []);
+        let err = <(Vec<u8>,)>::read(mem, &[Val::I32(0), Val::I32(1)]).unwrap_err();
+        match err {
+            crate::kernel::ExecutionError::Syscall(SyscallError(_, code)) => {
+                assert_eq!(code, ErrorNumber::IllegalArgument)
+            }
+            _ => panic!("expected a syscall error"),
+        }
+    }
+}
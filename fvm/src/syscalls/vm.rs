@@ -6,7 +6,8 @@ use fvm_shared::sys::SyscallSafe;
 
 use super::error::Abort;
 use super::Context;
-use crate::kernel::Kernel;
+use crate::kernel::{GasOps, Kernel, MessageOps};
+use crate::machine::Machine;
 
 /// An uninhabited type. We use this in `abort` to make sure there's no way to return without
 /// returning an error.
@@ -28,12 +29,20 @@ pub fn exit(
     message_len: u32,
 ) -> Result<Never, Abort> {
     let code = ExitCode::new(code);
-    if !code.is_success() && code.is_system_error() {
-        return Err(Abort::Exit(
-            ExitCode::SYS_ILLEGAL_EXIT_CODE,
-            format!("actor aborted with code {}", code),
-            blk,
-        ));
+    if !code.is_success() {
+        let exit_code = context
+            .kernel
+            .machine()
+            .context()
+            .exit_code_policy
+            .actor_exit(code);
+        if exit_code != code {
+            return Err(Abort::Exit(
+                exit_code,
+                format!("actor aborted with code {}", code),
+                blk,
+            ));
+        }
     }
 
     let message = if message_len == 0 {
@@ -60,6 +69,27 @@ pub fn exit(
     Err(Abort::Exit(code, message, blk))
 }
 
-pub fn message_context(context: Context<'_, impl Kernel>) -> crate::kernel::Result<MessageContext> {
+pub fn message_context(
+    context: Context<'_, impl MessageOps>,
+) -> crate::kernel::Result<MessageContext> {
     context.kernel.msg_context()
 }
+
+/// Writes as much of the message context as fits into the output buffer, returning the number of
+/// bytes written (see [`Extensible`](fvm_shared::sys::Extensible)).
+pub fn message_context_ext(
+    context: Context<'_, impl MessageOps + GasOps>,
+    obuf_off: u32,
+    obuf_len: u32,
+) -> crate::kernel::Result<u32> {
+    context.memory.check_bounds(obuf_off, obuf_len)?;
+
+    let ctx = context.kernel.msg_context()?;
+    let written = context.memory.write_extensible(&ctx, obuf_off, obuf_len)?;
+    context.kernel.charge_copy(written as usize)?;
+    Ok(written)
+}
+
+pub fn call_depth(context: Context<'_, impl MessageOps>) -> crate::kernel::Result<u32> {
+    Ok(context.kernel.call_depth())
+}
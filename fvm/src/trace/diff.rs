@@ -0,0 +1,203 @@
+// Copyright 2021-2023 Protocol Labs
+// SPDX-License-Identifier: Apache-2.0, MIT
+use std::collections::BTreeMap;
+
+use fvm_shared::address::Address;
+use fvm_shared::MethodNum;
+
+use super::{ExecutionEvent, ExecutionTrace};
+use crate::gas::{Gas, GasChargeName};
+
+/// The actor and method of a call, used to attribute gas charges to the call that made them.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct CallSite {
+    pub to: Address,
+    pub method: MethodNum,
+}
+
+/// The aggregate of a set of gas charges.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct GasTotals {
+    /// The number of charges.
+    pub count: u64,
+    /// The total compute gas.
+    pub compute_gas: Gas,
+    /// The total other gas.
+    pub other_gas: Gas,
+}
+
+impl GasTotals {
+    /// The total gas (compute and other).
+    pub fn total(&self) -> Gas {
+        self.compute_gas + self.other_gas
+    }
+}
+
+/// The key by which gas charges are aggregated: the innermost call active when the charge was made
+/// (if any), and the charge name.
+pub type GasDiffKey = (Option<CallSite>, GasChargeName);
+
+/// Gas charges aggregated by call site and charge name, in both traces.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct GasDiffEntry {
+    /// The call in which the charges were made, or `None` for charges made outside of any call
+    /// (e.g., message inclusion gas).
+    pub call_site: Option<CallSite>,
+    /// The name of the charges.
+    pub name: GasChargeName,
+    /// The charges in the first ("before") trace.
+    pub before: GasTotals,
+    /// The charges in the second ("after") trace.
+    pub after: GasTotals,
+}
+
+impl GasDiffEntry {
+    /// The change in total gas, in milligas.
+    pub fn delta_milligas(&self) -> i128 {
+        self.after.total().as_milligas() as i128 - self.before.total().as_milligas() as i128
+    }
+
+    /// Returns true if the charges differ in count or amount.
+    pub fn is_changed(&self) -> bool {
+        self.before != self.after
+    }
+}
+
+/// A structured diff between the gas charges of two execution traces (e.g., before and after a
+/// price list or actor change).
+///
+/// Charges are attributed to the innermost call active when they were made, identified by the
+/// called actor and method (so repeated calls to the same method are aggregated), and aggregated by
+/// charge name.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct GasTraceDiff {
+    /// All entries, sorted by call site then name.
+    pub entries: Vec<GasDiffEntry>,
+}
+
+impl GasTraceDiff {
+    /// Compute the diff between two traces.
+    pub fn new(before: &ExecutionTrace, after: &ExecutionTrace) -> Self {
+        let mut totals: BTreeMap<GasDiffKey, (GasTotals, GasTotals)> = BTreeMap::new();
+        for (key, t) in aggregate(before) {
+            totals.entry(key).or_default().0 = t;
+        }
+        for (key, t) in aggregate(after) {
+            totals.entry(key).or_default().1 = t;
+        }
+        GasTraceDiff {
+            entries: totals
+                .into_iter()
+                .map(|((call_site, name), (before, after))| GasDiffEntry {
+                    call_site,
+                    name,
+                    before,
+                    after,
+                })
+                .collect(),
+        }
+    }
+
+    /// Iterate over the entries that differ between the two traces.
+    pub fn changed(&self) -> impl Iterator<Item = &GasDiffEntry> + '_ {
+        self.entries.iter().filter(|e| e.is_changed())
+    }
+
+    /// The change in total gas across all charges, in milligas.
+    pub fn delta_milligas(&self) -> i128 {
+        self.entries.iter().map(GasDiffEntry::delta_milligas).sum()
+    }
+}
+
+/// Aggregate the gas charges in a trace by call site and charge name.
+pub fn aggregate(trace: &ExecutionTrace) -> BTreeMap<GasDiffKey, GasTotals> {
+    let mut totals: BTreeMap<GasDiffKey, GasTotals> = BTreeMap::new();
+    let mut stack: Vec<CallSite> = Vec::new();
+    for event in trace {
+        match event {
+            ExecutionEvent::GasCharge(charge) => {
+                let t = totals
+                    .entry((stack.last().copied(), charge.name.clone()))
+                    .or_default();
+                t.count += 1;
+                t.compute_gas += charge.compute_gas;
+                t.other_gas += charge.other_gas;
+            }
+            ExecutionEvent::Call { to, method, .. } => stack.push(CallSite {
+                to: *to,
+                method: *method,
+            }),
+            ExecutionEvent::CallReturn(..) | ExecutionEvent::CallError(_) => {
+                stack.pop();
+            }
+            _ => {}
+        }
+    }
+    totals
+}
+
+#[cfg(test)]
+mod tests {
+    use fvm_shared::error::ExitCode;
+
+    use super::*;
+    use crate::gas::GasCharge;
+
+    fn call(to: u64, method: MethodNum) -> ExecutionEvent {
+        ExecutionEvent::Call {
+            from: 0,
+            to: Address::new_id(to),
+            method,
+            params: None,
+            value: Default::default(),
+            gas_limit: 0,
+            read_only: false,
+        }
+    }
+
+    fn charge(name: &'static str, gas: u64) -> ExecutionEvent {
+        ExecutionEvent::GasCharge(GasCharge::new(name, Gas::new(gas), Gas::zero()))
+    }
+
+    #[test]
+    fn diff_traces() {
+        let ret = ExecutionEvent::CallReturn(ExitCode::OK, None);
+        let before = vec![
+            charge("OnChainMessage", 10),
+            call(100, 2),
+            charge("OnSyscall", 1),
+            call(101, 3),
+            charge("OnSyscall", 1),
+            ret.clone(),
+            charge("OnSyscall", 1),
+            ret.clone(),
+        ];
+        let after = vec![
+            charge("OnChainMessage", 10),
+            call(100, 2),
+            charge("OnSyscall", 2),
+            ret,
+        ];
+
+        let diff = GasTraceDiff::new(&before, &after);
+        assert_eq!(diff.entries.len(), 3);
+        assert_eq!(diff.delta_milligas(), -1000);
+
+        let changed: Vec<_> = diff.changed().collect();
+        assert_eq!(changed.len(), 2);
+
+        let outer = CallSite {
+            to: Address::new_id(100),
+            method: 2,
+        };
+        assert_eq!(changed[0].call_site, Some(outer));
+        assert_eq!(changed[0].name, GasChargeName::OnSyscall);
+        assert_eq!(changed[0].before.count, 2);
+        assert_eq!(changed[0].after.count, 1);
+        assert_eq!(changed[0].delta_milligas(), 0);
+
+        assert_eq!(changed[1].call_site.unwrap().to, Address::new_id(101));
+        assert_eq!(changed[1].after, GasTotals::default());
+        assert_eq!(changed[1].delta_milligas(), -1000);
+    }
+}
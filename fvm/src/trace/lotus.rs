@@ -0,0 +1,315 @@
+// Copyright 2021-2023 Protocol Labs
+// SPDX-License-Identifier: Apache-2.0, MIT
+use anyhow::anyhow;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use fvm_ipld_encoding::ipld_block::IpldBlock;
+use fvm_shared::address::Address;
+use serde::{Serialize, Serializer};
+
+use super::{ExecutionEvent, ExecutionTrace};
+use crate::gas::GasCharge;
+use crate::machine::ExitCodePolicy;
+use crate::Cid;
+
+/// An execution trace in the format used by Lotus (its `ExecutionTrace` type), as returned by the
+/// `StateReplay` and `StateCompute` APIs. This serializes (e.g., with `serde_json`) to the same
+/// JSON as Lotus, so existing tooling can consume traces produced by the FVM.
+///
+/// Convert a trace with [`LotusExecutionTrace::from_trace`].
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct LotusExecutionTrace {
+    /// The call.
+    pub msg: LotusMessageTrace,
+    /// The call's result.
+    pub msg_rct: LotusReturnTrace,
+    /// The gas charged by the call itself (excluding its subcalls).
+    pub gas_charges: Vec<LotusGasTrace>,
+    /// The calls made by the called actor, in order.
+    pub subcalls: Vec<LotusExecutionTrace>,
+}
+
+/// A call in a [`LotusExecutionTrace`].
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct LotusMessageTrace {
+    #[serde(serialize_with = "display")]
+    pub from: Address,
+    #[serde(serialize_with = "display")]
+    pub to: Address,
+    /// The value transferred, in attoFIL.
+    pub value: String,
+    pub method: u64,
+    #[serde(serialize_with = "bytes")]
+    pub params: Option<Vec<u8>>,
+    pub params_codec: u64,
+    pub gas_limit: u64,
+    pub read_only: bool,
+    /// The code of the invoked actor, if an actor was invoked.
+    #[serde(serialize_with = "cid")]
+    pub code_cid: Option<Cid>,
+}
+
+/// The result of a call in a [`LotusExecutionTrace`].
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct LotusReturnTrace {
+    pub exit_code: u32,
+    #[serde(rename = "Return", serialize_with = "bytes")]
+    pub return_data: Option<Vec<u8>>,
+    pub return_codec: u64,
+}
+
+/// A gas charge in a [`LotusExecutionTrace`]. Gas amounts are rounded up to whole gas units, and
+/// the time taken is in nanoseconds (zero if not measured).
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct LotusGasTrace {
+    #[serde(rename = "Name")]
+    pub name: String,
+    #[serde(rename = "tg")]
+    pub total_gas: u64,
+    #[serde(rename = "cg")]
+    pub compute_gas: u64,
+    #[serde(rename = "sg")]
+    pub storage_gas: u64,
+    #[serde(rename = "tt")]
+    pub time_taken: u64,
+}
+
+impl From<&GasCharge> for LotusGasTrace {
+    fn from(charge: &GasCharge) -> Self {
+        LotusGasTrace {
+            name: charge.name.to_string(),
+            total_gas: charge.total().round_up(),
+            compute_gas: charge.compute_gas.round_up(),
+            storage_gas: charge.other_gas.round_up(),
+            time_taken: charge
+                .elapsed
+                .get()
+                .map(|d| d.as_nanos() as u64)
+                .unwrap_or_default(),
+        }
+    }
+}
+
+impl LotusExecutionTrace {
+    /// Convert a trace of a message recorded with (at least) call and gas tracing enabled. Returns
+    /// `None` if the trace contains no calls (e.g., if the message failed validation).
+    ///
+    /// Gas charged before the top-level call (e.g., message inclusion gas) is attributed to the
+    /// top-level call, and failed sends are given the exit codes of the given [`ExitCodePolicy`]
+    /// (normally the machine's, see
+    /// [`NetworkConfig::exit_code_policy`](crate::machine::NetworkConfig::exit_code_policy)), as in
+    /// Lotus. Other events are ignored.
+    pub fn from_trace(
+        trace: &ExecutionTrace,
+        policy: &dyn ExitCodePolicy,
+    ) -> anyhow::Result<Option<Self>> {
+        let mut events = trace.iter();
+        let mut initial_charges = Vec::new();
+        while let Some(event) = events.next() {
+            match event {
+                ExecutionEvent::GasCharge(charge) => initial_charges.push(charge.into()),
+                ExecutionEvent::Call { .. } => {
+                    let mut root = Self::build(event, &mut events, policy)?;
+                    initial_charges.append(&mut root.gas_charges);
+                    root.gas_charges = initial_charges;
+                    return Ok(Some(root));
+                }
+                _ => {}
+            }
+        }
+        Ok(None)
+    }
+
+    /// Build the trace of the given call from the events that follow it, up to and including its
+    /// return.
+    fn build<'a>(
+        call: &ExecutionEvent,
+        events: &mut impl Iterator<Item = &'a ExecutionEvent>,
+        policy: &dyn ExitCodePolicy,
+    ) -> anyhow::Result<Self> {
+        let ExecutionEvent::Call {
+            from,
+            to,
+            method,
+            params,
+            value,
+            gas_limit,
+            read_only,
+        } = call else {
+            return Err(anyhow!("expected a call event"));
+        };
+        let (params, params_codec) = block_parts(params.as_ref());
+        let mut trace = LotusExecutionTrace {
+            msg: LotusMessageTrace {
+                from: Address::new_id(*from),
+                to: *to,
+                value: value.atto().to_string(),
+                method: *method,
+                params,
+                params_codec,
+                gas_limit: *gas_limit,
+                read_only: *read_only,
+                code_cid: None,
+            },
+            msg_rct: LotusReturnTrace {
+                exit_code: 0,
+                return_data: None,
+                return_codec: 0,
+            },
+            gas_charges: Vec::new(),
+            subcalls: Vec::new(),
+        };
+        while let Some(event) = events.next() {
+            match event {
+                ExecutionEvent::GasCharge(charge) => trace.gas_charges.push(charge.into()),
+                ExecutionEvent::InvokeActor(code) => trace.msg.code_cid = Some(*code),
+                ExecutionEvent::Call { .. } => {
+                    trace.subcalls.push(Self::build(event, events, policy)?)
+                }
+                ExecutionEvent::CallReturn(exit_code, ret) => {
+                    let (ret, ret_codec) = block_parts(ret.as_ref());
+                    trace.msg_rct = LotusReturnTrace {
+                        exit_code: exit_code.value(),
+                        return_data: ret,
+                        return_codec: ret_codec,
+                    };
+                    return Ok(trace);
+                }
+                ExecutionEvent::CallError(err) => {
+                    trace.msg_rct.exit_code = policy.send_error(err.1).value();
+                    return Ok(trace);
+                }
+                _ => {}
+            }
+        }
+        Err(anyhow!("trace ended before the call to {} returned", to))
+    }
+}
+
+fn block_parts(block: Option<&IpldBlock>) -> (Option<Vec<u8>>, u64) {
+    block.map_or((None, 0), |b| (Some(b.data.clone()), b.codec))
+}
+
+fn display<S: Serializer>(v: &impl std::fmt::Display, s: S) -> Result<S::Ok, S::Error> {
+    s.collect_str(v)
+}
+
+/// Byte strings are base64 encoded, and missing byte strings are `null`.
+fn bytes<S: Serializer>(v: &Option<Vec<u8>>, s: S) -> Result<S::Ok, S::Error> {
+    match v {
+        Some(v) => s.serialize_str(&BASE64.encode(v)),
+        None => s.serialize_none(),
+    }
+}
+
+/// CIDs are encoded as `{"/": "<cid>"}`.
+fn cid<S: Serializer>(v: &Option<Cid>, s: S) -> Result<S::Ok, S::Error> {
+    #[derive(Serialize)]
+    struct Link {
+        #[serde(rename = "/")]
+        cid: String,
+    }
+    v.map(|c| Link { cid: c.to_string() }).serialize(s)
+}
+
+#[cfg(test)]
+mod tests {
+    use fvm_shared::econ::TokenAmount;
+    use fvm_shared::error::{ErrorNumber, ExitCode};
+
+    use super::*;
+    use crate::gas::Gas;
+    use crate::kernel::SyscallError;
+    use crate::machine::DefaultExitCodePolicy;
+
+    #[derive(Debug)]
+    struct NotFoundPolicy;
+
+    impl ExitCodePolicy for NotFoundPolicy {
+        fn send_error(&self, _: ErrorNumber) -> ExitCode {
+            ExitCode::new(14)
+        }
+    }
+
+    fn call(to: u64) -> ExecutionEvent {
+        ExecutionEvent::Call {
+            from: 100,
+            to: Address::new_id(to),
+            method: 2,
+            params: Some(IpldBlock {
+                codec: 0x51,
+                data: vec![1, 2, 3],
+            }),
+            value: TokenAmount::from_atto(5),
+            gas_limit: 1000,
+            read_only: false,
+        }
+    }
+
+    #[test]
+    fn nests_calls() {
+        let charge = |name: &'static str| {
+            ExecutionEvent::GasCharge(GasCharge::new(name, Gas::new(1), Gas::from_milligas(1500)))
+        };
+        let trace = vec![
+            charge("OnChainMessage"),
+            call(101),
+            charge("OnMethodInvocation"),
+            call(102),
+            ExecutionEvent::CallError(SyscallError::new(ErrorNumber::NotFound, "no actor")),
+            ExecutionEvent::CallReturn(ExitCode::OK, None),
+        ];
+        let lotus = LotusExecutionTrace::from_trace(&trace, &DefaultExitCodePolicy)
+            .unwrap()
+            .unwrap();
+        assert_eq!(lotus.msg.to, Address::new_id(101));
+        assert_eq!(lotus.gas_charges.len(), 2);
+        assert_eq!(lotus.gas_charges[0].name, "OnChainMessage");
+        assert_eq!(lotus.gas_charges[0].total_gas, 3);
+        assert_eq!(lotus.subcalls.len(), 1);
+        assert_eq!(
+            lotus.subcalls[0].msg_rct.exit_code,
+            ExitCode::SYS_INVALID_RECEIVER.value()
+        );
+
+        let json = serde_json::to_value(&lotus.subcalls[0]).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "Msg": {
+                    "From": "f0100",
+                    "To": "f0102",
+                    "Value": "5",
+                    "Method": 2,
+                    "Params": "AQID",
+                    "ParamsCodec": 0x51,
+                    "GasLimit": 1000,
+                    "ReadOnly": false,
+                    "CodeCid": null,
+                },
+                "MsgRct": {
+                    "ExitCode": 5,
+                    "Return": null,
+                    "ReturnCodec": 0,
+                },
+                "GasCharges": [],
+                "Subcalls": [],
+            })
+        );
+
+        // Failed sends take their exit codes from the given policy.
+        let lotus = LotusExecutionTrace::from_trace(&trace, &NotFoundPolicy)
+            .unwrap()
+            .unwrap();
+        assert_eq!(lotus.subcalls[0].msg_rct.exit_code, 14);
+
+        assert_eq!(
+            LotusExecutionTrace::from_trace(&Vec::new(), &DefaultExitCodePolicy).unwrap(),
+            None
+        );
+        assert!(LotusExecutionTrace::from_trace(&vec![call(101)], &DefaultExitCodePolicy).is_err());
+    }
+}
@@ -1,15 +1,26 @@
 // Copyright 2021-2023 Protocol Labs
 // SPDX-License-Identifier: Apache-2.0, MIT
+use std::cell::RefCell;
+
 use fvm_ipld_encoding::ipld_block::IpldBlock;
 use fvm_shared::address::Address;
 use fvm_shared::econ::TokenAmount;
 use fvm_shared::error::ExitCode;
+use fvm_shared::event::StampedEvent;
 use fvm_shared::{ActorID, MethodNum};
 
 use crate::gas::GasCharge;
 use crate::kernel::SyscallError;
 use crate::Cid;
 
+mod diff;
+#[cfg(feature = "lotus-trace")]
+mod lotus;
+
+pub use diff::{aggregate, CallSite, GasDiffEntry, GasDiffKey, GasTotals, GasTraceDiff};
+#[cfg(feature = "lotus-trace")]
+pub use lotus::{LotusExecutionTrace, LotusGasTrace, LotusMessageTrace, LotusReturnTrace};
+
 /// Execution Trace, only for informational and debugging purposes.
 pub type ExecutionTrace = Vec<ExecutionEvent>;
 
@@ -35,4 +46,253 @@ pub enum ExecutionEvent {
     CallError(SyscallError),
     /// Emitted every time we successfully invoke an actor
     InvokeActor(Cid),
+    /// Emitted on each syscall invocation.
+    Syscall {
+        module: &'static str,
+        name: &'static str,
+    },
+    /// Emitted every time an actor emits an event, even if the event is later reverted.
+    Event(StampedEvent),
+    /// Emitted every time an actor's state is read or updated (including deletions).
+    StateAccess {
+        actor: ActorID,
+        update: bool,
+    },
+}
+
+impl ExecutionEvent {
+    /// The category of this event, as configured by [`TraceConfig`].
+    pub fn category(&self) -> TraceCategory {
+        match self {
+            ExecutionEvent::GasCharge(_) => TraceCategory::Gas,
+            ExecutionEvent::Call { .. }
+            | ExecutionEvent::CallReturn(..)
+            | ExecutionEvent::CallError(_)
+            | ExecutionEvent::InvokeActor(_) => TraceCategory::Calls,
+            ExecutionEvent::Syscall { .. } => TraceCategory::Syscalls,
+            ExecutionEvent::Event(_) => TraceCategory::Events,
+            ExecutionEvent::StateAccess { .. } => TraceCategory::StateAccess,
+        }
+    }
+}
+
+/// A category of [`ExecutionEvent`]s that can be traced independently.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum TraceCategory {
+    /// Sends, returns, and actor invocations.
+    Calls,
+    /// Gas charges.
+    Gas,
+    /// Syscall invocations.
+    Syscalls,
+    /// Events emitted by actors.
+    Events,
+    /// Actor state reads and updates.
+    StateAccess,
+}
+
+/// Configures which [`ExecutionEvent`]s are recorded in the execution trace.
+///
+/// Each category is either disabled (`None`), or enabled with a limit on the number of events of
+/// that category recorded per message (`Some(limit)`). Events over the limit are dropped.
+///
+/// Calls are kept balanced: a call and its return (or error) count as two events and are recorded
+/// or dropped together, along with all of the call's sub-calls.
+///
+/// Not consensus-critical, but each enabled category has a performance impact.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct TraceConfig {
+    /// The maximum number of call events.
+    pub calls: Option<usize>,
+    /// The maximum number of gas charge events.
+    pub gas: Option<usize>,
+    /// The maximum number of syscall events.
+    pub syscalls: Option<usize>,
+    /// The maximum number of actor event events.
+    pub events: Option<usize>,
+    /// The maximum number of state access events.
+    pub state_access: Option<usize>,
+}
+
+impl TraceConfig {
+    /// Trace all categories without limits.
+    pub const fn all() -> Self {
+        TraceConfig {
+            calls: Some(usize::MAX),
+            gas: Some(usize::MAX),
+            syscalls: Some(usize::MAX),
+            events: Some(usize::MAX),
+            state_access: Some(usize::MAX),
+        }
+    }
+
+    /// Returns true if any category is traced.
+    pub fn is_enabled(&self) -> bool {
+        *self != TraceConfig::default()
+    }
+
+    /// Returns the limit for the given category, or `None` if it's not traced.
+    pub fn limit(&self, category: TraceCategory) -> Option<usize> {
+        match category {
+            TraceCategory::Calls => self.calls,
+            TraceCategory::Gas => self.gas,
+            TraceCategory::Syscalls => self.syscalls,
+            TraceCategory::Events => self.events,
+            TraceCategory::StateAccess => self.state_access,
+        }
+    }
+
+    /// Returns true if the given category is traced.
+    pub fn is_traced(&self, category: TraceCategory) -> bool {
+        self.limit(category).is_some()
+    }
+}
+
+/// Records an [`ExecutionTrace`] according to a [`TraceConfig`].
+pub(crate) struct TraceRecorder {
+    config: TraceConfig,
+    trace: RefCell<ExecutionTrace>,
+    counts: RefCell<[usize; 5]>,
+    /// Whether each call currently on the call stack was recorded.
+    calls: RefCell<Vec<bool>>,
+}
+
+impl TraceRecorder {
+    pub fn new(config: TraceConfig) -> Self {
+        TraceRecorder {
+            config,
+            trace: Default::default(),
+            counts: Default::default(),
+            calls: Default::default(),
+        }
+    }
+
+    /// Returns true if the given category is traced.
+    pub fn is_traced(&self, category: TraceCategory) -> bool {
+        self.config.is_traced(category)
+    }
+
+    /// Record an event, unless its category is disabled or over its limit.
+    pub fn record(&self, event: ExecutionEvent) {
+        let category = event.category();
+        let Some(limit) = self.config.limit(category) else {
+            return;
+        };
+        let mut counts = self.counts.borrow_mut();
+        let count = &mut counts[category as usize];
+        let record = match event {
+            // Reserve room for the return when recording a call, and drop the sub-calls of dropped
+            // calls.
+            ExecutionEvent::Call { .. } => {
+                let mut calls = self.calls.borrow_mut();
+                let record = calls.last().copied().unwrap_or(true) && limit - *count >= 2;
+                calls.push(record);
+                if record {
+                    *count += 2;
+                }
+                record
+            }
+            // Returns were accounted for with their calls.
+            ExecutionEvent::CallReturn(..) | ExecutionEvent::CallError(_) => {
+                self.calls.borrow_mut().pop().unwrap_or(false)
+            }
+            // Invocations belong to the current call.
+            ExecutionEvent::InvokeActor(_) => {
+                let record = self.calls.borrow().last().copied().unwrap_or(false) && *count < limit;
+                if record {
+                    *count += 1;
+                }
+                record
+            }
+            _ => {
+                let record = *count < limit;
+                if record {
+                    *count += 1;
+                }
+                record
+            }
+        };
+        if record {
+            self.trace.borrow_mut().push(event);
+        }
+    }
+
+    /// Returns the recorded trace.
+    pub fn finish(self) -> ExecutionTrace {
+        self.trace.into_inner()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gas::Gas;
+
+    #[test]
+    fn limits_per_category() {
+        let recorder = TraceRecorder::new(TraceConfig {
+            gas: Some(2),
+            syscalls: Some(1),
+            ..Default::default()
+        });
+        for _ in 0..3 {
+            recorder.record(ExecutionEvent::GasCharge(GasCharge::new(
+                "OnTest",
+                Gas::new(1),
+                Gas::zero(),
+            )));
+            recorder.record(ExecutionEvent::Syscall {
+                module: "test",
+                name: "test",
+            });
+            recorder.record(ExecutionEvent::CallReturn(ExitCode::OK, None));
+        }
+        let trace = recorder.finish();
+        let count = |c| trace.iter().filter(|e| e.category() == c).count();
+        assert_eq!(count(TraceCategory::Gas), 2);
+        assert_eq!(count(TraceCategory::Syscalls), 1);
+        assert_eq!(count(TraceCategory::Calls), 0);
+    }
+
+    fn call() -> ExecutionEvent {
+        ExecutionEvent::Call {
+            from: 0,
+            to: Address::new_id(1),
+            method: 2,
+            params: None,
+            value: TokenAmount::default(),
+            gas_limit: 3,
+            read_only: false,
+        }
+    }
+
+    #[test]
+    fn calls_stay_balanced() {
+        let recorder = TraceRecorder::new(TraceConfig {
+            calls: Some(5),
+            ..Default::default()
+        });
+        // Recorded: the call, its invocation, and its return.
+        recorder.record(call());
+        recorder.record(ExecutionEvent::InvokeActor(*crate::EMPTY_ARR_CID));
+        // Dropped: there's no room for both the call and its return, so the sub-call (and its
+        // own sub-call) is dropped entirely.
+        recorder.record(call());
+        recorder.record(call());
+        recorder.record(ExecutionEvent::CallReturn(ExitCode::OK, None));
+        recorder.record(ExecutionEvent::CallError(SyscallError::new(
+            fvm_shared::error::ErrorNumber::Forbidden,
+            "test",
+        )));
+        recorder.record(ExecutionEvent::CallReturn(ExitCode::OK, None));
+
+        let trace = recorder.finish();
+        assert_eq!(trace.len(), 3);
+        assert!(matches!(trace[0], ExecutionEvent::Call { .. }));
+        assert!(matches!(trace[1], ExecutionEvent::InvokeActor(_)));
+        assert!(matches!(
+            trace[2],
+            ExecutionEvent::CallReturn(ExitCode::OK, None)
+        ));
+    }
 }
@@ -331,7 +331,7 @@ mod ipld {
 
         // assert gas
         {
-            let price_list = call_manager.machine.context().price_list;
+            let price_list = &call_manager.machine.context().price_list;
             let expected_create_price = price_list.on_block_create(block.len()).total();
             let expected_read_price = price_list.on_block_read(block.len()).total();
 
@@ -397,7 +397,7 @@ mod ipld {
 
         // assert gas
         {
-            let price_list = call_manager.machine.context().price_list;
+            let price_list = &call_manager.machine.context().price_list;
             let expected_create_price = price_list.on_block_create(block.len()).total();
             let expected_stat_price = price_list.on_block_stat().total();
 
@@ -6,7 +6,9 @@ use std::rc::Rc;
 
 use anyhow::Context;
 use cid::Cid;
-use fvm::call_manager::{Backtrace, CallManager, FinishRet, InvocationResult};
+use fvm::call_manager::{
+    Backtrace, CallFrame, CallManager, DebugArtifact, FinishRet, InvocationResult,
+};
 use fvm::engine::Engine;
 use fvm::externs::{Chain, Consensus, Externs, Rand};
 use fvm::gas::{Gas, GasCharge, GasTimer, GasTracker};
@@ -46,6 +48,15 @@ impl Rand for DummyExterns {
     ) -> anyhow::Result<[u8; 32]> {
         todo!()
     }
+
+    fn verify_beacon_entry(
+        &self,
+        _round: u64,
+        _prev_signature: &[u8],
+        _signature: &[u8],
+    ) -> anyhow::Result<bool> {
+        todo!()
+    }
 }
 
 impl Consensus for DummyExterns {
@@ -131,7 +142,7 @@ impl DummyMachine {
         let mut config = NetworkConfig::new(STUB_NETWORK_VER);
 
         // generate context from the new generated root and override actors with empty list
-        let ctx = config.override_actors(actors_cid).for_epoch(0, 0, root);
+        let ctx = config.override_actors(actors_cid).for_epoch(0, 0, root)?;
 
         Ok(Self {
             ctx,
@@ -292,13 +303,18 @@ impl CallManager for DummyCallManager {
         (
             Ok(FinishRet {
                 gas_used: 0,
+                call_gas: Vec::new(),
                 backtrace: Backtrace {
                     frames: Vec::new(),
                     cause: None,
                 },
+                out_of_gas: None,
                 exec_trace: Vec::new(),
                 events: Vec::new(),
                 events_root: None,
+                artifacts: Vec::new(),
+                max_call_depth: 0,
+                blockstore_stats: Default::default(),
             }),
             self.machine,
         )
@@ -354,6 +370,18 @@ impl CallManager for DummyCallManager {
         todo!()
     }
 
+    fn call_depth(&self) -> u32 {
+        todo!()
+    }
+
+    fn call_stack(&self) -> &[CallFrame] {
+        todo!()
+    }
+
+    fn record_consensus_fault_verification(&self) -> u32 {
+        todo!()
+    }
+
     fn limiter_mut(&mut self) -> &mut <Self::Machine as Machine>::Limiter {
         &mut self.limits
     }
@@ -362,6 +390,10 @@ impl CallManager for DummyCallManager {
         todo!()
     }
 
+    fn append_artifact(&mut self, _artifact: DebugArtifact) {
+        todo!()
+    }
+
     fn resolve_address(&self, address: &Address) -> fvm::kernel::Result<Option<ActorID>> {
         self.machine.state_tree().lookup_id(address)
     }
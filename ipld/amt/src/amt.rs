@@ -6,6 +6,7 @@ use anyhow::anyhow;
 use cid::multihash::Code;
 use cid::Cid;
 use fvm_ipld_blockstore::Blockstore;
+use fvm_ipld_collections::IpldArray;
 use fvm_ipld_encoding::de::DeserializeOwned;
 use fvm_ipld_encoding::ser::Serialize;
 use fvm_ipld_encoding::serde::Deserialize;
@@ -502,3 +503,35 @@ where
         Ok(())
     }
 }
+
+impl<V, BS, Ver> IpldArray<V> for AmtImpl<V, BS, Ver>
+where
+    V: DeserializeOwned + Serialize,
+    BS: Blockstore,
+    Ver: AmtVersion,
+{
+    type Error = Error;
+
+    fn get(&self, index: u64) -> Result<Option<&V>, Error> {
+        AmtImpl::get(self, index)
+    }
+
+    fn set(&mut self, index: u64, value: V) -> Result<(), Error> {
+        AmtImpl::set(self, index, value)
+    }
+
+    fn delete(&mut self, index: u64) -> Result<Option<V>, Error> {
+        AmtImpl::delete(self, index)
+    }
+
+    fn flush(&mut self) -> Result<Cid, Error> {
+        AmtImpl::flush(self)
+    }
+
+    fn for_each<F>(&self, f: F) -> Result<(), Error>
+    where
+        F: FnMut(u64, &V) -> anyhow::Result<()>,
+    {
+        AmtImpl::for_each(self, f)
+    }
+}
@@ -0,0 +1,76 @@
+// Copyright 2021-2023 Protocol Labs
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+//! Generic traits over the FVM's IPLD collections, so code manipulating maps and arrays (e.g.,
+//! actor state and state migrations) can be written once and run against the HAMT, the KAMT, the
+//! AMT, or the in-memory [`MemoryMap`] and [`MemoryArray`] (e.g., in tests).
+//!
+//! ```
+//! use fvm_ipld_collections::{IpldMap, MemoryMap};
+//!
+//! fn credit<M: IpldMap<u64, u64>>(balances: &mut M, id: u64, amount: u64) -> Result<(), M::Error> {
+//!     let balance = balances.get(&id)?.copied().unwrap_or_default();
+//!     balances.set(id, balance + amount)?;
+//!     Ok(())
+//! }
+//!
+//! let mut balances = MemoryMap::new();
+//! credit(&mut balances, 100, 10).unwrap();
+//! credit(&mut balances, 100, 5).unwrap();
+//! assert_eq!(balances.get(&100).unwrap(), Some(&15));
+//! ```
+
+use cid::Cid;
+
+mod memory;
+pub use memory::{Error, MemoryArray, MemoryMap};
+
+/// A map from keys to values, persisted as IPLD.
+pub trait IpldMap<K, V> {
+    /// The error returned by the map's operations.
+    type Error: std::error::Error + Send + Sync + 'static;
+
+    /// Returns the value associated with the key, if any.
+    fn get(&self, key: &K) -> Result<Option<&V>, Self::Error>;
+
+    /// Associates the value with the key, returning the value previously associated with it, if
+    /// any.
+    fn set(&mut self, key: K, value: V) -> Result<Option<V>, Self::Error>;
+
+    /// Removes the key from the map, returning the value previously associated with it, if any.
+    fn delete(&mut self, key: &K) -> Result<Option<V>, Self::Error>;
+
+    /// Persists the map's pending changes, returning the CID of its root.
+    fn flush(&mut self) -> Result<Cid, Self::Error>;
+
+    /// Calls the function on every key-value pair in the map, stopping at the first error.
+    ///
+    /// The iteration order is specific to the implementation.
+    fn for_each<F>(&self, f: F) -> Result<(), Self::Error>
+    where
+        F: FnMut(&K, &V) -> anyhow::Result<()>;
+}
+
+/// A sparse array of values indexed by `u64`, persisted as IPLD.
+pub trait IpldArray<V> {
+    /// The error returned by the array's operations.
+    type Error: std::error::Error + Send + Sync + 'static;
+
+    /// Returns the value at the index, if any.
+    fn get(&self, index: u64) -> Result<Option<&V>, Self::Error>;
+
+    /// Sets the value at the index.
+    fn set(&mut self, index: u64, value: V) -> Result<(), Self::Error>;
+
+    /// Removes the value at the index, returning it, if any.
+    fn delete(&mut self, index: u64) -> Result<Option<V>, Self::Error>;
+
+    /// Persists the array's pending changes, returning the CID of its root.
+    fn flush(&mut self) -> Result<Cid, Self::Error>;
+
+    /// Calls the function on every value in the array, in index order, stopping at the first
+    /// error.
+    fn for_each<F>(&self, f: F) -> Result<(), Self::Error>
+    where
+        F: FnMut(u64, &V) -> anyhow::Result<()>;
+}
@@ -0,0 +1,192 @@
+// Copyright 2021-2023 Protocol Labs
+// SPDX-License-Identifier: Apache-2.0, MIT
+use std::collections::BTreeMap;
+
+use cid::Cid;
+use fvm_ipld_blockstore::Block;
+use fvm_ipld_encoding::{to_vec, Error as EncodingError, DAG_CBOR};
+use multihash::Code;
+use serde::Serialize;
+use thiserror::Error;
+
+use crate::{IpldArray, IpldMap};
+
+/// The error returned by [`MemoryMap`] and [`MemoryArray`].
+#[derive(Debug, Error)]
+pub enum Error {
+    /// Dynamic error for when the error needs to be forwarded as is.
+    #[error("{0}")]
+    Dynamic(anyhow::Error),
+}
+
+impl From<anyhow::Error> for Error {
+    fn from(e: anyhow::Error) -> Self {
+        Self::Dynamic(e)
+    }
+}
+
+impl From<EncodingError> for Error {
+    fn from(e: EncodingError) -> Self {
+        Self::Dynamic(anyhow::anyhow!(e))
+    }
+}
+
+/// Returns the CID of the DAG-CBOR encoding of the entries. Nothing is written to a blockstore.
+fn entries_cid<K: Serialize, V: Serialize>(entries: &BTreeMap<K, V>) -> Result<Cid, Error> {
+    let entries: Vec<_> = entries.iter().collect();
+    Ok(Block::new(DAG_CBOR, to_vec(&entries)?).cid(Code::Blake2b256))
+}
+
+/// An [`IpldMap`] held in memory, iterated in key order.
+///
+/// "Flushing" the map returns the CID of the DAG-CBOR encoding of its entries (as a list of
+/// key-value pairs), without storing it anywhere: maps with the same entries have the same CID,
+/// but it's unrelated to the CID of a HAMT or KAMT with the same entries.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MemoryMap<K, V> {
+    entries: BTreeMap<K, V>,
+}
+
+impl<K, V> Default for MemoryMap<K, V> {
+    fn default() -> Self {
+        Self {
+            entries: BTreeMap::new(),
+        }
+    }
+}
+
+impl<K, V> MemoryMap<K, V> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the number of entries in the map.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns true if the map has no entries.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Returns the map's entries.
+    pub fn into_inner(self) -> BTreeMap<K, V> {
+        self.entries
+    }
+}
+
+impl<K: Ord, V> FromIterator<(K, V)> for MemoryMap<K, V> {
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        Self {
+            entries: iter.into_iter().collect(),
+        }
+    }
+}
+
+impl<K, V> IpldMap<K, V> for MemoryMap<K, V>
+where
+    K: Ord + Serialize,
+    V: Serialize,
+{
+    type Error = Error;
+
+    fn get(&self, key: &K) -> Result<Option<&V>, Error> {
+        Ok(self.entries.get(key))
+    }
+
+    fn set(&mut self, key: K, value: V) -> Result<Option<V>, Error> {
+        Ok(self.entries.insert(key, value))
+    }
+
+    fn delete(&mut self, key: &K) -> Result<Option<V>, Error> {
+        Ok(self.entries.remove(key))
+    }
+
+    fn flush(&mut self) -> Result<Cid, Error> {
+        entries_cid(&self.entries)
+    }
+
+    fn for_each<F>(&self, mut f: F) -> Result<(), Error>
+    where
+        F: FnMut(&K, &V) -> anyhow::Result<()>,
+    {
+        for (k, v) in &self.entries {
+            f(k, v)?;
+        }
+        Ok(())
+    }
+}
+
+/// An [`IpldArray`] held in memory.
+///
+/// "Flushing" the array returns the CID of the DAG-CBOR encoding of its entries (as a list of
+/// index-value pairs), without storing it anywhere: arrays with the same entries have the same
+/// CID, but it's unrelated to the CID of an AMT with the same entries.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MemoryArray<V> {
+    entries: BTreeMap<u64, V>,
+}
+
+impl<V> Default for MemoryArray<V> {
+    fn default() -> Self {
+        Self {
+            entries: BTreeMap::new(),
+        }
+    }
+}
+
+impl<V> MemoryArray<V> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the number of values in the array.
+    pub fn count(&self) -> u64 {
+        self.entries.len() as u64
+    }
+
+    /// Returns the array's values, by index.
+    pub fn into_inner(self) -> BTreeMap<u64, V> {
+        self.entries
+    }
+}
+
+impl<V> FromIterator<(u64, V)> for MemoryArray<V> {
+    fn from_iter<I: IntoIterator<Item = (u64, V)>>(iter: I) -> Self {
+        Self {
+            entries: iter.into_iter().collect(),
+        }
+    }
+}
+
+impl<V: Serialize> IpldArray<V> for MemoryArray<V> {
+    type Error = Error;
+
+    fn get(&self, index: u64) -> Result<Option<&V>, Error> {
+        Ok(self.entries.get(&index))
+    }
+
+    fn set(&mut self, index: u64, value: V) -> Result<(), Error> {
+        self.entries.insert(index, value);
+        Ok(())
+    }
+
+    fn delete(&mut self, index: u64) -> Result<Option<V>, Error> {
+        Ok(self.entries.remove(&index))
+    }
+
+    fn flush(&mut self) -> Result<Cid, Error> {
+        entries_cid(&self.entries)
+    }
+
+    fn for_each<F>(&self, mut f: F) -> Result<(), Error>
+    where
+        F: FnMut(u64, &V) -> anyhow::Result<()>,
+    {
+        for (&i, v) in &self.entries {
+            f(i, v)?;
+        }
+        Ok(())
+    }
+}
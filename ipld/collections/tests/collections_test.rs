@@ -0,0 +1,105 @@
+// Copyright 2021-2023 Protocol Labs
+// SPDX-License-Identifier: Apache-2.0, MIT
+use std::collections::BTreeMap;
+
+use fvm_ipld_amt::Amt;
+use fvm_ipld_blockstore::MemoryBlockstore;
+use fvm_ipld_collections::{IpldArray, IpldMap, MemoryArray, MemoryMap};
+use fvm_ipld_hamt::Hamt;
+use fvm_ipld_kamt::id::Identity;
+use fvm_ipld_kamt::{Config, Kamt};
+
+/// Exercises a map, returning its final entries.
+fn exercise_map<M: IpldMap<u64, String>>(map: &mut M) -> BTreeMap<u64, String> {
+    for i in 0..100 {
+        assert_eq!(map.set(i, format!("{}", i)).unwrap(), None);
+    }
+    assert_eq!(map.get(&7).unwrap(), Some(&"7".to_string()));
+    assert_eq!(map.get(&100).unwrap(), None);
+    assert_eq!(
+        map.set(7, "seven".to_string()).unwrap(),
+        Some("7".to_string())
+    );
+    for i in (0..100).step_by(2) {
+        assert_eq!(map.delete(&i).unwrap(), Some(format!("{}", i)));
+    }
+    assert_eq!(map.delete(&0).unwrap(), None);
+
+    let first = map.flush().unwrap();
+    assert_eq!(map.flush().unwrap(), first);
+
+    let mut entries = BTreeMap::new();
+    map.for_each(|&k, v| {
+        entries.insert(k, v.clone());
+        Ok(())
+    })
+    .unwrap();
+
+    let mut visited = 0;
+    let res = map.for_each(|_, _| {
+        visited += 1;
+        anyhow::ensure!(visited < 3, "stop");
+        Ok(())
+    });
+    assert!(res.is_err());
+    assert_eq!(visited, 3);
+
+    entries
+}
+
+/// Exercises an array, returning its final entries (in iteration order).
+fn exercise_array<A: IpldArray<String>>(array: &mut A) -> Vec<(u64, String)> {
+    for i in (0..1000).step_by(10) {
+        array.set(i, format!("{}", i)).unwrap();
+    }
+    assert_eq!(array.get(70).unwrap(), Some(&"70".to_string()));
+    assert_eq!(array.get(71).unwrap(), None);
+    array.set(70, "seventy".to_string()).unwrap();
+    assert_eq!(array.delete(500).unwrap(), Some("500".to_string()));
+    assert_eq!(array.delete(500).unwrap(), None);
+
+    let first = array.flush().unwrap();
+    assert_eq!(array.flush().unwrap(), first);
+
+    let mut entries = Vec::new();
+    array
+        .for_each(|i, v| {
+            entries.push((i, v.clone()));
+            Ok(())
+        })
+        .unwrap();
+    entries
+}
+
+#[test]
+fn maps_behave_alike() {
+    let expected = exercise_map(&mut MemoryMap::new());
+    assert_eq!(expected.len(), 50);
+    assert_eq!(expected[&7], "seven");
+
+    let store = MemoryBlockstore::default();
+    let mut hamt: Hamt<_, String, u64> = Hamt::new_with_bit_width(&store, 5);
+    assert_eq!(exercise_map(&mut hamt), expected);
+
+    let mut kamt: Kamt<_, u64, String, Identity> = Kamt::new_with_config(&store, Config::default());
+    assert_eq!(exercise_map(&mut kamt), expected);
+}
+
+#[test]
+fn arrays_behave_alike() {
+    let expected = exercise_array(&mut MemoryArray::new());
+    assert_eq!(expected.len(), 99);
+
+    let store = MemoryBlockstore::default();
+    let mut amt = Amt::new(&store);
+    assert_eq!(exercise_array(&mut amt), expected);
+}
+
+#[test]
+fn memory_cids_depend_on_entries() {
+    let mut a: MemoryMap<u64, u64> = [(1, 1), (2, 2)].into_iter().collect();
+    let mut b: MemoryMap<u64, u64> = [(2, 2), (1, 1)].into_iter().collect();
+    assert_eq!(a.flush().unwrap(), b.flush().unwrap());
+    b.set(3, 3).unwrap();
+    assert_ne!(a.flush().unwrap(), b.flush().unwrap());
+}
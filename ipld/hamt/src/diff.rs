@@ -0,0 +1,159 @@
+// Copyright 2021-2023 Protocol Labs
+// SPDX-License-Identifier: Apache-2.0, MIT
+use fvm_ipld_blockstore::Blockstore;
+use fvm_ipld_encoding::de::DeserializeOwned;
+use fvm_ipld_encoding::CborStore;
+
+use crate::node::Node;
+use crate::pointer::version::Version;
+use crate::pointer::Pointer;
+use crate::Error;
+
+/// A change to a key between two HAMTs, returned by [`Hamt::diff`](crate::Hamt::diff).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Change<K, V> {
+    /// The key was added, with the given value.
+    Add(K, V),
+    /// The key was removed. This is the removed value.
+    Remove(K, V),
+    /// The key's value changed from `before` to `after`.
+    Modify { key: K, before: V, after: V },
+}
+
+impl<K, V> Change<K, V> {
+    /// Returns the changed key.
+    pub fn key(&self) -> &K {
+        match self {
+            Change::Add(key, _) | Change::Remove(key, _) | Change::Modify { key, .. } => key,
+        }
+    }
+}
+
+/// Appends the changes from node `a` (in `store_a`) to node `b` (in `store_b`) to `changes`,
+/// walking both nodes in parallel and skipping identical subtrees.
+pub(crate) fn diff_nodes<K, V, H, Ver, BS>(
+    store_a: &BS,
+    a: &Node<K, V, H, Ver>,
+    store_b: &BS,
+    b: &Node<K, V, H, Ver>,
+    bit_width: u32,
+    changes: &mut Vec<Change<K, V>>,
+) -> Result<(), Error>
+where
+    K: Eq + Clone + DeserializeOwned,
+    V: PartialEq + Clone + DeserializeOwned,
+    Ver: Version,
+    BS: Blockstore,
+{
+    let child = |node: &'_ Node<K, V, H, Ver>, idx| {
+        node.bitfield
+            .test_bit(idx)
+            .then(|| node.index_for_bit_pos(idx))
+    };
+    for idx in 0..(1u32 << bit_width) {
+        let pa = child(a, idx).map(|i| &a.pointers[i]);
+        let pb = child(b, idx).map(|i| &b.pointers[i]);
+        match (pa, pb) {
+            (None, None) => {}
+            (Some(Pointer::Link { cid: ca, .. }), Some(Pointer::Link { cid: cb, .. }))
+                if ca == cb => {}
+            (Some(pa), Some(pb)) => match (load_child(store_a, pa)?, load_child(store_b, pb)?) {
+                (Some(na), Some(nb)) => diff_nodes(store_a, na, store_b, nb, bit_width, changes)?,
+                _ => diff_values(
+                    collect_values(store_a, pa)?,
+                    collect_values(store_b, pb)?,
+                    changes,
+                ),
+            },
+            (Some(pa), None) => changes.extend(
+                collect_values(store_a, pa)?
+                    .into_iter()
+                    .map(|(k, v)| Change::Remove(k.clone(), v.clone())),
+            ),
+            (None, Some(pb)) => changes.extend(
+                collect_values(store_b, pb)?
+                    .into_iter()
+                    .map(|(k, v)| Change::Add(k.clone(), v.clone())),
+            ),
+        }
+    }
+    Ok(())
+}
+
+/// Appends the changes between two (small) sets of key-value pairs to `changes`.
+fn diff_values<K, V>(a: Vec<(&K, &V)>, b: Vec<(&K, &V)>, changes: &mut Vec<Change<K, V>>)
+where
+    K: Eq + Clone,
+    V: PartialEq + Clone,
+{
+    for &(k, va) in &a {
+        match b.iter().find(|(kb, _)| *kb == k) {
+            Some(&(_, vb)) if va != vb => changes.push(Change::Modify {
+                key: k.clone(),
+                before: va.clone(),
+                after: vb.clone(),
+            }),
+            Some(_) => {}
+            None => changes.push(Change::Remove(k.clone(), va.clone())),
+        }
+    }
+    for &(k, vb) in &b {
+        if !a.iter().any(|(ka, _)| *ka == k) {
+            changes.push(Change::Add(k.clone(), vb.clone()));
+        }
+    }
+}
+
+/// Returns the node the pointer links to, loading (and caching) it if necessary, or `None` if
+/// the pointer holds values.
+fn load_child<'a, K, V, H, Ver, BS>(
+    store: &BS,
+    pointer: &'a Pointer<K, V, H, Ver>,
+) -> Result<Option<&'a Node<K, V, H, Ver>>, Error>
+where
+    K: DeserializeOwned,
+    V: DeserializeOwned,
+    Ver: Version,
+    BS: Blockstore,
+{
+    match pointer {
+        Pointer::Link { cid, cache } => {
+            if let Some(cached_node) = cache.get() {
+                return Ok(Some(cached_node));
+            }
+            let node = store
+                .get_cbor::<Node<K, V, H, Ver>>(cid)?
+                .ok_or_else(|| Error::CidNotFound(cid.to_string()))?;
+            // Ignore error intentionally, the cache value will always be the same
+            Ok(Some(cache.get_or_init(|| Box::new(node))))
+        }
+        Pointer::Dirty(node) => Ok(Some(node)),
+        Pointer::Values(_) => Ok(None),
+    }
+}
+
+/// Returns all key-value pairs under the pointer.
+fn collect_values<'a, K, V, H, Ver, BS>(
+    store: &BS,
+    pointer: &'a Pointer<K, V, H, Ver>,
+) -> Result<Vec<(&'a K, &'a V)>, Error>
+where
+    K: DeserializeOwned,
+    V: DeserializeOwned,
+    Ver: Version,
+    BS: Blockstore,
+{
+    let mut values = Vec::new();
+    let mut stack = vec![pointer];
+    while let Some(pointer) = stack.pop() {
+        match load_child(store, pointer)? {
+            Some(node) => stack.extend(&node.pointers),
+            None => {
+                if let Pointer::Values(kvs) = pointer {
+                    values.extend(kvs.iter().map(|kv| (kv.key(), kv.value())));
+                }
+            }
+        }
+    }
+    Ok(values)
+}
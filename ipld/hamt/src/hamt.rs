@@ -8,14 +8,17 @@ use std::marker::PhantomData;
 use cid::Cid;
 use forest_hash_utils::BytesKey;
 use fvm_ipld_blockstore::Blockstore;
+use fvm_ipld_collections::IpldMap;
 use fvm_ipld_encoding::CborStore;
 use multihash::Code;
 use serde::de::DeserializeOwned;
 use serde::{Serialize, Serializer};
 
+use crate::diff::{diff_nodes, Change};
 use crate::iter::IterImpl;
 use crate::node::Node;
 use crate::pointer::version::Version;
+use crate::validate::{validate_node, Problem};
 use crate::{pointer::version, Config, Error, Hash, HashAlgorithm, Sha256};
 
 /// Implementation of the HAMT data structure for IPLD.
@@ -445,6 +448,105 @@ where
         Ok((traversed, next))
     }
 
+    /// Returns the changes from this HAMT to `other`: the keys added, removed, and modified, in no
+    /// particular order. Subtrees shared by both HAMTs (linked by the same CID) are skipped without
+    /// being loaded, so diffing two versions of a large HAMT only loads the nodes that differ.
+    ///
+    /// Both HAMTs must use the same bit width and hash algorithm.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fvm_ipld_hamt::{Change, Hamt};
+    ///
+    /// let store = fvm_ipld_blockstore::MemoryBlockstore::default();
+    ///
+    /// let mut a: Hamt<_, _, u64> = Hamt::new_with_bit_width(&store, 5);
+    /// a.set(1, 1).unwrap();
+    /// a.set(2, 2).unwrap();
+    /// let mut b: Hamt<_, _, u64> = Hamt::new_with_bit_width(&store, 5);
+    /// b.set(2, 3).unwrap();
+    /// b.set(3, 3).unwrap();
+    ///
+    /// let mut changes = a.diff(&b).unwrap();
+    /// changes.sort_by_key(|c| *c.key());
+    /// assert_eq!(
+    ///     changes,
+    ///     vec![
+    ///         Change::Remove(1, 1),
+    ///         Change::Modify { key: 2, before: 2, after: 3 },
+    ///         Change::Add(3, 3),
+    ///     ]
+    /// );
+    /// ```
+    pub fn diff(&self, other: &Self) -> Result<Vec<Change<K, V>>, Error>
+    where
+        K: Clone,
+        V: PartialEq + Clone,
+    {
+        if self.conf.bit_width != other.conf.bit_width {
+            return Err(Error::Dynamic(anyhow::anyhow!(
+                "cannot diff HAMTs with different bit widths ({} and {})",
+                self.conf.bit_width,
+                other.conf.bit_width
+            )));
+        }
+        let mut changes = Vec::new();
+        diff_nodes(
+            &self.store,
+            &self.root,
+            &other.store,
+            &other.root,
+            self.conf.bit_width,
+            &mut changes,
+        )?;
+        Ok(changes)
+    }
+
+    /// Checks the HAMT's structural invariants, returning every problem found instead of stopping
+    /// at the first one. Specifically, this checks that:
+    ///
+    /// - Every node is reachable, and its bitfield matches its pointers.
+    /// - Every bucket is sorted, within the configured size and depth limits, and only contains
+    ///   keys that hash to it.
+    /// - The HAMT is in canonical form (e.g., no nodes that would have been collapsed into their
+    ///   parents on delete).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fvm_ipld_hamt::Hamt;
+    ///
+    /// let store = fvm_ipld_blockstore::MemoryBlockstore::default();
+    /// let mut map: Hamt<_, _, usize> = Hamt::new_with_bit_width(store, 5);
+    /// for i in 0..100 {
+    ///     map.set(i, i).unwrap();
+    /// }
+    /// map.flush().unwrap();
+    /// assert!(map.validate().is_empty());
+    /// ```
+    pub fn validate(&self) -> Vec<Problem> {
+        self.validate_with(|_, _| {})
+    }
+
+    /// Like [`validate`](Self::validate), but also calls `visit` on every key-value pair found,
+    /// including those in otherwise invalid parts of the HAMT.
+    pub fn validate_with<F>(&self, mut visit: F) -> Vec<Problem>
+    where
+        F: FnMut(&K, &V),
+    {
+        let mut problems = Vec::new();
+        validate_node(
+            &self.store,
+            &self.root,
+            &self.conf,
+            &mut Vec::new(),
+            &mut visit,
+            &mut problems,
+        );
+        problems
+    }
+
     /// Consumes this HAMT and returns the Blockstore it owns.
     pub fn into_store(self) -> BS {
         self.store
@@ -542,3 +644,37 @@ where
         self.iter()
     }
 }
+
+impl<BS, V, K, H, Ver> IpldMap<K, V> for HamtImpl<BS, V, K, H, Ver>
+where
+    K: Hash + Eq + PartialOrd + Serialize + DeserializeOwned,
+    V: Serialize + DeserializeOwned + PartialEq,
+    BS: Blockstore,
+    Ver: Version,
+    H: HashAlgorithm,
+{
+    type Error = Error;
+
+    fn get(&self, key: &K) -> Result<Option<&V>, Error> {
+        HamtImpl::get(self, key)
+    }
+
+    fn set(&mut self, key: K, value: V) -> Result<Option<V>, Error> {
+        HamtImpl::set(self, key, value)
+    }
+
+    fn delete(&mut self, key: &K) -> Result<Option<V>, Error> {
+        Ok(HamtImpl::delete(self, key)?.map(|(_, v)| v))
+    }
+
+    fn flush(&mut self) -> Result<Cid, Error> {
+        HamtImpl::flush(self)
+    }
+
+    fn for_each<F>(&self, f: F) -> Result<(), Error>
+    where
+        F: FnMut(&K, &V) -> anyhow::Result<()>,
+    {
+        HamtImpl::for_each(self, f)
+    }
+}
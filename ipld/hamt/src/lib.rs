@@ -11,6 +11,7 @@
 //! The Hamt is a data structure that mimmics a HashMap which has the features of being sharded, persisted, and indexable by a Cid. The Hamt supports a variable bit width to adjust the amount of possible pointers that can exist at each height of the tree. Hamt can be modified at any point, but the underlying values are only persisted to the store when the [flush](struct.Hamt.html#method.flush) is called.
 
 mod bitfield;
+mod diff;
 mod error;
 mod hamt;
 mod hash;
@@ -19,15 +20,18 @@ mod hash_bits;
 mod iter;
 mod node;
 mod pointer;
+mod validate;
 
 pub use forest_hash_utils::{BytesKey, Hash};
 use serde::{Deserialize, Serialize};
 
+pub use self::diff::Change;
 pub use self::error::Error;
 pub use self::hamt::{Hamt, Hamtv0};
 pub use self::hash::*;
 pub use self::hash_algorithm::*;
 pub use self::iter::{Iter, Iterv0};
+pub use self::validate::Problem;
 
 /// Default bit width for indexing a hash at each depth level
 #[deprecated]
@@ -0,0 +1,182 @@
+// Copyright 2021-2023 Protocol Labs
+// SPDX-License-Identifier: Apache-2.0, MIT
+use std::cmp::Ordering;
+use std::fmt;
+
+use fvm_ipld_blockstore::Blockstore;
+use fvm_ipld_encoding::de::DeserializeOwned;
+use fvm_ipld_encoding::CborStore;
+
+use crate::hash_bits::HashBits;
+use crate::node::Node;
+use crate::pointer::version::Version;
+use crate::pointer::Pointer;
+use crate::{Config, Hash, HashAlgorithm, KeyValuePair};
+
+/// A structural problem found in a HAMT by [`Hamt::validate`](crate::Hamt::validate).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Problem {
+    /// The path from the root to the offending node or bucket: the index of each pointer followed.
+    pub path: Vec<u32>,
+    /// A description of the problem.
+    pub description: String,
+}
+
+impl fmt::Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} at /", self.description)?;
+        for (i, idx) in self.path.iter().enumerate() {
+            if i > 0 {
+                f.write_str("/")?;
+            }
+            write!(f, "{}", idx)?;
+        }
+        Ok(())
+    }
+}
+
+/// Appends the problems found in `node` (at `path`) and its children to `problems`, calling
+/// `visit` on every key-value pair found along the way.
+pub(crate) fn validate_node<K, V, H, Ver, BS, F>(
+    store: &BS,
+    node: &Node<K, V, H, Ver>,
+    conf: &Config,
+    path: &mut Vec<u32>,
+    visit: &mut F,
+    problems: &mut Vec<Problem>,
+) where
+    K: Hash + PartialOrd + DeserializeOwned,
+    V: DeserializeOwned,
+    H: HashAlgorithm,
+    Ver: Version,
+    BS: Blockstore,
+    F: FnMut(&K, &V),
+{
+    let indices: Vec<u32> = (0..(1u32 << conf.bit_width))
+        .filter(|&idx| node.bitfield.test_bit(idx))
+        .collect();
+    if indices.len() != node.bitfield.count_ones() {
+        problems.push(problem(path, "bitfield has bits set beyond the bit width"));
+        return;
+    }
+    if indices.len() != node.pointers.len() {
+        problems.push(problem(
+            path,
+            format!(
+                "bitfield has {} bits set, but the node has {} pointers",
+                indices.len(),
+                node.pointers.len()
+            ),
+        ));
+        return;
+    }
+    if node.pointers.is_empty() && !path.is_empty() {
+        problems.push(problem(path, "empty node"));
+    }
+
+    let depth = path.len() as u32;
+    for (idx, pointer) in indices.into_iter().zip(&node.pointers) {
+        path.push(idx);
+        let loaded;
+        let child = match pointer {
+            Pointer::Values(kvs) => {
+                validate_bucket::<K, V, H>(kvs, conf, path, problems);
+                for kv in kvs {
+                    visit(kv.key(), kv.value());
+                }
+                None
+            }
+            Pointer::Dirty(node) => Some(&**node),
+            Pointer::Link { cid, cache } => match cache.get() {
+                Some(node) => Some(&**node),
+                None => match store.get_cbor::<Node<K, V, H, Ver>>(cid) {
+                    Ok(Some(node)) => {
+                        loaded = node;
+                        Some(&loaded)
+                    }
+                    Ok(None) => {
+                        problems.push(problem(path, format!("missing node {}", cid)));
+                        None
+                    }
+                    Err(e) => {
+                        problems.push(problem(path, format!("failed to load node {}: {}", cid, e)));
+                        None
+                    }
+                },
+            },
+        };
+        if let Some(child) = child {
+            // Nodes holding few enough values are collapsed into their parents on delete.
+            let collapsible = depth >= conf.min_data_depth
+                && !child.pointers.is_empty()
+                && child.pointers.len() <= conf.max_array_width
+                && child
+                    .pointers
+                    .iter()
+                    .map(|p| match p {
+                        Pointer::Values(kvs) => Some(kvs.len()),
+                        _ => None,
+                    })
+                    .sum::<Option<usize>>()
+                    .map_or(false, |n| n <= conf.max_array_width);
+            if collapsible {
+                problems.push(problem(
+                    path,
+                    "node should have been collapsed into its parent",
+                ));
+            }
+            validate_node(store, child, conf, path, visit, problems);
+        }
+        path.pop();
+    }
+}
+
+/// Appends the problems found in the bucket at `path` to `problems`.
+fn validate_bucket<K, V, H>(
+    kvs: &[KeyValuePair<K, V>],
+    conf: &Config,
+    path: &[u32],
+    problems: &mut Vec<Problem>,
+) where
+    K: Hash + PartialOrd,
+    H: HashAlgorithm,
+{
+    if kvs.is_empty() {
+        problems.push(problem(path, "empty bucket"));
+    }
+    if kvs.len() > conf.max_array_width {
+        problems.push(problem(
+            path,
+            format!(
+                "bucket has {} entries, more than the maximum of {}",
+                kvs.len(),
+                conf.max_array_width
+            ),
+        ));
+    }
+    if (path.len() as u32) <= conf.min_data_depth {
+        problems.push(problem(path, "bucket above the minimum data depth"));
+    }
+    if kvs
+        .windows(2)
+        .any(|w| w[0].key().partial_cmp(w[1].key()) != Some(Ordering::Less))
+    {
+        problems.push(problem(path, "bucket keys aren't sorted"));
+    }
+    let misplaced = kvs.iter().any(|kv| {
+        let hash = H::hash(kv.key());
+        let mut bits = HashBits::new(&hash);
+        path.iter()
+            .any(|&idx| bits.next(conf.bit_width).map_or(true, |b| b != idx))
+    });
+    if misplaced {
+        problems.push(problem(path, "bucket contains keys that hash elsewhere"));
+    }
+}
+
+fn problem(path: &[u32], description: impl Into<String>) -> Problem {
+    Problem {
+        path: path.to_vec(),
+        description: description.into(),
+    }
+}
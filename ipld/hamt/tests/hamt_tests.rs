@@ -13,7 +13,7 @@ use fvm_ipld_encoding::strict_bytes::ByteBuf;
 use fvm_ipld_encoding::CborStore;
 #[cfg(feature = "identity")]
 use fvm_ipld_hamt::Identity;
-use fvm_ipld_hamt::{BytesKey, Config, Error, Hamt, Hash};
+use fvm_ipld_hamt::{BytesKey, Change, Config, Error, Hamt, Hash};
 use multihash::Code;
 use quickcheck::Arbitrary;
 use rand::seq::SliceRandom;
@@ -579,6 +579,81 @@ fn for_each_ranged(
     }
 }
 
+fn diff(size_factor: usize, factory: HamtFactory) {
+    let store = MemoryBlockstore::default();
+
+    let mut hamt: Hamt<_, usize> = factory.new_with_bit_width(&store, 5);
+    for i in 0..size_factor {
+        hamt.set(tstring(i), i).unwrap();
+    }
+    let before = hamt.flush().unwrap();
+
+    // Delete every third key, modify every fifth remaining key, and add a few more.
+    let mut expected = Vec::new();
+    for i in 0..size_factor {
+        if i % 3 == 0 {
+            hamt.delete(&tstring(i)).unwrap();
+            expected.push(Change::Remove(tstring(i), i));
+        } else if i % 5 == 0 {
+            hamt.set(tstring(i), i + 1).unwrap();
+            expected.push(Change::Modify {
+                key: tstring(i),
+                before: i,
+                after: i + 1,
+            });
+        }
+    }
+    for i in size_factor..size_factor + size_factor / 4 + 1 {
+        hamt.set(tstring(i), i).unwrap();
+        expected.push(Change::Add(tstring(i), i));
+    }
+    let after = hamt.flush().unwrap();
+
+    let a: Hamt<_, usize> = factory.load_with_bit_width(&before, &store, 5).unwrap();
+    let b: Hamt<_, usize> = factory.load_with_bit_width(&after, &store, 5).unwrap();
+
+    let mut changes = a.diff(&b).unwrap();
+    changes.sort_by_key(|c| c.key().0.clone());
+    expected.sort_by_key(|c| c.key().0.clone());
+    assert_eq!(changes, expected);
+
+    // Diffing a HAMT with itself yields nothing.
+    assert!(a.diff(&a).unwrap().is_empty());
+}
+
+fn validate(size_factor: usize, factory: HamtFactory) {
+    let store = MemoryBlockstore::default();
+
+    let mut hamt: Hamt<_, usize> = factory.new_with_bit_width(&store, 5);
+    for i in 0..size_factor {
+        hamt.set(tstring(i), i).unwrap();
+    }
+    for i in (0..size_factor).step_by(3) {
+        hamt.delete(&tstring(i)).unwrap();
+    }
+    let root = hamt.flush().unwrap();
+    let expected = size_factor - (size_factor + 2) / 3;
+
+    let hamt: Hamt<_, usize> = factory.load_with_bit_width(&root, &store, 5).unwrap();
+    let mut visited = 0;
+    assert_eq!(hamt.validate_with(|_, _| visited += 1), Vec::new());
+    assert_eq!(visited, expected);
+
+    // Only keep the root node: every other node is reported missing, and everything else is
+    // still checked.
+    let partial = MemoryBlockstore::default();
+    partial
+        .put_keyed(&root, &store.get(&root).unwrap().unwrap())
+        .unwrap();
+    let hamt: Hamt<_, usize> = factory.load_with_bit_width(&root, &partial, 5).unwrap();
+    let mut visited = 0;
+    let problems = hamt.validate_with(|_, _| visited += 1);
+    assert!(problems
+        .iter()
+        .all(|p| p.description.starts_with("missing node") && p.path.len() == 1));
+    assert_eq!(problems.is_empty(), visited == expected);
+}
+
 #[cfg(feature = "identity")]
 fn add_and_remove_keys(
     bit_width: u32,
@@ -1144,6 +1219,20 @@ macro_rules! test_hamt_mod {
                 }
             }
 
+            #[test]
+            fn diff() {
+                for s in super::SIZE_FACTORS {
+                    super::diff(*s, $factory)
+                }
+            }
+
+            #[test]
+            fn validate() {
+                for s in super::SIZE_FACTORS {
+                    super::validate(*s, $factory)
+                }
+            }
+
             #[test]
             fn clean_child_ordering() {
                 super::clean_child_ordering($factory, None, CidChecker::empty())
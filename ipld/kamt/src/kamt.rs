@@ -6,6 +6,7 @@ use std::borrow::Borrow;
 
 use cid::Cid;
 use fvm_ipld_blockstore::Blockstore;
+use fvm_ipld_collections::IpldMap;
 use fvm_ipld_encoding::CborStore;
 use multihash::Code;
 use serde::de::DeserializeOwned;
@@ -355,3 +356,36 @@ where
         self.root.for_each(self.store.borrow(), &mut f)
     }
 }
+
+impl<BS, K, V, H, const N: usize> IpldMap<K, V> for Kamt<BS, K, V, H, N>
+where
+    K: Serialize + DeserializeOwned + PartialOrd,
+    H: AsHashedKey<K, N>,
+    V: Serialize + DeserializeOwned + PartialEq,
+    BS: Blockstore,
+{
+    type Error = Error;
+
+    fn get(&self, key: &K) -> Result<Option<&V>, Error> {
+        Kamt::get(self, key)
+    }
+
+    fn set(&mut self, key: K, value: V) -> Result<Option<V>, Error> {
+        Kamt::set(self, key, value)
+    }
+
+    fn delete(&mut self, key: &K) -> Result<Option<V>, Error> {
+        Kamt::delete(self, key)
+    }
+
+    fn flush(&mut self) -> Result<Cid, Error> {
+        Kamt::flush(self)
+    }
+
+    fn for_each<F>(&self, f: F) -> Result<(), Error>
+    where
+        F: FnMut(&K, &V) -> anyhow::Result<()>,
+    {
+        Kamt::for_each(self, f)
+    }
+}
@@ -0,0 +1,25 @@
+#!/bin/bash
+#
+# Measures the SDK's minimal-footprint profile: builds the minimal test actor with and without the
+# SDK's (and fvm_shared's) default features, and prints the size of each wasm binary.
+set -euo pipefail
+
+ROOT=$(cd "$(dirname "$0")/.." && pwd)
+PKG=fil_minimal_actor
+TARGET=wasm32-unknown-unknown
+TARGET_DIR="$ROOT/target/actor-size"
+
+# Builds the actor (separately from the other test actors, so that cargo doesn't unify its features
+# with theirs) and prints the size of the binary.
+build() {
+  cargo build --manifest-path "$ROOT/Cargo.toml" -p $PKG --target $TARGET --profile wasm \
+    --target-dir "$TARGET_DIR" "$@" >&2
+  wc -c < "$TARGET_DIR/$TARGET/wasm/$PKG.wasm"
+}
+
+minimal=$(build)
+default=$(build --features fvm_sdk/default,fvm_shared/default)
+
+echo "minimal profile: $minimal bytes"
+echo "default features: $default bytes"
+echo "saved: $((default - minimal)) bytes"
@@ -0,0 +1,81 @@
+// Copyright 2021-2023 Protocol Labs
+// SPDX-License-Identifier: Apache-2.0, MIT
+//! Derive macros for the FVM SDK. Use them through the re-exports in `fvm_sdk`.
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::ext::IdentExt;
+use syn::{parse_macro_input, Data, DeriveInput, Fields, LitStr};
+
+/// Derives `fvm_sdk::event::IntoEvent` for a struct with named fields. See the trait's
+/// documentation for the supported `#[event(...)]` field attributes.
+#[proc_macro_derive(IntoEvent, attributes(event))]
+pub fn derive_into_event(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    into_event(input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+fn into_event(input: DeriveInput) -> syn::Result<TokenStream2> {
+    let fields = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => &fields.named,
+            _ => {
+                return Err(syn::Error::new_spanned(
+                    &input.ident,
+                    "IntoEvent can only be derived for structs with named fields",
+                ))
+            }
+        },
+        _ => {
+            return Err(syn::Error::new_spanned(
+                &input.ident,
+                "IntoEvent can only be derived for structs",
+            ))
+        }
+    };
+
+    let mut entries = Vec::new();
+    for field in fields {
+        let ident = field.ident.as_ref().expect("named field");
+        let mut key = LitStr::new(&ident.unraw().to_string(), ident.span());
+        let mut method = quote!(field);
+        let mut skip = false;
+        for attr in field
+            .attrs
+            .iter()
+            .filter(|attr| attr.path().is_ident("event"))
+        {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("key") {
+                    key = meta.value()?.parse()?;
+                } else if meta.path.is_ident("key_indexed") {
+                    method = quote!(key_indexed_field);
+                } else if meta.path.is_ident("unindexed") {
+                    method = quote!(unindexed_field);
+                } else if meta.path.is_ident("skip") {
+                    skip = true;
+                } else {
+                    return Err(meta.error("unsupported event attribute"));
+                }
+                Ok(())
+            })?;
+        }
+        if !skip {
+            entries.push(quote!(.#method(#key, &self.#ident)));
+        }
+    }
+
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    Ok(quote! {
+        impl #impl_generics ::fvm_sdk::event::IntoEvent for #name #ty_generics #where_clause {
+            fn to_event(&self) -> ::fvm_sdk::event::ActorEvent {
+                ::fvm_sdk::event::EventBuilder::new()
+                    #(#entries)*
+                    .build()
+            }
+        }
+    })
+}
@@ -1,6 +1,8 @@
 // Copyright 2021-2023 Protocol Labs
 // SPDX-License-Identifier: Apache-2.0, MIT
 use core::option::Option;
+use std::cell::RefCell;
+use std::collections::BTreeMap;
 use std::ptr; // no_std
 
 use cid::Cid;
@@ -10,8 +12,16 @@ use fvm_shared::error::ErrorNumber;
 use fvm_shared::{ActorID, MAX_CID_LEN};
 use log::error;
 
+use crate::error::UnresolvedAddressError;
 use crate::{sys, SyscallResult};
 
+thread_local! {
+    /// Addresses resolved by [`resolve_id`] during this invocation.
+    static RESOLVED_IDS: RefCell<BTreeMap<Address, ActorID>> = RefCell::new(BTreeMap::new());
+    /// Delegated addresses found by [`lookup_delegated_address`] during this invocation.
+    static DELEGATED_ADDRESSES: RefCell<BTreeMap<ActorID, Address>> = RefCell::new(BTreeMap::new());
+}
+
 /// Resolves the ID address of an actor. Returns `None` if the address cannot be resolved.
 /// Successfully resolving an address doesn't necessarily mean the actor exists (e.g., if the
 /// addresss was already an actor ID).
@@ -30,9 +40,39 @@ pub fn resolve_address(addr: &Address) -> Option<ActorID> {
     }
 }
 
+/// Resolves the ID address of an actor like [`resolve_address`], but returns a descriptive error if
+/// the address can't be resolved.
+///
+/// Resolved addresses are cached for the rest of the invocation, so resolving the same address
+/// repeatedly only costs a single syscall. Unresolved addresses aren't cached, as the actor may
+/// still be created later in the invocation.
+pub fn resolve_id(addr: &Address) -> Result<ActorID, UnresolvedAddressError> {
+    if let &Payload::ID(id) = addr.payload() {
+        return Ok(id);
+    }
+    if let Some(id) = RESOLVED_IDS.with(|c| c.borrow().get(addr).copied()) {
+        return Ok(id);
+    }
+    let id = resolve_address(addr).ok_or(UnresolvedAddressError(*addr))?;
+    RESOLVED_IDS.with(|c| c.borrow_mut().insert(*addr, id));
+    Ok(id)
+}
+
 /// Looks up the delegated (f4) address of the specified actor. Returns `None` if the actor doesn't
 /// exist or it doesn't have f4 address.
+///
+/// Found addresses are cached for the rest of the invocation (an actor's delegated address can't
+/// change), so looking up the same actor repeatedly only costs a single syscall.
 pub fn lookup_delegated_address(addr: ActorID) -> Option<Address> {
+    if let Some(delegated) = DELEGATED_ADDRESSES.with(|c| c.borrow().get(&addr).copied()) {
+        return Some(delegated);
+    }
+    let delegated = lookup_delegated_address_uncached(addr)?;
+    DELEGATED_ADDRESSES.with(|c| c.borrow_mut().insert(addr, delegated));
+    Some(delegated)
+}
+
+fn lookup_delegated_address_uncached(addr: ActorID) -> Option<Address> {
     let mut out_buffer = [0u8; MAX_ADDRESS_LEN];
     unsafe {
         match sys::actor::lookup_delegated_address(
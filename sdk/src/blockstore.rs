@@ -0,0 +1,78 @@
+// Copyright 2021-2023 Protocol Labs
+// SPDX-License-Identifier: Apache-2.0, MIT
+use anyhow::{anyhow, Result};
+use cid::multihash::Code;
+use cid::Cid;
+use fvm_ipld_blockstore::Block;
+use fvm_shared::error::ErrorNumber;
+
+use crate::ipld;
+
+/// A [`Blockstore`](fvm_ipld_blockstore::Blockstore) backed by the IPLD syscalls, letting actors
+/// use `Hamt`, `Amt`, `CborStore`, etc. directly against their state.
+///
+/// - Reads are only possible for blocks in the actor's reachable set (see [`ipld::get`]). Blocks
+///   outside the reachable set are reported as missing.
+/// - Written blocks are only persisted if they're linked into the actor's state (via
+///   [`sself::set_root`](crate::sself::set_root)) before the end of the current invocation.
+///
+/// ```no_run
+/// use fvm_ipld_encoding::CborStore;
+/// use fvm_sdk::blockstore::ActorBlockstore;
+///
+/// let cid = ActorBlockstore
+///     .put_cbor(&42u64, cid::multihash::Code::Blake2b256)
+///     .unwrap();
+/// let value: Option<u64> = ActorBlockstore.get_cbor(&cid).unwrap();
+/// assert_eq!(value, Some(42));
+/// ```
+#[derive(Copy, Clone, Debug, Default)]
+pub struct ActorBlockstore;
+
+impl fvm_ipld_blockstore::Blockstore for ActorBlockstore {
+    fn get(&self, k: &Cid) -> Result<Option<Vec<u8>>> {
+        match ipld::get(k) {
+            Ok(block) => Ok(Some(block)),
+            Err(ErrorNumber::NotFound) => Ok(None),
+            Err(e) => Err(anyhow!("failed to get block {}: {}", k, e)),
+        }
+    }
+
+    /// Writes the block, failing if the CID computed by the runtime doesn't match the given CID.
+    fn put_keyed(&self, k: &Cid, block: &[u8]) -> Result<()> {
+        let mh = k.hash();
+        let cid = ipld::put(mh.code(), mh.size() as u32, k.codec(), block)
+            .map_err(|e| anyhow!("failed to put block {}: {}", k, e))?;
+        if cid != *k {
+            return Err(anyhow!(
+                "runtime computed CID {} for block, expected {}",
+                cid,
+                k
+            ));
+        }
+        Ok(())
+    }
+
+    /// Writes the block, letting the runtime compute the CID (rather than hashing it in the actor).
+    fn put<D>(&self, mh_code: Code, block: &Block<D>) -> Result<Cid>
+    where
+        D: AsRef<[u8]>,
+    {
+        let mh_size = digest_len(mh_code)?;
+        ipld::put(mh_code.into(), mh_size, block.codec, block.as_ref())
+            .map_err(|e| anyhow!("failed to put block: {}", e))
+    }
+}
+
+/// Returns the digest length of the given multihash, without hashing anything in the actor.
+/// Matches on the multicodec (rather than the `Code` variants, which depend on multihash's enabled
+/// features).
+fn digest_len(mh_code: Code) -> Result<u32> {
+    match u64::from(mh_code) {
+        // sha2-256, keccak-256, blake2b-256
+        0x12 | 0x1b | 0xb220 => Ok(32),
+        // blake2b-512
+        0xb240 => Ok(64),
+        code => Err(anyhow!("unsupported multihash: {:#x}", code)),
+    }
+}
@@ -6,13 +6,14 @@ use fvm_shared::address::Address;
 use fvm_shared::consensus::ConsensusFault;
 use fvm_shared::crypto::hash::SupportedHashes;
 use fvm_shared::crypto::signature::{
-    Signature, SECP_PUB_LEN, SECP_SIG_LEN, SECP_SIG_MESSAGE_HASH_SIZE,
+    Signature, SECP_PUB_LEN, SECP_SIG_COMPONENT_LEN, SECP_SIG_LEN, SECP_SIG_MESSAGE_HASH_SIZE,
 };
 use fvm_shared::piece::PieceInfo;
 use fvm_shared::sector::{
     AggregateSealVerifyProofAndInfos, RegisteredSealProof, ReplicaUpdateInfo, SealVerifyInfo,
     WindowPoStVerifyInfo,
 };
+use fvm_shared::sys::SecpRecoverFlags;
 use fvm_shared::MAX_CID_LEN;
 use num_traits::FromPrimitive;
 
@@ -51,6 +52,29 @@ pub fn recover_secp_public_key(
     unsafe { sys::crypto::recover_secp_public_key(hash.as_ptr(), signature.as_ptr()) }
 }
 
+/// Recovers the signer public key from the message hash and the `r`, `s`, and `v` components of
+/// the signature. `v` may be either the raw recovery id (0 or 1) or the Ethereum-style 27 or 28.
+///
+/// Malleable ("high-s") signatures are rejected with `Forbidden` if `flags` contains
+/// [`SecpRecoverFlags::REJECT_MALLEABLE`].
+pub fn recover_secp_public_key_rsv(
+    hash: &[u8; SECP_SIG_MESSAGE_HASH_SIZE],
+    r: &[u8; SECP_SIG_COMPONENT_LEN],
+    s: &[u8; SECP_SIG_COMPONENT_LEN],
+    v: u8,
+    flags: SecpRecoverFlags,
+) -> SyscallResult<[u8; SECP_PUB_LEN]> {
+    unsafe {
+        sys::crypto::recover_secp_public_key_rsv(
+            hash.as_ptr(),
+            r.as_ptr(),
+            s.as_ptr(),
+            v as u32,
+            flags.bits(),
+        )
+    }
+}
+
 /// Hashes input data using blake2b with 256 bit output.
 pub fn hash_blake2b(data: &[u8]) -> [u8; 32] {
     const BLAKE2B_256: u64 = 0xb220;
@@ -69,6 +93,38 @@ pub fn hash_blake2b(data: &[u8]) -> [u8; 32] {
     ret
 }
 
+/// Hashes input data using keccak256 (as used by Ethereum).
+pub fn hash_keccak(data: &[u8]) -> [u8; 32] {
+    let mut ret = [0u8; 32];
+    hash_into(SupportedHashes::Keccak256, data, &mut ret);
+    ret
+}
+
+/// Recovers the Ethereum address that signed the message hash, given an Ethereum-style
+/// `r || s || v` signature (`v` being either 0/1 or 27/28). The address is the last 20 bytes of
+/// the keccak256 hash of the recovered (uncompressed) public key.
+///
+/// Malleable ("high-s") signatures are accepted, as with Ethereum's `ecrecover`.
+pub fn recover_eth_address(
+    signature: &[u8; SECP_SIG_LEN],
+    hash: &[u8; SECP_SIG_MESSAGE_HASH_SIZE],
+) -> SyscallResult<[u8; 20]> {
+    let (r, rest) = signature.split_at(SECP_SIG_COMPONENT_LEN);
+    let (s, v) = rest.split_at(SECP_SIG_COMPONENT_LEN);
+    let pubkey = recover_secp_public_key_rsv(
+        hash,
+        r.try_into().unwrap(),
+        s.try_into().unwrap(),
+        v[0],
+        SecpRecoverFlags::empty(),
+    )?;
+    // Skip the 0x04 (uncompressed) prefix.
+    let digest = hash_keccak(&pubkey[1..]);
+    let mut addr = [0u8; 20];
+    addr.copy_from_slice(&digest[12..]);
+    Ok(addr)
+}
+
 /// Hashes input data using one of the supported functions.
 /// hashes longer than 64 bytes will be truncated.
 pub fn hash_owned(hasher: SupportedHashes, data: &[u8]) -> Vec<u8> {
@@ -0,0 +1,145 @@
+// Copyright 2021-2023 Protocol Labs
+// SPDX-License-Identifier: Apache-2.0, MIT
+//! Method dispatch, routing incoming method numbers to typed handlers (see [`dispatch!`]), and
+//! [FRC-42](https://github.com/filecoin-project/FIPs/blob/master/FRCs/frc-0042.md) method number
+//! hashing.
+use fvm_ipld_encoding::de::DeserializeOwned;
+use fvm_ipld_encoding::ipld_block::IpldBlock;
+use fvm_ipld_encoding::ser::Serialize;
+use fvm_shared::crypto::hash::SupportedHashes;
+use fvm_shared::error::ExitCode;
+use fvm_shared::method::check_method_name;
+pub use fvm_shared::method::FIRST_METHOD_NUMBER;
+#[doc(hidden)]
+pub use fvm_shared::method_hash as __method_hash;
+use fvm_shared::sys::BlockId;
+use fvm_shared::MethodNum;
+
+use crate::crypto::hash_into;
+use crate::error::MethodNameError;
+use crate::{message, vm, NO_DATA_BLOCK_ID};
+
+/// The exit code [`dispatch!`] aborts with when no handler matches the method number.
+pub const UNHANDLED_MESSAGE: ExitCode = ExitCode::USR_UNHANDLED_MESSAGE;
+
+/// The CBOR encoding of `null`.
+const CBOR_NULL: u8 = 0xf6;
+
+/// Computes the FRC-42 method number of the named method: the first 4-byte (big-endian) chunk of
+/// the Blake2b-512 hash of `"1|" + name` that is at least [`FIRST_METHOD_NUMBER`].
+///
+/// Method names must start with an uppercase ASCII letter or an underscore, and contain only
+/// ASCII letters, digits, and underscores.
+///
+/// This computes the hash with a syscall, which is cheaper than
+/// [`fvm_shared::method::method_hash`] when running as an actor. Prefer
+/// [`fvm_shared::method_hash!`] for method names known at compile time.
+pub fn method_hash(name: &str) -> Result<MethodNum, MethodNameError> {
+    check_method_name(name)?;
+
+    let mut digest = [0u8; 64];
+    hash_into(
+        SupportedHashes::Blake2b512,
+        format!("1|{}", name).as_bytes(),
+        &mut digest,
+    );
+    digest
+        .chunks_exact(4)
+        .map(|chunk| u32::from_be_bytes(chunk.try_into().unwrap()) as MethodNum)
+        .find(|&method| method >= FIRST_METHOD_NUMBER)
+        .ok_or(MethodNameError::IndeterminableId)
+}
+
+/// Invokes a method handler: decodes the parameters (aborting with `USR_SERIALIZATION` on
+/// failure), calls the handler, and returns the block ID of the CBOR-encoded return value. Missing
+/// parameters are decoded from CBOR `null` (e.g., as `()` or `None`), and `null` return values
+/// are returned as [`NO_DATA_BLOCK_ID`].
+///
+/// This is usually called through [`dispatch!`].
+pub fn call<P, R, F>(params: BlockId, handler: F) -> BlockId
+where
+    P: DeserializeOwned,
+    R: Serialize,
+    F: FnOnce(P) -> R,
+{
+    let params = message::params_raw(params).unwrap_or_else(|e| {
+        vm::abort(
+            ExitCode::USR_ASSERTION_FAILED.value(),
+            Some(&format!("failed to read the parameters: {}", e)),
+        )
+    });
+    let decoded = match &params {
+        Some(block) => block.deserialize(),
+        None => fvm_ipld_encoding::from_slice(&[CBOR_NULL]),
+    };
+    let params = decoded.unwrap_or_else(|e| {
+        vm::abort(
+            ExitCode::USR_SERIALIZATION.value(),
+            Some(&format!("failed to decode the parameters: {}", e)),
+        )
+    });
+
+    let ret = match IpldBlock::serialize_cbor(&handler(params)) {
+        Ok(Some(block)) if block.data != [CBOR_NULL] => block,
+        Ok(_) => return NO_DATA_BLOCK_ID,
+        Err(e) => vm::abort(
+            ExitCode::USR_SERIALIZATION.value(),
+            Some(&format!("failed to encode the return value: {}", e)),
+        ),
+    };
+    crate::ipld::put_block(ret.codec, &ret.data).unwrap_or_else(|e| {
+        vm::abort(
+            ExitCode::USR_ASSERTION_FAILED.value(),
+            Some(&format!("failed to write the return value: {}", e)),
+        )
+    })
+}
+
+/// Routes the current invocation to the handler of its method number, returning the block ID of
+/// the handler's return value (see [`call`]). Aborts with `USR_UNHANDLED_MESSAGE` if no handler
+/// matches.
+///
+/// Methods are either given by number, or by name: named methods are numbered with
+/// [`fvm_shared::method_hash!`] (FRC-42), at compile time. Handlers are functions (or closures)
+/// taking the decoded parameters and returning a serializable value.
+///
+/// ```no_run
+/// fn constructor(_: ()) {}
+///
+/// fn transfer((to, amount): (u64, u64)) -> bool {
+///     to != 0 && amount > 0
+/// }
+///
+/// #[no_mangle]
+/// pub fn invoke(params: u32) -> u32 {
+///     fvm_sdk::initialize();
+///     fvm_sdk::dispatch!(params, {
+///         1 => constructor,
+///         Transfer => transfer,
+///     })
+/// }
+/// ```
+#[macro_export]
+macro_rules! dispatch {
+    (@method $method:literal) => {
+        $method
+    };
+    (@method $method:ident) => {
+        $crate::dispatch::__method_hash!(stringify!($method))
+    };
+    ($params:expr, { $($method:tt => $handler:expr),* $(,)? }) => {
+        'dispatch: {
+            let params = $params;
+            let method = $crate::message::method_number();
+            $(
+                if method == $crate::dispatch!(@method $method) {
+                    break 'dispatch $crate::dispatch::call(params, $handler);
+                }
+            )*
+            $crate::vm::abort(
+                $crate::dispatch::UNHANDLED_MESSAGE.value(),
+                Some(&format!("unhandled method {}", method)),
+            )
+        }
+    };
+}
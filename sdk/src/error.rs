@@ -1,5 +1,8 @@
 // Copyright 2021-2023 Protocol Labs
 // SPDX-License-Identifier: Apache-2.0, MIT
+use fvm_ipld_encoding::ipld_block::IpldBlock;
+use fvm_shared::address::Address;
+use fvm_shared::error::{ErrorNumber, ExitCode};
 use thiserror::Error;
 
 #[derive(Copy, Clone, Debug, Error, Eq, PartialEq)]
@@ -14,6 +17,21 @@ pub enum StateUpdateError {
     ReadOnly,
 }
 
+/// An error loading or saving the actor's root state with [`crate::state`].
+#[derive(Clone, Debug, Error, Eq, PartialEq)]
+pub enum StateError {
+    #[error(transparent)]
+    Read(#[from] StateReadError),
+    #[error(transparent)]
+    Update(#[from] StateUpdateError),
+    #[error("failed to read or write the state block: {0}")]
+    Ipld(ErrorNumber),
+    #[error("failed to decode the state: {0}")]
+    Decode(String),
+    #[error("failed to encode the state: {0}")]
+    Encode(String),
+}
+
 #[derive(Copy, Clone, Debug, Error, Eq, PartialEq)]
 pub enum ActorDeleteError {
     #[error("deletion beneficiary is the current actor")]
@@ -22,6 +40,8 @@ pub enum ActorDeleteError {
     BeneficiaryDoesNotExist,
     #[error("current execution context is read-only")]
     ReadOnly,
+    #[error("self-destruct is disallowed on this network")]
+    Disallowed,
 }
 
 #[derive(Copy, Clone, Debug, Error, Eq, PartialEq)]
@@ -31,3 +51,26 @@ pub enum EpochBoundsError {
     #[error("the requested epoch exceeds the maximum lookback")]
     ExceedsLookback,
 }
+
+/// An error sending a message with [`crate::send::SendBuilder`].
+#[derive(Clone, Debug, Error, Eq, PartialEq)]
+pub enum CallError {
+    #[error("failed to encode the parameters: {0}")]
+    EncodeParams(String),
+    #[error("failed to send the message: {0}")]
+    Send(ErrorNumber),
+    #[error("call exited with {exit_code}")]
+    Exit {
+        exit_code: ExitCode,
+        return_data: Option<IpldBlock>,
+    },
+    #[error("failed to decode the return value: {0}")]
+    DecodeReturn(String),
+}
+
+pub use fvm_shared::method::MethodNameError;
+
+/// An address that couldn't be resolved to an actor ID, returned by [`crate::actor::resolve_id`].
+#[derive(Clone, Debug, Error, Eq, PartialEq)]
+#[error("address {0} could not be resolved to an actor ID")]
+pub struct UnresolvedAddressError(pub Address);
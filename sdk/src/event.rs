@@ -1,6 +1,8 @@
 // Copyright 2021-2023 Protocol Labs
 // SPDX-License-Identifier: Apache-2.0, MIT
-use fvm_shared::event::ActorEvent;
+#[cfg(feature = "derive")]
+pub use fvm_sdk_derive::IntoEvent;
+pub use fvm_shared::event::{ActorEvent, Entry, EntryValue, EventBuilder, Flags};
 
 use crate::{sys, SyscallResult};
 
@@ -10,3 +12,68 @@ pub fn emit_event(evt: &ActorEvent) -> SyscallResult<()> {
 
     unsafe { sys::event::emit_event(entries.as_ptr(), entries.len() as u32) }
 }
+
+/// A type that can be emitted as an event. It's implemented for events and [`EventBuilder`], so
+/// built events can be emitted directly:
+///
+/// ```no_run
+/// use fvm_sdk::event::{EventBuilder, IntoEvent};
+///
+/// EventBuilder::new()
+///     .field("type", "transfer")
+///     .field("from", 100u64)
+///     .field("to", 101u64)
+///     .unindexed_field("memo", b"thanks")
+///     .emit()
+///     .unwrap();
+/// ```
+///
+/// With the `derive` feature (enabled by default), it can be derived for structs with named
+/// fields, recording each field as an entry keyed by the field's name. Field values must be
+/// convertible (by reference) into an [`EntryValue`]. Entries are indexed by default (see
+/// [`EventBuilder::field`]), which can be changed with the `#[event(key_indexed)]` and
+/// `#[event(unindexed)]` field attributes. Fields can also be renamed with
+/// `#[event(key = "...")]`, or left out with `#[event(skip)]`:
+///
+/// ```no_run
+/// use fvm_sdk::event::IntoEvent;
+///
+/// #[derive(IntoEvent)]
+/// struct Transfer {
+///     #[event(key = "type")]
+///     kind: String,
+///     from: u64,
+///     to: u64,
+///     #[event(unindexed)]
+///     memo: String,
+/// }
+///
+/// let transfer = Transfer {
+///     kind: "transfer".into(),
+///     from: 100,
+///     to: 101,
+///     memo: "thanks".into(),
+/// };
+/// transfer.emit().unwrap();
+/// ```
+pub trait IntoEvent {
+    /// Converts the value into an event.
+    fn to_event(&self) -> ActorEvent;
+
+    /// Emits the value as an event (see [`emit_event`]).
+    fn emit(&self) -> SyscallResult<()> {
+        emit_event(&self.to_event())
+    }
+}
+
+impl IntoEvent for ActorEvent {
+    fn to_event(&self) -> ActorEvent {
+        self.clone()
+    }
+}
+
+impl IntoEvent for EventBuilder {
+    fn to_event(&self) -> ActorEvent {
+        self.clone().build()
+    }
+}
@@ -82,3 +82,26 @@ pub fn put_block(
 ) -> SyscallResult<fvm_shared::sys::BlockId> {
     unsafe { sys::ipld::block_create(codec, data.as_ptr(), data.len() as u32) }
 }
+
+/// Returns the CIDs linked from the block referenced by BlockId, without decoding it. Only DagCBOR
+/// blocks have links. Available from network version 21.
+pub fn block_links(id: fvm_shared::sys::BlockId) -> SyscallResult<Vec<Cid>> {
+    let mut buf = [0u8; 16 * MAX_CID_LEN];
+    let mut links = Vec::new();
+    loop {
+        let sys::ipld::IpldLinks {
+            total,
+            count,
+            length,
+        } = unsafe {
+            sys::ipld::block_links(id, links.len() as u32, buf.as_mut_ptr(), buf.len() as u32)?
+        };
+        let mut cids = &buf[..length as usize];
+        for _ in 0..count {
+            links.push(Cid::read_bytes(&mut cids).expect("runtime returned an invalid CID"));
+        }
+        if links.len() as u32 >= total {
+            return Ok(links);
+        }
+    }
+}
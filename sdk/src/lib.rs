@@ -1,17 +1,22 @@
 // Copyright 2021-2023 Protocol Labs
 // SPDX-License-Identifier: Apache-2.0, MIT
 pub mod actor;
+#[cfg(feature = "blockstore")]
+pub mod blockstore;
 pub mod crypto;
 pub mod debug;
+pub mod dispatch;
 pub mod error;
 pub mod event;
 pub mod gas;
 pub mod ipld;
+pub mod log;
 pub mod message;
 pub mod network;
 pub mod rand;
 pub mod send;
 pub mod sself;
+pub mod state;
 pub mod sys;
 pub mod vm;
 
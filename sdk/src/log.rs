@@ -0,0 +1,59 @@
+// Copyright 2021-2023 Protocol Labs
+// SPDX-License-Identifier: Apache-2.0, MIT
+//! Leveled logging macros, logging through the debug syscall.
+//!
+//! Messages are prefixed with their level and the module path they were logged from:
+//!
+//! ```no_run
+//! use fvm_sdk::log::info;
+//!
+//! let epoch = fvm_sdk::network::curr_epoch();
+//! info!("starting at epoch {}", epoch); // "[INFO] my_actor: starting at epoch 1234"
+//! ```
+//!
+//! Unlike the `log` crate facade (see [`init_logging`](crate::debug::init_logging)), these don't
+//! require a logger to be installed. When debugging is disabled in the network context, they
+//! don't format their arguments and don't make any syscalls.
+pub use crate::{__log_debug as debug, __log_info as info, __log_warn as warn};
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __log {
+    ($level:literal, $($arg:tt)+) => {
+        if $crate::debug::enabled() {
+            $crate::debug::log(format!(
+                "[{}] {}: {}",
+                $level,
+                module_path!(),
+                format_args!($($arg)+)
+            ));
+        }
+    };
+}
+
+/// Logs a message at the debug level (see the [module documentation](crate::log)).
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __log_debug {
+    ($($arg:tt)+) => {
+        $crate::__log!("DEBUG", $($arg)+)
+    };
+}
+
+/// Logs a message at the info level (see the [module documentation](crate::log)).
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __log_info {
+    ($($arg:tt)+) => {
+        $crate::__log!("INFO", $($arg)+)
+    };
+}
+
+/// Logs a message at the warn level (see the [module documentation](crate::log)).
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __log_warn {
+    ($($arg:tt)+) => {
+        $crate::__log!("WARN", $($arg)+)
+    };
+}
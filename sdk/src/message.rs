@@ -5,7 +5,7 @@ use std::convert::TryInto;
 use fvm_ipld_encoding::ipld_block::IpldBlock;
 use fvm_shared::econ::TokenAmount;
 use fvm_shared::sys::out::vm::MessageContext;
-use fvm_shared::sys::BlockId;
+use fvm_shared::sys::{BlockId, Codec};
 use fvm_shared::{ActorID, MethodNum};
 
 use crate::{sys, SyscallResult, NO_DATA_BLOCK_ID};
@@ -18,6 +18,12 @@ lazy_static::lazy_static! {
     };
 }
 
+/// Returns the message context. The context is looked up (with a syscall) on first use, then cached
+/// for the rest of the invocation, as are the values returned by the typed getters below.
+pub fn context() -> &'static MessageContext {
+    &MESSAGE_CONTEXT
+}
+
 /// Returns the nonce from the (explicit) message.
 #[inline(always)]
 pub fn nonce() -> u64 {
@@ -78,3 +84,31 @@ pub fn params_raw(id: BlockId) -> SyscallResult<Option<IpldBlock>> {
         }))
     }
 }
+
+/// Reads the message parameters into `buf` (replacing its contents, but reusing its allocation),
+/// returning the parameters' codec and the data borrowed from `buf`.
+///
+/// Unlike [`params_raw`], this doesn't allocate a new buffer for every call, and borrowed types can
+/// be deserialized directly from the returned data, without copying large parameters (e.g., proofs)
+/// any further:
+///
+/// ```no_run
+/// # let params_id = 1;
+/// let mut buf = Vec::new();
+/// let (_codec, data) = fvm_sdk::message::read_params(params_id, &mut buf).unwrap().unwrap();
+/// let (sector, proof): (u64, &[u8]) = fvm_ipld_encoding::from_slice(data).unwrap();
+/// ```
+pub fn read_params(id: BlockId, buf: &mut Vec<u8>) -> SyscallResult<Option<(Codec, &[u8])>> {
+    if id == NO_DATA_BLOCK_ID {
+        return Ok(None);
+    }
+    buf.clear();
+    unsafe {
+        let fvm_shared::sys::out::ipld::IpldStat { codec, size } = sys::ipld::block_stat(id)?;
+        buf.reserve_exact(size as usize);
+        let remaining = sys::ipld::block_read(id, 0, buf.as_mut_ptr(), size)?;
+        assert_eq!(remaining, 0, "expected to read the block exactly");
+        buf.set_len(size as usize);
+        Ok(Some((codec, buf.as_slice())))
+    }
+}
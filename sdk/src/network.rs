@@ -5,7 +5,7 @@ use fvm_shared::chainid::ChainID;
 use fvm_shared::clock::ChainEpoch;
 use fvm_shared::econ::TokenAmount;
 use fvm_shared::error::ErrorNumber;
-use fvm_shared::sys::out::network::NetworkContext;
+use fvm_shared::sys::out::network::{NetworkContext, NetworkContextV2};
 use fvm_shared::version::NetworkVersion;
 use fvm_shared::MAX_CID_LEN;
 
@@ -18,6 +18,24 @@ lazy_static::lazy_static! {
             sys::network::context().expect("failed to lookup network context")
         }
     };
+    static ref NETWORK_CONTEXT_V2: NetworkContextV2 = {
+        unsafe {
+            sys::network::context_v2().expect("failed to lookup extended network context")
+        }
+    };
+    static ref TOTAL_FIL_CIRC_SUPPLY: TokenAmount = {
+        unsafe {
+            sys::network::total_fil_circ_supply()
+                .expect("failed to get circulating supply")
+                .into()
+        }
+    };
+}
+
+/// Returns the network context. The context is looked up (with a syscall) on first use, then
+/// cached for the rest of the invocation, as are the values returned by the typed getters below.
+pub fn context() -> &'static NetworkContext {
+    &NETWORK_CONTEXT
 }
 
 pub fn chain_id() -> ChainID {
@@ -36,12 +54,10 @@ pub fn base_fee() -> TokenAmount {
     NETWORK_CONTEXT.base_fee.into()
 }
 
+/// Returns the circulating supply at the current epoch. This is looked up on first use, then
+/// cached for the rest of the invocation.
 pub fn total_fil_circ_supply() -> TokenAmount {
-    unsafe {
-        sys::network::total_fil_circ_supply()
-            .expect("failed to get circulating supply")
-            .into()
-    }
+    TOTAL_FIL_CIRC_SUPPLY.clone()
 }
 
 /// Returns the current block time in seconds since the EPOCH.
@@ -49,6 +65,16 @@ pub fn tipset_timestamp() -> u64 {
     NETWORK_CONTEXT.timestamp
 }
 
+/// Returns the time of the genesis block in seconds since the EPOCH.
+pub fn genesis_timestamp() -> u64 {
+    NETWORK_CONTEXT_V2.genesis_timestamp
+}
+
+/// Returns the maximum amount of gas that may be used by all messages in a block.
+pub fn block_gas_limit() -> u64 {
+    NETWORK_CONTEXT_V2.block_gas_limit
+}
+
 /// Returns the tipset CID of the specified epoch, if available. Allows querying from now up to
 /// finality (900 epochs).
 pub fn tipset_cid(epoch: ChainEpoch) -> Result<Cid, EpochBoundsError> {
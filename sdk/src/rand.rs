@@ -3,7 +3,7 @@
 use fvm_shared::clock::ChainEpoch;
 use fvm_shared::randomness::RANDOMNESS_LENGTH;
 
-use crate::{sys, SyscallResult};
+use crate::{status_code_to_bool, sys, SyscallResult};
 
 /// Gets 32 bytes of randomness from the ticket chain.
 /// The supplied output buffer must have at least 32 bytes of capacity.
@@ -34,3 +34,25 @@ pub fn get_beacon_randomness(
     };
     Ok(ret)
 }
+
+/// Verifies a beacon (currently Drand) entry for the given beacon round against the network's
+/// beacon chain info. Returns `true` if the signature is a valid beacon signature for the round.
+///
+/// For chained beacons, `prev_signature` must be the signature of the previous round. Pass an
+/// empty slice for unchained beacons.
+pub fn verify_beacon_entry(
+    round: u64,
+    prev_signature: &[u8],
+    signature: &[u8],
+) -> SyscallResult<bool> {
+    unsafe {
+        sys::rand::verify_beacon_entry(
+            round,
+            prev_signature.as_ptr(),
+            prev_signature.len() as u32,
+            signature.as_ptr(),
+            signature.len() as u32,
+        )
+        .map(status_code_to_bool)
+    }
+}
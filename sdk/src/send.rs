@@ -2,13 +2,16 @@
 // SPDX-License-Identifier: Apache-2.0, MIT
 use std::convert::TryInto;
 
+use fvm_ipld_encoding::de::DeserializeOwned;
 use fvm_ipld_encoding::ipld_block::IpldBlock;
+use fvm_ipld_encoding::ser::Serialize;
 use fvm_shared::address::Address;
 use fvm_shared::econ::TokenAmount;
 use fvm_shared::error::{ErrorNumber, ExitCode};
 use fvm_shared::sys::SendFlags;
 use fvm_shared::{MethodNum, Response};
 
+use crate::error::CallError;
 use crate::{sys, SyscallResult, NO_DATA_BLOCK_ID};
 
 /// Sends a message to another actor.
@@ -72,3 +75,119 @@ pub fn send(
         })
     }
 }
+
+/// Starts building a message to the given actor, to be sent with [`SendBuilder::call`]. By
+/// default, the message invokes method 0 with no parameters and no value, and may use all the
+/// remaining gas.
+///
+/// ```no_run
+/// use fvm_shared::address::Address;
+/// use fvm_shared::econ::TokenAmount;
+///
+/// let balance: TokenAmount = fvm_sdk::send::to(&Address::new_id(1000))
+///     .method(2)
+///     .params(&(1u64, "foo"))
+///     .value(TokenAmount::from_atto(10))
+///     .call()
+///     .unwrap();
+/// ```
+pub fn to(addr: &Address) -> SendBuilder {
+    SendBuilder {
+        to: *addr,
+        method: 0,
+        params: Ok(None),
+        value: TokenAmount::default(),
+        gas_limit: None,
+        flags: SendFlags::empty(),
+    }
+}
+
+/// A message being built with [`to`].
+#[must_use]
+#[derive(Clone, Debug)]
+pub struct SendBuilder {
+    to: Address,
+    method: MethodNum,
+    /// The encoded parameters, or the error encountered when encoding them.
+    params: Result<Option<IpldBlock>, String>,
+    value: TokenAmount,
+    gas_limit: Option<u64>,
+    flags: SendFlags,
+}
+
+impl SendBuilder {
+    /// Sets the method to invoke.
+    pub fn method(mut self, method: MethodNum) -> Self {
+        self.method = method;
+        self
+    }
+
+    /// Sets the parameters, encoded as CBOR. Encoding errors are reported by
+    /// [`call`](Self::call).
+    pub fn params<T: Serialize + ?Sized>(mut self, params: &T) -> Self {
+        self.params = IpldBlock::serialize_cbor(params).map_err(|e| e.to_string());
+        self
+    }
+
+    /// Sets the already-encoded parameters.
+    pub fn raw_params(mut self, params: Option<IpldBlock>) -> Self {
+        self.params = Ok(params);
+        self
+    }
+
+    /// Sets the value to transfer.
+    pub fn value(mut self, value: TokenAmount) -> Self {
+        self.value = value;
+        self
+    }
+
+    /// Limits the gas available to the call.
+    pub fn gas_limit(mut self, gas_limit: u64) -> Self {
+        self.gas_limit = Some(gas_limit);
+        self
+    }
+
+    /// Sends the message in read-only mode.
+    pub fn read_only(mut self) -> Self {
+        self.flags |= SendFlags::READ_ONLY;
+        self
+    }
+
+    /// Sends the message, returning the raw response (see [`send`]).
+    pub fn send(self) -> Result<Response, CallError> {
+        let params = self.params.map_err(CallError::EncodeParams)?;
+        send(
+            &self.to,
+            self.method,
+            params,
+            self.value,
+            self.gas_limit,
+            self.flags,
+        )
+        .map_err(CallError::Send)
+    }
+
+    /// Sends the message and decodes its return value. Fails if the message can't be sent, if the
+    /// call exits with a non-zero exit code, or if the return value can't be decoded. A call that
+    /// returns nothing is decoded as a CBOR `null` (e.g., into `()` or an `Option`).
+    pub fn call<R: DeserializeOwned>(self) -> Result<R, CallError> {
+        let Response {
+            exit_code,
+            return_data,
+        } = self.send()?;
+        if !exit_code.is_success() {
+            return Err(CallError::Exit {
+                exit_code,
+                return_data,
+            });
+        }
+        let ret = match return_data {
+            Some(block) => block.deserialize(),
+            None => fvm_ipld_encoding::from_slice(&[CBOR_NULL]),
+        };
+        ret.map_err(|e| CallError::DecodeReturn(e.to_string()))
+    }
+}
+
+/// The CBOR encoding of `null`.
+const CBOR_NULL: u8 = 0xf6;
@@ -65,6 +65,7 @@ pub fn self_destruct(beneficiary: &Address) -> Result<(), ActorDeleteError> {
             ErrorNumber::Forbidden => ActorDeleteError::BeneficiaryIsSelf,
             ErrorNumber::ReadOnly => ActorDeleteError::ReadOnly,
             ErrorNumber::NotFound => ActorDeleteError::BeneficiaryDoesNotExist,
+            ErrorNumber::IllegalOperation => ActorDeleteError::Disallowed,
             _ => panic!("unexpected error from `self::self_destruct` syscall: {}", e),
         })
     }
@@ -0,0 +1,58 @@
+// Copyright 2021-2023 Protocol Labs
+// SPDX-License-Identifier: Apache-2.0, MIT
+//! Typed access to the actor's root state.
+use cid::Cid;
+use fvm_ipld_encoding::de::DeserializeOwned;
+use fvm_ipld_encoding::ser::Serialize;
+use fvm_ipld_encoding::DAG_CBOR;
+
+use crate::error::StateError;
+use crate::{ipld, sself};
+
+/// The multihash code (blake2b-256) and digest length of state blocks.
+const BLAKE2B_256: u64 = 0xb220;
+const BLAKE2B_LEN: u32 = 32;
+
+/// Loads and decodes the actor's root state (which must be DAG-CBOR).
+pub fn load<S: DeserializeOwned>() -> Result<S, StateError> {
+    let root = sself::root()?;
+    let block = ipld::get(&root).map_err(StateError::Ipld)?;
+    fvm_ipld_encoding::from_slice(&block).map_err(|e| StateError::Decode(e.to_string()))
+}
+
+/// Encodes and stores the given state as DAG-CBOR, and sets it as the actor's root state,
+/// returning the new root.
+pub fn save<S: Serialize>(state: &S) -> Result<Cid, StateError> {
+    let block = fvm_ipld_encoding::to_vec(state).map_err(|e| StateError::Encode(e.to_string()))?;
+    let root = ipld::put(BLAKE2B_256, BLAKE2B_LEN, DAG_CBOR, &block).map_err(StateError::Ipld)?;
+    sself::set_root(&root)?;
+    Ok(root)
+}
+
+/// Loads the actor's root state, passes it to `f` to be mutated, and saves it as the new root
+/// state if (and only if) `f` succeeds. The root state is left untouched if `f` or saving the state
+/// fails.
+///
+/// # Example
+///
+/// ```no_run
+/// use fvm_sdk::error::StateError;
+///
+/// // An actor whose state is a simple counter.
+/// let count = fvm_sdk::state::transaction(|count: &mut u64| {
+///     *count += 1;
+///     Ok::<_, StateError>(*count)
+/// })
+/// .unwrap();
+/// ```
+pub fn transaction<S, R, E, F>(f: F) -> Result<R, E>
+where
+    S: Serialize + DeserializeOwned,
+    E: From<StateError>,
+    F: FnOnce(&mut S) -> Result<R, E>,
+{
+    let mut state = load()?;
+    let ret = f(&mut state)?;
+    save(&state)?;
+    Ok(ret)
+}
@@ -57,6 +57,32 @@ super::fvm_syscalls! {
         sig_off: *const u8,
     ) -> Result<[u8; SECP_PUB_LEN]>;
 
+    /// Recovers the signer public key from a signed message hash and the `r`, `s`, and `v`
+    /// components of its signature.
+    ///
+    /// Returns the public key in uncompressed 65 bytes form.
+    ///
+    /// # Arguments
+    ///
+    /// - `hash_off` specify location of a 32-byte message hash.
+    /// - `r_off` and `s_off` specify the locations of the 32-byte `r` and `s` components.
+    /// - `v` is the recovery id, either 0/1 or the Ethereum-style 27/28.
+    /// - `flags` are the [`SecpRecoverFlags`](fvm_shared::sys::SecpRecoverFlags).
+    ///
+    /// # Errors
+    ///
+    /// | Error               | Reason                                                         |
+    /// |---------------------|----------------------------------------------------------------|
+    /// | [`IllegalArgument`] | invalid buffers, recovery id, flags, or out of range `r`/`s`   |
+    /// | [`Forbidden`]       | the signature is malleable and `REJECT_MALLEABLE` was passed   |
+    pub fn recover_secp_public_key_rsv(
+        hash_off: *const u8,
+        r_off: *const u8,
+        s_off: *const u8,
+        v: u32,
+        flags: u64,
+    ) -> Result<[u8; SECP_PUB_LEN]>;
+
 
     /// Hashes input data using the specified hash function. The digest is written to the passed
     /// digest buffer and truncated to `digest_len`.
@@ -142,10 +168,11 @@ super::fvm_syscalls! {
     ///
     /// # Errors
     ///
-    /// | Error               | Reason                                |
-    /// |---------------------|---------------------------------------|
-    /// | [`LimitExceeded`]   | exceeded lookback limit finding block |
-    /// | [`IllegalArgument`] | an argument is malformed              |
+    /// | Error               | Reason                                                       |
+    /// |---------------------|--------------------------------------------------------------|
+    /// | [`LimitExceeded`]   | exceeded lookback limit finding block                        |
+    /// | [`LimitExceeded`]   | exceeded the network's per-message verification limit        |
+    /// | [`IllegalArgument`] | an argument is malformed                                     |
     pub fn verify_consensus_fault(
         h1_off: *const u8,
         h1_len: u32,
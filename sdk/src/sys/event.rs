@@ -15,9 +15,13 @@ super::fvm_syscalls! {
     ///
     /// # Errors
     ///
-    /// | Error               | Reason                                                              |
-    /// |---------------------|---------------------------------------------------------------------|
-    /// | [`IllegalArgument`] | entries failed to validate due to improper encoding or invalid data |
+    /// | Error               | Reason                                                                  |
+    /// |---------------------|-------------------------------------------------------------------------|
+    /// | [`Serialization`]   | the event failed to decode (if the network returns precise errors)      |
+    /// | [`LimitExceeded`]   | the event exceeds the network's limits (if it returns precise errors)   |
+    /// | [`IllegalCodec`]    | an entry's value codec isn't `IPLD_RAW`                                 |
+    /// | [`IllegalArgument`] | the event is invalid, or the passed buffer isn't valid, in memory, etc. |
+    /// | [`ReadOnly`]        | the actor is executing in read-only mode                                |
     pub fn emit_event(
         evt_off: *const u8,
         evt_len: u32,
@@ -91,6 +91,48 @@ super::fvm_syscalls! {
     /// | [`InvalidHandle`] | if the handle isn't known. |
     pub fn block_stat(id: u32) -> Result<IpldStat>;
 
+    /// Returns the codec, size, number of links, and reachability of the specified block.
+    ///
+    /// Counting the block's links requires scanning it, and is charged like
+    /// [`block_links`].
+    ///
+    /// # Errors
+    ///
+    /// | Error                | Reason                                 |
+    /// |----------------------|----------------------------------------|
+    /// | [`InvalidHandle`]    | if the handle isn't known.             |
+    /// | [`Serialization`]    | if the block can't be parsed.          |
+    /// | [`IllegalOperation`] | unavailable before network version 21. |
+    pub fn block_stat_v2(id: u32) -> Result<IpldStatV2>;
+
+    /// Enumerates the CIDs linked from the specified block, writing them back-to-back into `obuf`,
+    /// starting from the `start`th link. Only DagCBOR blocks have links.
+    ///
+    /// As many whole CIDs as fit are written: a buffer of at least `MAX_CID_LEN` bytes is
+    /// guaranteed to make progress. Callers should repeat the call, advancing `start` by the number
+    /// of links written, until all links have been read.
+    ///
+    /// # Arguments
+    ///
+    /// - `id` is ID of the block.
+    /// - `start` is the index of the first link to write.
+    /// - `obuf` is the output buffer (in wasm memory) where the FVM will write the CIDs.
+    /// - `max_len` is the length of the output buffer.
+    ///
+    /// # Returns
+    ///
+    /// The total number of links in the block, and the number of links (and bytes) written.
+    ///
+    /// # Errors
+    ///
+    /// | Error                | Reason                                            |
+    /// |----------------------|---------------------------------------------------|
+    /// | [`InvalidHandle`]    | if the handle isn't known.                        |
+    /// | [`Serialization`]    | if the block can't be parsed.                     |
+    /// | [`IllegalArgument`]  | if the passed buffer isn't valid, in memory, etc. |
+    /// | [`IllegalOperation`] | unavailable before network version 21.            |
+    pub fn block_links(id: u32, start: u32, obuf: *mut u8, max_len: u32) -> Result<IpldLinks>;
+
     /// Computes the given block's CID, writing the resulting CID into `cid`.
     ///
     /// The returned CID is added to the reachable set.
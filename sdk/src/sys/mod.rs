@@ -54,7 +54,7 @@
 #[doc(inline)]
 pub use fvm_shared::error::ErrorNumber;
 #[doc(inline)]
-pub use fvm_shared::sys::TokenAmount;
+pub use fvm_shared::sys::{Extensible, TokenAmount};
 
 pub mod actor;
 pub mod crypto;
@@ -180,3 +180,18 @@ macro_rules! fvm_syscalls {
 }
 
 pub(crate) use fvm_syscalls;
+
+/// Calls an extensible syscall (e.g., [`network::context_ext`]) returning a `T`, returning the
+/// value along with the number of bytes written by the FVM. Fields past that point (see
+/// [`sys_field_end`](fvm_shared::sys_field_end)) weren't known to the FVM and are zeroed.
+///
+/// # Safety
+///
+/// The syscall must be an extensible syscall returning a `T`.
+pub unsafe fn read_extensible<T: Extensible>(
+    syscall: unsafe fn(*mut u8, u32) -> Result<u32, ErrorNumber>,
+) -> Result<(T, u32), ErrorNumber> {
+    let mut ret = std::mem::MaybeUninit::<T>::zeroed();
+    let len = syscall(ret.as_mut_ptr() as *mut u8, std::mem::size_of::<T>() as u32)?;
+    Ok((ret.assume_init(), len))
+}
@@ -4,7 +4,7 @@
 
 // for documentation links
 #[doc(inline)]
-pub use fvm_shared::sys::out::network::NetworkContext;
+pub use fvm_shared::sys::out::network::{NetworkContext, NetworkContextV2};
 
 #[cfg(doc)]
 use crate::sys::ErrorNumber::*;
@@ -49,4 +49,32 @@ super::fvm_syscalls! {
     ///
     /// None
     pub fn context() -> Result<NetworkContext>;
+
+    /// Returns the extended details about the network, including the genesis timestamp and the
+    /// block gas limit.
+    ///
+    /// # Errors
+    ///
+    /// None
+    pub fn context_v2() -> Result<NetworkContextV2>;
+
+    /// Writes as much of the extended network context as fits into the output buffer. See
+    /// [`Extensible`][fvm_shared::sys::Extensible] and [`read_extensible`][super::read_extensible].
+    ///
+    /// # Arguments
+    ///
+    /// - `ret_off` and `ret_len` specify the location and length of the output buffer.
+    ///
+    /// # Returns
+    ///
+    /// Returns the number of bytes written.
+    ///
+    /// # Errors
+    ///
+    /// | Error                | Reason                                            |
+    /// |----------------------|---------------------------------------------------|
+    /// | [`IllegalArgument`]  | the output buffer is out of bounds                |
+    /// | [`BufferTooSmall`]   | the output buffer can't hold a [`NetworkContext`] |
+    /// | [`IllegalOperation`] | unavailable before network version 21             |
+    pub fn context_ext(ret_off: *mut u8, ret_len: u32) -> Result<u32>;
 }
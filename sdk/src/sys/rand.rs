@@ -56,4 +56,28 @@ super::fvm_syscalls! {
         entropy_off: *const u8,
         entropy_len: u32,
     ) -> Result<[u8; RANDOMNESS_LENGTH]>;
+
+    /// Verifies a beacon (currently Drand) entry against the network's beacon chain info.
+    ///
+    /// Returns 0 if the entry is valid, -1 otherwise.
+    ///
+    /// # Arguments
+    ///
+    /// - `round` is the beacon round (not the chain epoch) the entry belongs to.
+    /// - `prev_sig_off` and `prev_sig_len` specify the location and length of the previous round's
+    ///   signature, for chained beacons. Pass an empty buffer for unchained beacons.
+    /// - `sig_off` and `sig_len` specify the location and length of the beacon signature.
+    ///
+    /// # Errors
+    ///
+    /// | Error               | Reason                                        |
+    /// |---------------------|-----------------------------------------------|
+    /// | [`IllegalArgument`] | invalid buffer, or the host failed to verify. |
+    pub fn verify_beacon_entry(
+        round: u64,
+        prev_sig_off: *const u8,
+        prev_sig_len: u32,
+        sig_off: *const u8,
+        sig_len: u32,
+    ) -> Result<i32>;
 }
@@ -61,10 +61,12 @@ super::fvm_syscalls! {
     ///
     /// # Errors
     ///
-    /// | Error               | Reason                                                         |
-    /// |---------------------|----------------------------------------------------------------|
-    /// | [`NotFound`]        | beneficiary isn't found                                        |
-    /// | [`Forbidden`]       | beneficiary is not allowed (usually means beneficiary is self) |
-    /// | [`IllegalArgument`] | if the passed address buffer isn't valid, in memory, etc.      |
+    /// | Error                | Reason                                                         |
+    /// |----------------------|----------------------------------------------------------------|
+    /// | [`NotFound`]         | beneficiary isn't found                                        |
+    /// | [`Forbidden`]        | beneficiary is not allowed (usually means beneficiary is self) |
+    /// | [`IllegalArgument`]  | if the passed address buffer isn't valid, in memory, etc.      |
+    /// | [`IllegalOperation`] | self-destruct is disallowed on this network                    |
+    /// | [`ReadOnly`]         | the actor is executing in read-only mode                       |
     pub fn self_destruct(addr_off: *const u8, addr_len: u32) -> Result<()>;
 }
@@ -5,6 +5,9 @@
 #[doc(inline)]
 pub use fvm_shared::sys::out::vm::MessageContext;
 
+#[cfg(doc)]
+use crate::sys::ErrorNumber::*;
+
 super::fvm_syscalls! {
     module = "vm";
 
@@ -36,4 +39,34 @@ super::fvm_syscalls! {
     ///
     /// None
     pub fn message_context() -> Result<MessageContext>;
+
+    /// Writes as much of the message context as fits into the output buffer. See
+    /// [`Extensible`][fvm_shared::sys::Extensible] and [`read_extensible`][super::read_extensible].
+    ///
+    /// # Arguments
+    ///
+    /// - `ret_off` and `ret_len` specify the location and length of the output buffer.
+    ///
+    /// # Returns
+    ///
+    /// Returns the number of bytes written.
+    ///
+    /// # Errors
+    ///
+    /// | Error                | Reason                                                        |
+    /// |----------------------|---------------------------------------------------------------|
+    /// | [`IllegalArgument`]  | the output buffer is out of bounds                            |
+    /// | [`BufferTooSmall`]   | the output buffer can't hold the first version of the context |
+    /// | [`IllegalOperation`] | unavailable before network version 21                         |
+    pub fn message_context_ext(ret_off: *mut u8, ret_len: u32) -> Result<u32>;
+
+    /// Returns the depth of the current call stack: 1 for the actor invoked by the message, 2 for
+    /// an actor it invokes, etc. Available from network version 21.
+    ///
+    /// # Errors
+    ///
+    /// | Error                | Reason                                |
+    /// |----------------------|---------------------------------------|
+    /// | [`IllegalOperation`] | unavailable before network version 21 |
+    pub fn call_depth() -> Result<u32>;
 }
@@ -4,12 +4,20 @@ use std::ptr;
 
 use fvm_ipld_encoding::ipld_block::IpldBlock;
 use fvm_shared::error::ExitCode;
+use fvm_shared::IPLD_RAW;
 
 use crate::sys;
 
 /// BlockID representing nil parameters or return data.
 pub const NO_DATA_BLOCK_ID: u32 = 0;
 
+/// The exit code actors abort with when they panic (see [`set_panic_handler`]).
+pub const PANIC_EXIT_CODE: ExitCode = ExitCode::USR_ASSERTION_FAILED;
+
+/// The maximum length (in bytes) of the panic message included in the abort's return data (see
+/// [`set_panic_handler`]).
+pub const MAX_PANIC_MESSAGE_LEN: usize = 1024;
+
 /// Returns true if the invocation context is read-only. In read-only mode:
 ///
 /// - State-tree updates `sself::set_root` are forbidden.
@@ -20,6 +28,13 @@ pub fn read_only() -> bool {
     super::message::MESSAGE_CONTEXT.flags.read_only()
 }
 
+/// Returns the depth of the current call stack: 1 for the actor invoked by the message, 2 for an
+/// actor it invokes, etc. This is a cheap way to detect reentrancy. Available from network version
+/// 21.
+pub fn call_depth() -> u32 {
+    unsafe { sys::vm::call_depth().expect("failed to get call depth") }
+}
+
 /// Abort execution; exit code must be non zero.
 pub fn abort(code: u32, message: Option<&str>) -> ! {
     if code == 0 {
@@ -48,15 +63,52 @@ pub fn exit(code: u32, data: Option<IpldBlock>, message: Option<&str>) -> ! {
     }
 }
 
-/// Sets a panic handler to turn all panics into aborts with `USR_ASSERTION_FAILED`. This should be
-/// called early in the actor to improve debuggability.
+/// Sets a panic handler to turn all panics into aborts with [`PANIC_EXIT_CODE`]
+/// (`USR_ASSERTION_FAILED`) instead of traps. This should be called early in the actor to improve
+/// debuggability.
+///
+/// The panic message is both used as the abort message and returned (as an `IPLD_RAW` block,
+/// truncated to [`MAX_PANIC_MESSAGE_LEN`] bytes) as the abort's return data, so callers can see
+/// why the actor panicked.
 ///
 /// NOTE: This will incure a small cost on failure (to format an error message).
 pub fn set_panic_handler() {
     std::panic::set_hook(Box::new(|info| {
-        abort(
-            ExitCode::USR_ASSERTION_FAILED.value(),
-            Some(&format!("{}", info)),
-        )
+        let message = format!("{}", info);
+        let data = IpldBlock {
+            codec: IPLD_RAW,
+            data: truncate(&message, MAX_PANIC_MESSAGE_LEN)
+                .as_bytes()
+                .to_vec(),
+        };
+        exit(PANIC_EXIT_CODE.value(), Some(data), Some(&message))
     }));
 }
+
+/// Truncates the string to at most `max_len` bytes, on a character boundary.
+fn truncate(s: &str, max_len: usize) -> &str {
+    if s.len() <= max_len {
+        return s;
+    }
+    let mut end = max_len;
+    while !s.is_char_boundary(end) {
+        end -= 1;
+    }
+    &s[..end]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::truncate;
+
+    #[test]
+    fn truncate_on_char_boundary() {
+        assert_eq!(truncate("abc", 3), "abc");
+        assert_eq!(truncate("abc", 10), "abc");
+        assert_eq!(truncate("abc", 2), "ab");
+        assert_eq!(truncate("abc", 0), "");
+        // "é" is two bytes long.
+        assert_eq!(truncate("aéb", 2), "a");
+        assert_eq!(truncate("aéb", 3), "aé");
+    }
+}
@@ -0,0 +1,45 @@
+// Copyright 2021-2023 Protocol Labs
+// SPDX-License-Identifier: Apache-2.0, MIT
+use fvm_sdk::event::{EventBuilder, IntoEvent};
+use fvm_shared::address::Address;
+
+#[derive(IntoEvent)]
+struct Transfer {
+    #[event(key = "type")]
+    kind: String,
+    from: Address,
+    to: Address,
+    #[event(key_indexed)]
+    amount: u64,
+    #[event(unindexed)]
+    memo: String,
+    #[event(skip)]
+    #[allow(dead_code)]
+    internal: u64,
+}
+
+#[derive(IntoEvent)]
+struct Empty {}
+
+#[test]
+fn derived_events() {
+    let transfer = Transfer {
+        kind: "transfer".into(),
+        from: Address::new_id(100),
+        to: Address::new_id(101),
+        amount: 10,
+        memo: "thanks".into(),
+        internal: 0,
+    };
+    assert_eq!(
+        transfer.to_event(),
+        EventBuilder::new()
+            .field("type", "transfer")
+            .field("from", &Address::new_id(100))
+            .field("to", &Address::new_id(101))
+            .key_indexed_field("amount", 10u64)
+            .unindexed_field("memo", "thanks")
+            .build()
+    );
+    assert_eq!(Empty {}.to_event(), EventBuilder::new().build());
+}
@@ -35,6 +35,10 @@ pub enum Error {
     NonIDAddress,
     #[error("Cannot get delegated address from non delegate address")]
     NonDelegatedAddress,
+    #[error("Cannot get ethereum address from non ID or f410 address")]
+    NonEthAddress,
+    #[error("Invalid ethereum address")]
+    InvalidEthAddress,
 }
 
 impl From<num::ParseIntError> for Error {
@@ -0,0 +1,130 @@
+// Copyright 2021-2023 Protocol Labs
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+use std::fmt;
+use std::str::FromStr;
+
+use data_encoding::HEXLOWER_PERMISSIVE;
+use multihash::{Code, MultihashDigest};
+
+use super::{Address, Error, Payload};
+use crate::ActorID;
+
+/// The namespace of Ethereum (f410) addresses: the ID of the Ethereum Address Manager actor.
+pub const EAM_NAMESPACE: ActorID = 10;
+
+/// The length of an Ethereum address.
+pub const ETH_ADDRESS_LEN: usize = 20;
+
+/// The prefix of "masked" ID addresses: Ethereum addresses that embed an actor ID (in their last 8
+/// bytes, big-endian).
+const ETH_ID_PREFIX: [u8; 12] = [0xff, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0];
+
+/// An Ethereum address, convertible to and from its [`Address`] (f410 or, for "masked" IDs, f0)
+/// form, and displayed in (and parsed from) its EIP-55 checksummed `0x` text form.
+#[derive(Copy, Clone, Debug, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub struct EthAddress(pub [u8; ETH_ADDRESS_LEN]);
+
+impl EthAddress {
+    /// Returns the "masked" Ethereum address of an actor ID: `0xff`, followed by 11 zero bytes
+    /// and the big-endian ID.
+    pub fn from_id(id: ActorID) -> Self {
+        let mut bytes = [0u8; ETH_ADDRESS_LEN];
+        bytes[..ETH_ID_PREFIX.len()].copy_from_slice(&ETH_ID_PREFIX);
+        bytes[ETH_ID_PREFIX.len()..].copy_from_slice(&id.to_be_bytes());
+        Self(bytes)
+    }
+
+    /// Returns the actor ID embedded in a "masked" ID address, if any.
+    pub fn as_id(&self) -> Option<ActorID> {
+        let (prefix, id) = self.0.split_at(ETH_ID_PREFIX.len());
+        (prefix == ETH_ID_PREFIX).then(|| ActorID::from_be_bytes(id.try_into().unwrap()))
+    }
+
+    /// Returns the address: an ID address for "masked" ID addresses, an f410 address otherwise.
+    pub fn to_address(&self) -> Address {
+        match self.as_id() {
+            Some(id) => Address::new_id(id),
+            None => Address::new_delegated(EAM_NAMESPACE, &self.0)
+                .expect("ethereum addresses are valid subaddresses"),
+        }
+    }
+
+    /// Returns the Ethereum address of an ID address (masked) or f410 address.
+    pub fn from_address(addr: &Address) -> Result<Self, Error> {
+        match addr.payload() {
+            Payload::ID(id) => Ok(Self::from_id(*id)),
+            Payload::Delegated(delegated) if delegated.namespace() == EAM_NAMESPACE => {
+                let bytes = delegated
+                    .subaddress()
+                    .try_into()
+                    .map_err(|_| Error::InvalidPayloadLength(delegated.subaddress().len()))?;
+                Ok(Self(bytes))
+            }
+            _ => Err(Error::NonEthAddress),
+        }
+    }
+
+    /// Returns the EIP-55 checksummed hex form of the address (with a `0x` prefix).
+    pub fn to_checksummed(&self) -> String {
+        let hex = HEXLOWER_PERMISSIVE.encode(&self.0);
+        let mh = Code::Keccak256.digest(hex.as_bytes());
+        let hash = mh.digest();
+        let mut out = String::with_capacity(2 + hex.len());
+        out.push_str("0x");
+        out.extend(hex.chars().enumerate().map(|(i, c)| {
+            // Letters are uppercased if the corresponding nibble of the hash is >= 8.
+            let nibble = (hash[i / 2] >> (if i % 2 == 0 { 4 } else { 0 })) & 0xf;
+            if nibble >= 8 {
+                c.to_ascii_uppercase()
+            } else {
+                c
+            }
+        }));
+        out
+    }
+}
+
+impl From<EthAddress> for Address {
+    fn from(addr: EthAddress) -> Self {
+        addr.to_address()
+    }
+}
+
+impl TryFrom<&Address> for EthAddress {
+    type Error = Error;
+
+    fn try_from(addr: &Address) -> Result<Self, Error> {
+        Self::from_address(addr)
+    }
+}
+
+impl fmt::Display for EthAddress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.to_checksummed())
+    }
+}
+
+impl FromStr for EthAddress {
+    type Err = Error;
+
+    /// Parses a `0x`-prefixed hex Ethereum address. Mixed-case addresses must have a valid
+    /// EIP-55 checksum; all lowercase and all uppercase addresses aren't checksummed.
+    fn from_str(s: &str) -> Result<Self, Error> {
+        let hex = s.strip_prefix("0x").ok_or(Error::InvalidEthAddress)?;
+        if hex.len() != 2 * ETH_ADDRESS_LEN {
+            return Err(Error::InvalidLength);
+        }
+        let bytes = HEXLOWER_PERMISSIVE
+            .decode(hex.as_bytes())
+            .map_err(|_| Error::InvalidEthAddress)?;
+        let addr = Self(bytes.try_into().map_err(|_| Error::InvalidEthAddress)?);
+
+        let mixed_case = hex.bytes().any(|b| b.is_ascii_lowercase())
+            && hex.bytes().any(|b| b.is_ascii_uppercase());
+        if mixed_case && addr.to_checksummed()[2..] != *hex {
+            return Err(Error::InvalidChecksum);
+        }
+        Ok(addr)
+    }
+}
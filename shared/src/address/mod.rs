@@ -3,9 +3,11 @@
 // SPDX-License-Identifier: Apache-2.0, MIT
 
 mod errors;
+mod eth;
 mod network;
 mod payload;
 mod protocol;
+pub mod text_keys;
 
 use std::borrow::Cow;
 use std::fmt;
@@ -18,6 +20,7 @@ use fvm_ipld_encoding::strict_bytes;
 use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
 
 pub use self::errors::Error;
+pub use self::eth::{EthAddress, EAM_NAMESPACE, ETH_ADDRESS_LEN};
 pub use self::network::{current_network, set_current_network, Network};
 pub use self::payload::{DelegatedAddress, Payload};
 pub use self::protocol::Protocol;
@@ -61,7 +64,11 @@ pub const CHECKSUM_HASH_LEN: usize = 4;
 /// The max encoded length of an address.
 pub const MAX_ADDRESS_LEN: usize = 65;
 
-const MAX_ADDRRESS_TEXT_LEN: usize = 138;
+/// The max length of an address's text form.
+pub const MAX_ADDRESS_TEXT_LEN: usize = 138;
+
+/// The max length of the base32 encoded payload (and checksum) of an address's text form.
+const MAX_ENCODED_PAYLOAD_LEN: usize = ((MAX_SUBADDRESS_LEN + CHECKSUM_HASH_LEN) * 8 + 4) / 5;
 const MAINNET_PREFIX: &str = "f";
 const TESTNET_PREFIX: &str = "t";
 
@@ -176,17 +183,16 @@ impl Address {
             _ => Err(Error::NonIDAddress),
         }
     }
-}
 
-impl fmt::Display for Address {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    /// Writes the text form of the address (as displayed) to the writer, without allocating.
+    pub fn write_str(&self, w: &mut impl fmt::Write) -> fmt::Result {
         let protocol = self.protocol();
 
         // write `fP` where P is the protocol number.
-        write!(f, "{}{}", current_network().to_prefix(), protocol)?;
+        write!(w, "{}{}", current_network().to_prefix(), protocol)?;
 
         fn write_payload(
-            f: &mut fmt::Formatter<'_>,
+            w: &mut impl fmt::Write,
             protocol: Protocol,
             prefix: Option<&[u8]>,
             data: &[u8],
@@ -200,23 +206,27 @@ impl fmt::Display for Address {
             }
             hasher.update(data);
 
-            let mut buf = Vec::with_capacity(data.len() + CHECKSUM_HASH_LEN);
-            buf.extend(data);
-            buf.extend(hasher.finalize().as_bytes());
+            let mut buf = [0u8; MAX_SUBADDRESS_LEN + CHECKSUM_HASH_LEN];
+            let len = data.len() + CHECKSUM_HASH_LEN;
+            buf[..data.len()].copy_from_slice(data);
+            buf[data.len()..len].copy_from_slice(hasher.finalize().as_bytes());
 
-            f.write_str(&ADDRESS_ENCODER.encode(&buf))
+            let mut encoded = [0u8; MAX_ENCODED_PAYLOAD_LEN];
+            let encoded = &mut encoded[..ADDRESS_ENCODER.encode_len(len)];
+            ADDRESS_ENCODER.encode_mut(&buf[..len], encoded);
+            w.write_str(std::str::from_utf8(encoded).map_err(|_| fmt::Error)?)
         }
 
         match self.payload() {
-            Payload::ID(id) => write!(f, "{}", id),
+            Payload::ID(id) => write!(w, "{}", id),
             Payload::Secp256k1(data) | Payload::Actor(data) => {
-                write_payload(f, protocol, None, data)
+                write_payload(w, protocol, None, data)
             }
-            Payload::BLS(data) => write_payload(f, protocol, None, data),
+            Payload::BLS(data) => write_payload(w, protocol, None, data),
             Payload::Delegated(addr) => {
-                write!(f, "{}f", addr.namespace())?;
+                write!(w, "{}f", addr.namespace())?;
                 write_payload(
-                    f,
+                    w,
                     protocol,
                     Some(unsigned_varint::encode::u64(
                         addr.namespace(),
@@ -227,6 +237,39 @@ impl fmt::Display for Address {
             }
         }
     }
+
+    /// Formats the address (as displayed) into the buffer, without allocating, returning the
+    /// formatted text.
+    pub fn format_into<'a>(&self, buf: &'a mut [u8; MAX_ADDRESS_TEXT_LEN]) -> &'a str {
+        struct BufWriter<'a> {
+            buf: &'a mut [u8],
+            len: usize,
+        }
+
+        impl fmt::Write for BufWriter<'_> {
+            fn write_str(&mut self, s: &str) -> fmt::Result {
+                let end = self.len + s.len();
+                self.buf
+                    .get_mut(self.len..end)
+                    .ok_or(fmt::Error)?
+                    .copy_from_slice(s.as_bytes());
+                self.len = end;
+                Ok(())
+            }
+        }
+
+        let mut w = BufWriter { buf, len: 0 };
+        self.write_str(&mut w)
+            .expect("address text fits in MAX_ADDRESS_TEXT_LEN");
+        let BufWriter { buf, len } = w;
+        std::str::from_utf8(&buf[..len]).expect("address text is ascii")
+    }
+}
+
+impl fmt::Display for Address {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.write_str(f)
+    }
 }
 
 #[cfg(feature = "arb")]
@@ -239,7 +282,7 @@ impl quickcheck::Arbitrary for Address {
 }
 
 pub(self) fn parse_address(addr: &str) -> Result<(Address, Network), Error> {
-    if addr.len() > MAX_ADDRRESS_TEXT_LEN || addr.len() < 3 {
+    if addr.len() > MAX_ADDRESS_TEXT_LEN || addr.len() < 3 {
         return Err(Error::InvalidLength);
     }
     let network = Network::from_prefix(addr.get(0..1).ok_or(Error::UnknownNetwork)?)?;
@@ -0,0 +1,194 @@
+// Copyright 2021-2023 Protocol Labs
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+//! A serde adapter for maps keyed by [`Address`] (e.g., `HashMap<Address, T>` or
+//! `BTreeMap<Address, T>`), encoding keys as address strings in human-readable formats (e.g.,
+//! JSON), where keys must be strings. Non-human-readable formats (e.g., CBOR) are unaffected, and
+//! keep the addresses' byte encoding.
+//!
+//! Addresses are formatted with the [current network](super::current_network)'s prefix, but
+//! parsed with either network's prefix.
+//!
+//! ```
+//! use std::collections::BTreeMap;
+//!
+//! use fvm_shared::address::Address;
+//! use serde::{Deserialize, Serialize};
+//!
+//! #[derive(Serialize, Deserialize, PartialEq, Debug)]
+//! struct Balances {
+//!     #[serde(with = "fvm_shared::address::text_keys")]
+//!     balances: BTreeMap<Address, u64>,
+//! }
+//!
+//! let balances = Balances {
+//!     balances: [(Address::new_id(100), 10)].into_iter().collect(),
+//! };
+//! let json = serde_json::to_string(&balances).unwrap();
+//! assert_eq!(json, r#"{"balances":{"f0100":10}}"#);
+//! assert_eq!(serde_json::from_str::<Balances>(&json).unwrap(), balances);
+//! ```
+
+use std::fmt;
+use std::marker::PhantomData;
+
+use serde::de::{self, MapAccess, Visitor};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use super::Address;
+
+/// Serializes an address-keyed map, encoding keys as strings in human-readable formats.
+pub fn serialize<'a, M, T, S>(map: &'a M, serializer: S) -> Result<S::Ok, S::Error>
+where
+    &'a M: IntoIterator<Item = (&'a Address, &'a T)>,
+    T: Serialize + 'a,
+    S: Serializer,
+{
+    if serializer.is_human_readable() {
+        serializer.collect_map(map.into_iter().map(|(k, v)| (k.to_string(), v)))
+    } else {
+        serializer.collect_map(map)
+    }
+}
+
+/// Deserializes an address-keyed map, decoding keys from strings in human-readable formats.
+pub fn deserialize<'de, M, T, D>(deserializer: D) -> Result<M, D::Error>
+where
+    M: FromIterator<(Address, T)>,
+    T: Deserialize<'de>,
+    D: Deserializer<'de>,
+{
+    if deserializer.is_human_readable() {
+        let entries = deserializer.deserialize_map(EntriesVisitor::<TextAddress, T>::default())?;
+        Ok(entries.into_iter().map(|(k, v)| (k.0, v)).collect())
+    } else {
+        let entries = deserializer.deserialize_map(EntriesVisitor::<Address, T>::default())?;
+        Ok(entries.into_iter().collect())
+    }
+}
+
+/// An address decoded from its string form.
+struct TextAddress(Address);
+
+impl<'de> Deserialize<'de> for TextAddress {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let s = String::deserialize(deserializer)?;
+        super::parse_address(&s)
+            .map(|(addr, _)| TextAddress(addr))
+            .map_err(de::Error::custom)
+    }
+}
+
+/// Collects a map's entries, in order.
+struct EntriesVisitor<K, V>(PhantomData<(K, V)>);
+
+impl<K, V> Default for EntriesVisitor<K, V> {
+    fn default() -> Self {
+        EntriesVisitor(PhantomData)
+    }
+}
+
+impl<'de, K, V> Visitor<'de> for EntriesVisitor<K, V>
+where
+    K: Deserialize<'de>,
+    V: Deserialize<'de>,
+{
+    type Value = Vec<(K, V)>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a map keyed by addresses")
+    }
+
+    fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
+    where
+        A: MapAccess<'de>,
+    {
+        let mut entries = Vec::with_capacity(map.size_hint().unwrap_or(0).min(1024));
+        while let Some(entry) = map.next_entry()? {
+            entries.push(entry);
+        }
+        Ok(entries)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::{BTreeMap, HashMap};
+
+    use serde::{Deserialize, Serialize};
+
+    use crate::address::Address;
+
+    #[derive(Serialize, Deserialize, PartialEq, Debug)]
+    struct Maps {
+        #[serde(with = "super")]
+        btree: BTreeMap<Address, u64>,
+        #[serde(with = "super")]
+        hash: HashMap<Address, String>,
+    }
+
+    fn maps() -> Maps {
+        Maps {
+            btree: [(Address::new_id(100), 1), (Address::new_id(101), 2)]
+                .into_iter()
+                .collect(),
+            hash: [(Address::new_actor(b"actor"), "actor".to_string())]
+                .into_iter()
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn json_keys_are_text() {
+        let maps = maps();
+        let json = serde_json::to_value(&maps).unwrap();
+        // Skip the network prefix, as other tests change the current network.
+        let keys: Vec<_> = json["btree"]
+            .as_object()
+            .unwrap()
+            .keys()
+            .map(|k| &k[1..])
+            .collect();
+        assert_eq!(keys, ["0100", "0101"]);
+
+        let decoded: Maps = serde_json::from_value(json).unwrap();
+        assert_eq!(decoded, maps);
+    }
+
+    #[test]
+    fn json_accepts_either_network() {
+        let json = r#"{"btree":{"f0100":1,"t0101":2},"hash":{}}"#;
+        let decoded: Maps = serde_json::from_str(json).unwrap();
+        assert_eq!(decoded.btree, maps().btree);
+    }
+
+    #[test]
+    fn json_rejects_invalid_keys() {
+        let json = r#"{"btree":{"not an address":1},"hash":{}}"#;
+        assert!(serde_json::from_str::<Maps>(json).is_err());
+    }
+
+    #[test]
+    fn binary_encoding_unchanged() {
+        #[derive(Serialize)]
+        struct Plain {
+            btree: BTreeMap<Address, u64>,
+        }
+        #[derive(Serialize)]
+        struct Adapted {
+            #[serde(with = "super")]
+            btree: BTreeMap<Address, u64>,
+        }
+
+        let btree = maps().btree;
+        let plain = fvm_ipld_encoding::to_vec(&Plain {
+            btree: btree.clone(),
+        })
+        .map_err(|e| e.to_string());
+        let adapted = fvm_ipld_encoding::to_vec(&Adapted { btree }).map_err(|e| e.to_string());
+        assert_eq!(plain, adapted);
+    }
+}
@@ -2,13 +2,11 @@
 // Copyright 2019-2022 ChainSafe Systems
 // SPDX-License-Identifier: Apache-2.0, MIT
 
-use std::borrow::Cow;
-
 use fvm_ipld_encoding::strict_bytes;
 use num_bigint::{BigInt, Sign};
 use serde::{Deserialize, Serialize};
 
-use super::MAX_BIGINT_SIZE;
+use super::{deserialize_bytes, BigIntTooLargeError, MAX_BIGINT_SIZE};
 
 /// Wrapper for serializing big ints to match filecoin spec. Serializes as bytes.
 #[derive(Serialize)]
@@ -35,7 +33,9 @@ where
     }
 
     if bz.len() > MAX_BIGINT_SIZE {
-        return Err(<S::Error as serde::ser::Error>::custom("BigInt too large"));
+        return Err(<S::Error as serde::ser::Error>::custom(
+            BigIntTooLargeError(bz.len()),
+        ));
     }
 
     // Serialize as bytes
@@ -47,7 +47,8 @@ pub fn deserialize<'de, D>(deserializer: D) -> Result<BigInt, D::Error>
 where
     D: serde::Deserializer<'de>,
 {
-    let bz: Cow<'de, [u8]> = strict_bytes::Deserialize::deserialize(deserializer)?;
+    // Oversized values are rejected here, before being copied or decoded.
+    let bz = deserialize_bytes(deserializer)?;
     if bz.is_empty() {
         return Ok(BigInt::default());
     }
@@ -62,10 +63,6 @@ where
         }
     };
 
-    if bz.len() > MAX_BIGINT_SIZE {
-        return Err(<D::Error as serde::de::Error>::custom("BigInt too large"));
-    }
-
     Ok(BigInt::from_bytes_be(sign, &bz[1..]))
 }
 
@@ -113,4 +110,19 @@ mod tests {
         assert!(res.is_err());
         assert!(res.unwrap_err().to_string().contains("BigInt too large"));
     }
+
+    #[test]
+    fn test_bigint_size_checked_first() {
+        // Oversized values are rejected by size, before their sign byte is validated.
+        let huge = to_vec(&strict_bytes::ByteBuf(vec![0xff; MAX_BIGINT_SIZE + 1])).unwrap();
+        let err = from_slice::<BigIntDe>(&huge).unwrap_err().to_string();
+        assert!(
+            err.contains(&BigIntTooLargeError(MAX_BIGINT_SIZE + 1).to_string()),
+            "{}",
+            err
+        );
+
+        let max = to_vec(&strict_bytes::ByteBuf(vec![0; MAX_BIGINT_SIZE])).unwrap();
+        assert!(from_slice::<BigIntDe>(&max).is_ok());
+    }
 }
@@ -2,13 +2,11 @@
 // Copyright 2019-2022 ChainSafe Systems
 // SPDX-License-Identifier: Apache-2.0, MIT
 
-use std::borrow::Cow;
-
 use fvm_ipld_encoding::strict_bytes;
 use num_bigint::BigUint;
 use serde::{Deserialize, Serialize};
 
-use super::MAX_BIGINT_SIZE;
+use super::{deserialize_bytes, BigIntTooLargeError, MAX_BIGINT_SIZE};
 
 /// Wrapper for serializing big ints to match filecoin spec. Serializes as bytes.
 #[derive(Serialize)]
@@ -34,7 +32,9 @@ where
     }
 
     if bz.len() > MAX_BIGINT_SIZE {
-        return Err(<S::Error as serde::ser::Error>::custom("BigInt too large"));
+        return Err(<S::Error as serde::ser::Error>::custom(
+            BigIntTooLargeError(bz.len()),
+        ));
     }
 
     // Serialize as bytes
@@ -45,7 +45,8 @@ pub fn deserialize<'de, D>(deserializer: D) -> Result<BigUint, D::Error>
 where
     D: serde::Deserializer<'de>,
 {
-    let bz: Cow<'de, [u8]> = strict_bytes::Deserialize::deserialize(deserializer)?;
+    // Oversized values are rejected here, before being copied or decoded.
+    let bz = deserialize_bytes(deserializer)?;
     if bz.is_empty() {
         return Ok(BigUint::default());
     }
@@ -56,10 +57,6 @@ where
         ));
     }
 
-    if bz.len() > MAX_BIGINT_SIZE {
-        return Err(<D::Error as serde::de::Error>::custom("BigInt too large"));
-    }
-
     Ok(BigUint::from_bytes_be(&bz[1..]))
 }
 
@@ -2,6 +2,12 @@
 // Copyright 2019-2022 ChainSafe Systems
 // SPDX-License-Identifier: Apache-2.0, MIT
 
+use std::borrow::Cow;
+use std::fmt;
+
+use serde::de::{self, Visitor};
+use thiserror::Error;
+
 pub mod bigint_ser;
 pub mod biguint_ser;
 
@@ -11,3 +17,64 @@ pub use num_traits::Zero;
 
 /// The maximum number of bytes we accept to serialize/deserialize for a single BigInt.
 pub const MAX_BIGINT_SIZE: usize = 128;
+
+/// The error returned when serializing or deserializing a BigInt larger than [`MAX_BIGINT_SIZE`]
+/// bytes (including the sign byte).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Error)]
+#[error("BigInt too large: {0} bytes, the maximum is {}", MAX_BIGINT_SIZE)]
+pub struct BigIntTooLargeError(pub usize);
+
+/// Deserializes the bytes of a BigInt, rejecting byte strings larger than [`MAX_BIGINT_SIZE`]
+/// before copying them.
+fn deserialize_bytes<'de, D>(deserializer: D) -> Result<Cow<'de, [u8]>, D::Error>
+where
+    D: de::Deserializer<'de>,
+{
+    struct BoundedVisitor;
+
+    impl<'de> Visitor<'de> for BoundedVisitor {
+        type Value = Cow<'de, [u8]>;
+
+        fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+            write!(
+                formatter,
+                "a byte array of at most {} bytes",
+                MAX_BIGINT_SIZE
+            )
+        }
+
+        fn visit_borrowed_bytes<E>(self, v: &'de [u8]) -> Result<Self::Value, E>
+        where
+            E: de::Error,
+        {
+            check_size(v.len())?;
+            Ok(Cow::Borrowed(v))
+        }
+
+        fn visit_bytes<E>(self, v: &[u8]) -> Result<Self::Value, E>
+        where
+            E: de::Error,
+        {
+            check_size(v.len())?;
+            Ok(Cow::Owned(v.to_vec()))
+        }
+
+        fn visit_byte_buf<E>(self, v: Vec<u8>) -> Result<Self::Value, E>
+        where
+            E: de::Error,
+        {
+            check_size(v.len())?;
+            Ok(Cow::Owned(v))
+        }
+    }
+
+    deserializer.deserialize_bytes(BoundedVisitor)
+}
+
+/// Checks that a serialized BigInt isn't larger than [`MAX_BIGINT_SIZE`] bytes.
+fn check_size<E: de::Error>(len: usize) -> Result<(), E> {
+    if len > MAX_BIGINT_SIZE {
+        return Err(E::custom(BigIntTooLargeError(len)));
+    }
+    Ok(())
+}
@@ -3,7 +3,9 @@
 // SPDX-License-Identifier: Apache-2.0, MIT
 
 mod quantize;
+mod time;
 pub use quantize::*;
+pub use time::*;
 
 const _ISO_FORMAT: &str = "%FT%X.%.9F";
 
@@ -0,0 +1,154 @@
+// Copyright 2021-2023 Protocol Labs
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+use super::{ChainEpoch, EPOCH_DURATION_SECONDS};
+
+/// How to round timestamps and durations that don't fall on an epoch boundary.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Rounding {
+    /// Round towards the earlier epoch.
+    Down,
+    /// Round towards the later epoch.
+    Up,
+    /// Round to the closest epoch, rounding up when exactly halfway between two epochs.
+    Nearest,
+}
+
+impl Rounding {
+    /// Divides `n` by `d` (which must be positive), rounding as specified.
+    fn div(self, n: i128, d: i128) -> i128 {
+        match self {
+            Rounding::Down => n.div_euclid(d),
+            Rounding::Up => -(-n).div_euclid(d),
+            Rounding::Nearest => (2 * n + d).div_euclid(2 * d),
+        }
+    }
+}
+
+/// Converts between chain epochs and UNIX timestamps (in seconds), given the network's genesis
+/// timestamp and block delay (the duration of each epoch, in seconds). Epoch 0 is at the genesis
+/// timestamp, and epochs are never negative.
+///
+/// ```
+/// use fvm_shared::clock::{EpochClock, Rounding};
+///
+/// let clock = EpochClock::new(1_598_306_400, 30);
+/// assert_eq!(clock.timestamp_at(2), Some(1_598_306_460));
+/// assert_eq!(clock.epoch_at(1_598_306_470, Rounding::Down), Some(2));
+/// assert_eq!(clock.epoch_at(1_598_306_470, Rounding::Up), Some(3));
+/// ```
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct EpochClock {
+    /// The UNIX timestamp (in seconds) of epoch 0.
+    pub genesis_timestamp: u64,
+    /// The duration of each epoch, in seconds.
+    pub block_delay: u64,
+}
+
+impl EpochClock {
+    /// Creates a clock for a network with the given genesis timestamp and block delay.
+    ///
+    /// Panics if the block delay is zero.
+    pub const fn new(genesis_timestamp: u64, block_delay: u64) -> Self {
+        assert!(block_delay > 0, "block delay must be positive");
+        EpochClock {
+            genesis_timestamp,
+            block_delay,
+        }
+    }
+
+    /// Creates a clock for a network with the given genesis timestamp and the standard block delay
+    /// of [`EPOCH_DURATION_SECONDS`].
+    pub const fn with_genesis(genesis_timestamp: u64) -> Self {
+        Self::new(genesis_timestamp, EPOCH_DURATION_SECONDS as u64)
+    }
+
+    /// Returns the timestamp at which the epoch starts, or `None` if the epoch is negative or the
+    /// timestamp overflows.
+    pub fn timestamp_at(&self, epoch: ChainEpoch) -> Option<u64> {
+        u64::try_from(epoch)
+            .ok()?
+            .checked_mul(self.block_delay)?
+            .checked_add(self.genesis_timestamp)
+    }
+
+    /// Returns the epoch at the timestamp, rounding timestamps between two epochs as specified
+    /// (e.g., [`Rounding::Down`] returns the epoch in progress at the timestamp). Returns `None`
+    /// if the result would be before genesis.
+    pub fn epoch_at(&self, timestamp: u64, rounding: Rounding) -> Option<ChainEpoch> {
+        let elapsed = timestamp as i128 - self.genesis_timestamp as i128;
+        let epoch = rounding.div(elapsed, self.block_delay as i128);
+        if epoch < 0 {
+            return None;
+        }
+        ChainEpoch::try_from(epoch).ok()
+    }
+
+    /// Returns the number of epochs spanning the duration (in seconds), rounding as specified.
+    /// Saturates at [`ChainEpoch::MAX`], which is only reachable with a tiny block delay.
+    pub fn epochs_in(&self, seconds: u64, rounding: Rounding) -> ChainEpoch {
+        let epochs = rounding.div(seconds as i128, self.block_delay as i128);
+        ChainEpoch::try_from(epochs).unwrap_or(ChainEpoch::MAX)
+    }
+
+    /// Returns the duration (in seconds) of the given number of epochs, or `None` if the number of
+    /// epochs is negative or the duration overflows.
+    pub fn duration_of(&self, epochs: ChainEpoch) -> Option<u64> {
+        u64::try_from(epochs).ok()?.checked_mul(self.block_delay)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CLOCK: EpochClock = EpochClock::new(1000, 30);
+
+    #[test]
+    fn timestamps() {
+        assert_eq!(CLOCK.timestamp_at(0), Some(1000));
+        assert_eq!(CLOCK.timestamp_at(10), Some(1300));
+        assert_eq!(CLOCK.timestamp_at(-1), None);
+        assert_eq!(CLOCK.timestamp_at(ChainEpoch::MAX), None);
+    }
+
+    #[test]
+    fn epochs() {
+        for (ts, down, up, nearest) in [
+            (1000, Some(0), Some(0), Some(0)),
+            (1001, Some(0), Some(1), Some(0)),
+            (1015, Some(0), Some(1), Some(1)),
+            (1029, Some(0), Some(1), Some(1)),
+            (1030, Some(1), Some(1), Some(1)),
+            (999, None, Some(0), Some(0)),
+            (985, None, Some(0), Some(0)),
+            (984, None, Some(0), None),
+            (0, None, None, None),
+        ] {
+            assert_eq!(CLOCK.epoch_at(ts, Rounding::Down), down, "{}", ts);
+            assert_eq!(CLOCK.epoch_at(ts, Rounding::Up), up, "{}", ts);
+            assert_eq!(CLOCK.epoch_at(ts, Rounding::Nearest), nearest, "{}", ts);
+        }
+        for epoch in [0, 1, 12345] {
+            let ts = CLOCK.timestamp_at(epoch).unwrap();
+            assert_eq!(CLOCK.epoch_at(ts, Rounding::Down), Some(epoch));
+        }
+        let max = EpochClock::new(0, 1);
+        assert_eq!(max.epoch_at(u64::MAX, Rounding::Down), None);
+    }
+
+    #[test]
+    fn durations() {
+        assert_eq!(CLOCK.epochs_in(86400, Rounding::Down), 2880);
+        assert_eq!(CLOCK.epochs_in(100, Rounding::Down), 3);
+        assert_eq!(CLOCK.epochs_in(100, Rounding::Up), 4);
+        assert_eq!(CLOCK.epochs_in(100, Rounding::Nearest), 3);
+        assert_eq!(
+            EpochClock::new(0, 1).epochs_in(u64::MAX, Rounding::Up),
+            ChainEpoch::MAX
+        );
+        assert_eq!(CLOCK.duration_of(2880), Some(86400));
+        assert_eq!(CLOCK.duration_of(-1), None);
+        assert_eq!(EpochClock::with_genesis(0).block_delay, 30);
+    }
+}
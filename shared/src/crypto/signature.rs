@@ -24,6 +24,70 @@ pub const SECP_SIG_LEN: usize = 65;
 pub const SECP_PUB_LEN: usize = 65;
 /// Length of the signature input message hash in bytes (32).
 pub const SECP_SIG_MESSAGE_HASH_SIZE: usize = 32;
+/// Length of each of the `r` and `s` Secp256k1 signature components in bytes.
+pub const SECP_SIG_COMPONENT_LEN: usize = 32;
+
+/// The order of the Secp256k1 curve (big-endian).
+const SECP_CURVE_ORDER: [u8; SECP_SIG_COMPONENT_LEN] = [
+    0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xfe,
+    0xba, 0xae, 0xdc, 0xe6, 0xaf, 0x48, 0xa0, 0x3b, 0xbf, 0xd2, 0x5e, 0x8c, 0xd0, 0x36, 0x41, 0x41,
+];
+
+/// Half the order of the Secp256k1 curve (big-endian), rounded down. Signatures with an `s` value
+/// above this are "high-s" (malleable) signatures.
+const SECP_CURVE_HALF_ORDER: [u8; SECP_SIG_COMPONENT_LEN] = [
+    0x7f, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+    0x5d, 0x57, 0x6e, 0x73, 0x57, 0xa4, 0x50, 0x1d, 0xdf, 0xe9, 0x2f, 0x46, 0x68, 0x1b, 0x20, 0xa0,
+];
+
+/// Reasons an `(r, s, v)` Secp256k1 signature may be rejected by [`secp_signature_from_rsv`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Error)]
+pub enum SecpComponentError {
+    /// The recovery id wasn't one of 0, 1, 27, or 28.
+    #[error("invalid recovery id {0}")]
+    InvalidRecoveryId(u8),
+    /// The `r` or `s` component was zero or not less than the curve order.
+    #[error("signature component out of range")]
+    OutOfRange,
+    /// The `s` component was in the upper half of the curve order and malleable signatures were
+    /// rejected.
+    #[error("malleable signature (high s value)")]
+    Malleable,
+}
+
+/// Assembles a 65-byte recoverable Secp256k1 signature from its `r`, `s`, and `v` components.
+///
+/// The recovery id `v` may either be the raw recovery id (0 or 1) or the Ethereum-style 27 or 28.
+/// If `allow_malleable` is false, "high-s" signatures are rejected.
+pub fn secp_signature_from_rsv(
+    r: &[u8; SECP_SIG_COMPONENT_LEN],
+    s: &[u8; SECP_SIG_COMPONENT_LEN],
+    v: u8,
+    allow_malleable: bool,
+) -> Result<[u8; SECP_SIG_LEN], SecpComponentError> {
+    let recovery_id = match v {
+        0 | 1 => v,
+        27 | 28 => v - 27,
+        _ => return Err(SecpComponentError::InvalidRecoveryId(v)),
+    };
+
+    // Big-endian byte arrays compare lexicographically, which is the same as comparing the
+    // numbers they represent.
+    let in_range =
+        |x: &[u8; SECP_SIG_COMPONENT_LEN]| x.iter().any(|&b| b != 0) && x < &SECP_CURVE_ORDER;
+    if !in_range(r) || !in_range(s) {
+        return Err(SecpComponentError::OutOfRange);
+    }
+    if !allow_malleable && s > &SECP_CURVE_HALF_ORDER {
+        return Err(SecpComponentError::Malleable);
+    }
+
+    let mut signature = [0u8; SECP_SIG_LEN];
+    signature[..32].copy_from_slice(r);
+    signature[32..64].copy_from_slice(s);
+    signature[64] = recovery_id;
+    Ok(signature)
+}
 
 /// Signature variants for Filecoin signatures.
 #[derive(
@@ -33,6 +97,9 @@ pub const SECP_SIG_MESSAGE_HASH_SIZE: usize = 32;
 pub enum SignatureType {
     Secp256k1 = 1,
     BLS = 2,
+    /// An Ethereum-style Secp256k1 signature over the keccak256 hash of the data, signed by the
+    /// key behind an f410 (Ethereum) address.
+    Delegated = 3,
 }
 
 /// A cryptographic signature, represented in bytes, of any key protocol.
@@ -68,7 +135,7 @@ impl<'de> de::Deserialize<'de> for Signature {
 
         // Remove signature type byte
         let sig_type = SignatureType::from_u8(bytes[0])
-            .ok_or_else(|| de::Error::custom("Invalid signature type byte (must be 1 or 2)"))?;
+            .ok_or_else(|| de::Error::custom("Invalid signature type byte (must be 1, 2, or 3)"))?;
 
         Ok(Signature {
             bytes: bytes[1..].to_vec(),
@@ -94,6 +161,14 @@ impl Signature {
         }
     }
 
+    /// Creates a delegated (Ethereum) Signature given the raw `r || s || v` bytes.
+    pub fn new_delegated(bytes: Vec<u8>) -> Self {
+        Self {
+            sig_type: SignatureType::Delegated,
+            bytes,
+        }
+    }
+
     /// Returns reference to signature bytes.
     pub fn bytes(&self) -> &[u8] {
         &self.bytes
@@ -108,11 +183,12 @@ impl Signature {
 #[cfg(feature = "arb")]
 impl quickcheck::Arbitrary for SignatureType {
     fn arbitrary(g: &mut quickcheck::Gen) -> Self {
-        if bool::arbitrary(g) {
-            SignatureType::Secp256k1
-        } else {
-            SignatureType::BLS
-        }
+        *g.choose(&[
+            SignatureType::Secp256k1,
+            SignatureType::BLS,
+            SignatureType::Delegated,
+        ])
+        .unwrap()
     }
 }
 
@@ -144,6 +220,7 @@ pub fn verify(
     match sig_type {
         SignatureType::BLS => self::ops::verify_bls_sig(sig_data, data, addr),
         SignatureType::Secp256k1 => self::ops::verify_secp256k1_sig(sig_data, data, addr),
+        SignatureType::Delegated => self::ops::verify_delegated_sig(sig_data, data, addr),
     }
 }
 
@@ -155,9 +232,10 @@ pub mod ops {
     use libsecp256k1::{
         recover, Error as SecpError, Message, PublicKey, RecoveryId, Signature as EcsdaSignature,
     };
+    use multihash::{Code, MultihashDigest};
 
     use super::{Error, SECP_SIG_LEN, SECP_SIG_MESSAGE_HASH_SIZE};
-    use crate::address::{Address, Protocol};
+    use crate::address::{Address, EthAddress, Protocol, ETH_ADDRESS_LEN};
     use crate::crypto::signature::Signature;
 
     /// Returns `String` error if a bls signature is invalid.
@@ -229,6 +307,58 @@ pub mod ops {
         }
     }
 
+    /// Returns `String` error if a delegated (Ethereum) signature is invalid. The signature must be
+    /// an `r || s || v` Secp256k1 signature over the keccak256 hash of the data, by the key behind
+    /// the given f410 address.
+    pub fn verify_delegated_sig(
+        signature: &[u8],
+        data: &[u8],
+        addr: &Address,
+    ) -> Result<(), String> {
+        if addr.protocol() != Protocol::Delegated {
+            return Err(format!(
+                "cannot validate a delegated signature against a {} address",
+                addr.protocol()
+            ));
+        }
+        let eth_addr = EthAddress::from_address(addr).map_err(|e| e.to_string())?;
+
+        if signature.len() != SECP_SIG_LEN {
+            return Err(format!(
+                "Invalid delegated signature length. Was {}, must be 65",
+                signature.len()
+            ));
+        }
+
+        // Ecrecover with hash and signature
+        let mut sig = [0u8; SECP_SIG_LEN];
+        sig[..].copy_from_slice(signature);
+        let key = recover_secp_public_key(&keccak256(data), &sig).map_err(|e| e.to_string())?;
+        if eth_address_of(&key) == eth_addr {
+            Ok(())
+        } else {
+            Err("Delegated signature verification failed".to_owned())
+        }
+    }
+
+    /// Returns the keccak256 hash of the data.
+    pub fn keccak256(data: &[u8]) -> [u8; SECP_SIG_MESSAGE_HASH_SIZE] {
+        Code::Keccak256
+            .digest(data)
+            .digest()
+            .try_into()
+            .expect("fixed array size")
+    }
+
+    /// Returns the Ethereum address of a Secp256k1 public key: the last 20 bytes of the keccak256
+    /// hash of the uncompressed key (without its `0x04` prefix).
+    pub fn eth_address_of(key: &PublicKey) -> EthAddress {
+        let hash = Code::Keccak256.digest(&key.serialize()[1..]);
+        let mut addr = [0u8; ETH_ADDRESS_LEN];
+        addr.copy_from_slice(&hash.digest()[32 - ETH_ADDRESS_LEN..]);
+        EthAddress(addr)
+    }
+
     /// Aggregates and verifies bls signatures collectively.
     pub fn verify_bls_aggregate(
         data: &[&[u8]],
@@ -394,6 +524,78 @@ mod tests {
 
         assert_eq!(ecrecover(&hash, &signature).unwrap(), secp_addr);
     }
+
+    #[test]
+    fn delegated_verify() {
+        use super::ops::{eth_address_of, keccak256};
+
+        let rng = &mut ChaCha8Rng::seed_from_u64(8);
+
+        let priv_key = SecretKey::random(rng);
+        let pub_key = PublicKey::from_secret_key(&priv_key);
+        let addr = eth_address_of(&pub_key).to_address();
+        let data = b"message signing bytes";
+
+        let (sig, recovery_id) = sign(&Message::parse(&keccak256(data)), &priv_key);
+        let mut bytes = sig.serialize().to_vec();
+        bytes.push(recovery_id.serialize());
+        let signature = Signature::new_delegated(bytes);
+
+        signature.verify(data, &addr).unwrap();
+        assert!(signature.verify(b"other data", &addr).is_err());
+
+        // The signature isn't valid for any other address, nor as a plain secp256k1 signature.
+        let secp_addr = Address::new_secp256k1(&pub_key.serialize()).unwrap();
+        assert!(signature.verify(data, &secp_addr).is_err());
+        let other = Address::new_delegated(10, &[1; 20]).unwrap();
+        assert!(signature.verify(data, &other).is_err());
+    }
+
+    #[test]
+    fn recover_pubkey_rsv() {
+        let rng = &mut ChaCha8Rng::seed_from_u64(8);
+
+        let privkey = SecretKey::random(rng);
+        let pubkey = PublicKey::from_secret_key(&privkey);
+        let hash = [7u8; 32];
+
+        let (sig, recovery_id) = sign(&Message::parse(&hash), &privkey);
+        let sig = sig.serialize();
+        let r: [u8; 32] = sig[..32].try_into().unwrap();
+        let s: [u8; 32] = sig[32..].try_into().unwrap();
+        let v = recovery_id.serialize();
+
+        // Both the raw and Ethereum-style recovery ids are accepted.
+        for v in [v, v + 27] {
+            let signature = secp_signature_from_rsv(&r, &s, v, false).unwrap();
+            assert_eq!(pubkey, recover_secp_public_key(&hash, &signature).unwrap());
+        }
+
+        assert_eq!(
+            secp_signature_from_rsv(&r, &s, 2, false),
+            Err(SecpComponentError::InvalidRecoveryId(2))
+        );
+        assert_eq!(
+            secp_signature_from_rsv(&[0; 32], &s, v, false),
+            Err(SecpComponentError::OutOfRange)
+        );
+        assert_eq!(
+            secp_signature_from_rsv(&r, &SECP_CURVE_ORDER, v, false),
+            Err(SecpComponentError::OutOfRange)
+        );
+
+        // libsecp256k1 always produces low-s signatures, so flip s (and the recovery id) to get
+        // the equivalent high-s signature.
+        let mut high_s = libsecp256k1::Signature::parse_standard(&sig).unwrap();
+        high_s.s = -high_s.s;
+        let high_s: [u8; 32] = high_s.serialize()[32..].try_into().unwrap();
+        assert_eq!(
+            secp_signature_from_rsv(&r, &high_s, v ^ 1, false),
+            Err(SecpComponentError::Malleable)
+        );
+        let signature = secp_signature_from_rsv(&r, &high_s, v ^ 1, true).unwrap();
+        assert_eq!(pubkey, recover_secp_public_key(&hash, &signature).unwrap());
+    }
 }
 
 /// Crypto error
@@ -9,6 +9,7 @@ use num_bigint::BigInt;
 use num_integer::Integer;
 use num_traits::{Signed, Zero};
 use serde::{Deserialize, Serialize, Serializer};
+use thiserror::Error;
 
 use crate::bigint::bigint_ser;
 
@@ -67,6 +68,103 @@ impl TokenAmount {
     }
 }
 
+/// A unit in which token amounts are formatted and parsed (see [`TokenAmount::format_units`] and
+/// [`TokenAmount::parse_units`]).
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum TokenUnit {
+    /// Whole tokens (FIL).
+    Fil,
+    /// 10^-9 whole tokens (nanoFIL).
+    NanoFil,
+    /// Indivisible units, 10^-18 whole tokens (attoFIL).
+    AttoFil,
+}
+
+impl TokenUnit {
+    /// The number of decimal places of the unit, in indivisible units.
+    pub const fn decimals(self) -> usize {
+        match self {
+            TokenUnit::Fil => TokenAmount::DECIMALS,
+            TokenUnit::NanoFil => 9,
+            TokenUnit::AttoFil => 0,
+        }
+    }
+
+    /// The unit's symbol.
+    pub const fn symbol(self) -> &'static str {
+        match self {
+            TokenUnit::Fil => "FIL",
+            TokenUnit::NanoFil => "nanoFIL",
+            TokenUnit::AttoFil => "attoFIL",
+        }
+    }
+}
+
+/// An error parsing a token amount with [`TokenAmount::parse_units`].
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum ParseTokenAmountError {
+    #[error("empty token amount")]
+    Empty,
+    #[error("invalid token amount: expected decimal digits with an optional fraction")]
+    Invalid,
+    #[error("token amount has more than the {0} decimal places of the unit")]
+    TooPrecise(usize),
+}
+
+impl TokenAmount {
+    /// Formats the amount as a fixed-point decimal number of the given unit, without the unit's
+    /// symbol, rounding, or any locale-specific separators. Trailing fractional zeros are omitted,
+    /// as is the decimal point for whole amounts: e.g., `1.5`, `-2`, or `0.000000001`.
+    pub fn format_units(&self, unit: TokenUnit) -> String {
+        let sign = if self.atto.is_negative() { "-" } else { "" };
+        let digits = self.atto.abs().to_str_radix(10);
+        let decimals = unit.decimals();
+        if decimals == 0 {
+            return format!("{}{}", sign, digits);
+        }
+        // Pad with zeros so that there's at least one integer digit.
+        let digits = format!("{:0>width$}", digits, width = decimals + 1);
+        let (int, frac) = digits.split_at(digits.len() - decimals);
+        let frac = frac.trim_end_matches('0');
+        if frac.is_empty() {
+            format!("{}{}", sign, int)
+        } else {
+            format!("{}{}.{}", sign, int, frac)
+        }
+    }
+
+    /// Parses a fixed-point decimal number of the given unit (as formatted by
+    /// [`format_units`](Self::format_units)), e.g., `1.5`, `-2`, or `0.000000001`. Amounts more
+    /// precise than the unit's decimal places are rejected rather than rounded.
+    pub fn parse_units(s: &str, unit: TokenUnit) -> Result<Self, ParseTokenAmountError> {
+        if s.is_empty() {
+            return Err(ParseTokenAmountError::Empty);
+        }
+        let (negative, s) = match s.as_bytes()[0] {
+            b'-' => (true, &s[1..]),
+            b'+' => (false, &s[1..]),
+            _ => (false, s),
+        };
+        let (int, frac) = match s.split_once('.') {
+            Some((int, frac)) => (int, frac),
+            None => (s, ""),
+        };
+        let all_digits = |s: &str| s.bytes().all(|b| b.is_ascii_digit());
+        if int.is_empty() || !all_digits(int) || !all_digits(frac) || s.ends_with('.') {
+            return Err(ParseTokenAmountError::Invalid);
+        }
+        let decimals = unit.decimals();
+        if frac.len() > decimals {
+            return Err(ParseTokenAmountError::TooPrecise(decimals));
+        }
+
+        let digits = format!("{}{:0<width$}", int, frac, width = decimals);
+        let atto =
+            BigInt::parse_bytes(digits.as_bytes(), 10).ok_or(ParseTokenAmountError::Invalid)?;
+        Ok(Self::from_atto(if negative { -atto } else { atto }))
+    }
+}
+
 impl Zero for TokenAmount {
     #[inline]
     fn zero() -> Self {
@@ -335,6 +433,111 @@ impl TokenAmount {
     }
 }
 
+/// An error from checked token amount arithmetic (e.g., [`TokenAmount::checked_sub`]).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Error)]
+pub enum ArithmeticError {
+    #[error("token amount is negative")]
+    Negative,
+    #[error("division by zero")]
+    DivisionByZero,
+}
+
+// Checked and saturating arithmetic, for amounts that may never be negative (e.g., balances).
+// Checked operations fail if any operand or the result is negative, while saturating operations
+// clamp negative results to zero.
+impl TokenAmount {
+    /// Returns `self + other`, failing if either operand is negative.
+    pub fn checked_add(&self, other: &TokenAmount) -> Result<TokenAmount, ArithmeticError> {
+        non_negative(self)?;
+        non_negative(other)?;
+        Ok(self + other)
+    }
+
+    /// Returns `self - other`, failing if either operand or the result is negative (i.e., if
+    /// `other` is greater than `self`).
+    pub fn checked_sub(&self, other: &TokenAmount) -> Result<TokenAmount, ArithmeticError> {
+        non_negative(self)?;
+        non_negative(other)?;
+        if other > self {
+            return Err(ArithmeticError::Negative);
+        }
+        Ok(self - other)
+    }
+
+    /// Returns `self * factor`, failing if the amount or the factor is negative.
+    pub fn checked_mul(&self, factor: impl Into<BigInt>) -> Result<TokenAmount, ArithmeticError> {
+        non_negative(self)?;
+        let factor: BigInt = factor.into();
+        if factor.is_negative() {
+            return Err(ArithmeticError::Negative);
+        }
+        Ok(self * factor)
+    }
+
+    /// Divides the amount into `divisor` equal parts, returning the parts and the remainder.
+    /// Fails if the amount or the divisor is negative, or the divisor is zero.
+    pub fn checked_div_rem(
+        &self,
+        divisor: impl Into<BigInt>,
+    ) -> Result<(TokenAmount, TokenAmount), ArithmeticError> {
+        non_negative(self)?;
+        let divisor: BigInt = divisor.into();
+        if divisor.is_negative() {
+            return Err(ArithmeticError::Negative);
+        }
+        if divisor.is_zero() {
+            return Err(ArithmeticError::DivisionByZero);
+        }
+        Ok(self.div_rem(divisor))
+    }
+
+    /// Returns how many times `other` fits in the amount, and the remainder. Fails if either
+    /// amount is negative, or `other` is zero.
+    pub fn checked_div_rem_amount(
+        &self,
+        other: &TokenAmount,
+    ) -> Result<(BigInt, TokenAmount), ArithmeticError> {
+        non_negative(self)?;
+        non_negative(other)?;
+        if other.is_zero() {
+            return Err(ArithmeticError::DivisionByZero);
+        }
+        let (q, r) = self.atto.div_rem(&other.atto);
+        Ok((q, TokenAmount { atto: r }))
+    }
+
+    /// Returns `self + other`, or zero if the result is negative.
+    pub fn saturating_add(&self, other: &TokenAmount) -> TokenAmount {
+        clamp_negative(self + other)
+    }
+
+    /// Returns `self - other`, or zero if the result is negative.
+    pub fn saturating_sub(&self, other: &TokenAmount) -> TokenAmount {
+        clamp_negative(self - other)
+    }
+
+    /// Returns `self * factor`, or zero if the result is negative.
+    pub fn saturating_mul(&self, factor: impl Into<BigInt>) -> TokenAmount {
+        let factor: BigInt = factor.into();
+        clamp_negative(self * factor)
+    }
+}
+
+fn non_negative(amount: &TokenAmount) -> Result<(), ArithmeticError> {
+    if amount.is_negative() {
+        return Err(ArithmeticError::Negative);
+    }
+    Ok(())
+}
+
+fn clamp_negative(amount: TokenAmount) -> TokenAmount {
+    if amount.is_negative() {
+        TokenAmount::zero()
+    } else {
+        amount
+    }
+}
+
 impl Sum for TokenAmount {
     fn sum<I: Iterator<Item = Self>>(iter: I) -> Self {
         Self::from_atto(iter.map(|t| t.atto).sum::<BigInt>())
@@ -464,6 +667,132 @@ mod test {
         )
     }
 
+    #[test]
+    fn format_units() {
+        use super::TokenUnit::*;
+
+        let cases = [
+            (TokenAmount::zero(), "0", "0", "0"),
+            (whole(1), "1", "1000000000", "1000000000000000000"),
+            (atto(1), "0.000000000000000001", "0.000000001", "1"),
+            (
+                TokenAmount::from_nano(1500),
+                "0.0000015",
+                "1500",
+                "1500000000000",
+            ),
+            (
+                whole(1234) + atto(123_456_789_u64),
+                "1234.000000000123456789",
+                "1234000000000.123456789",
+                "1234000000000123456789",
+            ),
+            (
+                -whole(2) - atto(1),
+                "-2.000000000000000001",
+                "-2000000000.000000001",
+                "-2000000000000000001",
+            ),
+        ];
+        for (amount, fil, nano, attos) in cases {
+            assert_eq!(amount.format_units(Fil), fil);
+            assert_eq!(amount.format_units(NanoFil), nano);
+            assert_eq!(amount.format_units(AttoFil), attos);
+            assert_eq!(TokenAmount::parse_units(fil, Fil).unwrap(), amount);
+            assert_eq!(TokenAmount::parse_units(nano, NanoFil).unwrap(), amount);
+            assert_eq!(TokenAmount::parse_units(attos, AttoFil).unwrap(), amount);
+        }
+    }
+
+    #[test]
+    fn parse_units() {
+        use super::ParseTokenAmountError::*;
+        use super::TokenUnit::*;
+
+        assert_eq!(
+            TokenAmount::parse_units("1.50", Fil).unwrap(),
+            atto(1_500_000_000_000_000_000_u128)
+        );
+        assert_eq!(
+            TokenAmount::parse_units("+0.5", NanoFil).unwrap(),
+            atto(500_000_000)
+        );
+        assert_eq!(TokenAmount::parse_units("007", AttoFil).unwrap(), atto(7));
+        assert_eq!(
+            TokenAmount::parse_units("-0", Fil).unwrap(),
+            TokenAmount::zero()
+        );
+
+        assert_eq!(TokenAmount::parse_units("", Fil), Err(Empty));
+        for invalid in [
+            "-", ".5", "1.", "1.2.3", "1,5", "1e18", " 1", "0x10", "--1", "1.-5",
+        ] {
+            assert_eq!(
+                TokenAmount::parse_units(invalid, Fil),
+                Err(Invalid),
+                "{}",
+                invalid
+            );
+        }
+        assert_eq!(
+            TokenAmount::parse_units("0.0000000000000000001", Fil),
+            Err(TooPrecise(18))
+        );
+        assert_eq!(
+            TokenAmount::parse_units("0.0000000001", NanoFil),
+            Err(TooPrecise(9))
+        );
+        assert_eq!(TokenAmount::parse_units("1.0", AttoFil), Err(TooPrecise(0)));
+    }
+
+    #[test]
+    fn checked_ops() {
+        use super::ArithmeticError::*;
+
+        assert_eq!(atto(5).checked_add(&atto(3)), Ok(atto(8)));
+        assert_eq!(atto(5).checked_add(&atto(-3)), Err(Negative));
+        assert_eq!(atto(-5).checked_add(&atto(8)), Err(Negative));
+
+        assert_eq!(atto(5).checked_sub(&atto(3)), Ok(atto(2)));
+        assert_eq!(atto(5).checked_sub(&atto(5)), Ok(atto(0)));
+        assert_eq!(atto(5).checked_sub(&atto(6)), Err(Negative));
+        assert_eq!(atto(5).checked_sub(&atto(-1)), Err(Negative));
+
+        assert_eq!(atto(5).checked_mul(3), Ok(atto(15)));
+        assert_eq!(atto(5).checked_mul(0), Ok(atto(0)));
+        assert_eq!(atto(5).checked_mul(-3), Err(Negative));
+        assert_eq!(atto(-5).checked_mul(3), Err(Negative));
+
+        assert_eq!(atto(14).checked_div_rem(4), Ok((atto(3), atto(2))));
+        assert_eq!(atto(14).checked_div_rem(0), Err(DivisionByZero));
+        assert_eq!(atto(14).checked_div_rem(-4), Err(Negative));
+        assert_eq!(atto(-14).checked_div_rem(4), Err(Negative));
+
+        assert_eq!(
+            whole(3).checked_div_rem_amount(&atto(2)),
+            Ok((BigInt::from(TokenAmount::PRECISION) * 3 / 2, atto(0)))
+        );
+        assert_eq!(
+            atto(14).checked_div_rem_amount(&atto(4)),
+            Ok((BigInt::from(3), atto(2)))
+        );
+        assert_eq!(
+            atto(14).checked_div_rem_amount(&atto(0)),
+            Err(DivisionByZero)
+        );
+        assert_eq!(atto(14).checked_div_rem_amount(&atto(-4)), Err(Negative));
+    }
+
+    #[test]
+    fn saturating_ops() {
+        assert_eq!(atto(5).saturating_add(&atto(3)), atto(8));
+        assert_eq!(atto(5).saturating_add(&atto(-8)), atto(0));
+        assert_eq!(atto(5).saturating_sub(&atto(3)), atto(2));
+        assert_eq!(atto(5).saturating_sub(&atto(8)), atto(0));
+        assert_eq!(atto(5).saturating_mul(3), atto(15));
+        assert_eq!(atto(5).saturating_mul(-3), atto(0));
+    }
+
     #[test]
     fn test_mul() {
         let a = atto(2) * 3;
@@ -19,19 +19,164 @@ impl ExitCode {
         Self { value }
     }
 
-    pub fn value(self) -> u32 {
+    pub const fn value(self) -> u32 {
         self.value
     }
 
     /// Returns true if the exit code indicates success.
-    pub fn is_success(self) -> bool {
+    pub const fn is_success(self) -> bool {
         self.value == 0
     }
 
     /// Returns true if the error code is in the range of exit codes reserved for the VM
     /// (including Ok).
-    pub fn is_system_error(self) -> bool {
-        self.value < (Self::FIRST_USER_EXIT_CODE)
+    pub const fn is_system_error(self) -> bool {
+        Self::SYSTEM_RANGE.contains(self)
+    }
+
+    /// Returns true if the exit code is one of the standard exit codes shared by all actors (see
+    /// [`COMMON_USER_RANGE`](Self::COMMON_USER_RANGE)).
+    pub const fn is_common_user_error(self) -> bool {
+        Self::COMMON_USER_RANGE.contains(self)
+    }
+
+    /// Returns true if the exit code is in the range left to individual actors (see
+    /// [`ACTOR_SPECIFIC_RANGE`](Self::ACTOR_SPECIFIC_RANGE)).
+    pub const fn is_actor_specific(self) -> bool {
+        Self::ACTOR_SPECIFIC_RANGE.contains(self)
+    }
+
+    /// Returns true if the exit code is in the given range.
+    pub const fn in_range(self, range: ExitCodeRange) -> bool {
+        range.contains(self)
+    }
+
+    /// Returns the actor specific exit code at the given offset in the
+    /// [`ACTOR_SPECIFIC_RANGE`](Self::ACTOR_SPECIFIC_RANGE), if in range.
+    pub const fn actor_specific(offset: u32) -> Option<ExitCode> {
+        Self::ACTOR_SPECIFIC_RANGE.get(offset)
+    }
+
+    /// The exit codes reserved for the VM (including Ok). Actors may not abort with these.
+    pub const SYSTEM_RANGE: ExitCodeRange = ExitCodeRange::new(0, Self::FIRST_USER_EXIT_CODE - 1);
+
+    /// The standard exit codes shared by all actors (e.g.,
+    /// [`USR_ILLEGAL_ARGUMENT`](Self::USR_ILLEGAL_ARGUMENT)), including reserved but unassigned
+    /// ones.
+    pub const COMMON_USER_RANGE: ExitCodeRange = ExitCodeRange::new(
+        Self::FIRST_USER_EXIT_CODE,
+        Self::FIRST_ACTOR_SPECIFIC_EXIT_CODE - 1,
+    );
+
+    /// The exit codes left to individual actors to define.
+    pub const ACTOR_SPECIFIC_RANGE: ExitCodeRange =
+        ExitCodeRange::new(Self::FIRST_ACTOR_SPECIFIC_EXIT_CODE, u32::MAX);
+}
+
+/// An inclusive range of exit codes: e.g., the ranges of the exit code taxonomy (see
+/// [`ExitCode::SYSTEM_RANGE`]), or a range an embedder or actor reserves for its custom exit codes.
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub struct ExitCodeRange {
+    first: u32,
+    last: u32,
+}
+
+impl ExitCodeRange {
+    /// Creates the range `first..=last`. Panics if `first` is greater than `last`.
+    pub const fn new(first: u32, last: u32) -> Self {
+        assert!(first <= last, "empty exit code range");
+        Self { first, last }
+    }
+
+    /// The first exit code in the range.
+    pub const fn first(self) -> ExitCode {
+        ExitCode::new(self.first)
+    }
+
+    /// The last exit code in the range (inclusive).
+    pub const fn last(self) -> ExitCode {
+        ExitCode::new(self.last)
+    }
+
+    /// Returns true if the exit code is in the range.
+    pub const fn contains(self, code: ExitCode) -> bool {
+        self.first <= code.value && code.value <= self.last
+    }
+
+    /// Returns the exit code at the given offset from the start of the range, if in range.
+    pub const fn get(self, offset: u32) -> Option<ExitCode> {
+        match self.first.checked_add(offset) {
+            Some(value) if value <= self.last => Some(ExitCode::new(value)),
+            _ => None,
+        }
+    }
+}
+
+/// An exit code actors may abort with: any non-system exit code. Converting from a raw `u32` or
+/// an [`ExitCode`] fails for codes reserved for the VM, which the VM would otherwise replace with
+/// [`ExitCode::SYS_ILLEGAL_EXIT_CODE`] when aborting.
+#[derive(PartialEq, Eq, Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(try_from = "u32", into = "u32")]
+pub struct UserExitCode(ExitCode);
+
+impl UserExitCode {
+    /// Returns the user exit code with the given value, or `None` if it's reserved for the VM.
+    pub const fn new(value: u32) -> Option<Self> {
+        let code = ExitCode::new(value);
+        if code.is_system_error() {
+            None
+        } else {
+            Some(Self(code))
+        }
+    }
+
+    /// Returns the exit code.
+    pub const fn code(self) -> ExitCode {
+        self.0
+    }
+
+    /// Returns the exit code's value.
+    pub const fn value(self) -> u32 {
+        self.0.value
+    }
+}
+
+/// The error returned when converting an exit code reserved for the VM into a [`UserExitCode`].
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Error)]
+#[error("exit code {0} is reserved for the system")]
+pub struct ReservedExitCodeError(pub ExitCode);
+
+impl TryFrom<u32> for UserExitCode {
+    type Error = ReservedExitCodeError;
+
+    fn try_from(value: u32) -> Result<Self, Self::Error> {
+        Self::new(value).ok_or(ReservedExitCodeError(ExitCode::new(value)))
+    }
+}
+
+impl TryFrom<ExitCode> for UserExitCode {
+    type Error = ReservedExitCodeError;
+
+    fn try_from(code: ExitCode) -> Result<Self, Self::Error> {
+        Self::try_from(code.value)
+    }
+}
+
+impl From<UserExitCode> for ExitCode {
+    fn from(code: UserExitCode) -> Self {
+        code.0
+    }
+}
+
+impl From<UserExitCode> for u32 {
+    fn from(code: UserExitCode) -> Self {
+        code.0.value
+    }
+}
+
+impl std::fmt::Display for UserExitCode {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        std::fmt::Display::fmt(&self.0, f)
     }
 }
 
@@ -85,6 +230,10 @@ impl ExitCode {
     /// The lowest exit code that an actor may abort with.
     pub const FIRST_USER_EXIT_CODE: u32 = 16;
 
+    /// The lowest exit code left to individual actors to define (see
+    /// [`ACTOR_SPECIFIC_RANGE`](Self::ACTOR_SPECIFIC_RANGE)).
+    pub const FIRST_ACTOR_SPECIFIC_EXIT_CODE: u32 = 32;
+
     // Standard exit codes according to the built-in actors' calling convention.
     /// The method parameters are invalid.
     pub const USR_ILLEGAL_ARGUMENT: ExitCode = ExitCode::new(16);
@@ -118,6 +267,9 @@ impl ExitCode {
 /// When a syscall fails, it returns an `ErrorNumber` to indicate why. The syscalls themselves
 /// include documentation on _which_ syscall errors they can be expected to return, and what they
 /// mean in the context of the syscall.
+///
+/// Custom syscalls (in downstream kernels) may also return embedder-specific error numbers (see
+/// [`CustomErrorNumber`]).
 #[non_exhaustive]
 #[repr(u32)]
 #[derive(Copy, Clone, Eq, Debug, PartialEq, Error, FromPrimitive)]
@@ -175,3 +327,168 @@ impl std::fmt::Display for ErrorNumber {
         })
     }
 }
+
+/// An embedder-specific syscall error number, returned by custom syscalls in downstream kernels.
+///
+/// Custom error numbers are allocated from a range reserved for embedders,
+/// [`FIRST`](Self::FIRST) through [`LAST`](Self::LAST), from which [`ErrorNumber`]s will never be
+/// assigned. They're returned to actors as raw `u32` error numbers, and each is declared with the
+/// name used when displaying it.
+///
+/// ```
+/// use fvm_shared::error::CustomErrorNumber;
+///
+/// const QUOTA_EXCEEDED: CustomErrorNumber = CustomErrorNumber::new(0, "quota exceeded");
+///
+/// assert_eq!(QUOTA_EXCEEDED.value(), CustomErrorNumber::FIRST);
+/// assert_eq!(QUOTA_EXCEEDED.to_string(), "quota exceeded");
+/// ```
+#[derive(Copy, Clone, Debug)]
+pub struct CustomErrorNumber {
+    value: u32,
+    name: &'static str,
+}
+
+impl CustomErrorNumber {
+    /// The first error number reserved for embedders.
+    pub const FIRST: u32 = 1 << 31;
+    /// The last error number reserved for embedders.
+    pub const LAST: u32 = u32::MAX;
+
+    /// Declares the custom error number at the given offset into the reserved range, with the
+    /// given name.
+    ///
+    /// Panics if the offset is out of range (i.e., at least `2^31`).
+    pub const fn new(offset: u32, name: &'static str) -> Self {
+        match Self::FIRST.checked_add(offset) {
+            Some(value) => Self { value, name },
+            None => panic!("custom error number out of range"),
+        }
+    }
+
+    /// Returns true if the error number (as returned by a syscall) is in the range reserved for
+    /// embedders.
+    pub const fn is_reserved(value: u32) -> bool {
+        value >= Self::FIRST
+    }
+
+    /// Returns the error number's value, as returned by syscalls.
+    pub const fn value(self) -> u32 {
+        self.value
+    }
+
+    /// Returns the error number's offset into the reserved range.
+    pub const fn offset(self) -> u32 {
+        self.value - Self::FIRST
+    }
+
+    /// Returns the error number's name.
+    pub const fn name(self) -> &'static str {
+        self.name
+    }
+}
+
+// Error numbers are identified by their values alone.
+impl PartialEq for CustomErrorNumber {
+    fn eq(&self, other: &Self) -> bool {
+        self.value == other.value
+    }
+}
+
+impl Eq for CustomErrorNumber {}
+
+impl std::hash::Hash for CustomErrorNumber {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.value.hash(state)
+    }
+}
+
+impl std::fmt::Display for CustomErrorNumber {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn taxonomy() {
+        assert!(ExitCode::OK.is_system_error());
+        assert!(ExitCode::SYS_MISSING_RETURN.is_system_error());
+        assert!(!ExitCode::new(15).is_common_user_error());
+        assert!(ExitCode::USR_ILLEGAL_ARGUMENT.is_common_user_error());
+        assert!(ExitCode::new(31).is_common_user_error());
+        assert!(!ExitCode::new(31).is_actor_specific());
+        assert!(ExitCode::new(32).is_actor_specific());
+        assert!(ExitCode::new(u32::MAX).is_actor_specific());
+
+        assert_eq!(ExitCode::actor_specific(0), Some(ExitCode::new(32)));
+        assert_eq!(
+            ExitCode::actor_specific(u32::MAX - 32),
+            Some(ExitCode::new(u32::MAX))
+        );
+        assert_eq!(ExitCode::actor_specific(u32::MAX - 31), None);
+    }
+
+    #[test]
+    fn custom_range() {
+        const CUSTOM: ExitCodeRange = ExitCodeRange::new(1000, 1009);
+        assert_eq!(CUSTOM.first(), ExitCode::new(1000));
+        assert_eq!(CUSTOM.last(), ExitCode::new(1009));
+        assert_eq!(CUSTOM.get(9), Some(ExitCode::new(1009)));
+        assert_eq!(CUSTOM.get(10), None);
+        assert!(ExitCode::new(1005).in_range(CUSTOM));
+        assert!(!ExitCode::new(999).in_range(CUSTOM));
+        assert!(!ExitCode::new(1010).in_range(CUSTOM));
+    }
+
+    #[test]
+    fn custom_error_numbers() {
+        let number = CustomErrorNumber::new(7, "rate limited");
+        assert_eq!(number.value(), (1 << 31) + 7);
+        assert_eq!(number.offset(), 7);
+        assert_eq!(number.to_string(), "rate limited");
+        assert_eq!(number, CustomErrorNumber::new(7, "throttled"));
+        assert_ne!(number, CustomErrorNumber::new(8, "rate limited"));
+        assert_eq!(
+            CustomErrorNumber::new(u32::MAX >> 1, "last").value(),
+            CustomErrorNumber::LAST
+        );
+
+        assert!(CustomErrorNumber::is_reserved(number.value()));
+        assert!(!CustomErrorNumber::is_reserved(
+            ErrorNumber::ReadOnly as u32
+        ));
+        assert!(!CustomErrorNumber::is_reserved(
+            CustomErrorNumber::FIRST - 1
+        ));
+    }
+
+    #[test]
+    fn user_exit_code() {
+        assert_eq!(
+            UserExitCode::try_from(ExitCode::SYS_OUT_OF_GAS),
+            Err(ReservedExitCodeError(ExitCode::SYS_OUT_OF_GAS))
+        );
+        assert_eq!(
+            UserExitCode::try_from(0),
+            Err(ReservedExitCodeError(ExitCode::OK))
+        );
+        let code = UserExitCode::try_from(ExitCode::USR_FORBIDDEN).unwrap();
+        assert_eq!(ExitCode::from(code), ExitCode::USR_FORBIDDEN);
+        assert_eq!(UserExitCode::new(40).map(UserExitCode::value), Some(40));
+
+        let encoded = fvm_ipld_encoding::to_vec(&code).unwrap();
+        assert_eq!(encoded, fvm_ipld_encoding::to_vec(&18u32).unwrap());
+        assert_eq!(
+            fvm_ipld_encoding::from_slice::<UserExitCode>(&encoded).unwrap(),
+            code
+        );
+        assert!(fvm_ipld_encoding::from_slice::<UserExitCode>(
+            &fvm_ipld_encoding::to_vec(&7u32).unwrap()
+        )
+        .is_err());
+    }
+}
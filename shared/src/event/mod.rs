@@ -1,11 +1,18 @@
 // Copyright 2021-2023 Protocol Labs
 // SPDX-License-Identifier: Apache-2.0, MIT
 use bitflags::bitflags;
-use fvm_ipld_encoding::strict_bytes;
+use cid::Cid;
+use fvm_ipld_encoding::de::DeserializeOwned;
+use fvm_ipld_encoding::{strict_bytes, CBOR, DAG_CBOR};
+use num_bigint::{BigInt, Sign};
 use serde::{Deserialize, Serialize};
 use serde_tuple::*;
+use thiserror::Error;
 
-use crate::ActorID;
+use crate::address::Address;
+use crate::bigint::MAX_BIGINT_SIZE;
+use crate::econ::TokenAmount;
+use crate::{ActorID, IPLD_RAW};
 
 /// Event with extra information stamped by the FVM. This is the structure that gets committed
 /// on-chain via the receipt.
@@ -36,6 +43,12 @@ impl From<Vec<Entry>> for ActorEvent {
     }
 }
 
+impl From<EventBuilder> for ActorEvent {
+    fn from(builder: EventBuilder) -> Self {
+        builder.build()
+    }
+}
+
 bitflags! {
     /// Flags associated with an Event entry.
     #[derive(Deserialize, Serialize, Copy, Clone, Eq, PartialEq, Debug)]
@@ -60,3 +73,523 @@ pub struct Entry {
     #[serde(with = "strict_bytes")]
     pub value: Vec<u8>,
 }
+
+/// An entry's value, along with its codec. Values converted from common types are encoded as
+/// `IPLD_RAW` (the only codec the FVM currently accepts), and decoded by [`EventValue`]:
+///
+/// - Integers as big-endian bytes.
+/// - Big integers and token amounts (in attoFIL) as a sign byte followed by the big-endian
+///   magnitude, as in their CBOR serialization (zero is empty).
+/// - Byte vectors and arrays as-is.
+/// - Strings as UTF-8.
+/// - Addresses and CIDs in their binary form.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct EntryValue {
+    /// The value's codec.
+    pub codec: u64,
+    /// The encoded value.
+    pub value: Vec<u8>,
+}
+
+impl EntryValue {
+    /// Creates an `IPLD_RAW` value.
+    pub fn raw(value: impl Into<Vec<u8>>) -> Self {
+        Self {
+            codec: IPLD_RAW,
+            value: value.into(),
+        }
+    }
+}
+
+impl From<&u64> for EntryValue {
+    fn from(v: &u64) -> Self {
+        Self::raw(v.to_be_bytes())
+    }
+}
+
+impl From<&[u8]> for EntryValue {
+    fn from(v: &[u8]) -> Self {
+        Self::raw(v)
+    }
+}
+
+impl From<Vec<u8>> for EntryValue {
+    fn from(v: Vec<u8>) -> Self {
+        Self::raw(v)
+    }
+}
+
+impl From<&Vec<u8>> for EntryValue {
+    fn from(v: &Vec<u8>) -> Self {
+        Self::raw(v.as_slice())
+    }
+}
+
+impl<const N: usize> From<[u8; N]> for EntryValue {
+    fn from(v: [u8; N]) -> Self {
+        Self::raw(v)
+    }
+}
+
+impl<const N: usize> From<&[u8; N]> for EntryValue {
+    fn from(v: &[u8; N]) -> Self {
+        Self::raw(v.as_slice())
+    }
+}
+
+impl From<&str> for EntryValue {
+    fn from(v: &str) -> Self {
+        Self::raw(v)
+    }
+}
+
+impl From<String> for EntryValue {
+    fn from(v: String) -> Self {
+        Self::raw(v)
+    }
+}
+
+impl From<&String> for EntryValue {
+    fn from(v: &String) -> Self {
+        Self::raw(v.as_str())
+    }
+}
+
+impl From<&Cid> for EntryValue {
+    fn from(v: &Cid) -> Self {
+        Self::raw(v.to_bytes())
+    }
+}
+
+impl From<&Address> for EntryValue {
+    fn from(v: &Address) -> Self {
+        Self::raw(v.to_bytes())
+    }
+}
+
+impl From<&BigInt> for EntryValue {
+    fn from(v: &BigInt) -> Self {
+        let (sign, mut bytes) = v.to_bytes_be();
+        match sign {
+            Sign::Minus => bytes.insert(0, 1),
+            Sign::Plus => bytes.insert(0, 0),
+            Sign::NoSign => bytes.clear(),
+        }
+        Self::raw(bytes)
+    }
+}
+
+impl From<&TokenAmount> for EntryValue {
+    fn from(v: &TokenAmount) -> Self {
+        v.atto().into()
+    }
+}
+
+/// Implements the conversion from owned values by encoding them by reference.
+macro_rules! impl_entry_value_from_owned {
+    ($($ty:ty),*) => {
+        $(impl From<$ty> for EntryValue {
+            fn from(v: $ty) -> Self {
+                Self::from(&v)
+            }
+        })*
+    };
+}
+
+impl_entry_value_from_owned!(u64, Cid, Address, BigInt, TokenAmount);
+
+/// Builds an [`ActorEvent`] entry by entry, encoding values with the codec matching their type
+/// (see [`EntryValue`]).
+///
+/// ```
+/// use fvm_shared::address::Address;
+/// use fvm_shared::econ::TokenAmount;
+/// use fvm_shared::event::{EventBuilder, Flags};
+///
+/// let event = EventBuilder::new()
+///     .field("type", "transfer")
+///     .field("from", Address::new_id(100))
+///     .field("to", Address::new_id(101))
+///     .unindexed_field("amount", TokenAmount::from_whole(1))
+///     .build();
+/// assert_eq!(event.decode_field::<Address>("to"), Ok(Address::new_id(101)));
+/// assert_eq!(event.get("amount").unwrap().flags, Flags::empty());
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct EventBuilder {
+    entries: Vec<Entry>,
+}
+
+impl EventBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds an entry with both its key and value indexed.
+    pub fn field(self, key: &str, value: impl Into<EntryValue>) -> Self {
+        self.field_with_flags(key, value, Flags::FLAG_INDEXED_ALL)
+    }
+
+    /// Adds an entry with only its key indexed.
+    pub fn key_indexed_field(self, key: &str, value: impl Into<EntryValue>) -> Self {
+        self.field_with_flags(key, value, Flags::FLAG_INDEXED_KEY)
+    }
+
+    /// Adds an entry that isn't indexed.
+    pub fn unindexed_field(self, key: &str, value: impl Into<EntryValue>) -> Self {
+        self.field_with_flags(key, value, Flags::empty())
+    }
+
+    /// Adds an entry with the given flags.
+    pub fn field_with_flags(
+        mut self,
+        key: &str,
+        value: impl Into<EntryValue>,
+        flags: Flags,
+    ) -> Self {
+        let EntryValue { codec, value } = value.into();
+        self.entries.push(Entry {
+            flags,
+            key: key.into(),
+            codec,
+            value,
+        });
+        self
+    }
+
+    /// Returns the built event.
+    pub fn build(self) -> ActorEvent {
+        self.entries.into()
+    }
+}
+
+/// An error decoding an event (or one of its entries).
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum EventDecodeError {
+    #[error("event has no entry with key {0:?}")]
+    MissingKey(String),
+    #[error("unsupported codec {0:#x} for the requested type")]
+    UnsupportedCodec(u64),
+    #[error("invalid event value: {0}")]
+    InvalidValue(String),
+}
+
+/// A value that can be decoded from an event entry, based on the entry's codec (see
+/// [`Entry::decode`]). `IPLD_RAW` values are decoded as encoded by the SDK's event builder:
+///
+/// - Integers as big-endian bytes (of at most the integer's size).
+/// - Big integers and token amounts as a sign byte followed by the big-endian magnitude.
+/// - Byte vectors and arrays as-is.
+/// - Strings as UTF-8.
+/// - Addresses and CIDs in their binary form.
+///
+/// CBOR (or DAG-CBOR) values can be decoded into any deserializable type with [`Cbor`].
+pub trait EventValue: Sized {
+    /// Decodes the value from an entry's codec and value.
+    fn decode_event_value(codec: u64, value: &[u8]) -> Result<Self, EventDecodeError>;
+}
+
+/// Checks that the value is `IPLD_RAW` encoded, returning it.
+fn raw(codec: u64, value: &[u8]) -> Result<&[u8], EventDecodeError> {
+    match codec {
+        IPLD_RAW => Ok(value),
+        _ => Err(EventDecodeError::UnsupportedCodec(codec)),
+    }
+}
+
+impl EventValue for u64 {
+    fn decode_event_value(codec: u64, value: &[u8]) -> Result<Self, EventDecodeError> {
+        let value = raw(codec, value)?;
+        if value.len() > 8 {
+            return Err(EventDecodeError::InvalidValue(format!(
+                "{} byte integer doesn't fit in a u64",
+                value.len()
+            )));
+        }
+        let mut bytes = [0u8; 8];
+        bytes[8 - value.len()..].copy_from_slice(value);
+        Ok(u64::from_be_bytes(bytes))
+    }
+}
+
+impl EventValue for Vec<u8> {
+    fn decode_event_value(codec: u64, value: &[u8]) -> Result<Self, EventDecodeError> {
+        raw(codec, value).map(<[u8]>::to_vec)
+    }
+}
+
+impl<const N: usize> EventValue for [u8; N] {
+    fn decode_event_value(codec: u64, value: &[u8]) -> Result<Self, EventDecodeError> {
+        raw(codec, value)?.try_into().map_err(|_| {
+            EventDecodeError::InvalidValue(format!("expected {} bytes, got {}", N, value.len()))
+        })
+    }
+}
+
+impl EventValue for String {
+    fn decode_event_value(codec: u64, value: &[u8]) -> Result<Self, EventDecodeError> {
+        String::from_utf8(raw(codec, value)?.to_vec())
+            .map_err(|e| EventDecodeError::InvalidValue(e.to_string()))
+    }
+}
+
+impl EventValue for Cid {
+    fn decode_event_value(codec: u64, value: &[u8]) -> Result<Self, EventDecodeError> {
+        Cid::read_bytes(raw(codec, value)?)
+            .map_err(|e| EventDecodeError::InvalidValue(e.to_string()))
+    }
+}
+
+impl EventValue for Address {
+    fn decode_event_value(codec: u64, value: &[u8]) -> Result<Self, EventDecodeError> {
+        Address::from_bytes(raw(codec, value)?)
+            .map_err(|e| EventDecodeError::InvalidValue(e.to_string()))
+    }
+}
+
+impl EventValue for BigInt {
+    fn decode_event_value(codec: u64, value: &[u8]) -> Result<Self, EventDecodeError> {
+        let value = raw(codec, value)?;
+        if value.len() > MAX_BIGINT_SIZE {
+            return Err(EventDecodeError::InvalidValue(format!(
+                "{} byte integer exceeds the maximum of {} bytes",
+                value.len(),
+                MAX_BIGINT_SIZE
+            )));
+        }
+        let sign = match value.first() {
+            None => return Ok(BigInt::default()),
+            Some(0) => Sign::Plus,
+            Some(1) => Sign::Minus,
+            Some(b) => {
+                return Err(EventDecodeError::InvalidValue(format!(
+                    "invalid integer sign byte {}",
+                    b
+                )))
+            }
+        };
+        Ok(BigInt::from_bytes_be(sign, &value[1..]))
+    }
+}
+
+impl EventValue for TokenAmount {
+    fn decode_event_value(codec: u64, value: &[u8]) -> Result<Self, EventDecodeError> {
+        BigInt::decode_event_value(codec, value).map(TokenAmount::from_atto)
+    }
+}
+
+/// A CBOR (or DAG-CBOR) encoded event value.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Cbor<T>(pub T);
+
+impl<T: DeserializeOwned> EventValue for Cbor<T> {
+    fn decode_event_value(codec: u64, value: &[u8]) -> Result<Self, EventDecodeError> {
+        match codec {
+            CBOR | DAG_CBOR => fvm_ipld_encoding::from_slice(value)
+                .map(Cbor)
+                .map_err(|e| EventDecodeError::InvalidValue(e.to_string())),
+            _ => Err(EventDecodeError::UnsupportedCodec(codec)),
+        }
+    }
+}
+
+impl Entry {
+    /// Decodes the entry's value.
+    pub fn decode<T: EventValue>(&self) -> Result<T, EventDecodeError> {
+        T::decode_event_value(self.codec, &self.value)
+    }
+}
+
+impl ActorEvent {
+    /// Returns the first entry with the given key, if any.
+    pub fn get(&self, key: &str) -> Option<&Entry> {
+        self.entries.iter().find(|e| e.key == key)
+    }
+
+    /// Decodes the value of the first entry with the given key.
+    pub fn decode_field<T: EventValue>(&self, key: &str) -> Result<T, EventDecodeError> {
+        self.get(key)
+            .ok_or_else(|| EventDecodeError::MissingKey(key.into()))?
+            .decode()
+    }
+
+    /// Returns true if, for every `(key, value)` in the pattern, the event has an entry with that
+    /// key and (unless the pattern's value is `None`) that exact encoded value.
+    pub fn matches(&self, pattern: &[(&str, Option<&[u8]>)]) -> bool {
+        pattern.iter().all(|&(key, value)| {
+            self.entries
+                .iter()
+                .any(|e| e.key == key && value.map_or(true, |v| e.value == v))
+        })
+    }
+}
+
+/// Implements `TryFrom<&ActorEvent>` for a struct, decoding each of the listed fields from the
+/// (first) entry keyed by the field's name. All fields must be listed, and implement
+/// [`EventValue`].
+///
+/// ```
+/// use fvm_shared::event::{ActorEvent, Entry, Flags};
+///
+/// #[derive(Debug, PartialEq)]
+/// struct Transfer {
+///     from: u64,
+///     to: u64,
+///     memo: String,
+/// }
+///
+/// fvm_shared::impl_try_from_event!(Transfer { from, to, memo });
+///
+/// let entry = |key: &str, value: &[u8]| Entry {
+///     flags: Flags::FLAG_INDEXED_ALL,
+///     key: key.into(),
+///     codec: fvm_shared::IPLD_RAW,
+///     value: value.into(),
+/// };
+/// let event = ActorEvent::from(vec![
+///     entry("from", &100u64.to_be_bytes()),
+///     entry("to", &101u64.to_be_bytes()),
+///     entry("memo", b"thanks"),
+/// ]);
+/// let transfer = Transfer::try_from(&event).unwrap();
+/// assert_eq!(transfer, Transfer { from: 100, to: 101, memo: "thanks".into() });
+/// ```
+#[macro_export]
+macro_rules! impl_try_from_event {
+    ($ty:ident { $($field:ident),* $(,)? }) => {
+        impl ::std::convert::TryFrom<&$crate::event::ActorEvent> for $ty {
+            type Error = $crate::event::EventDecodeError;
+
+            fn try_from(event: &$crate::event::ActorEvent) -> Result<Self, Self::Error> {
+                Ok($ty {
+                    $($field: event.decode_field(stringify!($field))?,)*
+                })
+            }
+        }
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(key: &str, codec: u64, value: impl Into<Vec<u8>>) -> Entry {
+        Entry {
+            flags: Flags::FLAG_INDEXED_ALL,
+            key: key.into(),
+            codec,
+            value: value.into(),
+        }
+    }
+
+    #[test]
+    fn decode_values() {
+        let cid = Cid::new_v1(
+            IPLD_RAW,
+            cid::multihash::Multihash::wrap(0, b"data").unwrap(),
+        );
+        let event = ActorEvent::from(vec![
+            entry("int", IPLD_RAW, 1234u64.to_be_bytes()),
+            entry("short", IPLD_RAW, vec![1, 2]),
+            entry("long", IPLD_RAW, vec![1; 9]),
+            entry("str", IPLD_RAW, "hello"),
+            entry("bad_str", IPLD_RAW, vec![0xff]),
+            entry("cid", IPLD_RAW, cid.to_bytes()),
+            entry(
+                "cbor",
+                DAG_CBOR,
+                fvm_ipld_encoding::to_vec(&(1u8, "two")).unwrap(),
+            ),
+        ]);
+
+        assert_eq!(event.decode_field::<u64>("int"), Ok(1234));
+        assert_eq!(event.decode_field::<u64>("short"), Ok(0x0102));
+        assert!(matches!(
+            event.decode_field::<u64>("long"),
+            Err(EventDecodeError::InvalidValue(_))
+        ));
+        assert_eq!(event.decode_field::<[u8; 2]>("short"), Ok([1, 2]));
+        assert!(matches!(
+            event.decode_field::<[u8; 3]>("short"),
+            Err(EventDecodeError::InvalidValue(_))
+        ));
+        assert_eq!(event.decode_field::<Vec<u8>>("str"), Ok(b"hello".to_vec()));
+        assert_eq!(event.decode_field::<String>("str"), Ok("hello".into()));
+        assert!(matches!(
+            event.decode_field::<String>("bad_str"),
+            Err(EventDecodeError::InvalidValue(_))
+        ));
+        assert_eq!(event.decode_field::<Cid>("cid"), Ok(cid));
+        assert_eq!(
+            event.decode_field::<Cbor<(u8, String)>>("cbor"),
+            Ok(Cbor((1, "two".into())))
+        );
+        assert_eq!(
+            event.decode_field::<String>("cbor"),
+            Err(EventDecodeError::UnsupportedCodec(DAG_CBOR))
+        );
+        assert_eq!(
+            event.decode_field::<Cbor<u8>>("int"),
+            Err(EventDecodeError::UnsupportedCodec(IPLD_RAW))
+        );
+        assert_eq!(
+            event.decode_field::<u64>("missing"),
+            Err(EventDecodeError::MissingKey("missing".into()))
+        );
+    }
+
+    #[test]
+    fn build_values() {
+        let cid = Cid::new_v1(
+            IPLD_RAW,
+            cid::multihash::Multihash::wrap(0, b"data").unwrap(),
+        );
+        let addr = Address::new_actor(b"actor");
+        let event = EventBuilder::new()
+            .field("int", 1234u64)
+            .field("bytes", [1u8, 2])
+            .key_indexed_field("str", "hello")
+            .field("cid", cid)
+            .field("addr", &addr)
+            .field("neg", BigInt::from(-300))
+            .field("zero", BigInt::default())
+            .unindexed_field("amount", TokenAmount::from_atto(1000))
+            .build();
+
+        assert!(event.entries.iter().all(|e| e.codec == IPLD_RAW));
+        assert_eq!(event.get("int").unwrap().value, 1234u64.to_be_bytes());
+        assert_eq!(event.get("str").unwrap().flags, Flags::FLAG_INDEXED_KEY);
+        assert_eq!(event.get("amount").unwrap().flags, Flags::empty());
+        assert_eq!(event.get("neg").unwrap().value, [1, 1, 0x2c]);
+        assert!(event.get("zero").unwrap().value.is_empty());
+
+        assert_eq!(event.decode_field::<u64>("int"), Ok(1234));
+        assert_eq!(event.decode_field::<[u8; 2]>("bytes"), Ok([1, 2]));
+        assert_eq!(event.decode_field::<String>("str"), Ok("hello".into()));
+        assert_eq!(event.decode_field::<Cid>("cid"), Ok(cid));
+        assert_eq!(event.decode_field::<Address>("addr"), Ok(addr));
+        assert_eq!(event.decode_field::<BigInt>("neg"), Ok(BigInt::from(-300)));
+        assert_eq!(event.decode_field::<BigInt>("zero"), Ok(BigInt::default()));
+        assert_eq!(
+            event.decode_field::<TokenAmount>("amount"),
+            Ok(TokenAmount::from_atto(1000))
+        );
+        assert!(matches!(
+            event.decode_field::<BigInt>("str"),
+            Err(EventDecodeError::InvalidValue(_))
+        ));
+    }
+
+    #[test]
+    fn match_patterns() {
+        let event = ActorEvent::from(vec![
+            entry("type", IPLD_RAW, "transfer"),
+            entry("to", IPLD_RAW, 101u64.to_be_bytes()),
+        ]);
+        assert!(event.matches(&[]));
+        assert!(event.matches(&[("type", Some(&b"transfer"[..]))]));
+        assert!(event.matches(&[("type", Some(&b"transfer"[..])), ("to", None)]));
+        assert!(!event.matches(&[("type", Some(&b"mint"[..]))]));
+        assert!(!event.matches(&[("type", None), ("from", None)]));
+    }
+}
@@ -21,6 +21,7 @@ pub mod error;
 pub mod event;
 pub mod math;
 pub mod message;
+pub mod method;
 pub mod piece;
 pub mod randomness;
 pub mod receipt;
@@ -7,9 +7,10 @@ use fvm_ipld_encoding::de::{Deserialize, Deserializer};
 use fvm_ipld_encoding::ser::{Serialize, Serializer};
 use fvm_ipld_encoding::RawBytes;
 
-use crate::address::Address;
+use crate::address::{Address, Protocol};
 use crate::econ::TokenAmount;
-use crate::MethodNum;
+use crate::version::NetworkVersion;
+use crate::{MethodNum, BLOCK_GAS_LIMIT, TOTAL_FILECOIN};
 
 /// Default Unsigned VM message type which includes all data needed for a state transition
 #[cfg_attr(feature = "testing", derive(Default))]
@@ -27,15 +28,111 @@ pub struct Message {
     pub gas_premium: TokenAmount,
 }
 
+/// The syntactic rules checked by [`Message::check`]. The [`Default`] policy is the one the FVM
+/// enforces when applying messages, while [`MessagePolicy::mempool`] adds the stricter rules a
+/// message must satisfy to be included in a block.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MessagePolicy {
+    /// The maximum gas limit of a message.
+    pub max_gas_limit: u64,
+    /// The maximum value a message can transfer, if any. When set, negative values are rejected
+    /// too.
+    pub max_value: Option<TokenAmount>,
+    /// The maximum length of a message's parameters, if any.
+    pub max_params_len: Option<usize>,
+    /// Whether to require non-negative fees, with a gas premium no greater than the fee cap.
+    pub check_fees: bool,
+    /// Whether to require a known message version, and addresses supported by the network
+    /// version.
+    pub check_version: bool,
+}
+
+impl Default for MessagePolicy {
+    fn default() -> Self {
+        Self {
+            max_gas_limit: i64::MAX as u64,
+            max_value: None,
+            max_params_len: None,
+            check_fees: false,
+            check_version: false,
+        }
+    }
+}
+
+impl MessagePolicy {
+    /// The maximum length of a message's parameters accepted into the mempool.
+    pub const MAX_MEMPOOL_PARAMS_LEN: usize = 32 << 10;
+
+    /// The policy messages must satisfy to be included in a block.
+    pub fn mempool() -> Self {
+        Self {
+            max_gas_limit: BLOCK_GAS_LIMIT,
+            max_value: Some(TOTAL_FILECOIN.clone()),
+            max_params_len: Some(Self::MAX_MEMPOOL_PARAMS_LEN),
+            check_fees: true,
+            check_version: true,
+        }
+    }
+}
+
 impl Message {
-    /// Does some basic checks on the Message to see if the fields are valid.
-    pub fn check(self: &Message) -> anyhow::Result<()> {
+    /// Checks that the message's fields are valid under the given policy and network version.
+    /// This only performs syntactic checks: it doesn't look at the state tree.
+    pub fn check(
+        &self,
+        network_version: NetworkVersion,
+        policy: &MessagePolicy,
+    ) -> anyhow::Result<()> {
         if self.gas_limit == 0 {
             return Err(anyhow!("Message has no gas limit set"));
         }
-        // TODO: max block limit?
-        if self.gas_limit > i64::MAX as u64 {
-            return Err(anyhow!("Message gas exceeds i64 max"));
+        if self.gas_limit > policy.max_gas_limit {
+            return Err(anyhow!(
+                "Message gas limit {} exceeds the maximum of {}",
+                self.gas_limit,
+                policy.max_gas_limit
+            ));
+        }
+        if let Some(max_value) = &policy.max_value {
+            if self.value.is_negative() {
+                return Err(anyhow!("Message value is negative"));
+            }
+            if &self.value > max_value {
+                return Err(anyhow!("Message value exceeds the total filecoin supply"));
+            }
+        }
+        if let Some(max_params_len) = policy.max_params_len {
+            if self.params.len() > max_params_len {
+                return Err(anyhow!(
+                    "Message params length {} exceeds the maximum of {}",
+                    self.params.len(),
+                    max_params_len
+                ));
+            }
+        }
+        if policy.check_fees {
+            if self.gas_fee_cap.is_negative() {
+                return Err(anyhow!("Message gas fee cap is negative"));
+            }
+            if self.gas_premium.is_negative() {
+                return Err(anyhow!("Message gas premium is negative"));
+            }
+            if self.gas_premium > self.gas_fee_cap {
+                return Err(anyhow!("Message gas premium exceeds the gas fee cap"));
+            }
+        }
+        if policy.check_version {
+            if self.version != 0 {
+                return Err(anyhow!("Message version {} is not supported", self.version));
+            }
+            if network_version < NetworkVersion::V18
+                && (self.from.protocol() == Protocol::Delegated
+                    || self.to.protocol() == Protocol::Delegated)
+            {
+                return Err(anyhow!(
+                    "Delegated addresses are not supported before network version 18"
+                ));
+            }
         }
         Ok(())
     }
@@ -111,3 +208,90 @@ impl quickcheck::Arbitrary for Message {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn message() -> Message {
+        Message {
+            version: 0,
+            from: Address::new_id(100),
+            to: Address::new_id(101),
+            sequence: 0,
+            value: TokenAmount::from_atto(1),
+            method_num: 0,
+            params: RawBytes::default(),
+            gas_limit: 1_000_000,
+            gas_fee_cap: TokenAmount::from_atto(200),
+            gas_premium: TokenAmount::from_atto(100),
+        }
+    }
+
+    #[test]
+    fn check_default_policy() {
+        let policy = MessagePolicy::default();
+        assert!(message().check(NetworkVersion::V18, &policy).is_ok());
+
+        let msg = Message {
+            gas_limit: 0,
+            ..message()
+        };
+        assert!(msg.check(NetworkVersion::V18, &policy).is_err());
+
+        // Only the mempool policy bounds the gas limit by the block gas limit.
+        let msg = Message {
+            gas_limit: BLOCK_GAS_LIMIT + 1,
+            gas_premium: TokenAmount::from_atto(300),
+            version: 1,
+            ..message()
+        };
+        assert!(msg.check(NetworkVersion::V18, &policy).is_ok());
+        assert!(msg
+            .check(NetworkVersion::V18, &MessagePolicy::mempool())
+            .is_err());
+    }
+
+    #[test]
+    fn check_mempool_policy() {
+        let policy = MessagePolicy::mempool();
+        assert!(message().check(NetworkVersion::V18, &policy).is_ok());
+
+        let invalid = [
+            Message {
+                value: TokenAmount::from_atto(-1),
+                ..message()
+            },
+            Message {
+                value: TOTAL_FILECOIN.clone() + TokenAmount::from_atto(1),
+                ..message()
+            },
+            Message {
+                gas_premium: TokenAmount::from_atto(300),
+                ..message()
+            },
+            Message {
+                gas_fee_cap: TokenAmount::from_atto(-1),
+                ..message()
+            },
+            Message {
+                params: RawBytes::new(vec![0; MessagePolicy::MAX_MEMPOOL_PARAMS_LEN + 1]),
+                ..message()
+            },
+            Message {
+                version: 1,
+                ..message()
+            },
+        ];
+        for msg in invalid {
+            assert!(msg.check(NetworkVersion::V18, &policy).is_err());
+        }
+
+        let delegated = Message {
+            from: Address::new_delegated(10, &[0; 20]).unwrap(),
+            ..message()
+        };
+        assert!(delegated.check(NetworkVersion::V18, &policy).is_ok());
+        assert!(delegated.check(NetworkVersion::V17, &policy).is_err());
+    }
+}
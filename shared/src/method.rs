@@ -0,0 +1,246 @@
+// Copyright 2021-2023 Protocol Labs
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+//! [FRC-42](https://github.com/filecoin-project/FIPs/blob/master/FRCs/frc-0042.md) method number
+//! hashing, deriving the method numbers of exported methods from their names.
+
+use thiserror::Error;
+
+use crate::MethodNum;
+
+/// The first method number that may be assigned to an FRC-42 method. Method numbers below this
+/// are reserved for built-in (e.g., constructor) and legacy methods.
+pub const FIRST_METHOD_NUMBER: MethodNum = 1 << 24;
+
+/// An invalid FRC-42 method name, returned by [`method_hash`].
+#[derive(Copy, Clone, Debug, Error, Eq, PartialEq)]
+pub enum MethodNameError {
+    #[error("method name is empty")]
+    EmptyName,
+    #[error("method name must start with an uppercase letter or an underscore, followed by letters, digits, or underscores")]
+    IllegalName,
+    #[error("method name doesn't hash to a valid method number")]
+    IndeterminableId,
+}
+
+/// Computes the FRC-42 method number of the named method: the first 4-byte (big-endian) chunk of
+/// the Blake2b-512 hash of `"1|" + name` that is at least [`FIRST_METHOD_NUMBER`].
+///
+/// This is a `const fn`: use the [`method_hash!`](crate::method_hash) macro to compute method
+/// numbers at compile time.
+pub const fn method_hash(name: &str) -> Result<MethodNum, MethodNameError> {
+    if let Err(e) = check_method_name(name) {
+        return Err(e);
+    }
+    let digest = blake2b512(b"1|", name.as_bytes());
+    let mut i = 0;
+    while i < digest.len() {
+        let method = u32::from_be_bytes([digest[i], digest[i + 1], digest[i + 2], digest[i + 3]])
+            as MethodNum;
+        if method >= FIRST_METHOD_NUMBER {
+            return Ok(method);
+        }
+        i += 4;
+    }
+    Err(MethodNameError::IndeterminableId)
+}
+
+/// Checks that the method name is a valid FRC-42 method name: it must start with an uppercase
+/// ASCII letter or an underscore, and contain only ASCII letters, digits, and underscores.
+pub const fn check_method_name(name: &str) -> Result<(), MethodNameError> {
+    let name = name.as_bytes();
+    if name.is_empty() {
+        return Err(MethodNameError::EmptyName);
+    }
+    if !(name[0].is_ascii_uppercase() || name[0] == b'_') {
+        return Err(MethodNameError::IllegalName);
+    }
+    let mut i = 1;
+    while i < name.len() {
+        if !(name[i].is_ascii_alphanumeric() || name[i] == b'_') {
+            return Err(MethodNameError::IllegalName);
+        }
+        i += 1;
+    }
+    Ok(())
+}
+
+/// Computes the FRC-42 method number of the named method at compile time (see
+/// [`method::method_hash`](crate::method::method_hash)), failing to compile if the name is
+/// invalid.
+///
+/// ```
+/// const INVOKE_EVM: fvm_shared::MethodNum = fvm_shared::method_hash!("InvokeEVM");
+/// assert_eq!(INVOKE_EVM, 3844450837);
+/// ```
+#[macro_export]
+macro_rules! method_hash {
+    ($name:expr) => {{
+        const METHOD: $crate::MethodNum = match $crate::method::method_hash($name) {
+            Ok(method) => method,
+            Err(_) => panic!("invalid FRC-42 method name"),
+        };
+        METHOD
+    }};
+}
+
+const BLAKE2B_IV: [u64; 8] = [
+    0x6a09e667f3bcc908,
+    0xbb67ae8584caa73b,
+    0x3c6ef372fe94f82b,
+    0xa54ff53a5f1d36f1,
+    0x510e527fade682d1,
+    0x9b05688c2b3e6c1f,
+    0x1f83d9abfb41bd6b,
+    0x5be0cd19137e2179,
+];
+
+const BLAKE2B_SIGMA: [[usize; 16]; 12] = [
+    [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15],
+    [14, 10, 4, 8, 9, 15, 13, 6, 1, 12, 0, 2, 11, 7, 5, 3],
+    [11, 8, 12, 0, 5, 2, 15, 13, 10, 14, 3, 6, 7, 1, 9, 4],
+    [7, 9, 3, 1, 13, 12, 11, 14, 2, 6, 5, 10, 4, 0, 15, 8],
+    [9, 0, 5, 7, 2, 4, 10, 15, 14, 1, 11, 12, 6, 8, 3, 13],
+    [2, 12, 6, 10, 0, 11, 8, 3, 4, 13, 7, 5, 15, 14, 1, 9],
+    [12, 5, 1, 15, 14, 13, 4, 10, 0, 7, 6, 3, 9, 2, 8, 11],
+    [13, 11, 7, 14, 12, 1, 3, 9, 5, 0, 15, 4, 8, 6, 2, 10],
+    [6, 15, 14, 9, 11, 3, 0, 8, 12, 2, 13, 7, 1, 4, 10, 5],
+    [10, 2, 8, 4, 7, 6, 1, 5, 15, 11, 9, 14, 3, 12, 13, 0],
+    [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15],
+    [14, 10, 4, 8, 9, 15, 13, 6, 1, 12, 0, 2, 11, 7, 5, 3],
+];
+
+/// Computes the (unkeyed) Blake2b-512 hash of `prefix || data`, in a const context.
+const fn blake2b512(prefix: &[u8], data: &[u8]) -> [u8; 64] {
+    let len = prefix.len() + data.len();
+    let blocks = if len == 0 { 1 } else { (len + 127) / 128 };
+
+    let mut h = BLAKE2B_IV;
+    h[0] ^= 0x01010000 ^ 64;
+    let mut block = 0;
+    while block < blocks {
+        let mut m = [0u64; 16];
+        let mut i = 0;
+        while i < 128 {
+            let offset = block * 128 + i;
+            let byte = if offset < prefix.len() {
+                prefix[offset]
+            } else if offset < len {
+                data[offset - prefix.len()]
+            } else {
+                0
+            };
+            m[i / 8] |= (byte as u64) << (8 * (i % 8));
+            i += 1;
+        }
+        let last = block + 1 == blocks;
+        let counter = if last { len } else { (block + 1) * 128 };
+        h = blake2b_compress(h, &m, counter as u128, last);
+        block += 1;
+    }
+
+    let mut out = [0u8; 64];
+    let mut i = 0;
+    while i < 64 {
+        out[i] = (h[i / 8] >> (8 * (i % 8))) as u8;
+        i += 1;
+    }
+    out
+}
+
+const fn blake2b_compress(h: [u64; 8], m: &[u64; 16], counter: u128, last: bool) -> [u64; 8] {
+    let mut v = [0u64; 16];
+    let mut i = 0;
+    while i < 8 {
+        v[i] = h[i];
+        v[i + 8] = BLAKE2B_IV[i];
+        i += 1;
+    }
+    v[12] ^= counter as u64;
+    v[13] ^= (counter >> 64) as u64;
+    if last {
+        v[14] = !v[14];
+    }
+
+    let mut round = 0;
+    while round < 12 {
+        let s = &BLAKE2B_SIGMA[round];
+        v = blake2b_mix(v, 0, 4, 8, 12, m[s[0]], m[s[1]]);
+        v = blake2b_mix(v, 1, 5, 9, 13, m[s[2]], m[s[3]]);
+        v = blake2b_mix(v, 2, 6, 10, 14, m[s[4]], m[s[5]]);
+        v = blake2b_mix(v, 3, 7, 11, 15, m[s[6]], m[s[7]]);
+        v = blake2b_mix(v, 0, 5, 10, 15, m[s[8]], m[s[9]]);
+        v = blake2b_mix(v, 1, 6, 11, 12, m[s[10]], m[s[11]]);
+        v = blake2b_mix(v, 2, 7, 8, 13, m[s[12]], m[s[13]]);
+        v = blake2b_mix(v, 3, 4, 9, 14, m[s[14]], m[s[15]]);
+        round += 1;
+    }
+
+    let mut out = h;
+    let mut i = 0;
+    while i < 8 {
+        out[i] ^= v[i] ^ v[i + 8];
+        i += 1;
+    }
+    out
+}
+
+const fn blake2b_mix(
+    mut v: [u64; 16],
+    a: usize,
+    b: usize,
+    c: usize,
+    d: usize,
+    x: u64,
+    y: u64,
+) -> [u64; 16] {
+    v[a] = v[a].wrapping_add(v[b]).wrapping_add(x);
+    v[d] = (v[d] ^ v[a]).rotate_right(32);
+    v[c] = v[c].wrapping_add(v[d]);
+    v[b] = (v[b] ^ v[c]).rotate_right(24);
+    v[a] = v[a].wrapping_add(v[b]).wrapping_add(y);
+    v[d] = (v[d] ^ v[a]).rotate_right(16);
+    v[c] = v[c].wrapping_add(v[d]);
+    v[b] = (v[b] ^ v[c]).rotate_right(63);
+    v
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn blake2b_matches() {
+        let data: Vec<u8> = (0..300u32).map(|i| i as u8).collect();
+        for len in [0, 1, 2, 126, 127, 128, 129, 255, 256, 257, 300] {
+            for prefix in [&b""[..], b"1|"] {
+                let expected = blake2b_simd::blake2b(&[prefix, &data[..len]].concat());
+                assert_eq!(
+                    &blake2b512(prefix, &data[..len])[..],
+                    expected.as_bytes(),
+                    "length {}",
+                    len
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn method_numbers() {
+        assert_eq!(method_hash("Constructor"), Ok(3316146672));
+        assert_eq!(method_hash("AuthenticateMessage"), Ok(2643134072));
+        assert_eq!(method_hash("Receive"), Ok(3726118371));
+        assert_eq!(method_hash!("InvokeEVM"), 3844450837);
+        assert_eq!(method_hash("_Private_1"), Ok(163557220));
+    }
+
+    #[test]
+    fn invalid_names() {
+        assert_eq!(method_hash(""), Err(MethodNameError::EmptyName));
+        assert_eq!(method_hash("lower"), Err(MethodNameError::IllegalName));
+        assert_eq!(method_hash("1Digit"), Err(MethodNameError::IllegalName));
+        assert_eq!(method_hash("Has Space"), Err(MethodNameError::IllegalName));
+        assert_eq!(method_hash("Ünicode"), Err(MethodNameError::IllegalName));
+        assert_eq!(check_method_name("_Ok9"), Ok(()));
+    }
+}
@@ -8,9 +8,30 @@ pub mod zero;
 use cid::Cid;
 use serde::{Deserialize, Serialize};
 use serde_tuple::*;
+use thiserror::Error;
 #[cfg(feature = "proofs")]
 pub use zero::zero_piece_commitment;
 
+/// The minimum padded piece size.
+pub const MIN_PADDED_PIECE_SIZE: u64 = 128;
+
+/// Errors from the checked piece size arithmetic.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Error)]
+pub enum PieceSizeError {
+    /// The piece size is below the minimum (127 bytes unpadded, 128 bytes padded).
+    #[error("piece size {0} is below the minimum")]
+    TooSmall(u64),
+    /// The piece size isn't a power of 2 (padded), or a power of 2 multiple of 127 (unpadded).
+    #[error("invalid piece size {0}")]
+    InvalidSize(u64),
+    /// The offset isn't a multiple of the minimum padded piece size.
+    #[error("offset {0} is not a multiple of 128 bytes")]
+    Misaligned(u64),
+    /// The result doesn't fit in a u64.
+    #[error("piece size overflow")]
+    Overflow,
+}
+
 /// Size of a piece in bytes.
 #[derive(PartialEq, Debug, Eq, Clone, Copy)]
 pub struct UnpaddedPieceSize(pub u64);
@@ -34,6 +55,33 @@ impl UnpaddedPieceSize {
 
         Ok(())
     }
+
+    /// Validates the piece size and converts it into a padded piece size.
+    pub fn checked_padded(self) -> Result<PaddedPieceSize, PieceSizeError> {
+        if self.0 < 127 {
+            return Err(PieceSizeError::TooSmall(self.0));
+        }
+        if self.0 >> self.0.trailing_zeros() != 127 {
+            return Err(PieceSizeError::InvalidSize(self.0));
+        }
+        // The only valid size whose padded size overflows is 127 * 2^57.
+        let padded = self
+            .0
+            .checked_add(self.0 / 127)
+            .ok_or(PieceSizeError::Overflow)?;
+        Ok(PaddedPieceSize(padded))
+    }
+
+    /// Returns the smallest valid unpadded piece size that can hold `len` bytes.
+    pub fn fitting(len: u64) -> Result<Self, PieceSizeError> {
+        // The smallest power of 2 such that 127 * 2^n >= len.
+        let units = (len.max(1) - 1) / 127 + 1;
+        units
+            .checked_next_power_of_two()
+            .and_then(|units| units.checked_mul(127))
+            .map(Self)
+            .ok_or(PieceSizeError::Overflow)
+    }
 }
 
 /// Size of a piece in bytes with padding.
@@ -59,6 +107,74 @@ impl PaddedPieceSize {
 
         Ok(())
     }
+
+    /// Validates the piece size, returning a [`PieceSizeError`] if it's invalid.
+    pub fn checked_validate(self) -> Result<(), PieceSizeError> {
+        if self.0 < MIN_PADDED_PIECE_SIZE {
+            return Err(PieceSizeError::TooSmall(self.0));
+        }
+        if self.0.count_ones() != 1 {
+            return Err(PieceSizeError::InvalidSize(self.0));
+        }
+        Ok(())
+    }
+
+    /// Validates the piece size and converts it into an unpadded piece size.
+    pub fn checked_unpadded(self) -> Result<UnpaddedPieceSize, PieceSizeError> {
+        self.checked_validate()?;
+        Ok(self.unpadded())
+    }
+
+    /// Returns the smallest valid padded piece size that can hold `len` bytes (of padded data).
+    pub fn fitting(len: u64) -> Result<Self, PieceSizeError> {
+        len.max(MIN_PADDED_PIECE_SIZE)
+            .checked_next_power_of_two()
+            .map(Self)
+            .ok_or(PieceSizeError::Overflow)
+    }
+
+    /// Returns the first offset at or after `offset` at which a piece of this (valid) size can be
+    /// placed: pieces must be aligned to their size.
+    pub fn align(self, offset: u64) -> Result<u64, PieceSizeError> {
+        self.checked_validate()?;
+        let mask = self.0 - 1;
+        offset
+            .checked_add(mask)
+            .map(|o| o & !mask)
+            .ok_or(PieceSizeError::Overflow)
+    }
+
+    /// Returns the padding pieces to place at `offset` (a multiple of 128 bytes) so that a piece of
+    /// this (valid) size can follow them, from the first to the last. Each padding piece is aligned
+    /// to its own size.
+    pub fn padding_before(self, offset: u64) -> Result<Vec<PaddedPieceSize>, PieceSizeError> {
+        if offset % MIN_PADDED_PIECE_SIZE != 0 {
+            return Err(PieceSizeError::Misaligned(offset));
+        }
+        let mut offset = offset;
+        let end = self.align(offset)?;
+        let mut padding = Vec::new();
+        while offset < end {
+            // The largest piece aligned at the current offset that doesn't go past the end.
+            let size = (1 << offset.trailing_zeros().min(63)).min(1 << (end - offset).ilog2());
+            padding.push(PaddedPieceSize(size));
+            offset += size;
+        }
+        Ok(padding)
+    }
+
+    /// Returns the smallest valid piece size that can hold the given (valid) pieces, placed in
+    /// order, each aligned to its size.
+    pub fn join(pieces: &[PaddedPieceSize]) -> Result<PaddedPieceSize, PieceSizeError> {
+        let mut offset = 0u64;
+        for piece in pieces {
+            offset = piece
+                .align(offset)?
+                .checked_add(piece.0)
+                .ok_or(PieceSizeError::Overflow)?;
+        }
+        Self::fitting(offset)
+    }
 }
 
 /// Piece information for part or a whole file.
@@ -119,4 +235,77 @@ mod tests {
             Err("unpadded piece size must be a power of 2 multiple of 127")
         );
     }
+
+    #[test]
+    fn checked_conversions() {
+        assert_eq!(
+            UnpaddedPieceSize(127 << 3).checked_padded(),
+            Ok(PaddedPieceSize(1024))
+        );
+        assert_eq!(
+            UnpaddedPieceSize(126).checked_padded(),
+            Err(PieceSizeError::TooSmall(126))
+        );
+        assert_eq!(
+            UnpaddedPieceSize(254 + 127).checked_padded(),
+            Err(PieceSizeError::InvalidSize(381))
+        );
+        assert_eq!(
+            UnpaddedPieceSize(127 << 57).checked_padded(),
+            Err(PieceSizeError::Overflow)
+        );
+        assert_eq!(
+            PaddedPieceSize(1024).checked_unpadded(),
+            Ok(UnpaddedPieceSize(127 << 3))
+        );
+        assert_eq!(
+            PaddedPieceSize(1000).checked_unpadded(),
+            Err(PieceSizeError::InvalidSize(1000))
+        );
+
+        assert_eq!(UnpaddedPieceSize::fitting(0), Ok(UnpaddedPieceSize(127)));
+        assert_eq!(UnpaddedPieceSize::fitting(127), Ok(UnpaddedPieceSize(127)));
+        assert_eq!(UnpaddedPieceSize::fitting(128), Ok(UnpaddedPieceSize(254)));
+        assert_eq!(PaddedPieceSize::fitting(1), Ok(PaddedPieceSize(128)));
+        assert_eq!(PaddedPieceSize::fitting(1025), Ok(PaddedPieceSize(2048)));
+        assert_eq!(
+            PaddedPieceSize::fitting(u64::MAX),
+            Err(PieceSizeError::Overflow)
+        );
+    }
+
+    #[test]
+    fn alignment_and_joining() {
+        let piece = PaddedPieceSize(1024);
+        assert_eq!(piece.align(0), Ok(0));
+        assert_eq!(piece.align(128), Ok(1024));
+        assert_eq!(piece.align(1024), Ok(1024));
+
+        assert_eq!(piece.padding_before(1024), Ok(vec![]));
+        assert_eq!(
+            piece.padding_before(128),
+            Ok(vec![
+                PaddedPieceSize(128),
+                PaddedPieceSize(256),
+                PaddedPieceSize(512)
+            ])
+        );
+        assert_eq!(
+            piece.padding_before(100),
+            Err(PieceSizeError::Misaligned(100))
+        );
+
+        // 128 at 0, 1024 at 1024, 256 at 2048.
+        let pieces = [
+            PaddedPieceSize(128),
+            PaddedPieceSize(1024),
+            PaddedPieceSize(256),
+        ];
+        assert_eq!(PaddedPieceSize::join(&pieces), Ok(PaddedPieceSize(4096)));
+        assert_eq!(PaddedPieceSize::join(&[]), Ok(PaddedPieceSize(128)));
+        assert_eq!(
+            PaddedPieceSize::join(&[PaddedPieceSize(100)]),
+            Err(PieceSizeError::TooSmall(100))
+        );
+    }
 }
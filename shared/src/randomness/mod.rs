@@ -4,6 +4,9 @@
 
 use fvm_ipld_encoding::{BytesDe, BytesSer};
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use thiserror::Error;
+
+use crate::clock::ChainEpoch;
 
 // TODO: turn this back into a 32byte array once we no longer need go compat. It's a vec so that the
 // errors match.
@@ -31,3 +34,123 @@ impl<'de> Deserialize<'de> for Randomness {
         Ok(Self(bytes.0))
     }
 }
+
+/// Domain separation tags, personalizing randomness drawn for different purposes (see
+/// [`draw_randomness`]). The numeric values are part of consensus and never change.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[repr(i64)]
+pub enum DomainSeparationTag {
+    TicketProduction = 1,
+    ElectionProofProduction = 2,
+    WinningPoStChallengeSeed = 3,
+    WindowedPoStChallengeSeed = 4,
+    SealRandomness = 5,
+    InteractiveSealChallengeSeed = 6,
+    WindowedPoStDeadlineAssignment = 7,
+    MarketDealCronSeed = 8,
+    PoStChainCommit = 9,
+    EvmPrevRandao = 10,
+}
+
+impl DomainSeparationTag {
+    /// All domain separation tags, in numeric order.
+    pub const ALL: [DomainSeparationTag; 10] = [
+        Self::TicketProduction,
+        Self::ElectionProofProduction,
+        Self::WinningPoStChallengeSeed,
+        Self::WindowedPoStChallengeSeed,
+        Self::SealRandomness,
+        Self::InteractiveSealChallengeSeed,
+        Self::WindowedPoStDeadlineAssignment,
+        Self::MarketDealCronSeed,
+        Self::PoStChainCommit,
+        Self::EvmPrevRandao,
+    ];
+
+    /// Returns the tag's numeric value.
+    pub const fn value(self) -> i64 {
+        self as i64
+    }
+}
+
+impl From<DomainSeparationTag> for i64 {
+    fn from(tag: DomainSeparationTag) -> Self {
+        tag.value()
+    }
+}
+
+/// An unknown domain separation tag.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Error)]
+#[error("unknown domain separation tag {0}")]
+pub struct UnknownTagError(pub i64);
+
+impl TryFrom<i64> for DomainSeparationTag {
+    type Error = UnknownTagError;
+
+    fn try_from(value: i64) -> Result<Self, Self::Error> {
+        Self::ALL
+            .into_iter()
+            .find(|tag| tag.value() == value)
+            .ok_or(UnknownTagError(value))
+    }
+}
+
+/// Draws randomness from a base (a ticket or beacon digest), personalized by a domain separation
+/// tag, the epoch the base was drawn from, and some entropy: this is the blake2b-256 hash of the
+/// big-endian tag, the base, the big-endian epoch, and the entropy, as computed by the FVM's
+/// randomness syscalls.
+///
+/// The tag is usually a [`DomainSeparationTag`], but any value is accepted.
+pub fn draw_randomness(
+    rbase: &[u8; RANDOMNESS_LENGTH],
+    pers: impl Into<i64>,
+    round: ChainEpoch,
+    entropy: &[u8],
+) -> [u8; RANDOMNESS_LENGTH] {
+    blake2b_simd::Params::new()
+        .hash_length(RANDOMNESS_LENGTH)
+        .to_state()
+        .update(&pers.into().to_be_bytes())
+        .update(rbase)
+        .update(&round.to_be_bytes())
+        .update(entropy)
+        .finalize()
+        .as_bytes()
+        .try_into()
+        .expect("fixed array size")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tag_values() {
+        for (i, tag) in DomainSeparationTag::ALL.into_iter().enumerate() {
+            assert_eq!(tag.value(), i as i64 + 1);
+            assert_eq!(DomainSeparationTag::try_from(tag.value()), Ok(tag));
+        }
+        assert_eq!(DomainSeparationTag::try_from(0), Err(UnknownTagError(0)));
+        assert_eq!(DomainSeparationTag::try_from(11), Err(UnknownTagError(11)));
+    }
+
+    #[test]
+    fn draw_randomness_input() {
+        let rbase = [7u8; RANDOMNESS_LENGTH];
+        let mut input = Vec::new();
+        input.extend_from_slice(&5i64.to_be_bytes());
+        input.extend_from_slice(&rbase);
+        input.extend_from_slice(&1234i64.to_be_bytes());
+        input.extend_from_slice(b"entropy");
+        let expected = blake2b_simd::Params::new().hash_length(32).hash(&input);
+
+        let drawn = draw_randomness(
+            &rbase,
+            DomainSeparationTag::SealRandomness,
+            1234,
+            b"entropy",
+        );
+        assert_eq!(&drawn[..], expected.as_bytes());
+        assert_eq!(drawn, draw_randomness(&rbase, 5, 1234, b"entropy"));
+    }
+}
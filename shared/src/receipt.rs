@@ -19,3 +19,52 @@ pub struct Receipt {
     /// CBOR NULL value on the wire).
     pub events_root: Option<Cid>, // Amt<Event>
 }
+
+/// A [`Receipt`] extended with a coarse [`GasBreakdown`] of the gas charged for the message, for
+/// clients. Unlike the receipt itself, this is never committed to on-chain.
+#[derive(Serialize_tuple, Deserialize_tuple, Debug, PartialEq, Eq, Clone)]
+pub struct ExtendedReceipt {
+    pub receipt: Receipt,
+    pub gas_breakdown: GasBreakdown,
+}
+
+/// A coarse breakdown of the gas charged for a message.
+#[derive(Serialize_tuple, Deserialize_tuple, Debug, Default, PartialEq, Eq, Clone)]
+pub struct GasBreakdown {
+    /// The message's gas limit.
+    pub gas_limit: u64,
+    /// The unused gas refunded to the sender.
+    pub gas_refund: u64,
+    /// The unused gas burned as a penalty for over-estimating the gas limit.
+    pub gas_burned: u64,
+}
+
+#[cfg(test)]
+mod tests {
+    use fvm_ipld_encoding::{from_slice, to_vec};
+
+    use super::*;
+
+    #[test]
+    fn extended_receipt_roundtrip() {
+        let receipt = Receipt {
+            exit_code: ExitCode::OK,
+            return_data: RawBytes::new(vec![1, 2, 3]),
+            gas_used: 100,
+            events_root: None,
+        };
+        let extended = ExtendedReceipt {
+            receipt: receipt.clone(),
+            gas_breakdown: GasBreakdown {
+                gas_limit: 1000,
+                gas_refund: 800,
+                gas_burned: 100,
+            },
+        };
+        let bytes = to_vec(&extended).unwrap();
+        assert_eq!(from_slice::<ExtendedReceipt>(&bytes).unwrap(), extended);
+        // The extended receipt isn't a valid receipt.
+        assert!(from_slice::<Receipt>(&bytes).is_err());
+        assert_ne!(to_vec(&receipt).unwrap(), bytes);
+    }
+}
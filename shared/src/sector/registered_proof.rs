@@ -11,8 +11,15 @@ use super::SectorSize;
 use crate::clock;
 use crate::version::NetworkVersion;
 
+/// The number of partitions in a non-interactive PoRep proof (FIP-0092), whatever the sector size.
+const NI_POREP_PARTITIONS: usize = 126;
+
+/// The size of a single PoRep partition proof.
+const POREP_PARTITION_PROOF_SIZE: usize = 192;
+
 /// Seal proof type which defines the version and sector size.
 #[derive(PartialEq, Eq, Copy, Clone, Debug, Hash)]
+#[allow(non_camel_case_types)]
 pub enum RegisteredSealProof {
     StackedDRG2KiBV1,
     StackedDRG512MiBV1,
@@ -25,6 +32,20 @@ pub enum RegisteredSealProof {
     StackedDRG8MiBV1P1,
     StackedDRG32GiBV1P1,
     StackedDRG64GiBV1P1,
+
+    /// Synthetic PoRep (FIP-0059).
+    StackedDRG2KiBV1P1_Feat_SyntheticPoRep,
+    StackedDRG512MiBV1P1_Feat_SyntheticPoRep,
+    StackedDRG8MiBV1P1_Feat_SyntheticPoRep,
+    StackedDRG32GiBV1P1_Feat_SyntheticPoRep,
+    StackedDRG64GiBV1P1_Feat_SyntheticPoRep,
+
+    /// Non-interactive PoRep (FIP-0092).
+    StackedDRG2KiBV1P2_Feat_NiPoRep,
+    StackedDRG512MiBV1P2_Feat_NiPoRep,
+    StackedDRG8MiBV1P2_Feat_NiPoRep,
+    StackedDRG32GiBV1P2_Feat_NiPoRep,
+    StackedDRG64GiBV1P2_Feat_NiPoRep,
     // TODO: get rid of this option once we no longer need go compat.
     // We use it to ensure that we can deserialize bad values here because go checks this value
     // later.
@@ -77,15 +98,59 @@ impl RegisteredSealProof {
     pub fn proof_size(self) -> Result<usize, String> {
         use RegisteredSealProof::*;
         match self {
-            StackedDRG2KiBV1 | StackedDRG512MiBV1 | StackedDRG8MiBV1 | StackedDRG2KiBV1P1
-            | StackedDRG512MiBV1P1 | StackedDRG8MiBV1P1 => Ok(192),
-
-            StackedDRG32GiBV1 | StackedDRG64GiBV1 | StackedDRG32GiBV1P1 | StackedDRG64GiBV1P1 => {
-                Ok(1920)
+            StackedDRG2KiBV1
+            | StackedDRG512MiBV1
+            | StackedDRG8MiBV1
+            | StackedDRG2KiBV1P1
+            | StackedDRG512MiBV1P1
+            | StackedDRG8MiBV1P1
+            | StackedDRG2KiBV1P1_Feat_SyntheticPoRep
+            | StackedDRG512MiBV1P1_Feat_SyntheticPoRep
+            | StackedDRG8MiBV1P1_Feat_SyntheticPoRep => Ok(POREP_PARTITION_PROOF_SIZE),
+
+            StackedDRG32GiBV1
+            | StackedDRG64GiBV1
+            | StackedDRG32GiBV1P1
+            | StackedDRG64GiBV1P1
+            | StackedDRG32GiBV1P1_Feat_SyntheticPoRep
+            | StackedDRG64GiBV1P1_Feat_SyntheticPoRep => Ok(10 * POREP_PARTITION_PROOF_SIZE),
+
+            StackedDRG2KiBV1P2_Feat_NiPoRep
+            | StackedDRG512MiBV1P2_Feat_NiPoRep
+            | StackedDRG8MiBV1P2_Feat_NiPoRep
+            | StackedDRG32GiBV1P2_Feat_NiPoRep
+            | StackedDRG64GiBV1P2_Feat_NiPoRep => {
+                Ok(NI_POREP_PARTITIONS * POREP_PARTITION_PROOF_SIZE)
             }
             Invalid(i) => Err(format!("unsupported proof type: {}", i)),
         }
     }
+
+    /// Returns true if this is a synthetic PoRep proof type (FIP-0059).
+    pub fn is_synthetic(self) -> bool {
+        use RegisteredSealProof::*;
+        matches!(
+            self,
+            StackedDRG2KiBV1P1_Feat_SyntheticPoRep
+                | StackedDRG512MiBV1P1_Feat_SyntheticPoRep
+                | StackedDRG8MiBV1P1_Feat_SyntheticPoRep
+                | StackedDRG32GiBV1P1_Feat_SyntheticPoRep
+                | StackedDRG64GiBV1P1_Feat_SyntheticPoRep
+        )
+    }
+
+    /// Returns true if this is a non-interactive PoRep proof type (FIP-0092).
+    pub fn is_non_interactive(self) -> bool {
+        use RegisteredSealProof::*;
+        matches!(
+            self,
+            StackedDRG2KiBV1P2_Feat_NiPoRep
+                | StackedDRG512MiBV1P2_Feat_NiPoRep
+                | StackedDRG8MiBV1P2_Feat_NiPoRep
+                | StackedDRG32GiBV1P2_Feat_NiPoRep
+                | StackedDRG64GiBV1P2_Feat_NiPoRep
+        )
+    }
 }
 
 impl Default for RegisteredSealProof {
@@ -182,6 +247,26 @@ impl RegisteredPoStProof {
             Invalid(i) => Err(format!("unsupported proof type: {}", i)),
         }
     }
+
+    /// Returns true if this window PoSt proof type can prove sectors sealed with the seal proof
+    /// type: either the seal proof's own window PoSt type (V1P1), or the V1 type it replaced, for
+    /// the same sector size. This includes synthetic PoRep and NI-PoRep sectors, which are proven
+    /// like any other sector.
+    pub fn is_compatible_with(self, seal_proof: RegisteredSealProof) -> bool {
+        use RegisteredPoStProof::*;
+        let Ok(v1p1) = seal_proof.registered_window_post_proof() else {
+            return false;
+        };
+        let v1 = match v1p1 {
+            StackedDRGWindow2KiBV1P1 => StackedDRGWindow2KiBV1,
+            StackedDRGWindow8MiBV1P1 => StackedDRGWindow8MiBV1,
+            StackedDRGWindow512MiBV1P1 => StackedDRGWindow512MiBV1,
+            StackedDRGWindow32GiBV1P1 => StackedDRGWindow32GiBV1,
+            StackedDRGWindow64GiBV1P1 => StackedDRGWindow64GiBV1,
+            _ => return false,
+        };
+        self == v1 || self == v1p1
+    }
 }
 
 impl RegisteredSealProof {
@@ -189,11 +274,26 @@ impl RegisteredSealProof {
     pub fn sector_size(self) -> Result<SectorSize, String> {
         use RegisteredSealProof::*;
         match self {
-            StackedDRG2KiBV1 | StackedDRG2KiBV1P1 => Ok(SectorSize::_2KiB),
-            StackedDRG8MiBV1 | StackedDRG8MiBV1P1 => Ok(SectorSize::_8MiB),
-            StackedDRG512MiBV1 | StackedDRG512MiBV1P1 => Ok(SectorSize::_512MiB),
-            StackedDRG32GiBV1 | StackedDRG32GiBV1P1 => Ok(SectorSize::_32GiB),
-            StackedDRG64GiBV1 | StackedDRG64GiBV1P1 => Ok(SectorSize::_64GiB),
+            StackedDRG2KiBV1
+            | StackedDRG2KiBV1P1
+            | StackedDRG2KiBV1P1_Feat_SyntheticPoRep
+            | StackedDRG2KiBV1P2_Feat_NiPoRep => Ok(SectorSize::_2KiB),
+            StackedDRG8MiBV1
+            | StackedDRG8MiBV1P1
+            | StackedDRG8MiBV1P1_Feat_SyntheticPoRep
+            | StackedDRG8MiBV1P2_Feat_NiPoRep => Ok(SectorSize::_8MiB),
+            StackedDRG512MiBV1
+            | StackedDRG512MiBV1P1
+            | StackedDRG512MiBV1P1_Feat_SyntheticPoRep
+            | StackedDRG512MiBV1P2_Feat_NiPoRep => Ok(SectorSize::_512MiB),
+            StackedDRG32GiBV1
+            | StackedDRG32GiBV1P1
+            | StackedDRG32GiBV1P1_Feat_SyntheticPoRep
+            | StackedDRG32GiBV1P2_Feat_NiPoRep => Ok(SectorSize::_32GiB),
+            StackedDRG64GiBV1
+            | StackedDRG64GiBV1P1
+            | StackedDRG64GiBV1P1_Feat_SyntheticPoRep
+            | StackedDRG64GiBV1P2_Feat_NiPoRep => Ok(SectorSize::_64GiB),
             Invalid(i) => Err(format!("unsupported proof type: {}", i)),
         }
     }
@@ -204,11 +304,26 @@ impl RegisteredSealProof {
         // Resolve to seal proof and then compute size from that.
         use RegisteredSealProof::*;
         match self {
-            StackedDRG64GiBV1 | StackedDRG64GiBV1P1 => Ok(2300),
-            StackedDRG32GiBV1 | StackedDRG32GiBV1P1 => Ok(2349),
-            StackedDRG2KiBV1 | StackedDRG2KiBV1P1 => Ok(2),
-            StackedDRG8MiBV1 | StackedDRG8MiBV1P1 => Ok(2),
-            StackedDRG512MiBV1 | StackedDRG512MiBV1P1 => Ok(2),
+            StackedDRG64GiBV1
+            | StackedDRG64GiBV1P1
+            | StackedDRG64GiBV1P1_Feat_SyntheticPoRep
+            | StackedDRG64GiBV1P2_Feat_NiPoRep => Ok(2300),
+            StackedDRG32GiBV1
+            | StackedDRG32GiBV1P1
+            | StackedDRG32GiBV1P1_Feat_SyntheticPoRep
+            | StackedDRG32GiBV1P2_Feat_NiPoRep => Ok(2349),
+            StackedDRG2KiBV1
+            | StackedDRG2KiBV1P1
+            | StackedDRG2KiBV1P1_Feat_SyntheticPoRep
+            | StackedDRG2KiBV1P2_Feat_NiPoRep => Ok(2),
+            StackedDRG8MiBV1
+            | StackedDRG8MiBV1P1
+            | StackedDRG8MiBV1P1_Feat_SyntheticPoRep
+            | StackedDRG8MiBV1P2_Feat_NiPoRep => Ok(2),
+            StackedDRG512MiBV1
+            | StackedDRG512MiBV1P1
+            | StackedDRG512MiBV1P1_Feat_SyntheticPoRep
+            | StackedDRG512MiBV1P2_Feat_NiPoRep => Ok(2),
             Invalid(i) => Err(format!("unsupported proof type: {}", i)),
         }
     }
@@ -218,11 +333,26 @@ impl RegisteredSealProof {
     pub fn registered_winning_post_proof(self) -> Result<RegisteredPoStProof, String> {
         use RegisteredPoStProof::*;
         match self {
-            Self::StackedDRG64GiBV1 | Self::StackedDRG64GiBV1P1 => Ok(StackedDRGWinning64GiBV1),
-            Self::StackedDRG32GiBV1 | Self::StackedDRG32GiBV1P1 => Ok(StackedDRGWinning32GiBV1),
-            Self::StackedDRG2KiBV1 | Self::StackedDRG2KiBV1P1 => Ok(StackedDRGWinning2KiBV1),
-            Self::StackedDRG8MiBV1 | Self::StackedDRG8MiBV1P1 => Ok(StackedDRGWinning8MiBV1),
-            Self::StackedDRG512MiBV1 | Self::StackedDRG512MiBV1P1 => Ok(StackedDRGWinning512MiBV1),
+            Self::StackedDRG64GiBV1
+            | Self::StackedDRG64GiBV1P1
+            | Self::StackedDRG64GiBV1P1_Feat_SyntheticPoRep
+            | Self::StackedDRG64GiBV1P2_Feat_NiPoRep => Ok(StackedDRGWinning64GiBV1),
+            Self::StackedDRG32GiBV1
+            | Self::StackedDRG32GiBV1P1
+            | Self::StackedDRG32GiBV1P1_Feat_SyntheticPoRep
+            | Self::StackedDRG32GiBV1P2_Feat_NiPoRep => Ok(StackedDRGWinning32GiBV1),
+            Self::StackedDRG2KiBV1
+            | Self::StackedDRG2KiBV1P1
+            | Self::StackedDRG2KiBV1P1_Feat_SyntheticPoRep
+            | Self::StackedDRG2KiBV1P2_Feat_NiPoRep => Ok(StackedDRGWinning2KiBV1),
+            Self::StackedDRG8MiBV1
+            | Self::StackedDRG8MiBV1P1
+            | Self::StackedDRG8MiBV1P1_Feat_SyntheticPoRep
+            | Self::StackedDRG8MiBV1P2_Feat_NiPoRep => Ok(StackedDRGWinning8MiBV1),
+            Self::StackedDRG512MiBV1
+            | Self::StackedDRG512MiBV1P1
+            | Self::StackedDRG512MiBV1P1_Feat_SyntheticPoRep
+            | Self::StackedDRG512MiBV1P2_Feat_NiPoRep => Ok(StackedDRGWinning512MiBV1),
             Self::Invalid(_) => Err(format!(
                 "Unsupported mapping from {:?} to PoSt-winning RegisteredProof",
                 self
@@ -235,11 +365,26 @@ impl RegisteredSealProof {
     pub fn registered_window_post_proof(self) -> Result<RegisteredPoStProof, String> {
         use RegisteredPoStProof::*;
         match self {
-            Self::StackedDRG64GiBV1 | Self::StackedDRG64GiBV1P1 => Ok(StackedDRGWindow64GiBV1P1),
-            Self::StackedDRG32GiBV1 | Self::StackedDRG32GiBV1P1 => Ok(StackedDRGWindow32GiBV1P1),
-            Self::StackedDRG2KiBV1 | Self::StackedDRG2KiBV1P1 => Ok(StackedDRGWindow2KiBV1P1),
-            Self::StackedDRG8MiBV1 | Self::StackedDRG8MiBV1P1 => Ok(StackedDRGWindow8MiBV1P1),
-            Self::StackedDRG512MiBV1 | Self::StackedDRG512MiBV1P1 => Ok(StackedDRGWindow512MiBV1P1),
+            Self::StackedDRG64GiBV1
+            | Self::StackedDRG64GiBV1P1
+            | Self::StackedDRG64GiBV1P1_Feat_SyntheticPoRep
+            | Self::StackedDRG64GiBV1P2_Feat_NiPoRep => Ok(StackedDRGWindow64GiBV1P1),
+            Self::StackedDRG32GiBV1
+            | Self::StackedDRG32GiBV1P1
+            | Self::StackedDRG32GiBV1P1_Feat_SyntheticPoRep
+            | Self::StackedDRG32GiBV1P2_Feat_NiPoRep => Ok(StackedDRGWindow32GiBV1P1),
+            Self::StackedDRG2KiBV1
+            | Self::StackedDRG2KiBV1P1
+            | Self::StackedDRG2KiBV1P1_Feat_SyntheticPoRep
+            | Self::StackedDRG2KiBV1P2_Feat_NiPoRep => Ok(StackedDRGWindow2KiBV1P1),
+            Self::StackedDRG8MiBV1
+            | Self::StackedDRG8MiBV1P1
+            | Self::StackedDRG8MiBV1P1_Feat_SyntheticPoRep
+            | Self::StackedDRG8MiBV1P2_Feat_NiPoRep => Ok(StackedDRGWindow8MiBV1P1),
+            Self::StackedDRG512MiBV1
+            | Self::StackedDRG512MiBV1P1
+            | Self::StackedDRG512MiBV1P1_Feat_SyntheticPoRep
+            | Self::StackedDRG512MiBV1P2_Feat_NiPoRep => Ok(StackedDRGWindow512MiBV1P1),
             Self::Invalid(_) => Err(format!(
                 "Unsupported mapping from {:?} to PoSt-window RegisteredProof",
                 self
@@ -251,11 +396,26 @@ impl RegisteredSealProof {
     pub fn registered_update_proof(self) -> Result<RegisteredUpdateProof, String> {
         use RegisteredUpdateProof::*;
         match self {
-            Self::StackedDRG64GiBV1 | Self::StackedDRG64GiBV1P1 => Ok(StackedDRG64GiBV1),
-            Self::StackedDRG32GiBV1 | Self::StackedDRG32GiBV1P1 => Ok(StackedDRG32GiBV1),
-            Self::StackedDRG2KiBV1 | Self::StackedDRG2KiBV1P1 => Ok(StackedDRG2KiBV1),
-            Self::StackedDRG8MiBV1 | Self::StackedDRG8MiBV1P1 => Ok(StackedDRG8MiBV1),
-            Self::StackedDRG512MiBV1 | Self::StackedDRG512MiBV1P1 => Ok(StackedDRG512MiBV1),
+            Self::StackedDRG64GiBV1
+            | Self::StackedDRG64GiBV1P1
+            | Self::StackedDRG64GiBV1P1_Feat_SyntheticPoRep
+            | Self::StackedDRG64GiBV1P2_Feat_NiPoRep => Ok(StackedDRG64GiBV1),
+            Self::StackedDRG32GiBV1
+            | Self::StackedDRG32GiBV1P1
+            | Self::StackedDRG32GiBV1P1_Feat_SyntheticPoRep
+            | Self::StackedDRG32GiBV1P2_Feat_NiPoRep => Ok(StackedDRG32GiBV1),
+            Self::StackedDRG2KiBV1
+            | Self::StackedDRG2KiBV1P1
+            | Self::StackedDRG2KiBV1P1_Feat_SyntheticPoRep
+            | Self::StackedDRG2KiBV1P2_Feat_NiPoRep => Ok(StackedDRG2KiBV1),
+            Self::StackedDRG8MiBV1
+            | Self::StackedDRG8MiBV1P1
+            | Self::StackedDRG8MiBV1P1_Feat_SyntheticPoRep
+            | Self::StackedDRG8MiBV1P2_Feat_NiPoRep => Ok(StackedDRG8MiBV1),
+            Self::StackedDRG512MiBV1
+            | Self::StackedDRG512MiBV1P1
+            | Self::StackedDRG512MiBV1P1_Feat_SyntheticPoRep
+            | Self::StackedDRG512MiBV1P2_Feat_NiPoRep => Ok(StackedDRG512MiBV1),
             Self::Invalid(_) => Err(format!(
                 "Unsupported mapping from {:?} to Update RegisteredProof",
                 self
@@ -269,6 +429,8 @@ impl RegisteredSealProof {
 pub enum RegisteredAggregateProof {
     SnarkPackV1,
     SnarkPackV2,
+    /// SnarkPack aggregation of non-interactive PoRep proofs (FIP-0092).
+    SnarkPackV3,
     Invalid(i64),
 }
 
@@ -336,12 +498,25 @@ i64_conversion! {
     StackedDRG512MiBV1P1 => 7,
     StackedDRG32GiBV1P1 => 8,
     StackedDRG64GiBV1P1 => 9,
+
+    StackedDRG2KiBV1P1_Feat_SyntheticPoRep => 10,
+    StackedDRG8MiBV1P1_Feat_SyntheticPoRep => 11,
+    StackedDRG512MiBV1P1_Feat_SyntheticPoRep => 12,
+    StackedDRG32GiBV1P1_Feat_SyntheticPoRep => 13,
+    StackedDRG64GiBV1P1_Feat_SyntheticPoRep => 14,
+
+    StackedDRG2KiBV1P2_Feat_NiPoRep => 15,
+    StackedDRG8MiBV1P2_Feat_NiPoRep => 16,
+    StackedDRG512MiBV1P2_Feat_NiPoRep => 17,
+    StackedDRG32GiBV1P2_Feat_NiPoRep => 18,
+    StackedDRG64GiBV1P2_Feat_NiPoRep => 19,
 }
 
 i64_conversion! {
     RegisteredAggregateProof;
     SnarkPackV1 => 0,
     SnarkPackV2 => 1,
+    SnarkPackV3 => 2,
 }
 
 i64_conversion! {
@@ -360,6 +535,10 @@ impl TryFrom<RegisteredAggregateProof> for filecoin_proofs_api::RegisteredAggreg
         match p {
             SnarkPackV1 => Ok(Self::SnarkPackV1),
             SnarkPackV2 => Ok(Self::SnarkPackV2),
+            #[cfg(feature = "ni-porep")]
+            SnarkPackV3 => Ok(Self::SnarkPackV3),
+            #[cfg(not(feature = "ni-porep"))]
+            SnarkPackV3 => Err("SnarkPackV3 requires the ni-porep feature".into()),
             Invalid(i) => Err(format!("unsupported aggregate proof type: {}", i)),
         }
     }
@@ -381,6 +560,48 @@ impl TryFrom<RegisteredSealProof> for filecoin_proofs_api::RegisteredSealProof {
             StackedDRG2KiBV1P1 => Ok(Self::StackedDrg2KiBV1_1),
             StackedDRG8MiBV1P1 => Ok(Self::StackedDrg8MiBV1_1),
             StackedDRG512MiBV1P1 => Ok(Self::StackedDrg512MiBV1_1),
+            #[cfg(feature = "synthetic-porep")]
+            StackedDRG2KiBV1P1_Feat_SyntheticPoRep => {
+                Ok(Self::StackedDrg2KiBV1_1_Feat_SyntheticPoRep)
+            }
+            #[cfg(feature = "synthetic-porep")]
+            StackedDRG8MiBV1P1_Feat_SyntheticPoRep => {
+                Ok(Self::StackedDrg8MiBV1_1_Feat_SyntheticPoRep)
+            }
+            #[cfg(feature = "synthetic-porep")]
+            StackedDRG512MiBV1P1_Feat_SyntheticPoRep => {
+                Ok(Self::StackedDrg512MiBV1_1_Feat_SyntheticPoRep)
+            }
+            #[cfg(feature = "synthetic-porep")]
+            StackedDRG32GiBV1P1_Feat_SyntheticPoRep => {
+                Ok(Self::StackedDrg32GiBV1_1_Feat_SyntheticPoRep)
+            }
+            #[cfg(feature = "synthetic-porep")]
+            StackedDRG64GiBV1P1_Feat_SyntheticPoRep => {
+                Ok(Self::StackedDrg64GiBV1_1_Feat_SyntheticPoRep)
+            }
+            #[cfg(feature = "ni-porep")]
+            StackedDRG2KiBV1P2_Feat_NiPoRep => {
+                Ok(Self::StackedDrg2KiBV1_2_Feat_NonInteractivePoRep)
+            }
+            #[cfg(feature = "ni-porep")]
+            StackedDRG8MiBV1P2_Feat_NiPoRep => {
+                Ok(Self::StackedDrg8MiBV1_2_Feat_NonInteractivePoRep)
+            }
+            #[cfg(feature = "ni-porep")]
+            StackedDRG512MiBV1P2_Feat_NiPoRep => {
+                Ok(Self::StackedDrg512MiBV1_2_Feat_NonInteractivePoRep)
+            }
+            #[cfg(feature = "ni-porep")]
+            StackedDRG32GiBV1P2_Feat_NiPoRep => {
+                Ok(Self::StackedDrg32GiBV1_2_Feat_NonInteractivePoRep)
+            }
+            #[cfg(feature = "ni-porep")]
+            StackedDRG64GiBV1P2_Feat_NiPoRep => {
+                Ok(Self::StackedDrg64GiBV1_2_Feat_NonInteractivePoRep)
+            }
+            p if p.is_synthetic() => Err(format!("{:?} requires the synthetic-porep feature", p)),
+            p if p.is_non_interactive() => Err(format!("{:?} requires the ni-porep feature", p)),
             Invalid(i) => Err(format!("unsupported proof type: {}", i)),
         }
     }
@@ -503,3 +724,161 @@ impl<'de> Deserialize<'de> for RegisteredUpdateProof {
         Ok(Self::from(val))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SYNTHETIC: [RegisteredSealProof; 5] = [
+        RegisteredSealProof::StackedDRG2KiBV1P1_Feat_SyntheticPoRep,
+        RegisteredSealProof::StackedDRG8MiBV1P1_Feat_SyntheticPoRep,
+        RegisteredSealProof::StackedDRG512MiBV1P1_Feat_SyntheticPoRep,
+        RegisteredSealProof::StackedDRG32GiBV1P1_Feat_SyntheticPoRep,
+        RegisteredSealProof::StackedDRG64GiBV1P1_Feat_SyntheticPoRep,
+    ];
+
+    const NI_POREP: [RegisteredSealProof; 5] = [
+        RegisteredSealProof::StackedDRG2KiBV1P2_Feat_NiPoRep,
+        RegisteredSealProof::StackedDRG8MiBV1P2_Feat_NiPoRep,
+        RegisteredSealProof::StackedDRG512MiBV1P2_Feat_NiPoRep,
+        RegisteredSealProof::StackedDRG32GiBV1P2_Feat_NiPoRep,
+        RegisteredSealProof::StackedDRG64GiBV1P2_Feat_NiPoRep,
+    ];
+
+    const V1P1: [RegisteredSealProof; 5] = [
+        RegisteredSealProof::StackedDRG2KiBV1P1,
+        RegisteredSealProof::StackedDRG8MiBV1P1,
+        RegisteredSealProof::StackedDRG512MiBV1P1,
+        RegisteredSealProof::StackedDRG32GiBV1P1,
+        RegisteredSealProof::StackedDRG64GiBV1P1,
+    ];
+
+    #[test]
+    fn seal_proof_ids() {
+        for (i, p) in (10..).zip(SYNTHETIC.into_iter().chain(NI_POREP)) {
+            assert_eq!(i64::from(p), i);
+            assert_eq!(RegisteredSealProof::from(i), p);
+        }
+        assert_eq!(
+            RegisteredSealProof::from(20),
+            RegisteredSealProof::Invalid(20)
+        );
+        assert_eq!(
+            RegisteredAggregateProof::from(2),
+            RegisteredAggregateProof::SnarkPackV3
+        );
+        assert_eq!(i64::from(RegisteredAggregateProof::SnarkPackV3), 2);
+        assert_eq!(
+            RegisteredAggregateProof::from(3),
+            RegisteredAggregateProof::Invalid(3)
+        );
+    }
+
+    #[test]
+    fn seal_proof_kind() {
+        for p in SYNTHETIC {
+            assert!(p.is_synthetic());
+            assert!(!p.is_non_interactive());
+        }
+        for p in NI_POREP {
+            assert!(!p.is_synthetic());
+            assert!(p.is_non_interactive());
+        }
+        for p in V1P1 {
+            assert!(!p.is_synthetic());
+            assert!(!p.is_non_interactive());
+        }
+        assert!(!RegisteredSealProof::Invalid(10).is_synthetic());
+        assert!(!RegisteredSealProof::Invalid(15).is_non_interactive());
+    }
+
+    #[test]
+    fn seal_proof_size() {
+        for (synthetic, base) in SYNTHETIC.into_iter().zip(V1P1) {
+            assert_eq!(synthetic.proof_size(), base.proof_size());
+        }
+        assert_eq!(V1P1[0].proof_size(), Ok(192));
+        assert_eq!(V1P1[4].proof_size(), Ok(1920));
+        for p in NI_POREP {
+            assert_eq!(p.proof_size(), Ok(126 * 192));
+        }
+        assert!(RegisteredSealProof::Invalid(20).proof_size().is_err());
+    }
+
+    #[test]
+    fn seal_proof_mappings() {
+        for (p, base) in SYNTHETIC
+            .into_iter()
+            .chain(NI_POREP)
+            .zip(V1P1.into_iter().cycle())
+        {
+            assert_eq!(p.sector_size(), base.sector_size());
+            assert_eq!(
+                p.window_post_partitions_sector(),
+                base.window_post_partitions_sector()
+            );
+            assert_eq!(
+                p.registered_winning_post_proof(),
+                base.registered_winning_post_proof()
+            );
+            assert_eq!(
+                p.registered_window_post_proof(),
+                base.registered_window_post_proof()
+            );
+            assert_eq!(p.registered_update_proof(), base.registered_update_proof());
+        }
+        assert_eq!(
+            NI_POREP[3].registered_window_post_proof(),
+            Ok(RegisteredPoStProof::StackedDRGWindow32GiBV1P1)
+        );
+        assert_eq!(
+            SYNTHETIC[0].registered_update_proof(),
+            Ok(RegisteredUpdateProof::StackedDRG2KiBV1)
+        );
+    }
+
+    #[test]
+    fn post_proof_compatibility() {
+        use RegisteredPoStProof::*;
+        for p in SYNTHETIC.into_iter().chain(NI_POREP).chain(V1P1) {
+            let window = p.registered_window_post_proof().unwrap();
+            assert!(window.is_compatible_with(p));
+            assert!(!p
+                .registered_winning_post_proof()
+                .unwrap()
+                .is_compatible_with(p));
+        }
+        assert!(StackedDRGWindow32GiBV1.is_compatible_with(NI_POREP[3]));
+        assert!(StackedDRGWindow32GiBV1.is_compatible_with(SYNTHETIC[3]));
+        assert!(!StackedDRGWindow64GiBV1P1.is_compatible_with(NI_POREP[3]));
+        assert!(!StackedDRGWindow2KiBV1P1.is_compatible_with(RegisteredSealProof::Invalid(20)));
+        assert!(!Invalid(13).is_compatible_with(V1P1[3]));
+    }
+
+    #[cfg(feature = "proofs")]
+    #[test]
+    fn proofs_api_conversion() {
+        for p in SYNTHETIC {
+            assert_eq!(
+                filecoin_proofs_api::RegisteredSealProof::try_from(p).is_ok(),
+                cfg!(feature = "synthetic-porep")
+            );
+        }
+        for p in NI_POREP {
+            assert_eq!(
+                filecoin_proofs_api::RegisteredSealProof::try_from(p).is_ok(),
+                cfg!(feature = "ni-porep")
+            );
+        }
+        assert_eq!(
+            filecoin_proofs_api::RegisteredAggregationProof::try_from(
+                RegisteredAggregateProof::SnarkPackV3
+            )
+            .is_ok(),
+            cfg!(feature = "ni-porep")
+        );
+        for p in V1P1 {
+            assert!(filecoin_proofs_api::RegisteredSealProof::try_from(p).is_ok());
+        }
+    }
+}
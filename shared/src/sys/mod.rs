@@ -67,6 +67,22 @@ impl SendFlags {
     }
 }
 
+bitflags! {
+    /// Flags passed to the `recover_secp_public_key_rsv` syscall.
+    #[derive(Default, Copy, Clone, Eq, PartialEq, Debug)]
+    #[repr(transparent)]
+    pub struct SecpRecoverFlags: u64 {
+        /// Reject malleable ("high-s") signatures.
+        const REJECT_MALLEABLE = 0b00000001;
+    }
+}
+
+impl SecpRecoverFlags {
+    pub fn reject_malleable(self) -> bool {
+        self.intersects(Self::REJECT_MALLEABLE)
+    }
+}
+
 /// An unsafe trait to mark "syscall safe" types. These types must be safe to memcpy to and from
 /// WASM. This means:
 ///
@@ -95,10 +111,77 @@ assert_syscall_safe! {
     TokenAmount,
     out::ipld::IpldOpen,
     out::ipld::IpldStat,
+    out::ipld::IpldStatV2,
+    out::ipld::IpldLinks,
     out::send::Send,
     out::crypto::VerifyConsensusFault,
     out::network::NetworkContext,
+    out::network::NetworkContextV2,
     out::vm::MessageContext,
 }
 
 unsafe impl<T, const N: usize> SyscallSafe for [T; N] where T: SyscallSafe {}
+
+/// An unsafe trait to mark syscall structs that may be extended by appending fields, returned by
+/// "extensible" syscalls (e.g., `network::context_ext`).
+///
+/// Extensible syscalls take an output buffer instead of returning a fixed-size value. The FVM writes
+/// as much of the latest version of the struct as fits into the buffer, and returns the number of
+/// bytes written. This way:
+///
+/// 1. Actors built against an older version of the struct get the fields they know about.
+/// 2. Actors built against a newer version of the struct, running on an older FVM, can tell which
+///    fields are missing (see [`sys_field_end`](crate::sys_field_end)). The missing fields are zeroed.
+///
+/// To extend such a struct, only ever append fields: never remove, reorder, or resize them. If the
+/// struct is also returned by a fixed-size syscall, that syscall must keep returning a frozen copy
+/// of the current layout (as `network::context` does with [`out::network::NetworkContext`]).
+///
+/// # Safety
+///
+/// In addition to the [`SyscallSafe`] requirements, the all-zero bit pattern must be a valid value,
+/// and `MIN_SIZE` must not exceed the struct's size.
+pub unsafe trait Extensible: SyscallSafe {
+    /// The size of the first version of the struct. Extensible syscalls reject smaller buffers.
+    const MIN_SIZE: usize;
+}
+
+macro_rules! assert_extensible {
+    ($($t:ty => $first:ty,)*) => {
+        $(unsafe impl Extensible for $t {
+            const MIN_SIZE: usize = std::mem::size_of::<$first>();
+        })*
+    }
+}
+
+assert_extensible! {
+    out::network::NetworkContextV2 => out::network::NetworkContext,
+    out::vm::MessageContext => out::vm::MessageContext,
+}
+
+/// Returns the offset of the end of a field of a syscall struct. A value returned by an extensible
+/// syscall (see [`Extensible`](crate::sys::Extensible)) includes the field if, and only if, at
+/// least that many bytes were written.
+///
+/// ```
+/// use fvm_shared::sys::out::network::{NetworkContext, NetworkContextV2};
+/// use fvm_shared::sys_field_end;
+///
+/// assert_eq!(
+///     sys_field_end!(NetworkContextV2, network_version),
+///     std::mem::size_of::<NetworkContext>()
+/// );
+/// ```
+#[macro_export]
+macro_rules! sys_field_end {
+    ($t:ty, $field:ident) => {{
+        fn pointee_size<T>(_: *const T) -> usize {
+            ::core::mem::size_of::<T>()
+        }
+        let value = ::core::mem::MaybeUninit::<$t>::uninit();
+        let base = value.as_ptr();
+        // SAFETY: we only compute the field's address, without reading it.
+        let field = unsafe { ::core::ptr::addr_of!((*base).$field) };
+        (field as usize - base as usize) + pointee_size(field)
+    }};
+}
@@ -14,7 +14,8 @@
 //
 // Read up on https://doc.rust-lang.org/reference/type-layout.html for more information.
 //
-// Also, please also read the docs on super::SyscallSafe before modifying any of these types.
+// Also, please also read the docs on super::SyscallSafe before modifying any of these types, and
+// the docs on super::Extensible before extending types returned by extensible syscalls.
 
 pub mod ipld {
     #[derive(Debug, Copy, Clone, PartialEq, Eq)]
@@ -31,6 +32,43 @@ pub mod ipld {
         pub codec: u64,
         pub size: u32,
     }
+
+    /// Extended block information returned by the `ipld::block_stat_v2` syscall.
+    ///
+    /// This is a strict superset of [`IpldStat`]. The original struct (and the `ipld::block_stat`
+    /// syscall returning it) are left unchanged so that deployed actors continue to work.
+    #[derive(Debug, Copy, Clone, PartialEq, Eq)]
+    #[repr(packed, C)]
+    pub struct IpldStatV2 {
+        /// The block's codec.
+        pub codec: u64,
+        /// The block's size in bytes.
+        pub size: u32,
+        /// The number of links in the block.
+        pub links: u32,
+        /// 1 if the block is in the reachable set (it was opened by CID, or linked), 0 otherwise.
+        pub reachable: u32,
+    }
+
+    impl From<IpldStatV2> for IpldStat {
+        fn from(stat: IpldStatV2) -> Self {
+            IpldStat {
+                codec: stat.codec,
+                size: stat.size,
+            }
+        }
+    }
+
+    #[derive(Debug, Copy, Clone, PartialEq, Eq)]
+    #[repr(packed, C)]
+    pub struct IpldLinks {
+        /// The total number of links in the block.
+        pub total: u32,
+        /// The number of links written into the output buffer.
+        pub count: u32,
+        /// The number of bytes written into the output buffer.
+        pub length: u32,
+    }
 }
 
 pub mod send {
@@ -81,6 +119,8 @@ pub mod vm {
         }
     }
 
+    /// Returned by the `vm::message_context` syscall and, as an extensible struct, by the
+    /// `vm::message_context_ext` syscall.
     #[derive(Debug, Copy, Clone, PartialEq, Eq)]
     #[repr(packed, C)]
     pub struct MessageContext {
@@ -124,4 +164,41 @@ pub mod network {
         /// The network version.
         pub network_version: NetworkVersion,
     }
+
+    /// Extended network information returned by the `network::context_v2` syscall.
+    ///
+    /// This is a strict superset of [`NetworkContext`]. The original struct (and the
+    /// `network::context` syscall returning it) are left unchanged so that deployed actors
+    /// continue to work. This struct is also returned, as an extensible struct, by the
+    /// `network::context_ext` syscall.
+    #[derive(Debug, Copy, Clone, PartialEq, Eq)]
+    #[repr(packed, C)]
+    pub struct NetworkContextV2 {
+        /// The current epoch.
+        pub epoch: ChainEpoch,
+        /// The current time (seconds since the unix epoch).
+        pub timestamp: u64,
+        /// The current base-fee.
+        pub base_fee: TokenAmount,
+        /// The Chain ID of the network.
+        pub chain_id: u64,
+        /// The network version.
+        pub network_version: NetworkVersion,
+        /// The time of the genesis block (seconds since the unix epoch).
+        pub genesis_timestamp: u64,
+        /// The maximum amount of gas that may be used by all messages in a block.
+        pub block_gas_limit: u64,
+    }
+
+    impl From<NetworkContextV2> for NetworkContext {
+        fn from(ctx: NetworkContextV2) -> Self {
+            NetworkContext {
+                epoch: ctx.epoch,
+                timestamp: ctx.timestamp,
+                base_fee: ctx.base_fee,
+                chain_id: ctx.chain_id,
+                network_version: ctx.network_version,
+            }
+        }
+    }
 }
@@ -8,7 +8,8 @@ use std::str::FromStr;
 use data_encoding::{DecodeError, DecodeKind};
 use fvm_ipld_encoding::{from_slice, to_vec};
 use fvm_shared::address::{
-    Address, Error, Protocol, BLS_PUB_LEN, MAX_SUBADDRESS_LEN, PAYLOAD_HASH_LEN, SECP_PUB_LEN,
+    Address, Error, EthAddress, Protocol, BLS_PUB_LEN, EAM_NAMESPACE, MAX_ADDRESS_TEXT_LEN,
+    MAX_SUBADDRESS_LEN, PAYLOAD_HASH_LEN, SECP_PUB_LEN,
 };
 use quickcheck_macros::quickcheck;
 
@@ -589,6 +590,102 @@ fn address_hashmap() {
     assert_eq!(hm.get(&h1).unwrap(), &1);
 }
 
+#[test]
+fn format_without_allocating() {
+    const ID: Address = Address::new_id(u64::MAX);
+    let addrs = [
+        ID,
+        Address::new_secp256k1(&[1; SECP_PUB_LEN]).unwrap(),
+        Address::new_actor(b"actor"),
+        Address::new_bls(&[2; BLS_PUB_LEN]).unwrap(),
+        Address::new_delegated(u64::MAX, &[3; MAX_SUBADDRESS_LEN]).unwrap(),
+        Address::new_delegated(10, &[]).unwrap(),
+    ];
+    for addr in addrs {
+        let mut buf = [0u8; MAX_ADDRESS_TEXT_LEN];
+        assert_eq!(addr.format_into(&mut buf), addr.to_string());
+
+        let mut s = String::new();
+        addr.write_str(&mut s).unwrap();
+        assert_eq!(s, addr.to_string());
+    }
+}
+
+#[test]
+fn eth_address_checksum() {
+    // Test vectors from EIP-55.
+    let vectors = [
+        "0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed",
+        "0xfB6916095ca1df60bB79Ce92cE3Ea74c37c5d359",
+        "0xdbF03B407c01E7cD3CBea99509d93f8DDDC8C6FB",
+        "0xD1220A0cf47c7B9Be7A2E6BA89F429762e7b9aDb",
+    ];
+    for s in vectors {
+        let addr = EthAddress::from_str(s).unwrap();
+        assert_eq!(addr.to_string(), s);
+
+        // All lowercase and all uppercase addresses aren't checksummed.
+        let lower = format!("0x{}", s[2..].to_ascii_lowercase());
+        let upper = format!("0x{}", s[2..].to_ascii_uppercase());
+        assert_eq!(EthAddress::from_str(&lower).unwrap(), addr);
+        assert_eq!(EthAddress::from_str(&upper).unwrap(), addr);
+    }
+
+    assert_eq!(
+        EthAddress::from_str("0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAeD").unwrap_err(),
+        Error::InvalidChecksum
+    );
+    assert_eq!(
+        EthAddress::from_str("5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed").unwrap_err(),
+        Error::InvalidEthAddress
+    );
+    assert_eq!(
+        EthAddress::from_str("0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeA").unwrap_err(),
+        Error::InvalidLength
+    );
+    assert_eq!(
+        EthAddress::from_str("0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAgg").unwrap_err(),
+        Error::InvalidEthAddress
+    );
+}
+
+#[test]
+fn eth_address_conversions() {
+    let eth = EthAddress::from_str("0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed").unwrap();
+    let addr = Address::from(eth);
+    assert_eq!(addr, Address::new_delegated(EAM_NAMESPACE, &eth.0).unwrap());
+    assert_eq!(EthAddress::try_from(&addr).unwrap(), eth);
+    assert_eq!(
+        Address::from_str(&addr.to_string()).unwrap(),
+        Address::from(eth)
+    );
+
+    // Masked IDs round-trip through ID addresses.
+    let masked = EthAddress::from_id(1234);
+    assert_eq!(masked.as_id(), Some(1234));
+    assert_eq!(Address::from(masked), Address::new_id(1234));
+    assert_eq!(
+        EthAddress::try_from(&Address::new_id(1234)).unwrap(),
+        masked
+    );
+    assert_eq!(eth.as_id(), None);
+
+    // Other addresses aren't ethereum addresses.
+    assert_eq!(
+        EthAddress::try_from(&Address::new_actor(b"actor")).unwrap_err(),
+        Error::NonEthAddress
+    );
+    assert_eq!(
+        EthAddress::try_from(&Address::new_delegated(EAM_NAMESPACE, &[1; 19]).unwrap())
+            .unwrap_err(),
+        Error::InvalidPayloadLength(19)
+    );
+    assert_eq!(
+        EthAddress::try_from(&Address::new_delegated(32, &eth.0).unwrap()).unwrap_err(),
+        Error::NonEthAddress
+    );
+}
+
 #[test]
 fn invalid_strings_tests() {
     let invalid_strings = &[
@@ -9,6 +9,7 @@ use crate::vector::Randomness;
 
 /// The externs stub for testing. Forwards randomness requests to the randomness
 /// replayer, which replays randomness stored in the vector.
+#[derive(Clone)]
 pub struct TestExterns {
     rand: ReplayingRand,
 }
@@ -32,6 +33,16 @@ impl Rand for TestExterns {
     fn get_beacon_randomness(&self, round: ChainEpoch) -> anyhow::Result<[u8; 32]> {
         self.rand.get_beacon_randomness(round)
     }
+
+    fn verify_beacon_entry(
+        &self,
+        round: u64,
+        prev_signature: &[u8],
+        signature: &[u8],
+    ) -> anyhow::Result<bool> {
+        self.rand
+            .verify_beacon_entry(round, prev_signature, signature)
+    }
 }
 
 impl Consensus for TestExterns {
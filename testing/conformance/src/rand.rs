@@ -10,6 +10,7 @@ use crate::vector::{RandomnessKind, RandomnessMatch, RandomnessRule};
 /// Takes recorded randomness and replays it when input parameters match.
 /// When there's no match, it falls back to TestFallbackRand, which returns a
 /// fixed output.
+#[derive(Clone)]
 pub struct ReplayingRand {
     pub recorded: Vec<RandomnessMatch>,
     pub fallback: TestFallbackRand,
@@ -17,6 +18,7 @@ pub struct ReplayingRand {
 
 /// Implements the Rand extern and returns static values as randomness outputs
 /// when there's a vector miss.
+#[derive(Clone)]
 pub struct TestFallbackRand;
 
 impl Rand for TestFallbackRand {
@@ -27,6 +29,10 @@ impl Rand for TestFallbackRand {
     fn get_beacon_randomness(&self, _: ChainEpoch) -> anyhow::Result<[u8; 32]> {
         Ok(*b"i_am_random_____i_am_random_____")
     }
+
+    fn verify_beacon_entry(&self, _: u64, _: &[u8], _: &[u8]) -> anyhow::Result<bool> {
+        Ok(false)
+    }
 }
 
 impl ReplayingRand {
@@ -72,4 +78,15 @@ impl Rand for ReplayingRand {
             self.fallback.get_beacon_randomness(epoch)
         }
     }
+
+    fn verify_beacon_entry(
+        &self,
+        round: u64,
+        prev_signature: &[u8],
+        signature: &[u8],
+    ) -> anyhow::Result<bool> {
+        // Beacon verification is never recorded in test vectors.
+        self.fallback
+            .verify_beacon_entry(round, prev_signature, signature)
+    }
 }
@@ -1,18 +1,21 @@
 // Copyright 2021-2023 Protocol Labs
 // SPDX-License-Identifier: Apache-2.0, MIT
+use std::borrow::Cow;
 use std::convert::TryFrom;
+use std::rc::Rc;
 use std::sync::{Arc, Mutex};
 
 use anyhow::anyhow;
 use cid::Cid;
 use multihash::MultihashGeneric;
 
-use fvm::call_manager::{CallManager, DefaultCallManager};
-use fvm::gas::{price_list_by_network_version, Gas, GasTimer, PriceList};
+use fvm::call_manager::{CallFrame, CallManager, DefaultCallManager};
+use fvm::gas::{price_list_by_network_version, Gas, GasCharge, GasChargeName, GasTimer, PriceList};
 use fvm::kernel::*;
 use fvm::machine::limiter::MemoryLimiter;
 use fvm::machine::{DefaultMachine, Machine, MachineContext, Manifest, NetworkConfig};
 use fvm::state_tree::StateTree;
+use fvm::syscalls::InvocationData;
 use fvm::DefaultKernel;
 use fvm_ipld_blockstore::MemoryBlockstore;
 use fvm_shared::address::Address;
@@ -31,6 +34,7 @@ use fvm_shared::sector::{
 use fvm_shared::sys::SendFlags;
 use fvm_shared::version::NetworkVersion;
 use fvm_shared::{ActorID, MethodNum, TOTAL_FILECOIN};
+use wasmtime::Linker;
 
 use crate::externs::TestExterns;
 use crate::vector::{MessageVector, Variant};
@@ -98,17 +102,19 @@ impl TestMachine<Box<DefaultMachine<MemoryBlockstore, TestExterns>>> {
         let externs = TestExterns::new(&v.randomness);
 
         let mut nc = NetworkConfig::new(network_version);
-        let mut mc = nc.for_epoch(epoch, (epoch * 30) as u64, state_root);
+        let mut mc = nc.for_epoch(epoch, (epoch * 30) as u64, state_root)?;
         // Allow overriding prices to some other network version.
         if let Some(nv) = price_network_version {
-            nc.price_list = price_list_by_network_version(nv);
+            nc.price_list = Cow::Borrowed(price_list_by_network_version(nv));
         }
         mc.set_base_fee(base_fee);
-        mc.tracing = tracing;
+        if tracing {
+            mc.enable_tracing();
+        }
 
         let machine = DefaultMachine::new(&mc, blockstore, externs).unwrap();
 
-        let price_list = machine.context().price_list.clone();
+        let price_list = machine.context().price_list.clone().into_owned();
 
         let machine = TestMachine::<Box<DefaultMachine<_, _>>> {
             machine: Box::new(machine),
@@ -151,6 +157,14 @@ where
         self.machine.builtin_actors()
     }
 
+    fn set_builtin_actors(&mut self, manifest: Manifest) -> anyhow::Result<()> {
+        self.machine.set_builtin_actors(manifest)
+    }
+
+    fn advance_epoch(&mut self, epoch: ChainEpoch, timestamp: u64) -> anyhow::Result<()> {
+        self.machine.advance_epoch(epoch, timestamp)
+    }
+
     fn state_tree(&self) -> &StateTree<Self::Blockstore> {
         self.machine.state_tree()
     }
@@ -167,6 +181,10 @@ where
         self.machine.flush()
     }
 
+    fn flush_partial(&mut self) -> Result<Cid> {
+        self.machine.flush_partial()
+    }
+
     fn machine_id(&self) -> &str {
         self.machine.machine_id()
     }
@@ -178,6 +196,19 @@ where
             local_stats: TestStats::default(),
         }
     }
+
+    fn tipset_cid(&self, epoch: ChainEpoch) -> anyhow::Result<Cid> {
+        self.machine.tipset_cid(epoch)
+    }
+
+    fn verify_consensus_fault(
+        &self,
+        h1: &[u8],
+        h2: &[u8],
+        extra: &[u8],
+    ) -> anyhow::Result<(Option<ConsensusFault>, i64)> {
+        self.machine.verify_consensus_fault(h1, h2, extra)
+    }
 }
 
 /// A kernel for intercepting syscalls.
@@ -189,7 +220,17 @@ impl<M, C, K> Kernel for TestKernel<K>
 where
     M: Machine,
     C: CallManager<Machine = TestMachine<M>>,
-    K: Kernel<CallManager = C>,
+    K: Kernel<CallManager = C>
+        + ActorOps
+        + CircSupplyOps
+        + CryptoOps
+        + DebugOps
+        + EventOps
+        + IpldBlockOps
+        + MessageOps
+        + NetworkOps
+        + RandomnessOps
+        + SelfOps,
 {
     type CallManager = K::CallManager;
 
@@ -233,6 +274,10 @@ where
         self.0.machine()
     }
 
+    fn trace_syscall(&self, module: &'static str, name: &'static str) {
+        self.0.trace_syscall(module, name)
+    }
+
     fn send<KK>(
         &mut self,
         recipient: &Address,
@@ -251,11 +296,33 @@ where
     }
 }
 
+// Binds the default syscalls against the TestKernel so intercepted operations are reachable.
+impl<M, C, K> SyscallHandler<TestKernel<K>> for TestKernel<K>
+where
+    M: Machine,
+    C: CallManager<Machine = TestMachine<M>>,
+    K: Kernel<CallManager = C>
+        + ActorOps
+        + CircSupplyOps
+        + CryptoOps
+        + DebugOps
+        + EventOps
+        + IpldBlockOps
+        + MessageOps
+        + NetworkOps
+        + RandomnessOps
+        + SelfOps,
+{
+    fn bind_syscalls(linker: &mut Linker<InvocationData<TestKernel<K>>>) -> anyhow::Result<()> {
+        DefaultKernel::<C>::bind_syscalls(linker)
+    }
+}
+
 impl<M, C, K> ActorOps for TestKernel<K>
 where
     M: Machine,
     C: CallManager<Machine = TestMachine<M>>,
-    K: Kernel<CallManager = C>,
+    K: Kernel<CallManager = C> + ActorOps,
 {
     fn resolve_address(&self, address: &Address) -> Result<ActorID> {
         self.0.resolve_address(address)
@@ -304,7 +371,7 @@ impl<M, C, K> IpldBlockOps for TestKernel<K>
 where
     M: Machine,
     C: CallManager<Machine = TestMachine<M>>,
-    K: Kernel<CallManager = C>,
+    K: Kernel<CallManager = C> + IpldBlockOps,
 {
     fn block_open(&mut self, cid: &Cid) -> Result<(BlockId, BlockStat)> {
         self.0.block_open(cid)
@@ -325,13 +392,17 @@ where
     fn block_stat(&self, id: BlockId) -> Result<BlockStat> {
         self.0.block_stat(id)
     }
+
+    fn block_links(&self, id: BlockId) -> Result<Rc<[Cid]>> {
+        self.0.block_links(id)
+    }
 }
 
 impl<M, C, K> CircSupplyOps for TestKernel<K>
 where
     M: Machine,
     C: CallManager<Machine = TestMachine<M>>,
-    K: Kernel<CallManager = C>,
+    K: Kernel<CallManager = C> + CircSupplyOps,
 {
     // Not forwarded. Circulating supply is taken from the TestData.
     fn total_fil_circ_supply(&self) -> Result<TokenAmount> {
@@ -343,7 +414,7 @@ impl<M, C, K> CryptoOps for TestKernel<K>
 where
     M: Machine,
     C: CallManager<Machine = TestMachine<M>>,
-    K: Kernel<CallManager = C>,
+    K: Kernel<CallManager = C> + CryptoOps,
 {
     // forwarded
     fn hash(&self, code: u64, data: &[u8]) -> Result<MultihashGeneric<64>> {
@@ -388,7 +459,7 @@ where
     // NOT forwarded
     fn verify_post(&self, vi: &WindowPoStVerifyInfo) -> Result<bool> {
         let charge = self.1.price_list.on_verify_post(vi);
-        let _ = self.0.charge_gas(&charge.name, charge.total())?;
+        let _ = self.0.charge_gas(charge.name.clone(), charge.total())?;
         Ok(true)
     }
 
@@ -403,21 +474,21 @@ where
             .1
             .price_list
             .on_verify_consensus_fault(h1.len(), h2.len(), extra.len());
-        let _ = self.0.charge_gas(&charge.name, charge.total())?;
+        let _ = self.0.charge_gas(charge.name.clone(), charge.total())?;
         Ok(None)
     }
 
     // NOT forwarded
     fn verify_aggregate_seals(&self, agg: &AggregateSealVerifyProofAndInfos) -> Result<bool> {
         let charge = self.1.price_list.on_verify_aggregate_seals(agg);
-        let _ = self.0.charge_gas(&charge.name, charge.total())?;
+        let _ = self.0.charge_gas(charge.name.clone(), charge.total())?;
         Ok(true)
     }
 
     // NOT forwarded
     fn verify_replica_update(&self, rep: &ReplicaUpdateInfo) -> Result<bool> {
         let charge = self.1.price_list.on_verify_replica_update(rep);
-        let _ = self.0.charge_gas(&charge.name, charge.total())?;
+        let _ = self.0.charge_gas(charge.name.clone(), charge.total())?;
         Ok(true)
     }
 }
@@ -426,7 +497,7 @@ impl<M, C, K> DebugOps for TestKernel<K>
 where
     M: Machine,
     C: CallManager<Machine = TestMachine<M>>,
-    K: Kernel<CallManager = C>,
+    K: Kernel<CallManager = C> + DebugOps,
 {
     fn log(&self, msg: String) {
         self.0.log(msg)
@@ -436,7 +507,7 @@ where
         self.0.debug_enabled()
     }
 
-    fn store_artifact(&self, name: &str, data: &[u8]) -> Result<()> {
+    fn store_artifact(&mut self, name: &str, data: &[u8]) -> Result<()> {
         self.0.store_artifact(name, data)
     }
 }
@@ -451,10 +522,26 @@ where
         self.0.gas_used()
     }
 
-    fn charge_gas(&self, name: &str, compute: Gas) -> Result<GasTimer> {
+    fn charge_gas(&self, name: impl Into<GasChargeName>, compute: Gas) -> Result<GasTimer> {
         self.0.charge_gas(name, compute)
     }
 
+    fn charge_copy(&self, bytes: usize) -> Result<()> {
+        self.0.charge_copy(bytes)
+    }
+
+    fn charge_memory(&self, name: impl Into<GasChargeName>, gas: Gas) -> Result<GasTimer> {
+        self.0.charge_memory(name, gas)
+    }
+
+    fn charge_gas_many(&self, charges: &[GasCharge]) -> Result<Vec<GasTimer>> {
+        self.0.charge_gas_many(charges)
+    }
+
+    fn records_gas_charges(&self) -> bool {
+        self.0.records_gas_charges()
+    }
+
     fn price_list(&self) -> &PriceList {
         self.0.price_list()
     }
@@ -468,20 +555,28 @@ impl<M, C, K> MessageOps for TestKernel<K>
 where
     M: Machine,
     C: CallManager<Machine = TestMachine<M>>,
-    K: Kernel<CallManager = C>,
+    K: Kernel<CallManager = C> + MessageOps,
 {
     fn msg_context(&self) -> Result<fvm_shared::sys::out::vm::MessageContext> {
         self.0.msg_context()
     }
+
+    fn call_depth(&self) -> u32 {
+        self.0.call_depth()
+    }
+
+    fn call_stack(&self) -> &[CallFrame] {
+        self.0.call_stack()
+    }
 }
 
 impl<M, C, K> NetworkOps for TestKernel<K>
 where
     M: Machine,
     C: CallManager<Machine = TestMachine<M>>,
-    K: Kernel<CallManager = C>,
+    K: Kernel<CallManager = C> + NetworkOps,
 {
-    fn network_context(&self) -> Result<fvm_shared::sys::out::network::NetworkContext> {
+    fn network_context(&self) -> Result<fvm_shared::sys::out::network::NetworkContextV2> {
         self.0.network_context()
     }
 
@@ -494,7 +589,7 @@ impl<M, C, K> RandomnessOps for TestKernel<K>
 where
     M: Machine,
     C: CallManager<Machine = TestMachine<M>>,
-    K: Kernel<CallManager = C>,
+    K: Kernel<CallManager = C> + RandomnessOps,
 {
     fn get_randomness_from_tickets(
         &self,
@@ -515,13 +610,22 @@ where
         self.0
             .get_randomness_from_beacon(personalization, rand_epoch, entropy)
     }
+
+    fn verify_beacon_entry(
+        &self,
+        round: u64,
+        prev_signature: &[u8],
+        signature: &[u8],
+    ) -> Result<bool> {
+        self.0.verify_beacon_entry(round, prev_signature, signature)
+    }
 }
 
 impl<M, C, K> SelfOps for TestKernel<K>
 where
     M: Machine,
     C: CallManager<Machine = TestMachine<M>>,
-    K: Kernel<CallManager = C>,
+    K: Kernel<CallManager = C> + SelfOps,
 {
     fn root(&self) -> Result<Cid> {
         self.0.root()
@@ -555,7 +659,7 @@ impl<M, C, K> EventOps for TestKernel<K>
 where
     M: Machine,
     C: CallManager<Machine = TestMachine<M>>,
-    K: Kernel<CallManager = C>,
+    K: Kernel<CallManager = C> + EventOps,
 {
     fn emit_event(&mut self, raw_evt: &[u8]) -> Result<()> {
         self.0.emit_event(raw_evt)
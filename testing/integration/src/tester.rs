@@ -1,13 +1,16 @@
 // Copyright 2021-2023 Protocol Labs
 // SPDX-License-Identifier: Apache-2.0, MIT
+use std::collections::BTreeMap;
+
 use anyhow::{anyhow, Context, Result};
 use cid::Cid;
 use fvm::call_manager::DefaultCallManager;
 use fvm::engine::EnginePool;
-use fvm::executor::DefaultExecutor;
+use fvm::executor::{DefaultExecutor, Executor};
 use fvm::externs::Externs;
 use fvm::machine::{DefaultMachine, Machine, MachineContext, NetworkConfig};
 use fvm::state_tree::{ActorState, StateTree};
+use fvm::trace::TraceConfig;
 use fvm::{init_actor, system_actor, DefaultKernel};
 use fvm_ipld_blockstore::{Block, Blockstore, MemoryBlockstore};
 use fvm_ipld_encoding::{ser, CborStore};
@@ -42,13 +45,35 @@ pub type Account = (ActorID, Address);
 pub struct ExecutionOptions {
     /// Enables debug logging
     pub debug: bool,
-    /// Enables gas tracing
-    pub trace: bool,
+    /// Which execution events to trace
+    pub trace: TraceConfig,
     /// Enabls events
     pub events: bool,
 }
 
-pub struct Tester<B: Blockstore + 'static, E: Externs + 'static> {
+/// A snapshot of the tester's state, taken with [`Tester::snapshot`] and restored with
+/// [`Tester::restore`].
+#[derive(Clone, Debug)]
+pub struct Snapshot {
+    // Flushed state root
+    state_root: Cid,
+    // Sequences (nonces) of the accounts created by the tester
+    nonces: BTreeMap<ActorID, u64>,
+}
+
+impl Snapshot {
+    /// Returns the snapshotted state root.
+    pub fn state_root(&self) -> Cid {
+        self.state_root
+    }
+
+    /// Returns the snapshotted sequence (nonce) of an account created by the tester.
+    pub fn nonce(&self, id: ActorID) -> Option<u64> {
+        self.nonces.get(&id).copied()
+    }
+}
+
+pub struct Tester<B: Blockstore + Clone + 'static, E: Externs + Clone + 'static> {
     // Network version used in the test
     nv: NetworkVersion,
     // Builtin actors root Cid used in the Machine
@@ -59,6 +84,8 @@ pub struct Tester<B: Blockstore + 'static, E: Externs + 'static> {
     placeholder_code_cid: Cid,
     // Custom code cid deployed by developer
     code_cids: Vec<Cid>,
+    // Accounts created by the tester
+    accounts: Vec<ActorID>,
     // Executor used to interact with deployed actors.
     pub executor: Option<IntegrationExecutor<B, E>>,
     // State tree constructed before instantiating the Machine
@@ -73,8 +100,8 @@ pub struct Tester<B: Blockstore + 'static, E: Externs + 'static> {
 
 impl<B, E> Tester<B, E>
 where
-    B: Blockstore,
-    E: Externs,
+    B: Blockstore + Clone,
+    E: Externs + Clone,
 {
     pub fn new(
         nv: NetworkVersion,
@@ -107,6 +134,7 @@ where
             builtin_actors,
             executor: None,
             code_cids: vec![],
+            accounts: vec![],
             state_tree: Some(state_tree),
             accounts_code_cid,
             placeholder_code_cid,
@@ -281,14 +309,14 @@ where
         // Custom configuration.
         configure_nc(&mut nc);
 
-        let mut mc = nc.for_epoch(0, 0, state_root);
+        let mut mc = nc.for_epoch(0, 0, state_root)?;
         mc.set_base_fee(TokenAmount::from_atto(DEFAULT_BASE_FEE))
             .enable_tracing();
 
         // Custom configuration.
         configure_mc(&mut mc);
 
-        let engine = EnginePool::new_default((&mc.network.clone()).into())?;
+        let engine = EnginePool::new_default((&mc).into())?;
         engine.acquire().preload(&blockstore, &self.code_cids)?;
 
         let machine = DefaultMachine::new(&mc, blockstore, externs)?;
@@ -304,6 +332,45 @@ where
         Ok(())
     }
 
+    /// Snapshots the current state: the state root, along with the sequences (nonces) of the
+    /// accounts created by the tester. Once the machine has been instantiated, its buffered blocks
+    /// are flushed to the blockstore so that the snapshot survives subsequent flushes.
+    ///
+    /// Restoring a snapshot (see [`Tester::restore`]) is much cheaper than building a new tester,
+    /// and lets tests branch from (and reset to) a common state.
+    pub fn snapshot(&mut self) -> Result<Snapshot> {
+        let (state_root, nonces) = match (self.executor.as_mut(), self.state_tree.as_mut()) {
+            (Some(executor), _) => (
+                Executor::flush(executor).context(FailedToFlushTree)?,
+                account_nonces(executor.state_tree(), &self.accounts)?,
+            ),
+            (None, Some(state_tree)) => (
+                state_tree
+                    .flush()
+                    .map_err(anyhow::Error::from)
+                    .context(FailedToFlushTree)?,
+                account_nonces(state_tree, &self.accounts)?,
+            ),
+            (None, None) => return Err(anyhow!("no state tree to snapshot")),
+        };
+        Ok(Snapshot { state_root, nonces })
+    }
+
+    /// Restores a snapshot taken with [`Tester::snapshot`], discarding all changes made since. The
+    /// same snapshot may be restored any number of times, and before or after the machine has been
+    /// instantiated.
+    ///
+    /// Only the state is restored: reset the sequences tracked by accounts from the snapshot (see
+    /// [`BasicAccount::restore`]).
+    pub fn restore(&mut self, snapshot: &Snapshot) -> Result<()> {
+        match (self.executor.as_mut(), self.state_tree.as_mut()) {
+            (Some(executor), _) => executor.state_tree_mut().set_root(&snapshot.state_root)?,
+            (None, Some(state_tree)) => state_tree.set_root(&snapshot.state_root)?,
+            (None, None) => return Err(anyhow!("no state tree to restore")),
+        }
+        Ok(())
+    }
+
     /// Get blockstore
     pub fn blockstore(&self) -> &dyn Blockstore {
         if self.executor.is_some() {
@@ -342,6 +409,7 @@ where
         };
 
         state_tree.set_actor(assigned_addr, actor_state);
+        self.accounts.push(assigned_addr);
         Ok((assigned_addr, pub_key_addr))
     }
 }
@@ -356,6 +424,16 @@ pub struct BasicAccount {
     pub seqno: u64,
 }
 
+impl BasicAccount {
+    /// Resets the account's sequence to its value in the snapshot (see [`Tester::snapshot`]).
+    pub fn restore(&mut self, snapshot: &Snapshot) -> Result<()> {
+        self.seqno = snapshot
+            .nonce(self.account.0)
+            .ok_or_else(|| anyhow!("account {} not in snapshot", self.account.0))?;
+        Ok(())
+    }
+}
+
 impl BasicTester {
     pub fn new_basic_tester(bundle_path: String, options: ExecutionOptions) -> Result<BasicTester> {
         let blockstore = MemoryBlockstore::default();
@@ -408,7 +486,7 @@ impl BasicTester {
                 self.instantiate_machine_with_config(
                     DummyExterns,
                     |cfg| cfg.actor_debugging = options.debug,
-                    |mc| mc.tracing = options.trace,
+                    |mc| mc.trace = options.trace,
                 )?;
             } else {
                 self.instantiate_machine(DummyExterns)?;
@@ -419,6 +497,22 @@ impl BasicTester {
     }
 }
 
+/// Returns the sequences (nonces) of the given accounts.
+fn account_nonces<S: Blockstore>(
+    state_tree: &StateTree<S>,
+    accounts: &[ActorID],
+) -> Result<BTreeMap<ActorID, u64>> {
+    accounts
+        .iter()
+        .map(|&id| {
+            let actor = state_tree
+                .get_actor(id)?
+                .ok_or_else(|| anyhow!("account {} not found", id))?;
+            Ok((id, actor.sequence))
+        })
+        .collect()
+}
+
 /// Inserts the WASM code for the actor into the blockstore.
 fn put_wasm_code(blockstore: &impl Blockstore, wasm_binary: &[u8]) -> Result<Cid> {
     let cid = blockstore.put(
@@ -40,7 +40,7 @@ fn basic_send() {
     }
 
     let cases = {
-        let pl = executor.context().price_list;
+        let pl = &executor.context().price_list;
         [
             // Create the actor.
             Case {
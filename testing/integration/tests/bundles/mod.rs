@@ -19,7 +19,7 @@ lazy_static! {
 }
 
 #[allow(dead_code)]
-pub fn new_tester<B: Blockstore, E: Externs>(
+pub fn new_tester<B: Blockstore + Clone, E: Externs + Clone>(
     nv: NetworkVersion,
     stv: StateTreeVersion,
     blockstore: B,
@@ -114,11 +114,16 @@ const NOP_ACTOR: &str = r#"
 
 // Utility function to instantiation integration tester
 pub fn instantiate_tester() -> TestEnv {
+    instantiate_tester_with_version(NetworkVersion::V18)
+}
+
+/// Instantiates the integration tester on the given network version, which must have a registered
+/// schedule.
+pub fn instantiate_tester_with_version(nv: NetworkVersion) -> TestEnv {
     let blockstore = MemoryBlockstore::default();
     let root = bundle::import_bundle(&blockstore, actors_v10::BUNDLE_CAR).unwrap();
     // Instantiate tester
-    let mut tester =
-        Tester::new(NetworkVersion::V18, StateTreeVersion::V5, root, blockstore).unwrap();
+    let mut tester = Tester::new(nv, StateTreeVersion::V5, root, blockstore).unwrap();
 
     let sender: [Account; 1] = tester.create_accounts().unwrap();
 
@@ -3,7 +3,7 @@
 mod bundles;
 use bundles::*;
 use fvm::executor::{ApplyKind, Executor};
-use fvm::machine::Machine;
+use fvm::machine::{ActorEventLimits, Machine, NetworkConfig};
 use fvm_integration_tests::dummy::DummyExterns;
 use fvm_integration_tests::tester::IntegrationExecutor;
 use fvm_ipld_blockstore::{Blockstore, MemoryBlockstore};
@@ -66,12 +66,27 @@ fn events_test() {
     assert_eq!(ExitCode::OK, res.msg_receipt.exit_code);
     assert!(res.msg_receipt.events_root.is_none());
 
+    // === Emits events exceeding the default limits ===
+
+    let message = Message {
+        method_num: 6,
+        sequence: 2,
+        ..message
+    };
+
+    let res = executor
+        .execute_message(message.clone(), ApplyKind::Explicit, 100)
+        .unwrap();
+
+    assert_eq!(ExitCode::OK, res.msg_receipt.exit_code);
+    assert!(res.msg_receipt.events_root.is_none());
+
     let counter: u64 = 10;
 
     // === Performs subcalls, each emitting 2 events and all succeeding ===
     let message = Message {
         method_num: 4,
-        sequence: 2,
+        sequence: 3,
         params: to_vec(&counter).unwrap().into(),
         ..message
     };
@@ -88,7 +103,7 @@ fn events_test() {
     // === Performs subcalls, each emitting 2 events and reverting ===
     let message = Message {
         method_num: 5,
-        sequence: 3,
+        sequence: 4,
         params: to_vec(&counter).unwrap().into(),
         ..message
     };
@@ -106,7 +121,7 @@ fn events_test() {
     // === Out of gas records no events ===
     let message = Message {
         method_num: 2,
-        sequence: 4,
+        sequence: 5,
         gas_limit: gas_used - 1,
         ..message
     };
@@ -119,10 +134,78 @@ fn events_test() {
     assert_eq!(0, res.events.len());
 }
 
+#[test]
+fn events_custom_limits() {
+    let (mut executor, sender_address, actor_address) = setup_with_config(|nc| {
+        nc.event_limits(ActorEventLimits {
+            max_key_len: 2,
+            ..Default::default()
+        });
+    });
+
+    // The events emitted by this method have 3 byte keys, which now exceed the limit.
+    let message = Message {
+        from: sender_address,
+        to: actor_address,
+        gas_limit: 1000000000,
+        method_num: 2,
+        sequence: 0,
+        ..Message::default()
+    };
+
+    let res = executor
+        .execute_message(message, ApplyKind::Explicit, 100)
+        .unwrap();
+
+    assert!(!res.msg_receipt.exit_code.is_success());
+    assert!(res.msg_receipt.events_root.is_none());
+    assert_eq!(0, res.events.len());
+}
+
+#[test]
+fn events_precise_errors() {
+    let (mut executor, sender_address, actor_address) = setup_with_config(|nc| {
+        nc.event_limits(ActorEventLimits {
+            precise_errors: true,
+            ..Default::default()
+        });
+    });
+
+    // Both methods assert the precise error numbers when passed `true`.
+    for (sequence, method_num) in [(0, 3), (1, 6)] {
+        let message = Message {
+            from: sender_address,
+            to: actor_address,
+            gas_limit: 1000000000,
+            method_num,
+            sequence,
+            params: to_vec(&true).unwrap().into(),
+            ..Message::default()
+        };
+
+        let res = executor
+            .execute_message(message, ApplyKind::Explicit, 100)
+            .unwrap();
+
+        assert_eq!(ExitCode::OK, res.msg_receipt.exit_code);
+        assert!(res.msg_receipt.events_root.is_none());
+    }
+}
+
 fn setup() -> (
     IntegrationExecutor<MemoryBlockstore, DummyExterns>,
     Address,
     Address,
+) {
+    setup_with_config(|_| ())
+}
+
+fn setup_with_config(
+    configure: impl FnOnce(&mut NetworkConfig),
+) -> (
+    IntegrationExecutor<MemoryBlockstore, DummyExterns>,
+    Address,
+    Address,
 ) {
     // Instantiate tester
     let mut tester = new_tester(
@@ -148,7 +231,9 @@ fn setup() -> (
         .unwrap();
 
     // Instantiate machine
-    tester.instantiate_machine(DummyExterns).unwrap();
+    tester
+        .instantiate_machine_with_config(DummyExterns, configure, |_| ())
+        .unwrap();
 
     let executor = tester.executor.unwrap();
     (executor, sender, actor)
@@ -0,0 +1,194 @@
+// Copyright 2021-2023 Protocol Labs
+// SPDX-License-Identifier: Apache-2.0, MIT
+mod bundles;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use bundles::*;
+use cid::Cid;
+use fvm::executor::{ApplyKind, Executor};
+use fvm::externs::{Chain, Consensus, Externs, Rand};
+use fvm::machine::NetworkConfig;
+use fvm_integration_tests::dummy::DummyExterns;
+use fvm_integration_tests::tester::Account;
+use fvm_ipld_blockstore::MemoryBlockstore;
+use fvm_ipld_encoding::tuple::*;
+use fvm_shared::address::Address;
+use fvm_shared::clock::ChainEpoch;
+use fvm_shared::consensus::ConsensusFault;
+use fvm_shared::econ::TokenAmount;
+use fvm_shared::error::ExitCode;
+use fvm_shared::message::Message;
+use fvm_shared::state::StateTreeVersion;
+use fvm_shared::version::NetworkVersion;
+use num_traits::Zero;
+
+/// The state object.
+#[derive(Serialize_tuple, Deserialize_tuple, Clone, Debug, Default)]
+pub struct State {
+    pub count: u64,
+}
+
+/// Number of calls made to the externs.
+#[derive(Default)]
+struct Counters {
+    tipset_lookups: AtomicUsize,
+    fault_verifications: AtomicUsize,
+}
+
+/// Externs counting the number of tipset CID lookups and consensus fault verifications.
+#[derive(Clone)]
+struct CountingExterns(Arc<Counters>);
+
+impl Externs for CountingExterns {}
+
+impl Rand for CountingExterns {
+    fn get_chain_randomness(&self, round: ChainEpoch) -> anyhow::Result<[u8; 32]> {
+        DummyExterns.get_chain_randomness(round)
+    }
+
+    fn get_beacon_randomness(&self, round: ChainEpoch) -> anyhow::Result<[u8; 32]> {
+        DummyExterns.get_beacon_randomness(round)
+    }
+
+    fn verify_beacon_entry(
+        &self,
+        round: u64,
+        prev_signature: &[u8],
+        signature: &[u8],
+    ) -> anyhow::Result<bool> {
+        DummyExterns.verify_beacon_entry(round, prev_signature, signature)
+    }
+}
+
+impl Consensus for CountingExterns {
+    fn verify_consensus_fault(
+        &self,
+        h1: &[u8],
+        h2: &[u8],
+        extra: &[u8],
+    ) -> anyhow::Result<(Option<ConsensusFault>, i64)> {
+        self.0.fault_verifications.fetch_add(1, Ordering::SeqCst);
+        DummyExterns.verify_consensus_fault(h1, h2, extra)
+    }
+}
+
+impl Chain for CountingExterns {
+    fn get_tipset_cid(&self, epoch: ChainEpoch) -> anyhow::Result<Cid> {
+        self.0.tipset_lookups.fetch_add(1, Ordering::SeqCst);
+        DummyExterns.get_tipset_cid(epoch)
+    }
+}
+
+// Looks up the tipset CID of epoch 9 twice.
+const WAT_TIPSET_CID: &str = r#"
+(module
+  (type (;0;) (func (param i32 i64 i32 i32) (result i32)))
+  (import "network" "tipset_cid" (func $tipset_cid (type 0)))
+  (memory (export "memory") 1)
+  (func (export "invoke") (param $x i32) (result i32)
+    (if (call $tipset_cid (i32.const 0) (i64.const 9) (i32.const 16) (i32.const 128))
+      (then unreachable))
+    (if (call $tipset_cid (i32.const 0) (i64.const 9) (i32.const 16) (i32.const 128))
+      (then unreachable))
+    (i32.const 0)))
+"#;
+
+// Verifies the same consensus fault three times.
+const WAT_CONSENSUS_FAULT: &str = r#"
+(module
+  (type (;0;) (func (param i32 i32 i32 i32 i32 i32 i32) (result i32)))
+  (import "crypto" "verify_consensus_fault" (func $verify_consensus_fault (type 0)))
+  (memory (export "memory") 1)
+  (data (i32.const 64) "header1")
+  (data (i32.const 80) "header2")
+  (func $verify (result i32)
+    (call $verify_consensus_fault
+      (i32.const 0)
+      (i32.const 64) (i32.const 7)
+      (i32.const 80) (i32.const 7)
+      (i32.const 0) (i32.const 0)))
+  (func (export "invoke") (param $x i32) (result i32)
+    (if (call $verify) (then unreachable))
+    (if (call $verify) (then unreachable))
+    (if (call $verify) (then unreachable))
+    (i32.const 0)))
+"#;
+
+/// Executes `messages` messages against an actor running the given wat, returning the exit codes.
+fn run(
+    wat: &str,
+    messages: u64,
+    configure: impl FnOnce(&mut NetworkConfig),
+) -> (Vec<ExitCode>, Arc<Counters>) {
+    let mut tester = new_tester(
+        NetworkVersion::V18,
+        StateTreeVersion::V5,
+        MemoryBlockstore::default(),
+    )
+    .unwrap();
+
+    let sender: [Account; 1] = tester.create_accounts().unwrap();
+
+    let wasm_bin = wat::parse_str(wat).unwrap();
+    let state_cid = tester.set_state(&State::default()).unwrap();
+    let actor_address = Address::new_id(10000);
+    tester
+        .set_actor_from_bin(&wasm_bin, state_cid, actor_address, TokenAmount::zero())
+        .unwrap();
+
+    let counters = Arc::new(Counters::default());
+    tester
+        .instantiate_machine_with_config(CountingExterns(counters.clone()), configure, |mc| {
+            mc.epoch = 10
+        })
+        .unwrap();
+
+    let mut executor = tester.executor.unwrap();
+    let exit_codes = (0..messages)
+        .map(|sequence| {
+            let message = Message {
+                from: sender[0].1,
+                to: actor_address,
+                gas_limit: 1_000_000_000,
+                method_num: 1,
+                sequence,
+                ..Message::default()
+            };
+
+            executor
+                .execute_message(message, ApplyKind::Explicit, 100)
+                .unwrap()
+                .msg_receipt
+                .exit_code
+        })
+        .collect();
+    (exit_codes, counters)
+}
+
+#[test]
+fn tipset_cid_cached() {
+    let (exit_codes, counters) = run(WAT_TIPSET_CID, 2, |_| ());
+    assert_eq!(exit_codes, vec![ExitCode::OK; 2]);
+
+    // Four lookups across two messages, but only one hits the externs.
+    assert_eq!(counters.tipset_lookups.load(Ordering::SeqCst), 1);
+}
+
+#[test]
+fn consensus_fault_cached() {
+    let (exit_codes, counters) = run(WAT_CONSENSUS_FAULT, 2, |_| ());
+    assert_eq!(exit_codes, vec![ExitCode::OK; 2]);
+
+    // Six verifications across two messages, but only one hits the externs.
+    assert_eq!(counters.fault_verifications.load(Ordering::SeqCst), 1);
+}
+
+#[test]
+fn consensus_fault_limit() {
+    // The third verification exceeds the limit and fails, aborting each message.
+    let (exit_codes, _) = run(WAT_CONSENSUS_FAULT, 2, |nc| {
+        nc.max_consensus_fault_verifications(2);
+    });
+    assert_eq!(exit_codes, vec![ExitCode::SYS_ILLEGAL_INSTRUCTION; 2]);
+}
@@ -368,19 +368,31 @@ fn on_send() {
 #[cfg(feature = "calibration")]
 fn on_verify_signature() {
     use bls_signatures::Serialize;
+    use fvm::machine::NetworkSchedule;
     use fvm_shared::address::Address;
+    use fvm_shared::crypto::signature::ops::{eth_address_of, keccak256};
     use fvm_shared::crypto::signature::SignatureType;
+    use fvm_shared::version::NetworkVersion;
     use rand::{thread_rng, Rng, RngCore};
 
     const CHARGE_NAME: &str = "OnVerifySignature";
     const METHOD: Method = Method::OnVerifySignature;
 
-    let sig_types = vec![SignatureType::BLS, SignatureType::Secp256k1];
+    let sig_types = vec![
+        SignatureType::BLS,
+        SignatureType::Secp256k1,
+        SignatureType::Delegated,
+    ];
 
     let sizes = common_sizes();
     let iterations = 100;
 
-    let mut te = instantiate_tester();
+    // Delegated signatures are only supported from network version 21.
+    NetworkSchedule::register(
+        NetworkVersion::V21,
+        NetworkSchedule::builtin(NetworkVersion::V21).unwrap(),
+    );
+    let mut te = instantiate_tester_with_version(NetworkVersion::V21);
     let mut obs = Vec::new();
     let mut rng = thread_rng();
 
@@ -408,6 +420,16 @@ fn on_verify_signature() {
                 let sig = sk.sign(&data).as_bytes();
                 (addr, sig)
             }
+            SignatureType::Delegated => {
+                let sk = libsecp256k1::SecretKey::random(&mut rng);
+                let pk = libsecp256k1::PublicKey::from_secret_key(&sk);
+                let addr = eth_address_of(&pk).to_address();
+                let msg = libsecp256k1::Message::parse(&keccak256(&data));
+                let (sig, recovery_id) = libsecp256k1::sign(&msg, &sk);
+                let mut sig = sig.serialize().to_vec();
+                sig.push(recovery_id.serialize());
+                (addr, sig)
+            }
         };
 
         for size in sizes.iter() {
@@ -6,7 +6,7 @@ mod bundles;
 use std::fs;
 
 use anyhow::Context;
-use fvm::trace::{ExecutionEvent, ExecutionTrace};
+use fvm::trace::{ExecutionEvent, ExecutionTrace, TraceConfig};
 use fvm_integration_tests::{tester, testkit};
 use fvm_shared::address::Address;
 use fvm_shared::error::ExitCode;
@@ -35,7 +35,7 @@ fn gasfuzz_fuzz(charge_points_milligas: Vec<u64>) {
     // set up the tester
     let options = tester::ExecutionOptions {
         debug: false,
-        trace: false,
+        trace: TraceConfig::default(),
         events: false,
     };
 
@@ -77,7 +77,10 @@ fn gasfuzz_fuzz(charge_points_milligas: Vec<u64>) {
 fn gasfuzz_get_exec_trace() -> ExecutionTrace {
     let options = tester::ExecutionOptions {
         debug: false,
-        trace: true,
+        trace: TraceConfig {
+            gas: Some(usize::MAX),
+            ..Default::default()
+        },
         events: false,
     };
 
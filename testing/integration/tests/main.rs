@@ -3,10 +3,22 @@
 use std::cell::RefCell;
 use std::collections::HashSet;
 use std::rc::Rc;
+use std::time::Duration;
 
 use anyhow::anyhow;
 use cid::Cid;
-use fvm::executor::{ApplyKind, Executor, ThreadedExecutor};
+use fvm::call_manager::DebugArtifact;
+use fvm::engine::{EnginePool, ExecutionBudgetExceeded};
+use fvm::executor::{
+    ActorOverride, ApplyKind, ChainMessage, EpochJobs, EstimateOptions, Executor, ExecutorEvents,
+    ImplicitCall, MessageStats, SenderRejection, SenderValidator, StateOverrides, ThreadedExecutor,
+    TipsetBlock,
+};
+use fvm::gas::{price_list_by_network_version, FeeDistribution, Gas, GasChargeName};
+use fvm::machine::{
+    ExitCodePolicy, Machine, NetworkConfig, SelfDestructPolicy, BURNT_FUNDS_ACTOR_ID,
+};
+use fvm::trace::{ExecutionEvent, TraceCategory, TraceConfig};
 use fvm_integration_tests::dummy::DummyExterns;
 use fvm_integration_tests::tester::{Account, IntegrationExecutor};
 use fvm_ipld_blockstore::{Blockstore, MemoryBlockstore};
@@ -27,7 +39,7 @@ use num_traits::Zero;
 mod bundles;
 use bundles::*;
 use fvm_shared::chainid::ChainID;
-use fvm_shared::ActorID;
+use fvm_shared::{ActorID, BLOCK_GAS_LIMIT};
 
 /// The state object.
 #[derive(Serialize_tuple, Deserialize_tuple, Clone, Debug, Default)]
@@ -165,6 +177,8 @@ fn syscalls() {
             DummyExterns,
             |nc| {
                 nc.chain_id = ChainID::from(1);
+                // Enable the syscalls introduced in nv21.
+                nc.disabled_syscalls.clear();
             },
             |_| {},
         )
@@ -193,6 +207,33 @@ fn syscalls() {
             panic!("non-zero exit code {}", res.msg_receipt.exit_code)
         }
     }
+
+    // Beacon entry verifications (valid or not) are priced by the length of both signatures.
+    let expected = price_list_by_network_version(NetworkVersion::V18).on_verify_beacon_entry(0, 96);
+    let charges: Vec<_> = res
+        .exec_trace
+        .iter()
+        .filter_map(|evt| match evt {
+            ExecutionEvent::GasCharge(charge)
+                if charge.name == GasChargeName::OnVerifyBeaconEntry =>
+            {
+                Some(charge.clone())
+            }
+            _ => None,
+        })
+        .collect();
+    assert_eq!(charges, vec![expected.clone(), expected]);
+
+    // Each block's links are only scanned (and charged for) once, no matter how many pages are
+    // read: the test reads the links of three distinct blocks.
+    let link_scans = res
+        .exec_trace
+        .iter()
+        .filter(|evt| {
+            matches!(evt, ExecutionEvent::GasCharge(charge) if charge.name == GasChargeName::OnBlockLinks)
+        })
+        .count();
+    assert_eq!(link_scans, 3);
 }
 
 #[test]
@@ -542,6 +583,14 @@ fn native_stack_overflow() {
 }
 
 fn test_exitcode(wat: &str, code: ExitCode) {
+    test_exitcode_with_config(wat, code, |_| ())
+}
+
+fn test_exitcode_with_config(
+    wat: &str,
+    code: ExitCode,
+    configure: impl FnOnce(&mut NetworkConfig),
+) {
     // Instantiate tester
     let mut tester = new_tester(
         NetworkVersion::V18,
@@ -567,7 +616,9 @@ fn test_exitcode(wat: &str, code: ExitCode) {
         .unwrap();
 
     // Instantiate machine
-    tester.instantiate_machine(DummyExterns).unwrap();
+    tester
+        .instantiate_machine_with_config(DummyExterns, configure, |_| ())
+        .unwrap();
 
     // Send message
     let message = Message {
@@ -599,44 +650,1892 @@ fn out_of_gas() {
 }
 
 #[test]
-fn unreachable() {
-    test_exitcode(
+fn out_of_gas_checkpoint() {
+    let mut tester = new_tester(
+        NetworkVersion::V18,
+        StateTreeVersion::V5,
+        MemoryBlockstore::default(),
+    )
+    .unwrap();
+
+    let sender: [Account; 1] = tester.create_accounts().unwrap();
+
+    let wasm_bin = wat::parse_str(
         r#"(module
              (memory (export "memory") 1)
              (func (export "invoke") (param $x i32) (result i32)
-               unreachable))"#,
-        ExitCode::SYS_ILLEGAL_INSTRUCTION,
-    );
+               (loop (br 0))
+               (i32.const 1)))"#,
+    )
+    .unwrap();
+    let state_cid = tester.set_state(&State { count: 0 }).unwrap();
+    let actor_address = Address::new_id(10000);
+    tester
+        .set_actor_from_bin(&wasm_bin, state_cid, actor_address, TokenAmount::zero())
+        .unwrap();
+
+    tester
+        .instantiate_machine_with_config(
+            DummyExterns,
+            |nc| {
+                nc.enable_actor_debugging();
+            },
+            |_| (),
+        )
+        .unwrap();
+
+    let message = Message {
+        from: sender[0].1,
+        to: actor_address,
+        gas_limit: 10_000_000,
+        method_num: 1,
+        ..Message::default()
+    };
+
+    let res = tester
+        .executor
+        .unwrap()
+        .execute_message(message, ApplyKind::Explicit, 100)
+        .unwrap();
+    assert_eq!(res.msg_receipt.exit_code, ExitCode::SYS_OUT_OF_GAS);
+
+    let checkpoint = res.out_of_gas.expect("expected an out of gas checkpoint");
+    assert_eq!(checkpoint.actor, 10000);
+    assert_eq!(checkpoint.method, 1);
+    let last = checkpoint.recent_charges.last().expect("expected charges");
+    assert_eq!(last.name, "wasm_exec");
+    assert!(checkpoint.wasm_backtrace.is_some());
 }
 
 #[test]
-fn div_by_zero() {
-    test_exitcode(
+fn execution_budget_exceeded() {
+    let mut tester = new_tester(
+        NetworkVersion::V18,
+        StateTreeVersion::V5,
+        MemoryBlockstore::default(),
+    )
+    .unwrap();
+
+    let sender: [Account; 1] = tester.create_accounts().unwrap();
+
+    let wasm_bin = wat::parse_str(
         r#"(module
              (memory (export "memory") 1)
              (func (export "invoke") (param $x i32) (result i32)
-               i32.const 10
-               i32.const 0
-               i32.div_u))"#,
-        ExitCode::SYS_ILLEGAL_INSTRUCTION,
+               (loop (br 0))
+               (i32.const 1)))"#,
+    )
+    .unwrap();
+    let state_cid = tester.set_state(&State { count: 0 }).unwrap();
+    let actor_address = Address::new_id(10000);
+    tester
+        .set_actor_from_bin(&wasm_bin, state_cid, actor_address, TokenAmount::zero())
+        .unwrap();
+
+    tester
+        .instantiate_machine_with_config(
+            DummyExterns,
+            |_| (),
+            |mc| {
+                mc.set_execution_budget(Duration::from_millis(50));
+            },
+        )
+        .unwrap();
+
+    // Enough gas to loop for far longer than the budget.
+    let message = Message {
+        from: sender[0].1,
+        to: actor_address,
+        gas_limit: BLOCK_GAS_LIMIT,
+        method_num: 1,
+        ..Message::default()
+    };
+
+    let mut executor = tester.executor.unwrap();
+
+    // The budget can't be enforced by engines compiled without epoch interruption.
+    let forked = executor.fork().unwrap();
+    let engine = EnginePool::new_default((&forked.context().network).into()).unwrap();
+    assert!(!engine.config().epoch_interruption);
+    assert!(IntegrationExecutor::new(engine, forked).is_err());
+
+    let mut executor = ThreadedExecutor(executor);
+    let err = executor
+        .execute_message(message, ApplyKind::Explicit, 100)
+        .expect_err("expected the execution budget to be exceeded");
+    assert!(err.downcast_ref::<ExecutionBudgetExceeded>().is_some());
+}
+
+#[test]
+fn estimate_gas() {
+    let mut tester = new_tester(
+        NetworkVersion::V18,
+        StateTreeVersion::V5,
+        MemoryBlockstore::default(),
+    )
+    .unwrap();
+
+    let sender: [Account; 1] = tester.create_accounts().unwrap();
+
+    let wasm_bin = wat::parse_str(
+        r#"(module
+             (memory (export "memory") 1)
+             (func (export "invoke") (param $x i32) (result i32)
+               (i32.const 0)))"#,
+    )
+    .unwrap();
+    let state_cid = tester.set_state(&State { count: 0 }).unwrap();
+    let actor_address = Address::new_id(10000);
+    tester
+        .set_actor_from_bin(&wasm_bin, state_cid, actor_address, TokenAmount::zero())
+        .unwrap();
+
+    tester.instantiate_machine(DummyExterns).unwrap();
+    let mut executor = tester.executor.unwrap();
+
+    // The sender can't afford this fee cap, but estimation ignores gas fees.
+    let message = Message {
+        from: sender[0].1,
+        to: actor_address,
+        gas_limit: BLOCK_GAS_LIMIT,
+        gas_fee_cap: TokenAmount::from_whole(1_000_000),
+        method_num: 1,
+        ..Message::default()
+    };
+
+    let estimate = executor
+        .estimate_gas(
+            message.clone(),
+            100,
+            &EstimateOptions {
+                search: true,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+    assert_eq!(estimate.apply_ret.msg_receipt.exit_code, ExitCode::OK);
+    assert!(estimate.apply_ret.fees.refund.is_zero());
+    assert!(estimate.gas_used > 0);
+    assert_eq!(estimate.min_gas_limit, estimate.gas_used);
+    assert_eq!(
+        estimate.suggested_gas_limit,
+        estimate.min_gas_limit + estimate.min_gas_limit / 4
+    );
+
+    // Estimation doesn't change the state: the nonce is unchanged, and the message fails with the
+    // estimated gas limit minus one.
+    let res = executor
+        .execute_message(
+            Message {
+                gas_limit: estimate.min_gas_limit - 1,
+                gas_fee_cap: TokenAmount::zero(),
+                ..message.clone()
+            },
+            ApplyKind::Explicit,
+            100,
+        )
+        .unwrap();
+    assert_eq!(res.msg_receipt.exit_code, ExitCode::SYS_OUT_OF_GAS);
+
+    // An explicit message with the suggested gas limit but an unaffordable fee cap still fails.
+    let res = executor
+        .execute_message(
+            Message {
+                gas_limit: estimate.suggested_gas_limit,
+                sequence: 1,
+                ..message
+            },
+            ApplyKind::Explicit,
+            100,
+        )
+        .unwrap();
+    assert_eq!(
+        res.msg_receipt.exit_code,
+        ExitCode::SYS_SENDER_STATE_INVALID
     );
 }
 
 #[test]
-fn out_of_stack() {
-    test_exitcode(
+fn fee_breakdown() {
+    let mut tester = new_tester(
+        NetworkVersion::V18,
+        StateTreeVersion::V5,
+        MemoryBlockstore::default(),
+    )
+    .unwrap();
+
+    let sender = tester
+        .make_secp256k1_account(
+            libsecp256k1::SecretKey::random(&mut rand::thread_rng()),
+            TokenAmount::from_whole(1),
+        )
+        .unwrap();
+
+    let wasm_bin = wat::parse_str(
         r#"(module
              (memory (export "memory") 1)
              (func (export "invoke") (param $x i32) (result i32)
-               (i64.const 123)
-               (call 1)
-               (drop)
-               (i32.const 0))
-             (func (param $x i64) (result i64)
-               (local.get 0)
-               (call 1)))"#,
-        ExitCode::SYS_ILLEGAL_INSTRUCTION,
+               (i32.const 0)))"#,
+    )
+    .unwrap();
+    let state_cid = tester.set_state(&State { count: 0 }).unwrap();
+    let actor_address = Address::new_id(10000);
+    tester
+        .set_actor_from_bin(&wasm_bin, state_cid, actor_address, TokenAmount::zero())
+        .unwrap();
+
+    tester
+        .instantiate_machine_with_config(
+            DummyExterns,
+            |_| (),
+            |mc| {
+                mc.set_base_fee(TokenAmount::from_atto(100));
+            },
+        )
+        .unwrap();
+
+    let gas_limit = 1_000_000_000;
+    let message = Message {
+        from: sender.1,
+        to: actor_address,
+        gas_limit,
+        gas_fee_cap: TokenAmount::from_atto(150),
+        gas_premium: TokenAmount::from_atto(10),
+        method_num: 1,
+        ..Message::default()
+    };
+
+    let res = tester
+        .executor
+        .unwrap()
+        .execute_message(message, ApplyKind::Explicit, 100)
+        .unwrap();
+    assert_eq!(res.msg_receipt.exit_code, ExitCode::OK);
+
+    let fees = &res.fees;
+    assert_eq!(fees.gas_limit, gas_limit);
+    assert_eq!(fees.gas_used, res.msg_receipt.gas_used);
+    assert_eq!(fees.gas_refund + fees.gas_burned + fees.gas_used, gas_limit);
+    assert_eq!(
+        fees.base_fee_burn,
+        TokenAmount::from_atto(100) * fees.gas_used
+    );
+    assert_eq!(
+        fees.over_estimation_burn,
+        TokenAmount::from_atto(100) * fees.gas_burned
+    );
+    assert_eq!(fees.miner_tip, TokenAmount::from_atto(10) * gas_limit);
+    assert!(fees.miner_penalty.is_zero());
+    assert_eq!(
+        fees.total_paid() + &fees.refund,
+        TokenAmount::from_atto(150) * gas_limit
     );
+
+    // The breakdown matches the individual fee fields.
+    assert_eq!(fees.base_fee_burn, res.base_fee_burn);
+    assert_eq!(fees.over_estimation_burn, res.over_estimation_burn);
+    assert_eq!(fees.miner_tip, res.miner_tip);
+    assert_eq!(fees.miner_penalty, res.penalty);
+    assert_eq!(fees.refund, res.refund);
+    assert_eq!(fees.gas_refund, res.gas_refund);
+    assert_eq!(fees.gas_burned, res.gas_burned);
+}
+
+#[test]
+fn call_gas() {
+    let mut tester = new_tester(
+        NetworkVersion::V18,
+        StateTreeVersion::V5,
+        MemoryBlockstore::default(),
+    )
+    .unwrap();
+
+    let sender: [Account; 1] = tester.create_accounts().unwrap();
+
+    let wasm_bin = wat::parse_str(
+        r#"(module
+             (memory (export "memory") 1)
+             (func (export "invoke") (param $x i32) (result i32)
+               (i32.const 0)))"#,
+    )
+    .unwrap();
+    let state_cid = tester.set_state(&State { count: 0 }).unwrap();
+    let actor_address = Address::new_id(10000);
+    tester
+        .set_actor_from_bin(&wasm_bin, state_cid, actor_address, TokenAmount::zero())
+        .unwrap();
+
+    tester.instantiate_machine(DummyExterns).unwrap();
+
+    let message = Message {
+        from: sender[0].1,
+        to: actor_address,
+        gas_limit: 1_000_000_000,
+        method_num: 1,
+        ..Message::default()
+    };
+
+    let res = tester
+        .executor
+        .unwrap()
+        .execute_message(message, ApplyKind::Explicit, 100)
+        .unwrap();
+    assert_eq!(res.msg_receipt.exit_code, ExitCode::OK);
+
+    // The call is attributed all gas except for the message inclusion gas.
+    let call_gas = res.call_gas.expect("expected call gas when tracing");
+    assert_eq!(call_gas.to, actor_address);
+    assert_eq!(call_gas.method, 1);
+    assert!(call_gas.children.is_empty());
+    assert!(!call_gas.local.is_zero());
+    assert!(call_gas.total() < Gas::new(res.msg_receipt.gas_used));
+}
+
+#[test]
+fn trace_config() {
+    let mut tester = new_tester(
+        NetworkVersion::V18,
+        StateTreeVersion::V5,
+        MemoryBlockstore::default(),
+    )
+    .unwrap();
+
+    let sender: [Account; 1] = tester.create_accounts().unwrap();
+
+    let wasm_bin = wat::parse_str(
+        r#"(module
+             (type (;0;) (func (param i32) (result i32)))
+             (import "self" "current_balance" (func $current_balance (type 0)))
+             (memory (export "memory") 1)
+             (func (export "invoke") (param $x i32) (result i32)
+               (drop (call $current_balance (i32.const 0)))
+               (drop (call $current_balance (i32.const 0)))
+               (i32.const 0)))"#,
+    )
+    .unwrap();
+    let state_cid = tester.set_state(&State { count: 0 }).unwrap();
+    let actor_address = Address::new_id(10000);
+    tester
+        .set_actor_from_bin(&wasm_bin, state_cid, actor_address, TokenAmount::zero())
+        .unwrap();
+
+    // Trace syscalls (at most one) and state accesses only.
+    tester
+        .instantiate_machine_with_config(
+            DummyExterns,
+            |_| (),
+            |mc| {
+                mc.set_trace_config(TraceConfig {
+                    syscalls: Some(1),
+                    state_access: Some(usize::MAX),
+                    ..Default::default()
+                });
+            },
+        )
+        .unwrap();
+
+    let message = Message {
+        from: sender[0].1,
+        to: actor_address,
+        gas_limit: 1_000_000_000,
+        method_num: 1,
+        ..Message::default()
+    };
+
+    let res = tester
+        .executor
+        .unwrap()
+        .execute_message(message, ApplyKind::Explicit, 100)
+        .unwrap();
+    assert_eq!(res.msg_receipt.exit_code, ExitCode::OK);
+
+    let syscalls: Vec<_> = res
+        .exec_trace
+        .iter()
+        .filter_map(|e| match e {
+            ExecutionEvent::Syscall { module, name } => Some((*module, *name)),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(syscalls, [("self", "current_balance")]);
+    assert!(res
+        .exec_trace
+        .iter()
+        .any(|e| matches!(e, ExecutionEvent::StateAccess { actor, .. } if *actor == 10000)));
+    assert!(res.exec_trace.iter().all(|e| matches!(
+        e.category(),
+        TraceCategory::Syscalls | TraceCategory::StateAccess
+    )));
+    assert!(res.call_gas.is_none());
+}
+
+#[test]
+fn savepoint() {
+    let mut tester = new_tester(
+        NetworkVersion::V18,
+        StateTreeVersion::V5,
+        MemoryBlockstore::default(),
+    )
+    .unwrap();
+
+    let [(sender_id, sender), (receiver_id, receiver)] = tester.create_accounts().unwrap();
+
+    tester.instantiate_machine(DummyExterns).unwrap();
+    let mut executor = tester.executor.unwrap();
+
+    type Exec = IntegrationExecutor<MemoryBlockstore, DummyExterns>;
+    let apply_transfers = |executor: &mut Exec| {
+        for sequence in 0..2 {
+            let message = Message {
+                from: sender,
+                to: receiver,
+                sequence,
+                value: TokenAmount::from_atto(100),
+                gas_limit: 1_000_000_000,
+                ..Message::default()
+            };
+            let res = executor
+                .execute_message(message, ApplyKind::Explicit, 100)
+                .unwrap();
+            assert_eq!(res.msg_receipt.exit_code, ExitCode::OK);
+        }
+    };
+    let actor = |executor: &Exec, id| executor.state_tree().get_actor(id).unwrap().unwrap();
+
+    // Apply two messages, then revert them both.
+    let savepoint = executor.savepoint();
+    apply_transfers(&mut executor);
+    assert_eq!(actor(&executor, sender_id).sequence, 2);
+    assert!(executor.flush().is_err());
+    executor.rollback(savepoint).unwrap();
+
+    assert_eq!(actor(&executor, sender_id).sequence, 0);
+    assert_eq!(
+        actor(&executor, receiver_id).balance,
+        TokenAmount::from_atto(10000)
+    );
+
+    // Apply them again, and keep them.
+    let savepoint = executor.savepoint();
+    apply_transfers(&mut executor);
+    executor.commit(savepoint).unwrap();
+    executor.flush().unwrap();
+
+    assert_eq!(actor(&executor, sender_id).sequence, 2);
+    assert_eq!(
+        actor(&executor, receiver_id).balance,
+        TokenAmount::from_atto(10200)
+    );
+}
+
+#[test]
+fn query() {
+    let mut tester = new_tester(
+        NetworkVersion::V18,
+        StateTreeVersion::V5,
+        MemoryBlockstore::default(),
+    )
+    .unwrap();
+
+    let sender: [Account; 1] = tester.create_accounts().unwrap();
+
+    let wasm_bin = wat::parse_str(
+        r#"(module
+             (memory (export "memory") 1)
+             (func (export "invoke") (param $x i32) (result i32)
+               (i32.const 0)))"#,
+    )
+    .unwrap();
+    let state_cid = tester.set_state(&State { count: 0 }).unwrap();
+    let actor_address = Address::new_id(10000);
+    tester
+        .set_actor_from_bin(&wasm_bin, state_cid, actor_address, TokenAmount::zero())
+        .unwrap();
+
+    tester.instantiate_machine(DummyExterns).unwrap();
+    let mut executor = tester.executor.unwrap();
+
+    // Queries ignore the nonce and the sender's balance.
+    let message = Message {
+        from: sender[0].1,
+        to: actor_address,
+        gas_limit: BLOCK_GAS_LIMIT,
+        gas_fee_cap: TokenAmount::from_whole(1_000_000),
+        method_num: 1,
+        sequence: 42,
+        ..Message::default()
+    };
+    let res = executor
+        .execute_message(message.clone(), ApplyKind::Query, 100)
+        .unwrap();
+    assert_eq!(res.msg_receipt.exit_code, ExitCode::OK);
+    assert!(res.fees.total_paid().is_zero());
+
+    // Queries can't transfer value.
+    let res = executor
+        .execute_message(
+            Message {
+                value: TokenAmount::from_atto(1),
+                ..message
+            },
+            ApplyKind::Query,
+            100,
+        )
+        .unwrap();
+    assert_eq!(res.msg_receipt.exit_code, ExitCode::USR_READ_ONLY);
+
+    // The sender's nonce is unchanged.
+    let res = executor
+        .execute_message(
+            Message {
+                from: sender[0].1,
+                to: actor_address,
+                gas_limit: 1_000_000_000,
+                method_num: 1,
+                ..Message::default()
+            },
+            ApplyKind::Explicit,
+            100,
+        )
+        .unwrap();
+    assert_eq!(res.msg_receipt.exit_code, ExitCode::OK);
+}
+
+#[test]
+fn query_unmetered() {
+    let mut tester = new_tester(
+        NetworkVersion::V18,
+        StateTreeVersion::V5,
+        MemoryBlockstore::default(),
+    )
+    .unwrap();
+
+    let sender: [Account; 1] = tester.create_accounts().unwrap();
+
+    // Charges twice the block gas limit.
+    let wasm_bin = wat::parse_str(
+        r#"(module
+             (import "gas" "charge" (func $charge (param i32 i32 i64) (result i32)))
+             (memory (export "memory") 1)
+             (data (i32.const 0) "expensive")
+             (func (export "invoke") (param $x i32) (result i32)
+               (drop (call $charge (i32.const 0) (i32.const 9) (i64.const 20000000000)))
+               (i32.const 0)))"#,
+    )
+    .unwrap();
+    let state_cid = tester.set_state(&State { count: 0 }).unwrap();
+    let actor_address = Address::new_id(10000);
+    tester
+        .set_actor_from_bin(&wasm_bin, state_cid, actor_address, TokenAmount::zero())
+        .unwrap();
+
+    tester.instantiate_machine(DummyExterns).unwrap();
+    let mut executor = tester.executor.unwrap();
+
+    let message = Message {
+        from: sender[0].1,
+        to: actor_address,
+        gas_limit: BLOCK_GAS_LIMIT,
+        method_num: 1,
+        ..Message::default()
+    };
+
+    // The query succeeds, even though it uses more than the block gas limit.
+    let res = executor
+        .execute_message(message.clone(), ApplyKind::Query, 100)
+        .unwrap();
+    assert_eq!(res.msg_receipt.exit_code, ExitCode::OK);
+    assert!(res.msg_receipt.gas_used > BLOCK_GAS_LIMIT);
+
+    // The same message runs out of gas when metered.
+    let res = executor
+        .execute_message(message, ApplyKind::Implicit, 100)
+        .unwrap();
+    assert_eq!(res.msg_receipt.exit_code, ExitCode::SYS_OUT_OF_GAS);
+}
+
+#[test]
+fn dry_run() {
+    let mut tester = new_tester(
+        NetworkVersion::V18,
+        StateTreeVersion::V5,
+        MemoryBlockstore::default(),
+    )
+    .unwrap();
+
+    let sender: [Account; 1] = tester.create_accounts().unwrap();
+
+    let wasm_bin = wat::parse_str(
+        r#"(module
+             (memory (export "memory") 1)
+             (func (export "invoke") (param $x i32) (result i32)
+               (i32.const 0)))"#,
+    )
+    .unwrap();
+    let state_cid = tester.set_state(&State { count: 0 }).unwrap();
+    let actor_address = Address::new_id(10000);
+    tester
+        .set_actor_from_bin(&wasm_bin, state_cid, actor_address, TokenAmount::zero())
+        .unwrap();
+
+    tester.instantiate_machine(DummyExterns).unwrap();
+    let mut executor = tester.executor.unwrap();
+
+    // The sender can't afford this message, and the nonce is wrong.
+    let message = Message {
+        from: sender[0].1,
+        to: actor_address,
+        gas_limit: 1_000_000_000,
+        gas_fee_cap: TokenAmount::from_atto(1000),
+        method_num: 1,
+        sequence: 5,
+        ..Message::default()
+    };
+    let res = executor
+        .dry_run(
+            message.clone(),
+            ApplyKind::Explicit,
+            100,
+            &StateOverrides::new(),
+        )
+        .unwrap();
+    assert_eq!(
+        res.msg_receipt.exit_code,
+        ExitCode::SYS_SENDER_STATE_INVALID
+    );
+
+    let overrides: StateOverrides = [(
+        sender[0].1,
+        ActorOverride {
+            balance: Some(TokenAmount::from_whole(1_000)),
+            sequence: Some(5),
+            ..Default::default()
+        },
+    )]
+    .into_iter()
+    .collect();
+    let res = executor
+        .dry_run(message, ApplyKind::Explicit, 100, &overrides)
+        .unwrap();
+    assert_eq!(res.msg_receipt.exit_code, ExitCode::OK);
+    assert!(!res.fees.total_paid().is_zero());
+
+    // Nothing was persisted.
+    let actor = executor
+        .state_tree()
+        .get_actor(sender[0].0)
+        .unwrap()
+        .unwrap();
+    assert_eq!(actor.balance, TokenAmount::from_atto(10000));
+    assert_eq!(actor.sequence, 0);
+
+    // Overridden actors must exist.
+    let overrides: StateOverrides = [(Address::new_id(9999), ActorOverride::default())]
+        .into_iter()
+        .collect();
+    assert!(executor
+        .dry_run(
+            Message {
+                from: sender[0].1,
+                to: actor_address,
+                gas_limit: 1_000_000_000,
+                method_num: 1,
+                ..Message::default()
+            },
+            ApplyKind::Explicit,
+            100,
+            &overrides
+        )
+        .is_err());
+}
+
+#[test]
+fn base_fee_schedule() {
+    let mut tester = new_tester(
+        NetworkVersion::V18,
+        StateTreeVersion::V5,
+        MemoryBlockstore::default(),
+    )
+    .unwrap();
+
+    let sender = tester
+        .make_secp256k1_account(
+            libsecp256k1::SecretKey::random(&mut rand::thread_rng()),
+            TokenAmount::from_whole(1),
+        )
+        .unwrap();
+
+    let wasm_bin = wat::parse_str(
+        r#"(module
+             (memory (export "memory") 1)
+             (func (export "invoke") (param $x i32) (result i32)
+               (i32.const 0)))"#,
+    )
+    .unwrap();
+    let state_cid = tester.set_state(&State { count: 0 }).unwrap();
+    let actor_address = Address::new_id(10000);
+    tester
+        .set_actor_from_bin(&wasm_bin, state_cid, actor_address, TokenAmount::zero())
+        .unwrap();
+
+    // The schedule takes precedence over the static base fee.
+    tester
+        .instantiate_machine_with_config(
+            DummyExterns,
+            |_| (),
+            |mc| {
+                mc.epoch = 10;
+                mc.set_base_fee_schedule(|epoch| TokenAmount::from_atto(epoch * 20));
+            },
+        )
+        .unwrap();
+
+    let executor = tester.executor.as_mut().unwrap();
+    assert_eq!(executor.context().base_fee, TokenAmount::from_atto(200));
+
+    let message = Message {
+        from: sender.1,
+        to: actor_address,
+        gas_limit: 1_000_000_000,
+        gas_fee_cap: TokenAmount::from_atto(300),
+        method_num: 1,
+        ..Message::default()
+    };
+
+    let res = executor
+        .execute_message(message, ApplyKind::Explicit, 100)
+        .unwrap();
+    assert_eq!(res.msg_receipt.exit_code, ExitCode::OK);
+    assert_eq!(
+        res.fees.base_fee_burn,
+        TokenAmount::from_atto(200) * res.fees.gas_used
+    );
+}
+
+#[test]
+fn apply_tipset() {
+    use cid::multihash::{Code, MultihashDigest};
+    use fvm_shared::clock::ChainEpoch;
+
+    // Invokes the target actor at the end of every epoch.
+    struct Job;
+
+    impl EpochJobs for Job {
+        fn implicit_calls(&self, _epoch: ChainEpoch) -> Vec<ImplicitCall> {
+            vec![ImplicitCall {
+                to: 10000,
+                method_num: 1,
+                params: RawBytes::default(),
+                gas_limit: 1_000_000_000,
+            }]
+        }
+    }
+
+    let mut tester = new_tester(
+        NetworkVersion::V18,
+        StateTreeVersion::V5,
+        MemoryBlockstore::default(),
+    )
+    .unwrap();
+
+    let sender: [Account; 1] = tester.create_accounts().unwrap();
+
+    // Stand-ins for the target actor, and the reward and cron actors.
+    let wasm_bin = wat::parse_str(
+        r#"(module
+             (memory (export "memory") 1)
+             (func (export "invoke") (param $x i32) (result i32)
+               (i32.const 0)))"#,
+    )
+    .unwrap();
+    let state_cid = tester.set_state(&State { count: 0 }).unwrap();
+    let actor_address = Address::new_id(10000);
+    for addr in [actor_address, Address::new_id(2), Address::new_id(3)] {
+        tester
+            .set_actor_from_bin(&wasm_bin, state_cid, addr, TokenAmount::zero())
+            .unwrap();
+    }
+
+    tester.instantiate_machine(DummyExterns).unwrap();
+
+    let chain_message = |sequence: u64| {
+        let message = Message {
+            from: sender[0].1,
+            to: actor_address,
+            gas_limit: 1_000_000_000,
+            method_num: 1,
+            sequence,
+            ..Message::default()
+        };
+        let data = fvm_ipld_encoding::to_vec(&message).unwrap();
+        ChainMessage {
+            cid: Cid::new_v1(fvm_ipld_encoding::DAG_CBOR, Code::Blake2b256.digest(&data)),
+            raw_length: data.len(),
+            message,
+        }
+    };
+
+    // The first message is included in both blocks, but only applied once.
+    let blocks = [
+        TipsetBlock {
+            miner: Address::new_id(1000),
+            win_count: 1,
+            messages: vec![chain_message(0)],
+        },
+        TipsetBlock {
+            miner: Address::new_id(1001),
+            win_count: 1,
+            messages: vec![chain_message(0), chain_message(1)],
+        },
+    ];
+
+    let mut executor = tester.executor.unwrap().with_epoch_jobs(Job);
+    assert_eq!(executor.end_of_epoch_calls(0).len(), 2);
+    let res = executor.apply_tipset(&blocks, 0).unwrap();
+
+    assert_eq!(res.blocks.len(), 2);
+    assert_eq!(res.blocks[0].messages.len(), 1);
+    assert_eq!(res.blocks[0].duplicates, 0);
+    assert_eq!(res.blocks[1].messages.len(), 1);
+    assert_eq!(res.blocks[1].duplicates, 1);
+    assert_eq!(res.blocks[1].messages[0].0, blocks[1].messages[1].cid);
+    for block in &res.blocks {
+        let (_, ret) = &block.messages[0];
+        assert_eq!(ret.msg_receipt.exit_code, ExitCode::OK);
+        assert_eq!(block.gas_limit, 1_000_000_000);
+        assert_eq!(block.gas_used, ret.msg_receipt.gas_used);
+        assert_eq!(block.reward.msg_receipt.exit_code, ExitCode::OK);
+    }
+    assert_eq!(res.cron.msg_receipt.exit_code, ExitCode::OK);
+    assert_eq!(res.jobs.len(), 1);
+    assert_eq!(res.jobs[0].msg_receipt.exit_code, ExitCode::OK);
+    assert_eq!(res.receipts().count(), 2);
+    // Two block rewards, cron, and the job.
+    assert_eq!(res.implicit_receipts().count(), 4);
+    assert_eq!(res.state_root, executor.flush().unwrap());
+
+    // The machine is for epoch 0.
+    assert!(executor.apply_tipset(&[], 1).is_err());
+}
+
+#[test]
+fn apply_tipset_epoch_jobs() {
+    use std::sync::{Arc, Mutex};
+
+    use fvm::engine::MultiEngine;
+    use fvm::executor::CRON_ACTOR_ID;
+    use fvm::machine::REWARD_ACTOR_ID;
+    use fvm_shared::clock::ChainEpoch;
+    use fvm_shared::ActorID;
+
+    const JOB_ACTOR: ActorID = 10000;
+    const FAILING_ACTOR: ActorID = 10001;
+
+    // Invokes the job actor at the end of every epoch, then the failing actor at epoch 2.
+    struct Jobs;
+
+    impl EpochJobs for Jobs {
+        fn implicit_calls(&self, epoch: ChainEpoch) -> Vec<ImplicitCall> {
+            let call = |to| ImplicitCall {
+                to,
+                method_num: 1,
+                params: RawBytes::default(),
+                gas_limit: 1_000_000_000,
+            };
+            let mut calls = vec![call(JOB_ACTOR)];
+            if epoch == 2 {
+                calls.push(call(FAILING_ACTOR));
+            }
+            calls
+        }
+    }
+
+    // Records the receivers of implicit messages, in the order they're applied.
+    #[derive(Default, Clone)]
+    struct Recorder(Arc<Mutex<Vec<ActorID>>>);
+
+    impl Recorder {
+        fn take(&self) -> Vec<ActorID> {
+            std::mem::take(&mut *self.0.lock().unwrap())
+        }
+    }
+
+    impl ExecutorEvents for Recorder {
+        fn message_started(&self, msg: &Message, apply_kind: ApplyKind) {
+            if apply_kind == ApplyKind::Implicit {
+                self.0.lock().unwrap().push(msg.to.id().unwrap());
+            }
+        }
+    }
+
+    let mut tester = new_tester(
+        NetworkVersion::V18,
+        StateTreeVersion::V5,
+        MemoryBlockstore::default(),
+    )
+    .unwrap();
+
+    // Stand-ins for the job actor, and the reward and cron actors.
+    let wasm_bin = wat::parse_str(
+        r#"(module
+             (memory (export "memory") 1)
+             (func (export "invoke") (param $x i32) (result i32)
+               (i32.const 0)))"#,
+    )
+    .unwrap();
+    let state_cid = tester.set_state(&State { count: 0 }).unwrap();
+    for id in [JOB_ACTOR, REWARD_ACTOR_ID, CRON_ACTOR_ID] {
+        tester
+            .set_actor_from_bin(
+                &wasm_bin,
+                state_cid,
+                Address::new_id(id),
+                TokenAmount::zero(),
+            )
+            .unwrap();
+    }
+    let failing_bin = wat::parse_str(
+        r#"(module
+             (memory (export "memory") 1)
+             (func (export "invoke") (param $x i32) (result i32)
+               (unreachable)))"#,
+    )
+    .unwrap();
+    tester
+        .set_actor_from_bin(
+            &failing_bin,
+            state_cid,
+            Address::new_id(FAILING_ACTOR),
+            TokenAmount::zero(),
+        )
+        .unwrap();
+
+    tester.instantiate_machine(DummyExterns).unwrap();
+    let recorder = Recorder::default();
+    let mut executor = tester
+        .executor
+        .unwrap()
+        .with_epoch_jobs(Jobs)
+        .with_events(recorder.clone());
+    let engines = MultiEngine::new(1);
+
+    // Cron runs after the block rewards, and before the jobs.
+    let blocks = [TipsetBlock {
+        miner: Address::new_id(1000),
+        win_count: 1,
+        messages: vec![],
+    }];
+    let res = executor.apply_tipset(&blocks, 0).unwrap();
+    assert_eq!(
+        recorder.take(),
+        vec![REWARD_ACTOR_ID, CRON_ACTOR_ID, JOB_ACTOR]
+    );
+
+    // The job's receipt is reported with the other implicit receipts, after cron's.
+    assert_eq!(res.jobs.len(), 1);
+    assert_eq!(res.jobs[0].msg_receipt.exit_code, ExitCode::OK);
+    let implicit: Vec<_> = res.implicit_receipts().collect();
+    assert_eq!(
+        implicit,
+        vec![
+            &res.blocks[0].reward.msg_receipt,
+            &res.cron.msg_receipt,
+            &res.jobs[0].msg_receipt
+        ]
+    );
+
+    // Null epochs only run the end of epoch calls.
+    executor.advance_epoch(&engines, 1, 30).unwrap();
+    let calls = executor.end_of_epoch_calls(1);
+    assert_eq!(
+        calls.iter().map(|c| c.to).collect::<Vec<_>>(),
+        vec![CRON_ACTOR_ID, JOB_ACTOR]
+    );
+    let rets = executor.apply_implicit_calls(&calls, 1).unwrap();
+    assert_eq!(rets.len(), 2);
+    assert!(rets
+        .iter()
+        .all(|ret| ret.msg_receipt.exit_code.is_success()));
+    assert_eq!(recorder.take(), vec![CRON_ACTOR_ID, JOB_ACTOR]);
+
+    // A failing job aborts the tipset, after running cron and the previous jobs.
+    executor.advance_epoch(&engines, 2, 60).unwrap();
+    let err = executor.apply_tipset(&[], 2).unwrap_err();
+    assert!(
+        format!("{err:#}").contains("failed with exit code"),
+        "unexpected error: {err:#}"
+    );
+    assert_eq!(
+        recorder.take(),
+        vec![CRON_ACTOR_ID, JOB_ACTOR, FAILING_ACTOR]
+    );
+}
+
+#[test]
+fn unreachable() {
+    test_exitcode(
+        r#"(module
+             (memory (export "memory") 1)
+             (func (export "invoke") (param $x i32) (result i32)
+               unreachable))"#,
+        ExitCode::SYS_ILLEGAL_INSTRUCTION,
+    );
+}
+
+#[test]
+fn custom_exit_code_policy() {
+    #[derive(Debug)]
+    struct RenumberedPolicy;
+
+    impl ExitCodePolicy for RenumberedPolicy {
+        fn illegal_instruction(&self) -> ExitCode {
+            ExitCode::new(14)
+        }
+
+        fn sender_invalid(&self) -> ExitCode {
+            ExitCode::new(15)
+        }
+    }
+
+    let mut tester = new_tester(
+        NetworkVersion::V18,
+        StateTreeVersion::V5,
+        MemoryBlockstore::default(),
+    )
+    .unwrap();
+
+    let sender: [Account; 1] = tester.create_accounts().unwrap();
+
+    let wasm_bin = wat::parse_str(
+        r#"(module
+             (memory (export "memory") 1)
+             (func (export "invoke") (param $x i32) (result i32)
+               unreachable))"#,
+    )
+    .unwrap();
+    let state_cid = tester.set_state(&State { count: 0 }).unwrap();
+    let actor_address = Address::new_id(10000);
+    tester
+        .set_actor_from_bin(&wasm_bin, state_cid, actor_address, TokenAmount::zero())
+        .unwrap();
+
+    tester
+        .instantiate_machine_with_config(
+            DummyExterns,
+            |nc| {
+                nc.exit_code_policy(RenumberedPolicy);
+            },
+            |_| (),
+        )
+        .unwrap();
+
+    let mut executor = ThreadedExecutor(tester.executor.unwrap());
+
+    // A trapping actor exits with the policy's code.
+    let res = executor
+        .execute_message(
+            Message {
+                from: sender[0].1,
+                to: actor_address,
+                gas_limit: 10_000_000,
+                method_num: 1,
+                ..Message::default()
+            },
+            ApplyKind::Explicit,
+            100,
+        )
+        .unwrap();
+    assert_eq!(res.msg_receipt.exit_code, ExitCode::new(14));
+
+    // So does a message from a sender that doesn't exist.
+    let res = executor
+        .execute_message(
+            Message {
+                from: Address::new_id(20000),
+                to: actor_address,
+                gas_limit: 10_000_000,
+                method_num: 1,
+                ..Message::default()
+            },
+            ApplyKind::Explicit,
+            100,
+        )
+        .unwrap();
+    assert_eq!(res.msg_receipt.exit_code, ExitCode::new(15));
+}
+
+#[test]
+fn div_by_zero() {
+    test_exitcode(
+        r#"(module
+             (memory (export "memory") 1)
+             (func (export "invoke") (param $x i32) (result i32)
+               i32.const 10
+               i32.const 0
+               i32.div_u))"#,
+        ExitCode::SYS_ILLEGAL_INSTRUCTION,
+    );
+}
+
+#[test]
+fn out_of_stack() {
+    test_exitcode(
+        r#"(module
+             (memory (export "memory") 1)
+             (func (export "invoke") (param $x i32) (result i32)
+               (i64.const 123)
+               (call 1)
+               (drop)
+               (i32.const 0))
+             (func (param $x i64) (result i64)
+               (local.get 0)
+               (call 1)))"#,
+        ExitCode::SYS_ILLEGAL_INSTRUCTION,
+    );
+}
+
+#[test]
+fn simd() {
+    test_exitcode_with_config(
+        r#"(module
+             (memory (export "memory") 1)
+             (func (export "invoke") (param $x i32) (result i32)
+               (v128.store (i32.const 0)
+                 (i32x4.add
+                   (v128.load (i32.const 0))
+                   (i32x4.splat (i32.const 1))))
+               (i32x4.extract_lane 0 (v128.load (i32.const 0)))
+               (drop)
+               (i32.const 0)))"#,
+        ExitCode::OK,
+        |nc| {
+            nc.wasm_simd(true);
+        },
+    );
+}
+
+#[test]
+fn simd_float_rejected() {
+    let mut tester = new_tester(
+        NetworkVersion::V18,
+        StateTreeVersion::V5,
+        MemoryBlockstore::default(),
+    )
+    .unwrap();
+
+    let wasm_bin = wat::parse_str(
+        r#"(module
+             (memory (export "memory") 1)
+             (func (export "invoke") (param $x i32) (result i32)
+               (f32x4.add (v128.load (i32.const 0)) (v128.load (i32.const 16)))
+               (drop)
+               (i32.const 0)))"#,
+    )
+    .unwrap();
+
+    let state_cid = tester.set_state(&State { count: 0 }).unwrap();
+    tester
+        .set_actor_from_bin(
+            &wasm_bin,
+            state_cid,
+            Address::new_id(10000),
+            TokenAmount::zero(),
+        )
+        .unwrap();
+
+    // Floating point SIMD is non-deterministic, so loading the actor must fail even with SIMD
+    // enabled.
+    assert!(tester
+        .instantiate_machine_with_config(
+            DummyExterns,
+            |nc| {
+                nc.wasm_simd(true);
+            },
+            |_| ()
+        )
+        .is_err());
+}
+
+#[test]
+fn self_destruct_disallowed() {
+    // Self-destruct to f00 must fail with IllegalOperation (2) when disallowed.
+    test_exitcode_with_config(
+        r#"(module
+             (type (;0;) (func (param i32 i32) (result i32)))
+             (import "self" "self_destruct" (func $self_destruct (type 0)))
+             (memory (export "memory") 1)
+             (func (export "invoke") (param $x i32) (result i32)
+               (if (i32.ne (call $self_destruct (i32.const 0) (i32.const 2)) (i32.const 2))
+                 (then unreachable))
+               (i32.const 0)))"#,
+        ExitCode::OK,
+        |nc| {
+            nc.self_destruct_policy(SelfDestructPolicy::Disallow);
+        },
+    );
+}
+
+#[test]
+fn self_destruct_burn() {
+    let mut tester = new_tester(
+        NetworkVersion::V18,
+        StateTreeVersion::V5,
+        MemoryBlockstore::default(),
+    )
+    .unwrap();
+
+    let sender: [Account; 1] = tester.create_accounts().unwrap();
+
+    // Self-destructs to f01234, which doesn't exist. The beneficiary is ignored when burning.
+    let wasm_bin = wat::parse_str(
+        r#"(module
+             (type (;0;) (func (param i32 i32) (result i32)))
+             (import "self" "self_destruct" (func $self_destruct (type 0)))
+             (memory (export "memory") 1)
+             (data (i32.const 0) "\00\d2\09")
+             (func (export "invoke") (param $x i32) (result i32)
+               (if (call $self_destruct (i32.const 0) (i32.const 3))
+                 (then unreachable))
+               (i32.const 0)))"#,
+    )
+    .unwrap();
+    let state_cid = tester.set_state(&State { count: 0 }).unwrap();
+    let actor_address = Address::new_id(10000);
+    tester
+        .set_actor_from_bin(
+            &wasm_bin,
+            state_cid,
+            actor_address,
+            TokenAmount::from_atto(1000),
+        )
+        .unwrap();
+
+    let burnt_before = tester
+        .state_tree
+        .as_ref()
+        .unwrap()
+        .get_actor(BURNT_FUNDS_ACTOR_ID)
+        .unwrap()
+        .unwrap()
+        .balance;
+
+    // Without a base fee, gas doesn't burn anything either.
+    tester
+        .instantiate_machine_with_config(
+            DummyExterns,
+            |nc| {
+                nc.self_destruct_policy(SelfDestructPolicy::Burn);
+            },
+            |mc| {
+                mc.set_base_fee(TokenAmount::zero());
+            },
+        )
+        .unwrap();
+
+    let message = Message {
+        from: sender[0].1,
+        to: actor_address,
+        gas_limit: 10_000_000,
+        method_num: 1,
+        ..Message::default()
+    };
+
+    let mut executor = tester.executor.unwrap();
+    let res = executor
+        .execute_message(message, ApplyKind::Explicit, 100)
+        .unwrap();
+    assert_eq!(res.msg_receipt.exit_code, ExitCode::OK);
+
+    // The actor is gone, and its balance was burnt.
+    assert!(executor.state_tree().get_actor(10000).unwrap().is_none());
+    let burnt_after = executor
+        .state_tree()
+        .get_actor(BURNT_FUNDS_ACTOR_ID)
+        .unwrap()
+        .unwrap()
+        .balance;
+    assert_eq!(burnt_after - burnt_before, TokenAmount::from_atto(1000));
+}
+
+#[test]
+fn disabled_syscall() {
+    // Disabled syscalls can still be imported, but fail with IllegalOperation (2).
+    test_exitcode_with_config(
+        r#"(module
+             (type (;0;) (func (param i32) (result i32)))
+             (import "self" "current_balance" (func $current_balance (type 0)))
+             (memory (export "memory") 1)
+             (func (export "invoke") (param $x i32) (result i32)
+               (if (i32.ne (call $current_balance (i32.const 0)) (i32.const 2))
+                 (then unreachable))
+               (i32.const 0)))"#,
+        ExitCode::OK,
+        |nc| {
+            nc.disabled_syscalls = vec![("self", "current_balance")];
+        },
+    );
+}
+
+#[test]
+fn call_depth() {
+    // Calls itself once, checking the call depth of both calls.
+    let wat = r#"(module
+             (type (;0;) (func (param i32) (result i32)))
+             (type (;1;) (func (param i32 i32 i32 i64 i32 i64 i64 i64 i64) (result i32)))
+             (import "vm" "call_depth" (func $call_depth (type 0)))
+             (import "send" "send" (func $send (type 1)))
+             (memory (export "memory") 1)
+             (data (i32.const 64) "\00\90\4e")
+             (func (export "invoke") (param $x i32) (result i32)
+               (if (call $call_depth (i32.const 0))
+                 (then unreachable))
+               (if (i32.eq (i32.load (i32.const 0)) (i32.const 2))
+                 (then (return (i32.const 0))))
+               (if (i32.ne (i32.load (i32.const 0)) (i32.const 1))
+                 (then unreachable))
+               (if (call $send (i32.const 16) (i32.const 64) (i32.const 3) (i64.const 1)
+                     (i32.const 0) (i64.const 0) (i64.const 0) (i64.const -1) (i64.const 0))
+                 (then unreachable))
+               (if (i32.load (i32.const 16))
+                 (then unreachable))
+               (i32.const 0)))"#;
+    test_exitcode_with_config(wat, ExitCode::OK, |nc| {
+        // Enable the syscalls introduced in nv21.
+        nc.disabled_syscalls.clear();
+    });
+
+    // Unavailable before nv21: the syscall fails with IllegalOperation.
+    test_exitcode(wat, ExitCode::SYS_ILLEGAL_INSTRUCTION);
+}
+
+#[test]
+fn debug_artifacts() {
+    // Instantiate tester
+    let mut tester = new_tester(
+        NetworkVersion::V18,
+        StateTreeVersion::V5,
+        MemoryBlockstore::default(),
+    )
+    .unwrap();
+
+    let sender: [Account; 1] = tester.create_accounts().unwrap();
+
+    // Stores the artifact "hello" with the contents "world".
+    let wasm_bin = wat::parse_str(
+        r#"(module
+             (type (;0;) (func (param i32 i32 i32 i32) (result i32)))
+             (import "debug" "store_artifact" (func $store_artifact (type 0)))
+             (memory (export "memory") 1)
+             (data (i32.const 0) "hello")
+             (data (i32.const 8) "world")
+             (func (export "invoke") (param $x i32) (result i32)
+               (if (call $store_artifact (i32.const 0) (i32.const 5) (i32.const 8) (i32.const 5))
+                 (then unreachable))
+               (i32.const 0)))"#,
+    )
+    .unwrap();
+
+    let state_cid = tester.set_state(&State { count: 0 }).unwrap();
+    let actor_address = Address::new_id(10000);
+    tester
+        .set_actor_from_bin(&wasm_bin, state_cid, actor_address, TokenAmount::zero())
+        .unwrap();
+
+    // Instantiate machine (the tester enables actor debugging).
+    tester.instantiate_machine(DummyExterns).unwrap();
+
+    let message = Message {
+        from: sender[0].1,
+        to: actor_address,
+        gas_limit: 10_000_000,
+        method_num: 1,
+        ..Message::default()
+    };
+
+    let res = tester
+        .executor
+        .unwrap()
+        .execute_message(message, ApplyKind::Explicit, 100)
+        .unwrap();
+
+    assert_eq!(res.msg_receipt.exit_code, ExitCode::OK);
+    assert_eq!(
+        res.artifacts,
+        vec![DebugArtifact {
+            actor: 10000,
+            name: "hello".into(),
+            data: b"world".to_vec(),
+        }]
+    );
+
+    let dir = std::env::temp_dir().join(format!("fvm-debug-artifacts-{}", std::process::id()));
+    res.write_artifacts(&dir).unwrap();
+    assert_eq!(
+        std::fs::read(dir.join("10000").join("hello")).unwrap(),
+        b"world"
+    );
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn executor_events() {
+    #[derive(Default, Clone)]
+    struct Recorder(std::sync::Arc<std::sync::Mutex<(usize, Vec<MessageStats>)>>);
+
+    impl ExecutorEvents for Recorder {
+        fn message_started(&self, _msg: &Message, _apply_kind: ApplyKind) {
+            self.0.lock().unwrap().0 += 1;
+        }
+
+        fn message_finished(&self, stats: &MessageStats) {
+            self.0.lock().unwrap().1.push(stats.clone());
+        }
+    }
+
+    // Instantiate tester
+    let mut tester = new_tester(
+        NetworkVersion::V18,
+        StateTreeVersion::V5,
+        MemoryBlockstore::default(),
+    )
+    .unwrap();
+
+    let sender: [Account; 1] = tester.create_accounts().unwrap();
+
+    let wasm_bin = wat::parse_str(
+        r#"(module
+             (memory (export "memory") 1)
+             (func (export "invoke") (param $x i32) (result i32)
+               (i32.const 0)))"#,
+    )
+    .unwrap();
+
+    let state_cid = tester.set_state(&State { count: 0 }).unwrap();
+    let actor_address = Address::new_id(10000);
+    tester
+        .set_actor_from_bin(&wasm_bin, state_cid, actor_address, TokenAmount::zero())
+        .unwrap();
+
+    tester.instantiate_machine(DummyExterns).unwrap();
+
+    let recorder = Recorder::default();
+    let mut executor = tester.executor.unwrap().with_events(recorder.clone());
+
+    let message = Message {
+        from: sender[0].1,
+        to: actor_address,
+        gas_limit: 10_000_000,
+        method_num: 1,
+        ..Message::default()
+    };
+    let res = executor
+        .execute_message(message.clone(), ApplyKind::Explicit, 100)
+        .unwrap();
+    assert_eq!(res.msg_receipt.exit_code, ExitCode::OK);
+
+    // Reusing the nonce fails validation.
+    let failed = executor
+        .execute_message(message, ApplyKind::Explicit, 100)
+        .unwrap();
+    assert_eq!(
+        failed.msg_receipt.exit_code,
+        ExitCode::SYS_SENDER_STATE_INVALID
+    );
+
+    let (started, finished) = &*recorder.0.lock().unwrap();
+    assert_eq!(*started, 2);
+    assert_eq!(finished.len(), 2);
+    assert_eq!(finished[0].apply_kind, ApplyKind::Explicit);
+    assert_eq!(finished[0].exit_code, ExitCode::OK);
+    assert_eq!(finished[0].gas_used, res.msg_receipt.gas_used);
+    assert_eq!(finished[0].max_call_depth, 1);
+    assert_eq!(finished[1].exit_code, ExitCode::SYS_SENDER_STATE_INVALID);
+    assert_eq!(finished[1].max_call_depth, 0);
+}
+
+#[test]
+fn sponsored_sender() {
+    use fvm::state_tree::ActorState;
+
+    // Lets the actor with the given code send messages, with gas paid for by the sponsor.
+    struct Sponsored {
+        code: Cid,
+        sponsor: ActorID,
+    }
+
+    impl SenderValidator for Sponsored {
+        fn handles(&self, code: &Cid) -> bool {
+            *code == self.code
+        }
+
+        fn validate(
+            &self,
+            msg: &Message,
+            _apply_kind: ApplyKind,
+            _sender_id: ActorID,
+            sender: &mut ActorState,
+        ) -> Result<ActorID, SenderRejection> {
+            if msg.sequence != sender.sequence {
+                return Err(SenderRejection::new(
+                    ExitCode::SYS_SENDER_STATE_INVALID,
+                    "bad sequence",
+                ));
+            }
+            sender.sequence += 1;
+            Ok(self.sponsor)
+        }
+    }
+
+    // Instantiate tester
+    let mut tester = new_tester(
+        NetworkVersion::V18,
+        StateTreeVersion::V5,
+        MemoryBlockstore::default(),
+    )
+    .unwrap();
+
+    let sponsor = tester
+        .make_secp256k1_account(
+            libsecp256k1::SecretKey::random(&mut rand::thread_rng()),
+            TokenAmount::from_whole(1),
+        )
+        .unwrap();
+
+    let wasm_bin = wat::parse_str(
+        r#"(module
+             (memory (export "memory") 1)
+             (func (export "invoke") (param $x i32) (result i32)
+               (i32.const 0)))"#,
+    )
+    .unwrap();
+
+    let state_cid = tester.set_state(&State { count: 0 }).unwrap();
+    let actor_address = Address::new_id(10000);
+    let code = tester
+        .set_actor_from_bin(&wasm_bin, state_cid, actor_address, TokenAmount::zero())
+        .unwrap();
+
+    tester.instantiate_machine(DummyExterns).unwrap();
+
+    let mut executor = tester.executor.unwrap().with_sender_validator(Sponsored {
+        code,
+        sponsor: sponsor.0,
+    });
+
+    // The actor isn't an account, but the validator lets it send anyway.
+    let message = Message {
+        from: actor_address,
+        to: sponsor.1,
+        gas_limit: 10_000_000,
+        gas_fee_cap: TokenAmount::from_atto(200),
+        gas_premium: TokenAmount::from_atto(10),
+        ..Message::default()
+    };
+    let res = executor
+        .execute_message(message.clone(), ApplyKind::Explicit, 100)
+        .unwrap();
+    assert_eq!(res.msg_receipt.exit_code, ExitCode::OK);
+
+    // The sponsor paid for the gas, while the sender's sequence was bumped.
+    let actor = executor.state_tree().get_actor(10000).unwrap().unwrap();
+    assert_eq!(actor.sequence, 1);
+    assert!(actor.balance.is_zero());
+    let sponsor_state = executor.state_tree().get_actor(sponsor.0).unwrap().unwrap();
+    assert_eq!(sponsor_state.sequence, 0);
+    assert_eq!(
+        sponsor_state.balance,
+        TokenAmount::from_whole(1) - res.fees.total_paid()
+    );
+
+    // The validator rejects replays.
+    let failed = executor
+        .execute_message(message, ApplyKind::Explicit, 100)
+        .unwrap();
+    assert_eq!(
+        failed.msg_receipt.exit_code,
+        ExitCode::SYS_SENDER_STATE_INVALID
+    );
+}
+
+#[test]
+fn prefetch_code() {
+    let mut tester = new_tester(
+        NetworkVersion::V18,
+        StateTreeVersion::V5,
+        MemoryBlockstore::default(),
+    )
+    .unwrap();
+
+    let accounts: [Account; 2] = tester.create_accounts().unwrap();
+    tester.instantiate_machine(DummyExterns).unwrap();
+    let mut executor = tester.executor.unwrap();
+
+    let message = Message {
+        from: accounts[0].1,
+        to: accounts[1].1,
+        gas_limit: 10_000_000,
+        method_num: 2,
+        ..Message::default()
+    };
+    let missing = Message {
+        to: Address::new_id(12345),
+        ..message.clone()
+    };
+
+    // The account actor's code isn't preloaded in tests, so it gets compiled (once).
+    let prefetch = executor
+        .prefetch_code([&message, &message, &missing])
+        .unwrap();
+    assert_eq!(prefetch.len(), 1);
+    prefetch.wait();
+    assert!(executor.prefetch_code([&message]).unwrap().is_empty());
+
+    let res = executor
+        .execute_message(message, ApplyKind::Explicit, 100)
+        .unwrap();
+    assert_eq!(res.msg_receipt.exit_code, ExitCode::OK);
+}
+
+#[test]
+fn custom_fee_distribution() {
+    use fvm::machine::REWARD_ACTOR_ID;
+    use fvm_integration_tests::tester::INITIAL_ACCOUNT_BALANCE;
+    use fvm_shared::clock::ChainEpoch;
+
+    // Splits the miner tip evenly between the reward actor and a treasury.
+    struct Split {
+        treasury: ActorID,
+    }
+
+    impl FeeDistribution for Split {
+        fn miner_tip(&self, _epoch: ChainEpoch, tip: &TokenAmount) -> Vec<(ActorID, TokenAmount)> {
+            let half = tip.div_floor(2);
+            vec![(REWARD_ACTOR_ID, tip - &half), (self.treasury, half)]
+        }
+    }
+
+    let mut tester = new_tester(
+        NetworkVersion::V18,
+        StateTreeVersion::V5,
+        MemoryBlockstore::default(),
+    )
+    .unwrap();
+
+    let sender = tester
+        .make_secp256k1_account(
+            libsecp256k1::SecretKey::random(&mut rand::thread_rng()),
+            TokenAmount::from_whole(1),
+        )
+        .unwrap();
+    let treasury = tester.create_account().unwrap();
+
+    tester.instantiate_machine(DummyExterns).unwrap();
+    let mut executor = tester.executor.unwrap().with_fee_distribution(Split {
+        treasury: treasury.0,
+    });
+
+    let message = Message {
+        from: sender.1,
+        to: sender.1,
+        gas_limit: 10_000_000,
+        gas_fee_cap: TokenAmount::from_atto(200),
+        gas_premium: TokenAmount::from_atto(11),
+        ..Message::default()
+    };
+    let res = executor
+        .execute_message(message, ApplyKind::Explicit, 100)
+        .unwrap();
+    assert_eq!(res.msg_receipt.exit_code, ExitCode::OK);
+    assert!(!res.fees.miner_tip.is_zero());
+
+    let treasury_state = executor
+        .state_tree()
+        .get_actor(treasury.0)
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        treasury_state.balance,
+        INITIAL_ACCOUNT_BALANCE.clone() + res.fees.miner_tip.div_floor(2)
+    );
+}
+
+#[test]
+fn invalid_fee_distribution() {
+    use fvm_shared::clock::ChainEpoch;
+
+    // Pays out one more attoFIL than the miner tip.
+    struct Overpay {
+        treasury: ActorID,
+    }
+
+    impl FeeDistribution for Overpay {
+        fn miner_tip(&self, _epoch: ChainEpoch, tip: &TokenAmount) -> Vec<(ActorID, TokenAmount)> {
+            vec![(self.treasury, tip + TokenAmount::from_atto(1))]
+        }
+    }
+
+    let mut tester = new_tester(
+        NetworkVersion::V18,
+        StateTreeVersion::V5,
+        MemoryBlockstore::default(),
+    )
+    .unwrap();
+
+    let sender = tester
+        .make_secp256k1_account(
+            libsecp256k1::SecretKey::random(&mut rand::thread_rng()),
+            TokenAmount::from_whole(1),
+        )
+        .unwrap();
+    let treasury = tester.create_account().unwrap();
+
+    tester.instantiate_machine(DummyExterns).unwrap();
+    let mut executor = tester.executor.unwrap().with_fee_distribution(Overpay {
+        treasury: treasury.0,
+    });
+
+    let burnt_balance = |executor: &IntegrationExecutor<_, _>| {
+        executor
+            .state_tree()
+            .get_actor(BURNT_FUNDS_ACTOR_ID)
+            .unwrap()
+            .unwrap()
+            .balance
+    };
+    let burnt_before = burnt_balance(&executor);
+
+    let message = Message {
+        from: sender.1,
+        to: sender.1,
+        gas_limit: 10_000_000,
+        gas_fee_cap: TokenAmount::from_atto(200),
+        gas_premium: TokenAmount::from_atto(11),
+        ..Message::default()
+    };
+    executor
+        .execute_message(message, ApplyKind::Explicit, 100)
+        .expect_err("the fee distribution doesn't add up");
+
+    // The base fee burn is distributed before the miner tip, but nothing was transferred.
+    assert_eq!(burnt_balance(&executor), burnt_before);
+}
+
+#[test]
+fn fork_machine() {
+    let mut tester = new_tester(
+        NetworkVersion::V18,
+        StateTreeVersion::V5,
+        MemoryBlockstore::default(),
+    )
+    .unwrap();
+
+    let accounts: [Account; 2] = tester.create_accounts().unwrap();
+    tester.instantiate_machine(DummyExterns).unwrap();
+    let mut executor = tester.executor.unwrap();
+
+    let transfer = |sequence| Message {
+        from: accounts[0].1,
+        to: accounts[1].1,
+        sequence,
+        value: TokenAmount::from_atto(100),
+        gas_limit: 10_000_000,
+        ..Message::default()
+    };
+
+    // Apply a message, then fork before flushing.
+    let res = executor
+        .execute_message(transfer(0), ApplyKind::Explicit, 100)
+        .unwrap();
+    assert_eq!(res.msg_receipt.exit_code, ExitCode::OK);
+
+    let forked = executor.fork().unwrap();
+    let engine = EnginePool::new_default((&forked.context().network).into()).unwrap();
+    let mut forked = IntegrationExecutor::new(engine, forked).unwrap();
+
+    // The fork sees the unflushed transfer, and diverges from there.
+    let balance = |executor: &IntegrationExecutor<_, _>, id| {
+        executor
+            .state_tree()
+            .get_actor(id)
+            .unwrap()
+            .unwrap()
+            .balance
+    };
+    assert_eq!(
+        balance(&forked, accounts[1].0),
+        balance(&executor, accounts[1].0)
+    );
+    let res = forked
+        .execute_message(transfer(1), ApplyKind::Explicit, 100)
+        .unwrap();
+    assert_eq!(res.msg_receipt.exit_code, ExitCode::OK);
+    assert_eq!(
+        balance(&forked, accounts[1].0),
+        balance(&executor, accounts[1].0) + TokenAmount::from_atto(100)
+    );
+
+    // The original machine is unaffected, and can still apply the same message.
+    let res = executor
+        .execute_message(transfer(1), ApplyKind::Explicit, 100)
+        .unwrap();
+    assert_eq!(res.msg_receipt.exit_code, ExitCode::OK);
+}
+
+#[test]
+fn advance_across_upgrade() {
+    use fvm::engine::MultiEngine;
+
+    let mut tester = new_tester(
+        NetworkVersion::V18,
+        StateTreeVersion::V5,
+        MemoryBlockstore::default(),
+    )
+    .unwrap();
+
+    let accounts: [Account; 2] = tester.create_accounts().unwrap();
+    tester
+        .instantiate_machine_with_config(
+            DummyExterns,
+            |nc| {
+                nc.chain_id(ChainID::from(314159u64))
+                    .fork_schedule([(10, NetworkVersion::V19)])
+                    .unwrap();
+            },
+            |_| (),
+        )
+        .unwrap();
+    let mut executor = tester.executor.unwrap();
+    let engines = MultiEngine::new(1);
+
+    let transfer = |sequence| Message {
+        from: accounts[0].1,
+        to: accounts[1].1,
+        sequence,
+        value: TokenAmount::from_atto(100),
+        gas_limit: 10_000_000,
+        ..Message::default()
+    };
+
+    // Before the upgrade.
+    executor.advance_epoch(&engines, 5, 150).unwrap();
+    assert_eq!(executor.context().epoch, 5);
+    assert_eq!(executor.context().network_version, NetworkVersion::V18);
+    let res = executor
+        .execute_message(transfer(0), ApplyKind::Explicit, 100)
+        .unwrap();
+    assert_eq!(res.msg_receipt.exit_code, ExitCode::OK);
+
+    // Crossing the upgrade switches network versions, keeping the chain ID.
+    executor.advance_epoch(&engines, 10, 300).unwrap();
+    assert_eq!(executor.context().network_version, NetworkVersion::V19);
+    assert_eq!(executor.context().chain_id, ChainID::from(314159u64));
+    let res = executor
+        .execute_message(transfer(1), ApplyKind::Explicit, 100)
+        .unwrap();
+    assert_eq!(res.msg_receipt.exit_code, ExitCode::OK);
+
+    // Machines can't go back in time.
+    assert!(executor.advance_epoch(&engines, 9, 270).is_err());
 }
 
 #[test]
@@ -765,7 +2664,10 @@ fn backtraces() {
             .unwrap()
     };
 
-    println!("abort backtrace: {}", res.failure_info.unwrap());
+    let failure = res.failure_info.unwrap().to_string();
+    println!("abort backtrace: {}", failure);
+    // Standard method names are resolved.
+    assert!(failure.contains("(method 1: Constructor)"));
 
     let res = {
         let message = Message {
@@ -0,0 +1,188 @@
+// Copyright 2021-2023 Protocol Labs
+// SPDX-License-Identifier: Apache-2.0, MIT
+mod bundles;
+use bundles::*;
+use fvm::executor::{ApplyKind, ApplyRet, Executor};
+use fvm::machine::Machine;
+use fvm_integration_tests::dummy::DummyExterns;
+use fvm_ipld_blockstore::MemoryBlockstore;
+use fvm_ipld_encoding::tuple::*;
+use fvm_ipld_encoding::RawBytes;
+use fvm_shared::address::Address;
+use fvm_shared::clock::ChainEpoch;
+use fvm_shared::crypto::signature::ops::eth_address_of;
+use fvm_shared::econ::TokenAmount;
+use fvm_shared::error::ExitCode;
+use fvm_shared::message::Message;
+use fvm_shared::state::StateTreeVersion;
+use fvm_shared::version::NetworkVersion;
+use fvm_shared::{method_hash, ActorID, MethodNum};
+use fvm_test_actors::wasm_bin::SDK_ACTOR_BINARY;
+use num_traits::Zero;
+
+/// The state of the SDK test actor.
+#[derive(Serialize_tuple, Deserialize_tuple)]
+struct State {
+    count: u64,
+}
+
+/// Encodes the parameters as CBOR.
+fn params<T: serde::Serialize + ?Sized>(params: &T) -> RawBytes {
+    RawBytes::serialize(params).unwrap()
+}
+
+#[test]
+fn sdk_helpers() {
+    // Instantiate tester
+    let mut tester = new_tester(
+        NetworkVersion::V18,
+        StateTreeVersion::V5,
+        MemoryBlockstore::default(),
+    )
+    .unwrap();
+
+    let [(sender_id, sender_address)] = tester.create_accounts().unwrap();
+
+    // An actor with a delegated address.
+    let f4_address = Address::new_delegated(10, &[1; 20]).unwrap();
+    tester
+        .create_placeholder(&f4_address, TokenAmount::zero())
+        .unwrap();
+    let f4_id = tester
+        .state_tree
+        .as_ref()
+        .unwrap()
+        .lookup_id(&f4_address)
+        .unwrap()
+        .unwrap();
+
+    // Set actor
+    let state_cid = tester.set_state(&State { count: 0 }).unwrap();
+    let actor_address = Address::new_id(10000);
+    tester
+        .set_actor_from_bin(
+            SDK_ACTOR_BINARY,
+            state_cid,
+            actor_address,
+            TokenAmount::zero(),
+        )
+        .unwrap();
+
+    // Instantiate machine
+    tester.instantiate_machine(DummyExterns).unwrap();
+
+    let executor = tester.executor.as_mut().unwrap();
+    let (epoch, base_fee) = (
+        executor.context().epoch,
+        executor.context().base_fee.clone(),
+    );
+    let mut sequence = 0;
+    let mut call = |method_num: MethodNum, params: RawBytes, value: TokenAmount| -> ApplyRet {
+        let message = Message {
+            from: sender_address,
+            to: actor_address,
+            gas_limit: 1000000000,
+            method_num,
+            params,
+            value,
+            sequence,
+            ..Message::default()
+        };
+        sequence += 1;
+        executor
+            .execute_message(message, ApplyKind::Explicit, 100)
+            .unwrap()
+    };
+    let ok = |res: ApplyRet| -> RawBytes {
+        assert!(
+            res.msg_receipt.exit_code.is_success(),
+            "{:?}",
+            res.failure_info
+        );
+        res.msg_receipt.return_data
+    };
+
+    // State transactions persist the state only if they succeed.
+    let ret = ok(call(2, params(&3u64), TokenAmount::zero()));
+    assert_eq!(ret.deserialize::<u64>().unwrap(), 3);
+    ok(call(3, params(&4u64), TokenAmount::zero()));
+    let ret = ok(call(
+        method_hash!("Count"),
+        RawBytes::default(),
+        TokenAmount::zero(),
+    ));
+    assert_eq!(ret.deserialize::<u64>().unwrap(), 3);
+
+    // Methods returning nothing return no data.
+    let ret = ok(call(
+        method_hash!("Nothing"),
+        RawBytes::default(),
+        TokenAmount::zero(),
+    ));
+    assert!(ret.is_empty());
+
+    // Unhandled methods abort.
+    let res = call(1234, RawBytes::default(), TokenAmount::zero());
+    assert_eq!(res.msg_receipt.exit_code, ExitCode::USR_UNHANDLED_MESSAGE);
+
+    // Parameters that can't be decoded abort.
+    let res = call(2, params(&"three"), TokenAmount::zero());
+    assert_eq!(res.msg_receipt.exit_code, ExitCode::USR_SERIALIZATION);
+
+    // The send builder (checked by the actor itself).
+    ok(call(4, RawBytes::default(), TokenAmount::zero()));
+
+    // Panics abort with the (truncated) panic message.
+    let message = "x".repeat(2000);
+    let res = call(5, params(&message), TokenAmount::zero());
+    assert_eq!(res.msg_receipt.exit_code, ExitCode::USR_ASSERTION_FAILED);
+    let data = res.msg_receipt.return_data;
+    assert_eq!(data.len(), 1024);
+    assert!(String::from_utf8(data.to_vec()).unwrap().contains("xxxx"));
+
+    // Cached contexts.
+    let ret = ok(call(6, RawBytes::default(), TokenAmount::from_atto(100)));
+    assert_eq!(
+        ret.deserialize::<(ActorID, TokenAmount, ChainEpoch, TokenAmount)>()
+            .unwrap(),
+        (sender_id, TokenAmount::from_atto(100), epoch, base_fee)
+    );
+
+    // Address resolution.
+    let unknown = Address::new_secp256k1(&[3; 65]).unwrap();
+    for (addr, expected) in [
+        (sender_address, (Some(sender_id), None)),
+        (f4_address, (Some(f4_id), Some(f4_address))),
+        (Address::new_id(f4_id), (Some(f4_id), Some(f4_address))),
+        (unknown, (None, None)),
+    ] {
+        let ret = ok(call(7, params(&addr), TokenAmount::zero()));
+        assert_eq!(
+            ret.deserialize::<(Option<ActorID>, Option<Address>)>()
+                .unwrap(),
+            expected
+        );
+    }
+
+    // Hashing and Ethereum address recovery.
+    let hash: [u8; 32] =
+        hex::decode("c5d2460186f7233c927e7db2dcc703c0e500b653ca82273b7bfad8045d85a470")
+            .unwrap()
+            .try_into()
+            .unwrap();
+    let key = libsecp256k1::SecretKey::parse(&[1; 32]).unwrap();
+    let (signature, recovery_id) = libsecp256k1::sign(&libsecp256k1::Message::parse(&hash), &key);
+    let mut signature = signature.serialize().to_vec();
+    signature.push(recovery_id.serialize());
+    let ret = ok(call(
+        8,
+        params(&(RawBytes::default(), RawBytes::new(signature))),
+        TokenAmount::zero(),
+    ));
+    let (actual_hash, eth_address) = ret.deserialize::<(RawBytes, RawBytes)>().unwrap();
+    assert_eq!(actual_hash.bytes(), hash);
+    assert_eq!(
+        eth_address.bytes(),
+        eth_address_of(&libsecp256k1::PublicKey::from_secret_key(&key)).0
+    );
+}
@@ -0,0 +1,118 @@
+// Copyright 2021-2023 Protocol Labs
+// SPDX-License-Identifier: Apache-2.0, MIT
+mod bundles;
+use bundles::*;
+use cid::Cid;
+use fvm::executor::{ApplyFailure, ApplyKind};
+use fvm_integration_tests::dummy::DummyExterns;
+use fvm_ipld_blockstore::MemoryBlockstore;
+use fvm_ipld_encoding::{to_vec, DAG_CBOR};
+use fvm_shared::address::Address;
+use fvm_shared::crypto::signature::ops::eth_address_of;
+use fvm_shared::crypto::signature::Signature;
+use fvm_shared::econ::TokenAmount;
+use fvm_shared::error::ExitCode;
+use fvm_shared::message::Message;
+use fvm_shared::state::StateTreeVersion;
+use fvm_shared::version::NetworkVersion;
+use fvm_shared::METHOD_SEND;
+use multihash::{Code, MultihashDigest};
+
+/// Signs the message's CID with the secp256k1 key, as Filecoin wallets do.
+fn secp_sign(key: &libsecp256k1::SecretKey, msg: &Message) -> Signature {
+    let cid = Cid::new_v1(DAG_CBOR, Code::Blake2b256.digest(&to_vec(msg).unwrap()));
+    let hash = blake2b_simd::Params::new()
+        .hash_length(32)
+        .hash(&cid.to_bytes());
+    let (sig, recovery_id) = libsecp256k1::sign(
+        &libsecp256k1::Message::parse_slice(hash.as_bytes()).unwrap(),
+        key,
+    );
+    let mut bytes = sig.serialize().to_vec();
+    bytes.push(recovery_id.serialize());
+    Signature::new_secp256k1(bytes)
+}
+
+#[test]
+fn signed_messages() {
+    // Instantiate tester
+    let mut tester = new_tester(
+        NetworkVersion::V18,
+        StateTreeVersion::V5,
+        MemoryBlockstore::default(),
+    )
+    .unwrap();
+
+    let key = libsecp256k1::SecretKey::parse(&[1; 32]).unwrap();
+    let (_, sender) = tester
+        .make_secp256k1_account(key, TokenAmount::from_whole(100))
+        .unwrap();
+    let [(_, receiver)] = tester.create_accounts().unwrap();
+
+    // An Ethereum account (placeholder) sender.
+    let eth_key = libsecp256k1::SecretKey::parse(&[2; 32]).unwrap();
+    let eth_sender =
+        eth_address_of(&libsecp256k1::PublicKey::from_secret_key(&eth_key)).to_address();
+    tester
+        .create_placeholder(&eth_sender, TokenAmount::from_whole(100))
+        .unwrap();
+
+    // Instantiate machine
+    tester.instantiate_machine(DummyExterns).unwrap();
+    let executor = tester.executor.as_mut().unwrap();
+
+    let message = |from: Address, sequence: u64| Message {
+        from,
+        to: receiver,
+        gas_limit: 1000000000,
+        method_num: METHOD_SEND,
+        sequence,
+        value: TokenAmount::from_atto(100),
+        ..Message::default()
+    };
+    let assert_rejected = |ret: fvm::executor::ApplyRet| {
+        assert_eq!(ret.msg_receipt.exit_code, ExitCode::SYS_SENDER_INVALID);
+        assert!(
+            matches!(ret.failure_info, Some(ApplyFailure::PreValidation(_))),
+            "{:?}",
+            ret.failure_info
+        );
+    };
+
+    // Correctly signed messages are applied.
+    let msg = message(sender, 0);
+    let signature = secp_sign(&key, &msg);
+    let ret = executor
+        .execute_signed_message(msg, &signature, ApplyKind::Explicit, 100)
+        .unwrap();
+    assert!(
+        ret.msg_receipt.exit_code.is_success(),
+        "{:?}",
+        ret.failure_info
+    );
+
+    // Messages signed for a different message, or by a different key, fail pre-validation.
+    let ret = executor
+        .execute_signed_message(message(sender, 1), &signature, ApplyKind::Explicit, 100)
+        .unwrap();
+    assert_rejected(ret);
+    let msg = message(sender, 1);
+    let signature = secp_sign(&eth_key, &msg);
+    let ret = executor
+        .execute_signed_message(msg, &signature, ApplyKind::Explicit, 100)
+        .unwrap();
+    assert_rejected(ret);
+
+    // Delegated signatures are only valid for messages that can be expressed as Ethereum
+    // transactions, which plain sends can't.
+    let msg = message(eth_sender, 0);
+    let ret = executor
+        .execute_signed_message(
+            msg,
+            &Signature::new_delegated(vec![0; 65]),
+            ApplyKind::Explicit,
+            100,
+        )
+        .unwrap();
+    assert_rejected(ret);
+}
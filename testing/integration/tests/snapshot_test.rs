@@ -0,0 +1,95 @@
+// Copyright 2021-2023 Protocol Labs
+// SPDX-License-Identifier: Apache-2.0, MIT
+#![cfg(test)]
+
+mod bundles;
+use bundles::*;
+use fvm::executor::{ApplyKind, Executor};
+use fvm::machine::Machine;
+use fvm_integration_tests::tester::{BasicAccount, BasicTester, ExecutionOptions};
+use fvm_shared::address::Address;
+use fvm_shared::econ::TokenAmount;
+use fvm_shared::message::Message;
+use fvm_shared::METHOD_SEND;
+
+fn send(tester: &mut BasicTester, sender: &mut BasicAccount, to: Address, value: u64) {
+    let message = Message {
+        from: sender.account.1,
+        to,
+        gas_limit: 1000000000,
+        method_num: METHOD_SEND,
+        sequence: sender.seqno,
+        value: TokenAmount::from_atto(value),
+        ..Message::default()
+    };
+    let res = tester
+        .with_executor(|e| e.execute_message(message, ApplyKind::Explicit, 100))
+        .unwrap();
+    assert!(res.msg_receipt.exit_code.is_success());
+    sender.seqno += 1;
+}
+
+fn balance(tester: &mut BasicTester, addr: Address) -> Option<TokenAmount> {
+    tester
+        .with_executor(|e| {
+            Ok(e.state_tree()
+                .get_actor_by_address(&addr)?
+                .map(|actor| actor.balance))
+        })
+        .unwrap()
+}
+
+#[test]
+fn snapshot_restore() {
+    let mut tester = new_basic_tester(ExecutionOptions::default()).unwrap();
+    let [mut sender] = tester.create_basic_accounts().unwrap();
+    let receiver = Address::new_delegated(10, b"foobar").unwrap();
+
+    // Snapshot before instantiating the machine.
+    let genesis = tester.snapshot().unwrap();
+    assert_eq!(genesis.nonce(sender.account.0), Some(0));
+
+    send(&mut tester, &mut sender, receiver, 1);
+    let first = tester.snapshot().unwrap();
+    assert_eq!(first.nonce(sender.account.0), Some(1));
+
+    send(&mut tester, &mut sender, receiver, 2);
+    assert_eq!(
+        balance(&mut tester, receiver),
+        Some(TokenAmount::from_atto(3))
+    );
+
+    // Branch from the first snapshot.
+    tester.restore(&first).unwrap();
+    sender.restore(&first).unwrap();
+    assert_eq!(sender.seqno, 1);
+    assert_eq!(
+        balance(&mut tester, receiver),
+        Some(TokenAmount::from_atto(1))
+    );
+
+    send(&mut tester, &mut sender, receiver, 5);
+    assert_eq!(
+        balance(&mut tester, receiver),
+        Some(TokenAmount::from_atto(6))
+    );
+
+    // Flushing the new branch doesn't lose either snapshot.
+    tester.snapshot().unwrap();
+    tester.restore(&first).unwrap();
+    assert_eq!(
+        balance(&mut tester, receiver),
+        Some(TokenAmount::from_atto(1))
+    );
+
+    // Reset to the initial state.
+    tester.restore(&genesis).unwrap();
+    sender.restore(&genesis).unwrap();
+    assert_eq!(balance(&mut tester, receiver), None);
+
+    send(&mut tester, &mut sender, receiver, 1);
+    assert_eq!(
+        balance(&mut tester, receiver),
+        Some(TokenAmount::from_atto(1))
+    );
+}
@@ -3,11 +3,23 @@
 use fvm_ipld_encoding::ipld_block::IpldBlock;
 use fvm_ipld_encoding::IPLD_RAW;
 use fvm_sdk as sdk;
+use fvm_sdk::event::{EventBuilder, IntoEvent};
 use fvm_shared::address::Address;
 use fvm_shared::bigint::Zero;
-use fvm_shared::error::ExitCode;
+use fvm_shared::error::{ErrorNumber, ExitCode};
 use fvm_shared::event::{Entry, Flags};
 
+/// The multi-entry event, as a struct.
+#[derive(IntoEvent)]
+struct MultiEntry {
+    bar: Vec<u8>,
+    #[event(key = "baz")]
+    renamed: Vec<u8>,
+    #[event(skip)]
+    #[allow(dead_code)]
+    skipped: u64,
+}
+
 #[no_mangle]
 pub fn invoke(params: u32) -> u32 {
     sdk::initialize();
@@ -16,6 +28,7 @@ pub fn invoke(params: u32) -> u32 {
     const EMIT_MALFORMED: u64 = 3;
     const EMIT_SUBCALLS: u64 = 4;
     const EMIT_SUBCALLS_REVERT: u64 = 5;
+    const EMIT_OVER_LIMITS: u64 = 6;
 
     let payload1 = "abc".as_bytes();
     let payload2 = "def".as_bytes();
@@ -29,36 +42,89 @@ pub fn invoke(params: u32) -> u32 {
         value: payload1.to_owned(),
     }];
 
-    let multi_entry = vec![
-        Entry {
-            flags: Flags::all(),
-            key: "bar".to_owned(),
-            codec: IPLD_RAW,
-            value: payload2.to_owned(),
-        },
-        Entry {
-            flags: Flags::FLAG_INDEXED_KEY | Flags::FLAG_INDEXED_VALUE,
-            key: "baz".to_string(),
-            codec: IPLD_RAW,
-            value: payload3.to_owned(),
-        },
-    ];
+    // Emit a multi-entry event, built with the event builder.
+    let multi_entry = EventBuilder::new()
+        .field("bar", payload2)
+        .field("baz", payload3)
+        .build();
+
+    // The malformed and over-limit methods optionally take a boolean parameter indicating whether
+    // the network returns precise event validation errors.
+    let precise_errors = || match sdk::message::params_raw(params).unwrap() {
+        Some(p) => fvm_ipld_encoding::from_slice::<bool>(&p.data).unwrap(),
+        None => false,
+    };
 
     match sdk::message::method_number() {
         EMIT_SEVERAL_OK => {
             sdk::event::emit_event(&single_entry_evt.into()).unwrap();
-            sdk::event::emit_event(&multi_entry.into()).unwrap();
+
+            // The derived event matches the built one.
+            let multi_entry_struct = MultiEntry {
+                bar: payload2.to_owned(),
+                renamed: payload3.to_owned(),
+                skipped: 0,
+            };
+            assert_eq!(multi_entry_struct.to_event(), multi_entry);
+            multi_entry_struct.emit().unwrap();
         }
         EMIT_MALFORMED => unsafe {
+            let expected = if precise_errors() {
+                ErrorNumber::Serialization
+            } else {
+                ErrorNumber::IllegalArgument
+            };
+
             // mangle an event.
             let mut serialized = fvm_ipld_encoding::to_vec(&single_entry_evt).unwrap();
             serialized[1] = 0xff;
 
-            assert!(
-                sdk::sys::event::emit_event(serialized.as_ptr(), serialized.len() as u32).is_err(),
+            assert_eq!(
+                sdk::sys::event::emit_event(serialized.as_ptr(), serialized.len() as u32),
+                Err(expected),
                 "expected failed syscall"
             );
         },
+        EMIT_OVER_LIMITS => {
+            let expected = if precise_errors() {
+                ErrorNumber::LimitExceeded
+            } else {
+                ErrorNumber::IllegalArgument
+            };
+
+            let entry = |key: &str, codec, value: &[u8]| Entry {
+                flags: Flags::all(),
+                key: key.to_owned(),
+                codec,
+                value: value.to_owned(),
+            };
+
+            // Key too long.
+            let key = "k".repeat(33);
+            assert_eq!(
+                sdk::event::emit_event(&vec![entry(&key, IPLD_RAW, payload1)].into()),
+                Err(expected)
+            );
+
+            // Too many entries.
+            let entries = vec![entry("foo", IPLD_RAW, payload1); 257];
+            assert_eq!(sdk::event::emit_event(&entries.into()), Err(expected));
+
+            // Values too large.
+            let value = vec![0u8; (8 << 10) + 1];
+            assert_eq!(
+                sdk::event::emit_event(&vec![entry("foo", IPLD_RAW, &value)].into()),
+                Err(expected)
+            );
+
+            // Bad codec.
+            assert_eq!(
+                sdk::event::emit_event(
+                    &vec![entry("foo", fvm_ipld_encoding::CBOR, payload1)].into()
+                ),
+                Err(ErrorNumber::IllegalCodec)
+            );
+        }
         EMIT_SUBCALLS => {
             let msg_params = sdk::message::params_raw(params).unwrap().unwrap();
             assert_eq!(msg_params.codec, fvm_ipld_encoding::CBOR);
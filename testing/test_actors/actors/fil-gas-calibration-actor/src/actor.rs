@@ -100,6 +100,7 @@ fn on_verify_signature(p: OnVerifySignatureParams) -> Result<()> {
     let sig_type = match p.signer.protocol() {
         Protocol::BLS => SignatureType::BLS,
         Protocol::Secp256k1 => SignatureType::Secp256k1,
+        Protocol::Delegated => SignatureType::Delegated,
         other => return Err(anyhow!("unexpected protocol: {other}")),
     };
     let sig = Signature {
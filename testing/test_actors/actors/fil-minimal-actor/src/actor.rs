@@ -0,0 +1,38 @@
+// Copyright 2021-2023 Protocol Labs
+// SPDX-License-Identifier: Apache-2.0, MIT
+use fvm_ipld_encoding::tuple::*;
+use fvm_sdk as sdk;
+use fvm_sdk::error::StateError;
+use fvm_shared::address::Address;
+use fvm_shared::ActorID;
+
+/// The actor's state: a simple counter.
+#[derive(Serialize_tuple, Deserialize_tuple)]
+struct State {
+    count: u64,
+}
+
+#[no_mangle]
+pub fn invoke(params: u32) -> u32 {
+    sdk::initialize();
+
+    sdk::dispatch!(params, {
+        Increment => increment,
+        Resolve => resolve,
+    })
+}
+
+/// Increments the counter, returning the new count.
+fn increment(by: u64) -> u64 {
+    sdk::log::info!("incrementing by {}", by);
+    sdk::state::transaction(|state: &mut State| {
+        state.count += by;
+        Ok::<_, StateError>(state.count)
+    })
+    .unwrap()
+}
+
+/// Resolves the address to an actor ID.
+fn resolve(addr: Address) -> Option<ActorID> {
+    sdk::actor::resolve_id(&addr).ok()
+}
@@ -0,0 +1,185 @@
+// Copyright 2021-2023 Protocol Labs
+// SPDX-License-Identifier: Apache-2.0, MIT
+use fvm_ipld_encoding::tuple::*;
+use fvm_ipld_encoding::RawBytes;
+use fvm_sdk as sdk;
+use fvm_sdk::error::{CallError, StateError, UnresolvedAddressError};
+use fvm_shared::address::Address;
+use fvm_shared::clock::ChainEpoch;
+use fvm_shared::crypto::signature::SECP_SIG_LEN;
+use fvm_shared::econ::TokenAmount;
+use fvm_shared::error::ExitCode;
+use fvm_shared::{method_hash, ActorID};
+
+/// The actor's state: a simple counter.
+#[derive(Serialize_tuple, Deserialize_tuple)]
+struct State {
+    count: u64,
+}
+
+#[no_mangle]
+pub fn invoke(params: u32) -> u32 {
+    sdk::initialize();
+
+    sdk::dispatch!(params, {
+        2 => increment,
+        3 => increment_and_fail,
+        4 => send_builder,
+        5 => fail,
+        6 => contexts,
+        7 => resolve,
+        8 => hashes,
+        Count => count,
+        Nothing => nothing,
+    })
+}
+
+/// Increments the counter in a state transaction, returning the new count.
+fn increment(by: u64) -> u64 {
+    sdk::state::transaction(|state: &mut State| {
+        state.count += by;
+        Ok::<_, StateError>(state.count)
+    })
+    .unwrap()
+}
+
+/// Increments the counter in a failing state transaction, which must leave the state untouched.
+fn increment_and_fail(by: u64) {
+    let root = sdk::sself::root().unwrap();
+    let res = sdk::state::transaction(|state: &mut State| {
+        state.count += by;
+        Err::<(), _>(StateError::Decode("failed".into()))
+    });
+    assert_eq!(res, Err(StateError::Decode("failed".into())));
+    assert_eq!(sdk::sself::root().unwrap(), root);
+}
+
+/// Returns the counter.
+fn count(_: ()) -> u64 {
+    sdk::state::load::<State>().unwrap().count
+}
+
+/// Returns nothing.
+fn nothing(_: ()) {}
+
+/// Panics with the given message.
+fn fail(message: String) {
+    panic!("{}", message)
+}
+
+/// Calls this actor's other methods through the send builder.
+fn send_builder(_: ()) {
+    let this = Address::new_id(sdk::message::receiver());
+
+    // Parameters are encoded, and return values decoded.
+    let count: u64 = sdk::send::to(&this).method(2).params(&5u64).call().unwrap();
+    assert_eq!(count, sdk::state::load::<State>().unwrap().count);
+    let count: u64 = sdk::send::to(&this)
+        .method(method_hash!("Count"))
+        .read_only()
+        .call()
+        .unwrap();
+    assert_eq!(count, sdk::state::load::<State>().unwrap().count);
+
+    // Calls returning nothing decode as null.
+    let () = sdk::send::to(&this)
+        .method(method_hash!("Nothing"))
+        .call()
+        .unwrap();
+    let none: Option<u64> = sdk::send::to(&this)
+        .method(method_hash!("Nothing"))
+        .call()
+        .unwrap();
+    assert_eq!(none, None);
+
+    // Return values of the wrong type can't be decoded.
+    let res = sdk::send::to(&this)
+        .method(method_hash!("Count"))
+        .call::<String>();
+    assert!(matches!(res, Err(CallError::DecodeReturn(_))), "{:?}", res);
+
+    // Unhandled methods abort.
+    match sdk::send::to(&this).method(1234).call::<()>() {
+        Err(CallError::Exit { exit_code, .. }) => {
+            assert_eq!(exit_code, ExitCode::USR_UNHANDLED_MESSAGE)
+        }
+        res => panic!("unexpected result: {:?}", res),
+    }
+
+    // Panics abort with the panic message.
+    match sdk::send::to(&this).method(5).params("boom").call::<()>() {
+        Err(CallError::Exit {
+            exit_code,
+            return_data: Some(data),
+        }) => {
+            assert_eq!(exit_code, sdk::vm::PANIC_EXIT_CODE);
+            assert!(String::from_utf8(data.data).unwrap().contains("boom"));
+        }
+        res => panic!("unexpected result: {:?}", res),
+    }
+}
+
+/// Checks the cached message and network contexts against fresh syscalls, returning the caller,
+/// the value received, the epoch, and the base fee.
+fn contexts(_: ()) -> (ActorID, TokenAmount, ChainEpoch, TokenAmount) {
+    let message = unsafe { sdk::sys::vm::message_context() }.unwrap();
+    let network = unsafe { sdk::sys::network::context() }.unwrap();
+    assert!(std::ptr::eq(
+        sdk::message::context(),
+        sdk::message::context()
+    ));
+    assert!(std::ptr::eq(
+        sdk::network::context(),
+        sdk::network::context()
+    ));
+
+    assert_eq!(sdk::message::caller(), message.caller);
+    assert_eq!(sdk::message::receiver(), message.receiver);
+    assert_eq!(
+        sdk::message::value_received(),
+        TokenAmount::try_from(message.value_received).unwrap()
+    );
+    assert_eq!(sdk::network::curr_epoch(), network.epoch);
+    assert_eq!(
+        sdk::network::base_fee(),
+        TokenAmount::from(network.base_fee)
+    );
+    (
+        sdk::message::caller(),
+        sdk::message::value_received(),
+        sdk::network::curr_epoch(),
+        sdk::network::base_fee(),
+    )
+}
+
+/// Resolves the address to an actor ID and looks up the actor's delegated address, twice (the
+/// second time from the caches).
+fn resolve(addr: Address) -> (Option<ActorID>, Option<Address>) {
+    let id = match sdk::actor::resolve_id(&addr) {
+        Ok(id) => Some(id),
+        Err(e) => {
+            assert_eq!(e, UnresolvedAddressError(addr));
+            None
+        }
+    };
+    let delegated = id.and_then(sdk::actor::lookup_delegated_address);
+
+    assert_eq!(sdk::actor::resolve_id(&addr).ok(), id);
+    assert_eq!(id.and_then(sdk::actor::lookup_delegated_address), delegated);
+    (id, delegated)
+}
+
+/// Hashes the data with keccak256, and recovers the Ethereum address that signed the hash. Also
+/// checks FRC-42 method numbers computed at runtime.
+fn hashes((data, signature): (RawBytes, RawBytes)) -> (RawBytes, RawBytes) {
+    assert_eq!(
+        sdk::dispatch::method_hash("Count"),
+        Ok(method_hash!("Count"))
+    );
+    assert!(sdk::dispatch::method_hash("count").is_err());
+
+    let hash = sdk::crypto::hash_keccak(&data);
+    let signature: [u8; SECP_SIG_LEN] = signature.bytes().try_into().unwrap();
+    let addr = sdk::crypto::recover_eth_address(&signature, &hash).unwrap();
+    (hash.to_vec().into(), addr.to_vec().into())
+}
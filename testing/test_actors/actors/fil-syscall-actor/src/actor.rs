@@ -8,6 +8,7 @@ use fvm_shared::crypto::hash::SupportedHashes as SharedSupportedHashes;
 use fvm_shared::crypto::signature::{Signature, SECP_SIG_LEN};
 use fvm_shared::error::ErrorNumber;
 use fvm_shared::sector::RegisteredSealProof;
+use fvm_shared::sys::SecpRecoverFlags;
 use multihash::derive::Multihash;
 use multihash::{Blake2b256, Blake2b512, Keccak256, Ripemd160, Sha2_256};
 use std::ptr;
@@ -40,6 +41,9 @@ pub fn invoke(_: u32) -> u32 {
     test_message_context();
     test_balance();
     test_unaligned();
+    test_block_links();
+    test_verify_beacon_entry();
+    test_extensible_context();
 
     #[cfg(coverage)]
     sdk::debug::store_artifact("syscall_actor.profraw", minicov::capture_coverage());
@@ -139,6 +143,39 @@ fn test_signature() {
         let res = sdk::sys::crypto::recover_secp_public_key(hash.as_ptr(), (u32::MAX) as *const u8);
         assert_eq!(res, Err(ErrorNumber::IllegalArgument));
     }
+
+    // test we can recover the public key from the (r, s, v) signature components, with both
+    // raw and Ethereum-style recovery ids
+    //
+    let r: [u8; 32] = sig[..32].try_into().unwrap();
+    let s: [u8; 32] = sig[32..64].try_into().unwrap();
+    for v in [sig[64], sig[64] + 27] {
+        let res = sdk::crypto::recover_secp_public_key_rsv(
+            &hash,
+            &r,
+            &s,
+            v,
+            SecpRecoverFlags::REJECT_MALLEABLE,
+        )
+        .unwrap();
+        assert_eq!(res, pub_key_bytes.as_slice());
+    }
+
+    // test that invalid recovery ids and out of range components result in IllegalArgument
+    //
+    let res = sdk::crypto::recover_secp_public_key_rsv(&hash, &r, &s, 2, Default::default());
+    assert_eq!(res, Err(ErrorNumber::IllegalArgument));
+    let res =
+        sdk::crypto::recover_secp_public_key_rsv(&hash, &[0; 32], &s, sig[64], Default::default());
+    assert_eq!(res, Err(ErrorNumber::IllegalArgument));
+    let res = sdk::crypto::recover_secp_public_key_rsv(
+        &hash,
+        &r,
+        &[0xff; 32],
+        sig[64],
+        Default::default(),
+    );
+    assert_eq!(res, Err(ErrorNumber::IllegalArgument));
 }
 
 // use SDK methods to hash and compares against locally (inside the actor) hashed digest
@@ -331,6 +368,8 @@ fn test_network_context() {
     assert_eq!(sdk::network::version(), NetworkVersion::V18);
     assert_eq!(sdk::network::tipset_timestamp(), 0);
     assert_eq!(sdk::network::base_fee(), TokenAmount::from_atto(100));
+    assert_eq!(sdk::network::genesis_timestamp(), 0);
+    assert_eq!(sdk::network::block_gas_limit(), fvm_shared::BLOCK_GAS_LIMIT);
 }
 
 fn test_message_context() {
@@ -377,3 +416,112 @@ fn test_unaligned() {
         assert_eq!(expected, actual);
     }
 }
+
+fn test_block_links() {
+    use fvm_ipld_encoding::{to_vec, DAG_CBOR, IPLD_RAW};
+    use fvm_shared::MAX_CID_LEN;
+
+    // Enough links to need multiple pages.
+    let links: Vec<_> = (0..100u64)
+        .map(|i| {
+            sdk::ipld::put(0xb220, 32, IPLD_RAW, &i.to_be_bytes()).expect("failed to put block")
+        })
+        .collect();
+
+    let id = sdk::ipld::put_block(DAG_CBOR, &to_vec(&links).unwrap()).unwrap();
+    assert_eq!(sdk::ipld::block_links(id).unwrap(), links);
+
+    // Links in raw blocks aren't traversed.
+    let raw = sdk::ipld::put_block(IPLD_RAW, &to_vec(&links).unwrap()).unwrap();
+    assert_eq!(sdk::ipld::block_links(raw).unwrap(), vec![]);
+
+    // A buffer too small for a single CID makes no progress.
+    let mut buf = [0u8; 8];
+    let out =
+        unsafe { sdk::sys::ipld::block_links(id, 0, buf.as_mut_ptr(), buf.len() as u32) }.unwrap();
+    assert_eq!((out.total, out.count, out.length), (100, 0, 0));
+
+    // Reading past the end writes nothing.
+    let mut buf = [0u8; MAX_CID_LEN];
+    let out = unsafe { sdk::sys::ipld::block_links(id, 200, buf.as_mut_ptr(), buf.len() as u32) }
+        .unwrap();
+    assert_eq!((out.total, out.count, out.length), (100, 0, 0));
+
+    // Invalid handles are rejected.
+    assert_eq!(
+        sdk::ipld::block_links(12345),
+        Err(ErrorNumber::InvalidHandle)
+    );
+
+    // The extended stat reports links and reachability.
+    let stat = unsafe { sdk::sys::ipld::block_stat_v2(id) }.unwrap();
+    assert_eq!((stat.codec, stat.links, stat.reachable), (DAG_CBOR, 100, 0));
+    let stat = unsafe { sdk::sys::ipld::block_stat_v2(raw) }.unwrap();
+    assert_eq!((stat.codec, stat.links, stat.reachable), (IPLD_RAW, 0, 0));
+
+    // Opened blocks are reachable.
+    let k = sdk::ipld::put(0xb220, 32, DAG_CBOR, &to_vec(&links).unwrap()).unwrap();
+    let mut cid_buf = [0u8; MAX_CID_LEN];
+    k.write_bytes(&mut cid_buf[..]).unwrap();
+    let opened = unsafe { sdk::sys::ipld::block_open(cid_buf.as_ptr()) }.unwrap();
+    let stat = unsafe { sdk::sys::ipld::block_stat_v2(opened.id) }.unwrap();
+    assert_eq!({ stat.size }, { opened.size });
+    assert_eq!((stat.links, stat.reachable), (100, 1));
+}
+
+fn test_verify_beacon_entry() {
+    // The test externs accept any non-empty signature.
+    assert_eq!(sdk::rand::verify_beacon_entry(1, &[], &[1; 96]), Ok(true));
+    assert_eq!(sdk::rand::verify_beacon_entry(2, &[1; 96], &[]), Ok(false));
+}
+
+fn test_extensible_context() {
+    use fvm_shared::sys::out::network::NetworkContextV2;
+    use fvm_shared::sys::out::vm::MessageContext;
+    use fvm_shared::sys::Extensible;
+    use std::mem::size_of;
+
+    let expected = unsafe { sdk::sys::network::context_v2() }.unwrap();
+
+    // Buffers that can't hold the first version of the struct are rejected.
+    let mut buf = [0u8; size_of::<NetworkContextV2>()];
+    let res = unsafe {
+        sdk::sys::network::context_ext(buf.as_mut_ptr(), NetworkContextV2::MIN_SIZE as u32 - 1)
+    };
+    assert_eq!(res, Err(ErrorNumber::BufferTooSmall));
+    let res = unsafe {
+        sdk::sys::vm::message_context_ext(buf.as_mut_ptr(), MessageContext::MIN_SIZE as u32 - 1)
+    };
+    assert_eq!(res, Err(ErrorNumber::BufferTooSmall));
+
+    // Truncated buffers get the fields that fit.
+    let len = NetworkContextV2::MIN_SIZE as u32;
+    assert_eq!(
+        unsafe { sdk::sys::network::context_ext(buf.as_mut_ptr(), len) },
+        Ok(len)
+    );
+    let truncated: NetworkContextV2 = unsafe { ptr::read_unaligned(buf.as_ptr() as *const _) };
+    assert_eq!(
+        NetworkContext::from(truncated),
+        NetworkContext::from(expected)
+    );
+    assert_eq!(
+        ({ truncated.genesis_timestamp }, {
+            truncated.block_gas_limit
+        }),
+        (0, 0)
+    );
+
+    // Full buffers round-trip.
+    let (actual, len) =
+        unsafe { sdk::sys::read_extensible::<NetworkContextV2>(sdk::sys::network::context_ext) }
+            .unwrap();
+    assert_eq!(len as usize, size_of::<NetworkContextV2>());
+    assert_eq!(actual, expected);
+
+    let (actual, len) =
+        unsafe { sdk::sys::read_extensible::<MessageContext>(sdk::sys::vm::message_context_ext) }
+            .unwrap();
+    assert_eq!(len as usize, size_of::<MessageContext>());
+    assert_eq!(actual, unsafe { sdk::sys::vm::message_context() }.unwrap());
+}
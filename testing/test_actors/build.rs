@@ -31,6 +31,7 @@ const ACTORS: &[(&str, &str)] = &[
     ("CREATE_ACTOR_BINARY", "fil_create_actor"),
     ("OOM_ACTOR_BINARY", "fil_oom_actor"),
     ("SSELF_ACTOR_BINARY", "fil_sself_actor"),
+    ("SDK_ACTOR_BINARY", "fil_sdk_actor"),
 ];
 
 const WASM_TARGET: &str = "wasm32-unknown-unknown";
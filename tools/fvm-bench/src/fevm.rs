@@ -11,7 +11,7 @@ fn handle_result(tester: &tester::BasicTester, name: &str, res: &ApplyRet) -> an
     let (trace, events) = tester
         .options
         .as_ref()
-        .map(|o| (o.trace, o.events))
+        .map(|o| (o.trace.is_enabled(), o.events))
         .unwrap_or_default();
 
     if trace && !res.exec_trace.is_empty() {
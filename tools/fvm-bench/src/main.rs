@@ -7,6 +7,7 @@ use std::fs;
 
 use anyhow::{anyhow, Context};
 use clap::Parser;
+use fvm::trace::TraceConfig;
 use fvm_integration_tests::tester;
 
 /// Run a contract invocation for benchmarking purposes
@@ -20,10 +21,34 @@ struct Args {
     #[arg(short, long, default_value = "false")]
     debug: bool,
 
-    /// Emit detailed gas tracing information
+    /// Emit detailed tracing information (all categories)
     #[arg(short, long, default_value = "false")]
     trace: bool,
 
+    /// Trace sends, returns, and actor invocations
+    #[arg(long, default_value = "false")]
+    trace_calls: bool,
+
+    /// Trace gas charges
+    #[arg(long, default_value = "false")]
+    trace_gas: bool,
+
+    /// Trace syscall invocations
+    #[arg(long, default_value = "false")]
+    trace_syscalls: bool,
+
+    /// Trace events emitted by actors
+    #[arg(long, default_value = "false")]
+    trace_events: bool,
+
+    /// Trace actor state reads and updates
+    #[arg(long, default_value = "false")]
+    trace_state_access: bool,
+
+    /// Maximum number of traced events per category
+    #[arg(long)]
+    trace_limit: Option<usize>,
+
     /// Emit user generated logs
     #[arg(short, long, default_value = "false")]
     events: bool,
@@ -50,9 +75,17 @@ struct Args {
 fn run() -> anyhow::Result<()> {
     env_logger::init();
     let args = Args::parse();
+    let limit = args.trace_limit.unwrap_or(usize::MAX);
+    let category = |enabled: bool| (args.trace || enabled).then_some(limit);
     let options = tester::ExecutionOptions {
         debug: args.debug,
-        trace: args.trace,
+        trace: TraceConfig {
+            calls: category(args.trace_calls),
+            gas: category(args.trace_gas),
+            syscalls: category(args.trace_syscalls),
+            events: category(args.trace_events),
+            state_access: category(args.trace_state_access),
+        },
         events: args.events,
     };
     let mut tester = tester::BasicTester::new_basic_tester(args.bundle, options)?;